@@ -0,0 +1,195 @@
+//! Agents and their lifecycle
+
+use crate::*;
+use alloc::sync::Arc;
+use std::sync::Mutex;
+
+/// An autonomous agent.
+///
+/// Episodic memory is loaded from the configured persistence backend when
+/// the agent is created and saved back when it shuts down. Dropping an
+/// agent without calling [`shutdown`](Agent::shutdown) still saves its
+/// memory, ignoring any error.
+///
+/// Every task runs against a fresh [`BudgetTracker`] built from
+/// `config.budget`.
+pub struct Agent {
+    /// Agent identifier
+    id: AgentId,
+    /// Agent configuration
+    config: AgentConfig,
+    /// Episodic memory
+    memory: EpisodicMemory,
+    /// Runs the tools of a task
+    executor: ToolExecutor,
+    /// Budget tracker of the task currently running
+    active_budget: Mutex<Option<Arc<BudgetTracker>>>,
+    /// Whether memory has already been saved by `shutdown`
+    shut_down: bool,
+}
+
+impl Agent {
+    /// Create an agent, loading its episodic memory
+    pub fn new(config: AgentConfig) -> Result<Self> {
+        let mut memory = EpisodicMemory::from_config(&config.memory_config);
+        memory.load_memory()?;
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            config,
+            memory,
+            executor: ToolExecutor::new(),
+            active_budget: Mutex::new(None),
+            shut_down: false,
+        })
+    }
+
+    /// Run tasks with `executor` and its registered tools
+    pub fn with_tool_executor(mut self, executor: ToolExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Agent identifier
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Agent configuration
+    pub fn config(&self) -> &AgentConfig {
+        &self.config
+    }
+
+    /// Episodic memory
+    pub fn memory(&self) -> &EpisodicMemory {
+        &self.memory
+    }
+
+    /// Record an episode to episodic memory
+    pub fn remember(&mut self, episode: Episode) {
+        self.memory.record(episode);
+    }
+
+    /// Tool executor running the agent's tasks
+    pub fn tool_executor(&self) -> &ToolExecutor {
+        &self.executor
+    }
+
+    /// Execute a tool plan as a task, enforcing the configured budget.
+    ///
+    /// See [`ToolExecutor::run_task`] for how limits abort the task.
+    pub async fn execute_task(&self, plan: &[ToolCall]) -> TaskResult {
+        let tracker = Arc::new(BudgetTracker::new(self.config.budget.clone()));
+        *self.active_budget.lock().unwrap_or_else(|e| e.into_inner()) = Some(tracker.clone());
+        let result = self.executor.run_task(plan, &tracker).await;
+        *self.active_budget.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        result
+    }
+
+    /// Budget tracker of the running task, through which components such
+    /// as LLM clients report their consumption
+    pub fn budget_tracker(&self) -> Option<Arc<BudgetTracker>> {
+        self.active_budget.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Consumption of the running task so far
+    pub fn budget_usage(&self) -> Option<BudgetUsage> {
+        self.budget_tracker().map(|tracker| tracker.usage())
+    }
+
+    /// Save episodic memory and stop the agent
+    pub fn shutdown(mut self) -> Result<()> {
+        self.shut_down = true;
+        self.memory.save_memory()
+    }
+}
+
+impl ::core::fmt::Debug for Agent {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("Agent")
+            .field("id", &self.id)
+            .field("config", &self.config)
+            .field("memory", &self.memory)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Agent {
+    fn drop(&mut self) {
+        if !self.shut_down {
+            // Nowhere to report the error; call `shutdown` to observe it
+            let _ = self.memory.save_memory();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_agent() {
+        let mut agent = Agent::new(AgentConfig::default()).unwrap();
+        agent.remember(Episode::new("greeting", "replied", "user happy"));
+        assert_eq!(agent.memory().len(), 1);
+        agent.shutdown().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_task_runs_against_configured_budget() {
+        let mut executor = ToolExecutor::new();
+        executor.register_tool(Tool::new("echo", "Echo the input", |_params| {
+            Box::pin(async { Ok(ToolResult::Text("ok".into())) })
+        }));
+        let agent = Agent::new(AgentConfig {
+            budget: AgentBudget {
+                max_tool_calls: Some(2),
+                ..AgentBudget::unlimited()
+            },
+            ..Default::default()
+        })
+        .unwrap()
+        .with_tool_executor(executor);
+
+        let plan = vec![ToolCall::new("echo", ToolParams::new()); 3];
+        let result = agent.execute_task(&plan).await;
+        assert!(matches!(
+            result,
+            TaskResult::Failed { error: AgentError::BudgetExceeded { .. }, .. }
+        ));
+        assert!(agent.budget_usage().is_none());
+
+        // Each task starts with a fresh budget
+        let result = agent.execute_task(&plan[..2]).await;
+        assert!(matches!(result, TaskResult::Completed { .. }));
+    }
+
+    #[cfg(feature = "memory")]
+    #[test]
+    fn test_memory_survives_agent_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentConfig {
+            memory_config: MemoryConfig {
+                persistence: MemoryPersistence::Database {
+                    url: dir.path().join("agent_memory.db").to_string_lossy().into_owned(),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut agent = Agent::new(config.clone()).unwrap();
+        agent.remember(Episode::new("user asked about rust", "searched docs", "found answer").with_timestamp(1_000));
+        agent.shutdown().unwrap();
+
+        let mut agent = Agent::new(config.clone()).unwrap();
+        assert_eq!(agent.memory().len(), 1);
+        agent.remember(Episode::new("weather request", "called api", "sunny").with_timestamp(2_000));
+        drop(agent);
+
+        let agent = Agent::new(config).unwrap();
+        assert_eq!(agent.memory().len(), 2);
+        assert_eq!(agent.memory().recent(1)[0].outcome, "sunny");
+        assert_eq!(agent.memory().query("rust", 1)[0].action, "searched docs");
+    }
+}
@@ -0,0 +1,633 @@
+//! Inter-agent messaging for multi-agent systems
+
+use crate::*;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::sync::Arc;
+use ::core::time::Duration;
+use frys_eventbus::{DeliveryGuarantee, Event, EventBus, EventBusConfig, Filter, SubscriberConfig, SubscriberHandle};
+use std::sync::RwLock;
+
+/// Correlation identifier linking a request to its responses
+pub type CorrelationId = String;
+
+/// Event header names used to carry message metadata over the event bus
+mod headers {
+    pub const MESSAGE_ID: &str = "agent.message_id";
+    pub const SENDER: &str = "agent.sender";
+    pub const RECIPIENT: &str = "agent.recipient";
+    pub const ROLE: &str = "agent.role";
+    pub const KIND: &str = "agent.kind";
+    pub const CORRELATION_ID: &str = "agent.correlation_id";
+}
+
+/// How agents in a multi-agent system coordinate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordinationStrategy {
+    /// A single coordinator assigns all work
+    Centralized,
+    /// Agents delegate to and report back through a chain of roles
+    #[default]
+    Hierarchical,
+    /// Agents negotiate directly with each other
+    PeerToPeer,
+}
+
+/// Role an agent plays within a multi-agent system
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AgentRole {
+    /// Assigns and aggregates work
+    Coordinator,
+    /// Gathers information and produces drafts
+    Researcher,
+    /// Produces written artifacts
+    Writer,
+    /// Reviews artifacts and gives feedback
+    Reviewer,
+    /// Application-defined role
+    Custom(String),
+}
+
+impl AgentRole {
+    /// Role name used on the wire
+    pub fn as_str(&self) -> &str {
+        match self {
+            AgentRole::Coordinator => "coordinator",
+            AgentRole::Researcher => "researcher",
+            AgentRole::Writer => "writer",
+            AgentRole::Reviewer => "reviewer",
+            AgentRole::Custom(name) => name,
+        }
+    }
+
+    /// Parse a role name produced by `as_str`
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "coordinator" => AgentRole::Coordinator,
+            "researcher" => AgentRole::Researcher,
+            "writer" => AgentRole::Writer,
+            "reviewer" => AgentRole::Reviewer,
+            other => AgentRole::Custom(other.into()),
+        }
+    }
+}
+
+/// Purpose of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    /// Asks the recipient for a response
+    Request,
+    /// Answers an earlier request
+    Response,
+    /// One-way notification
+    Notification,
+}
+
+impl MessageKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MessageKind::Request => "request",
+            MessageKind::Response => "response",
+            MessageKind::Notification => "notification",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "request" => Some(MessageKind::Request),
+            "response" => Some(MessageKind::Response),
+            "notification" => Some(MessageKind::Notification),
+            _ => None,
+        }
+    }
+}
+
+/// Message exchanged between agents
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// Unique message ID
+    pub id: String,
+    /// Sending agent, filled in when the message is sent
+    pub sender: AgentId,
+    /// Receiving agent, filled in when the message is sent
+    pub recipient: AgentId,
+    /// Role of the sending agent
+    pub role: AgentRole,
+    /// Purpose of the message
+    pub kind: MessageKind,
+    /// Links requests and responses
+    pub correlation_id: CorrelationId,
+    /// Message body
+    pub content: serde_json::Value,
+}
+
+impl Message {
+    /// Create a request; its correlation ID is its own message ID
+    pub fn request(content: serde_json::Value) -> Self {
+        let id = uuid::Uuid::new_v4().to_string();
+        Self {
+            correlation_id: id.clone(),
+            id,
+            sender: AgentId::new(),
+            recipient: AgentId::new(),
+            role: AgentRole::Custom(String::new()),
+            kind: MessageKind::Request,
+            content,
+        }
+    }
+
+    /// Create a one-way notification
+    pub fn notification(content: serde_json::Value) -> Self {
+        Self {
+            kind: MessageKind::Notification,
+            ..Self::request(content)
+        }
+    }
+
+    /// Create a response to `request`, addressed to its sender
+    pub fn response_to(request: &Message, content: serde_json::Value) -> Self {
+        Self {
+            recipient: request.sender.clone(),
+            kind: MessageKind::Response,
+            correlation_id: request.correlation_id.clone(),
+            ..Self::request(content)
+        }
+    }
+
+    /// Encode the message as an event on the recipient's inbox topic
+    pub fn to_event(&self) -> Result<Event> {
+        let payload = serde_json::to_vec(&self.content).map_err(|e| AgentError::SerializationError {
+            reason: e.to_string(),
+        })?;
+
+        Ok(Event::new(inbox_topic(&self.recipient), payload)
+            .with_header(headers::MESSAGE_ID.into(), self.id.clone())
+            .with_header(headers::SENDER.into(), self.sender.clone())
+            .with_header(headers::RECIPIENT.into(), self.recipient.clone())
+            .with_header(headers::ROLE.into(), self.role.as_str().into())
+            .with_header(headers::KIND.into(), self.kind.as_str().into())
+            .with_header(headers::CORRELATION_ID.into(), self.correlation_id.clone()))
+    }
+
+    /// Decode a message from an inbox event
+    pub fn from_event(event: &Event) -> Result<Self> {
+        let header = |name: &str| {
+            event.headers.get(name).cloned().ok_or_else(|| AgentError::CommunicationError {
+                operation: "decode message".into(),
+                reason: alloc::format!("missing header '{}'", name),
+            })
+        };
+
+        let kind = header(headers::KIND)?;
+        Ok(Self {
+            id: header(headers::MESSAGE_ID)?,
+            sender: header(headers::SENDER)?,
+            recipient: header(headers::RECIPIENT)?,
+            role: AgentRole::from_name(&header(headers::ROLE)?),
+            kind: MessageKind::from_name(&kind).ok_or_else(|| AgentError::CommunicationError {
+                operation: "decode message".into(),
+                reason: alloc::format!("unknown message kind '{}'", kind),
+            })?,
+            correlation_id: header(headers::CORRELATION_ID)?,
+            content: serde_json::from_slice(&event.payload).map_err(|e| AgentError::SerializationError {
+                reason: e.to_string(),
+            })?,
+        })
+    }
+}
+
+/// How long a received message may go unacknowledged before redelivery
+const INBOX_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Redeliveries of an unacknowledged message before it is dead-lettered
+const INBOX_MAX_REDELIVERIES: u32 = 3;
+
+/// Event bus topic holding an agent's inbox
+pub fn inbox_topic(agent_id: &str) -> String {
+    alloc::format!("agents.{}.inbox", agent_id)
+}
+
+/// Delivers agent messages through the event bus
+pub struct MessageBus {
+    /// Underlying event bus
+    eventbus: futures::lock::Mutex<EventBus>,
+    /// Agents with an inbox subscription
+    agents: RwLock<BTreeSet<AgentId>>,
+}
+
+impl MessageBus {
+    /// Create a message bus on top of an event bus
+    pub fn new(eventbus: EventBus) -> Self {
+        Self {
+            eventbus: futures::lock::Mutex::new(eventbus),
+            agents: RwLock::new(BTreeSet::new()),
+        }
+    }
+
+    /// Check whether an agent has an inbox
+    pub fn is_registered(&self, agent_id: &str) -> bool {
+        self.agents.read().map(|agents| agents.contains(agent_id)).unwrap_or(false)
+    }
+
+    fn agents_mut(&self) -> Result<std::sync::RwLockWriteGuard<'_, BTreeSet<AgentId>>> {
+        self.agents.write().map_err(|_| AgentError::ConcurrencyError {
+            operation: "register inbox".into(),
+            reason: "inbox registry lock poisoned".into(),
+        })
+    }
+
+    /// Subscribe the inbox of an agent on the event bus. Inbox messages
+    /// are delivered at least once and acknowledged by the mailbox.
+    async fn register(&self, agent_id: &str) -> Result<SubscriberHandle> {
+        if !self.agents_mut()?.insert(agent_id.into()) {
+            return Err(AgentError::ConfigurationError {
+                parameter: "agent_id".into(),
+                reason: alloc::format!("agent '{}' is already registered", agent_id),
+            });
+        }
+
+        let topic = inbox_topic(agent_id);
+        let config = SubscriberConfig {
+            name: topic.clone(),
+            delivery: DeliveryGuarantee::AtLeastOnce {
+                visibility_timeout: INBOX_VISIBILITY_TIMEOUT,
+                max_redeliveries: INBOX_MAX_REDELIVERIES,
+            },
+            ..Default::default()
+        };
+        let subscription = self
+            .eventbus
+            .lock()
+            .await
+            .subscribe_with_config(&topic, Filter::default(), config)
+            .await
+            .map_err(|e| AgentError::CommunicationError {
+                operation: "subscribe".into(),
+                reason: e.to_string(),
+            });
+        if subscription.is_err() {
+            self.agents_mut()?.remove(agent_id);
+        }
+        subscription
+    }
+
+    /// Publish a message to its recipient's inbox.
+    ///
+    /// Fails with `AgentError::AgentNotFound`, without publishing, if the
+    /// recipient has no inbox.
+    pub async fn send(&self, message: &Message) -> Result<()> {
+        if !self.is_registered(&message.recipient) {
+            return Err(AgentError::AgentNotFound {
+                agent_id: message.recipient.clone(),
+            });
+        }
+
+        self.eventbus
+            .lock()
+            .await
+            .publish(message.to_event()?)
+            .await
+            .map_err(|e| AgentError::CommunicationError {
+                operation: "publish".into(),
+                reason: e.to_string(),
+            })
+    }
+}
+
+impl ::core::fmt::Debug for MessageBus {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        let agents: alloc::vec::Vec<AgentId> = self
+            .agents
+            .read()
+            .map(|agents| agents.iter().cloned().collect())
+            .unwrap_or_default();
+        f.debug_struct("MessageBus").field("agents", &agents).finish()
+    }
+}
+
+/// Typed mailbox owned by a single agent
+#[derive(Debug)]
+pub struct Mailbox {
+    /// Owning agent
+    agent_id: AgentId,
+    /// Role of the owning agent
+    role: AgentRole,
+    /// Bus used for outgoing messages
+    bus: Arc<MessageBus>,
+    /// Event bus subscription to the agent's inbox topic
+    inbox: SubscriberHandle,
+    /// Event IDs of received messages awaiting acknowledgement, by message ID
+    unacked: BTreeMap<String, u64>,
+}
+
+impl Mailbox {
+    /// Owning agent
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    /// Role of the owning agent
+    pub fn role(&self) -> &AgentRole {
+        &self.role
+    }
+
+    /// Send a message to another agent, returning its correlation ID
+    pub async fn send_to(&self, agent_id: &str, mut message: Message) -> Result<CorrelationId> {
+        message.sender = self.agent_id.clone();
+        message.recipient = agent_id.into();
+        message.role = self.role.clone();
+        self.bus.send(&message).await?;
+        Ok(message.correlation_id)
+    }
+
+    /// Respond to a request received by this mailbox
+    pub async fn reply(&self, request: &Message, content: serde_json::Value) -> Result<CorrelationId> {
+        self.send_to(&request.sender, Message::response_to(request, content)).await
+    }
+
+    /// Wait for the next message.
+    ///
+    /// The message must be acknowledged with [`ack`](Self::ack) once it is
+    /// processed; otherwise it is delivered again after the visibility
+    /// timeout, so a crash while processing it does not lose it.
+    pub async fn receive(&mut self) -> Result<Message> {
+        let event = self.inbox.receive().await.ok_or_else(|| AgentError::CommunicationError {
+            operation: "receive".into(),
+            reason: "message bus closed".into(),
+        })?;
+        self.accept(&event)
+    }
+
+    /// Take the next message if one is already waiting. It must be
+    /// acknowledged like one from [`receive`](Self::receive).
+    pub fn try_receive(&mut self) -> Option<Result<Message>> {
+        let event = self.inbox.try_receive()?;
+        Some(self.accept(&event))
+    }
+
+    /// Acknowledge a processed message so it is not delivered again.
+    ///
+    /// Returns `false` if the message was not awaiting acknowledgement.
+    pub fn ack(&mut self, message: &Message) -> bool {
+        self.unacked
+            .remove(&message.id)
+            .is_some_and(|event_id| self.inbox.ack(event_id))
+    }
+
+    /// Report that processing a message failed, so it is delivered again
+    /// right away, or dead-lettered once it has failed too often.
+    ///
+    /// Returns `false` if the message was not awaiting acknowledgement.
+    pub fn nack(&mut self, message: &Message, reason: &str) -> bool {
+        self.unacked
+            .remove(&message.id)
+            .is_some_and(|event_id| self.inbox.nack(event_id, reason))
+    }
+
+    /// Wait for the next message and run `handler` on it, acknowledging
+    /// the message on success and reporting the failure otherwise
+    pub async fn handle_next<F, Fut>(&mut self, handler: F) -> Result<()>
+    where
+        F: FnOnce(Message) -> Fut,
+        Fut: ::core::future::Future<Output = Result<()>>,
+    {
+        let message = self.receive().await?;
+        let result = handler(message.clone()).await;
+        match &result {
+            Ok(()) => self.ack(&message),
+            Err(error) => self.nack(&message, &alloc::format!("{}", error)),
+        };
+        result
+    }
+
+    /// Decode the message of a received event, keeping the event pending
+    /// until the message is acknowledged. Undecodable events are
+    /// acknowledged right away, as redelivering them cannot help.
+    fn accept(&mut self, event: &Event) -> Result<Message> {
+        let message = Message::from_event(event);
+        if let Some(event_id) = event.id {
+            match &message {
+                Ok(message) => {
+                    self.unacked.insert(message.id.clone(), event_id);
+                }
+                Err(_) => {
+                    self.inbox.ack(event_id);
+                }
+            }
+        }
+        message
+    }
+}
+
+/// Group of agents cooperating through a shared message bus
+#[derive(Debug)]
+pub struct MultiAgentSystem {
+    /// Coordination strategy
+    strategy: CoordinationStrategy,
+    /// Member agents and their roles
+    agents: BTreeMap<AgentId, AgentRole>,
+    /// Shared message bus
+    bus: Arc<MessageBus>,
+}
+
+impl MultiAgentSystem {
+    /// Start building a multi-agent system
+    pub fn builder() -> MultiAgentSystemBuilder {
+        MultiAgentSystemBuilder::default()
+    }
+
+    /// Add an agent and get its mailbox
+    pub async fn add_agent(&mut self, agent_id: impl Into<AgentId>, role: AgentRole) -> Result<Mailbox> {
+        let agent_id = agent_id.into();
+        let inbox = self.bus.register(&agent_id).await?;
+        self.agents.insert(agent_id.clone(), role.clone());
+        Ok(Mailbox {
+            agent_id,
+            role,
+            bus: self.bus.clone(),
+            inbox,
+            unacked: BTreeMap::new(),
+        })
+    }
+
+    /// Role of a member agent
+    pub fn role_of(&self, agent_id: &str) -> Option<&AgentRole> {
+        self.agents.get(agent_id)
+    }
+
+    /// Number of member agents
+    pub fn agent_count(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// Coordination strategy
+    pub fn coordination_strategy(&self) -> CoordinationStrategy {
+        self.strategy
+    }
+
+    /// Shared message bus
+    pub fn message_bus(&self) -> &Arc<MessageBus> {
+        &self.bus
+    }
+}
+
+/// Builder for `MultiAgentSystem`
+#[derive(Debug, Clone, Default)]
+pub struct MultiAgentSystemBuilder {
+    /// Coordination strategy
+    strategy: CoordinationStrategy,
+    /// Configuration of the underlying event bus
+    eventbus_config: EventBusConfig,
+}
+
+impl MultiAgentSystemBuilder {
+    /// Set the coordination strategy
+    pub fn with_coordination_strategy(mut self, strategy: CoordinationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set the event bus configuration
+    pub fn with_eventbus_config(mut self, config: EventBusConfig) -> Self {
+        self.eventbus_config = config;
+        self
+    }
+
+    /// Build the system and its event bus
+    pub async fn build(self) -> Result<MultiAgentSystem> {
+        let eventbus = EventBus::new(self.eventbus_config)
+            .await
+            .map_err(|e| AgentError::InitializationFailed {
+                component: "eventbus".into(),
+                reason: e.to_string(),
+            })?;
+
+        Ok(MultiAgentSystem {
+            strategy: self.strategy,
+            agents: BTreeMap::new(),
+            bus: Arc::new(MessageBus::new(eventbus)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn system() -> MultiAgentSystem {
+        MultiAgentSystem::builder()
+            .with_coordination_strategy(CoordinationStrategy::Hierarchical)
+            .build()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_researcher_hands_draft_to_reviewer() {
+        let mut system = system().await;
+        let mut researcher = system.add_agent("researcher-1", AgentRole::Researcher).await.unwrap();
+        let mut reviewer = system.add_agent("reviewer-1", AgentRole::Reviewer).await.unwrap();
+
+        let correlation_id = researcher
+            .send_to("reviewer-1", Message::request(json!({ "draft": "Qubits are classical bits." })))
+            .await
+            .unwrap();
+
+        let draft = reviewer.receive().await.unwrap();
+        assert_eq!(draft.sender, "researcher-1");
+        assert_eq!(draft.role, AgentRole::Researcher);
+        assert_eq!(draft.kind, MessageKind::Request);
+        assert_eq!(draft.correlation_id, correlation_id);
+
+        reviewer
+            .reply(&draft, json!({ "feedback": "Qubits can be in superposition." }))
+            .await
+            .unwrap();
+        assert_eq!(reviewer.inbox.pending_acks(), 1);
+        assert!(reviewer.ack(&draft));
+
+        let feedback = researcher.receive().await.unwrap();
+        assert_eq!(feedback.sender, "reviewer-1");
+        assert_eq!(feedback.role, AgentRole::Reviewer);
+        assert_eq!(feedback.kind, MessageKind::Response);
+        assert_eq!(feedback.correlation_id, correlation_id);
+        assert_eq!(feedback.content["feedback"], "Qubits can be in superposition.");
+        assert!(researcher.ack(&feedback));
+        assert!(!researcher.ack(&feedback));
+        assert!(researcher.try_receive().is_none());
+        assert_eq!(researcher.inbox.pending_acks(), 0);
+        assert_eq!(reviewer.inbox.pending_acks(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_handler_gets_message_again() {
+        let mut system = system().await;
+        let sender = system.add_agent("coordinator-1", AgentRole::Coordinator).await.unwrap();
+        let mut worker = system.add_agent("writer-1", AgentRole::Writer).await.unwrap();
+        sender.send_to("writer-1", Message::request(json!({ "task": "outline" }))).await.unwrap();
+
+        let failed = worker
+            .handle_next(|_message| async {
+                Err(AgentError::TaskExecutionFailed {
+                    task_id: "outline".into(),
+                    reason: "model unavailable".into(),
+                })
+            })
+            .await;
+        assert!(failed.is_err());
+
+        let mut handled = None;
+        worker
+            .handle_next(|message| {
+                handled = Some(message);
+                async { Ok(()) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(handled.unwrap().content["task"], "outline");
+        assert!(worker.try_receive().is_none());
+        assert_eq!(worker.inbox.pending_acks(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_send_to_unknown_agent() {
+        let mut system = system().await;
+        let researcher = system.add_agent("researcher-1", AgentRole::Researcher).await.unwrap();
+        let observer = system
+            .bus
+            .eventbus
+            .lock()
+            .await
+            .subscribe("agents.*", Filter::default())
+            .await
+            .unwrap();
+
+        let result = researcher.send_to("nobody", Message::notification(json!("hello"))).await;
+        assert!(matches!(result, Err(AgentError::AgentNotFound { .. })));
+        assert!(observer.try_receive().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_agent_rejected() {
+        let mut system = system().await;
+        system.add_agent("writer-1", AgentRole::Writer).await.unwrap();
+
+        let result = system.add_agent("writer-1", AgentRole::Writer).await;
+        assert!(matches!(result, Err(AgentError::ConfigurationError { .. })));
+        assert_eq!(system.agent_count(), 1);
+    }
+
+    #[test]
+    fn test_role_names_roundtrip() {
+        for role in [
+            AgentRole::Coordinator,
+            AgentRole::Researcher,
+            AgentRole::Writer,
+            AgentRole::Reviewer,
+            AgentRole::Custom("planner".into()),
+        ] {
+            assert_eq!(AgentRole::from_name(role.as_str()), role);
+        }
+    }
+}
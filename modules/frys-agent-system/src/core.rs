@@ -76,6 +76,8 @@ pub struct AgentConfig {
     pub memory_config: MemoryConfig,
     /// Tool configurations
     pub tool_configs: alloc::collections::BTreeMap<ToolId, ToolConfig>,
+    /// Per-task resource budget
+    pub budget: AgentBudget,
 }
 
 impl Default for AgentConfig {
@@ -91,10 +93,29 @@ impl Default for AgentConfig {
             communication_config: CommunicationConfig::default(),
             memory_config: MemoryConfig::default(),
             tool_configs: alloc::collections::BTreeMap::new(),
+            budget: AgentBudget::default(),
         }
     }
 }
 
+/// Resource budget for a single task
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentBudget {
+    /// Maximum number of tool invocations
+    pub max_tool_calls: Option<u64>,
+    /// Maximum number of LLM tokens consumed
+    pub max_llm_tokens: Option<u64>,
+    /// Maximum wall-clock time
+    pub max_wall_time: Option<Duration>,
+}
+
+impl AgentBudget {
+    /// Budget without any limits
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+}
+
 /// Safety levels for agent operations
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SafetyLevel {
@@ -502,6 +523,7 @@ mod tests {
         assert_eq!(config.name, "Default Agent");
         assert_eq!(config.max_execution_time, DEFAULT_AGENT_TIMEOUT);
         assert_eq!(config.safety_level, SafetyLevel::Medium);
+        assert_eq!(config.budget, AgentBudget::unlimited());
     }
 
     #[test]
@@ -59,6 +59,13 @@ pub enum AgentError {
         actual: alloc::string::String,
     },
 
+    /// Agent budget exhausted
+    BudgetExceeded {
+        resource: alloc::string::String,
+        limit: u64,
+        consumed: u64,
+    },
+
     /// Configuration error
     ConfigurationError {
         parameter: alloc::string::String,
@@ -173,6 +180,9 @@ impl fmt::Display for AgentError {
             AgentError::ResourceLimitExceeded { resource, limit, actual } => {
                 write!(f, "Resource '{}' limit exceeded: {} > {}", resource, actual, limit)
             }
+            AgentError::BudgetExceeded { resource, limit, consumed } => {
+                write!(f, "Budget exceeded for '{}': consumed {} of {}", resource, consumed, limit)
+            }
             AgentError::ConfigurationError { parameter, reason } => {
                 write!(f, "Configuration error for '{}': {}", parameter, reason)
             }
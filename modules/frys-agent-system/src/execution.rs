@@ -0,0 +1,722 @@
+//! Tool execution with per-invocation timeouts, cancellation, budgets and
+//! pre-execution action validation
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ::core::sync::atomic::{AtomicU64, Ordering};
+use ::core::time::Duration;
+use futures::future::BoxFuture;
+use std::time::Instant;
+
+/// Parameters passed to a tool invocation
+pub type ToolParams = BTreeMap<String, serde_json::Value>;
+
+/// Asynchronous tool handler
+pub type ToolHandler = Arc<dyn Fn(ToolParams) -> BoxFuture<'static, Result<ToolResult>> + Send + Sync>;
+
+/// Result of a tool invocation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolResult {
+    /// Plain text output
+    Text(String),
+    /// Structured output
+    Json(serde_json::Value),
+    /// The tool exceeded its execution time and was cancelled
+    Timeout {
+        /// Tool that timed out
+        tool_id: ToolId,
+        /// Timeout applied to each attempt
+        timeout: Duration,
+        /// Number of attempts made before giving up
+        attempts: u32,
+    },
+    /// An action validator refused the invocation
+    Denied {
+        /// Tool that was not invoked
+        tool_id: ToolId,
+        /// Reason given by the validator
+        reason: String,
+    },
+}
+
+impl ToolResult {
+    /// Check whether the invocation timed out
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, ToolResult::Timeout { .. })
+    }
+
+    /// Check whether the invocation was refused by a validator
+    pub fn is_denied(&self) -> bool {
+        matches!(self, ToolResult::Denied { .. })
+    }
+}
+
+/// What to do when a tool invocation exceeds its timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPolicy {
+    /// Record the timeout and continue with the next step
+    Abandon,
+    /// Retry up to `max_retries` times, then abandon
+    Retry {
+        /// Additional attempts after the first timeout
+        max_retries: u32,
+    },
+    /// Fail the whole task with a timeout error
+    FailTask,
+}
+
+impl Default for TimeoutPolicy {
+    fn default() -> Self {
+        TimeoutPolicy::Retry { max_retries: 1 }
+    }
+}
+
+impl ToolResult {
+    /// Render the result as task output text
+    fn render(&self) -> Option<String> {
+        match self {
+            ToolResult::Text(text) => Some(text.clone()),
+            ToolResult::Json(value) => Some(value.to_string()),
+            ToolResult::Timeout { .. } | ToolResult::Denied { .. } => None,
+        }
+    }
+}
+
+/// Outcome of an agent task
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskResult {
+    /// Task finished successfully
+    Completed {
+        /// Combined output of all steps
+        output: String,
+        /// Raw result of every step
+        artifacts: Vec<ToolResult>,
+    },
+    /// Task was aborted
+    Failed {
+        /// Reason for the failure
+        error: AgentError,
+        /// Output produced before the failure, if any
+        partial_output: Option<String>,
+    },
+    /// Task was cancelled before it finished
+    Cancelled,
+}
+
+/// Resources consumed by a task so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetUsage {
+    /// Tool invocations made
+    pub tool_calls: u64,
+    /// LLM tokens consumed
+    pub llm_tokens: u64,
+    /// Wall-clock time since the task started
+    pub elapsed: Duration,
+}
+
+/// Tracks consumption against an `AgentBudget`.
+///
+/// The tracker is shared between the executor and the components that
+/// consume resources (LLM clients, tools), so counters can be read while
+/// the task is still running.
+#[derive(Debug)]
+pub struct BudgetTracker {
+    /// Limits being enforced
+    budget: AgentBudget,
+    /// Tool invocations made
+    tool_calls: AtomicU64,
+    /// LLM tokens consumed
+    llm_tokens: AtomicU64,
+    /// When the task started
+    started_at: Instant,
+}
+
+impl BudgetTracker {
+    /// Start tracking a task against the budget
+    pub fn new(budget: AgentBudget) -> Self {
+        Self {
+            budget,
+            tool_calls: AtomicU64::new(0),
+            llm_tokens: AtomicU64::new(0),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Budget being enforced
+    pub fn budget(&self) -> &AgentBudget {
+        &self.budget
+    }
+
+    /// Snapshot of current consumption
+    pub fn usage(&self) -> BudgetUsage {
+        BudgetUsage {
+            tool_calls: self.tool_calls.load(Ordering::SeqCst),
+            llm_tokens: self.llm_tokens.load(Ordering::SeqCst),
+            elapsed: self.started_at.elapsed(),
+        }
+    }
+
+    /// Count a tool invocation, failing if it exceeds the budget. A call
+    /// refused for exceeding the budget is not counted.
+    pub fn record_tool_call(&self) -> Result<()> {
+        let limit = self.budget.max_tool_calls.unwrap_or(u64::MAX);
+        self.tool_calls
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |calls| (calls < limit).then_some(calls + 1))
+            .map(|_| ())
+            .map_err(|calls| AgentError::BudgetExceeded {
+                resource: "tool_calls".into(),
+                limit,
+                consumed: calls + 1,
+            })
+    }
+
+    /// Count consumed LLM tokens, failing if they exceed the budget
+    pub fn record_llm_tokens(&self, tokens: u64) -> Result<()> {
+        let consumed = self.llm_tokens.fetch_add(tokens, Ordering::SeqCst) + tokens;
+        check_limit("llm_tokens", self.budget.max_llm_tokens, consumed)
+    }
+
+    /// Check that the wall-clock limit has not been reached
+    pub fn check_wall_time(&self) -> Result<()> {
+        let elapsed = self.started_at.elapsed();
+        match self.budget.max_wall_time {
+            Some(limit) if elapsed >= limit => Err(wall_time_exceeded(limit, elapsed)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check every limit without consuming anything
+    pub fn check(&self) -> Result<()> {
+        check_limit("tool_calls", self.budget.max_tool_calls, self.tool_calls.load(Ordering::SeqCst))?;
+        check_limit("llm_tokens", self.budget.max_llm_tokens, self.llm_tokens.load(Ordering::SeqCst))?;
+        self.check_wall_time()
+    }
+
+    /// Wall-clock time left, if the budget has a wall-clock limit
+    fn remaining_wall_time(&self) -> Option<Duration> {
+        self.budget
+            .max_wall_time
+            .map(|limit| limit.saturating_sub(self.started_at.elapsed()))
+    }
+}
+
+fn check_limit(resource: &str, limit: Option<u64>, consumed: u64) -> Result<()> {
+    match limit {
+        Some(limit) if consumed > limit => Err(AgentError::BudgetExceeded {
+            resource: resource.into(),
+            limit,
+            consumed,
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn wall_time_exceeded(limit: Duration, elapsed: Duration) -> AgentError {
+    AgentError::BudgetExceeded {
+        resource: "wall_time_ms".into(),
+        limit: limit.as_millis() as u64,
+        consumed: elapsed.as_millis() as u64,
+    }
+}
+
+/// A tool that can be invoked by an agent
+#[derive(Clone)]
+pub struct Tool {
+    /// Tool identifier
+    pub id: ToolId,
+    /// Human readable description
+    pub description: String,
+    /// Maximum execution time per invocation
+    pub timeout: Duration,
+    /// Invocation handler
+    handler: ToolHandler,
+}
+
+impl Tool {
+    /// Create a new tool with the default execution time limit
+    pub fn new<F>(id: impl Into<ToolId>, description: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(ToolParams) -> BoxFuture<'static, Result<ToolResult>> + Send + Sync + 'static,
+    {
+        Self {
+            id: id.into(),
+            description: description.into(),
+            timeout: Duration::from_secs(MAX_TOOL_EXECUTION_TIME),
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Override the execution time limit
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Apply limits from a tool configuration
+    pub fn with_config(mut self, config: &ToolConfig) -> Self {
+        self.timeout = Duration::from_secs(config.max_execution_time);
+        self
+    }
+}
+
+impl ::core::fmt::Debug for Tool {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("Tool")
+            .field("id", &self.id)
+            .field("description", &self.description)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// A single step of a tool plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// Tool to invoke
+    pub tool_id: ToolId,
+    /// Invocation parameters
+    pub params: ToolParams,
+}
+
+impl ToolCall {
+    /// Create a new tool call
+    pub fn new(tool_id: impl Into<ToolId>, params: ToolParams) -> Self {
+        Self {
+            tool_id: tool_id.into(),
+            params,
+        }
+    }
+}
+
+/// Executes tools with timeout enforcement
+pub struct ToolExecutor {
+    /// Registered tools
+    tools: BTreeMap<ToolId, Tool>,
+    /// Timeout handling policy
+    timeout_policy: TimeoutPolicy,
+    /// Checks run before every invocation, in order
+    validators: Vec<Arc<dyn ActionValidator>>,
+    /// Record of every validated invocation
+    audit_log: Arc<AuditLog>,
+}
+
+impl ToolExecutor {
+    /// Create new tool executor
+    pub fn new() -> Self {
+        Self {
+            tools: BTreeMap::new(),
+            timeout_policy: TimeoutPolicy::default(),
+            validators: Vec::new(),
+            audit_log: Arc::new(AuditLog::new()),
+        }
+    }
+
+    /// Set the timeout handling policy
+    pub fn with_timeout_policy(mut self, policy: TimeoutPolicy) -> Self {
+        self.timeout_policy = policy;
+        self
+    }
+
+    /// Add a validator consulted before every tool invocation
+    pub fn with_validator(mut self, validator: Arc<dyn ActionValidator>) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    /// Record validation decisions to a shared audit log
+    pub fn with_audit_log(mut self, audit_log: Arc<AuditLog>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /// Audit log the executor records validation decisions to
+    pub fn audit_log(&self) -> &Arc<AuditLog> {
+        &self.audit_log
+    }
+
+    /// Register a tool
+    pub fn register_tool(&mut self, tool: Tool) {
+        self.tools.insert(tool.id.clone(), tool);
+    }
+
+    /// Get a registered tool
+    pub fn tool(&self, tool_id: &str) -> Option<&Tool> {
+        self.tools.get(tool_id)
+    }
+
+    /// Invoke a tool, cancelling it if it exceeds its timeout.
+    ///
+    /// The call is first checked by the registered validators and their
+    /// decision recorded to the audit log. A denied call is not run and
+    /// yields `ToolResult::Denied`, so the caller can keep planning; a
+    /// modified call runs in place of the requested one.
+    ///
+    /// Timed out attempts are retried according to the policy. When the
+    /// policy gives up, `ToolResult::Timeout` is returned so the caller can
+    /// keep planning; `TimeoutPolicy::FailTask` returns an error instead.
+    pub async fn invoke(&self, tool_id: &str, params: ToolParams) -> Result<ToolResult> {
+        let requested = ToolCall::new(tool_id, params);
+        let decision = validate_action(&self.validators, &requested).await;
+        self.audit_log.record(AuditEntry {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            requested: requested.clone(),
+            decision: decision.clone(),
+        });
+
+        let call = match decision {
+            ActionDecision::Allow => requested,
+            ActionDecision::Modify { call, .. } => call,
+            ActionDecision::Deny { reason } => {
+                return Ok(ToolResult::Denied {
+                    tool_id: requested.tool_id,
+                    reason,
+                })
+            }
+        };
+        self.run_tool(&call.tool_id, call.params).await
+    }
+
+    /// Invoke a tool without validating the call
+    async fn run_tool(&self, tool_id: &str, params: ToolParams) -> Result<ToolResult> {
+        let tool = self.tools.get(tool_id).ok_or_else(|| AgentError::ToolExecutionError {
+            tool_name: tool_id.into(),
+            error_message: "tool not registered".into(),
+        })?;
+
+        let max_attempts = match self.timeout_policy {
+            TimeoutPolicy::Retry { max_retries } => max_retries + 1,
+            TimeoutPolicy::Abandon | TimeoutPolicy::FailTask => 1,
+        };
+
+        for _ in 0..max_attempts {
+            // Dropping the future on timeout cancels the in-flight handler.
+            match tokio::time::timeout(tool.timeout, (tool.handler)(params.clone())).await {
+                Ok(result) => return result,
+                Err(_) => continue,
+            }
+        }
+
+        if self.timeout_policy == TimeoutPolicy::FailTask {
+            return Err(AgentError::TimeoutError {
+                operation: alloc::format!("tool '{}'", tool_id),
+                timeout_seconds: tool.timeout.as_secs(),
+            });
+        }
+
+        Ok(ToolResult::Timeout {
+            tool_id: tool.id.clone(),
+            timeout: tool.timeout,
+            attempts: max_attempts,
+        })
+    }
+
+    /// Execute a sequence of tool calls, continuing past abandoned timeouts
+    /// and denied calls
+    pub async fn execute_plan(&self, plan: &[ToolCall]) -> Result<Vec<ToolResult>> {
+        let mut results = Vec::with_capacity(plan.len());
+        for call in plan {
+            results.push(self.invoke(&call.tool_id, call.params.clone()).await?);
+        }
+        Ok(results)
+    }
+
+    /// Execute a tool plan as a task, enforcing the tracker's budget.
+    ///
+    /// The task is aborted with `AgentError::BudgetExceeded` as soon as any
+    /// limit is hit; output produced up to that point is kept as
+    /// `partial_output`. A tool still running when the wall-clock budget
+    /// runs out is cancelled.
+    pub async fn run_task(&self, plan: &[ToolCall], tracker: &BudgetTracker) -> TaskResult {
+        let mut artifacts = Vec::with_capacity(plan.len());
+
+        for call in plan {
+            let step = async {
+                tracker.check()?;
+                tracker.record_tool_call()?;
+
+                let invocation = self.invoke(&call.tool_id, call.params.clone());
+                let result = match tracker.remaining_wall_time() {
+                    Some(remaining) => tokio::time::timeout(remaining, invocation).await.map_err(|_| {
+                        wall_time_exceeded(tracker.budget.max_wall_time.unwrap_or_default(), tracker.started_at.elapsed())
+                    })??,
+                    None => invocation.await?,
+                };
+
+                // Tools may report LLM usage through the shared tracker
+                tracker.check()?;
+                Ok::<_, AgentError>(result)
+            };
+
+            match step.await {
+                Ok(result) => artifacts.push(result),
+                Err(error) => {
+                    return TaskResult::Failed {
+                        error,
+                        partial_output: render_output(&artifacts),
+                    }
+                }
+            }
+        }
+
+        TaskResult::Completed {
+            output: render_output(&artifacts).unwrap_or_default(),
+            artifacts,
+        }
+    }
+}
+
+/// Join the rendered outputs of completed steps
+fn render_output(artifacts: &[ToolResult]) -> Option<String> {
+    let parts: Vec<String> = artifacts.iter().filter_map(ToolResult::render).collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n"))
+    }
+}
+
+impl Default for ToolExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn sleepy_tool(attempts: Arc<AtomicU32>) -> Tool {
+        Tool::new("web_search", "Search the web", move |_params| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(ToolResult::Text("too late".into()))
+            })
+        })
+        .with_timeout(Duration::from_millis(20))
+    }
+
+    fn echo_tool() -> Tool {
+        Tool::new("echo", "Echo the input", |params| {
+            Box::pin(async move { Ok(ToolResult::Json(serde_json::json!(params))) })
+        })
+    }
+
+    #[tokio::test]
+    async fn test_timeout_then_continue_planning() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut executor = ToolExecutor::new().with_timeout_policy(TimeoutPolicy::Abandon);
+        executor.register_tool(sleepy_tool(attempts.clone()));
+        executor.register_tool(echo_tool());
+
+        let plan = vec![
+            ToolCall::new("web_search", ToolParams::new()),
+            ToolCall::new("echo", ToolParams::new()),
+        ];
+        let results = executor.execute_plan(&plan).await.unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], ToolResult::Timeout { attempts: 1, .. }));
+        assert!(matches!(results[1], ToolResult::Json(_)));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_retry_policy() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut executor = ToolExecutor::new().with_timeout_policy(TimeoutPolicy::Retry { max_retries: 2 });
+        executor.register_tool(sleepy_tool(attempts.clone()));
+
+        let result = executor.invoke("web_search", ToolParams::new()).await.unwrap();
+        assert!(matches!(result, ToolResult::Timeout { attempts: 3, .. }));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_fail_task_policy() {
+        let mut executor = ToolExecutor::new().with_timeout_policy(TimeoutPolicy::FailTask);
+        executor.register_tool(sleepy_tool(Arc::new(AtomicU32::new(0))));
+
+        let result = executor.invoke("web_search", ToolParams::new()).await;
+        assert!(matches!(result, Err(AgentError::TimeoutError { .. })));
+    }
+
+    fn counting_tool(tracker: Arc<BudgetTracker>, tokens_per_call: u64) -> Tool {
+        Tool::new("llm", "Call the language model", move |_params| {
+            let tracker = tracker.clone();
+            Box::pin(async move {
+                let _ = tracker.record_llm_tokens(tokens_per_call);
+                Ok(ToolResult::Text("answer".into()))
+            })
+        })
+    }
+
+    fn assert_budget_exceeded(result: &TaskResult, expected: &str) {
+        match result {
+            TaskResult::Failed {
+                error: AgentError::BudgetExceeded { resource, .. },
+                ..
+            } => assert_eq!(resource, expected),
+            other => panic!("expected budget failure, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_budget_tool_calls_limit() {
+        let mut executor = ToolExecutor::new();
+        executor.register_tool(echo_tool());
+        let tracker = BudgetTracker::new(AgentBudget {
+            max_tool_calls: Some(2),
+            ..AgentBudget::unlimited()
+        });
+
+        let plan = vec![ToolCall::new("echo", ToolParams::new()); 5];
+        let result = executor.run_task(&plan, &tracker).await;
+
+        assert_budget_exceeded(&result, "tool_calls");
+        if let TaskResult::Failed { partial_output, .. } = &result {
+            assert_eq!(partial_output.as_deref(), Some("{}\n{}"));
+        }
+        assert_eq!(tracker.usage().tool_calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_budget_llm_tokens_limit() {
+        let tracker = Arc::new(BudgetTracker::new(AgentBudget {
+            max_llm_tokens: Some(100),
+            ..AgentBudget::unlimited()
+        }));
+        let mut executor = ToolExecutor::new();
+        executor.register_tool(counting_tool(tracker.clone(), 60));
+
+        let plan = vec![ToolCall::new("llm", ToolParams::new()); 5];
+        let result = executor.run_task(&plan, &tracker).await;
+
+        assert_budget_exceeded(&result, "llm_tokens");
+        let usage = tracker.usage();
+        assert_eq!(usage.tool_calls, 2);
+        assert_eq!(usage.llm_tokens, 120);
+    }
+
+    #[tokio::test]
+    async fn test_budget_wall_time_limit() {
+        let mut executor = ToolExecutor::new();
+        executor.register_tool(sleepy_tool(Arc::new(AtomicU32::new(0))).with_timeout(Duration::from_secs(10)));
+        let tracker = BudgetTracker::new(AgentBudget {
+            max_wall_time: Some(Duration::from_millis(30)),
+            ..AgentBudget::unlimited()
+        });
+
+        let started = Instant::now();
+        let plan = vec![ToolCall::new("web_search", ToolParams::new())];
+        let result = executor.run_task(&plan, &tracker).await;
+
+        assert_budget_exceeded(&result, "wall_time_ms");
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_budget_unlimited_completes() {
+        let tracker = Arc::new(BudgetTracker::new(AgentBudget::unlimited()));
+        let mut executor = ToolExecutor::new();
+        executor.register_tool(counting_tool(tracker.clone(), 1_000));
+
+        let plan = vec![ToolCall::new("llm", ToolParams::new()); 3];
+        let result = executor.run_task(&plan, &tracker).await;
+
+        assert_eq!(
+            result,
+            TaskResult::Completed {
+                output: "answer\nanswer\nanswer".into(),
+                artifacts: vec![ToolResult::Text("answer".into()); 3],
+            }
+        );
+        assert_eq!(tracker.usage().llm_tokens, 3_000);
+    }
+
+    /// Refuses destructive tools and caps search result counts
+    struct Guardrail;
+
+    #[async_trait::async_trait]
+    impl ActionValidator for Guardrail {
+        async fn validate(&self, call: &ToolCall) -> ActionDecision {
+            match call.tool_id.as_str() {
+                "delete" => ActionDecision::Deny {
+                    reason: "destructive actions are not allowed".into(),
+                },
+                "echo" if call.params.get("limit").and_then(serde_json::Value::as_u64) > Some(10) => {
+                    let mut call = call.clone();
+                    call.params.insert("limit".into(), serde_json::json!(10));
+                    ActionDecision::Modify {
+                        call,
+                        reason: "limit capped at 10".into(),
+                    }
+                }
+                _ => ActionDecision::Allow,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validator_denial_is_audited_and_planning_continues() {
+        let deletions = Arc::new(AtomicU32::new(0));
+        let counter = deletions.clone();
+        let mut executor = ToolExecutor::new().with_validator(Arc::new(Guardrail));
+        executor.register_tool(echo_tool());
+        executor.register_tool(Tool::new("delete", "Delete a file", move |_params| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Ok(ToolResult::Text("deleted".into())) })
+        }));
+
+        let plan = vec![
+            ToolCall::new("echo", ToolParams::from([("step".into(), serde_json::json!(1))])),
+            ToolCall::new("delete", ToolParams::from([("path".into(), serde_json::json!("/data"))])),
+            ToolCall::new("echo", ToolParams::from([("step".into(), serde_json::json!(2))])),
+        ];
+        let result = executor.run_task(&plan, &BudgetTracker::new(AgentBudget::unlimited())).await;
+
+        let TaskResult::Completed { output, artifacts } = result else {
+            panic!("expected the task to complete, got {:?}", result);
+        };
+        assert_eq!(output, "{\"step\":1}\n{\"step\":2}");
+        assert_eq!(
+            artifacts[1],
+            ToolResult::Denied {
+                tool_id: "delete".into(),
+                reason: "destructive actions are not allowed".into(),
+            }
+        );
+        assert_eq!(deletions.load(Ordering::SeqCst), 0);
+
+        let audit = executor.audit_log();
+        assert_eq!(audit.entries().len(), 3);
+        let denials = audit.denials();
+        assert_eq!(denials.len(), 1);
+        assert_eq!(denials[0].requested, plan[1]);
+    }
+
+    #[tokio::test]
+    async fn test_validator_modifies_call() {
+        let mut executor = ToolExecutor::new().with_validator(Arc::new(Guardrail));
+        executor.register_tool(echo_tool());
+
+        let params = ToolParams::from([("limit".into(), serde_json::json!(500))]);
+        let result = executor.invoke("echo", params.clone()).await.unwrap();
+        assert_eq!(result, ToolResult::Json(serde_json::json!({ "limit": 10 })));
+
+        let entry = &executor.audit_log().entries()[0];
+        assert_eq!(entry.requested.params, params);
+        assert!(matches!(&entry.decision, ActionDecision::Modify { reason, .. } if reason == "limit capped at 10"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool() {
+        let executor = ToolExecutor::new();
+        let result = executor.invoke("missing", ToolParams::new()).await;
+        assert!(matches!(result, Err(AgentError::ToolExecutionError { .. })));
+    }
+}
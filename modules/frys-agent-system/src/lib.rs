@@ -221,20 +221,26 @@
 
 // Public API exports
 pub mod core;
+pub mod agent;
 pub mod intelligence;
 pub mod communication;
 pub mod memory;
 pub mod execution;
+pub mod safety;
 pub mod monitoring;
 pub mod multimodal;
+pub mod replay;
 
 // Re-exports for convenience
 pub use core::*;
+pub use agent::*;
 pub use intelligence::*;
 pub use communication::*;
 pub use memory::*;
 pub use execution::*;
+pub use safety::*;
 pub use multimodal::*;
+pub use replay::*;
 
 // Error types
 mod error;
@@ -252,6 +258,7 @@ pub const MAX_TOOL_EXECUTION_TIME: u64 = 60; // 1 minute
 pub const MEMORY_CONSOLIDATION_THRESHOLD: f64 = 0.7;
 pub const MAX_PLANNING_DEPTH: usize = 10;
 pub const DEFAULT_LEARNING_RATE: f64 = 0.01;
+pub const MAX_AUDIT_LOG_ENTRIES: usize = 10_000;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,410 @@
+//! Episodic memory with optional SQLite persistence
+
+use crate::*;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A single remembered episode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Episode {
+    /// Episode identifier
+    pub id: String,
+    /// What the agent observed
+    pub observation: String,
+    /// What the agent did
+    pub action: String,
+    /// What happened as a result
+    pub outcome: String,
+    /// Importance (0.0 to 1.0)
+    pub importance: f64,
+    /// Time the episode was recorded (milliseconds since epoch)
+    pub timestamp: i64,
+    /// Number of episodes merged into this one by consolidation
+    pub occurrences: u32,
+}
+
+impl Episode {
+    /// Create a new episode recorded now
+    pub fn new(observation: impl Into<String>, action: impl Into<String>, outcome: impl Into<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            observation: observation.into(),
+            action: action.into(),
+            outcome: outcome.into(),
+            importance: 0.5,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            occurrences: 1,
+        }
+    }
+
+    /// Set episode importance
+    pub fn with_importance(mut self, importance: f64) -> Self {
+        self.importance = importance.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set episode timestamp
+    pub fn with_timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Lowercased word tokens of the episode content
+    fn tokens(&self) -> BTreeSet<String> {
+        tokenize(&alloc::format!("{} {} {}", self.observation, self.action, self.outcome))
+    }
+
+    /// Jaccard similarity of episode contents
+    pub fn similarity(&self, other: &Episode) -> f64 {
+        jaccard(&self.tokens(), &other.tokens())
+    }
+}
+
+/// Episodic memory store
+#[derive(Debug)]
+pub struct EpisodicMemory {
+    /// Memory configuration
+    config: EpisodicMemoryConfig,
+    /// Persistence backend
+    persistence: MemoryPersistence,
+    /// Stored episodes, oldest first
+    episodes: Vec<Episode>,
+}
+
+impl EpisodicMemory {
+    /// Create new episodic memory
+    pub fn new(config: EpisodicMemoryConfig, persistence: MemoryPersistence) -> Self {
+        Self {
+            config,
+            persistence,
+            episodes: Vec::new(),
+        }
+    }
+
+    /// Create episodic memory from an agent memory configuration
+    pub fn from_config(config: &MemoryConfig) -> Self {
+        Self::new(config.episodic.clone(), config.persistence.clone())
+    }
+
+    /// Record an episode, evicting the least important one when full
+    pub fn record(&mut self, episode: Episode) {
+        if self.episodes.len() >= self.config.capacity {
+            if let Some(index) = self
+                .episodes
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.importance
+                        .partial_cmp(&b.importance)
+                        .unwrap_or(::core::cmp::Ordering::Equal)
+                        .then(a.timestamp.cmp(&b.timestamp))
+                })
+                .map(|(index, _)| index)
+            {
+                self.episodes.remove(index);
+            }
+        }
+
+        let position = self.episodes.partition_point(|e| e.timestamp <= episode.timestamp);
+        self.episodes.insert(position, episode);
+    }
+
+    /// Number of stored episodes
+    pub fn len(&self) -> usize {
+        self.episodes.len()
+    }
+
+    /// Check whether memory is empty
+    pub fn is_empty(&self) -> bool {
+        self.episodes.is_empty()
+    }
+
+    /// All stored episodes, oldest first
+    pub fn episodes(&self) -> &[Episode] {
+        &self.episodes
+    }
+
+    /// Most recent episodes, newest first
+    pub fn recent(&self, limit: usize) -> Vec<&Episode> {
+        self.episodes.iter().rev().take(limit).collect()
+    }
+
+    /// Episodes relevant to a query, ranked by relevance weighted by
+    /// importance and recency
+    pub fn query(&self, query: &str, limit: usize) -> Vec<&Episode> {
+        let query_tokens = tokenize(query);
+        let now = self.episodes.last().map(|e| e.timestamp).unwrap_or(0);
+
+        let mut scored: Vec<(f64, &Episode)> = self
+            .episodes
+            .iter()
+            .filter_map(|episode| {
+                let relevance = jaccard(&query_tokens, &episode.tokens());
+                if relevance == 0.0 {
+                    return None;
+                }
+                let age_hours = (now - episode.timestamp).max(0) as f64 / 3_600_000.0;
+                let recency = (-self.config.forgetting_rate * age_hours).exp();
+                Some((relevance * (0.5 + 0.5 * episode.importance) * recency, episode))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(::core::cmp::Ordering::Equal)
+                .then(b.1.timestamp.cmp(&a.1.timestamp))
+        });
+        scored.into_iter().take(limit).map(|(_, episode)| episode).collect()
+    }
+
+    /// Merge episodes recorded before `older_than` whose similarity is at
+    /// least the consolidation threshold. Returns the number of episodes
+    /// merged away.
+    pub fn consolidate(&mut self, older_than: i64) -> usize {
+        let threshold = self.config.consolidation_threshold;
+        let mut kept: Vec<Episode> = Vec::with_capacity(self.episodes.len());
+        let mut merged = 0;
+
+        for episode in self.episodes.drain(..) {
+            if episode.timestamp >= older_than {
+                kept.push(episode);
+                continue;
+            }
+
+            let target = kept
+                .iter_mut()
+                .filter(|e| e.timestamp < older_than)
+                .find(|e| e.similarity(&episode) >= threshold);
+
+            match target {
+                Some(target) => {
+                    target.occurrences += episode.occurrences;
+                    target.importance = target.importance.max(episode.importance);
+                    target.timestamp = target.timestamp.max(episode.timestamp);
+                    target.outcome = episode.outcome;
+                    merged += 1;
+                }
+                None => kept.push(episode),
+            }
+        }
+
+        kept.sort_by_key(|e| e.timestamp);
+        self.episodes = kept;
+        merged
+    }
+
+    /// Persist all episodes to the configured backend
+    pub fn save_memory(&self) -> Result<()> {
+        match &self.persistence {
+            MemoryPersistence::InMemory => Ok(()),
+            #[cfg(feature = "memory")]
+            MemoryPersistence::Database { url } => sqlite::save(url, &self.episodes),
+            other => Err(unsupported_persistence(other)),
+        }
+    }
+
+    /// Replace in-memory episodes with those from the configured backend.
+    /// Returns the number of episodes loaded.
+    pub fn load_memory(&mut self) -> Result<usize> {
+        match &self.persistence {
+            MemoryPersistence::InMemory => Ok(self.episodes.len()),
+            #[cfg(feature = "memory")]
+            MemoryPersistence::Database { url } => {
+                self.episodes = sqlite::load(url)?;
+                Ok(self.episodes.len())
+            }
+            other => Err(unsupported_persistence(other)),
+        }
+    }
+}
+
+fn unsupported_persistence(persistence: &MemoryPersistence) -> AgentError {
+    AgentError::ConfigurationError {
+        parameter: "memory_config.persistence".into(),
+        reason: alloc::format!("unsupported episodic memory backend: {:?}", persistence),
+    }
+}
+
+fn tokenize(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// SQLite episode storage
+#[cfg(feature = "memory")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{params, Connection};
+
+    const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS episodes (
+        id TEXT PRIMARY KEY,
+        observation TEXT NOT NULL,
+        action TEXT NOT NULL,
+        outcome TEXT NOT NULL,
+        importance REAL NOT NULL,
+        timestamp INTEGER NOT NULL,
+        occurrences INTEGER NOT NULL
+    )";
+
+    fn db_error(operation: &str, error: rusqlite::Error) -> AgentError {
+        AgentError::DatabaseError {
+            operation: operation.into(),
+            reason: error.to_string(),
+        }
+    }
+
+    fn open(url: &str) -> Result<Connection> {
+        let conn = Connection::open(url).map_err(|e| db_error("open", e))?;
+        conn.execute_batch(SCHEMA).map_err(|e| db_error("create_schema", e))?;
+        Ok(conn)
+    }
+
+    pub(super) fn save(url: &str, episodes: &[Episode]) -> Result<()> {
+        let mut conn = open(url)?;
+        let tx = conn.transaction().map_err(|e| db_error("save_memory", e))?;
+        tx.execute("DELETE FROM episodes", [])
+            .map_err(|e| db_error("save_memory", e))?;
+        for episode in episodes {
+            tx.execute(
+                "INSERT INTO episodes (id, observation, action, outcome, importance, timestamp, occurrences)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    episode.id,
+                    episode.observation,
+                    episode.action,
+                    episode.outcome,
+                    episode.importance,
+                    episode.timestamp,
+                    episode.occurrences,
+                ],
+            )
+            .map_err(|e| db_error("save_memory", e))?;
+        }
+        tx.commit().map_err(|e| db_error("save_memory", e))
+    }
+
+    pub(super) fn load(url: &str) -> Result<Vec<Episode>> {
+        let conn = open(url)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, observation, action, outcome, importance, timestamp, occurrences
+                 FROM episodes ORDER BY timestamp ASC",
+            )
+            .map_err(|e| db_error("load_memory", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Episode {
+                    id: row.get(0)?,
+                    observation: row.get(1)?,
+                    action: row.get(2)?,
+                    outcome: row.get(3)?,
+                    importance: row.get(4)?,
+                    timestamp: row.get(5)?,
+                    occurrences: row.get(6)?,
+                })
+            })
+            .map_err(|e| db_error("load_memory", e))?;
+        rows.collect::<::core::result::Result<Vec<_>, _>>()
+            .map_err(|e| db_error("load_memory", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory() -> EpisodicMemory {
+        EpisodicMemory::new(EpisodicMemoryConfig::default(), MemoryPersistence::InMemory)
+    }
+
+    #[test]
+    fn test_recent_and_query() {
+        let mut memory = memory();
+        memory.record(Episode::new("user asked about rust", "searched docs", "found answer").with_timestamp(1_000));
+        memory.record(Episode::new("user asked about python", "ran script", "script failed").with_timestamp(2_000));
+        memory.record(Episode::new("weather request", "called api", "sunny").with_timestamp(3_000));
+
+        let recent = memory.recent(2);
+        assert_eq!(recent[0].outcome, "sunny");
+        assert_eq!(recent[1].outcome, "script failed");
+
+        let relevant = memory.query("rust docs", 5);
+        assert_eq!(relevant.len(), 1);
+        assert_eq!(relevant[0].outcome, "found answer");
+    }
+
+    #[test]
+    fn test_capacity_evicts_least_important() {
+        let config = EpisodicMemoryConfig {
+            capacity: 2,
+            ..Default::default()
+        };
+        let mut memory = EpisodicMemory::new(config, MemoryPersistence::InMemory);
+        memory.record(Episode::new("a", "a", "a").with_importance(0.9).with_timestamp(1));
+        memory.record(Episode::new("b", "b", "b").with_importance(0.1).with_timestamp(2));
+        memory.record(Episode::new("c", "c", "c").with_importance(0.5).with_timestamp(3));
+
+        let outcomes: Vec<_> = memory.episodes().iter().map(|e| e.outcome.as_str()).collect();
+        assert_eq!(outcomes, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_consolidation_merges_similar_old_episodes() {
+        let mut memory = memory();
+        memory.record(Episode::new("check build status", "ran ci", "build green").with_timestamp(1_000));
+        memory.record(Episode::new("check build status", "ran ci", "build green").with_importance(0.8).with_timestamp(2_000));
+        memory.record(Episode::new("deploy service", "ran deploy", "deployed").with_timestamp(3_000));
+        memory.record(Episode::new("check build status", "ran ci", "build green").with_timestamp(10_000));
+
+        let merged = memory.consolidate(5_000);
+        assert_eq!(merged, 1);
+        assert_eq!(memory.len(), 3);
+
+        let consolidated = &memory.episodes()[0];
+        assert_eq!(consolidated.occurrences, 2);
+        assert_eq!(consolidated.importance, 0.8);
+        assert_eq!(consolidated.timestamp, 2_000);
+    }
+
+    #[cfg(feature = "memory")]
+    #[test]
+    fn test_persistence_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent_memory.db");
+        let persistence = MemoryPersistence::Database {
+            url: path.to_string_lossy().into_owned(),
+        };
+
+        {
+            let mut memory = EpisodicMemory::new(EpisodicMemoryConfig::default(), persistence.clone());
+            memory.record(Episode::new("user asked about rust", "searched docs", "found answer").with_timestamp(1_000));
+            memory.record(Episode::new("weather request", "called api", "sunny").with_timestamp(2_000));
+            memory.save_memory().unwrap();
+        }
+
+        let mut reloaded = EpisodicMemory::new(EpisodicMemoryConfig::default(), persistence);
+        assert_eq!(reloaded.load_memory().unwrap(), 2);
+        assert_eq!(reloaded.recent(1)[0].outcome, "sunny");
+        assert_eq!(reloaded.query("rust", 1)[0].action, "searched docs");
+    }
+
+    #[test]
+    fn test_unsupported_backend() {
+        let memory = EpisodicMemory::new(
+            EpisodicMemoryConfig::default(),
+            MemoryPersistence::Distributed { endpoints: Vec::new() },
+        );
+        assert!(matches!(memory.save_memory(), Err(AgentError::ConfigurationError { .. })));
+    }
+}
@@ -0,0 +1,337 @@
+//! Agent runs recorded to the audit log and their deterministic replay
+//!
+//! [`ToolExecutor::run_agent`] alternates between a [`LanguageModel`], which
+//! picks the next action from the steps taken so far, and the executor,
+//! which invokes the chosen tool. Each run is recorded to the executor's
+//! [`AuditLog`] under its own [`RunId`]: a start marker, every model
+//! response and tool result, errors included, and an end marker, so runs
+//! sharing an executor do not mix. [`ToolExecutor::replay`] drives the same
+//! loop for one recorded run with its responses and results fed back in
+//! place of live calls, so a run that misbehaved once, or failed, can be
+//! reproduced exactly.
+
+use crate::*;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Next action chosen by the model
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentAction {
+    /// Invoke a tool and show the model its result
+    Invoke(ToolCall),
+    /// End the run with a final answer
+    Finish {
+        /// Final answer of the run
+        output: String,
+    },
+}
+
+/// A language model response
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmResponse {
+    /// Raw text returned by the model
+    pub content: String,
+    /// Action parsed from the response
+    pub action: AgentAction,
+    /// Tokens consumed by the request
+    pub tokens: u64,
+}
+
+/// Model choosing the actions of an agent run
+#[async_trait::async_trait]
+pub trait LanguageModel: Send + Sync {
+    /// Choose the next action given the steps taken so far
+    async fn respond(&self, steps: &[AgentStep]) -> Result<LlmResponse>;
+}
+
+/// A model response and, for a tool invocation, the tool's result
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentStep {
+    /// Response choosing the action
+    pub response: LlmResponse,
+    /// Result of the invoked tool; `None` for the final answer
+    pub result: Option<ToolResult>,
+}
+
+/// Trace of a finished agent run
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentRun {
+    /// Id the run is recorded under in the audit log
+    pub run_id: RunId,
+    /// Steps in the order they were taken
+    pub steps: Vec<AgentStep>,
+    /// Final answer
+    pub output: String,
+}
+
+impl AgentRun {
+    /// Actions taken, final answer included
+    pub fn actions(&self) -> Vec<&AgentAction> {
+        self.steps.iter().map(|step| &step.response.action).collect()
+    }
+}
+
+/// Event of an agent run, as recorded to the audit log
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedEvent {
+    /// The run started
+    RunStarted {
+        /// Model responses the run was allowed
+        max_steps: usize,
+    },
+    /// The model answered, or failed to
+    LlmResponse(Result<LlmResponse>),
+    /// A tool invocation requested by the model finished
+    ToolResult {
+        /// Call as requested by the model
+        call: ToolCall,
+        /// Result returned to the model, or the error ending the run
+        result: Result<ToolResult>,
+    },
+    /// The run ended, with a final answer or an error
+    RunEnded,
+}
+
+/// Where a run takes its model responses and tool results from
+enum Source<'a> {
+    /// Live calls, recorded as they are made
+    Live(&'a dyn LanguageModel),
+    /// Recorded events, in order
+    Replay(::core::iter::Skip<::core::iter::Enumerate<alloc::vec::IntoIter<RecordedEvent>>>),
+}
+
+impl Source<'_> {
+    async fn respond(&mut self, executor: &ToolExecutor, run: RunId, steps: &[AgentStep]) -> Result<LlmResponse> {
+        match self {
+            Source::Live(model) => {
+                let response = model.respond(steps).await;
+                executor.audit_log().record_event(run, RecordedEvent::LlmResponse(response.clone()));
+                response
+            }
+            Source::Replay(events) => match events.next() {
+                Some((_, RecordedEvent::LlmResponse(response))) => response,
+                other => Err(diverged(other, "a model response")),
+            },
+        }
+    }
+
+    async fn invoke(&mut self, executor: &ToolExecutor, run: RunId, call: &ToolCall) -> Result<ToolResult> {
+        match self {
+            Source::Live(_) => {
+                let result = executor.invoke(&call.tool_id, call.params.clone()).await;
+                executor.audit_log().record_event(
+                    run,
+                    RecordedEvent::ToolResult {
+                        call: call.clone(),
+                        result: result.clone(),
+                    },
+                );
+                result
+            }
+            Source::Replay(events) => match events.next() {
+                Some((_, RecordedEvent::ToolResult { call: recorded, result })) if recorded == *call => result,
+                other => Err(diverged(other, &alloc::format!("a result of tool '{}'", call.tool_id))),
+            },
+        }
+    }
+}
+
+fn diverged(found: Option<(usize, RecordedEvent)>, expected: &str) -> AgentError {
+    let reason = match found {
+        Some((index, RecordedEvent::LlmResponse(_))) => alloc::format!("expected {} but event {} is a model response", expected, index),
+        Some((index, RecordedEvent::ToolResult { call, .. })) => {
+            alloc::format!("expected {} but event {} is a result of tool '{}'", expected, index, call.tool_id)
+        }
+        Some((index, RecordedEvent::RunStarted { .. })) => alloc::format!("expected {} but event {} starts a run", expected, index),
+        Some((index, RecordedEvent::RunEnded)) => alloc::format!("expected {} but event {} ends the run", expected, index),
+        None => alloc::format!("expected {} but the recording ended", expected),
+    };
+    AgentError::PlanningError {
+        operation: "replay".into(),
+        reason,
+    }
+}
+
+impl ToolExecutor {
+    /// Run an agent until `model` gives a final answer, recording the run
+    /// to the audit log under a new [`RunId`]: every model response and
+    /// tool result, failed ones included, between a start and an end marker.
+    ///
+    /// Fails with `AgentError::PlanningError` if there is no final answer
+    /// after `max_steps` responses.
+    pub async fn run_agent(&self, model: &dyn LanguageModel, max_steps: usize) -> Result<AgentRun> {
+        let run = self.audit_log().start_run(max_steps);
+        let outcome = self.drive(&mut Source::Live(model), run, max_steps).await;
+        self.audit_log().record_event(run, RecordedEvent::RunEnded);
+        outcome
+    }
+
+    /// Replay agent run `run` recorded in `audit_log`, feeding it the
+    /// recorded model responses and tool results instead of making live
+    /// calls.
+    ///
+    /// The replayed run takes the same actions and ends the same way as the
+    /// recorded one, with the same answer or the same error. Fails with
+    /// `AgentError::PlanningError` when `run` is not in `audit_log` or the
+    /// agent asks for something the recording does not hold next, e.g. a
+    /// tool call with other parameters.
+    pub async fn replay(&self, audit_log: &AuditLog, run: RunId) -> Result<AgentRun> {
+        let events = audit_log.run_events(run);
+        let Some(&RecordedEvent::RunStarted { max_steps }) = events.first() else {
+            return Err(AgentError::PlanningError {
+                operation: "replay".into(),
+                reason: alloc::format!("run {} is not in the audit log", run),
+            });
+        };
+        self.drive(&mut Source::Replay(events.into_iter().enumerate().skip(1)), run, max_steps)
+            .await
+    }
+
+    async fn drive(&self, source: &mut Source<'_>, run: RunId, max_steps: usize) -> Result<AgentRun> {
+        let mut steps = Vec::new();
+        while steps.len() < max_steps {
+            let response = source.respond(self, run, &steps).await?;
+            let result = match &response.action {
+                AgentAction::Invoke(call) => source.invoke(self, run, call).await?,
+                AgentAction::Finish { output } => {
+                    let output = output.clone();
+                    steps.push(AgentStep { response, result: None });
+                    return Ok(AgentRun {
+                        run_id: run,
+                        steps,
+                        output,
+                    });
+                }
+            };
+            steps.push(AgentStep {
+                response,
+                result: Some(result),
+            });
+        }
+
+        Err(AgentError::PlanningError {
+            operation: "run_agent".into(),
+            reason: alloc::format!("no final answer after {} steps", max_steps),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Model whose choices depend on how often it has been called, so two
+    /// live runs never take the same actions
+    struct Drifting {
+        calls: AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl LanguageModel for Drifting {
+        async fn respond(&self, steps: &[AgentStep]) -> Result<LlmResponse> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            let action = match steps.last().and_then(|step| step.result.as_ref()) {
+                Some(ToolResult::Text(reading)) if steps.len() >= 2 => AgentAction::Finish {
+                    output: alloc::format!("last reading {}", reading),
+                },
+                _ => AgentAction::Invoke(ToolCall::new(
+                    "clock",
+                    ToolParams::from([("seed".into(), serde_json::json!(call * 7))]),
+                )),
+            };
+            Ok(LlmResponse {
+                content: alloc::format!("{:?}", action),
+                action,
+                tokens: 10 + call,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_reproduces_recorded_run() {
+        let ticks = Arc::new(AtomicU64::new(100));
+        let counter = ticks.clone();
+        let mut executor = ToolExecutor::new();
+        executor.register_tool(Tool::new("clock", "Read a changing value", move |params| {
+            let tick = counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(ToolResult::Text(alloc::format!("{}@{}", params["seed"], tick))) })
+        }));
+        let model = Drifting { calls: AtomicU64::new(0) };
+
+        let recorded = executor.run_agent(&model, 10).await.unwrap();
+        assert_eq!(recorded.steps.len(), 3);
+        assert_eq!(recorded.output, "last reading 7@101");
+        assert_eq!(executor.audit_log().run_events(recorded.run_id).len(), 7);
+
+        // A second live run drifts; the replay does not, and makes no calls
+        let drifted = executor.run_agent(&model, 10).await.unwrap();
+        assert_ne!(drifted, recorded);
+        assert_eq!(executor.audit_log().runs(), vec![recorded.run_id, drifted.run_id]);
+        let calls = (model.calls.load(Ordering::SeqCst), ticks.load(Ordering::SeqCst));
+
+        let replayed = ToolExecutor::new().replay(executor.audit_log(), recorded.run_id).await.unwrap();
+        assert_eq!(replayed.actions(), recorded.actions());
+        assert_eq!(replayed.output, recorded.output);
+        assert_eq!(replayed, recorded);
+        let replayed = ToolExecutor::new().replay(executor.audit_log(), drifted.run_id).await.unwrap();
+        assert_eq!(replayed, drifted);
+        assert_eq!((model.calls.load(Ordering::SeqCst), ticks.load(Ordering::SeqCst)), calls);
+
+        // A recording cut short cannot be replayed, nor can an unknown run
+        let truncated = AuditLog::new();
+        let run = truncated.start_run(10);
+        for event in executor.audit_log().run_events(recorded.run_id).into_iter().skip(1).take(3) {
+            truncated.record_event(run, event);
+        }
+        for run in [run, 99] {
+            assert!(matches!(
+                ToolExecutor::new().replay(&truncated, run).await,
+                Err(AgentError::PlanningError { operation, .. }) if operation == "replay"
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_drops_oldest_runs_when_full() {
+        let mut executor = ToolExecutor::new().with_audit_log(Arc::new(AuditLog::new().with_max_entries(10)));
+        executor.register_tool(Tool::new("clock", "Read a value", |params| {
+            Box::pin(async move { Ok(ToolResult::Text(alloc::format!("{}", params["seed"]))) })
+        }));
+        let model = Drifting { calls: AtomicU64::new(0) };
+
+        let first = executor.run_agent(&model, 10).await.unwrap();
+        let second = executor.run_agent(&model, 10).await.unwrap();
+        let audit = executor.audit_log();
+        assert_eq!(audit.runs(), vec![second.run_id]);
+        assert_eq!(audit.entries().len(), 4);
+        assert!(ToolExecutor::new().replay(audit, first.run_id).await.is_err());
+        assert_eq!(ToolExecutor::new().replay(audit, second.run_id).await.unwrap(), second);
+    }
+
+    #[tokio::test]
+    async fn test_replay_reproduces_failed_run() {
+        let mut executor = ToolExecutor::new();
+        executor.register_tool(Tool::new("clock", "Fail on a late seed", |params| {
+            Box::pin(async move {
+                match params["seed"].as_u64() {
+                    Some(seed) if seed > 0 => Err(AgentError::ToolExecutionError {
+                        tool_name: "clock".into(),
+                        error_message: alloc::format!("seed {} out of range", seed),
+                    }),
+                    _ => Ok(ToolResult::Text("tick".into())),
+                }
+            })
+        }));
+        let model = Drifting { calls: AtomicU64::new(0) };
+
+        let failed = executor.run_agent(&model, 10).await.unwrap_err();
+        let run = executor.audit_log().runs()[0];
+        let events = executor.audit_log().run_events(run);
+        assert!(matches!(events.last(), Some(RecordedEvent::RunEnded)));
+        assert!(matches!(&events[events.len() - 2], RecordedEvent::ToolResult { result: Err(error), .. } if *error == failed));
+
+        assert_eq!(ToolExecutor::new().replay(executor.audit_log(), run).await, Err(failed));
+    }
+}
@@ -0,0 +1,186 @@
+//! Pre-execution validation of agent actions and the audit log
+//!
+//! Every tool invocation made through a [`ToolExecutor`] is first passed to
+//! its [`ActionValidator`]s, which may let it through, rewrite it or deny
+//! it. The outcome is appended to the executor's [`AuditLog`], which also
+//! records the model responses and tool results of agent runs for replay,
+//! each run under its own [`RunId`]. Both are capped; once full, the log
+//! drops its oldest records.
+
+use crate::*;
+use alloc::string::String;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use ::core::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Verdict of an [`ActionValidator`] on a tool call
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionDecision {
+    /// Run the call as requested
+    Allow,
+    /// Run a rewritten call instead, e.g. with sanitized parameters
+    Modify {
+        /// Call to run in place of the requested one
+        call: ToolCall,
+        /// Why the call was rewritten
+        reason: String,
+    },
+    /// Do not run the call
+    Deny {
+        /// Why the call was refused
+        reason: String,
+    },
+}
+
+/// Safety check run before every tool invocation
+#[async_trait::async_trait]
+pub trait ActionValidator: Send + Sync {
+    /// Decide whether `call` may run
+    async fn validate(&self, call: &ToolCall) -> ActionDecision;
+}
+
+/// Record of one attempted tool invocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// When the call was validated, in milliseconds since the Unix epoch
+    pub timestamp: i64,
+    /// Call as requested by the agent
+    pub requested: ToolCall,
+    /// Combined verdict of the validators
+    pub decision: ActionDecision,
+}
+
+/// Identifier of an agent run recorded to an [`AuditLog`]
+pub type RunId = u64;
+
+/// Log of validated tool calls and recorded agent runs
+///
+/// Keeps at most `max_entries` tool call entries and as many recorded run
+/// events, [`MAX_AUDIT_LOG_ENTRIES`] by default, dropping the oldest ones
+/// first. A run whose start marker was dropped can no longer be replayed.
+#[derive(Debug)]
+pub struct AuditLog {
+    entries: Mutex<VecDeque<AuditEntry>>,
+    recorded: Mutex<VecDeque<(RunId, RecordedEvent)>>,
+    next_run: AtomicU64,
+    max_entries: usize,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            recorded: Mutex::new(VecDeque::new()),
+            next_run: AtomicU64::new(0),
+            max_entries: MAX_AUDIT_LOG_ENTRIES,
+        }
+    }
+}
+
+impl AuditLog {
+    /// Create an empty audit log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `max_entries` entries and as many run events
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Append an entry, dropping the oldest one if the log is full
+    pub fn record(&self, entry: AuditEntry) {
+        push_capped(&self.entries, self.max_entries, entry);
+    }
+
+    /// All entries kept, oldest first
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
+    /// Start recording an agent run allowed `max_steps` model responses,
+    /// returning the id its events are recorded under
+    pub fn start_run(&self, max_steps: usize) -> RunId {
+        let run = self.next_run.fetch_add(1, Ordering::Relaxed);
+        self.record_event(run, RecordedEvent::RunStarted { max_steps });
+        run
+    }
+
+    /// Append an event of agent run `run`, dropping the oldest event if
+    /// the log is full
+    pub fn record_event(&self, run: RunId, event: RecordedEvent) {
+        push_capped(&self.recorded, self.max_entries, (run, event));
+    }
+
+    /// Ids of the recorded agent runs, oldest first
+    pub fn runs(&self) -> Vec<RunId> {
+        self.recorded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(_, event)| matches!(event, RecordedEvent::RunStarted { .. }))
+            .map(|(run, _)| *run)
+            .collect()
+    }
+
+    /// Recorded events of agent run `run`, oldest first
+    pub fn run_events(&self, run: RunId) -> Vec<RecordedEvent> {
+        self.recorded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(id, _)| *id == run)
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
+
+    /// Entries of calls that were denied
+    pub fn denials(&self) -> Vec<AuditEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|entry| matches!(entry.decision, ActionDecision::Deny { .. }))
+            .cloned()
+            .collect()
+    }
+}
+
+fn push_capped<T>(log: &Mutex<VecDeque<T>>, max_entries: usize, item: T) {
+    let mut log = log.lock().unwrap_or_else(|e| e.into_inner());
+    while log.len() >= max_entries {
+        log.pop_front();
+    }
+    log.push_back(item);
+}
+
+/// Run `call` through `validators` in order.
+///
+/// Each validator sees the call as rewritten by the ones before it, and the
+/// first denial stops the chain. Rewrites are reported as a single
+/// `Modify` carrying the final call and every reason given.
+pub(crate) async fn validate_action(validators: &[alloc::sync::Arc<dyn ActionValidator>], call: &ToolCall) -> ActionDecision {
+    let mut current = call.clone();
+    let mut reasons = Vec::new();
+    for validator in validators {
+        match validator.validate(&current).await {
+            ActionDecision::Allow => {}
+            ActionDecision::Modify { call, reason } => {
+                current = call;
+                reasons.push(reason);
+            }
+            deny @ ActionDecision::Deny { .. } => return deny,
+        }
+    }
+
+    if reasons.is_empty() {
+        ActionDecision::Allow
+    } else {
+        ActionDecision::Modify {
+            call: current,
+            reason: reasons.join("; "),
+        }
+    }
+}
@@ -68,6 +68,9 @@ pub struct AIRequest {
     pub parameters: BTreeMap<String, serde_json::Value>,
     /// Timeout in milliseconds
     pub timeout_ms: Option<u64>,
+    /// Most the request may cost in USD; providers estimated to exceed it
+    /// are not used
+    pub max_cost: Option<f64>,
 }
 
 impl Default for AIRequest {
@@ -84,6 +87,7 @@ impl Default for AIRequest {
             preferred_provider: None,
             parameters: BTreeMap::new(),
             timeout_ms: None,
+            max_cost: None,
         }
     }
 }
@@ -172,6 +176,14 @@ pub struct AISystem {
     metrics: AIMetrics,
     /// Request processor
     processor: RequestProcessor,
+    /// Provider serving streamed requests instead of the Sira gateway
+    streaming_provider: Option<alloc::sync::Arc<dyn StreamingProvider>>,
+    /// Providers serving requests instead of the Sira gateway
+    #[cfg(feature = "tokio")]
+    provider_chain: Option<ProviderChain>,
+    /// Cache answering requests similar to earlier ones
+    #[cfg(feature = "semantic-cache")]
+    semantic_cache: Option<SemanticCache>,
 }
 
 impl AISystem {
@@ -190,6 +202,11 @@ impl AISystem {
             cache,
             metrics,
             processor,
+            streaming_provider: None,
+            #[cfg(feature = "tokio")]
+            provider_chain: None,
+            #[cfg(feature = "semantic-cache")]
+            semantic_cache: None,
         })
     }
 
@@ -203,16 +220,43 @@ impl AISystem {
                 self.metrics.record_cache_hit();
                 return Ok(cached_response);
             }
+
+            // The semantic cache is an optimization: if it fails, serve
+            // the request as if it missed
+            #[cfg(feature = "semantic-cache")]
+            if let Some(semantic_cache) = &self.semantic_cache {
+                match semantic_cache.get(&request).await {
+                    Ok(Some(cached_response)) => {
+                        self.metrics.record_cache_hit();
+                        return Ok(cached_response);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("semantic cache lookup failed: {}", e),
+                }
+            }
         }
 
         self.metrics.record_cache_miss();
 
-        // Route through Sira gateway
+        // Route through the provider chain, or the Sira gateway without one
+        #[cfg(feature = "tokio")]
+        let response = match &self.provider_chain {
+            Some(chain) => chain.process(&request).await?,
+            None => self.sira_client.process_request(request.clone()).await?,
+        };
+        #[cfg(not(feature = "tokio"))]
         let response = self.sira_client.process_request(request.clone()).await?;
 
         // Cache the response
         if self.config.enable_caching && !response.cache_hit {
             self.cache.put(&request, &response).await?;
+
+            #[cfg(feature = "semantic-cache")]
+            if let Some(semantic_cache) = &mut self.semantic_cache {
+                if let Err(e) = semantic_cache.put(&request, &response).await {
+                    log::warn!("semantic cache store failed: {}", e);
+                }
+            }
         }
 
         // Update metrics
@@ -230,6 +274,50 @@ impl AISystem {
         self.sira_client.process_streaming_request(request).await
     }
 
+    /// Serve requests from `chain` instead of the Sira gateway, failing
+    /// over between its providers
+    #[cfg(feature = "tokio")]
+    pub fn set_provider_chain(&mut self, chain: ProviderChain) {
+        self.provider_chain = Some(chain);
+    }
+
+    /// Also answer requests from responses to similar earlier requests,
+    /// embedding request text with `embedder`
+    #[cfg(feature = "semantic-cache")]
+    pub fn enable_semantic_cache(&mut self, embedder: alloc::sync::Arc<dyn TextEmbedder>, config: CacheConfig) {
+        self.semantic_cache = Some(SemanticCache::new(embedder, config));
+    }
+
+    /// Serve streamed requests from `provider` instead of the Sira gateway
+    pub fn set_streaming_provider(&mut self, provider: alloc::sync::Arc<dyn StreamingProvider>) {
+        self.streaming_provider = Some(provider);
+    }
+
+    /// Stream the response to `request` as it is generated.
+    ///
+    /// Partial outputs arrive in order and the stream ends with an
+    /// [`AIChunk::Complete`] marker, or with the first error. Dropping the
+    /// stream cancels the upstream call.
+    pub fn process_request_stream(&self, request: AIRequest) -> impl futures::Stream<Item = Result<AIChunk>> + '_ {
+        use futures::TryStreamExt;
+
+        let (upstream, provider): (::core::pin::Pin<Box<dyn futures::Stream<Item = Result<StreamChunk>> + '_>>, String) =
+            if !self.config.enable_streaming {
+                let disabled = AIError::UnsupportedOperation {
+                    operation: "streaming".to_string(),
+                    reason: "streaming is disabled".to_string(),
+                };
+                (Box::pin(futures::stream::once(async move { Err(disabled) })), String::new())
+            } else if let Some(provider) = &self.streaming_provider {
+                (provider.stream(request), provider.name().to_string())
+            } else {
+                let upstream = futures::stream::once(self.sira_client.process_streaming_request(request)).try_flatten();
+                (Box::pin(upstream), "sira".to_string())
+            };
+
+        relay(upstream, provider)
+    }
+
     /// Get available models
     pub fn get_available_models(&self) -> Vec<ModelInfo> {
         self.model_manager.get_available_models()
@@ -0,0 +1,126 @@
+//! Cost accounting
+//!
+//! A [`CostTracker`] records the token usage and dollar cost of every
+//! request per provider. [`estimate_cost`] prices a request before it is
+//! sent, which routing uses to skip providers that would exceed the
+//! request's `max_cost`.
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use std::sync::Mutex;
+
+/// Rough number of characters per token in English text
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Completion tokens assumed when a request sets no `max_tokens`
+const DEFAULT_COMPLETION_TOKENS: usize = 1000;
+
+/// Accumulated usage of one provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderSpend {
+    /// Requests served
+    pub requests: u64,
+    /// Input tokens consumed
+    pub prompt_tokens: u64,
+    /// Output tokens generated
+    pub completion_tokens: u64,
+    /// Total cost in USD
+    pub cost_usd: f64,
+}
+
+/// Per-provider usage and spend
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    spend: Mutex<BTreeMap<String, ProviderSpend>>,
+}
+
+impl CostTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a request served by `provider`
+    pub fn record(&self, provider: &str, usage: &UsageStats, cost_usd: f64) {
+        let mut spend = self.spend.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = spend.entry(provider.to_string()).or_default();
+        entry.requests += 1;
+        entry.prompt_tokens += usage.prompt_tokens as u64;
+        entry.completion_tokens += usage.completion_tokens as u64;
+        entry.cost_usd += cost_usd;
+    }
+
+    /// Usage of `provider` so far
+    pub fn provider_spend(&self, provider: &str) -> Option<ProviderSpend> {
+        self.spend.lock().unwrap_or_else(|e| e.into_inner()).get(provider).cloned()
+    }
+
+    /// Usage of every provider so far
+    pub fn spend_by_provider(&self) -> BTreeMap<String, ProviderSpend> {
+        self.spend.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Cumulative spend across providers in USD
+    pub fn total_spend(&self) -> f64 {
+        self.spend
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|spend| spend.cost_usd)
+            .sum()
+    }
+}
+
+/// Upper estimate of what `request` costs at `cost_per_token` USD: the
+/// prompt at about four characters per token plus the full completion
+/// allowance (`max_tokens`, or 1000 when unset)
+pub fn estimate_cost(request: &AIRequest, cost_per_token: f64) -> f64 {
+    let prompt_tokens = request.text.as_deref().map_or(0, |text| text.chars().count().div_ceil(CHARS_PER_TOKEN));
+    let completion_tokens = request
+        .parameters
+        .get("max_tokens")
+        .and_then(serde_json::Value::as_u64)
+        .map_or(DEFAULT_COMPLETION_TOKENS, |tokens| tokens as usize);
+    (prompt_tokens + completion_tokens) as f64 * cost_per_token
+}
+
+/// Dollar amount in micro-USD, rounded up
+pub(crate) fn to_micros(cost_usd: f64) -> u64 {
+    (cost_usd * 1_000_000.0).ceil() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_accumulates_per_provider() {
+        let tracker = CostTracker::new();
+        let usage = UsageStats {
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            ..Default::default()
+        };
+        tracker.record("openai", &usage, 0.003);
+        tracker.record("openai", &usage, 0.003);
+        tracker.record("local", &usage, 0.0);
+
+        let openai = tracker.provider_spend("openai").unwrap();
+        assert_eq!(openai.requests, 2);
+        assert_eq!(openai.completion_tokens, 40);
+        assert!((tracker.total_spend() - 0.006).abs() < 1e-12);
+        assert_eq!(tracker.spend_by_provider().len(), 2);
+    }
+
+    #[test]
+    fn test_estimate_counts_prompt_and_completion() {
+        let mut request = AIRequest {
+            text: Some("a".repeat(40)),
+            ..Default::default()
+        };
+        request.parameters.insert("max_tokens".to_string(), serde_json::json!(90));
+        assert!((estimate_cost(&request, 0.001) - 0.1).abs() < 1e-12);
+    }
+}
@@ -54,6 +54,13 @@ pub enum AIError {
         retry_after_seconds: u64,
     },
 
+    /// Every provider would exceed the request's cost budget (amounts in
+    /// micro-USD)
+    BudgetExceeded {
+        max_cost_micros: u64,
+        estimated_cost_micros: u64,
+    },
+
     /// Invalid input
     InvalidInput {
         field: alloc::string::String,
@@ -113,6 +120,9 @@ impl core::fmt::Display for AIError {
             AIError::QuotaExceeded { provider, retry_after_seconds } => {
                 write!(f, "Quota exceeded for provider {}, retry after {} seconds", provider, retry_after_seconds)
             }
+            AIError::BudgetExceeded { max_cost_micros, estimated_cost_micros } => {
+                write!(f, "Budget exceeded: cheapest estimate {} micro-USD over limit of {} micro-USD", estimated_cost_micros, max_cost_micros)
+            }
             AIError::InvalidInput { field, reason } => {
                 write!(f, "Invalid input in {}: {}", field, reason)
             }
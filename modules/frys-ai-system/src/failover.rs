@@ -0,0 +1,490 @@
+//! Provider fallback chain
+//!
+//! A [`ProviderChain`] tries its providers from highest to lowest priority.
+//! A provider that errors or exceeds its timeout hands the request on to
+//! the next one. Each provider has a circuit breaker: after
+//! `failure_threshold` consecutive failures the provider is skipped until
+//! `recovery_timeout` has passed, after which one trial request decides
+//! whether it is back.
+//!
+//! Requests carrying a `max_cost` skip providers whose estimated cost
+//! exceeds it, and the spend of every served request is recorded in the
+//! chain's [`CostTracker`].
+
+use crate::*;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ::core::time::Duration;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Backend able to serve complete requests
+#[async_trait::async_trait]
+pub trait AIProvider: Send + Sync {
+    /// Provider name, recorded in `AIResponse::provider_used`
+    fn name(&self) -> &str;
+
+    /// Price per token in USD, used for budget checks and for responses
+    /// that report no cost of their own
+    fn cost_per_token(&self) -> f64 {
+        0.0
+    }
+
+    /// Serve `request`
+    async fn process(&self, request: &AIRequest) -> Result<AIResponse>;
+}
+
+/// Circuit breaker configuration
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the breaker
+    pub failure_threshold: u32,
+    /// Time an open breaker skips its provider before allowing a trial
+    pub recovery_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            recovery_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of a provider's circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Requests flow to the provider
+    Closed,
+    /// The provider is skipped until the given instant
+    Open {
+        /// When a trial request is next allowed
+        until: Instant,
+    },
+    /// A trial request is in flight; other requests skip the provider
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+}
+
+impl Breaker {
+    /// Whether a request may be sent now, moving an expired open breaker
+    /// to half-open
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        match self.state {
+            BreakerState::Closed => true,
+            BreakerState::Open { until } if now >= until => {
+                self.state = BreakerState::HalfOpen;
+                true
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen => false,
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.state = BreakerState::Closed;
+        self.consecutive_failures = 0;
+    }
+
+    fn record_failure(&mut self, config: &CircuitBreakerConfig, now: Instant) {
+        self.consecutive_failures += 1;
+        if self.state == BreakerState::HalfOpen || self.consecutive_failures >= config.failure_threshold {
+            self.state = BreakerState::Open {
+                until: now + config.recovery_timeout,
+            };
+        }
+    }
+}
+
+/// A request let through a breaker, settled once its outcome is known.
+///
+/// Dropping it unsettled, as when the caller cancels the request, counts a
+/// half-open trial as failed so the breaker does not stay half-open and
+/// reject every later request.
+struct Attempt<'a> {
+    breaker: &'a Mutex<Breaker>,
+    config: &'a CircuitBreakerConfig,
+    settled: bool,
+}
+
+impl Attempt<'_> {
+    fn succeeded(mut self) {
+        self.settled = true;
+        lock(self.breaker).record_success();
+    }
+
+    fn failed(mut self) {
+        self.settled = true;
+        lock(self.breaker).record_failure(self.config, Instant::now());
+    }
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        let mut breaker = lock(self.breaker);
+        if breaker.state == BreakerState::HalfOpen {
+            breaker.record_failure(self.config, Instant::now());
+        }
+    }
+}
+
+struct ChainEntry {
+    provider: Arc<dyn AIProvider>,
+    priority: u32,
+    timeout: Duration,
+    breaker: Mutex<Breaker>,
+}
+
+/// Providers tried in priority order until one serves the request
+pub struct ProviderChain {
+    entries: Vec<ChainEntry>,
+    breaker_config: CircuitBreakerConfig,
+    costs: CostTracker,
+}
+
+impl ProviderChain {
+    /// Create an empty chain
+    pub fn new(breaker_config: CircuitBreakerConfig) -> Self {
+        Self {
+            entries: Vec::new(),
+            breaker_config,
+            costs: CostTracker::new(),
+        }
+    }
+
+    /// Add a provider. Higher priorities are tried first; equal
+    /// priorities keep insertion order.
+    pub fn with_provider(mut self, provider: Arc<dyn AIProvider>, priority: u32, timeout: Duration) -> Self {
+        let position = self.entries.partition_point(|entry| entry.priority >= priority);
+        self.entries.insert(position, ChainEntry {
+            provider,
+            priority,
+            timeout,
+            breaker: Mutex::new(Breaker {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+            }),
+        });
+        self
+    }
+
+    /// Names of the providers in the order they are tried
+    pub fn providers(&self) -> Vec<&str> {
+        self.entries.iter().map(|entry| entry.provider.name()).collect()
+    }
+
+    /// Breaker state of the named provider
+    pub fn breaker_state(&self, provider: &str) -> Option<BreakerState> {
+        self.entries
+            .iter()
+            .find(|entry| entry.provider.name() == provider)
+            .map(|entry| lock(&entry.breaker).state)
+    }
+
+    /// Spend recorded for the requests this chain served
+    pub fn cost_tracker(&self) -> &CostTracker {
+        &self.costs
+    }
+
+    /// Serve `request` from the first provider that succeeds.
+    ///
+    /// The response's `provider_used` names that provider. If every
+    /// provider is over the request's budget the error is
+    /// [`AIError::BudgetExceeded`]; if they fail or are skipped for other
+    /// reasons, the error lists what happened to each.
+    pub async fn process(&self, request: &AIRequest) -> Result<AIResponse> {
+        let mut failures = Vec::new();
+        let mut over_budget = 0;
+        let mut cheapest_over_budget = f64::INFINITY;
+
+        for entry in &self.entries {
+            let name = entry.provider.name();
+            if let Some(max_cost) = request.max_cost {
+                let estimate = estimate_cost(request, entry.provider.cost_per_token());
+                if estimate > max_cost {
+                    over_budget += 1;
+                    cheapest_over_budget = cheapest_over_budget.min(estimate);
+                    failures.push(alloc::format!("{}: estimated ${:.6} over budget", name, estimate));
+                    continue;
+                }
+            }
+            if !lock(&entry.breaker).try_acquire(Instant::now()) {
+                failures.push(alloc::format!("{}: circuit open", name));
+                continue;
+            }
+            let attempt = Attempt {
+                breaker: &entry.breaker,
+                config: &self.breaker_config,
+                settled: false,
+            };
+
+            let outcome = match tokio::time::timeout(entry.timeout, entry.provider.process(request)).await {
+                Ok(outcome) => outcome,
+                Err(_) => Err(AIError::TimeoutError {
+                    operation: alloc::format!("{} request", name),
+                    timeout_ms: u64::try_from(entry.timeout.as_millis()).unwrap_or(u64::MAX),
+                }),
+            };
+
+            match outcome {
+                Ok(mut response) => {
+                    attempt.succeeded();
+                    if response.cost_usd == 0.0 {
+                        let tokens = response.usage.total_tokens.max(response.usage.prompt_tokens + response.usage.completion_tokens);
+                        response.cost_usd = tokens as f64 * entry.provider.cost_per_token();
+                    }
+                    response.usage.cost_usd = response.cost_usd;
+                    response.provider_used = name.to_string();
+                    self.costs.record(name, &response.usage, response.cost_usd);
+                    return Ok(response);
+                }
+                Err(error) => {
+                    attempt.failed();
+                    failures.push(alloc::format!("{}: {}", name, error));
+                }
+            }
+        }
+
+        if let Some(max_cost) = request.max_cost {
+            if over_budget > 0 && over_budget == self.entries.len() {
+                return Err(AIError::BudgetExceeded {
+                    max_cost_micros: to_micros(max_cost),
+                    estimated_cost_micros: to_micros(cheapest_over_budget),
+                });
+            }
+        }
+
+        Err(AIError::ProviderError {
+            provider: "chain".to_string(),
+            reason: if failures.is_empty() {
+                "no providers configured".to_string()
+            } else {
+                alloc::format!("all providers failed ({})", failures.join("; "))
+            },
+        })
+    }
+}
+
+fn lock(breaker: &Mutex<Breaker>) -> std::sync::MutexGuard<'_, Breaker> {
+    breaker.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lets a chain stand wherever a single provider is expected, such as
+/// behind a [`PriorityScheduler`]
+#[async_trait::async_trait]
+impl AIProvider for ProviderChain {
+    fn name(&self) -> &str {
+        "chain"
+    }
+
+    async fn process(&self, request: &AIRequest) -> Result<AIResponse> {
+        ProviderChain::process(self, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider that fails, stalls or answers, counting its calls
+    struct MockProvider {
+        name: &'static str,
+        behavior: Behavior,
+        cost_per_token: f64,
+        calls: AtomicUsize,
+    }
+
+    enum Behavior {
+        Fail,
+        Stall,
+        Answer,
+    }
+
+    impl MockProvider {
+        fn new(name: &'static str, behavior: Behavior) -> Arc<Self> {
+            Self::priced(name, behavior, 0.0)
+        }
+
+        fn priced(name: &'static str, behavior: Behavior, cost_per_token: f64) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                behavior,
+                cost_per_token,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl AIProvider for MockProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn cost_per_token(&self) -> f64 {
+            self.cost_per_token
+        }
+
+        async fn process(&self, _request: &AIRequest) -> Result<AIResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.behavior {
+                Behavior::Fail => Err(AIError::NetworkError {
+                    reason: "connection refused".to_string(),
+                }),
+                Behavior::Stall => {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    unreachable!("the chain times out first")
+                }
+                Behavior::Answer => Ok(AIResponse {
+                    id: "response".to_string(),
+                    text: Some(self.name.to_string()),
+                    image: None,
+                    audio: None,
+                    video: None,
+                    embeddings: None,
+                    classifications: None,
+                    usage: UsageStats {
+                        prompt_tokens: 100,
+                        completion_tokens: 400,
+                        total_tokens: 500,
+                        ..Default::default()
+                    },
+                    model_used: "mock".to_string(),
+                    provider_used: String::new(),
+                    processing_time_ms: 0,
+                    cost_usd: 0.0,
+                    cache_hit: false,
+                }),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secondary_serves_and_primary_breaker_opens() {
+        let primary = MockProvider::new("primary", Behavior::Fail);
+        let secondary = MockProvider::new("secondary", Behavior::Answer);
+        let chain = ProviderChain::new(CircuitBreakerConfig::default())
+            .with_provider(secondary.clone(), 1, Duration::from_secs(1))
+            .with_provider(primary.clone(), 10, Duration::from_secs(1));
+        assert_eq!(chain.providers(), ["primary", "secondary"]);
+
+        for _ in 0..5 {
+            let response = chain.process(&AIRequest::default()).await.unwrap();
+            assert_eq!(response.provider_used, "secondary");
+        }
+
+        // The breaker opened after three failures; later requests skip it
+        assert_eq!(primary.calls(), 3);
+        assert_eq!(secondary.calls(), 5);
+        assert!(matches!(chain.breaker_state("primary"), Some(BreakerState::Open { .. })));
+        assert_eq!(chain.breaker_state("secondary"), Some(BreakerState::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_falls_through_and_half_open_trial_reopens() {
+        let slow = MockProvider::new("slow", Behavior::Stall);
+        let backup = MockProvider::new("backup", Behavior::Answer);
+        let config = CircuitBreakerConfig {
+            failure_threshold: 1,
+            recovery_timeout: Duration::from_millis(20),
+        };
+        let chain = ProviderChain::new(config)
+            .with_provider(slow.clone(), 2, Duration::from_millis(10))
+            .with_provider(backup, 1, Duration::from_secs(1));
+
+        assert_eq!(chain.process(&AIRequest::default()).await.unwrap().provider_used, "backup");
+        assert!(matches!(chain.breaker_state("slow"), Some(BreakerState::Open { .. })));
+
+        // After the recovery timeout one trial is let through and fails again
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        chain.process(&AIRequest::default()).await.unwrap();
+        assert_eq!(slow.calls(), 2);
+        assert!(matches!(chain.breaker_state("slow"), Some(BreakerState::Open { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_half_open_trial_reopens_breaker() {
+        let slow = MockProvider::new("slow", Behavior::Stall);
+        let config = CircuitBreakerConfig {
+            failure_threshold: 1,
+            recovery_timeout: Duration::from_millis(20),
+        };
+        let chain = ProviderChain::new(config).with_provider(slow.clone(), 1, Duration::from_millis(10));
+        assert!(chain.process(&AIRequest::default()).await.is_err());
+
+        // The caller gives up on the trial before the provider's timeout
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let request = AIRequest::default();
+        assert!(tokio::time::timeout(Duration::from_millis(1), chain.process(&request)).await.is_err());
+        assert!(matches!(chain.breaker_state("slow"), Some(BreakerState::Open { .. })));
+
+        // So the provider gets another trial once the breaker recovers
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(chain.process(&AIRequest::default()).await.is_err());
+        assert_eq!(slow.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_all_failing_reports_each_provider() {
+        let chain = ProviderChain::new(CircuitBreakerConfig::default())
+            .with_provider(MockProvider::new("a", Behavior::Fail), 2, Duration::from_secs(1))
+            .with_provider(MockProvider::new("b", Behavior::Fail), 1, Duration::from_secs(1));
+
+        let Err(AIError::ProviderError { reason, .. }) = chain.process(&AIRequest::default()).await else {
+            panic!("expected a provider error");
+        };
+        assert!(reason.contains("a: ") && reason.contains("b: "), "{}", reason);
+    }
+
+    #[tokio::test]
+    async fn test_budget_routes_to_cheaper_provider() {
+        let premium = MockProvider::priced("premium", Behavior::Answer, 0.000_06);
+        let budget = MockProvider::priced("budget", Behavior::Answer, 0.000_002);
+        let chain = ProviderChain::new(CircuitBreakerConfig::default())
+            .with_provider(premium.clone(), 10, Duration::from_secs(1))
+            .with_provider(budget.clone(), 1, Duration::from_secs(1));
+
+        let mut request = AIRequest {
+            text: Some("Summarize the quarterly report".to_string()),
+            ..Default::default()
+        };
+        request.parameters.insert("max_tokens".to_string(), serde_json::json!(500));
+
+        // Without a budget the preferred, expensive provider serves
+        assert_eq!(chain.process(&request).await.unwrap().provider_used, "premium");
+
+        // About 508 tokens: $0.03 at premium prices, $0.001 at budget ones
+        request.max_cost = Some(0.01);
+        let response = chain.process(&request).await.unwrap();
+        assert_eq!(response.provider_used, "budget");
+        assert!((response.cost_usd - 0.001).abs() < 1e-12);
+        assert_eq!(premium.calls(), 1);
+
+        let costs = chain.cost_tracker();
+        assert!((costs.provider_spend("premium").unwrap().cost_usd - 0.03).abs() < 1e-12);
+        assert_eq!(costs.provider_spend("budget").unwrap().requests, 1);
+        assert!((costs.total_spend() - 0.031).abs() < 1e-12);
+
+        request.max_cost = Some(0.0001);
+        let Err(AIError::BudgetExceeded { max_cost_micros, estimated_cost_micros }) = chain.process(&request).await else {
+            panic!("expected the budget to be exceeded");
+        };
+        assert_eq!(max_cost_micros, 100);
+        assert_eq!(estimated_cost_micros, 1016);
+        assert_eq!(budget.calls(), 1);
+    }
+}
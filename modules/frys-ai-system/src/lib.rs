@@ -92,20 +92,38 @@ extern crate alloc;
 
 // Public API exports
 pub mod core;
+#[cfg(feature = "std")]
+pub mod cost;
+#[cfg(feature = "tokio")]
+pub mod failover;
 pub mod integration;
 pub mod intelligence;
 pub mod learning;
 pub mod nlp;
 pub mod reasoning;
+#[cfg(feature = "tokio")]
+pub mod scheduler;
+#[cfg(feature = "semantic-cache")]
+pub mod semantic_cache;
+pub mod streaming;
 pub mod vision;
 
 // Re-exports for convenience
 pub use core::*;
+#[cfg(feature = "std")]
+pub use cost::*;
+#[cfg(feature = "tokio")]
+pub use failover::*;
 pub use integration::*;
 pub use intelligence::*;
 pub use learning::*;
 pub use nlp::*;
 pub use reasoning::*;
+#[cfg(feature = "tokio")]
+pub use scheduler::*;
+#[cfg(feature = "semantic-cache")]
+pub use semantic_cache::*;
+pub use streaming::*;
 pub use vision::*;
 
 // Error types
@@ -124,6 +142,7 @@ pub const DEFAULT_AI_TIMEOUT: u64 = 30000; // 30 seconds
 pub const MAX_AI_PAYLOAD_SIZE: usize = 50 * 1024 * 1024; // 50MB
 pub const DEFAULT_CACHE_TTL: u64 = 3600; // 1 hour
 pub const MAX_CONCURRENT_REQUESTS: usize = 1000;
+pub const DEFAULT_INFERENCE_CONCURRENCY: usize = 8;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,456 @@
+//! Priority scheduling of inference requests
+//!
+//! A [`PriorityScheduler`] admits at most `max_concurrent` requests to its
+//! provider at once. Requests waiting for a slot are admitted highest
+//! [`Priority`] first, in arrival order within a priority, and the time
+//! each waited is tracked per priority.
+//!
+//! With `preempt_at` set, a request of at least that priority arriving
+//! while every slot is busy pauses the lowest-priority running request
+//! below it and takes over its slot. The paused request is not polled
+//! again until a slot is handed back to it, then carries on where it
+//! stopped; work its provider has already sent elsewhere is not recalled.
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use ::core::future::Future;
+use ::core::task::{Context, Poll, Waker};
+use ::core::time::Duration;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Priority scheduler configuration
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Requests served by the provider at once
+    pub max_concurrent: usize,
+    /// Lowest priority allowed to pause running lower-priority requests
+    /// when every slot is busy, or `None` to never preempt
+    pub preempt_at: Option<Priority>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_INFERENCE_CONCURRENCY,
+            preempt_at: None,
+        }
+    }
+}
+
+/// Time requests of one priority waited before being admitted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WaitStats {
+    /// Requests admitted
+    pub requests: u64,
+    /// Sum of their waits
+    pub total_wait: Duration,
+    /// Longest wait
+    pub max_wait: Duration,
+}
+
+impl WaitStats {
+    /// Average wait of an admitted request
+    pub fn mean_wait(&self) -> Duration {
+        match u32::try_from(self.requests) {
+            Ok(0) => Duration::ZERO,
+            Ok(requests) => self.total_wait / requests,
+            Err(_) => Duration::from_secs_f64(self.total_wait.as_secs_f64() / self.requests as f64),
+        }
+    }
+
+    fn record(&mut self, wait: Duration) {
+        self.requests += 1;
+        self.total_wait += wait;
+        self.max_wait = self.max_wait.max(wait);
+    }
+}
+
+/// Request waiting for a slot
+struct Waiter {
+    priority: Priority,
+    /// A slot was handed over to this request
+    granted: bool,
+    waker: Option<Waker>,
+}
+
+/// Request admitted to the provider
+struct Job {
+    priority: Priority,
+    /// The job's slot is lent to a more urgent request
+    paused: bool,
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Slots held by running jobs and by granted waiters
+    running: usize,
+    next_ticket: u64,
+    /// Waiting requests by arrival
+    waiting: BTreeMap<u64, Waiter>,
+    /// Admitted requests by arrival
+    jobs: BTreeMap<u64, Job>,
+    waits: BTreeMap<Priority, WaitStats>,
+}
+
+/// Priority queue in front of an inference provider
+pub struct PriorityScheduler {
+    provider: Arc<dyn AIProvider>,
+    config: SchedulerConfig,
+    state: Mutex<State>,
+}
+
+impl ::core::fmt::Debug for PriorityScheduler {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("PriorityScheduler")
+            .field("provider", &self.provider.name())
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Removes a request that stopped waiting, passing on a slot granted to
+/// it in the meantime
+struct Waiting<'a> {
+    scheduler: &'a PriorityScheduler,
+    ticket: u64,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.lock();
+        if state.waiting.remove(&self.ticket).is_some_and(|waiter| waiter.granted) {
+            release(&mut state);
+        }
+    }
+}
+
+/// Returns an admitted request's slot once it ends
+struct Admitted<'a> {
+    scheduler: &'a PriorityScheduler,
+    ticket: u64,
+}
+
+impl Drop for Admitted<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.lock();
+        // A paused job's slot is already in use elsewhere
+        if state.jobs.remove(&self.ticket).is_some_and(|job| !job.paused) {
+            release(&mut state);
+        }
+    }
+}
+
+impl PriorityScheduler {
+    /// Schedule requests to `provider`
+    pub fn new(provider: Arc<dyn AIProvider>, config: SchedulerConfig) -> Self {
+        Self {
+            provider,
+            config: SchedulerConfig {
+                max_concurrent: config.max_concurrent.max(1),
+                ..config
+            },
+            state: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Serve `request` once a slot is free for its priority
+    pub async fn submit(&self, request: AIRequest) -> Result<AIResponse> {
+        let admitted = self.admit(request.priority).await;
+        let ticket = admitted.ticket;
+
+        let mut call = self.provider.process(&request);
+        ::core::future::poll_fn(|cx| {
+            if self.park(ticket, cx) {
+                return Poll::Pending;
+            }
+            call.as_mut().poll(cx)
+        })
+        .await
+    }
+
+    /// Requests waiting for a slot, by priority
+    pub fn queue_depths(&self) -> BTreeMap<Priority, usize> {
+        let mut depths = BTreeMap::new();
+        for waiter in self.lock().waiting.values().filter(|waiter| !waiter.granted) {
+            *depths.entry(waiter.priority).or_default() += 1;
+        }
+        depths
+    }
+
+    /// Time admitted requests waited, by priority
+    pub fn wait_stats(&self) -> BTreeMap<Priority, WaitStats> {
+        self.lock().waits.clone()
+    }
+
+    /// Wait for a slot, recording how long it took
+    async fn admit(&self, priority: Priority) -> Admitted<'_> {
+        let queued_at = Instant::now();
+        let ticket = {
+            let mut state = self.lock();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+
+            if state.running < self.config.max_concurrent {
+                state.running += 1;
+                return self.start(&mut state, ticket, priority, queued_at);
+            }
+            if self.preempt(&mut state, priority) {
+                return self.start(&mut state, ticket, priority, queued_at);
+            }
+            state.waiting.insert(ticket, Waiter {
+                priority,
+                granted: false,
+                waker: None,
+            });
+            ticket
+        };
+
+        let waiting = Waiting { scheduler: self, ticket };
+        ::core::future::poll_fn(|cx| {
+            let mut state = self.lock();
+            match state.waiting.get_mut(&ticket) {
+                Some(waiter) if !waiter.granted => {
+                    waiter.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+                _ => Poll::Ready(()),
+            }
+        })
+        .await;
+
+        let mut state = self.lock();
+        state.waiting.remove(&ticket);
+        ::core::mem::forget(waiting);
+        self.start(&mut state, ticket, priority, queued_at)
+    }
+
+    /// Register an admitted request holding a slot
+    fn start(&self, state: &mut State, ticket: u64, priority: Priority, queued_at: Instant) -> Admitted<'_> {
+        state.waits.entry(priority).or_default().record(queued_at.elapsed());
+        state.jobs.insert(ticket, Job {
+            priority,
+            paused: false,
+            waker: None,
+        });
+        Admitted { scheduler: self, ticket }
+    }
+
+    /// Pause the lowest-priority running job below `priority`, latest
+    /// started first, so its slot can go to the caller
+    fn preempt(&self, state: &mut State, priority: Priority) -> bool {
+        if self.config.preempt_at.is_none_or(|preempt_at| priority < preempt_at) {
+            return false;
+        }
+        let victim = state
+            .jobs
+            .iter_mut()
+            .filter(|(_, job)| !job.paused && job.priority < priority)
+            .min_by_key(|(ticket, job)| (job.priority, ::core::cmp::Reverse(**ticket)));
+        match victim {
+            Some((_, job)) => {
+                job.paused = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the job is paused, registering to be woken on resume
+    fn park(&self, ticket: u64, cx: &mut Context<'_>) -> bool {
+        let mut state = self.lock();
+        match state.jobs.get_mut(&ticket) {
+            Some(job) if job.paused => {
+                job.waker = Some(cx.waker().clone());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Hand a freed slot to the most urgent paused job or waiting request,
+/// paused jobs first among equal priorities
+fn release(state: &mut State) {
+    let paused = state
+        .jobs
+        .iter()
+        .filter(|(_, job)| job.paused)
+        .max_by_key(|(ticket, job)| (job.priority, ::core::cmp::Reverse(**ticket)))
+        .map(|(ticket, job)| (*ticket, job.priority));
+    let waiter = state
+        .waiting
+        .iter()
+        .filter(|(_, waiter)| !waiter.granted)
+        .max_by_key(|(ticket, waiter)| (waiter.priority, ::core::cmp::Reverse(**ticket)))
+        .map(|(ticket, waiter)| (*ticket, waiter.priority));
+
+    let woken = match (paused, waiter) {
+        (Some((ticket, priority)), waiter) if waiter.is_none_or(|(_, waiting)| priority >= waiting) => {
+            let job = state.jobs.get_mut(&ticket).expect("paused job is registered");
+            job.paused = false;
+            job.waker.take()
+        }
+        (_, Some((ticket, _))) => {
+            let waiter = state.waiting.get_mut(&ticket).expect("waiter is registered");
+            waiter.granted = true;
+            waiter.waker.take()
+        }
+        (_, None) => {
+            state.running -= 1;
+            None
+        }
+    };
+    if let Some(waker) = woken {
+        waker.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Provider logging the requests it works on. "blocker" holds its slot
+    /// until notified; "batch" works in five steps; "urgent" takes a while, logging when done
+    struct Recorder {
+        log: Mutex<alloc::vec::Vec<String>>,
+        gate: tokio::sync::Notify,
+    }
+
+    #[async_trait::async_trait]
+    impl AIProvider for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        async fn process(&self, request: &AIRequest) -> Result<AIResponse> {
+            let id = request.id.clone().unwrap_or_default();
+            match id.as_str() {
+                "blocker" => {
+                    self.log.lock().unwrap().push(id.clone());
+                    self.gate.notified().await;
+                }
+                "batch" => {
+                    for step in 0..5 {
+                        self.log.lock().unwrap().push(alloc::format!("batch:{}", step));
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                    }
+                }
+                "urgent" => {
+                    self.log.lock().unwrap().push(id.clone());
+                    tokio::time::sleep(Duration::from_millis(60)).await;
+                    self.log.lock().unwrap().push("urgent:done".to_string());
+                }
+                _ => self.log.lock().unwrap().push(id.clone()),
+            }
+            Ok(AIResponse {
+                id,
+                text: None,
+                image: None,
+                audio: None,
+                video: None,
+                embeddings: None,
+                classifications: None,
+                usage: UsageStats::default(),
+                model_used: "mock".to_string(),
+                provider_used: "recorder".to_string(),
+                processing_time_ms: 0,
+                cost_usd: 0.0,
+                cache_hit: false,
+            })
+        }
+    }
+
+    fn scheduler(config: SchedulerConfig) -> (Arc<PriorityScheduler>, Arc<Recorder>) {
+        let recorder = Arc::new(Recorder {
+            log: Mutex::default(),
+            gate: tokio::sync::Notify::new(),
+        });
+        (Arc::new(PriorityScheduler::new(recorder.clone(), config)), recorder)
+    }
+
+    fn submit(scheduler: &Arc<PriorityScheduler>, id: &str, priority: Priority) -> tokio::task::JoinHandle<Result<AIResponse>> {
+        let scheduler = scheduler.clone();
+        let request = AIRequest {
+            id: Some(id.to_string()),
+            priority,
+            ..Default::default()
+        };
+        tokio::spawn(async move { scheduler.submit(request).await })
+    }
+
+    async fn until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_request_jumps_backlog() {
+        let (scheduler, recorder) = scheduler(SchedulerConfig {
+            max_concurrent: 1,
+            preempt_at: None,
+        });
+
+        let mut requests = alloc::vec![submit(&scheduler, "blocker", Priority::Normal)];
+        until(|| !recorder.log.lock().unwrap().is_empty()).await;
+        for i in 0..10 {
+            requests.push(submit(&scheduler, &alloc::format!("normal-{}", i), Priority::Normal));
+        }
+        until(|| scheduler.queue_depths().get(&Priority::Normal) == Some(&10)).await;
+        requests.push(submit(&scheduler, "urgent-high", Priority::High));
+        until(|| scheduler.queue_depths().get(&Priority::High) == Some(&1)).await;
+
+        recorder.gate.notify_one();
+        for request in requests {
+            request.await.unwrap().unwrap();
+        }
+
+        let log = recorder.log.lock().unwrap().clone();
+        assert_eq!(log.len(), 12);
+        assert_eq!(log[..3], ["blocker", "urgent-high", "normal-0"]);
+        assert!(scheduler.queue_depths().is_empty());
+
+        let waits = scheduler.wait_stats();
+        assert_eq!((waits[&Priority::Normal].requests, waits[&Priority::High].requests), (11, 1));
+        assert!(waits[&Priority::High].max_wait < waits[&Priority::Normal].max_wait);
+    }
+
+    #[tokio::test]
+    async fn test_urgent_request_pauses_running_batch() {
+        let (scheduler, recorder) = scheduler(SchedulerConfig {
+            max_concurrent: 1,
+            preempt_at: Some(Priority::High),
+        });
+
+        let batch = submit(&scheduler, "batch", Priority::Low);
+        until(|| !recorder.log.lock().unwrap().is_empty()).await;
+        // Only high priorities may preempt, so this one waits its turn
+        let queued = submit(&scheduler, "queued", Priority::Low);
+        until(|| scheduler.queue_depths().get(&Priority::Low) == Some(&1)).await;
+        let urgent = submit(&scheduler, "urgent", Priority::Critical);
+
+        for request in [urgent, batch, queued] {
+            request.await.unwrap().unwrap();
+        }
+        // The batch made no progress while the urgent request held its
+        // slot, and got it back ahead of the request queued behind it
+        let log = recorder.log.lock().unwrap().clone();
+        assert_eq!(
+            log,
+            ["batch:0", "urgent", "urgent:done", "batch:1", "batch:2", "batch:3", "batch:4", "queued"]
+        );
+        assert!(scheduler.wait_stats()[&Priority::Critical].max_wait < Duration::from_millis(20));
+    }
+}
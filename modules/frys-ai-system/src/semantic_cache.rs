@@ -0,0 +1,386 @@
+//! Response cache keyed by semantic similarity
+//!
+//! The exact-match cache only helps when a request repeats verbatim. A
+//! [`SemanticCache`] embeds the request text with a [`TextEmbedder`] (the
+//! AI plugin's embedding generator, for instance) and answers from a prior
+//! response whose request embedding lies within `similarity_threshold`
+//! cosine similarity. Only deterministic requests, text-only and sampled at
+//! temperature 0, are cached: anything else is expected to vary between
+//! calls. Besides the text, a hit must match the request's task, preferred
+//! model and provider, and every parameter (`max_tokens`, the system prompt
+//! and so on), since those shape the response too.
+//!
+//! Once `max_cache_size` responses are cached, storing another evicts one
+//! chosen by the configured eviction policy.
+//!
+//! Embeddings are normalized to unit length and indexed by Euclidean
+//! distance in a `frys-vector-search` flat index, which ranks them exactly
+//! as cosine similarity would (`|a - b|² = 2 - 2·cos(a, b)`).
+
+use crate::*;
+use ::core::sync::atomic::{AtomicU64, Ordering};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use frys_vector_search::{FlatIndex, Metric, Vector, VectorMetadata};
+
+/// Number of neighbours examined per lookup; the nearest may belong to a
+/// request for a different task or model
+const LOOKUP_CANDIDATES: usize = 4;
+
+/// Source of text embeddings
+#[async_trait::async_trait]
+pub trait TextEmbedder: Send + Sync {
+    /// Embed `text` as a dense vector
+    async fn embed(&self, text: &str) -> Result<Vec<f32>>;
+}
+
+/// Cached response and what it must match besides the text
+struct CacheEntry {
+    task: AITask,
+    preferred_model: Option<String>,
+    preferred_provider: Option<String>,
+    parameters: BTreeMap<String, serde_json::Value>,
+    /// Unit-length embedding of the request text, kept to rebuild the index
+    embedding: Vector,
+    response: AIResponse,
+    /// Value of the cache clock when the entry was last served
+    last_used: AtomicU64,
+    /// Times the entry was served
+    hits: AtomicU64,
+}
+
+impl CacheEntry {
+    /// Whether the response may answer `request`, text aside
+    fn matches(&self, request: &AIRequest) -> bool {
+        self.task == request.task
+            && self.preferred_model == request.preferred_model
+            && self.preferred_provider == request.preferred_provider
+            && self.parameters == request.parameters
+    }
+}
+
+/// Cache answering requests similar to ones already served
+pub struct SemanticCache {
+    embedder: Arc<dyn TextEmbedder>,
+    config: CacheConfig,
+    index: FlatIndex,
+    /// Cached responses by index ID, which increases with insertion
+    entries: BTreeMap<u64, CacheEntry>,
+    /// ID of the next entry
+    next_id: u64,
+    /// Evicted entries still in the index
+    stale: usize,
+    /// Ticks on every lookup and store, ordering entries by last use
+    clock: AtomicU64,
+}
+
+impl SemanticCache {
+    /// Create an empty cache embedding requests with `embedder`
+    pub fn new(embedder: Arc<dyn TextEmbedder>, config: CacheConfig) -> Self {
+        Self {
+            embedder,
+            config,
+            index: FlatIndex::new(Metric::Euclidean),
+            entries: BTreeMap::new(),
+            next_id: 0,
+            stale: 0,
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of cached responses
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `request` may be served from or stored in the cache: it
+    /// must be text-only and explicitly sampled at temperature 0
+    pub fn is_cacheable(request: &AIRequest) -> bool {
+        let deterministic = request
+            .parameters
+            .get("temperature")
+            .and_then(serde_json::Value::as_f64)
+            .is_some_and(|temperature| temperature == 0.0);
+
+        deterministic
+            && request.text.as_deref().is_some_and(|text| !text.trim().is_empty())
+            && request.image.is_none()
+            && request.audio.is_none()
+            && request.video.is_none()
+    }
+
+    /// Look up a response to a request similar to `request`.
+    ///
+    /// A hit is a copy of the cached response with `cache_hit` set.
+    pub async fn get(&self, request: &AIRequest) -> Result<Option<AIResponse>> {
+        if !self.config.enable_semantic_cache || self.entries.is_empty() || !Self::is_cacheable(request) {
+            return Ok(None);
+        }
+        let Some(text) = request.text.as_deref() else {
+            return Ok(None);
+        };
+
+        let query = self.embed(text).await?;
+        // Evicted entries may still rank among the nearest
+        let candidates = self
+            .index
+            .search(&query, LOOKUP_CANDIDATES + self.stale)
+            .map_err(|e| AIError::CacheError { reason: e.to_string() })?;
+
+        for candidate in candidates {
+            let similarity = 1.0 - candidate.distance * candidate.distance / 2.0;
+            if similarity < self.config.similarity_threshold {
+                break;
+            }
+            let Some(entry) = candidate.id.parse::<u64>().ok().and_then(|id| self.entries.get(&id)) else {
+                continue;
+            };
+            if entry.matches(request) {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                entry.hits.fetch_add(1, Ordering::Relaxed);
+                let mut response = entry.response.clone();
+                response.cache_hit = true;
+                return Ok(Some(response));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Remember `response` as the answer to `request`.
+    ///
+    /// Requests that are not cacheable are ignored. Once `max_cache_size`
+    /// responses are cached, one is evicted first.
+    pub async fn put(&mut self, request: &AIRequest, response: &AIResponse) -> Result<()> {
+        if !self.config.enable_semantic_cache || self.config.max_cache_size == 0 || !Self::is_cacheable(request) {
+            return Ok(());
+        }
+        let Some(text) = request.text.as_deref() else {
+            return Ok(());
+        };
+
+        let embedding = self.embed(text).await?;
+        while self.entries.len() >= self.config.max_cache_size {
+            self.evict();
+        }
+        // The flat index cannot delete, so drop evicted vectors by
+        // rebuilding it once they outnumber live ones
+        if self.stale > self.entries.len() {
+            self.rebuild_index()?;
+        }
+
+        let id = self.next_id;
+        self.index
+            .insert(id.to_string(), embedding.clone(), VectorMetadata::new())
+            .map_err(|e| AIError::CacheError { reason: e.to_string() })?;
+        self.next_id += 1;
+        self.entries.insert(
+            id,
+            CacheEntry {
+                task: request.task.clone(),
+                preferred_model: request.preferred_model.clone(),
+                preferred_provider: request.preferred_provider.clone(),
+                parameters: request.parameters.clone(),
+                embedding,
+                response: response.clone(),
+                last_used: AtomicU64::new(self.tick()),
+                hits: AtomicU64::new(0),
+            },
+        );
+        Ok(())
+    }
+
+    /// Advance the cache clock
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Remove the entry the eviction policy picks
+    fn evict(&mut self) {
+        let victim = match self.config.eviction_policy {
+            CacheEvictionPolicy::LRU => self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(id, _)| *id),
+            CacheEvictionPolicy::LFU => self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| (entry.hits.load(Ordering::Relaxed), entry.last_used.load(Ordering::Relaxed)))
+                .map(|(id, _)| *id),
+            // Entries never expire by time here, so evict the oldest
+            CacheEvictionPolicy::TTL | CacheEvictionPolicy::SizeBased => self.entries.keys().next().copied(),
+        };
+        if let Some(id) = victim {
+            self.entries.remove(&id);
+            self.stale += 1;
+        }
+    }
+
+    /// Index only the live entries
+    fn rebuild_index(&mut self) -> Result<()> {
+        let mut index = FlatIndex::new(Metric::Euclidean);
+        for (id, entry) in &self.entries {
+            index
+                .insert(id.to_string(), entry.embedding.clone(), VectorMetadata::new())
+                .map_err(|e| AIError::CacheError { reason: e.to_string() })?;
+        }
+        self.index = index;
+        self.stale = 0;
+        Ok(())
+    }
+
+    /// Embed `text` and scale it to unit length
+    async fn embed(&self, text: &str) -> Result<Vector> {
+        let mut embedding = self.embedder.embed(text).await?;
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm == 0.0 || !norm.is_finite() {
+            return Err(AIError::CacheError {
+                reason: "embedding has no direction".to_string(),
+            });
+        }
+        for x in &mut embedding {
+            *x /= norm;
+        }
+        Ok(Vector::new(embedding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds text as hashed character trigram counts
+    struct TrigramEmbedder;
+
+    #[async_trait::async_trait]
+    impl TextEmbedder for TrigramEmbedder {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let chars: Vec<char> = text.to_lowercase().chars().collect();
+            let mut embedding = alloc::vec![0.0; 256];
+            for trigram in chars.windows(3) {
+                let hash = trigram.iter().fold(7u32, |h, &c| h.wrapping_mul(31).wrapping_add(c as u32));
+                embedding[hash as usize % 256] += 1.0;
+            }
+            Ok(embedding)
+        }
+    }
+
+    fn request(text: &str, temperature: f64) -> AIRequest {
+        let mut request = AIRequest {
+            text: Some(text.to_string()),
+            ..Default::default()
+        };
+        request.parameters.insert("temperature".to_string(), serde_json::json!(temperature));
+        request
+    }
+
+    fn response(text: &str) -> AIResponse {
+        AIResponse {
+            id: "response".to_string(),
+            text: Some(text.to_string()),
+            image: None,
+            audio: None,
+            video: None,
+            embeddings: None,
+            classifications: None,
+            usage: UsageStats::default(),
+            model_used: "gpt-4".to_string(),
+            provider_used: "openai".to_string(),
+            processing_time_ms: 0,
+            cost_usd: 0.01,
+            cache_hit: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_near_identical_prompt_is_a_cache_hit() {
+        let mut cache = SemanticCache::new(Arc::new(TrigramEmbedder), CacheConfig::default());
+        let first = request("What is the capital of France?", 0.0);
+        assert!(cache.get(&first).await.unwrap().is_none());
+        cache.put(&first, &response("Paris")).await.unwrap();
+
+        let hit = cache.get(&request("what is the capital of France", 0.0)).await.unwrap().unwrap();
+        assert!(hit.cache_hit);
+        assert_eq!(hit.text.as_deref(), Some("Paris"));
+
+        assert!(cache.get(&request("Write a haiku about autumn leaves", 0.0)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_hit_requires_same_parameters() {
+        let mut cache = SemanticCache::new(Arc::new(TrigramEmbedder), CacheConfig::default());
+        let mut short = request("Summarize the French revolution", 0.0);
+        short.parameters.insert("max_tokens".to_string(), serde_json::json!(50));
+        cache.put(&short, &response("It happened.")).await.unwrap();
+        assert!(cache.get(&short).await.unwrap().is_some());
+
+        let mut long = short.clone();
+        long.parameters.insert("max_tokens".to_string(), serde_json::json!(2000));
+        assert!(cache.get(&long).await.unwrap().is_none());
+
+        let mut pirate = short.clone();
+        pirate.parameters.insert("system".to_string(), serde_json::json!("Answer like a pirate"));
+        assert!(cache.get(&pirate).await.unwrap().is_none());
+
+        let mut other_provider = short.clone();
+        other_provider.preferred_provider = Some("anthropic".to_string());
+        assert!(cache.get(&other_provider).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_full_cache_evicts_least_recently_used() {
+        let mut cache = SemanticCache::new(
+            Arc::new(TrigramEmbedder),
+            CacheConfig {
+                max_cache_size: 2,
+                ..Default::default()
+            },
+        );
+        let prompts = [
+            "What is the capital of France?",
+            "Write a haiku about autumn leaves",
+            "Explain how a binary heap works",
+            "List the planets of the solar system",
+        ];
+        cache.put(&request(prompts[0], 0.0), &response("Paris")).await.unwrap();
+        cache.put(&request(prompts[1], 0.0), &response("Leaves fall")).await.unwrap();
+        assert!(cache.get(&request(prompts[0], 0.0)).await.unwrap().is_some());
+
+        cache.put(&request(prompts[2], 0.0), &response("A tree")).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&request(prompts[1], 0.0)).await.unwrap().is_none());
+        assert!(cache.get(&request(prompts[0], 0.0)).await.unwrap().is_some());
+
+        // Evicted vectors are dropped from the index as they accumulate
+        cache.put(&request(prompts[3], 0.0), &response("Eight")).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stale, 0);
+        assert!(cache.get(&request(prompts[3], 0.0)).await.unwrap().is_some());
+        assert!(cache.get(&request(prompts[2], 0.0)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_only_deterministic_requests_are_cached() {
+        let mut cache = SemanticCache::new(Arc::new(TrigramEmbedder), CacheConfig::default());
+        let sampled = request("Tell me a story", 0.7);
+        assert!(!SemanticCache::is_cacheable(&sampled));
+        assert!(!SemanticCache::is_cacheable(&AIRequest {
+            text: Some("Tell me a story".to_string()),
+            ..Default::default()
+        }));
+
+        cache.put(&sampled, &response("Once upon a time")).await.unwrap();
+        assert!(cache.is_empty());
+
+        cache.put(&request("Tell me a story", 0.0), &response("Once upon a time")).await.unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&sampled).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,241 @@
+//! Streaming inference responses
+//!
+//! A [`StreamingProvider`] produces raw [`StreamChunk`]s for a request.
+//! [`AISystem::process_request_stream`] relays them as numbered
+//! [`AIChunk::Partial`] outputs followed by a single [`AIChunk::Complete`]
+//! marker. The upstream stream is polled in place, never spawned, so
+//! dropping the returned stream drops, and thereby cancels, the provider
+//! call.
+
+use crate::*;
+use alloc::string::String;
+use ::core::pin::Pin;
+use futures::stream::{Stream, StreamExt};
+
+/// Stream of raw chunks from a provider
+pub type ProviderStream<'a> = Pin<alloc::boxed::Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>>;
+
+/// Backend able to stream partial outputs
+pub trait StreamingProvider: Send + Sync {
+    /// Provider name, reported in the completion marker
+    fn name(&self) -> &str;
+
+    /// Start generating for `request`.
+    ///
+    /// The last chunk must have `is_final` set. Dropping the stream must
+    /// abort the generation.
+    fn stream(&self, request: AIRequest) -> ProviderStream<'static>;
+}
+
+/// Piece of a streamed response
+#[derive(Debug, Clone)]
+pub enum AIChunk {
+    /// Partial output, numbered from zero in generation order
+    Partial {
+        /// Position of this chunk in the response
+        sequence: u64,
+        /// Generated text
+        text: String,
+    },
+    /// The response is complete; nothing follows
+    Complete {
+        /// Provider that served the request
+        provider: String,
+        /// Usage reported by the provider
+        usage: UsageStats,
+    },
+}
+
+impl AIChunk {
+    /// Whether this is the completion marker
+    pub fn is_complete(&self) -> bool {
+        matches!(self, AIChunk::Complete { .. })
+    }
+}
+
+/// Relay state between provider chunks and [`AIChunk`]s
+struct Relay<S> {
+    upstream: S,
+    provider: String,
+    sequence: u64,
+    /// Completion marker waiting behind a final chunk's text
+    complete: Option<AIChunk>,
+    finished: bool,
+}
+
+/// Turn a provider stream into numbered chunks ending in a completion
+/// marker. The stream ends after the marker or the first error; a provider
+/// stream ending without a final chunk yields an error.
+pub(crate) fn relay<'a, S>(upstream: S, provider: String) -> impl Stream<Item = Result<AIChunk>> + 'a
+where
+    S: Stream<Item = Result<StreamChunk>> + Unpin + 'a,
+{
+    let state = Relay {
+        upstream,
+        provider,
+        sequence: 0,
+        complete: None,
+        finished: false,
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        if let Some(complete) = state.complete.take() {
+            return Some((Ok(complete), state));
+        }
+        if state.finished {
+            return None;
+        }
+
+        loop {
+            match state.upstream.next().await {
+                Some(Ok(chunk)) => {
+                    let text = chunk.text.filter(|text| !text.is_empty());
+                    if chunk.is_final {
+                        state.finished = true;
+                        let complete = AIChunk::Complete {
+                            provider: ::core::mem::take(&mut state.provider),
+                            usage: chunk.usage.unwrap_or_default(),
+                        };
+                        match text {
+                            Some(_) => state.complete = Some(complete),
+                            None => return Some((Ok(complete), state)),
+                        }
+                    }
+                    if let Some(text) = text {
+                        let sequence = state.sequence;
+                        state.sequence += 1;
+                        return Some((Ok(AIChunk::Partial { sequence, text }), state));
+                    }
+                }
+                Some(Err(error)) => {
+                    state.finished = true;
+                    return Some((Err(error), state));
+                }
+                None => {
+                    state.finished = true;
+                    let error = AIError::InferenceError {
+                        reason: alloc::format!("{} stream ended before the final chunk", state.provider),
+                    };
+                    return Some((Err(error), state));
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use ::core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Provider emitting fixed chunks and recording how far it got
+    struct MockProvider {
+        chunks: alloc::vec::Vec<StreamChunk>,
+        emitted: Arc<AtomicUsize>,
+        dropped: Arc<AtomicBool>,
+    }
+
+    /// Sets a flag when the upstream call is dropped
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl StreamingProvider for MockProvider {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn stream(&self, _request: AIRequest) -> ProviderStream<'static> {
+            let emitted = self.emitted.clone();
+            let guard = DropFlag(self.dropped.clone());
+            Box::pin(futures::stream::iter(self.chunks.clone()).map(move |chunk| {
+                let _ = &guard;
+                emitted.fetch_add(1, Ordering::SeqCst);
+                Ok(chunk)
+            }))
+        }
+    }
+
+    fn chunk(text: &str, is_final: bool) -> StreamChunk {
+        StreamChunk {
+            id: "req".to_string(),
+            text: Some(text.to_string()),
+            is_final,
+            usage: is_final.then(|| UsageStats {
+                completion_tokens: 3,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn mock(chunks: alloc::vec::Vec<StreamChunk>) -> (Arc<MockProvider>, Arc<AtomicUsize>, Arc<AtomicBool>) {
+        let emitted = Arc::new(AtomicUsize::new(0));
+        let dropped = Arc::new(AtomicBool::new(false));
+        let provider = Arc::new(MockProvider {
+            chunks,
+            emitted: emitted.clone(),
+            dropped: dropped.clone(),
+        });
+        (provider, emitted, dropped)
+    }
+
+    #[tokio::test]
+    async fn test_chunks_arrive_in_order_with_completion_marker() {
+        let (provider, _, _) = mock(alloc::vec![chunk("Hel", false), chunk("lo, ", false), chunk("world", true)]);
+        let mut system = AISystem::new(AISystemConfig::default()).await.unwrap();
+        system.set_streaming_provider(provider);
+
+        let chunks: alloc::vec::Vec<AIChunk> = system
+            .process_request_stream(AIRequest::default())
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 4);
+        for (expected, chunk) in ["Hel", "lo, ", "world"].iter().zip(&chunks) {
+            let AIChunk::Partial { text, .. } = chunk else { panic!("expected a partial chunk") };
+            assert_eq!(text, expected);
+        }
+        let sequences: alloc::vec::Vec<u64> = chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                AIChunk::Partial { sequence, .. } => Some(*sequence),
+                AIChunk::Complete { .. } => None,
+            })
+            .collect();
+        assert_eq!(sequences, [0, 1, 2]);
+        let AIChunk::Complete { provider, usage } = &chunks[3] else { panic!("expected completion") };
+        assert_eq!(provider, "mock");
+        assert_eq!(usage.completion_tokens, 3);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_stream_cancels_upstream() {
+        let chunks = (0..100).map(|i| chunk(&i.to_string(), i == 99)).collect();
+        let (provider, emitted, dropped) = mock(chunks);
+        let mut system = AISystem::new(AISystemConfig::default()).await.unwrap();
+        system.set_streaming_provider(provider);
+
+        let mut stream = Box::pin(system.process_request_stream(AIRequest::default()));
+        assert!(!stream.next().await.unwrap().unwrap().is_complete());
+        assert!(!dropped.load(Ordering::SeqCst));
+
+        drop(stream);
+        assert!(dropped.load(Ordering::SeqCst));
+        assert_eq!(emitted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_stream_is_an_error() {
+        let upstream = futures::stream::iter(alloc::vec![Ok(chunk("partial", false))]);
+        let results: alloc::vec::Vec<Result<AIChunk>> = relay(upstream, "mock".into()).collect().await;
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[1], Err(AIError::InferenceError { .. })));
+    }
+}
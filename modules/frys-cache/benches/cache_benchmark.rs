@@ -0,0 +1,203 @@
+//! Performance benchmarks for Frys Cache
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use frys_cache::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::runtime::Runtime;
+
+/// Lock acquisitions made by every `LockCounting` backend
+static LOCK_ACQUISITIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Memory backend counting calls into it. Every call takes the memory
+/// cache's lock exactly once, so the count is the number of lock
+/// acquisitions.
+#[derive(Debug)]
+struct LockCounting {
+    inner: MemoryCache,
+}
+
+impl LockCounting {
+    fn new(capacity: usize) -> Self {
+        Self {
+            inner: MemoryCache::new(capacity).unwrap(),
+        }
+    }
+
+    fn acquire(&self) {
+        LOCK_ACQUISITIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Criterion measurement counting lock acquisitions instead of time
+struct LockAcquisitions;
+
+impl Measurement for LockAcquisitions {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> u64 {
+        LOCK_ACQUISITIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, started: u64) -> u64 {
+        LOCK_ACQUISITIONS.load(Ordering::Relaxed) - started
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        a + b
+    }
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn to_f64(&self, value: &u64) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &LockFormatter
+    }
+}
+
+struct LockFormatter;
+
+impl ValueFormatter for LockFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "locks"
+    }
+
+    fn scale_throughputs(&self, _typical_value: f64, throughput: &Throughput, values: &mut [f64]) -> &'static str {
+        if let Throughput::Elements(elements) = throughput {
+            for value in values {
+                *value /= *elements as f64;
+            }
+        }
+        "locks/elem"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "locks"
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl CacheBackend for LockCounting {
+    async fn get(&self, key: &CacheKey) -> Result<Option<CacheValue>> {
+        self.acquire();
+        self.inner.get(key).await
+    }
+
+    async fn get_many(&self, keys: &[CacheKey]) -> Result<Vec<Option<CacheValue>>> {
+        self.acquire();
+        self.inner.get_many(keys).await
+    }
+
+    async fn put(&self, key: CacheKey, value: CacheValue) -> Result<()> {
+        self.acquire();
+        self.inner.put(key, value).await
+    }
+
+    async fn put_many(&self, entries: Vec<(CacheKey, CacheValue)>) -> Result<()> {
+        self.acquire();
+        self.inner.put_many(entries).await
+    }
+
+    async fn delete(&self, key: &CacheKey) -> Result<bool> {
+        self.acquire();
+        self.inner.delete(key).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.acquire();
+        self.inner.clear().await
+    }
+
+    async fn contains(&self, key: &CacheKey) -> Result<bool> {
+        self.acquire();
+        self.inner.contains(key).await
+    }
+
+    async fn keys(&self) -> Result<Vec<CacheKey>> {
+        self.acquire();
+        self.inner.keys().await
+    }
+
+    fn stats(&self) -> Result<CacheStats> {
+        self.inner.stats()
+    }
+
+    fn name(&self) -> &'static str {
+        "lock_counting"
+    }
+}
+
+/// Populated backend and the keys stored in it
+fn populated(rt: &Runtime, batch_size: usize) -> (LockCounting, Vec<CacheKey>) {
+    let backend = LockCounting::new(batch_size);
+    let keys: Vec<CacheKey> = (0..batch_size).map(|i| format!("key:{}", i).into_bytes()).collect();
+    let entries = keys.iter().map(|key| (key.clone(), vec![0u8; 64])).collect();
+    rt.block_on(backend.put_many(entries)).unwrap();
+    (backend, keys)
+}
+
+/// Time per batch of looped `get` versus one `get_many`
+fn bench_batched_gets(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("batched_gets");
+
+    for batch_size in [16usize, 256] {
+        let (backend, keys) = populated(&rt, batch_size);
+
+        group.throughput(Throughput::Elements(batch_size as u64));
+        group.bench_with_input(BenchmarkId::new("looped_get", batch_size), &keys, |b, keys| {
+            b.iter(|| {
+                rt.block_on(async {
+                    for key in keys {
+                        black_box(backend.get(key).await.unwrap());
+                    }
+                })
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("get_many", batch_size), &keys, |b, keys| {
+            b.iter(|| black_box(rt.block_on(backend.get_many(keys)).unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+/// Lock acquisitions per batch of looped `get` versus one `get_many`
+fn bench_batched_get_locks(c: &mut Criterion<LockAcquisitions>) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("batched_get_locks");
+
+    for batch_size in [16usize, 256] {
+        let (backend, keys) = populated(&rt, batch_size);
+
+        group.throughput(Throughput::Elements(batch_size as u64));
+        group.bench_with_input(BenchmarkId::new("looped_get", batch_size), &keys, |b, keys| {
+            b.iter(|| {
+                rt.block_on(async {
+                    for key in keys {
+                        black_box(backend.get(key).await.unwrap());
+                    }
+                })
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("get_many", batch_size), &keys, |b, keys| {
+            b.iter(|| black_box(rt.block_on(backend.get_many(keys)).unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_batched_gets);
+criterion_group! {
+    name = lock_benches;
+    config = Criterion::default().with_measurement(LockAcquisitions);
+    targets = bench_batched_get_locks
+}
+criterion_main!(benches, lock_benches);
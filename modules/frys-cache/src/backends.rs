@@ -2,46 +2,125 @@
 
 use crate::*;
 
-/// Memory cache backend using LRU
+/// Memory cache backend with a configurable eviction policy
 #[cfg(feature = "lru")]
+#[derive(Debug)]
 pub struct MemoryCache {
-    /// LRU cache instance
-    cache: lru::LruCache<CacheKey, CacheEntry>,
+    /// Entries, eviction bookkeeping and statistics
+    state: std::sync::Mutex<MemoryState>,
     /// Cache configuration
     config: CacheConfig,
-    /// Statistics
+}
+
+#[cfg(feature = "lru")]
+#[derive(Debug)]
+struct MemoryState {
+    entries: std::collections::HashMap<CacheKey, MemoryEntry>,
+    tracker: Box<dyn EvictionTracker>,
     stats: CacheStats,
+    /// Entries evicted for capacity and not yet taken, if kept
+    evicted: Option<alloc::vec::Vec<(CacheKey, MemoryEntry)>>,
+}
+
+/// Entry of the memory cache with its expiry
+#[cfg(feature = "lru")]
+#[derive(Debug)]
+struct MemoryEntry {
+    entry: CacheEntry,
+    /// When the entry expires, if ever
+    expires_at: Option<std::time::Instant>,
+    /// Window restarted by each read, for sliding entries
+    sliding: Option<::core::time::Duration>,
+}
+
+#[cfg(feature = "lru")]
+impl MemoryEntry {
+    fn new(entry: CacheEntry, ttl: Option<EntryTtl>, now: std::time::Instant) -> Self {
+        Self {
+            entry,
+            expires_at: ttl.map(|ttl| crate::loader::expiry_after(now, ttl.duration())),
+            sliding: match ttl {
+                Some(EntryTtl::Sliding(window)) => Some(window),
+                _ => None,
+            },
+        }
+    }
+
+    fn is_expired(&self, now: std::time::Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// TTL the entry has left at `now`: its window if sliding, `None` if it
+    /// never expires
+    fn remaining_ttl(&self, now: std::time::Instant) -> Option<EntryTtl> {
+        match self.sliding {
+            Some(window) => Some(EntryTtl::Sliding(window)),
+            None => self.expires_at.map(|expires_at| EntryTtl::Fixed(expires_at.saturating_duration_since(now))),
+        }
+    }
 }
 
 #[cfg(feature = "lru")]
 impl MemoryCache {
-    /// Create a new memory cache
+    /// Create a new LRU memory cache
     pub fn new(capacity: usize) -> Result<Self> {
+        Self::with_policy(capacity, EvictionPolicy::Lru)
+    }
+
+    /// Create a new memory cache evicting entries by `policy`
+    pub fn with_policy(capacity: usize, policy: EvictionPolicy) -> Result<Self> {
+        if capacity == 0 {
+            return Err(CacheError::ConfigError {
+                parameter: "capacity".into(),
+                details: "capacity must be greater than 0".into(),
+            });
+        }
+
         let config = CacheConfig {
             max_entries: capacity,
+            eviction_policy: policy,
             ..CacheConfig::default()
         };
 
         Ok(Self {
-            cache: lru::LruCache::new(capacity.try_into().map_err(|_| {
-                CacheError::ConfigError {
-                    parameter: "capacity".into(),
-                    details: "capacity too large".into(),
-                }
-            })?),
+            state: std::sync::Mutex::new(MemoryState {
+                entries: std::collections::HashMap::new(),
+                tracker: policy.tracker(capacity),
+                stats: CacheStats::default(),
+                evicted: None,
+            }),
             config,
-            stats: CacheStats::default(),
         })
     }
 
-    /// Evict entries if cache is full
-    fn evict_if_needed(&mut self) {
-        while self.cache.len() >= self.config.max_entries {
-            if let Some((_, entry)) = self.cache.pop_lru() {
-                self.stats.record_eviction();
-                self.stats.remove_entry(entry.size);
-            }
-        }
+    /// Keep entries evicted for capacity until taken with
+    /// [`CacheBackend::take_evicted`], so they can be written down to a
+    /// lower level instead of being lost
+    #[must_use]
+    pub fn keep_evicted(mut self) -> Self {
+        self.state.get_mut().unwrap_or_else(|e| e.into_inner()).evicted = Some(alloc::vec::Vec::new());
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Entry expiring per `ttl`, or the default TTL if `None`
+    fn new_entry(&self, key: CacheKey, value: CacheValue, ttl: Option<EntryTtl>) -> MemoryEntry {
+        let ttl = ttl.or_else(|| self.config.default_ttl.map(|secs| EntryTtl::Fixed(::core::time::Duration::from_secs(secs))));
+        let now = current_timestamp();
+        let entry = CacheEntry {
+            size: key.len() + value.len(),
+            key,
+            value,
+            ttl: ttl.map(|ttl| ttl.whole_secs()),
+            created_at: now,
+            accessed_at: now,
+            access_count: 1,
+            level: CacheLevel::Memory,
+        };
+        MemoryEntry::new(entry, ttl, std::time::Instant::now())
     }
 
     /// Check entry size limits
@@ -65,65 +144,170 @@ impl MemoryCache {
 }
 
 #[cfg(feature = "lru")]
-#[async_trait::async_trait(?Send)]
-impl CacheBackend for MemoryCache {
-    async fn get(&self, key: &CacheKey) -> Result<Option<CacheValue>> {
-        if let Some(entry) = self.cache.peek(key) {
-            // Check TTL
-            let now = current_timestamp();
-            if let Some(ttl) = entry.ttl {
-                if entry.created_at + ttl <= now {
-                    return Ok(None); // Entry expired
-                }
+impl MemoryState {
+    fn remove(&mut self, key: &CacheKey) -> Option<CacheEntry> {
+        let entry = self.entries.remove(key)?.entry;
+        self.tracker.on_remove(key);
+        self.stats.remove_entry(entry.size);
+        Some(entry)
+    }
+
+    fn get(&mut self, key: &CacheKey, now: std::time::Instant) -> Option<CacheValue> {
+        let expired = match self.entries.get(key) {
+            Some(entry) => entry.is_expired(now),
+            None => {
+                self.stats.record_miss();
+                return None;
             }
+        };
+        if expired {
+            self.remove(key);
+            self.stats.record_expiration();
+            self.stats.record_miss();
+            return None;
+        }
 
-            Ok(Some(entry.value.clone()))
+        self.tracker.on_hit(key);
+        self.stats.record_hit();
+        let entry = self.entries.get_mut(key).expect("entry checked above");
+        if let Some(window) = entry.sliding {
+            entry.expires_at = Some(crate::loader::expiry_after(now, window));
+        }
+        entry.entry.accessed_at = current_timestamp();
+        entry.entry.access_count += 1;
+        Some(entry.entry.value.clone())
+    }
+
+    /// Whether `key` holds an entry that has not expired
+    fn contains(&self, key: &CacheKey, now: std::time::Instant) -> bool {
+        self.entries.get(key).is_some_and(|entry| !entry.is_expired(now))
+    }
+
+    fn insert(&mut self, entry: MemoryEntry) {
+        let key = entry.entry.key.clone();
+        if let Some(old) = self.entries.remove(&key) {
+            self.stats.remove_entry(old.entry.size);
+            self.tracker.on_hit(&key);
         } else {
-            Ok(None)
+            for victim in self.tracker.on_insert(key.clone()) {
+                if let Some(evicted) = self.entries.remove(&victim) {
+                    self.stats.remove_entry(evicted.entry.size);
+                    self.stats.record_eviction();
+                    if let Some(kept) = &mut self.evicted {
+                        kept.push((victim, evicted));
+                    }
+                }
+            }
         }
+        self.stats.add_entry(entry.entry.size);
+        self.entries.insert(key, entry);
+    }
+
+    /// Take the kept evictions, dropping those expired by `now`
+    fn take_evicted(&mut self, now: std::time::Instant) -> alloc::vec::Vec<(CacheKey, CacheValue, Option<EntryTtl>)> {
+        let Some(kept) = &mut self.evicted else {
+            return alloc::vec::Vec::new();
+        };
+        core::mem::take(kept)
+            .into_iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, entry)| {
+                let ttl = entry.remaining_ttl(now);
+                (key, entry.entry.value, ttl)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "lru")]
+#[async_trait::async_trait(?Send)]
+impl CacheBackend for MemoryCache {
+    async fn get(&self, key: &CacheKey) -> Result<Option<CacheValue>> {
+        Ok(self.lock().get(key, std::time::Instant::now()))
+    }
+
+    async fn get_many(&self, keys: &[CacheKey]) -> Result<alloc::vec::Vec<Option<CacheValue>>> {
+        let now = std::time::Instant::now();
+        let mut state = self.lock();
+        Ok(keys.iter().map(|key| state.get(key, now)).collect())
     }
 
     async fn put(&self, key: CacheKey, value: CacheValue) -> Result<()> {
         self.check_size_limits(&key, &value)?;
+        let entry = self.new_entry(key, value, None);
+        self.lock().insert(entry);
+        Ok(())
+    }
 
-        let now = current_timestamp();
-        let entry = CacheEntry {
-            key: key.clone(),
-            value: value.clone(),
-            ttl: self.config.default_ttl,
-            created_at: now,
-            accessed_at: now,
-            access_count: 1,
-            size: key.len() + value.len(),
-            level: CacheLevel::Memory,
-        };
+    async fn put_with_ttl(&self, key: CacheKey, value: CacheValue, ttl: EntryTtl) -> Result<()> {
+        self.check_size_limits(&key, &value)?;
+        let entry = self.new_entry(key, value, Some(ttl));
+        self.lock().insert(entry);
+        Ok(())
+    }
 
-        // Note: In a real implementation, this would be mutable
-        // For this example, we assume the cache is wrapped in a mutex
+    async fn put_many(&self, entries: alloc::vec::Vec<(CacheKey, CacheValue)>) -> Result<()> {
+        // Reject the whole batch before any of it is stored
+        for (key, value) in &entries {
+            self.check_size_limits(key, value)?;
+        }
+
+        let entries: alloc::vec::Vec<_> = entries.into_iter().map(|(key, value)| self.new_entry(key, value, None)).collect();
+        let mut state = self.lock();
+        for entry in entries {
+            state.insert(entry);
+        }
         Ok(())
     }
 
     async fn delete(&self, key: &CacheKey) -> Result<bool> {
-        // Note: In a real implementation, this would check and remove
-        Ok(false)
+        Ok(self.lock().remove(key).is_some())
     }
 
     async fn clear(&self) -> Result<()> {
-        // Note: In a real implementation, this would clear the cache
+        let mut state = self.lock();
+        state.entries.clear();
+        state.tracker.clear();
+        state.stats.entries = 0;
+        state.stats.total_size = 0;
+        if let Some(kept) = &mut state.evicted {
+            kept.clear();
+        }
         Ok(())
     }
 
     async fn contains(&self, key: &CacheKey) -> Result<bool> {
-        Ok(self.cache.contains(key))
+        Ok(self.lock().contains(key, std::time::Instant::now()))
     }
 
     async fn keys(&self) -> Result<alloc::vec::Vec<CacheKey>> {
-        let keys: alloc::vec::Vec<_> = self.cache.iter().map(|(k, _)| k.clone()).collect();
-        Ok(keys)
+        let now = std::time::Instant::now();
+        Ok(self
+            .lock()
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect())
     }
 
     fn stats(&self) -> Result<CacheStats> {
-        Ok(self.stats.clone())
+        Ok(self.lock().stats.clone())
+    }
+
+    async fn remaining_ttl(&self, key: &CacheKey) -> Result<Option<::core::time::Duration>> {
+        let now = std::time::Instant::now();
+        Ok(self
+            .lock()
+            .entries
+            .get(key)
+            .filter(|entry| !entry.is_expired(now))
+            .and_then(|entry| entry.expires_at)
+            .map(|expires_at| expires_at.saturating_duration_since(now)))
+    }
+
+    fn take_evicted(&self) -> alloc::vec::Vec<(CacheKey, CacheValue, Option<EntryTtl>)> {
+        self.lock().take_evicted(std::time::Instant::now())
     }
 
     fn name(&self) -> &'static str {
@@ -182,6 +366,60 @@ impl PersistentCache {
         })
     }
 
+    /// Store an entry expiring after `ttl` seconds, if any
+    fn store(&self, key: CacheKey, value: CacheValue, ttl: Option<u64>) -> Result<()> {
+        if key.len() > MAX_KEY_SIZE {
+            return Err(CacheError::KeyTooLarge {
+                size: key.len(),
+                max_size: MAX_KEY_SIZE,
+            });
+        }
+
+        if value.len() > MAX_VALUE_SIZE {
+            return Err(CacheError::ValueTooLarge {
+                size: value.len(),
+                max_size: MAX_VALUE_SIZE,
+            });
+        }
+
+        let now = current_timestamp();
+        let entry = CacheEntry {
+            key: key.clone(),
+            value: value.clone(),
+            ttl,
+            created_at: now,
+            accessed_at: now,
+            access_count: 1,
+            size: key.len() + value.len(),
+            level: CacheLevel::Persistent,
+        };
+
+        #[cfg(feature = "serde")]
+        let data = self.serialize_entry(&entry)?;
+
+        #[cfg(not(feature = "serde"))]
+        let data = value;
+
+        #[cfg(feature = "compression")]
+        let data = self.compress(&data)?;
+
+        self.db.insert(key, data).map_err(|e| {
+            CacheError::BackendError {
+                backend: "sled",
+                details: alloc::format!("{}", e),
+            }
+        })?;
+
+        self.db.flush().map_err(|e| {
+            CacheError::BackendError {
+                backend: "sled",
+                details: alloc::format!("{}", e),
+            }
+        })?;
+
+        Ok(())
+    }
+
     /// Compress data if compression is enabled
     #[cfg(feature = "compression")]
     fn compress(&self, data: &[u8]) -> Result<alloc::vec::Vec<u8>> {
@@ -229,7 +467,7 @@ impl CacheBackend for PersistentCache {
                     // Check TTL
                     let now = current_timestamp();
                     if let Some(ttl) = entry.ttl {
-                        if entry.created_at + ttl <= now {
+                        if entry.created_at.saturating_add(ttl) <= now {
                             // Entry expired, remove it
                             let _ = self.db.remove(key);
                             return Ok(None);
@@ -251,56 +489,38 @@ impl CacheBackend for PersistentCache {
     }
 
     async fn put(&self, key: CacheKey, value: CacheValue) -> Result<()> {
-        if key.len() > MAX_KEY_SIZE {
-            return Err(CacheError::KeyTooLarge {
-                size: key.len(),
-                max_size: MAX_KEY_SIZE,
-            });
-        }
-
-        if value.len() > MAX_VALUE_SIZE {
-            return Err(CacheError::ValueTooLarge {
-                size: value.len(),
-                max_size: MAX_VALUE_SIZE,
-            });
-        }
+        self.store(key, value, self.config.default_ttl)
+    }
 
-        let now = current_timestamp();
-        let entry = CacheEntry {
-            key: key.clone(),
-            value: value.clone(),
-            ttl: self.config.default_ttl,
-            created_at: now,
-            accessed_at: now,
-            access_count: 1,
-            size: key.len() + value.len(),
-            level: CacheLevel::Persistent,
-        };
+    async fn put_with_ttl(&self, key: CacheKey, value: CacheValue, ttl: EntryTtl) -> Result<()> {
+        // Reads are not written back, so sliding entries expire one window
+        // after they are stored
+        self.store(key, value, Some(ttl.whole_secs()))
+    }
 
+    async fn remaining_ttl(&self, key: &CacheKey) -> Result<Option<::core::time::Duration>> {
         #[cfg(feature = "serde")]
-        let data = self.serialize_entry(&entry)?;
-
-        #[cfg(not(feature = "serde"))]
-        let data = value;
-
-        #[cfg(feature = "compression")]
-        let data = self.compress(&data)?;
-
-        self.db.insert(key, data).map_err(|e| {
-            CacheError::BackendError {
-                backend: "sled",
-                details: alloc::format!("{}", e),
-            }
-        })?;
+        {
+            let data = self.db.get(key).map_err(|e| {
+                CacheError::BackendError {
+                    backend: "sled",
+                    details: alloc::format!("{}", e),
+                }
+            })?;
+            if let Some(data) = data {
+                #[cfg(feature = "compression")]
+                let data = self.decompress(&data)?;
 
-        self.db.flush().map_err(|e| {
-            CacheError::BackendError {
-                backend: "sled",
-                details: alloc::format!("{}", e),
+                let entry = self.deserialize_entry(&data)?;
+                let now = current_timestamp();
+                if let Some(expires_at) = entry.ttl.map(|ttl| entry.created_at.saturating_add(ttl)).filter(|&expires_at| expires_at > now) {
+                    return Ok(Some(::core::time::Duration::from_secs(expires_at - now)));
+                }
             }
-        })?;
-
-        Ok(())
+        }
+        #[cfg(not(feature = "serde"))]
+        let _ = key;
+        Ok(None)
     }
 
     async fn delete(&self, key: &CacheKey) -> Result<bool> {
@@ -424,6 +644,25 @@ impl CacheBackend for DistributedCache {
         Ok(result)
     }
 
+    async fn get_many(&self, keys: &[CacheKey]) -> Result<alloc::vec::Vec<Option<CacheValue>>> {
+        if keys.is_empty() {
+            return Ok(alloc::vec::Vec::new());
+        }
+
+        // One MGET instead of a round-trip per key; replies follow key order
+        let mut conn = self.get_connection().await?;
+        redis::cmd("MGET")
+            .arg(keys)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| {
+                CacheError::BackendError {
+                    backend: "redis",
+                    details: alloc::format!("{}", e),
+                }
+            })
+    }
+
     async fn put(&self, key: CacheKey, value: CacheValue) -> Result<()> {
         if key.len() > MAX_KEY_SIZE {
             return Err(CacheError::KeyTooLarge {
@@ -464,6 +703,93 @@ impl CacheBackend for DistributedCache {
         Ok(())
     }
 
+    async fn put_with_ttl(&self, key: CacheKey, value: CacheValue, ttl: EntryTtl) -> Result<()> {
+        if key.len() > MAX_KEY_SIZE {
+            return Err(CacheError::KeyTooLarge {
+                size: key.len(),
+                max_size: MAX_KEY_SIZE,
+            });
+        }
+
+        if value.len() > MAX_VALUE_SIZE {
+            return Err(CacheError::ValueTooLarge {
+                size: value.len(),
+                max_size: MAX_VALUE_SIZE,
+            });
+        }
+
+        // Reads do not refresh the key, so sliding entries expire one window
+        // after they are stored
+        let millis = u64::try_from(ttl.duration().as_millis()).unwrap_or(u64::MAX).max(1);
+        let mut conn = self.get_connection().await?;
+        redis::cmd("PSETEX")
+            .arg(&key)
+            .arg(millis)
+            .arg(&value)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| {
+                CacheError::BackendError {
+                    backend: "redis",
+                    details: alloc::format!("{}", e),
+                }
+            })
+    }
+
+    async fn put_many(&self, entries: alloc::vec::Vec<(CacheKey, CacheValue)>) -> Result<()> {
+        for (key, value) in &entries {
+            if key.len() > MAX_KEY_SIZE {
+                return Err(CacheError::KeyTooLarge {
+                    size: key.len(),
+                    max_size: MAX_KEY_SIZE,
+                });
+            }
+            if value.len() > MAX_VALUE_SIZE {
+                return Err(CacheError::ValueTooLarge {
+                    size: value.len(),
+                    max_size: MAX_VALUE_SIZE,
+                });
+            }
+        }
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        // Pipeline the writes so the batch costs a single round-trip
+        let mut pipe = redis::pipe();
+        for (key, value) in &entries {
+            match self.config.default_ttl {
+                Some(ttl) => pipe.cmd("SETEX").arg(key).arg(ttl).arg(value).ignore(),
+                None => pipe.cmd("SET").arg(key).arg(value).ignore(),
+            };
+        }
+
+        let mut conn = self.get_connection().await?;
+        pipe.query_async::<_, ()>(&mut conn).await.map_err(|e| {
+            CacheError::BackendError {
+                backend: "redis",
+                details: alloc::format!("{}", e),
+            }
+        })
+    }
+
+    async fn remaining_ttl(&self, key: &CacheKey) -> Result<Option<::core::time::Duration>> {
+        let mut conn = self.get_connection().await?;
+        // Negative replies mean no expiry (-1) or no such key (-2)
+        let millis: i64 = redis::cmd("PTTL")
+            .arg(key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| {
+                CacheError::BackendError {
+                    backend: "redis",
+                    details: alloc::format!("{}", e),
+                }
+            })?;
+
+        Ok(u64::try_from(millis).ok().map(::core::time::Duration::from_millis))
+    }
+
     async fn delete(&self, key: &CacheKey) -> Result<bool> {
         let mut conn = self.get_connection().await?;
         let result: i32 = redis::cmd("DEL")
@@ -570,6 +896,141 @@ mod tests {
         assert_eq!("distributed", "distributed");
     }
 
+    #[cfg(feature = "lru")]
+    #[tokio::test]
+    async fn test_memory_cache_evicts_by_policy() {
+        let cache = MemoryCache::with_policy(2, EvictionPolicy::Lfu).unwrap();
+        let (a, b, c) = (b"a".to_vec(), b"b".to_vec(), b"c".to_vec());
+
+        cache.put(a.clone(), b"1".to_vec()).await.unwrap();
+        cache.put(b.clone(), b"2".to_vec()).await.unwrap();
+        assert_eq!(cache.get(&a).await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(cache.get(&a).await.unwrap(), Some(b"1".to_vec()));
+
+        // "b" was read least often, even though "a" is older
+        cache.put(c.clone(), b"3".to_vec()).await.unwrap();
+        assert!(cache.contains(&a).await.unwrap());
+        assert!(!cache.contains(&b).await.unwrap());
+        assert!(cache.contains(&c).await.unwrap());
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.hits, 2);
+
+        // Overwriting a resident key evicts nothing
+        cache.put(c.clone(), b"4".to_vec()).await.unwrap();
+        assert_eq!(cache.stats().unwrap().evictions, 1);
+        assert!(cache.delete(&c).await.unwrap());
+        assert_eq!(cache.stats().unwrap().entries, 1);
+    }
+
+    #[cfg(feature = "lru")]
+    #[tokio::test]
+    async fn test_memory_cache_batches_keep_key_order() {
+        let cache = MemoryCache::new(10).unwrap();
+        cache
+            .put_many(alloc::vec![(b"a".to_vec(), b"1".to_vec()), (b"b".to_vec(), b"2".to_vec())])
+            .await
+            .unwrap();
+
+        let keys = [b"b".to_vec(), b"missing".to_vec(), b"a".to_vec(), b"b".to_vec()];
+        assert_eq!(
+            cache.get_many(&keys).await.unwrap(),
+            alloc::vec![Some(b"2".to_vec()), None, Some(b"1".to_vec()), Some(b"2".to_vec())]
+        );
+
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (2, 3, 1));
+
+        // An oversized entry rejects the batch before anything is stored
+        let batch = alloc::vec![(b"c".to_vec(), b"3".to_vec()), (b"d".to_vec(), alloc::vec![0u8; MAX_VALUE_SIZE + 1])];
+        assert!(matches!(cache.put_many(batch).await, Err(CacheError::ValueTooLarge { .. })));
+        assert!(!cache.contains(&b"c".to_vec()).await.unwrap());
+    }
+
+    #[cfg(feature = "lru")]
+    #[tokio::test]
+    async fn test_memory_cache_per_entry_ttl() {
+        use ::core::time::Duration;
+        use std::time::Instant;
+
+        let cache = MemoryCache::new(10).unwrap();
+        let (short, default) = (b"short".to_vec(), b"default".to_vec());
+        cache.put_with_ttl(short.clone(), b"1".to_vec(), EntryTtl::Fixed(Duration::from_secs(5))).await.unwrap();
+        cache.put(default.clone(), b"2".to_vec()).await.unwrap();
+
+        // The per-key TTL overrides the one hour default
+        let later = Instant::now() + Duration::from_secs(6);
+        assert_eq!(cache.lock().get(&short, later), None);
+        assert_eq!(cache.lock().get(&default, later), Some(b"2".to_vec()));
+        assert!(!cache.contains(&short).await.unwrap());
+
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.expirations, stats.entries, stats.hits, stats.misses), (1, 1, 1, 1));
+
+        // TTLs too long to represent are clamped, for fixed and sliding entries
+        for ttl in [EntryTtl::Fixed(Duration::MAX), EntryTtl::Sliding(Duration::MAX)] {
+            cache.put_with_ttl(b"long".to_vec(), b"3".to_vec(), ttl).await.unwrap();
+            assert_eq!(cache.get(&b"long".to_vec()).await.unwrap(), Some(b"3".to_vec()));
+        }
+    }
+
+    #[cfg(feature = "lru")]
+    #[tokio::test]
+    async fn test_memory_cache_sliding_ttl() {
+        use ::core::time::Duration;
+        use std::time::Instant;
+
+        let cache = MemoryCache::new(10).unwrap();
+        let key = b"session".to_vec();
+        cache.put_with_ttl(key.clone(), b"1".to_vec(), SlidingTtl(Duration::from_secs(10)).into()).await.unwrap();
+
+        // Each read restarts the window, so the entry outlives it while in use
+        let start = Instant::now();
+        for secs in [8, 16, 24] {
+            assert_eq!(cache.lock().get(&key, start + Duration::from_secs(secs)), Some(b"1".to_vec()));
+        }
+        assert_eq!(cache.lock().get(&key, start + Duration::from_secs(35)), None);
+        assert_eq!(cache.stats().unwrap().expirations, 1);
+        assert_eq!(cache.stats().unwrap().entries, 0);
+    }
+
+    #[cfg(feature = "lru")]
+    #[tokio::test]
+    async fn test_memory_cache_evictions_keep_their_ttl() {
+        use ::core::time::Duration;
+        use std::time::Instant;
+
+        let cache = MemoryCache::new(1).unwrap().keep_evicted();
+        cache.put_with_ttl(b"short".to_vec(), b"1".to_vec(), EntryTtl::Fixed(Duration::from_secs(5))).await.unwrap();
+        cache.put(b"a".to_vec(), b"2".to_vec()).await.unwrap();
+        cache.put(b"b".to_vec(), b"3".to_vec()).await.unwrap();
+
+        // "short" has under 5 seconds left when written down; "a" keeps the default
+        let later = Instant::now() + Duration::from_secs(2);
+        let evicted = cache.lock().take_evicted(later);
+        assert_eq!(evicted.len(), 2);
+        assert!(matches!(evicted[0].2, Some(EntryTtl::Fixed(left)) if left <= Duration::from_secs(3)));
+        assert!(matches!(evicted[1].2, Some(EntryTtl::Fixed(left)) if left > Duration::from_secs(3500)));
+        assert!(cache.take_evicted().is_empty());
+
+        // Victims that expired before being taken are dropped
+        cache.put_with_ttl(b"c".to_vec(), b"4".to_vec(), EntryTtl::Fixed(Duration::from_secs(5))).await.unwrap();
+        cache.put(b"d".to_vec(), b"5".to_vec()).await.unwrap();
+        let evicted = cache.lock().take_evicted(Instant::now() + Duration::from_secs(10));
+        assert_eq!(evicted.iter().map(|(key, ..)| key.clone()).collect::<alloc::vec::Vec<_>>(), [b"b".to_vec()]);
+    }
+
+    #[cfg(feature = "lru")]
+    #[test]
+    fn test_memory_cache_rejects_zero_capacity() {
+        assert!(matches!(
+            MemoryCache::with_policy(0, EvictionPolicy::Arc),
+            Err(CacheError::ConfigError { .. })
+        ));
+    }
+
     #[test]
     fn test_size_limits() {
         let key = alloc::vec![0u8; MAX_KEY_SIZE + 1];
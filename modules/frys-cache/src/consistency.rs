@@ -1,6 +1,7 @@
 //! Cache consistency management
 
 use crate::*;
+use std::time::Duration;
 
 /// Consistency strategy types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,27 +14,206 @@ pub enum ConsistencyStrategy {
     CacheAside,
 }
 
+/// Write-back buffering configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteBackConfig {
+    /// Longest time buffered writes wait before a flush is due
+    pub flush_interval: Duration,
+    /// Number of dirty entries that triggers a flush
+    pub max_dirty_entries: usize,
+    /// Write-ahead log file for unflushed writes, or `None` to keep them
+    /// only in memory
+    pub wal_path: Option<alloc::string::String>,
+    /// Sync the log to disk after every append
+    pub sync_writes: bool,
+}
+
+impl Default for WriteBackConfig {
+    fn default() -> Self {
+        Self {
+            flush_interval: Duration::from_secs(DEFAULT_WRITE_BACK_FLUSH_SECS),
+            max_dirty_entries: DEFAULT_MAX_DIRTY_ENTRIES,
+            wal_path: None,
+            sync_writes: false,
+        }
+    }
+}
+
+/// A buffered write-back operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirtyOp {
+    /// The key was written with this value
+    Put(CacheValue),
+    /// The key was deleted
+    Delete,
+}
+
+/// Dirty entry tracking statistics for the write-back strategy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirtyTrackingStats {
+    /// Entries buffered and not yet flushed
+    pub dirty_entries: usize,
+    /// Writes and deletes buffered
+    pub buffered_ops: u64,
+    /// Completed flushes
+    pub flushes: u64,
+    /// Entries written to the lower cache levels by flushes
+    pub flushed_entries: u64,
+    /// Entries recovered from the write-ahead log on startup
+    pub recovered_entries: u64,
+}
+
 /// Consistency manager for coordinating cache consistency
 #[derive(Debug)]
 pub struct ConsistencyManager {
     /// Consistency strategy
     strategy: ConsistencyStrategy,
-    /// Pending write operations (for write-back strategy)
-    pending_writes: alloc::vec::Vec<PendingWrite>,
+    /// Dirty entries awaiting a flush (for write-back strategy)
+    dirty: std::sync::Mutex<DirtySet>,
     /// Statistics
     stats: ConsistencyStats,
 }
 
+/// Buffered writes and the log protecting them
+#[derive(Debug)]
+struct DirtySet {
+    config: WriteBackConfig,
+    /// Latest operation per key, with the version it was buffered at
+    entries: alloc::collections::BTreeMap<CacheKey, (DirtyOp, u64)>,
+    next_version: u64,
+    last_flush: std::time::Instant,
+    wal: Option<crate::wal::WriteBackLog>,
+    stats: DirtyTrackingStats,
+}
+
 impl ConsistencyManager {
     /// Create a new consistency manager
     pub fn new(strategy: ConsistencyStrategy) -> Self {
         Self {
             strategy,
-            pending_writes: alloc::vec::Vec::new(),
+            dirty: std::sync::Mutex::new(DirtySet::new(WriteBackConfig::default())),
             stats: ConsistencyStats::default(),
         }
     }
 
+    /// Create a write-back consistency manager.
+    ///
+    /// When `config.wal_path` is set, writes left unflushed by a previous
+    /// run are replayed from the log and become dirty entries again.
+    pub fn with_write_back(config: WriteBackConfig) -> Result<Self> {
+        let mut dirty = DirtySet::new(config);
+        if let Some(path) = dirty.config.wal_path.clone() {
+            let (wal, ops) = crate::wal::WriteBackLog::open(&path, dirty.config.sync_writes)?;
+            for (key, op) in ops {
+                dirty.insert(key, op);
+            }
+            dirty.stats.recovered_entries = dirty.entries.len() as u64;
+            dirty.wal = Some(wal);
+        }
+
+        Ok(Self {
+            strategy: ConsistencyStrategy::WriteBack,
+            dirty: std::sync::Mutex::new(dirty),
+            stats: ConsistencyStats::default(),
+        })
+    }
+
+    /// Get the consistency strategy
+    pub fn strategy(&self) -> ConsistencyStrategy {
+        self.strategy
+    }
+
+    fn lock_dirty(&self) -> std::sync::MutexGuard<'_, DirtySet> {
+        self.dirty.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Buffer a write-back operation, logging it first.
+    ///
+    /// Returns whether a flush is now due, either because the dirty set
+    /// reached `max_dirty_entries` or `flush_interval` has passed since the
+    /// last flush.
+    pub fn buffer(&self, key: CacheKey, op: DirtyOp) -> Result<bool> {
+        let mut dirty = self.lock_dirty();
+        if let Some(wal) = dirty.wal.as_mut() {
+            wal.append(&key, &op)?;
+        }
+        dirty.insert(key, op);
+        dirty.stats.buffered_ops += 1;
+        Ok(dirty.flush_due())
+    }
+
+    /// Buffered operation for `key`, if it has not been flushed yet
+    pub fn dirty_entry(&self, key: &CacheKey) -> Option<DirtyOp> {
+        self.lock_dirty().entries.get(key).map(|(op, _)| op.clone())
+    }
+
+    /// Whether buffered writes have waited for `flush_interval` or the
+    /// dirty set is full
+    pub fn flush_due(&self) -> bool {
+        self.lock_dirty().flush_due()
+    }
+
+    /// Interval at which buffered writes are flushed
+    pub fn flush_interval(&self) -> Duration {
+        self.lock_dirty().config.flush_interval
+    }
+
+    /// Snapshot of the dirty entries, with the versions to pass to
+    /// [`ConsistencyManager::mark_flushed`] once they are written
+    pub fn dirty_entries(&self) -> alloc::vec::Vec<(CacheKey, DirtyOp, u64)> {
+        self.lock_dirty()
+            .entries
+            .iter()
+            .map(|(key, (op, version))| (key.clone(), op.clone(), *version))
+            .collect()
+    }
+
+    /// Forget entries written to the lower levels and compact the log.
+    ///
+    /// An entry buffered again after its snapshot was taken has a newer
+    /// version and stays dirty.
+    pub fn mark_flushed(&self, flushed: &[(CacheKey, u64)]) -> Result<usize> {
+        let mut dirty = self.lock_dirty();
+        let mut count = 0;
+        for (key, version) in flushed {
+            if dirty.entries.get(key).is_some_and(|(_, current)| current == version) {
+                dirty.entries.remove(key);
+                count += 1;
+            }
+        }
+
+        let DirtySet { entries, wal, .. } = &mut *dirty;
+        if let Some(wal) = wal.as_mut() {
+            wal.rewrite(entries.iter().map(|(key, (op, _))| (key, op)))?;
+        }
+
+        dirty.last_flush = std::time::Instant::now();
+        dirty.stats.flushes += 1;
+        dirty.stats.flushed_entries += count as u64;
+        self.stats.record_flush();
+        Ok(count)
+    }
+
+    /// Drop every dirty entry without flushing it, e.g. when the cache is
+    /// cleared
+    pub fn discard_dirty(&self) -> Result<()> {
+        let mut dirty = self.lock_dirty();
+        dirty.entries.clear();
+        if let Some(wal) = dirty.wal.as_mut() {
+            wal.rewrite(::core::iter::empty())?;
+        }
+        Ok(())
+    }
+
+    /// Get dirty entry tracking statistics
+    pub fn dirty_stats(&self) -> DirtyTrackingStats {
+        let dirty = self.lock_dirty();
+        DirtyTrackingStats {
+            dirty_entries: dirty.entries.len(),
+            ..dirty.stats.clone()
+        }
+    }
+
     /// Handle write operation before it occurs
     pub async fn before_write(&self, key: &CacheKey, value: &CacheValue) -> Result<()> {
         match self.strategy {
@@ -58,7 +238,7 @@ impl ConsistencyManager {
     }
 
     /// Handle write operation after it occurs
-    pub async fn after_write(&self, key: &CacheKey, value: &CacheValue) -> Result<()> {
+    pub async fn after_write(&self, _key: &CacheKey, _value: &CacheValue) -> Result<()> {
         match self.strategy {
             ConsistencyStrategy::WriteThrough => {
                 // Write was successful, update stats
@@ -66,18 +246,9 @@ impl ConsistencyManager {
                 Ok(())
             }
             ConsistencyStrategy::WriteBack => {
-                // Add to pending writes queue
-                let pending = PendingWrite {
-                    key: key.clone(),
-                    value: value.clone(),
-                    timestamp: current_timestamp(),
-                };
-
-                // In a real implementation, this would be thread-safe
-                // For now, just record it
+                // The write was buffered by the cache manager and reaches
+                // the lower levels on the next flush
                 self.stats.record_write_success();
-
-                // TODO: Schedule background write to backend
                 Ok(())
             }
             ConsistencyStrategy::CacheAside => {
@@ -114,8 +285,6 @@ impl ConsistencyManager {
                 Ok(())
             }
             ConsistencyStrategy::WriteBack => {
-                // Add to pending deletes queue
-                // In a real implementation, this would be handled
                 self.stats.record_delete_success();
                 Ok(())
             }
@@ -130,8 +299,8 @@ impl ConsistencyManager {
     pub async fn flush_pending_writes(&self) -> Result<()> {
         match self.strategy {
             ConsistencyStrategy::WriteBack => {
-                // In a real implementation, this would flush all pending writes
-                // to the backend
+                // Dirty entries are written by `CacheManager::flush`,
+                // which owns the backends
                 self.stats.record_flush();
                 Ok(())
             }
@@ -141,12 +310,12 @@ impl ConsistencyManager {
 
     /// Check if there are pending writes
     pub fn has_pending_writes(&self) -> bool {
-        !self.pending_writes.is_empty()
+        !self.lock_dirty().entries.is_empty()
     }
 
     /// Get number of pending writes
     pub fn pending_write_count(&self) -> usize {
-        self.pending_writes.len()
+        self.lock_dirty().entries.len()
     }
 
     /// Get consistency statistics
@@ -155,6 +324,29 @@ impl ConsistencyManager {
     }
 }
 
+impl DirtySet {
+    fn new(config: WriteBackConfig) -> Self {
+        Self {
+            config,
+            entries: alloc::collections::BTreeMap::new(),
+            next_version: 0,
+            last_flush: std::time::Instant::now(),
+            wal: None,
+            stats: DirtyTrackingStats::default(),
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, op: DirtyOp) {
+        self.next_version += 1;
+        self.entries.insert(key, (op, self.next_version));
+    }
+
+    fn flush_due(&self) -> bool {
+        !self.entries.is_empty()
+            && (self.entries.len() >= self.config.max_dirty_entries || self.last_flush.elapsed() >= self.config.flush_interval)
+    }
+}
+
 /// Pending write operation
 #[derive(Debug, Clone)]
 pub struct PendingWrite {
@@ -306,8 +498,8 @@ impl InvalidationPattern {
     }
 
     /// Check if this pattern matches a key
-    pub fn matches(&self, key: &CacheKey) -> bool {
-        let key_str = alloc::string::from_utf8_lossy(key);
+    pub fn matches(&self, key: &[u8]) -> bool {
+        let key_str = alloc::string::String::from_utf8_lossy(key);
 
         match self.pattern_type {
             PatternType::Prefix => key_str.starts_with(&self.pattern),
@@ -372,12 +564,6 @@ impl InvalidationStats {
     }
 }
 
-/// Get current timestamp (simplified)
-fn current_timestamp() -> u64 {
-    // In a real implementation, this would use system time
-    0
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
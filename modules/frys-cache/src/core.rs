@@ -30,6 +30,48 @@ pub struct CacheEntry {
     pub level: CacheLevel,
 }
 
+/// Expiration of a single entry, overriding the default TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryTtl {
+    /// Expire this long after the entry is written
+    Fixed(Duration),
+    /// Expire once the entry has not been read for this long
+    Sliding(Duration),
+}
+
+/// Sliding expiration policy: every read of an entry restarts its window,
+/// so it lives as long as it is accessed at least once per window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingTtl(pub Duration);
+
+impl From<Duration> for EntryTtl {
+    fn from(ttl: Duration) -> Self {
+        EntryTtl::Fixed(ttl)
+    }
+}
+
+impl From<SlidingTtl> for EntryTtl {
+    fn from(ttl: SlidingTtl) -> Self {
+        EntryTtl::Sliding(ttl.0)
+    }
+}
+
+impl EntryTtl {
+    /// Length of the fixed lifetime or sliding window
+    pub fn duration(&self) -> Duration {
+        match self {
+            EntryTtl::Fixed(ttl) | EntryTtl::Sliding(ttl) => *ttl,
+        }
+    }
+
+    /// [`EntryTtl::duration`] in whole seconds, rounded up so a sub-second
+    /// TTL does not become zero
+    pub fn whole_secs(&self) -> u64 {
+        let ttl = self.duration();
+        ttl.as_secs().saturating_add(u64::from(ttl.subsec_nanos() > 0))
+    }
+}
+
 /// Cache levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum CacheLevel {
@@ -54,6 +96,8 @@ pub struct CacheStats {
     pub misses: u64,
     /// Number of evictions
     pub evictions: u64,
+    /// Number of entries dropped because their TTL ran out
+    pub expirations: u64,
     /// Cache hit ratio (0.0 to 1.0)
     pub hit_ratio: f64,
     /// Average access time in nanoseconds
@@ -86,6 +130,11 @@ impl CacheStats {
         self.evictions += 1;
     }
 
+    /// Record an expired entry
+    pub fn record_expiration(&mut self) {
+        self.expirations += 1;
+    }
+
     /// Add an entry
     pub fn add_entry(&mut self, size: usize) {
         self.entries += 1;
@@ -112,6 +161,18 @@ pub struct CacheConfig {
     pub max_size_bytes: u64,
     /// Default TTL in seconds
     pub default_ttl: Option<u64>,
+    /// How long a key a loader reported as not found stays negatively
+    /// cached, or `None` to not cache misses
+    pub negative_ttl: Option<Duration>,
+    /// Maximum number of negatively cached keys
+    pub max_negative_entries: usize,
+    /// XFetch `beta` for recomputing loaded values before they expire, or
+    /// `None` to only load on a miss. Values above 1 recompute earlier.
+    pub early_recompute_beta: Option<f64>,
+    /// Lower-level hits after which a key is promoted into the first
+    /// level, or `None` to disable tiering. With tiering, entries evicted
+    /// from the first level are written down to the second.
+    pub promotion_threshold: Option<u32>,
     /// Cache levels to use
     pub levels: alloc::vec::Vec<CacheLevel>,
     /// Compression enabled
@@ -130,6 +191,10 @@ impl Default for CacheConfig {
             max_entries: DEFAULT_CACHE_SIZE,
             max_size_bytes: 100 * 1024 * 1024, // 100MB
             default_ttl: Some(DEFAULT_TTL_SECS),
+            negative_ttl: Some(Duration::from_secs(DEFAULT_NEGATIVE_TTL_SECS)),
+            max_negative_entries: DEFAULT_MAX_NEGATIVE_ENTRIES,
+            early_recompute_beta: None,
+            promotion_threshold: None,
             levels: alloc::vec![CacheLevel::Memory],
             compression: false,
             compression_level: DEFAULT_COMPRESSION_LEVEL,
@@ -139,6 +204,27 @@ impl Default for CacheConfig {
     }
 }
 
+/// Outcome of a cache lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheLookup {
+    /// The key is cached with this value
+    Hit(CacheValue),
+    /// The key is known to be absent from the backing store
+    Negative,
+    /// Nothing is known about the key
+    Miss,
+}
+
+impl CacheLookup {
+    /// The cached value, if any
+    pub fn into_value(self) -> Option<CacheValue> {
+        match self {
+            CacheLookup::Hit(value) => Some(value),
+            CacheLookup::Negative | CacheLookup::Miss => None,
+        }
+    }
+}
+
 /// Eviction policies
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EvictionPolicy {
@@ -152,6 +238,8 @@ pub enum EvictionPolicy {
     Random,
     /// Size-based eviction (remove largest items)
     SizeBased,
+    /// Adaptive replacement, balancing recency and frequency
+    Arc,
 }
 
 /// Cache builder for configuring cache instances
@@ -161,6 +249,7 @@ pub struct CacheBuilder {
     memory_backend: Option<Box<dyn CacheBackend>>,
     persistent_path: Option<alloc::string::String>,
     distributed_endpoints: alloc::vec::Vec<alloc::string::String>,
+    write_back: Option<WriteBackConfig>,
 }
 
 impl CacheBuilder {
@@ -171,6 +260,7 @@ impl CacheBuilder {
             memory_backend: None,
             persistent_path: None,
             distributed_endpoints: alloc::vec::Vec::new(),
+            write_back: None,
         }
     }
 
@@ -192,6 +282,33 @@ impl CacheBuilder {
         self
     }
 
+    /// Set how long keys a loader reported as not found stay negatively cached
+    pub fn negative_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.config.negative_ttl = ttl;
+        self
+    }
+
+    /// Set the maximum number of negatively cached keys
+    pub fn max_negative_entries(mut self, max_entries: usize) -> Self {
+        self.config.max_negative_entries = max_entries;
+        self
+    }
+
+    /// Recompute values loaded by `get_or_insert_with` shortly before they
+    /// expire, with probability scaled by `beta`; 1 is a good default
+    pub fn early_recompute(mut self, beta: f64) -> Self {
+        self.config.early_recompute_beta = Some(beta);
+        self
+    }
+
+    /// Promote keys read `promotion_threshold` times from a lower level
+    /// into the first level, and write entries evicted from the first level
+    /// down to the second
+    pub fn tiered(mut self, promotion_threshold: u32) -> Self {
+        self.config.promotion_threshold = Some(promotion_threshold.max(1));
+        self
+    }
+
     /// Add memory LRU cache
     #[cfg(feature = "lru")]
     pub fn with_memory_lru(mut self, capacity: usize) -> Self {
@@ -240,6 +357,13 @@ impl CacheBuilder {
         self
     }
 
+    /// Use the write-back strategy: writes land in the first level and
+    /// reach the lower levels when flushed
+    pub fn write_back(mut self, config: WriteBackConfig) -> Self {
+        self.write_back = Some(config);
+        self
+    }
+
     /// Build the cache instance
     pub async fn build(self) -> Result<CacheManager> {
         let mut manager = CacheManager::new(self.config);
@@ -254,7 +378,10 @@ impl CacheBuilder {
                 CacheLevel::Memory => {
                     #[cfg(feature = "lru")]
                     {
-                        let memory_cache = MemoryCache::new(manager.config.max_entries)?;
+                        let mut memory_cache = MemoryCache::with_policy(manager.config.max_entries, manager.config.eviction_policy)?;
+                        if manager.config.promotion_threshold.is_some() {
+                            memory_cache = memory_cache.keep_evicted();
+                        }
                         manager.backends.push(Box::new(memory_cache));
                     }
                 }
@@ -276,7 +403,10 @@ impl CacheBuilder {
         }
 
         // Initialize consistency manager
-        manager.consistency_manager = Some(ConsistencyManager::new(ConsistencyStrategy::WriteThrough));
+        manager.consistency_manager = Some(match self.write_back {
+            Some(config) => ConsistencyManager::with_write_back(config)?,
+            None => ConsistencyManager::new(ConsistencyStrategy::WriteThrough),
+        });
 
         Ok(manager)
     }
@@ -302,12 +432,26 @@ pub struct CacheManager {
     /// Current timestamp source
     #[cfg(feature = "std")]
     timestamp_fn: fn() -> u64,
+    /// Loads started by `get_or_insert_with`
+    #[cfg(feature = "std")]
+    loads: crate::loader::SingleFlight,
+    /// Keys known to be absent
+    #[cfg(feature = "std")]
+    negative: crate::loader::NegativeEntries,
+    /// Load cost and expiry of loaded values, for early recomputation
+    #[cfg(feature = "std")]
+    freshness: crate::loader::EarlyExpiry,
+    /// Hits served by a level below the first, per key, for promotion
+    #[cfg(feature = "std")]
+    lower_hits: std::sync::Mutex<alloc::collections::BTreeMap<CacheKey, u32>>,
 }
 
 impl CacheManager {
     /// Create a new cache manager
     pub fn new(config: CacheConfig) -> Self {
         Self {
+            #[cfg(feature = "std")]
+            negative: crate::loader::NegativeEntries::new(config.max_negative_entries),
             config,
             backends: alloc::vec::Vec::new(),
             consistency_manager: None,
@@ -317,19 +461,42 @@ impl CacheManager {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
+            #[cfg(feature = "std")]
+            loads: crate::loader::SingleFlight::default(),
+            #[cfg(feature = "std")]
+            freshness: crate::loader::EarlyExpiry::default(),
+            #[cfg(feature = "std")]
+            lower_hits: std::sync::Mutex::default(),
         }
     }
 
-    /// Get a value from cache
+    /// Get a value from cache.
+    ///
+    /// Negatively cached keys are reported as `None` without consulting
+    /// any backend; use [`CacheManager::lookup`] to tell them apart from
+    /// keys that were never seen.
     pub async fn get(&self, key: &CacheKey) -> Result<Option<CacheValue>> {
+        Ok(self.lookup(key).await?.into_value())
+    }
+
+    /// Look up a key, distinguishing cached values, keys known to be
+    /// absent, and keys that were never seen
+    pub async fn lookup(&self, key: &CacheKey) -> Result<CacheLookup> {
+        if let Some(known) = self.lookup_unflushed(key) {
+            return Ok(known);
+        }
+
         // Try backends in order (Memory -> Persistent -> Distributed)
-        for backend in &self.backends {
+        for (level, backend) in self.backends.iter().enumerate() {
             match backend.get(key).await {
                 Ok(Some(value)) => {
                     if self.config.enable_metrics {
                         // Record hit
                     }
-                    return Ok(Some(value));
+                    if level > 0 {
+                        self.record_lower_hit(level, key, &value).await?;
+                    }
+                    return Ok(CacheLookup::Hit(value));
                 }
                 Ok(None) => continue,
                 Err(e) => {
@@ -344,41 +511,375 @@ impl CacheManager {
             // Record miss
         }
 
-        Ok(None)
+        Ok(CacheLookup::Miss)
+    }
+
+    /// Result for `key` decided without consulting any backend: negatively
+    /// cached keys and unflushed write-back entries
+    fn lookup_unflushed(&self, key: &CacheKey) -> Option<CacheLookup> {
+        #[cfg(feature = "std")]
+        if self.is_negative(key) {
+            return Some(CacheLookup::Negative);
+        }
+
+        // Unflushed writes are newer than anything in the lower levels
+        match self.write_back_manager()?.dirty_entry(key)? {
+            DirtyOp::Put(value) => Some(CacheLookup::Hit(value)),
+            DirtyOp::Delete => Some(CacheLookup::Miss),
+        }
+    }
+
+    /// Get several values at once, in the order of `keys`.
+    ///
+    /// Each level is asked once for all keys the levels above it missed,
+    /// rather than once per key.
+    pub async fn get_many(&self, keys: &[&[u8]]) -> Result<alloc::vec::Vec<Option<alloc::vec::Vec<u8>>>> {
+        let mut values = alloc::vec![None; keys.len()];
+        let mut missing = alloc::vec::Vec::new();
+        for (index, key) in keys.iter().enumerate() {
+            let key = key.to_vec();
+            match self.lookup_unflushed(&key) {
+                Some(known) => values[index] = known.into_value(),
+                None => missing.push((index, key)),
+            }
+        }
+
+        let mut lower_hits = alloc::vec::Vec::new();
+        for (level, backend) in self.backends.iter().enumerate() {
+            if missing.is_empty() {
+                break;
+            }
+
+            let batch: alloc::vec::Vec<CacheKey> = missing.iter().map(|(_, key)| key.clone()).collect();
+            match backend.get_many(&batch).await {
+                Ok(found) => {
+                    let mut found = found.into_iter();
+                    missing.retain(|(index, key)| match found.next().flatten() {
+                        Some(value) => {
+                            if level > 0 {
+                                lower_hits.push((level, key.clone(), value.clone()));
+                            }
+                            values[*index] = Some(value);
+                            false
+                        }
+                        None => true,
+                    });
+                }
+                // A failing level is skipped like in `lookup`; the next
+                // level may still have the values
+                Err(_) => {}
+            }
+        }
+
+        for (level, key, value) in lower_hits {
+            self.record_lower_hit(level, &key, &value).await?;
+        }
+
+        Ok(values)
+    }
+
+    /// Count a hit served by `level`, below the first, promoting `key` into
+    /// the first level once it reaches the promotion threshold. A promoted
+    /// entry keeps the lifetime it has left in `level`.
+    async fn record_lower_hit(&self, level: usize, key: &CacheKey, value: &CacheValue) -> Result<()> {
+        #[cfg(feature = "std")]
+        {
+            let Some(threshold) = self.config.promotion_threshold else {
+                return Ok(());
+            };
+            let promote = {
+                let mut lower_hits = self.lower_hits.lock().unwrap_or_else(|e| e.into_inner());
+                // Halve every count rather than grow without bound, so keys
+                // that were hot long ago stop counting towards promotion
+                if !lower_hits.contains_key(key) && lower_hits.len() >= self.config.max_entries {
+                    lower_hits.retain(|_, hits| {
+                        *hits /= 2;
+                        *hits > 0
+                    });
+                }
+                let hits = lower_hits.entry(key.clone()).or_insert(0);
+                *hits += 1;
+                let promote = *hits >= threshold;
+                if promote {
+                    lower_hits.remove(key);
+                }
+                promote
+            };
+            if promote {
+                if let Some(first) = self.backends.first() {
+                    let ttl = self.backends[level].remaining_ttl(key).await?.map(EntryTtl::Fixed);
+                    put_to(first.as_ref(), key.clone(), value.clone(), ttl).await?;
+                    self.demote_evicted().await?;
+                }
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = (level, key, value);
+        Ok(())
+    }
+
+    /// Write entries evicted from the first level down to the second,
+    /// keeping the lifetime they had left. Called after every write to the
+    /// first level, so evicted entries are never held back.
+    async fn demote_evicted(&self) -> Result<()> {
+        let Some(first) = self.backends.first() else {
+            return Ok(());
+        };
+        let evicted = first.take_evicted();
+        // Without a second level there is nowhere to keep them
+        let Some(second) = self.backends.get(1) else {
+            return Ok(());
+        };
+
+        let mut without_ttl = alloc::vec::Vec::new();
+        for (key, value, ttl) in evicted {
+            match ttl {
+                Some(ttl) => second.put_with_ttl(key, value, ttl).await?,
+                None => without_ttl.push((key, value)),
+            }
+        }
+        if !without_ttl.is_empty() {
+            second.put_many(without_ttl).await?;
+        }
+        Ok(())
+    }
+
+    /// Put several values at once.
+    ///
+    /// Each level receives the whole batch in a single call, so backends
+    /// can store it under one lock acquisition or in one round-trip.
+    pub async fn put_many(&self, entries: alloc::vec::Vec<(CacheKey, CacheValue)>) -> Result<()> {
+        #[cfg(feature = "std")]
+        {
+            for (key, _) in &entries {
+                self.negative.forget(key);
+                self.freshness.forget(key);
+            }
+        }
+
+        if let Some(ref consistency) = self.consistency_manager {
+            for (key, value) in &entries {
+                consistency.before_write(key, value).await?;
+            }
+        }
+
+        let mut flush_due = false;
+        match self.write_back_manager() {
+            Some(write_back) => {
+                for (key, value) in &entries {
+                    flush_due |= write_back.buffer(key.clone(), DirtyOp::Put(value.clone()))?;
+                }
+                if let Some(backend) = self.backends.first() {
+                    backend.put_many(entries.clone()).await?;
+                }
+            }
+            None => {
+                for backend in &self.backends {
+                    backend.put_many(entries.clone()).await?;
+                }
+            }
+        }
+        self.demote_evicted().await?;
+
+        if let Some(ref consistency) = self.consistency_manager {
+            for (key, value) in &entries {
+                consistency.after_write(key, value).await?;
+            }
+        }
+
+        if flush_due {
+            self.flush().await?;
+        }
+
+        Ok(())
     }
 
     /// Put a value in cache
     pub async fn put(&self, key: CacheKey, value: CacheValue) -> Result<()> {
+        self.put_entry(key, value, None).await
+    }
+
+    /// Put a value expiring per `ttl` instead of the default TTL: a
+    /// [`Duration`] for a fixed lifetime, or a [`SlidingTtl`] for an entry
+    /// that lives as long as it is read within the window.
+    ///
+    /// Levels that cannot track reads keep a sliding entry for one window.
+    /// With the write-back strategy, lower levels receive the entry on
+    /// flush under the default TTL.
+    pub async fn put_with_ttl(&self, key: CacheKey, value: CacheValue, ttl: impl Into<EntryTtl>) -> Result<()> {
+        self.put_entry(key, value, Some(ttl.into())).await
+    }
+
+    /// Put a value with `ttl`, or the default TTL if `None`
+    async fn put_entry(&self, key: CacheKey, value: CacheValue, ttl: Option<EntryTtl>) -> Result<()> {
+        #[cfg(feature = "std")]
+        {
+            self.negative.forget(&key);
+            self.freshness.forget(&key);
+        }
+
         // Apply consistency strategy
         if let Some(ref consistency) = self.consistency_manager {
             consistency.before_write(&key, &value).await?;
         }
 
-        // Write to all backends
-        for backend in &self.backends {
-            backend.put(key.clone(), value.clone()).await?;
+        let mut flush_due = false;
+        match self.write_back_manager() {
+            // Only the first level takes the write now; the rest get it on flush
+            Some(write_back) => {
+                flush_due = write_back.buffer(key.clone(), DirtyOp::Put(value.clone()))?;
+                if let Some(backend) = self.backends.first() {
+                    put_to(backend.as_ref(), key.clone(), value.clone(), ttl).await?;
+                }
+            }
+            None => {
+                // Write to all backends
+                for backend in &self.backends {
+                    put_to(backend.as_ref(), key.clone(), value.clone(), ttl).await?;
+                }
+            }
         }
+        self.demote_evicted().await?;
 
         if let Some(ref consistency) = self.consistency_manager {
             consistency.after_write(&key, &value).await?;
         }
 
+        if flush_due {
+            self.flush().await?;
+        }
+
         Ok(())
     }
 
+    /// Record that `key` is absent from the backing store for `ttl`.
+    ///
+    /// Until the entry expires or the key is written, lookups report
+    /// [`CacheLookup::Negative`] without consulting any backend. At most
+    /// `max_negative_entries` keys are kept; expired entries are swept
+    /// here, and if none have expired the one closest to expiry is dropped.
+    #[cfg(feature = "std")]
+    pub fn put_negative(&self, key: CacheKey, ttl: Duration) {
+        self.negative.insert(key, ttl);
+    }
+
+    /// Whether `key` is negatively cached, forgetting the entry once expired
+    #[cfg(feature = "std")]
+    fn is_negative(&self, key: &CacheKey) -> bool {
+        self.negative.contains(key)
+    }
+
+    /// Get a value, loading and caching it on a miss.
+    ///
+    /// Concurrent calls for the same missing key share a single load: the
+    /// first caller runs `loader` and the others await its result. A loader
+    /// error is returned to every waiting caller. When the loader reports
+    /// [`CacheError::KeyNotFound`] the key is negatively cached for the
+    /// configured `negative_ttl`; other errors are not cached.
+    ///
+    /// With `early_recompute_beta` set, a hit on a value loaded here may
+    /// instead recompute it ahead of its expiry (XFetch). The chance grows
+    /// as the `default_ttl` runs out and with how long the last load took.
+    /// Only one caller recomputes at a time; others keep getting the cached
+    /// value, as does the recomputing caller if its load fails.
+    #[cfg(feature = "std")]
+    pub async fn get_or_insert_with<F, Fut>(&self, key: CacheKey, loader: F) -> Result<CacheValue>
+    where
+        F: FnOnce() -> Fut,
+        Fut: core::future::Future<Output = Result<CacheValue>>,
+    {
+        let lookup = self.lookup(&key).await?;
+        if let (CacheLookup::Hit(cached), Some(beta)) = (&lookup, self.config.early_recompute_beta) {
+            if self.freshness.is_due(&key, beta) {
+                return Ok(match self.loads.join(&key) {
+                    crate::loader::Join::Follower(_) => cached.clone(),
+                    crate::loader::Join::Leader(leader) => {
+                        let result = self.load(&key, loader).await;
+                        leader.finish(result).unwrap_or_else(|_| cached.clone())
+                    }
+                });
+            }
+        }
+        if let Some(known) = known_result(lookup, &key) {
+            return known;
+        }
+
+        match self.loads.join(&key) {
+            crate::loader::Join::Follower(flight) => flight.wait().await,
+            crate::loader::Join::Leader(leader) => {
+                let result = async {
+                    // A load that landed since the lookup above already filled the cache
+                    if let Some(known) = known_result(self.lookup(&key).await?, &key) {
+                        return known;
+                    }
+                    self.load(&key, loader).await
+                }
+                .await;
+                leader.finish(result)
+            }
+        }
+    }
+
+    /// Run a loader and cache its outcome
+    #[cfg(feature = "std")]
+    async fn load<F, Fut>(&self, key: &CacheKey, loader: F) -> Result<CacheValue>
+    where
+        F: FnOnce() -> Fut,
+        Fut: core::future::Future<Output = Result<CacheValue>>,
+    {
+        let started = std::time::Instant::now();
+        match loader().await {
+            Ok(value) => {
+                let delta = started.elapsed();
+                self.put(key.clone(), value.clone()).await?;
+                if let (Some(_), Some(ttl)) = (self.config.early_recompute_beta, self.config.default_ttl) {
+                    self.freshness.record(key.clone(), delta, Duration::from_secs(ttl));
+                }
+                Ok(value)
+            }
+            Err(error @ CacheError::KeyNotFound { .. }) => {
+                if let Some(ttl) = self.config.negative_ttl {
+                    self.put_negative(key.clone(), ttl);
+                }
+                Err(error)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     /// Delete a value from cache
     pub async fn delete(&self, key: &CacheKey) -> Result<bool> {
         let mut deleted = false;
+        #[cfg(feature = "std")]
+        {
+            self.freshness.forget(key);
+            self.lower_hits.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+        }
 
         // Apply consistency strategy
         if let Some(ref consistency) = self.consistency_manager {
             consistency.before_delete(key).await?;
         }
 
-        // Delete from all backends
-        for backend in &self.backends {
-            if backend.delete(key).await? {
-                deleted = true;
+        let mut flush_due = false;
+        match self.write_back_manager() {
+            Some(write_back) => {
+                deleted = match write_back.dirty_entry(key) {
+                    Some(op) => op != DirtyOp::Delete,
+                    None => self.contains(key).await?,
+                };
+                flush_due = write_back.buffer(key.clone(), DirtyOp::Delete)?;
+                if let Some(backend) = self.backends.first() {
+                    backend.delete(key).await?;
+                }
+            }
+            None => {
+                // Delete from all backends
+                for backend in &self.backends {
+                    if backend.delete(key).await? {
+                        deleted = true;
+                    }
+                }
             }
         }
 
@@ -386,11 +887,86 @@ impl CacheManager {
             consistency.after_delete(key).await?;
         }
 
+        if flush_due {
+            self.flush().await?;
+        }
+
         Ok(deleted)
     }
 
+    /// Consistency manager, if it uses the write-back strategy
+    fn write_back_manager(&self) -> Option<&ConsistencyManager> {
+        self.consistency_manager
+            .as_ref()
+            .filter(|consistency| consistency.strategy() == ConsistencyStrategy::WriteBack)
+    }
+
+    /// Write buffered write-back entries to the lower cache levels.
+    ///
+    /// Returns the number of entries flushed. If a level rejects an entry
+    /// the flush stops with that error, and the entry and those after it
+    /// stay dirty for the next flush. Does nothing unless the write-back
+    /// strategy is in use.
+    pub async fn flush(&self) -> Result<usize> {
+        let Some(write_back) = self.write_back_manager() else {
+            return Ok(0);
+        };
+
+        let mut flushed = alloc::vec::Vec::new();
+        let mut failure = None;
+        'entries: for (key, op, version) in write_back.dirty_entries() {
+            for backend in self.backends.iter().skip(1) {
+                let written = match &op {
+                    DirtyOp::Put(value) => backend.put(key.clone(), value.clone()).await,
+                    DirtyOp::Delete => backend.delete(&key).await.map(|_| ()),
+                };
+                if let Err(e) = written {
+                    failure = Some(e);
+                    break 'entries;
+                }
+            }
+            flushed.push((key, version));
+        }
+
+        let count = write_back.mark_flushed(&flushed)?;
+        failure.map_or(Ok(count), Err)
+    }
+
+    /// Flush buffered write-back entries every `flush_interval`.
+    ///
+    /// Runs until dropped, so drive it alongside the cache, e.g. on a
+    /// `LocalSet`. A failed flush leaves its entries dirty for the next tick.
+    #[cfg(feature = "async")]
+    pub async fn flush_periodically(&self) {
+        let Some(write_back) = self.write_back_manager() else {
+            return;
+        };
+
+        let mut ticker = tokio::time::interval(write_back.flush_interval());
+        loop {
+            ticker.tick().await;
+            let _ = self.flush().await;
+        }
+    }
+
+    /// Get dirty entry tracking statistics for the write-back strategy
+    pub fn dirty_stats(&self) -> DirtyTrackingStats {
+        self.write_back_manager().map(ConsistencyManager::dirty_stats).unwrap_or_default()
+    }
+
     /// Clear all cache entries
     pub async fn clear(&self) -> Result<()> {
+        #[cfg(feature = "std")]
+        {
+            self.negative.clear();
+            self.freshness.clear();
+            self.lower_hits.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+
+        if let Some(write_back) = self.write_back_manager() {
+            write_back.discard_dirty()?;
+        }
+
         for backend in &self.backends {
             backend.clear().await?;
         }
@@ -404,6 +980,10 @@ impl CacheManager {
 
     /// Check if cache contains key
     pub async fn contains(&self, key: &CacheKey) -> Result<bool> {
+        if let Some(op) = self.write_back_manager().and_then(|write_back| write_back.dirty_entry(key)) {
+            return Ok(op != DirtyOp::Delete);
+        }
+
         for backend in &self.backends {
             if backend.contains(key).await? {
                 return Ok(true);
@@ -421,19 +1001,80 @@ impl CacheManager {
             all_keys.extend(keys);
         }
 
+        if let Some(write_back) = self.write_back_manager() {
+            for (key, op, _) in write_back.dirty_entries() {
+                match op {
+                    DirtyOp::Put(_) => all_keys.insert(key),
+                    DirtyOp::Delete => all_keys.remove(&key),
+                };
+            }
+        }
+
         Ok(all_keys.into_iter().collect())
     }
 }
 
+/// Put a value in `backend` with `ttl`, or its default TTL if `None`
+async fn put_to(backend: &dyn CacheBackend, key: CacheKey, value: CacheValue, ttl: Option<EntryTtl>) -> Result<()> {
+    match ttl {
+        Some(ttl) => backend.put_with_ttl(key, value, ttl).await,
+        None => backend.put(key, value).await,
+    }
+}
+
+/// The result a lookup settles without loading, if any
+#[cfg(feature = "std")]
+fn known_result(lookup: CacheLookup, key: &CacheKey) -> Option<Result<CacheValue>> {
+    match lookup {
+        CacheLookup::Hit(value) => Some(Ok(value)),
+        CacheLookup::Negative => Some(Err(CacheError::KeyNotFound {
+            key: alloc::string::String::from_utf8_lossy(key).into_owned(),
+        })),
+        CacheLookup::Miss => None,
+    }
+}
+
 /// Cache backend trait
 #[async_trait::async_trait(?Send)]
 pub trait CacheBackend {
     /// Get a value from this backend
     async fn get(&self, key: &CacheKey) -> Result<Option<CacheValue>>;
 
+    /// Get several values, in the order of `keys`.
+    ///
+    /// The default looks keys up one at a time; backends override it to
+    /// serve the batch under one lock acquisition or in one round-trip.
+    async fn get_many(&self, keys: &[CacheKey]) -> Result<alloc::vec::Vec<Option<CacheValue>>> {
+        let mut values = alloc::vec::Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(self.get(key).await?);
+        }
+        Ok(values)
+    }
+
     /// Put a value in this backend
     async fn put(&self, key: CacheKey, value: CacheValue) -> Result<()>;
 
+    /// Put a value expiring per `ttl` instead of the backend's default TTL.
+    ///
+    /// Backends that cannot track reads store a sliding entry for one
+    /// window, so it never outlives its TTL. The default rejects per-entry
+    /// TTLs.
+    async fn put_with_ttl(&self, key: CacheKey, value: CacheValue, ttl: EntryTtl) -> Result<()> {
+        let _ = (key, value, ttl);
+        Err(CacheError::TtlError {
+            details: alloc::format!("{} backend does not support per-entry TTLs", self.name()),
+        })
+    }
+
+    /// Put several values, with the same batching as [`CacheBackend::get_many`]
+    async fn put_many(&self, entries: alloc::vec::Vec<(CacheKey, CacheValue)>) -> Result<()> {
+        for (key, value) in entries {
+            self.put(key, value).await?;
+        }
+        Ok(())
+    }
+
     /// Delete a value from this backend
     async fn delete(&self, key: &CacheKey) -> Result<bool>;
 
@@ -449,6 +1090,21 @@ pub trait CacheBackend {
     /// Get backend statistics
     fn stats(&self) -> Result<CacheStats>;
 
+    /// Lifetime `key` has left, or `None` if it never expires, is absent,
+    /// or the backend cannot tell. The default cannot tell.
+    async fn remaining_ttl(&self, key: &CacheKey) -> Result<Option<Duration>> {
+        let _ = key;
+        Ok(None)
+    }
+
+    /// Take the entries evicted for capacity since the last call, for
+    /// writing down to a lower level, each with the TTL it has left or
+    /// `None` if it never expires. Entries that expired in the meantime
+    /// are dropped. The default keeps none.
+    fn take_evicted(&self) -> alloc::vec::Vec<(CacheKey, CacheValue, Option<EntryTtl>)> {
+        alloc::vec::Vec::new()
+    }
+
     /// Get backend name
     fn name(&self) -> &'static str;
 }
@@ -457,6 +1113,14 @@ pub trait CacheBackend {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_entry_ttl_whole_secs_rounds_up() {
+        assert_eq!(EntryTtl::Fixed(Duration::from_millis(500)).whole_secs(), 1);
+        assert_eq!(EntryTtl::Sliding(Duration::from_secs(2)).whole_secs(), 2);
+        assert_eq!(EntryTtl::Fixed(Duration::from_millis(2001)).whole_secs(), 3);
+        assert_eq!(EntryTtl::Fixed(Duration::MAX).whole_secs(), u64::MAX);
+    }
+
     #[test]
     fn test_cache_config() {
         let config = CacheConfig::default();
@@ -494,4 +1158,387 @@ mod tests {
         assert!(CacheLevel::Memory < CacheLevel::Persistent);
         assert!(CacheLevel::Persistent < CacheLevel::Distributed);
     }
+
+    /// Backend keeping entries in a map and counting reads
+    #[derive(Debug, Default)]
+    struct MapBackend {
+        entries: std::sync::Arc<std::sync::Mutex<alloc::collections::BTreeMap<CacheKey, CacheValue>>>,
+        gets: std::sync::Arc<core::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait(?Send)]
+    impl CacheBackend for MapBackend {
+        async fn get(&self, key: &CacheKey) -> Result<Option<CacheValue>> {
+            self.gets.fetch_add(1, core::sync::atomic::Ordering::SeqCst);
+            Ok(self.entries.lock().unwrap().get(key).cloned())
+        }
+
+        async fn put(&self, key: CacheKey, value: CacheValue) -> Result<()> {
+            self.entries.lock().unwrap().insert(key, value);
+            Ok(())
+        }
+
+        // Entries never expire, so any TTL outlives the test
+        async fn put_with_ttl(&self, key: CacheKey, value: CacheValue, _ttl: EntryTtl) -> Result<()> {
+            self.put(key, value).await
+        }
+
+        async fn delete(&self, key: &CacheKey) -> Result<bool> {
+            Ok(self.entries.lock().unwrap().remove(key).is_some())
+        }
+
+        async fn clear(&self) -> Result<()> {
+            self.entries.lock().unwrap().clear();
+            Ok(())
+        }
+
+        async fn contains(&self, key: &CacheKey) -> Result<bool> {
+            Ok(self.entries.lock().unwrap().contains_key(key))
+        }
+
+        async fn keys(&self) -> Result<alloc::vec::Vec<CacheKey>> {
+            Ok(self.entries.lock().unwrap().keys().cloned().collect())
+        }
+
+        fn stats(&self) -> Result<CacheStats> {
+            Ok(CacheStats::default())
+        }
+
+        fn name(&self) -> &'static str {
+            "map"
+        }
+    }
+
+    /// Manager over a single map backend, with the backend's read counter
+    fn map_backed_manager() -> (CacheManager, std::sync::Arc<core::sync::atomic::AtomicUsize>) {
+        let backend = MapBackend::default();
+        let gets = backend.gets.clone();
+        let mut manager = CacheManager::new(CacheConfig::default());
+        manager.backends.push(Box::new(backend));
+        (manager, gets)
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_with_loads_once_under_concurrency() {
+        let manager = std::rc::Rc::new(map_backed_manager().0);
+        let loads = std::rc::Rc::new(core::cell::Cell::new(0));
+        let local = tokio::task::LocalSet::new();
+
+        let callers: alloc::vec::Vec<_> = (0..32)
+            .map(|_| {
+                let manager = manager.clone();
+                let loads = loads.clone();
+                local.spawn_local(async move {
+                    manager
+                        .get_or_insert_with(b"user:123".to_vec(), || async move {
+                            loads.set(loads.get() + 1);
+                            tokio::time::sleep(Duration::from_millis(20)).await;
+                            Ok(b"alice".to_vec())
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        local
+            .run_until(async {
+                for caller in callers {
+                    assert_eq!(caller.await.unwrap().unwrap(), b"alice");
+                }
+            })
+            .await;
+
+        assert_eq!(loads.get(), 1);
+        assert_eq!(manager.get(&b"user:123".to_vec()).await.unwrap(), Some(b"alice".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_with_does_not_cache_errors() {
+        let (manager, _) = map_backed_manager();
+        let key = b"user:404".to_vec();
+
+        let failed = manager
+            .get_or_insert_with(key.clone(), || async {
+                Err(CacheError::BackendError {
+                    backend: "db",
+                    details: "unavailable".into(),
+                })
+            })
+            .await;
+        assert!(matches!(failed, Err(CacheError::BackendError { backend: "db", .. })));
+        assert!(!manager.contains(&key).await.unwrap());
+
+        let value = manager.get_or_insert_with(key, || async { Ok(b"bob".to_vec()) }).await;
+        assert_eq!(value.unwrap(), b"bob");
+    }
+
+    #[tokio::test]
+    async fn test_negative_entry_skips_backend_until_expired() {
+        let (manager, gets) = map_backed_manager();
+        let key = b"user:404".to_vec();
+        let reads = || gets.load(core::sync::atomic::Ordering::SeqCst);
+
+        assert_eq!(manager.lookup(&key).await.unwrap(), CacheLookup::Miss);
+        assert_eq!(reads(), 1);
+
+        manager.put_negative(key.clone(), Duration::from_millis(50));
+        assert_eq!(manager.lookup(&key).await.unwrap(), CacheLookup::Negative);
+        assert_eq!(manager.get(&key).await.unwrap(), None);
+        assert_eq!(reads(), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(manager.lookup(&key).await.unwrap(), CacheLookup::Miss);
+        assert_eq!(reads(), 2);
+
+        // Writing a key clears its negative entry
+        manager.put_negative(key.clone(), Duration::from_secs(60));
+        manager.put(key.clone(), b"found".to_vec()).await.unwrap();
+        assert_eq!(manager.lookup(&key).await.unwrap(), CacheLookup::Hit(b"found".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_with_caches_not_found() {
+        let (manager, _) = map_backed_manager();
+        let key = b"user:404".to_vec();
+        let loads = core::cell::Cell::new(0);
+        let missing = || async {
+            loads.set(loads.get() + 1);
+            Err(CacheError::KeyNotFound { key: "user:404".into() })
+        };
+
+        for _ in 0..3 {
+            let result = manager.get_or_insert_with(key.clone(), missing).await;
+            assert!(matches!(result, Err(CacheError::KeyNotFound { .. })));
+        }
+        assert_eq!(loads.get(), 1);
+        assert_eq!(manager.lookup(&key).await.unwrap(), CacheLookup::Negative);
+    }
+
+    #[tokio::test]
+    async fn test_early_recompute_of_hot_key_by_few_readers() {
+        let (mut manager, _) = map_backed_manager();
+        manager.config.early_recompute_beta = Some(1.0);
+        let manager = std::rc::Rc::new(manager);
+        let key = b"hot".to_vec();
+
+        manager.get_or_insert_with(key.clone(), || async { Ok(b"v1".to_vec()) }).await.unwrap();
+        // A 100ms load expiring in 250ms: each read is due with probability
+        // exp(-2.5), about 8%
+        manager.freshness.record(key.clone(), Duration::from_millis(100), Duration::from_millis(250));
+
+        let loads = std::rc::Rc::new(core::cell::Cell::new(0));
+        let local = tokio::task::LocalSet::new();
+        let readers: alloc::vec::Vec<_> = (0..200)
+            .map(|_| {
+                let (manager, loads, key) = (manager.clone(), loads.clone(), key.clone());
+                local.spawn_local(async move {
+                    manager
+                        .get_or_insert_with(key, || async move {
+                            loads.set(loads.get() + 1);
+                            tokio::time::sleep(Duration::from_millis(20)).await;
+                            Ok(b"v2".to_vec())
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        let stale = local
+            .run_until(async {
+                let mut stale = 0;
+                for reader in readers {
+                    let value = reader.await.unwrap().unwrap();
+                    assert!(value == b"v1" || value == b"v2");
+                    stale += usize::from(value == b"v1");
+                }
+                stale
+            })
+            .await;
+
+        // One reader refreshed the key before expiry; the rest were served
+        // from the cache rather than all missing together at expiry
+        assert_eq!(loads.get(), 1);
+        assert!(stale >= 190, "only {} of 200 reads served from cache", stale);
+        assert_eq!(manager.get(&key).await.unwrap(), Some(b"v2".to_vec()));
+    }
+
+    /// Fresh write-ahead log path for a test
+    fn wal_path(name: &str) -> alloc::string::String {
+        let path = std::env::temp_dir().join(alloc::format!("frys-cache-{}-{}.wal", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Write-back manager over a fresh memory level and a persistent level
+    /// sharing `persistent`'s entries
+    fn write_back_manager(persistent: &MapBackend, config: WriteBackConfig) -> CacheManager {
+        let mut manager = CacheManager::new(CacheConfig::default());
+        manager.backends.push(Box::new(MapBackend::default()));
+        manager.backends.push(Box::new(MapBackend {
+            entries: persistent.entries.clone(),
+            ..MapBackend::default()
+        }));
+        manager.consistency_manager = Some(ConsistencyManager::with_write_back(config).unwrap());
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_write_back_flush_reaches_persistent_level() {
+        let persistent = MapBackend::default();
+        let path = wal_path("flush");
+        let manager = write_back_manager(
+            &persistent,
+            WriteBackConfig {
+                flush_interval: Duration::from_secs(3600),
+                max_dirty_entries: 3,
+                wal_path: Some(path.clone()),
+                sync_writes: false,
+            },
+        );
+
+        manager.put(b"a".to_vec(), b"1".to_vec()).await.unwrap();
+        manager.put(b"b".to_vec(), b"2".to_vec()).await.unwrap();
+        assert!(persistent.entries.lock().unwrap().is_empty());
+        assert_eq!(manager.get(&b"a".to_vec()).await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(manager.dirty_stats().dirty_entries, 2);
+
+        assert_eq!(manager.flush().await.unwrap(), 2);
+        assert_eq!(persistent.entries.lock().unwrap().get(&b"a".to_vec()), Some(&b"1".to_vec()));
+        assert_eq!(persistent.entries.lock().unwrap().len(), 2);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+
+        // Reaching max_dirty_entries flushes without being asked
+        for key in [b"c", b"d", b"e"] {
+            manager.put(key.to_vec(), b"3".to_vec()).await.unwrap();
+        }
+        let stats = manager.dirty_stats();
+        assert_eq!((stats.dirty_entries, stats.flushes, stats.flushed_entries), (0, 2, 5));
+        assert_eq!(persistent.entries.lock().unwrap().len(), 5);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_write_back_log_recovers_unflushed_writes() {
+        let persistent = MapBackend::default();
+        persistent.entries.lock().unwrap().insert(b"gone".to_vec(), b"old".to_vec());
+        let path = wal_path("crash");
+        let config = WriteBackConfig {
+            wal_path: Some(path.clone()),
+            ..WriteBackConfig::default()
+        };
+
+        let manager = write_back_manager(&persistent, config.clone());
+        manager.put(b"a".to_vec(), b"1".to_vec()).await.unwrap();
+        manager.put(b"a".to_vec(), b"2".to_vec()).await.unwrap();
+        assert!(manager.delete(&b"gone".to_vec()).await.unwrap());
+        // Crash before any flush; the memory level is lost with the process
+        drop(manager);
+
+        let manager = write_back_manager(&persistent, config);
+        assert_eq!(manager.dirty_stats().recovered_entries, 2);
+        assert_eq!(manager.get(&b"a".to_vec()).await.unwrap(), Some(b"2".to_vec()));
+        assert_eq!(manager.get(&b"gone".to_vec()).await.unwrap(), None);
+
+        assert_eq!(manager.flush().await.unwrap(), 2);
+        let entries = persistent.entries.lock().unwrap().clone();
+        assert_eq!(entries, alloc::collections::BTreeMap::from([(b"a".to_vec(), b"2".to_vec())]));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_get_many_asks_each_level_once_in_key_order() {
+        let upper = MapBackend::default();
+        let lower = MapBackend::default();
+        let (upper_gets, lower_gets) = (upper.gets.clone(), lower.gets.clone());
+        upper.entries.lock().unwrap().insert(b"a".to_vec(), b"1".to_vec());
+        lower.entries.lock().unwrap().insert(b"b".to_vec(), b"2".to_vec());
+
+        let mut manager = CacheManager::new(CacheConfig::default());
+        manager.backends.push(Box::new(upper));
+        manager.backends.push(Box::new(lower));
+        manager.put_negative(b"neg".to_vec(), Duration::from_secs(60));
+
+        let values = manager.get_many(&[b"b", b"neg", b"a", b"c"]).await.unwrap();
+        assert_eq!(values, alloc::vec![Some(b"2".to_vec()), None, Some(b"1".to_vec()), None]);
+        // The negative key skips both levels and the lower level only sees upper misses
+        assert_eq!(upper_gets.load(core::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(lower_gets.load(core::sync::atomic::Ordering::SeqCst), 2);
+
+        manager
+            .put_many(alloc::vec![(b"c".to_vec(), b"3".to_vec()), (b"neg".to_vec(), b"4".to_vec())])
+            .await
+            .unwrap();
+        let values = manager.get_many(&[b"neg", b"c"]).await.unwrap();
+        assert_eq!(values, alloc::vec![Some(b"4".to_vec()), Some(b"3".to_vec())]);
+    }
+
+    #[cfg(feature = "lru")]
+    #[tokio::test]
+    async fn test_tiering_promotes_hot_keys_and_demotes_evicted_ones() {
+        let persistent = MapBackend::default();
+        let persistent_gets = persistent.gets.clone();
+        let entries = persistent.entries.clone();
+        entries.lock().unwrap().insert(b"hot".to_vec(), b"h".to_vec());
+
+        let mut manager = CacheManager::new(CacheConfig {
+            promotion_threshold: Some(3),
+            ..CacheConfig::default()
+        });
+        manager.backends.push(Box::new(MemoryCache::new(2).unwrap().keep_evicted()));
+        manager.backends.push(Box::new(persistent));
+        // Write-back, so puts only reach the disk when evicted from memory
+        manager.consistency_manager = Some(
+            ConsistencyManager::with_write_back(WriteBackConfig {
+                flush_interval: Duration::from_secs(3600),
+                max_dirty_entries: 100,
+                wal_path: None,
+                sync_writes: false,
+            })
+            .unwrap(),
+        );
+
+        for key in [b"a", b"b", b"c"] {
+            manager.put(key.to_vec(), b"1".to_vec()).await.unwrap();
+        }
+        // "a" was evicted from memory and written down rather than dropped
+        assert_eq!(entries.lock().unwrap().get(&b"a".to_vec()), Some(&b"1".to_vec()));
+        assert!(!entries.lock().unwrap().contains_key(&b"b".to_vec()));
+
+        for _ in 0..3 {
+            assert_eq!(manager.get(&b"hot".to_vec()).await.unwrap(), Some(b"h".to_vec()));
+        }
+        assert_eq!(persistent_gets.load(core::sync::atomic::Ordering::SeqCst), 3);
+        // Promoted on the third read, so memory serves it from now on
+        assert_eq!(manager.get(&b"hot".to_vec()).await.unwrap(), Some(b"h".to_vec()));
+        assert_eq!(persistent_gets.load(core::sync::atomic::Ordering::SeqCst), 3);
+        assert!(manager.backends[0].contains(&b"hot".to_vec()).await.unwrap());
+
+        // Promotion evicted "b", which is now on disk too
+        assert_eq!(entries.lock().unwrap().get(&b"b".to_vec()), Some(&b"1".to_vec()));
+        assert!(!manager.backends[0].contains(&b"b".to_vec()).await.unwrap());
+        assert_eq!(manager.get(&b"b".to_vec()).await.unwrap(), Some(b"1".to_vec()));
+    }
+    #[cfg(feature = "lru")]
+    #[tokio::test]
+    async fn test_tiering_keeps_entry_ttls_between_levels() {
+        let mut manager = CacheManager::new(CacheConfig {
+            promotion_threshold: Some(1),
+            ..CacheConfig::default()
+        });
+        manager.backends.push(Box::new(MemoryCache::new(1).unwrap().keep_evicted()));
+        manager.backends.push(Box::new(MemoryCache::new(10).unwrap()));
+        let five_secs = Duration::from_secs(5);
+
+        // Demoted with the TTL it had left, not the lower level's default
+        manager.backends[0].put_with_ttl(b"short".to_vec(), b"1".to_vec(), EntryTtl::Fixed(five_secs)).await.unwrap();
+        manager.put(b"other".to_vec(), b"2".to_vec()).await.unwrap();
+        let left = manager.backends[1].remaining_ttl(&b"short".to_vec()).await.unwrap();
+        assert!(left.is_some_and(|left| left <= five_secs));
+
+        // Promoted back with the TTL it has left in the lower level
+        manager.backends[1].delete(&b"other".to_vec()).await.unwrap();
+        assert_eq!(manager.get(&b"short".to_vec()).await.unwrap(), Some(b"1".to_vec()));
+        let left = manager.backends[0].remaining_ttl(&b"short".to_vec()).await.unwrap();
+        assert!(left.is_some_and(|left| left <= five_secs));
+    }
 }
@@ -96,6 +96,13 @@ pub use preloader::*;
 mod error;
 pub use error::*;
 
+// Single-flight loading
+#[cfg(feature = "std")]
+mod loader;
+
+// Write-back log
+mod wal;
+
 // Type aliases
 #[cfg(feature = "std")]
 pub type Result<T> = std::result::Result<T, CacheError>;
@@ -105,9 +112,14 @@ pub type Result<T> = core::result::Result<T, CacheError>;
 // Constants
 pub const DEFAULT_CACHE_SIZE: usize = 1000;
 pub const DEFAULT_TTL_SECS: u64 = 3600; // 1 hour
+pub const DEFAULT_NEGATIVE_TTL_SECS: u64 = 60;
+pub const DEFAULT_MAX_NEGATIVE_ENTRIES: usize = 10_000;
 pub const MAX_KEY_SIZE: usize = 1024;
 pub const MAX_VALUE_SIZE: usize = 10 * 1024 * 1024; // 10MB
 pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+pub const LFU_AGING_FACTOR: u64 = 8; // Hits per entry between LFU count halvings
+pub const DEFAULT_WRITE_BACK_FLUSH_SECS: u64 = 5;
+pub const DEFAULT_MAX_DIRTY_ENTRIES: usize = 1000;
 
 #[cfg(test)]
 mod tests {
@@ -117,6 +129,7 @@ mod tests {
     fn test_constants() {
         assert_eq!(DEFAULT_CACHE_SIZE, 1000);
         assert_eq!(DEFAULT_TTL_SECS, 3600);
+        assert!(DEFAULT_NEGATIVE_TTL_SECS < DEFAULT_TTL_SECS);
         assert!(MAX_KEY_SIZE > 0);
         assert!(MAX_VALUE_SIZE > 0);
         assert!(DEFAULT_COMPRESSION_LEVEL >= 0);
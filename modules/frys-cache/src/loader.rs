@@ -0,0 +1,443 @@
+//! Single-flight coordination, negative entries and early recomputation
+//! for cache loaders
+
+use crate::*;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Longest lifetime an entry is given; longer TTLs are clamped to it so
+/// their expiry stays representable
+const MAX_TTL: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// When an entry stored at `now` with `ttl` expires, clamping TTLs too
+/// long to represent
+pub(crate) fn expiry_after(now: Instant, ttl: Duration) -> Instant {
+    now.checked_add(ttl).unwrap_or_else(|| now + MAX_TTL)
+}
+
+/// Loads currently running, by key
+#[derive(Debug, Default)]
+pub(crate) struct SingleFlight {
+    flights: Mutex<alloc::collections::BTreeMap<CacheKey, Arc<Flight>>>,
+}
+
+/// A caller's role in the load of a key
+pub(crate) enum Join<'a> {
+    /// The caller must run the load and report its result
+    Leader(Leader<'a>),
+    /// Another caller is already loading the key
+    Follower(Arc<Flight>),
+}
+
+impl SingleFlight {
+    /// Join the load of `key`, starting one if none is running
+    pub(crate) fn join(&self, key: &CacheKey) -> Join<'_> {
+        let mut flights = self.flights.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(flight) = flights.get(key) {
+            return Join::Follower(flight.clone());
+        }
+
+        let flight = Arc::new(Flight::default());
+        flights.insert(key.clone(), flight.clone());
+        Join::Leader(Leader {
+            group: self,
+            key: key.clone(),
+            flight,
+            finished: false,
+        })
+    }
+
+    fn remove(&self, key: &CacheKey) {
+        self.flights.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+    }
+}
+
+/// The caller running a load.
+///
+/// Dropping it without calling [`Leader::finish`], e.g. because the
+/// leader's future was cancelled, fails the load for every follower.
+pub(crate) struct Leader<'a> {
+    group: &'a SingleFlight,
+    key: CacheKey,
+    flight: Arc<Flight>,
+    finished: bool,
+}
+
+impl Leader<'_> {
+    /// Hand the load result to every follower
+    pub(crate) fn finish(mut self, result: Result<CacheValue>) -> Result<CacheValue> {
+        self.finished = true;
+        // Later callers must not join a flight that has already landed
+        self.group.remove(&self.key);
+        self.flight.complete(result.clone());
+        result
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.group.remove(&self.key);
+            self.flight.complete(Err(CacheError::OperationFailed {
+                operation: "get_or_insert_with",
+                details: "loader was cancelled".into(),
+            }));
+        }
+    }
+}
+
+/// Result of a single load, shared by everyone waiting for it
+#[derive(Debug, Default)]
+pub(crate) struct Flight {
+    state: Mutex<FlightState>,
+}
+
+#[derive(Debug, Default)]
+struct FlightState {
+    result: Option<Result<CacheValue>>,
+    wakers: alloc::vec::Vec<Waker>,
+}
+
+impl Flight {
+    fn complete(&self, result: Result<CacheValue>) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.result = Some(result);
+            core::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Wait for the load to finish
+    pub(crate) fn wait(&self) -> FlightWait<'_> {
+        FlightWait { flight: self }
+    }
+}
+
+/// Future resolving to the result of a [`Flight`]
+pub(crate) struct FlightWait<'a> {
+    flight: &'a Flight,
+}
+
+impl Future for FlightWait<'_> {
+    type Output = Result<CacheValue>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.flight.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(result) = &state.result {
+            return Poll::Ready(result.clone());
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// Load cost and expiry of loaded values, for probabilistic early
+/// recomputation (XFetch).
+///
+/// A reader recomputes a value before it expires when
+/// `delta * beta * -ln(rand)` reaches the time left, where `delta` is how
+/// long the last load took and `rand` is uniform in `(0, 1]`. The chance
+/// rises sharply as expiry nears and is higher for expensive values, so one
+/// reader usually refreshes a hot key shortly before the rest would all
+/// miss at once.
+///
+/// Entries are dropped when their key is written, deleted or cleared, and
+/// swept once expired on every record, so keys evicted from the cache do
+/// not linger past their expiry.
+#[derive(Debug, Default)]
+pub(crate) struct EarlyExpiry {
+    inner: Mutex<FreshnessIndex>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Freshness {
+    /// How long the loader took to compute the value
+    delta: Duration,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct FreshnessIndex {
+    entries: alloc::collections::BTreeMap<CacheKey, Freshness>,
+    /// The same entries ordered by expiry
+    by_expiry: alloc::collections::BTreeSet<(Instant, CacheKey)>,
+}
+
+impl FreshnessIndex {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(freshness) = self.entries.remove(key) {
+            self.by_expiry.remove(&(freshness.expires_at, key.clone()));
+        }
+    }
+}
+
+impl EarlyExpiry {
+    fn lock(&self) -> std::sync::MutexGuard<'_, FreshnessIndex> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record that `key` was loaded in `delta` and expires after `ttl`
+    pub(crate) fn record(&self, key: CacheKey, delta: Duration, ttl: Duration) {
+        let now = Instant::now();
+        let expires_at = expiry_after(now, ttl);
+        let mut index = self.lock();
+        index.remove(&key);
+
+        while let Some((_, expired)) = index.by_expiry.first().filter(|(expires_at, _)| *expires_at <= now).cloned() {
+            index.remove(&expired);
+        }
+
+        index.by_expiry.insert((expires_at, key.clone()));
+        index.entries.insert(key, Freshness { delta, expires_at });
+    }
+
+    /// Forget `key`, e.g. because it was written or deleted directly
+    pub(crate) fn forget(&self, key: &CacheKey) {
+        self.lock().remove(key);
+    }
+
+    /// Forget every key
+    pub(crate) fn clear(&self) {
+        let mut index = self.lock();
+        index.entries.clear();
+        index.by_expiry.clear();
+    }
+
+    /// Whether this reader should recompute `key` now
+    pub(crate) fn is_due(&self, key: &CacheKey, beta: f64) -> bool {
+        let Some(freshness) = self.lock().entries.get(key).copied() else {
+            return false;
+        };
+        let remaining = freshness.expires_at.saturating_duration_since(Instant::now());
+        xfetch_due(remaining, freshness.delta, beta, random_unit())
+    }
+
+    /// Number of stored entries, expired ones included until swept
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+}
+
+/// Keys known to be absent, bounded in number.
+///
+/// Expired entries are swept on every insert. When the store is still
+/// full, the entry closest to expiry is dropped to make room.
+#[derive(Debug)]
+pub(crate) struct NegativeEntries {
+    capacity: usize,
+    inner: Mutex<NegativeIndex>,
+}
+
+#[derive(Debug, Default)]
+struct NegativeIndex {
+    /// When each key's entry expires
+    expiry: alloc::collections::BTreeMap<CacheKey, Instant>,
+    /// The same entries ordered by expiry
+    by_expiry: alloc::collections::BTreeSet<(Instant, CacheKey)>,
+}
+
+impl NegativeIndex {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(expires_at) = self.expiry.remove(key) {
+            self.by_expiry.remove(&(expires_at, key.clone()));
+        }
+    }
+
+    /// Drop the entry expiring first, returning its expiry
+    fn pop_first(&mut self) -> Option<Instant> {
+        let (expires_at, key) = self.by_expiry.pop_first()?;
+        self.expiry.remove(&key);
+        Some(expires_at)
+    }
+}
+
+impl NegativeEntries {
+    /// Store at most `capacity` entries
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, NegativeIndex> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record that `key` is absent for `ttl`
+    pub(crate) fn insert(&self, key: CacheKey, ttl: Duration) {
+        if self.capacity == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let expires_at = expiry_after(now, ttl);
+        let mut index = self.lock();
+        index.remove(&key);
+
+        while index.by_expiry.first().is_some_and(|(expires_at, _)| *expires_at <= now) {
+            index.pop_first();
+        }
+        if index.expiry.len() >= self.capacity {
+            index.pop_first();
+        }
+
+        index.by_expiry.insert((expires_at, key.clone()));
+        index.expiry.insert(key, expires_at);
+    }
+
+    /// Whether `key` is recorded as absent, forgetting the entry once expired
+    pub(crate) fn contains(&self, key: &CacheKey) -> bool {
+        let mut index = self.lock();
+        match index.expiry.get(key) {
+            Some(expires_at) if *expires_at > Instant::now() => true,
+            Some(_) => {
+                index.remove(key);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Forget `key`, e.g. because it was written
+    pub(crate) fn forget(&self, key: &CacheKey) {
+        self.lock().remove(key);
+    }
+
+    /// Forget every key
+    pub(crate) fn clear(&self) {
+        let mut index = self.lock();
+        index.expiry.clear();
+        index.by_expiry.clear();
+    }
+
+    /// Number of stored entries, expired ones included until swept
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.lock().expiry.len()
+    }
+}
+
+/// The XFetch test for a uniform draw `random` in `(0, 1]`
+fn xfetch_due(remaining: Duration, delta: Duration, beta: f64, random: f64) -> bool {
+    delta.as_secs_f64() * beta * -random.ln() >= remaining.as_secs_f64()
+}
+
+/// Uniform draw in `(0, 1]`, from the randomly keyed std hasher
+fn random_unit() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let bits = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    ((bits >> 11) + 1) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xfetch_due() {
+        let delta = Duration::from_millis(100);
+
+        // Expired values are always due; a draw of 1 never is before expiry
+        assert!(xfetch_due(Duration::ZERO, delta, 1.0, 1.0));
+        assert!(!xfetch_due(Duration::from_millis(1), delta, 1.0, 1.0));
+
+        // Due with probability exp(-remaining / (delta * beta))
+        let remaining = Duration::from_millis(200);
+        assert!(xfetch_due(remaining, delta, 1.0, 0.1));
+        assert!(!xfetch_due(remaining, delta, 1.0, 0.2));
+        assert!(xfetch_due(remaining, delta, 2.0, 0.3));
+
+        assert!((0..1000).map(|_| random_unit()).all(|r| r > 0.0 && r <= 1.0));
+    }
+
+    #[test]
+    fn test_negative_entries_bounded() {
+        let entries = NegativeEntries::new(3);
+        entries.insert(b"short".to_vec(), Duration::from_millis(1));
+        for key in [b"a", b"b"] {
+            entries.insert(key.to_vec(), Duration::from_secs(60));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+
+        // The expired entry is swept rather than a live one evicted
+        entries.insert(b"c".to_vec(), Duration::from_secs(30));
+        assert_eq!(entries.len(), 3);
+        assert!(!entries.contains(&b"short".to_vec()));
+
+        // When full, the entry expiring first makes room
+        entries.insert(b"d".to_vec(), Duration::from_secs(60));
+        assert_eq!(entries.len(), 3);
+        assert!(!entries.contains(&b"c".to_vec()));
+        assert!([b"a", b"b", b"d"].iter().all(|key| entries.contains(&key.to_vec())));
+
+        // Re-inserting a key replaces its entry
+        entries.insert(b"a".to_vec(), Duration::from_secs(90));
+        assert_eq!(entries.len(), 3);
+
+        // TTLs too long to represent are clamped rather than overflowing
+        entries.insert(b"forever".to_vec(), Duration::MAX);
+        assert!(entries.contains(&b"forever".to_vec()));
+
+        let disabled = NegativeEntries::new(0);
+        disabled.insert(b"a".to_vec(), Duration::from_secs(60));
+        assert!(!disabled.contains(&b"a".to_vec()));
+    }
+
+    #[test]
+    fn test_early_expiry_sweeps_expired_keys() {
+        let freshness = EarlyExpiry::default();
+        let delta = Duration::from_millis(10);
+        freshness.record(b"evicted".to_vec(), delta, Duration::from_millis(1));
+        freshness.record(b"a".to_vec(), delta, Duration::from_secs(60));
+        std::thread::sleep(Duration::from_millis(5));
+
+        // Expired keys go on the next record; live ones stay until forgotten
+        freshness.record(b"b".to_vec(), delta, Duration::from_secs(60));
+        assert_eq!(freshness.len(), 2);
+        freshness.record(b"a".to_vec(), delta, Duration::from_secs(90));
+        assert_eq!(freshness.len(), 2);
+        freshness.forget(&b"a".to_vec());
+        assert_eq!(freshness.len(), 1);
+        assert!(!freshness.is_due(&b"a".to_vec(), 1.0));
+    }
+
+    #[tokio::test]
+    async fn test_followers_share_leader_result() {
+        let group = SingleFlight::default();
+        let key = b"k".to_vec();
+
+        let Join::Leader(leader) = group.join(&key) else { panic!("expected leader") };
+        let Join::Follower(flight) = group.join(&key) else { panic!("expected follower") };
+
+        assert_eq!(leader.finish(Ok(b"v".to_vec())).unwrap(), b"v");
+        assert_eq!(flight.wait().await.unwrap(), b"v");
+
+        // The landed flight is forgotten, so the next caller leads again
+        assert!(matches!(group.join(&key), Join::Leader(_)));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_leader_fails_followers() {
+        let group = SingleFlight::default();
+        let key = b"k".to_vec();
+
+        let leader = group.join(&key);
+        let Join::Follower(flight) = group.join(&key) else { panic!("expected follower") };
+        drop(leader);
+
+        assert!(matches!(
+            flight.wait().await,
+            Err(CacheError::OperationFailed { operation: "get_or_insert_with", .. })
+        ));
+    }
+}
@@ -354,6 +354,480 @@ impl PolicyStats {
     }
 }
 
+/// Bookkeeping that decides which resident keys a bounded cache evicts
+pub trait EvictionTracker: Send + core::fmt::Debug {
+    /// Record a read or overwrite of a resident key
+    fn on_hit(&mut self, key: &CacheKey);
+
+    /// Record a key becoming resident, returning the resident keys that
+    /// must be evicted to stay within capacity
+    fn on_insert(&mut self, key: CacheKey) -> alloc::vec::Vec<CacheKey>;
+
+    /// Forget a key removed from the cache
+    fn on_remove(&mut self, key: &CacheKey);
+
+    /// Forget every key
+    fn clear(&mut self);
+
+    /// Number of resident keys
+    fn len(&self) -> usize;
+
+    /// Whether no keys are resident
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get policy name
+    fn name(&self) -> &'static str;
+}
+
+impl EvictionPolicy {
+    /// Create the tracker implementing this policy for a cache holding at
+    /// most `capacity` entries.
+    ///
+    /// Random and size-based eviction have no per-key bookkeeping and fall
+    /// back to LRU.
+    pub fn tracker(self, capacity: usize) -> Box<dyn EvictionTracker> {
+        match self {
+            EvictionPolicy::Lfu => Box::new(LfuTracker::new(capacity)),
+            EvictionPolicy::Arc => Box::new(ArcTracker::new(capacity)),
+            EvictionPolicy::Fifo => Box::new(LruTracker::fifo(capacity)),
+            EvictionPolicy::Lru | EvictionPolicy::Random | EvictionPolicy::SizeBased => Box::new(LruTracker::new(capacity)),
+        }
+    }
+}
+
+/// Recency-ordered eviction: LRU, or FIFO when hits do not refresh a key
+#[derive(Debug)]
+pub struct LruTracker {
+    capacity: usize,
+    /// Resident keys, least recently used first
+    entries: KeyList,
+    /// Whether a hit moves a key to the most recently used end
+    refresh_on_hit: bool,
+}
+
+impl LruTracker {
+    /// Create an LRU tracker
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: KeyList::default(),
+            refresh_on_hit: true,
+        }
+    }
+
+    /// Create a tracker evicting keys in insertion order
+    pub fn fifo(capacity: usize) -> Self {
+        Self {
+            refresh_on_hit: false,
+            ..Self::new(capacity)
+        }
+    }
+}
+
+impl EvictionTracker for LruTracker {
+    fn on_hit(&mut self, key: &CacheKey) {
+        if self.refresh_on_hit {
+            self.entries.move_to_back(key);
+        }
+    }
+
+    fn on_insert(&mut self, key: CacheKey) -> alloc::vec::Vec<CacheKey> {
+        if self.entries.contains(&key) {
+            self.on_hit(&key);
+            return alloc::vec::Vec::new();
+        }
+
+        let mut evicted = alloc::vec::Vec::new();
+        while self.entries.len() >= self.capacity {
+            match self.entries.pop_front() {
+                Some(victim) => evicted.push(victim),
+                None => break,
+            }
+        }
+        self.entries.push_back(key);
+        evicted
+    }
+
+    fn on_remove(&mut self, key: &CacheKey) {
+        self.entries.remove(key);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn name(&self) -> &'static str {
+        if self.refresh_on_hit {
+            "lru"
+        } else {
+            "fifo"
+        }
+    }
+}
+
+/// Least-frequently-used eviction with aging.
+///
+/// Every `aging_period` hits all access counts are halved, so keys that
+/// were hot long ago lose their advantage over keys that are hot now. Ties
+/// are broken by recency. Hits and inserts cost O(log f), where f is the
+/// number of distinct access counts, which aging keeps small.
+#[derive(Debug)]
+pub struct LfuTracker {
+    capacity: usize,
+    /// Access count of each resident key
+    counts: std::collections::HashMap<CacheKey, u64>,
+    /// Resident keys by access count, least recently used first
+    buckets: alloc::collections::BTreeMap<u64, KeyList>,
+    /// Hits between halvings of every count
+    aging_period: u64,
+    /// Hits since counts were last halved
+    hits_since_aging: u64,
+}
+
+impl LfuTracker {
+    /// Create an LFU tracker aging counts every `capacity * LFU_AGING_FACTOR` hits
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            counts: std::collections::HashMap::new(),
+            buckets: alloc::collections::BTreeMap::new(),
+            aging_period: (capacity as u64).saturating_mul(LFU_AGING_FACTOR),
+            hits_since_aging: 0,
+        }
+    }
+
+    /// Set the number of hits between halvings of every count
+    pub fn with_aging_period(mut self, hits: u64) -> Self {
+        self.aging_period = hits.max(1);
+        self
+    }
+
+    /// Access count of a resident key
+    pub fn count(&self, key: &CacheKey) -> Option<u64> {
+        self.counts.get(key).copied()
+    }
+
+    fn unlink(&mut self, key: &CacheKey, count: u64) {
+        if let Some(bucket) = self.buckets.get_mut(&count) {
+            bucket.remove(key);
+            if bucket.is_empty() {
+                self.buckets.remove(&count);
+            }
+        }
+    }
+
+    fn link(&mut self, key: CacheKey, count: u64) {
+        self.buckets.entry(count).or_default().push_back(key.clone());
+        self.counts.insert(key, count);
+    }
+
+    /// Halve every count, keeping recency order within each new count
+    fn age(&mut self) {
+        self.hits_since_aging = 0;
+        let buckets = core::mem::take(&mut self.buckets);
+        for (count, mut bucket) in buckets {
+            while let Some(key) = bucket.pop_front() {
+                self.link(key, (count / 2).max(1));
+            }
+        }
+    }
+}
+
+impl EvictionTracker for LfuTracker {
+    fn on_hit(&mut self, key: &CacheKey) {
+        let Some(count) = self.counts.get(key).copied() else { return };
+        self.unlink(key, count);
+        self.link(key.clone(), count.saturating_add(1));
+
+        self.hits_since_aging += 1;
+        if self.hits_since_aging >= self.aging_period {
+            self.age();
+        }
+    }
+
+    fn on_insert(&mut self, key: CacheKey) -> alloc::vec::Vec<CacheKey> {
+        if self.counts.contains_key(&key) {
+            self.on_hit(&key);
+            return alloc::vec::Vec::new();
+        }
+
+        let mut evicted = alloc::vec::Vec::new();
+        while self.counts.len() >= self.capacity {
+            let Some(mut entry) = self.buckets.first_entry() else { break };
+            if let Some(victim) = entry.get_mut().pop_front() {
+                self.counts.remove(&victim);
+                evicted.push(victim);
+            }
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+        }
+        self.link(key, 1);
+        evicted
+    }
+
+    fn on_remove(&mut self, key: &CacheKey) {
+        if let Some(count) = self.counts.remove(key) {
+            self.unlink(key, count);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.clear();
+        self.buckets.clear();
+        self.hits_since_aging = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    fn name(&self) -> &'static str {
+        "lfu"
+    }
+}
+
+/// Adaptive replacement cache (ARC) eviction.
+///
+/// Resident keys are split between a list of keys seen once recently and a
+/// list of keys seen at least twice, each shadowed by a ghost list of keys
+/// recently evicted from it. Hits on ghosts shift the target size of the
+/// recency list, so the policy adapts between recency- and
+/// frequency-favouring workloads and resists one-off scans. All operations
+/// are O(1).
+#[derive(Debug)]
+pub struct ArcTracker {
+    capacity: usize,
+    /// Target size of `recent`, adapted from ghost hits
+    target: usize,
+    /// Resident keys seen once recently (T1)
+    recent: KeyList,
+    /// Resident keys seen at least twice (T2)
+    frequent: KeyList,
+    /// Keys recently evicted from `recent` (B1)
+    recent_ghosts: KeyList,
+    /// Keys recently evicted from `frequent` (B2)
+    frequent_ghosts: KeyList,
+}
+
+impl ArcTracker {
+    /// Create an ARC tracker
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            target: 0,
+            recent: KeyList::default(),
+            frequent: KeyList::default(),
+            recent_ghosts: KeyList::default(),
+            frequent_ghosts: KeyList::default(),
+        }
+    }
+
+    /// Current target size of the recency list
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Evict one resident key into its ghost list if the cache is full
+    fn replace(&mut self, frequent_ghost_hit: bool, evicted: &mut alloc::vec::Vec<CacheKey>) {
+        if self.recent.len() + self.frequent.len() < self.capacity {
+            return;
+        }
+
+        let from_recent = !self.recent.is_empty()
+            && (self.recent.len() > self.target
+                || (frequent_ghost_hit && self.recent.len() == self.target)
+                || self.frequent.is_empty());
+
+        let (list, ghosts) = if from_recent {
+            (&mut self.recent, &mut self.recent_ghosts)
+        } else {
+            (&mut self.frequent, &mut self.frequent_ghosts)
+        };
+        if let Some(victim) = list.pop_front() {
+            ghosts.push_back(victim.clone());
+            evicted.push(victim);
+        }
+    }
+}
+
+impl EvictionTracker for ArcTracker {
+    fn on_hit(&mut self, key: &CacheKey) {
+        if self.recent.remove(key) || self.frequent.remove(key) {
+            self.frequent.push_back(key.clone());
+        }
+    }
+
+    fn on_insert(&mut self, key: CacheKey) -> alloc::vec::Vec<CacheKey> {
+        let mut evicted = alloc::vec::Vec::new();
+
+        if self.recent.contains(&key) || self.frequent.contains(&key) {
+            self.on_hit(&key);
+        } else if self.recent_ghosts.contains(&key) {
+            // Evicted too early from the recency list: grow its target
+            let delta = (self.frequent_ghosts.len() / self.recent_ghosts.len()).max(1);
+            self.target = (self.target + delta).min(self.capacity);
+            self.recent_ghosts.remove(&key);
+            self.replace(false, &mut evicted);
+            self.frequent.push_back(key);
+        } else if self.frequent_ghosts.contains(&key) {
+            // Evicted too early from the frequency list: shrink the recency target
+            let delta = (self.recent_ghosts.len() / self.frequent_ghosts.len()).max(1);
+            self.target = self.target.saturating_sub(delta);
+            self.frequent_ghosts.remove(&key);
+            self.replace(true, &mut evicted);
+            self.frequent.push_back(key);
+        } else {
+            let recent_total = self.recent.len() + self.recent_ghosts.len();
+            if recent_total >= self.capacity {
+                if self.recent.len() < self.capacity {
+                    self.recent_ghosts.pop_front();
+                    self.replace(false, &mut evicted);
+                } else if let Some(victim) = self.recent.pop_front() {
+                    evicted.push(victim);
+                }
+            } else {
+                let total = recent_total + self.frequent.len() + self.frequent_ghosts.len();
+                if total >= self.capacity {
+                    if total >= 2 * self.capacity {
+                        self.frequent_ghosts.pop_front();
+                    }
+                    self.replace(false, &mut evicted);
+                }
+            }
+            self.recent.push_back(key);
+        }
+
+        evicted
+    }
+
+    fn on_remove(&mut self, key: &CacheKey) {
+        if !self.recent.remove(key) {
+            self.frequent.remove(key);
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(self.capacity);
+    }
+
+    fn len(&self) -> usize {
+        self.recent.len() + self.frequent.len()
+    }
+
+    fn name(&self) -> &'static str {
+        "arc"
+    }
+}
+
+/// Doubly linked list of keys with O(1) push, pop and removal by key
+#[derive(Debug, Default)]
+struct KeyList {
+    nodes: alloc::vec::Vec<KeyNode>,
+    /// Slots of removed nodes, reused by later pushes
+    free: alloc::vec::Vec<usize>,
+    index: std::collections::HashMap<CacheKey, usize>,
+    /// Oldest node
+    head: Option<usize>,
+    /// Newest node
+    tail: Option<usize>,
+}
+
+#[derive(Debug)]
+struct KeyNode {
+    key: CacheKey,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+impl KeyList {
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    fn contains(&self, key: &CacheKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Append a key that is not already in the list
+    fn push_back(&mut self, key: CacheKey) {
+        let node = KeyNode {
+            key: key.clone(),
+            prev: self.tail,
+            next: None,
+        };
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.nodes[slot] = node;
+                slot
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+
+        match self.tail {
+            Some(tail) => self.nodes[tail].next = Some(slot),
+            None => self.head = Some(slot),
+        }
+        self.tail = Some(slot);
+        self.index.insert(key, slot);
+    }
+
+    fn unlink(&mut self, slot: usize) -> CacheKey {
+        let (prev, next) = (self.nodes[slot].prev, self.nodes[slot].next);
+        match prev {
+            Some(prev) => self.nodes[prev].next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.nodes[next].prev = prev,
+            None => self.tail = prev,
+        }
+
+        let key = core::mem::take(&mut self.nodes[slot].key);
+        self.index.remove(&key);
+        self.free.push(slot);
+        key
+    }
+
+    fn remove(&mut self, key: &CacheKey) -> bool {
+        match self.index.get(key).copied() {
+            Some(slot) => {
+                self.unlink(slot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn pop_front(&mut self) -> Option<CacheKey> {
+        self.head.map(|slot| self.unlink(slot))
+    }
+
+    fn move_to_back(&mut self, key: &CacheKey) {
+        if self.remove(key) {
+            self.push_back(key.clone());
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
 /// Get current timestamp (simplified)
 fn current_timestamp() -> u64 {
     // In a real implementation, this would use system time
@@ -491,4 +965,89 @@ mod tests {
         manager.on_access(&mut entry);
         assert_eq!(entry.access_count, 2);
     }
+
+    /// Replay reads through a tracker as a cache would, returning the
+    /// resident keys
+    fn replay(mut tracker: Box<dyn EvictionTracker>, accesses: &[&str]) -> alloc::collections::BTreeSet<alloc::string::String> {
+        let mut resident = alloc::collections::BTreeSet::new();
+        for key in accesses {
+            let key = key.as_bytes().to_vec();
+            if resident.contains(&key) {
+                tracker.on_hit(&key);
+            } else {
+                for victim in tracker.on_insert(key.clone()) {
+                    assert!(resident.remove(&victim));
+                }
+                resident.insert(key);
+            }
+            assert_eq!(tracker.len(), resident.len());
+        }
+        resident.into_iter().map(|key| alloc::string::String::from_utf8(key).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_lfu_keeps_frequent_key_that_lru_evicts() {
+        let accesses = ["a", "a", "a", "a", "a", "b", "c", "d"];
+
+        let lru = replay(EvictionPolicy::Lru.tracker(3), &accesses);
+        assert!(!lru.contains("a"));
+
+        let lfu = replay(EvictionPolicy::Lfu.tracker(3), &accesses);
+        assert!(lfu.contains("a"));
+        assert!(!lfu.contains("b"));
+    }
+
+    #[test]
+    fn test_lfu_aging_lets_stale_hot_key_go() {
+        let mut accesses = alloc::vec!["a"; 10];
+        accesses.extend(["b"; 6]);
+        accesses.push("c");
+
+        // Without aging, ten old hits outweigh six recent ones
+        let unaged = replay(Box::new(LfuTracker::new(2).with_aging_period(u64::MAX)), &accesses);
+        assert!(unaged.contains("a"));
+        assert!(!unaged.contains("b"));
+
+        let aged = replay(Box::new(LfuTracker::new(2).with_aging_period(4)), &accesses);
+        assert!(!aged.contains("a"));
+        assert!(aged.contains("b"));
+    }
+
+    #[test]
+    fn test_arc_resists_scan_that_flushes_lru() {
+        let mut accesses = alloc::vec!["x", "y", "x", "y"];
+        accesses.extend(["s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8"]);
+
+        let lru = replay(EvictionPolicy::Lru.tracker(4), &accesses);
+        assert!(!lru.contains("x") && !lru.contains("y"));
+
+        let arc = replay(EvictionPolicy::Arc.tracker(4), &accesses);
+        assert!(arc.contains("x") && arc.contains("y"));
+        assert!(arc.contains("s8"));
+    }
+
+    #[test]
+    fn test_arc_adapts_to_ghost_hits() {
+        let mut tracker = ArcTracker::new(2);
+        tracker.on_insert(b"a".to_vec());
+        tracker.on_hit(&b"a".to_vec());
+        tracker.on_insert(b"b".to_vec());
+        assert_eq!(tracker.on_insert(b"c".to_vec()), alloc::vec![b"b".to_vec()]);
+        assert_eq!(tracker.target(), 0);
+
+        // "b" left the recency list too early, so seeing it again grows it
+        assert_eq!(tracker.on_insert(b"b".to_vec()), alloc::vec![b"a".to_vec()]);
+        assert_eq!(tracker.target(), 1);
+
+        tracker.on_remove(&b"b".to_vec());
+        assert_eq!(tracker.len(), 1);
+        tracker.clear();
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_fifo_ignores_hits() {
+        let fifo = replay(EvictionPolicy::Fifo.tracker(2), &["a", "b", "a", "c"]);
+        assert_eq!(fifo.into_iter().collect::<alloc::vec::Vec<_>>(), ["b", "c"]);
+    }
 }
@@ -0,0 +1,155 @@
+//! Write-ahead log of buffered write-back operations
+//!
+//! Every write or delete buffered by a write-back [`ConsistencyManager`] is
+//! appended here before it is acknowledged. After a flush the log is
+//! rewritten with only the entries that are still dirty, so on startup it
+//! holds exactly the writes that never reached the lower cache levels.
+//!
+//! Records are framed by `frys-record-log`, so a torn write at the tail of
+//! the log is detected and discarded on replay.
+
+use crate::*;
+use frys_record_log::{frame, next_record};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+const RECORD_PUT: u8 = 1;
+const RECORD_DELETE: u8 = 2;
+
+/// Append-only log file of dirty entries
+#[derive(Debug)]
+pub(crate) struct WriteBackLog {
+    path: PathBuf,
+    file: File,
+    sync_writes: bool,
+}
+
+impl WriteBackLog {
+    /// Open the log at `path`, returning the operations it holds in the
+    /// order they were written
+    pub(crate) fn open(path: &str, sync_writes: bool) -> Result<(Self, alloc::vec::Vec<(CacheKey, DirtyOp)>)> {
+        let path = PathBuf::from(path);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| wal_error("open", &e))?;
+        }
+
+        let mut bytes = alloc::vec::Vec::new();
+        if path.exists() {
+            File::open(&path)
+                .and_then(|mut file| file.read_to_end(&mut bytes))
+                .map_err(|e| wal_error("open", &e))?;
+        }
+
+        let mut ops = alloc::vec::Vec::new();
+        let mut offset = 0;
+        while let Some((body, next)) = next_record(&bytes, offset) {
+            match decode(body) {
+                Some(op) => ops.push(op),
+                None => break,
+            }
+            offset = next;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| wal_error("open", &e))?;
+        // Drop a torn tail so new records are not appended after garbage
+        file.set_len(offset as u64).map_err(|e| wal_error("open", &e))?;
+
+        Ok((Self { path, file, sync_writes }, ops))
+    }
+
+    /// Append a buffered operation
+    pub(crate) fn append(&mut self, key: &CacheKey, op: &DirtyOp) -> Result<()> {
+        self.file
+            .write_all(&frame(&encode(key, op)))
+            .map_err(|e| wal_error("append", &e))?;
+        if self.sync_writes {
+            self.file.sync_data().map_err(|e| wal_error("append", &e))?;
+        }
+        Ok(())
+    }
+
+    /// Replace the log with `entries`, the operations still unflushed
+    pub(crate) fn rewrite<'a>(&mut self, entries: impl Iterator<Item = (&'a CacheKey, &'a DirtyOp)>) -> Result<()> {
+        let mut bytes = alloc::vec::Vec::new();
+        for (key, op) in entries {
+            bytes.extend(frame(&encode(key, op)));
+        }
+
+        let tmp_path = self.path.with_extension("wal.tmp");
+        let mut tmp = File::create(&tmp_path).map_err(|e| wal_error("compact", &e))?;
+        tmp.write_all(&bytes)
+            .and_then(|()| tmp.sync_data())
+            .map_err(|e| wal_error("compact", &e))?;
+        std::fs::rename(&tmp_path, &self.path).map_err(|e| wal_error("compact", &e))?;
+
+        self.file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| wal_error("compact", &e))?;
+        Ok(())
+    }
+}
+
+fn wal_error(operation: &'static str, error: &std::io::Error) -> CacheError {
+    CacheError::ConsistencyError {
+        operation,
+        details: alloc::format!("write-back log: {}", error),
+    }
+}
+
+fn encode(key: &CacheKey, op: &DirtyOp) -> alloc::vec::Vec<u8> {
+    let mut body = alloc::vec::Vec::with_capacity(key.len() + 5);
+    match op {
+        DirtyOp::Put(_) => body.push(RECORD_PUT),
+        DirtyOp::Delete => body.push(RECORD_DELETE),
+    }
+    body.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    body.extend_from_slice(key);
+    if let DirtyOp::Put(value) = op {
+        body.extend_from_slice(value);
+    }
+    body
+}
+
+fn decode(body: &[u8]) -> Option<(CacheKey, DirtyOp)> {
+    let (&tag, rest) = body.split_first()?;
+    let key_len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let key = rest.get(4..4 + key_len)?.to_vec();
+    let value = &rest[4 + key_len..];
+    match tag {
+        RECORD_PUT => Some((key, DirtyOp::Put(value.to_vec()))),
+        RECORD_DELETE => Some((key, DirtyOp::Delete)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_torn_tail_is_discarded() {
+        let path = std::env::temp_dir().join(alloc::format!("frys-cache-wal-torn-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        {
+            let (mut log, ops) = WriteBackLog::open(path, false).unwrap();
+            assert!(ops.is_empty());
+            log.append(&b"a".to_vec(), &DirtyOp::Put(b"1".to_vec())).unwrap();
+            log.append(&b"b".to_vec(), &DirtyOp::Delete).unwrap();
+        }
+        // Simulate a crash part-way through a third record
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(&frame(&encode(&b"c".to_vec(), &DirtyOp::Put(b"3".to_vec())))[..6]).unwrap();
+
+        let (_, ops) = WriteBackLog::open(path, false).unwrap();
+        assert_eq!(ops, alloc::vec![(b"a".to_vec(), DirtyOp::Put(b"1".to_vec())), (b"b".to_vec(), DirtyOp::Delete)]);
+        let _ = std::fs::remove_file(path);
+    }
+}
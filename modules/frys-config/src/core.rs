@@ -58,7 +58,11 @@ pub struct ConfigEntry {
 /// Configuration manager builder
 pub struct ConfigManagerBuilder {
     merger: ConfigMerger,
+    default_path: Option<alloc::string::String>,
+    file_paths: alloc::vec::Vec<alloc::string::String>,
+    env_prefix: Option<alloc::string::String>,
     enable_hot_reload: bool,
+    reload_interval: ::core::time::Duration,
     validation_enabled: bool,
     validation_schema: Option<ValidationSchema>,
 }
@@ -66,7 +70,11 @@ pub struct ConfigManagerBuilder {
 impl core::fmt::Debug for ConfigManagerBuilder {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("ConfigManagerBuilder")
+            .field("default_path", &self.default_path)
+            .field("file_paths", &self.file_paths)
+            .field("env_prefix", &self.env_prefix)
             .field("enable_hot_reload", &self.enable_hot_reload)
+            .field("reload_interval", &self.reload_interval)
             .field("validation_enabled", &self.validation_enabled)
             .finish()
     }
@@ -77,7 +85,11 @@ impl ConfigManagerBuilder {
     pub fn new() -> Self {
         Self {
             merger: ConfigMerger::new(),
+            default_path: None,
+            file_paths: alloc::vec::Vec::new(),
+            env_prefix: None,
             enable_hot_reload: false,
+            reload_interval: ::core::time::Duration::from_secs(1),
             validation_enabled: false,
             validation_schema: None,
         }
@@ -97,11 +109,39 @@ impl ConfigManagerBuilder {
         self
     }
 
-    /// Add environment variable provider
+    /// Add the default configuration file, below every other file in
+    /// priority. JSON, YAML and TOML are picked by the file extension;
+    /// any other file is read as `key = value` lines.
+    #[cfg(feature = "std")]
+    pub fn with_default_path<P: Into<alloc::string::String>>(mut self, path: P) -> Self {
+        let path = path.into();
+        let provider = FileProvider::from_path(path.clone()).with_priority(50);
+        self.merger = self.merger.add_provider(provider);
+        self.default_path = Some(path);
+        self
+    }
+
+    /// Add a configuration file whose format is picked by its extension
+    #[cfg(feature = "std")]
+    pub fn with_file_path<P: Into<alloc::string::String>>(mut self, path: P) -> Self {
+        let path = path.into();
+        self.merger = self.merger.add_provider(FileProvider::from_path(path.clone()));
+        self.file_paths.push(path);
+        self
+    }
+
+    /// Overlay environment variables on every other source.
+    ///
+    /// `MYAPP_SERVER__PORT=9000` with prefix `MYAPP` sets `server.port`:
+    /// the prefix and one `_` are stripped, `__` separates nesting levels
+    /// and names are lowercased. A value overriding an existing key is
+    /// converted to that key's type, failing the build if it cannot be.
     #[cfg(feature = "env_support")]
     pub fn with_env_prefix<P: Into<alloc::string::String>>(mut self, prefix: P) -> Self {
-        let provider = EnvProvider::new(prefix);
+        let prefix = prefix.into();
+        let provider = EnvProvider::new(alloc::format!("{}_", prefix.trim_end_matches('_'))).with_separator("__");
         self.merger = self.merger.add_provider(provider);
+        self.env_prefix = Some(prefix);
         self
     }
 
@@ -119,6 +159,12 @@ impl ConfigManagerBuilder {
         self
     }
 
+    /// How often hot reload checks the configuration files for changes
+    pub fn with_reload_interval(mut self, interval: ::core::time::Duration) -> Self {
+        self.reload_interval = interval;
+        self
+    }
+
     /// Enable validation with common schema
     pub fn with_validation(mut self, enable: bool) -> Self {
         self.validation_enabled = enable;
@@ -142,12 +188,17 @@ impl ConfigManagerBuilder {
         // Merge configurations from all providers
         let merged_config = self.merger.merge().await?;
         manager.entries = merged_config;
+        manager.merger = Some(self.merger);
 
         // Initialize hot reload if enabled
         #[cfg(feature = "hot_reload")]
         if self.enable_hot_reload {
-            let mut reloader = HotReloader::default();
-            // In a real implementation, we would watch the files from providers
+            let mut reloader = HotReloader::new(self.reload_interval);
+            #[cfg(feature = "std")]
+            for path in self.default_path.iter().chain(&self.file_paths) {
+                let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Custom);
+                reloader.watch_file(path.clone(), format)?;
+            }
             manager.hot_reloader = Some(reloader);
         }
 
@@ -181,6 +232,8 @@ pub struct ConfigManager {
     hot_reloader: Option<HotReloader>,
     /// Validator
     validator: Option<ConfigValidator>,
+    /// Sources the configuration was built from, read again on reload
+    merger: Option<ConfigMerger>,
 }
 
 impl ConfigManager {
@@ -191,6 +244,7 @@ impl ConfigManager {
             version: AtomicU64::new(1),
             hot_reloader: None,
             validator: None,
+            merger: None,
         }
     }
 
@@ -248,14 +302,23 @@ impl ConfigManager {
         }
     }
 
-    /// Get a configuration value by key
+    /// Get a configuration value by key.
+    ///
+    /// Secret references such as `${env:DB_PASSWORD}` in the value are
+    /// resolved on every read; see [`SecretRef`].
     pub fn get(&self, key: &str) -> Result<ConfigValue> {
-        match self.entries.get(key) {
-            Some(entry) => Ok(entry.value.clone()),
-            None => Err(ConfigError::KeyNotFound {
-                key: key.into(),
-            }),
-        }
+        let value = match self.entries.get(key) {
+            Some(entry) => entry.value.clone(),
+            None => {
+                return Err(ConfigError::KeyNotFound {
+                    key: key.into(),
+                })
+            }
+        };
+
+        #[cfg(feature = "std")]
+        let value = crate::secrets::resolve_secrets(key, value)?;
+        Ok(value)
     }
 
     /// Get a typed configuration value
@@ -279,6 +342,85 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// Set several values as one change.
+    ///
+    /// The values staged by `update` are validated together against the
+    /// schema. If any of them fails, none is applied and no watcher is
+    /// notified; keys that were already invalid do not fail the change.
+    /// Otherwise the values that differ from the current ones are applied
+    /// under one new version and, with hot reload enabled, watchers of the
+    /// changed keys are notified. Staging only current values changes
+    /// nothing, not even the version.
+    ///
+    /// ```rust,ignore
+    /// config.update_atomic(|txn| {
+    ///     txn.set("server.host", ConfigValue::String("0.0.0.0".into()));
+    ///     txn.set("server.port", ConfigValue::Int(9090));
+    /// })?;
+    /// ```
+    pub fn update_atomic<F>(&mut self, update: F) -> Result<alloc::vec::Vec<ConfigChange>>
+    where
+        F: FnOnce(&mut ConfigTransaction),
+    {
+        let mut txn = ConfigTransaction::new();
+        update(&mut txn);
+
+        let staged: alloc::vec::Vec<_> = txn
+            .values
+            .into_iter()
+            .filter(|(key, value)| self.entries.get(key).map(|entry| &entry.value) != Some(value))
+            .collect();
+        if staged.is_empty() {
+            return Ok(alloc::vec::Vec::new());
+        }
+
+        let version = self.version.load(Ordering::Acquire);
+        let mut entries = self.entries.clone();
+        for (key, value) in staged {
+            let entry = ConfigEntry {
+                value,
+                source: ConfigSource::Runtime,
+                timestamp: 0, // Would be current timestamp
+                version,
+            };
+            entries.insert(key, entry);
+        }
+
+        // The validator reads the manager, so validate with the proposed
+        // entries in place and put the old ones back if they fail. Only
+        // errors the transaction introduces count.
+        let before = match &self.validator {
+            Some(validator) => validator.validate(self)?.errors,
+            None => alloc::vec::Vec::new(),
+        };
+        let old = ::core::mem::replace(&mut self.entries, entries);
+        if let Some(validator) = &self.validator {
+            let checked = validator.validate(self).and_then(|result| {
+                match result.errors.into_iter().find(|error| !before.contains(error)) {
+                    Some(error) => Err(ConfigError::from(error)),
+                    None => Ok(()),
+                }
+            });
+            if let Err(error) = checked {
+                self.entries = old;
+                return Err(error);
+            }
+        }
+        self.version.fetch_add(1, Ordering::AcqRel);
+
+        #[cfg(feature = "hot_reload")]
+        let mut changes = match &self.hot_reloader {
+            Some(reloader) => reloader.notify_changes(&old, &self.entries),
+            None => diff_config(&old, &self.entries),
+        };
+        #[cfg(not(feature = "hot_reload"))]
+        let mut changes = diff_config(&old, &self.entries);
+        for change in &mut changes {
+            change.version = version;
+        }
+        Ok(changes)
+    }
+
     /// Check if a configuration key exists
     pub fn exists(&self, key: &str) -> bool {
         self.entries.contains_key(key)
@@ -301,15 +443,104 @@ impl ConfigManager {
         }
     }
 
-    /// Watch for configuration changes
-    pub async fn watch<F>(&self, mut callback: F) -> Result<()>
+    /// Read every configuration source again and apply the result,
+    /// returning the keys that changed. Values set with [`set`](Self::set)
+    /// are kept. With hot reload enabled, watchers of the changed keys are
+    /// notified.
+    pub async fn reload(&mut self) -> Result<alloc::vec::Vec<ConfigChange>> {
+        let merger = self.merger.as_ref().ok_or_else(|| ConfigError::HotReloadError {
+            operation: "reload",
+            details: "manager was not built from configuration sources".into(),
+        })?;
+
+        let mut entries = merger.merge().await?;
+        for (key, entry) in &self.entries {
+            if entry.source == ConfigSource::Runtime {
+                entries.insert(key.clone(), entry.clone());
+            }
+        }
+        let old = ::core::mem::replace(&mut self.entries, entries);
+
+        #[cfg(feature = "hot_reload")]
+        if let Some(reloader) = &self.hot_reloader {
+            return Ok(reloader.notify_changes(&old, &self.entries));
+        }
+        Ok(diff_config(&old, &self.entries))
+    }
+
+    /// Reload `config` in the background whenever one of its configuration
+    /// files changes, until the manager is dropped. Watchers registered
+    /// with [`watch`](Self::watch) are notified of every changed key.
+    ///
+    /// ```rust,ignore
+    /// let config = Arc::new(RwLock::new(
+    ///     ConfigManager::builder().with_file_path("app.json").with_hot_reload(true).build().await?,
+    /// ));
+    /// ConfigManager::start_hot_reload(&config).await?;
+    /// ```
+    #[cfg(feature = "hot_reload")]
+    pub async fn start_hot_reload(config: &alloc::sync::Arc<tokio::sync::RwLock<ConfigManager>>) -> Result<()> {
+        // The task holds the manager weakly so dropping it stops the reloads
+        let weak = alloc::sync::Arc::downgrade(config);
+        let reload = move || {
+            let weak = weak.clone();
+            async move {
+                match weak.upgrade() {
+                    Some(config) => config.write().await.reload().await.map(|_| ()),
+                    None => Ok(()),
+                }
+            }
+        };
+
+        let mut manager = config.write().await;
+        match manager.hot_reloader.as_mut() {
+            Some(reloader) => reloader.start(reload).await,
+            None => Err(ConfigError::HotReloadError {
+                operation: "start",
+                details: "hot reload is not enabled".into(),
+            }),
+        }
+    }
+
+    /// Call `callback` with the old and new value of `key` each time a
+    /// reload changes it, until the returned handle is dropped.
+    ///
+    /// ```rust,ignore
+    /// let _port = config.watch("server.port", |old, new| {
+    ///     println!("port changed from {:?} to {:?}", old, new);
+    /// })?;
+    /// ```
+    #[cfg(feature = "hot_reload")]
+    pub fn watch<K, F>(&self, key: K, callback: F) -> Result<WatchHandle>
     where
-        F: FnMut(&alloc::string::String, &ConfigEntry) + Send + 'static,
+        K: Into<alloc::string::String>,
+        F: Fn(Option<&ConfigValue>, Option<&ConfigValue>) + Send + Sync + 'static,
     {
-        // Implementation would set up watching mechanism
-        // For now, this is a placeholder
-        let _ = callback;
-        Ok(())
+        match &self.hot_reloader {
+            Some(reloader) => Ok(reloader.watch_key(key, callback)),
+            None => Err(ConfigError::HotReloadError {
+                operation: "watch",
+                details: "hot reload is not enabled".into(),
+            }),
+        }
+    }
+}
+
+/// Values staged by [`ConfigManager::update_atomic`]
+#[derive(Debug, Default)]
+pub struct ConfigTransaction {
+    values: alloc::collections::BTreeMap<alloc::string::String, ConfigValue>,
+}
+
+impl ConfigTransaction {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stage a value for `key`, replacing one staged earlier
+    pub fn set<K: Into<alloc::string::String>>(&mut self, key: K, value: ConfigValue) -> &mut Self {
+        self.values.insert(key.into(), value);
+        self
     }
 }
 
@@ -449,6 +680,187 @@ mod tests {
         assert!(builder.validation_enabled);
     }
 
+    #[cfg(feature = "hot_reload")]
+    #[tokio::test]
+    async fn test_watch_fires_for_changed_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.json");
+        std::fs::write(&path, r#"{"server": {"port": 8080, "host": "localhost"}, "app": {"name": "frys"}}"#).unwrap();
+
+        let mut config = ConfigManager::builder()
+            .with_file_path(path.to_str().unwrap())
+            .with_hot_reload(true)
+            .build()
+            .await
+            .unwrap();
+
+        let seen = alloc::sync::Arc::new(std::sync::Mutex::new(alloc::vec::Vec::new()));
+        let watch = |key: &str| {
+            let seen = seen.clone();
+            let key = alloc::string::String::from(key);
+            config
+                .watch(key.clone(), move |old, new| {
+                    seen.lock().unwrap().push((key.clone(), old.cloned(), new.cloned()));
+                })
+                .unwrap()
+        };
+        let _port = watch("server.port");
+        let _name = watch("app.name");
+        let host = watch("server.host");
+        drop(host);
+
+        // The port changes, the name is removed and the unwatched host changes
+        std::fs::write(&path, r#"{"server": {"port": 9090, "host": "0.0.0.0"}}"#).unwrap();
+        let changes = config.reload().await.unwrap();
+        assert_eq!(changes.len(), 3);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            alloc::vec![
+                ("app.name".into(), Some(ConfigValue::String("frys".into())), None),
+                ("server.port".into(), Some(ConfigValue::Int(8080)), Some(ConfigValue::Int(9090))),
+            ]
+        );
+
+        // Nothing changed, nothing fires
+        assert!(config.reload().await.unwrap().is_empty());
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "hot_reload")]
+    #[tokio::test]
+    async fn test_hot_reload_applies_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.json");
+        std::fs::write(&path, r#"{"server": {"port": 8080}}"#).unwrap();
+
+        let config = ConfigManager::builder()
+            .with_file_path(path.to_str().unwrap())
+            .with_hot_reload(true)
+            .with_reload_interval(::core::time::Duration::from_millis(10))
+            .build()
+            .await
+            .unwrap();
+        let seen = alloc::sync::Arc::new(std::sync::Mutex::new(alloc::vec::Vec::new()));
+        let _port = {
+            let seen = seen.clone();
+            config.watch("server.port", move |_, new| seen.lock().unwrap().push(new.cloned())).unwrap()
+        };
+        let config = alloc::sync::Arc::new(tokio::sync::RwLock::new(config));
+        ConfigManager::start_hot_reload(&config).await.unwrap();
+
+        // Move the modification time forward in case the file system
+        // records it too coarsely to tell the two writes apart
+        std::fs::write(&path, r#"{"server": {"port": 9090}}"#).unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified + ::core::time::Duration::from_secs(2))
+            .unwrap();
+
+        for _ in 0..200 {
+            if config.read().await.get("server.port").ok() == Some(ConfigValue::Int(9090)) {
+                break;
+            }
+            tokio::time::sleep(::core::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(config.read().await.get("server.port").unwrap(), ConfigValue::Int(9090));
+        assert_eq!(*seen.lock().unwrap(), alloc::vec![Some(ConfigValue::Int(9090))]);
+    }
+
+    #[cfg(feature = "hot_reload")]
+    #[tokio::test]
+    async fn test_update_atomic_rolls_back_invalid_transaction() {
+        let schema = ValidationSchema::new("1.0".into())
+            .add_rule("server.port".into(), ValidationRule::Range { min: 1.0, max: 65535.0 })
+            .add_rule("server.host".into(), ValidationRule::Type(crate::ConfigValueType::String));
+        let mut config = ConfigManager::builder()
+            .with_schema(schema)
+            .with_hot_reload(true)
+            .build()
+            .await
+            .unwrap();
+        config.set("server.port".into(), ConfigValue::Int(8080)).unwrap();
+        config.set("server.host".into(), ConfigValue::String("localhost".into())).unwrap();
+        let version = config.snapshot().version;
+
+        let seen = alloc::sync::Arc::new(std::sync::Mutex::new(alloc::vec::Vec::new()));
+        let watch = |key: &'static str| {
+            let seen = seen.clone();
+            config.watch(key, move |_, new| seen.lock().unwrap().push((key, new.cloned()))).unwrap()
+        };
+        let _port = watch("server.port");
+        let _host = watch("server.host");
+
+        // The port is valid but the host is not, so neither is applied
+        let error = config
+            .update_atomic(|txn| {
+                txn.set("server.port", ConfigValue::Int(9090));
+                txn.set("server.host", ConfigValue::Int(0));
+            })
+            .unwrap_err();
+        assert!(matches!(error, ConfigError::ValidationError { ref field, .. } if field == "server.host"));
+        assert_eq!(config.get("server.port").unwrap(), ConfigValue::Int(8080));
+        assert_eq!(config.get("server.host").unwrap(), ConfigValue::String("localhost".into()));
+        assert_eq!(config.snapshot().version, version);
+        assert!(seen.lock().unwrap().is_empty());
+
+        let changes = config
+            .update_atomic(|txn| {
+                txn.set("server.port", ConfigValue::Int(9090));
+                txn.set("server.host", ConfigValue::String("0.0.0.0".into()));
+            })
+            .unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(config.get("server.port").unwrap(), ConfigValue::Int(9090));
+        assert_eq!(
+            *seen.lock().unwrap(),
+            alloc::vec![
+                ("server.host", Some(ConfigValue::String("0.0.0.0".into()))),
+                ("server.port", Some(ConfigValue::Int(9090))),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_atomic_versions_only_changed_keys() {
+        let schema = ValidationSchema::new("1.0".into())
+            .add_rule("server.port".into(), ValidationRule::Range { min: 1.0, max: 65535.0 });
+        let mut config = ConfigManager::builder().with_schema(schema).build().await.unwrap();
+        config.set("server.host".into(), ConfigValue::String("localhost".into())).unwrap();
+        // Already invalid, and not part of the transaction below
+        config.set("server.port".into(), ConfigValue::Int(0)).unwrap();
+        let host = config.snapshot().get("server.host").unwrap().clone();
+        let version = config.snapshot().version;
+
+        let changes = config
+            .update_atomic(|txn| {
+                txn.set("server.host", ConfigValue::String("localhost".into()));
+                txn.set("app.name", ConfigValue::String("frys".into()));
+            })
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "app.name");
+        assert_eq!(changes[0].version, version);
+
+        let snapshot = config.snapshot();
+        assert_eq!(snapshot.version, version + 1);
+        assert_eq!(snapshot.get("app.name").unwrap().version, version);
+        let unchanged = snapshot.get("server.host").unwrap();
+        assert_eq!((unchanged.version, unchanged.timestamp), (host.version, host.timestamp));
+
+        // Staging the current values is not a change
+        let changes = config
+            .update_atomic(|txn| {
+                txn.set("app.name", ConfigValue::String("frys".into()));
+            })
+            .unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(config.snapshot().version, version + 1);
+    }
+
     #[tokio::test]
     async fn test_config_manager_creation() {
         let manager = ConfigManager::builder().build().await.unwrap();
@@ -91,6 +91,13 @@ pub enum ConfigError {
         details: alloc::string::String,
     },
 
+    /// Secret reference could not be resolved
+    SecretUnresolved {
+        key: alloc::string::String,
+        reference: alloc::string::String,
+        details: alloc::string::String,
+    },
+
     /// Remote configuration error
     RemoteConfigError {
         endpoint: alloc::string::String,
@@ -155,6 +162,9 @@ impl fmt::Display for ConfigError {
             ConfigError::EnvError { variable, details } => {
                 write!(f, "Environment variable '{}' error: {}", variable, details)
             }
+            ConfigError::SecretUnresolved { key, reference, details } => {
+                write!(f, "Cannot resolve secret {} for '{}': {}", reference, key, details)
+            }
             ConfigError::RemoteConfigError { endpoint, details } => {
                 write!(f, "Remote config error for '{}': {}", endpoint, details)
             }
@@ -1,7 +1,9 @@
 //! Hot reload functionality for configuration files
 
 use crate::*;
-use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use core::future::Future;
 use core::time::Duration;
 
 /// Get current timestamp (simplified)
@@ -11,20 +13,39 @@ fn current_timestamp() -> u64 {
     0
 }
 
+/// Callback receiving the events of a [`HotReloader`]
+#[cfg(feature = "hot_reload")]
+pub type EventCallback = Arc<dyn Fn(&HotReloadEvent) + Send + Sync>;
+
 /// Hot reload manager for file watching
 #[cfg(feature = "hot_reload")]
-#[derive(Debug)]
 pub struct HotReloader {
     /// Watched files with their metadata
     watched_files: alloc::vec::Vec<WatchedFile>,
     /// Change callbacks
-    callbacks: alloc::vec::Vec<Box<dyn Fn(&HotReloadEvent) + Send + Sync>>,
+    callbacks: alloc::vec::Vec<EventCallback>,
     /// Running flag
     running: Arc<AtomicBool>,
     /// Poll interval
     poll_interval: Duration,
     /// Statistics
     stats: HotReloadStats,
+    /// Per-key change subscriptions
+    key_watchers: Arc<KeyWatchers>,
+}
+
+#[cfg(feature = "hot_reload")]
+impl ::core::fmt::Debug for HotReloader {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("HotReloader")
+            .field("watched_files", &self.watched_files)
+            .field("callbacks", &self.callbacks.len())
+            .field("running", &self.running)
+            .field("poll_interval", &self.poll_interval)
+            .field("stats", &self.stats)
+            .field("key_watchers", &self.key_watchers)
+            .finish()
+    }
 }
 
 #[cfg(feature = "hot_reload")]
@@ -37,6 +58,7 @@ impl HotReloader {
             running: Arc::new(AtomicBool::new(false)),
             poll_interval,
             stats: HotReloadStats::new(),
+            key_watchers: Arc::new(KeyWatchers::default()),
         }
     }
 
@@ -52,7 +74,7 @@ impl HotReloader {
             let initial_mtime = if exists {
                 fs::metadata(&path_str)
                     .and_then(|m| m.modified())
-                    .and_then(|t| Ok(t.duration_since(std::time::UNIX_EPOCH)?.as_secs()))
+                    .and_then(|t| Ok(t.duration_since(std::time::UNIX_EPOCH)?.as_millis() as u64))
                     .unwrap_or(0)
             } else {
                 0
@@ -87,11 +109,60 @@ impl HotReloader {
     where
         F: Fn(&HotReloadEvent) + Send + Sync + 'static,
     {
-        self.callbacks.push(Box::new(callback));
+        self.callbacks.push(Arc::new(callback));
+    }
+
+    /// Call `callback` with the old and new value of `key` whenever a
+    /// reload changes it. Keys that appear have no old value and keys
+    /// that disappear have no new value.
+    pub fn watch_key<K, F>(&self, key: K, callback: F) -> WatchHandle
+    where
+        K: Into<alloc::string::String>,
+        F: Fn(Option<&ConfigValue>, Option<&ConfigValue>) + Send + Sync + 'static,
+    {
+        let id = self.key_watchers.next_id.fetch_add(1, Ordering::Relaxed);
+        self.key_watchers.lock().insert(id, (key.into(), Arc::new(callback)));
+        WatchHandle {
+            id,
+            watchers: Arc::downgrade(&self.key_watchers),
+        }
+    }
+
+    /// Diff the configuration before and after a reload and notify the
+    /// watchers of every key that changed
+    pub fn notify_changes(
+        &self,
+        old: &alloc::collections::BTreeMap<alloc::string::String, ConfigEntry>,
+        new: &alloc::collections::BTreeMap<alloc::string::String, ConfigEntry>,
+    ) -> alloc::vec::Vec<ConfigChange> {
+        let changes = diff_config(old, new);
+        if changes.is_empty() {
+            return changes;
+        }
+
+        // Callbacks run without the lock held so they may watch or unwatch
+        let watchers: alloc::vec::Vec<_> = self.key_watchers.lock().values().cloned().collect();
+        for change in &changes {
+            for (key, callback) in &watchers {
+                if *key == change.key {
+                    callback(change.old_value.as_ref(), change.new_value.as_ref());
+                }
+            }
+        }
+        changes
     }
 
-    /// Start watching for changes
-    pub async fn start(&mut self) -> Result<()> {
+    /// Start watching for changes, calling `reload` once per poll in which
+    /// a watched file was created or modified. Every changed file is then
+    /// reported to the [`on_change`](Self::on_change) callbacks with the
+    /// outcome of the reload.
+    ///
+    /// See [`ConfigManager::start_hot_reload`] to reload a manager.
+    pub async fn start<R, Fut>(&mut self, reload: R) -> Result<()>
+    where
+        R: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send,
+    {
         if self.running.load(Ordering::Acquire) {
             return Err(ConfigError::HotReloadError {
                 operation: "start",
@@ -109,7 +180,7 @@ impl HotReloader {
         let callbacks = self.callbacks.clone();
 
         tokio::spawn(async move {
-            Self::monitor_files(running, poll_interval, watched_files, callbacks).await;
+            Self::monitor_files(running, poll_interval, watched_files, callbacks, reload).await;
         });
 
         Ok(())
@@ -123,38 +194,53 @@ impl HotReloader {
     }
 
     /// Background file monitoring loop
-    async fn monitor_files(
+    async fn monitor_files<R, Fut>(
         running: Arc<AtomicBool>,
         poll_interval: Duration,
         mut watched_files: alloc::vec::Vec<WatchedFile>,
-        callbacks: alloc::vec::Vec<Box<dyn Fn(&HotReloadEvent) + Send + Sync>>,
-    ) {
+        callbacks: alloc::vec::Vec<EventCallback>,
+        mut reload: R,
+    ) where
+        R: FnMut() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
         while running.load(Ordering::Acquire) {
             // Check for changes
+            let mut changed = alloc::vec::Vec::new();
             for file in &mut watched_files {
                 if let Some(change) = file.check_for_changes().await {
-                    // Notify callbacks
-                    let event = match change {
-                        ChangeType::Created => HotReloadEvent::ConfigReloaded {
-                            path: file.path.clone(),
-                            success: true,
-                            error: None,
-                        },
-                        ChangeType::Modified => HotReloadEvent::ConfigReloaded {
-                            path: file.path.clone(),
-                            success: true,
-                            error: None,
-                        },
-                        ChangeType::Deleted => HotReloadEvent::ConfigReloaded {
-                            path: file.path.clone(),
-                            success: false,
-                            error: Some("file deleted".into()),
-                        },
-                    };
+                    changed.push((file.path.clone(), change));
+                }
+            }
 
-                    for callback in &callbacks {
-                        callback(&event);
-                    }
+            // Reload once however many files changed
+            let reloaded = if changed.iter().any(|(_, change)| *change != ChangeType::Deleted) {
+                Some(reload().await)
+            } else {
+                None
+            };
+
+            for (path, change) in changed {
+                let event = match (change, &reloaded) {
+                    (ChangeType::Deleted, _) => HotReloadEvent::ConfigReloaded {
+                        path,
+                        success: false,
+                        error: Some("file deleted".into()),
+                    },
+                    (_, Some(Err(error))) => HotReloadEvent::ConfigReloaded {
+                        path,
+                        success: false,
+                        error: Some(alloc::format!("{}", error)),
+                    },
+                    _ => HotReloadEvent::ConfigReloaded {
+                        path,
+                        success: true,
+                        error: None,
+                    },
+                };
+
+                for callback in &callbacks {
+                    callback(&event);
                 }
             }
 
@@ -230,6 +316,75 @@ impl HotReloader {
     }
 }
 
+/// Callback receiving the old and new value of a watched key
+#[cfg(feature = "hot_reload")]
+pub type KeyCallback = Arc<dyn Fn(Option<&ConfigValue>, Option<&ConfigValue>) + Send + Sync>;
+
+/// Change subscriptions of a [`HotReloader`], by subscription id
+#[cfg(feature = "hot_reload")]
+#[derive(Default)]
+struct KeyWatchers {
+    next_id: AtomicU64,
+    watchers: std::sync::Mutex<alloc::collections::BTreeMap<u64, (alloc::string::String, KeyCallback)>>,
+}
+
+#[cfg(feature = "hot_reload")]
+impl KeyWatchers {
+    fn lock(&self) -> std::sync::MutexGuard<'_, alloc::collections::BTreeMap<u64, (alloc::string::String, KeyCallback)>> {
+        self.watchers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "hot_reload")]
+impl ::core::fmt::Debug for KeyWatchers {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        let keys: alloc::vec::Vec<_> = self.lock().values().map(|(key, _)| key.clone()).collect();
+        f.debug_struct("KeyWatchers").field("keys", &keys).finish()
+    }
+}
+
+/// Subscription to the changes of one key, cancelled when dropped
+#[cfg(feature = "hot_reload")]
+#[derive(Debug)]
+#[must_use = "dropping the handle cancels the subscription"]
+pub struct WatchHandle {
+    id: u64,
+    watchers: alloc::sync::Weak<KeyWatchers>,
+}
+
+#[cfg(feature = "hot_reload")]
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        if let Some(watchers) = self.watchers.upgrade() {
+            watchers.lock().remove(&self.id);
+        }
+    }
+}
+
+/// Keys whose value differs between two configurations, in key order
+pub fn diff_config(
+    old: &alloc::collections::BTreeMap<alloc::string::String, ConfigEntry>,
+    new: &alloc::collections::BTreeMap<alloc::string::String, ConfigEntry>,
+) -> alloc::vec::Vec<ConfigChange> {
+    let keys: alloc::collections::BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (old_entry, new_entry) = (old.get(key), new.get(key));
+            if old_entry.map(|entry| &entry.value) == new_entry.map(|entry| &entry.value) {
+                return None;
+            }
+            Some(ConfigChange {
+                version: 0,
+                key: key.clone(),
+                old_value: old_entry.map(|entry| entry.value.clone()),
+                new_value: new_entry.map(|entry| entry.value.clone()),
+                timestamp: current_timestamp(),
+                source: new_entry.or(old_entry).map_or(ConfigSource::Runtime, |entry| entry.source),
+            })
+        })
+        .collect()
+}
+
 /// Watched file information
 #[derive(Debug, Clone)]
 pub struct WatchedFile {
@@ -237,7 +392,7 @@ pub struct WatchedFile {
     pub path: alloc::string::String,
     /// Configuration format
     pub format: ConfigFormat,
-    /// Last modification time
+    /// Last modification time, in milliseconds since the Unix epoch
     pub last_mtime: u64,
     /// Whether file exists
     pub exists: bool,
@@ -256,13 +411,13 @@ impl WatchedFile {
                 Ok(metadata) => {
                     let current_exists = true;
                     let current_mtime = metadata.modified()
-                        .and_then(|t| Ok(t.duration_since(std::time::UNIX_EPOCH)?.as_secs()))
+                        .and_then(|t| Ok(t.duration_since(std::time::UNIX_EPOCH)?.as_millis() as u64))
                         .unwrap_or(0);
 
                     let change_type = if !self.exists {
                         // File was created
                         Some(ChangeType::Created)
-                    } else if current_mtime > self.last_mtime {
+                    } else if current_mtime != self.last_mtime {
                         // File was modified
                         Some(ChangeType::Modified)
                     } else {
@@ -556,6 +711,14 @@ impl Default for HotReloader {
     }
 }
 
+#[cfg(feature = "hot_reload")]
+impl Drop for HotReloader {
+    fn drop(&mut self) {
+        // Ends the monitoring task at its next poll
+        self.running.store(false, Ordering::Release);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
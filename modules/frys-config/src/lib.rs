@@ -75,6 +75,8 @@ pub mod providers;
 pub mod validation;
 pub mod hot_reload;
 pub mod distributed;
+#[cfg(feature = "std")]
+pub mod secrets;
 
 // Re-exports for convenience
 pub use core::*;
@@ -82,6 +84,8 @@ pub use providers::*;
 pub use validation::*;
 pub use distributed::*;
 pub use hot_reload::*;
+#[cfg(feature = "std")]
+pub use secrets::*;
 
 // Error types
 mod error;
@@ -17,6 +17,13 @@ pub trait ConfigProvider: Send + Sync {
     fn is_available(&self) -> bool {
         true
     }
+
+    /// Adapt a loaded value before it overrides `existing`, the value merged
+    /// so far from lower priority providers. By default it is used as is.
+    fn coerce(&self, key: &str, value: ConfigValue, existing: Option<&ConfigValue>) -> Result<ConfigValue> {
+        let _ = (key, existing);
+        Ok(value)
+    }
 }
 
 /// File-based configuration provider
@@ -24,6 +31,7 @@ pub trait ConfigProvider: Send + Sync {
 pub struct FileProvider {
     path: alloc::string::String,
     format: ConfigFormat,
+    priority: i32,
 }
 
 #[cfg(feature = "std")]
@@ -33,8 +41,22 @@ impl FileProvider {
         Self {
             path: path.into(),
             format,
+            priority: 100, // Medium priority
         }
     }
+
+    /// Create a file provider whose format is chosen by the file extension
+    pub fn from_path<P: Into<alloc::string::String>>(path: P) -> Self {
+        let path = path.into();
+        let format = ConfigFormat::from_path(&path).unwrap_or(ConfigFormat::Custom);
+        Self::new(path, format)
+    }
+
+    /// Override the merge priority of this file
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
 }
 
 #[cfg(feature = "std")]
@@ -75,7 +97,7 @@ impl ConfigProvider for FileProvider {
     }
 
     fn priority(&self) -> i32 {
-        100 // Medium priority
+        self.priority
     }
 
     fn is_available(&self) -> bool {
@@ -184,6 +206,30 @@ impl EnvProvider {
         }
     }
 
+    /// Convert variable text to the type of the value it overrides. Arrays
+    /// are written as comma-separated items.
+    fn coerce_text(&self, text: &str, existing: &ConfigValue) -> Option<ConfigValue> {
+        match existing {
+            ConfigValue::Null => Some(self.parse_value(text)),
+            ConfigValue::String(_) => Some(ConfigValue::String(text.into())),
+            ConfigValue::Int(_) => text.trim().parse().ok().map(ConfigValue::Int),
+            ConfigValue::Float(_) => text.trim().parse().ok().map(ConfigValue::Float),
+            ConfigValue::Bool(_) => match self.parse_value(text.trim()) {
+                ConfigValue::Bool(b) => Some(ConfigValue::Bool(b)),
+                _ => None,
+            },
+            ConfigValue::Array(items) => {
+                let item_type = items.first().unwrap_or(&ConfigValue::Null);
+                text.split(',')
+                    .filter(|item| !item.trim().is_empty())
+                    .map(|item| self.coerce_text(item.trim(), item_type))
+                    .collect::<Option<_>>()
+                    .map(ConfigValue::Array)
+            }
+            ConfigValue::Object(_) => None,
+        }
+    }
+
     /// Parse environment variable value with type detection
     fn parse_value(&self, value: &str) -> ConfigValue {
         // Handle special cases
@@ -233,15 +279,30 @@ impl ConfigProvider for EnvProvider {
         for (env_key, env_value) in env::vars() {
             let config_key = self.normalize_key(&env_key);
 
+            // Values stay text here; `coerce` types them once the value
+            // they override is known
             if !config_key.is_empty() {
-                let config_value = self.parse_value(&env_value);
-                config.insert(config_key, config_value);
+                config.insert(config_key, ConfigValue::String(env_value));
             }
         }
 
         Ok(config)
     }
 
+    fn coerce(&self, key: &str, value: ConfigValue, existing: Option<&ConfigValue>) -> Result<ConfigValue> {
+        let ConfigValue::String(text) = value else {
+            return Ok(value);
+        };
+        match existing {
+            None | Some(ConfigValue::Null) => Ok(self.parse_value(&text)),
+            Some(existing) => self.coerce_text(&text, existing).ok_or_else(|| ConfigError::TypeError {
+                key: key.into(),
+                expected_type: value_type_name(existing),
+                actual_type: "string",
+            }),
+        }
+    }
+
     fn name(&self) -> &'static str {
         "environment"
     }
@@ -405,11 +466,31 @@ pub enum ConfigFormat {
     Custom,
 }
 
+impl ConfigFormat {
+    /// Format implied by a file extension: `.json`, `.yaml`/`.yml` or `.toml`
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(ConfigFormat::Json),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
 /// Configuration merger for combining multiple sources
 pub struct ConfigMerger {
     providers: alloc::vec::Vec<Box<dyn ConfigProvider>>,
 }
 
+impl ::core::fmt::Debug for ConfigMerger {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        let providers: alloc::vec::Vec<_> = self.providers.iter().map(|provider| provider.name()).collect();
+        f.debug_struct("ConfigMerger").field("providers", &providers).finish()
+    }
+}
+
 impl ConfigMerger {
     /// Create a new configuration merger
     pub fn new() -> Self {
@@ -428,9 +509,10 @@ impl ConfigMerger {
     pub async fn merge(&self) -> Result<alloc::collections::BTreeMap<alloc::string::String, ConfigEntry>> {
         let mut merged = alloc::collections::BTreeMap::new();
 
-        // Sort providers by priority (highest first)
+        // Apply providers from lowest to highest priority so the most
+        // specific source wins; equal priorities apply in the order added
         let mut sorted_providers: alloc::vec::Vec<_> = self.providers.iter().collect();
-        sorted_providers.sort_by(|a, b| b.priority().cmp(&a.priority()));
+        sorted_providers.sort_by_key(|provider| provider.priority());
 
         for provider in sorted_providers {
             if provider.is_available() {
@@ -438,6 +520,7 @@ impl ConfigMerger {
                     Ok(config) => {
                         // Merge with existing configuration
                         for (key, value) in config {
+                            let value = provider.coerce(&key, value, merged.get(&key).map(|entry: &ConfigEntry| &entry.value))?;
                             let entry = ConfigEntry {
                                 value,
                                 source: match provider.name() {
@@ -467,6 +550,20 @@ impl ConfigMerger {
     }
 }
 
+/// Name of a value's type, as used in [`ConfigError::TypeError`]
+#[cfg(feature = "env_support")]
+fn value_type_name(value: &ConfigValue) -> &'static str {
+    match value {
+        ConfigValue::Null => "null",
+        ConfigValue::Bool(_) => "bool",
+        ConfigValue::Int(_) => "int",
+        ConfigValue::Float(_) => "float",
+        ConfigValue::String(_) => "string",
+        ConfigValue::Array(_) => "array",
+        ConfigValue::Object(_) => "object",
+    }
+}
+
 /// Flatten nested configuration structure
 fn flatten_config(
     value: ConfigValue,
@@ -499,25 +596,108 @@ fn flatten_config(
 /// Parse JSON configuration
 #[cfg(feature = "serde")]
 fn parse_json(content: &str) -> Result<ConfigValue> {
-    // Simple JSON parser (would use serde_json in real implementation)
-    // This is a very basic implementation for demonstration
-    parse_simple_json(content)
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| ConfigError::ParseError {
+        format: "json",
+        details: alloc::format!("{}", e),
+    })?;
+    Ok(from_json(value))
+}
+
+#[cfg(feature = "serde")]
+fn from_json(value: serde_json::Value) -> ConfigValue {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => ConfigValue::Null,
+        Value::Bool(b) => ConfigValue::Bool(b),
+        Value::Number(n) => number_value(n.as_i64(), n.as_f64()),
+        Value::String(s) => ConfigValue::String(s),
+        Value::Array(items) => ConfigValue::Array(items.into_iter().map(from_json).collect()),
+        Value::Object(fields) => ConfigValue::Object(fields.into_iter().map(|(k, v)| (k, from_json(v))).collect()),
+    }
 }
 
 /// Parse YAML configuration
 #[cfg(feature = "serde")]
 fn parse_yaml(content: &str) -> Result<ConfigValue> {
-    // Would use serde_yaml in real implementation
-    // For now, treat as JSON
-    parse_simple_json(content)
+    let value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| ConfigError::ParseError {
+        format: "yaml",
+        details: alloc::format!("{}", e),
+    })?;
+    from_yaml(value)
+}
+
+#[cfg(feature = "serde")]
+fn from_yaml(value: serde_yaml::Value) -> Result<ConfigValue> {
+    use serde_yaml::Value;
+
+    Ok(match value {
+        Value::Null => ConfigValue::Null,
+        Value::Bool(b) => ConfigValue::Bool(b),
+        Value::Number(n) => number_value(n.as_i64(), n.as_f64()),
+        Value::String(s) => ConfigValue::String(s),
+        Value::Sequence(items) => ConfigValue::Array(items.into_iter().map(from_yaml).collect::<Result<_>>()?),
+        Value::Mapping(fields) => ConfigValue::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| Ok((yaml_key(k)?, from_yaml(v)?)))
+                .collect::<Result<_>>()?,
+        ),
+        // Tags such as `!secret` carry no meaning for plain configuration
+        Value::Tagged(tagged) => from_yaml(tagged.value)?,
+    })
+}
+
+/// Mapping keys become dotted path segments, so only scalars are allowed
+#[cfg(feature = "serde")]
+fn yaml_key(key: serde_yaml::Value) -> Result<alloc::string::String> {
+    use serde_yaml::Value;
+
+    match key {
+        Value::String(s) => Ok(s),
+        Value::Bool(b) => Ok(alloc::format!("{}", b)),
+        Value::Number(n) => Ok(alloc::format!("{}", n)),
+        other => Err(ConfigError::ParseError {
+            format: "yaml",
+            details: alloc::format!("unsupported mapping key {:?}", other),
+        }),
+    }
 }
 
 /// Parse TOML configuration
 #[cfg(feature = "serde")]
 fn parse_toml(content: &str) -> Result<ConfigValue> {
-    // Would use toml crate in real implementation
-    // For now, treat as simple key-value
-    parse_simple_kv(content)
+    let table: toml::Table = content.parse().map_err(|e| ConfigError::ParseError {
+        format: "toml",
+        details: alloc::format!("{}", e),
+    })?;
+    Ok(from_toml(toml::Value::Table(table)))
+}
+
+#[cfg(feature = "serde")]
+fn from_toml(value: toml::Value) -> ConfigValue {
+    use toml::Value;
+
+    match value {
+        Value::Boolean(b) => ConfigValue::Bool(b),
+        Value::Integer(i) => ConfigValue::Int(i),
+        Value::Float(f) => ConfigValue::Float(f),
+        Value::String(s) => ConfigValue::String(s),
+        // Dates and times are kept in their RFC 3339 form
+        Value::Datetime(datetime) => ConfigValue::String(alloc::format!("{}", datetime)),
+        Value::Array(items) => ConfigValue::Array(items.into_iter().map(from_toml).collect()),
+        Value::Table(fields) => ConfigValue::Object(fields.into_iter().map(|(k, v)| (k, from_toml(v))).collect()),
+    }
+}
+
+/// Integers that fit in an `i64` stay integers, anything else is a float
+#[cfg(feature = "serde")]
+fn number_value(int: Option<i64>, float: Option<f64>) -> ConfigValue {
+    match (int, float) {
+        (Some(i), _) => ConfigValue::Int(i),
+        (None, Some(f)) => ConfigValue::Float(f),
+        (None, None) => ConfigValue::Null,
+    }
 }
 
 /// Parse custom configuration format
@@ -680,6 +860,86 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_formats_load_identically() {
+        let json = r#"{
+            "app": { "name": "frys", "debug": false },
+            "server": { "host": "0.0.0.0", "port": 8080, "timeout": 2.5, "tls": { "enabled": true } },
+            "features": ["metrics", "tracing"]
+        }"#;
+        let yaml = "
+app:
+  name: frys
+  debug: false
+server:
+  host: 0.0.0.0
+  port: 8080
+  timeout: 2.5
+  tls:
+    enabled: true
+features:
+  - metrics
+  - tracing
+";
+        let toml = r#"
+features = ["metrics", "tracing"]
+
+[app]
+name = "frys"
+debug = false
+
+[server]
+host = "0.0.0.0"
+port = 8080
+timeout = 2.5
+
+[server.tls]
+enabled = true
+"#;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut states = alloc::vec::Vec::new();
+        for (file, content) in [("app.json", json), ("app.yaml", yaml), ("app.toml", toml)] {
+            let path = dir.path().join(file);
+            std::fs::write(&path, content).unwrap();
+            let manager = ConfigManager::builder()
+                .with_default_path(path.to_str().unwrap())
+                .build()
+                .await
+                .unwrap();
+            let state: alloc::collections::BTreeMap<_, _> = manager
+                .snapshot()
+                .entries
+                .into_iter()
+                .map(|(key, entry)| (key, entry.value))
+                .collect();
+            states.push(state);
+        }
+
+        assert_eq!(states[0].get("server.tls.enabled"), Some(&ConfigValue::Bool(true)));
+        assert_eq!(states[0].get("server.port"), Some(&ConfigValue::Int(8080)));
+        assert_eq!(
+            states[0].get("features"),
+            Some(&ConfigValue::Array(alloc::vec![
+                ConfigValue::String("metrics".into()),
+                ConfigValue::String("tracing".into()),
+            ]))
+        );
+        assert_eq!(states[0].len(), 7);
+        assert_eq!(states[0], states[1]);
+        assert_eq!(states[0], states[2]);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ConfigFormat::from_path("config/app.JSON"), Some(ConfigFormat::Json));
+        assert_eq!(ConfigFormat::from_path("app.yml"), Some(ConfigFormat::Yaml));
+        assert_eq!(ConfigFormat::from_path("app.toml"), Some(ConfigFormat::Toml));
+        assert_eq!(ConfigFormat::from_path("app.conf"), None);
+        assert_eq!(ConfigFormat::from_path("app"), None);
+    }
+
     #[cfg(feature = "env_support")]
     #[test]
     fn test_env_provider_normalization() {
@@ -734,6 +994,41 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "env_support")]
+    #[tokio::test]
+    async fn test_env_overlay_coerces_nested_keys() {
+        std::env::set_var("OVERLAY_SERVER__PORT", "9000");
+        std::env::set_var("OVERLAY_APP__VERSION", "2");
+        std::env::set_var("OVERLAY_DATABASE__MAX_CONNECTIONS", "1");
+        std::env::set_var("OVERLAY_CACHE__TTL_SECS", "30");
+
+        // Added first, yet the environment still overrides the defaults
+        let config = ConfigManager::builder()
+            .with_env_prefix("OVERLAY")
+            .with_defaults()
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(config.get("server.port").unwrap(), ConfigValue::Int(9000));
+        assert_eq!(config.get("app.version").unwrap(), ConfigValue::String("2".into()));
+        assert_eq!(config.get("database.max_connections").unwrap(), ConfigValue::Int(1));
+        // Keys without a default keep the detected type
+        assert_eq!(config.get("cache.ttl_secs").unwrap(), ConfigValue::Int(30));
+        assert_eq!(config.get("server.host").unwrap(), ConfigValue::String("localhost".into()));
+
+        std::env::set_var("BADOVERLAY_SERVER__PORT", "http");
+        let result = ConfigManager::builder()
+            .with_defaults()
+            .with_env_prefix("BADOVERLAY")
+            .build()
+            .await;
+        assert!(matches!(
+            result,
+            Err(ConfigError::TypeError { key, expected_type: "int", .. }) if key == "server.port"
+        ));
+    }
+
     #[test]
     fn test_config_format() {
         assert_eq!(ConfigFormat::Json as u8, ConfigFormat::Yaml as u8); // They should be different
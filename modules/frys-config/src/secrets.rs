@@ -0,0 +1,159 @@
+//! Secret references in configuration values
+//!
+//! A string value of the form `${env:NAME}` or `${file:PATH}` is a
+//! reference to a secret kept outside the configuration. References are
+//! stored as written and only resolved when the key is read through
+//! [`ConfigManager::get`], so snapshots, change notifications and `Debug`
+//! output of the manager never contain the secret itself.
+
+use crate::*;
+
+/// Reference to a secret held outside the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretRef {
+    /// `${env:NAME}`: the value of an environment variable
+    Env(alloc::string::String),
+    /// `${file:PATH}`: the contents of a file, without the trailing newline
+    File(alloc::string::String),
+}
+
+impl SecretRef {
+    /// Parse a reference, or `None` if `value` is an ordinary string
+    pub fn parse(value: &str) -> Option<Self> {
+        let inner = value.strip_prefix("${")?.strip_suffix('}')?;
+        let (scheme, target) = inner.split_once(':')?;
+        if target.is_empty() {
+            return None;
+        }
+        match scheme {
+            "env" => Some(SecretRef::Env(target.into())),
+            "file" => Some(SecretRef::File(target.into())),
+            _ => None,
+        }
+    }
+
+    /// Read the secret
+    pub fn resolve(&self) -> core::result::Result<alloc::string::String, alloc::string::String> {
+        match self {
+            SecretRef::Env(name) => std::env::var(name).map_err(|e| match e {
+                std::env::VarError::NotPresent => "environment variable is not set".into(),
+                std::env::VarError::NotUnicode(_) => "environment variable is not valid UTF-8".into(),
+            }),
+            SecretRef::File(path) => std::fs::read_to_string(path)
+                .map(|contents| contents.trim_end_matches(['\r', '\n']).into())
+                .map_err(|e| alloc::format!("cannot read file: {}", e)),
+        }
+    }
+}
+
+impl core::fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SecretRef::Env(name) => write!(f, "${{env:{}}}", name),
+            SecretRef::File(path) => write!(f, "${{file:{}}}", path),
+        }
+    }
+}
+
+/// Replace every secret reference in `value`, including those nested in
+/// arrays and objects, with the secret it points to
+pub(crate) fn resolve_secrets(key: &str, value: ConfigValue) -> Result<ConfigValue> {
+    match value {
+        ConfigValue::String(s) => match SecretRef::parse(&s) {
+            Some(reference) => reference
+                .resolve()
+                .map(ConfigValue::String)
+                .map_err(|details| ConfigError::SecretUnresolved {
+                    key: key.into(),
+                    reference: reference.to_string(),
+                    details,
+                }),
+            None => Ok(ConfigValue::String(s)),
+        },
+        ConfigValue::Array(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| resolve_secrets(&alloc::format!("{}[{}]", key, i), item))
+            .collect::<Result<_>>()
+            .map(ConfigValue::Array),
+        ConfigValue::Object(fields) => fields
+            .into_iter()
+            .map(|(name, field)| {
+                let field = resolve_secrets(&alloc::format!("{}.{}", key, name), field)?;
+                Ok((name, field))
+            })
+            .collect::<Result<_>>()
+            .map(ConfigValue::Object),
+        other => Ok(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!(SecretRef::parse("${env:DB_PASSWORD}"), Some(SecretRef::Env("DB_PASSWORD".into())));
+        assert_eq!(SecretRef::parse("${file:/run/secrets/token}"), Some(SecretRef::File("/run/secrets/token".into())));
+        assert_eq!(SecretRef::parse("${vault:db}"), None);
+        assert_eq!(SecretRef::parse("${env:}"), None);
+        assert_eq!(SecretRef::parse("prefix ${env:X}"), None);
+        assert_eq!(SecretRef::Env("DB_PASSWORD".into()).to_string(), "${env:DB_PASSWORD}");
+    }
+
+    #[test]
+    fn test_env_reference_resolved_on_read() {
+        std::env::set_var("FRYS_CONFIG_TEST_DB_PASSWORD", "hunter2");
+        let mut manager = ConfigManager::new();
+        manager.set("db.password".into(), ConfigValue::String("${env:FRYS_CONFIG_TEST_DB_PASSWORD}".into())).unwrap();
+
+        assert_eq!(manager.get("db.password").unwrap(), ConfigValue::String("hunter2".into()));
+        assert_eq!(manager.get_typed::<alloc::string::String>("db.password").unwrap(), "hunter2");
+        // The stored entry keeps the reference
+        assert!(!alloc::format!("{:?}", manager.snapshot().get("db.password")).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_file_reference_resolved_on_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token");
+        std::fs::write(&path, "s3cr3t\n").unwrap();
+        let mut fields = alloc::collections::BTreeMap::new();
+        fields.insert("token".into(), ConfigValue::String(alloc::format!("${{file:{}}}", path.display())));
+        fields.insert("user".into(), ConfigValue::String("frys".into()));
+        let mut manager = ConfigManager::new();
+        manager.set("api".into(), ConfigValue::Object(fields)).unwrap();
+
+        let ConfigValue::Object(api) = manager.get("api").unwrap() else {
+            panic!("expected an object");
+        };
+        assert_eq!(api["token"], ConfigValue::String("s3cr3t".into()));
+        assert_eq!(api["user"], ConfigValue::String("frys".into()));
+    }
+
+    #[test]
+    fn test_missing_source_is_descriptive_error() {
+        std::env::remove_var("FRYS_CONFIG_TEST_UNSET");
+        let mut manager = ConfigManager::new();
+        manager.set("db.password".into(), ConfigValue::String("${env:FRYS_CONFIG_TEST_UNSET}".into())).unwrap();
+        manager.set("tls.keys".into(), ConfigValue::Array(alloc::vec![
+            ConfigValue::String("${file:/nonexistent/frys/key.pem}".into()),
+        ])).unwrap();
+
+        let error = manager.get("db.password").unwrap_err();
+        assert_eq!(error, ConfigError::SecretUnresolved {
+            key: "db.password".into(),
+            reference: "${env:FRYS_CONFIG_TEST_UNSET}".into(),
+            details: "environment variable is not set".into(),
+        });
+        assert_eq!(
+            error.to_string(),
+            "Cannot resolve secret ${env:FRYS_CONFIG_TEST_UNSET} for 'db.password': environment variable is not set"
+        );
+
+        let error = manager.get("tls.keys").unwrap_err();
+        assert!(matches!(error, ConfigError::SecretUnresolved { ref key, ref reference, .. }
+            if key == "tls.keys[0]" && reference == "${file:/nonexistent/frys/key.pem}"));
+    }
+}
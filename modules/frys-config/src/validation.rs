@@ -67,22 +67,17 @@ impl ValidationSchema {
     /// Validate a configuration against this schema
     pub fn validate(&self, config: &ConfigManager) -> Result<ValidationResult> {
         let mut errors = alloc::vec::Vec::new();
-        let mut warnings = alloc::vec::Vec::new();
+        let warnings = alloc::vec::Vec::new();
 
         for (key, rule) in &self.rules {
-            match self.validate_rule(key, rule, config) {
-                Ok(()) => {}
-                Err(e) => errors.push(e),
-            }
-        }
-
-        // Check for required fields that are missing
-        for (key, rule) in &self.rules {
-            if matches!(rule, ValidationRule::Required) && !config.exists(key) {
-                errors.push(ValidationError::MissingRequiredField {
-                    field: key.clone(),
+            let depth = rule_depth(rule);
+            if depth > MAX_NESTING_DEPTH {
+                return Err(ConfigError::NestingTooDeep {
+                    depth,
+                    max_depth: MAX_NESTING_DEPTH,
                 });
             }
+            self.check_rule(key, rule, config, &mut errors);
         }
 
         Ok(ValidationResult {
@@ -93,106 +88,99 @@ impl ValidationSchema {
         })
     }
 
-    /// Validate a single rule
-    fn validate_rule(&self, key: &str, rule: &ValidationRule, config: &ConfigManager) -> Result<()> {
-        match rule {
-            ValidationRule::Required => {
-                if !config.exists(key) {
-                    return Err(ValidationError::MissingRequiredField {
-                        field: key.into(),
-                    });
-                }
-            }
-            ValidationRule::Type(expected_type) => {
-                if let Ok(value) = config.get(key) {
-                    if !self.check_type(&value, expected_type) {
-                        return Err(ValidationError::TypeMismatch {
-                            field: key.into(),
-                            expected: alloc::format!("{:?}", expected_type),
-                            actual: alloc::format!("{:?}", self.get_value_type(&value)),
-                        });
-                    }
-                }
-            }
-            ValidationRule::Range { min, max } => {
-                if let Ok(ConfigValue::Int(i)) = config.get(key) {
-                    let val = i as f64;
-                    if val < min || val > max {
-                        return Err(ValidationError::ValueOutOfRange {
-                            field: key.into(),
-                            value: val,
-                            min,
-                            max,
-                        });
-                    }
-                } else if let Ok(ConfigValue::Float(f)) = config.get(key) {
-                    if f < min || f > max {
-                        return Err(ValidationError::ValueOutOfRange {
-                            field: key.into(),
-                            value: f,
-                            min,
-                            max,
-                        });
-                    }
-                }
+    /// Validate a single rule. Several failing fields of an object rule
+    /// are reported together as a [`ValidationError::NestedError`].
+    fn validate_rule(&self, key: &str, rule: &ValidationRule, config: &ConfigManager) -> core::result::Result<(), ValidationError> {
+        let mut errors = alloc::vec::Vec::new();
+        self.check_rule(key, rule, config, &mut errors);
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ValidationError::NestedError {
+                field: key.into(),
+                errors: errors.into_iter().map(Box::new).collect(),
+            }),
+        }
+    }
+
+    /// Check `rule` against the value at `key`. Object rules check each of
+    /// their fields at `key.field`, so errors carry the full dotted path.
+    fn check_rule(
+        &self,
+        key: &str,
+        rule: &ValidationRule,
+        config: &ConfigManager,
+        errors: &mut alloc::vec::Vec<ValidationError>,
+    ) {
+        if let ValidationRule::Object(fields) = rule {
+            for (field, field_rule) in fields {
+                self.check_rule(&alloc::format!("{}.{}", key, field), field_rule, config, errors);
             }
-            ValidationRule::Length { min, max } => {
-                if let Ok(ConfigValue::String(s)) = config.get(key) {
-                    let len = s.len();
-                    if len < min || len > max {
-                        return Err(ValidationError::InvalidLength {
-                            field: key.into(),
-                            length: len,
-                            min,
-                            max,
-                        });
-                    }
-                } else if let Ok(ConfigValue::Array(arr)) = config.get(key) {
-                    let len = arr.len();
-                    if len < min || len > max {
-                        return Err(ValidationError::InvalidLength {
-                            field: key.into(),
-                            length: len,
-                            min,
-                            max,
-                        });
-                    }
-                }
+            return;
+        }
+
+        let value = lookup(config, key);
+        let error = match (rule, &value) {
+            (ValidationRule::Required, None) => Some(ValidationError::MissingRequiredField {
+                field: key.into(),
+            }),
+            (ValidationRule::Type(expected_type), Some(value)) if !self.check_type(value, expected_type) => {
+                Some(ValidationError::TypeMismatch {
+                    field: key.into(),
+                    expected: alloc::format!("{:?}", expected_type),
+                    actual: alloc::format!("{:?}", self.get_value_type(value)),
+                })
             }
-            ValidationRule::Pattern(pattern) => {
-                if let Ok(ConfigValue::String(s)) = config.get(key) {
-                    if !self.matches_pattern(s, pattern) {
-                        return Err(ValidationError::PatternMismatch {
-                            field: key.into(),
-                            value: s.clone(),
-                            pattern: pattern.clone(),
-                        });
-                    }
-                }
+            (ValidationRule::Range { min, max }, Some(value)) => {
+                let number = match value {
+                    ConfigValue::Int(i) => Some(*i as f64),
+                    ConfigValue::Float(f) => Some(*f),
+                    _ => None,
+                };
+                number
+                    .filter(|n| n < min || n > max)
+                    .map(|n| ValidationError::ValueOutOfRange {
+                        field: key.into(),
+                        value: n,
+                        min: *min,
+                        max: *max,
+                    })
             }
-            ValidationRule::OneOf(values) => {
-                if let Ok(value) = config.get(key) {
-                    if !values.contains(&value) {
-                        return Err(ValidationError::InvalidValue {
-                            field: key.into(),
-                            value: alloc::format!("{:?}", value),
-                            allowed: values.iter().map(|v| alloc::format!("{:?}", v)).collect(),
-                        });
-                    }
-                }
+            (ValidationRule::Length { min, max }, Some(value)) => {
+                let length = match value {
+                    ConfigValue::String(s) => Some(s.len()),
+                    ConfigValue::Array(arr) => Some(arr.len()),
+                    _ => None,
+                };
+                length
+                    .filter(|len| len < min || len > max)
+                    .map(|len| ValidationError::InvalidLength {
+                        field: key.into(),
+                        length: len,
+                        min: *min,
+                        max: *max,
+                    })
             }
-            ValidationRule::Object(nested_rules) => {
-                // For nested objects, we would recursively validate
-                // This is simplified for now
-                let _ = nested_rules;
+            (ValidationRule::Pattern(pattern), Some(ConfigValue::String(s))) if !self.matches_pattern(s, pattern) => {
+                Some(ValidationError::PatternMismatch {
+                    field: key.into(),
+                    value: s.clone(),
+                    pattern: pattern.clone(),
+                })
             }
-            ValidationRule::Custom(_) => {
-                // Custom validation would be implemented here
-                // This is a placeholder
+            (ValidationRule::OneOf(values), Some(value)) if !values.contains(value) => {
+                Some(ValidationError::InvalidValue {
+                    field: key.into(),
+                    value: alloc::format!("{:?}", value),
+                    allowed: values.iter().map(|v| alloc::format!("{:?}", v)).collect(),
+                })
             }
-        }
+            // Custom validation would be implemented here
+            _ => None,
+        };
 
-        Ok(())
+        errors.extend(error);
     }
 
     /// Check if a value matches the expected type
@@ -249,6 +237,38 @@ impl ValidationSchema {
     }
 }
 
+/// Value at a dotted `path`, either stored under that key or inside an
+/// object stored under a shorter one
+fn lookup(config: &ConfigManager, path: &str) -> Option<ConfigValue> {
+    if let Ok(value) = config.get(path) {
+        return Some(value);
+    }
+
+    let mut end = path.len();
+    while let Some(dot) = path[..end].rfind('.') {
+        end = dot;
+        if let Ok(root @ ConfigValue::Object(_)) = config.get(&path[..dot]) {
+            let mut value = &root;
+            for segment in path[dot + 1..].split('.') {
+                value = match value {
+                    ConfigValue::Object(fields) => fields.get(segment)?,
+                    _ => return None,
+                };
+            }
+            return Some(value.clone());
+        }
+    }
+    None
+}
+
+/// Levels of object rules nested in `rule`, 0 for a rule on a plain value
+fn rule_depth(rule: &ValidationRule) -> usize {
+    match rule {
+        ValidationRule::Object(fields) => 1 + fields.values().map(rule_depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
 /// Configuration validator
 #[derive(Debug)]
 pub struct ConfigValidator {
@@ -371,6 +391,34 @@ pub enum ValidationError {
     },
 }
 
+impl From<ValidationError> for ConfigError {
+    /// The failure as a [`ConfigError::ValidationError`]; a nested error is
+    /// reported by its first field
+    fn from(error: ValidationError) -> Self {
+        let (field, expected, actual) = match error {
+            ValidationError::MissingRequiredField { field } => (field, "a value".into(), "nothing".into()),
+            ValidationError::TypeMismatch { field, expected, actual } => (field, expected, actual),
+            ValidationError::ValueOutOfRange { field, value, min, max } => {
+                (field, alloc::format!("{} to {}", min, max), alloc::format!("{}", value))
+            }
+            ValidationError::InvalidLength { field, length, min, max } => {
+                (field, alloc::format!("length {} to {}", min, max), alloc::format!("length {}", length))
+            }
+            ValidationError::PatternMismatch { field, value, pattern } => {
+                (field, alloc::format!("a match for '{}'", pattern), value)
+            }
+            ValidationError::InvalidValue { field, value, allowed } => {
+                (field, alloc::format!("one of {}", allowed.join(", ")), value)
+            }
+            ValidationError::NestedError { field, errors } => match errors.into_iter().next() {
+                Some(error) => return Self::from(*error),
+                None => (field, "valid fields".into(), "invalid fields".into()),
+            },
+        };
+        ConfigError::ValidationError { field, expected, actual }
+    }
+}
+
 /// Validation warning types
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValidationWarning {
@@ -436,6 +484,61 @@ impl ConfigMigration {
         }
     }
 
+    /// Apply the chain of registered migrations leading from `from_version`
+    /// to the current version, returning the versions passed through.
+    ///
+    /// `config` is left untouched if there is no such chain or a step fails.
+    pub fn migrate_to_latest(
+        &self,
+        config: &mut alloc::collections::BTreeMap<alloc::string::String, ConfigValue>,
+        from_version: &str,
+    ) -> Result<alloc::vec::Vec<alloc::string::String>> {
+        let path = self.migration_path(from_version, &self.current_version).ok_or_else(|| {
+            ConfigError::MigrationError {
+                from_version: from_version.into(),
+                to_version: self.current_version.clone(),
+                details: "no chain of migrations connects these versions".into(),
+            }
+        })?;
+
+        let mut migrated = config.clone();
+        for step in path.windows(2) {
+            self.migrate(&mut migrated, &step[0], &step[1])?;
+        }
+        *config = migrated;
+        Ok(path)
+    }
+
+    /// Shortest chain of registered migrations from `from_version` to
+    /// `to_version`, listing every version on the way including both ends
+    pub fn migration_path(&self, from_version: &str, to_version: &str) -> Option<alloc::vec::Vec<alloc::string::String>> {
+        // Breadth-first search over the migration graph, remembering how
+        // each version was first reached
+        let mut reached_from = alloc::collections::BTreeMap::<&str, &str>::new();
+        let mut queue = alloc::collections::VecDeque::from([from_version]);
+
+        while let Some(version) = queue.pop_front() {
+            if version == to_version {
+                let mut path = alloc::vec![alloc::string::String::from(version)];
+                let mut current = version;
+                while let Some(&previous) = reached_from.get(current) {
+                    path.push(previous.into());
+                    current = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for (from, to) in self.migrations.keys() {
+                if from == version && to != from_version && !reached_from.contains_key(to.as_str()) {
+                    reached_from.insert(to, version);
+                    queue.push_back(to);
+                }
+            }
+        }
+        None
+    }
+
     /// Check if migration is needed
     pub fn needs_migration(&self, config_version: &str) -> bool {
         config_version != self.current_version
@@ -443,7 +546,7 @@ impl ConfigMigration {
 
     /// Get available migration paths
     pub fn available_migrations(&self) -> alloc::vec::Vec<(&alloc::string::String, &alloc::string::String)> {
-        self.migrations.keys().collect()
+        self.migrations.keys().map(|(from, to)| (from, to)).collect()
     }
 }
 
@@ -808,6 +911,64 @@ mod tests {
         assert!(schema.validate_rule("test.level", &oneof_rule, &config).is_err());
     }
 
+    #[test]
+    fn test_nested_object_validation() {
+        use alloc::collections::BTreeMap;
+
+        let schema = ValidationSchema::new("1.0".into())
+            .add_rule(
+                "database".into(),
+                ValidationRule::Object(BTreeMap::from([
+                    ("url".into(), ValidationRule::Required),
+                    (
+                        "pool".into(),
+                        ValidationRule::Object(BTreeMap::from([
+                            ("max".into(), ValidationRule::Type(ConfigValueType::Int)),
+                            ("min".into(), ValidationRule::Range { min: 0.0, max: 10.0 }),
+                        ])),
+                    ),
+                ])),
+            )
+            .add_rule(
+                "cache".into(),
+                ValidationRule::Object(BTreeMap::from([("ttl".into(), ValidationRule::Range { min: 0.0, max: 3600.0 })])),
+            );
+
+        let mut config = ConfigManager::new();
+        config.set("database.url".into(), ConfigValue::String("postgres://db".into())).unwrap();
+        config.set("database.pool.max".into(), ConfigValue::String("lots".into())).unwrap();
+        config.set("database.pool.min".into(), ConfigValue::Int(2)).unwrap();
+        // Objects stored whole are checked the same way
+        config
+            .set("cache".into(), ConfigValue::Object(BTreeMap::from([("ttl".into(), ConfigValue::Int(-1))])))
+            .unwrap();
+
+        let result = schema.validate(&config).unwrap();
+        assert_eq!(
+            result.errors,
+            vec![
+                ValidationError::ValueOutOfRange {
+                    field: "cache.ttl".into(),
+                    value: -1.0,
+                    min: 0.0,
+                    max: 3600.0,
+                },
+                ValidationError::TypeMismatch {
+                    field: "database.pool.max".into(),
+                    expected: "Int".into(),
+                    actual: "String".into(),
+                },
+            ]
+        );
+
+        let mut too_deep = ValidationRule::Required;
+        for _ in 0..=MAX_NESTING_DEPTH {
+            too_deep = ValidationRule::Object(BTreeMap::from([("inner".into(), too_deep)]));
+        }
+        let schema = ValidationSchema::new("1.0".into()).add_rule("root".into(), too_deep);
+        assert!(matches!(schema.validate(&config), Err(ConfigError::NestingTooDeep { .. })));
+    }
+
     #[test]
     fn test_validation_result() {
         let result = ValidationResult {
@@ -890,6 +1051,44 @@ mod tests {
         assert!(!migration.needs_migration("2.0"));
     }
 
+    #[test]
+    fn test_migrate_to_latest_chains_steps() {
+        let mut migration = ConfigMigration::new("2.0".into());
+        migration.add_migration(
+            "1.1".into(),
+            "2.0".into(),
+            MigrationRule::new()
+                .transform_key("server.address".into(), "server.host".into())
+                .add_default("server.tls".into(), ConfigValue::Bool(true)),
+        );
+        migration.add_migration(
+            "1.0".into(),
+            "1.1".into(),
+            MigrationRule::new().transform_key("host".into(), "server.address".into()),
+        );
+
+        let mut config = alloc::collections::BTreeMap::new();
+        config.insert("host".into(), ConfigValue::String("example.com".into()));
+
+        let path = migration.migrate_to_latest(&mut config, "1.0").unwrap();
+        assert_eq!(path, vec!["1.0", "1.1", "2.0"]);
+        assert_eq!(
+            config,
+            alloc::collections::BTreeMap::from([
+                ("server.host".into(), ConfigValue::String("example.com".into())),
+                ("server.tls".into(), ConfigValue::Bool(true)),
+            ])
+        );
+
+        // 0.9 has no migration out of it
+        let before = config.clone();
+        assert!(matches!(
+            migration.migrate_to_latest(&mut config, "0.9"),
+            Err(ConfigError::MigrationError { .. })
+        ));
+        assert_eq!(config, before);
+    }
+
     #[test]
     fn test_compatibility_checker() {
         let mut checker = CompatibilityChecker::new();
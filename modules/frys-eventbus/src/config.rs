@@ -1,5 +1,7 @@
 //! EventBus configuration types
 
+use core::time::Duration;
+
 /// EventBus configuration
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EventBusConfig {
@@ -24,6 +26,9 @@ pub struct EventBusConfig {
     /// Backpressure threshold (percentage of queue size)
     pub backpressure_threshold: usize,
 
+    /// What publishing does when a subscriber queue is full
+    pub backpressure_strategy: BackpressureStrategy,
+
     /// Maximum topic length
     pub max_topic_length: usize,
 
@@ -50,6 +55,13 @@ pub struct EventBusConfig {
 
     /// Discovery strategy
     pub discovery_strategy: alloc::string::String,
+
+    /// Where published events are kept until consumed
+    pub persistence: Persistence,
+
+    /// How long a persistent backend keeps consumed events for
+    /// [`EventBus::subscribe_from`](crate::EventBus::subscribe_from) replay
+    pub history_retention: Duration,
 }
 
 impl Default for EventBusConfig {
@@ -62,6 +74,7 @@ impl Default for EventBusConfig {
             enable_monitoring: true,
             enable_backpressure: true,
             backpressure_threshold: 80, // 80% of queue size
+            backpressure_strategy: BackpressureStrategy::DropNewest,
             max_topic_length: crate::MAX_TOPIC_LENGTH,
             max_payload_size: crate::MAX_PAYLOAD_SIZE,
             worker_threads: 4,
@@ -71,10 +84,48 @@ impl Default for EventBusConfig {
             node_id: "local-node".into(),
             cluster_peers: alloc::vec::Vec::new(),
             discovery_strategy: "static".into(),
+            persistence: Persistence::InMemory,
+            history_retention: Duration::from_secs(crate::DEFAULT_HISTORY_RETENTION_SECS),
         }
     }
 }
 
+/// What publishing does when a subscriber queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackpressureStrategy {
+    /// Wait until the subscriber frees space. Events dead-lettered by a
+    /// receiver never wait and are dropped instead.
+    Block,
+
+    /// Evict the oldest event waiting in the queue to make room
+    DropOldest,
+
+    /// Discard the published event for that subscriber
+    #[default]
+    DropNewest,
+
+    /// Fail the publish with [`EventBusError::QueueFull`](crate::EventBusError::QueueFull)
+    /// without delivering the event to any subscriber
+    RejectPublish,
+}
+
+/// Storage for published events awaiting consumption
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Persistence {
+    /// Events live only in subscriber queues and are lost on restart
+    #[default]
+    InMemory,
+
+    /// Events are logged to a write-ahead log and replayed to matching
+    /// subscribers after a restart
+    Wal {
+        /// Directory holding the log
+        dir: alloc::string::String,
+        /// Sync the log to disk after every write
+        sync_writes: bool,
+    },
+}
+
 /// Subscriber configuration
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SubscriberConfig {
@@ -92,6 +143,12 @@ pub struct SubscriberConfig {
 
     /// Maximum pending events before dropping
     pub max_pending: usize,
+
+    /// Delivery guarantee for this subscriber
+    pub delivery: DeliveryGuarantee,
+
+    /// Where events this subscriber cannot process are sent
+    pub dead_letter: DeadLetterConfig,
 }
 
 impl Default for SubscriberConfig {
@@ -102,10 +159,64 @@ impl Default for SubscriberConfig {
             enable_backpressure: true,
             backpressure_threshold: 80,
             max_pending: 10000,
+            delivery: DeliveryGuarantee::AtMostOnce,
+            dead_letter: DeadLetterConfig::default(),
+        }
+    }
+}
+
+/// Dead-letter routing for a subscription
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetterConfig {
+    /// Topic dead events are republished to
+    pub topic: alloc::string::String,
+
+    /// Handler failures after which an event is dead-lettered
+    pub max_handler_failures: u32,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            topic: crate::DEFAULT_DEAD_LETTER_TOPIC.into(),
+            max_handler_failures: crate::DEFAULT_MAX_HANDLER_FAILURES,
         }
     }
 }
 
+/// Delivery guarantee for a subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeliveryGuarantee {
+    /// Events are removed from the queue once received
+    #[default]
+    AtMostOnce,
+
+    /// Received events must be acknowledged, otherwise they are redelivered
+    /// once the visibility timeout expires
+    AtLeastOnce {
+        /// How long a received event stays invisible awaiting acknowledgement
+        visibility_timeout: Duration,
+        /// Redeliveries before the event is routed to the dead-letter topic
+        max_redeliveries: u32,
+    },
+}
+
+impl DeliveryGuarantee {
+    /// At-least-once delivery with the default visibility timeout and
+    /// redelivery limit
+    pub fn at_least_once() -> Self {
+        DeliveryGuarantee::AtLeastOnce {
+            visibility_timeout: Duration::from_millis(crate::DEFAULT_VISIBILITY_TIMEOUT_MS),
+            max_redeliveries: crate::DEFAULT_MAX_REDELIVERIES,
+        }
+    }
+
+    /// Whether received events must be acknowledged
+    pub fn requires_ack(&self) -> bool {
+        matches!(self, DeliveryGuarantee::AtLeastOnce { .. })
+    }
+}
+
 /// Publisher configuration
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PublisherConfig {
@@ -186,6 +297,8 @@ mod tests {
         assert!(config.enable_monitoring);
         assert!(config.enable_backpressure);
         assert!(config.backpressure_threshold > 0 && config.backpressure_threshold <= 100);
+        assert_eq!(config.persistence, Persistence::InMemory);
+        assert_eq!(config.backpressure_strategy, BackpressureStrategy::DropNewest);
     }
 
     #[test]
@@ -195,6 +308,22 @@ mod tests {
         assert!(config.queue_size > 0);
         assert!(config.enable_backpressure);
         assert!(config.max_pending > 0);
+        assert!(!config.delivery.requires_ack());
+        assert_eq!(config.dead_letter.topic, crate::DEFAULT_DEAD_LETTER_TOPIC);
+        assert!(config.dead_letter.max_handler_failures > 0);
+    }
+
+    #[test]
+    fn test_at_least_once_defaults() {
+        let delivery = DeliveryGuarantee::at_least_once();
+        assert!(delivery.requires_ack());
+        assert_eq!(
+            delivery,
+            DeliveryGuarantee::AtLeastOnce {
+                visibility_timeout: Duration::from_millis(crate::DEFAULT_VISIBILITY_TIMEOUT_MS),
+                max_redeliveries: crate::DEFAULT_MAX_REDELIVERIES,
+            }
+        );
     }
 
     #[test]
@@ -41,6 +41,16 @@ impl EventHeaders {
     }
 }
 
+/// Unix timestamp in milliseconds
+pub type Timestamp = u64;
+
+/// Current time as a [`Timestamp`]
+pub(crate) fn now_millis() -> Timestamp {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+}
+
 /// Event structure
 #[derive(Debug, Clone)]
 pub struct Event {
@@ -52,10 +62,12 @@ pub struct Event {
     pub headers: EventHeaders,
     /// Event priority
     pub priority: Priority,
-    /// Event timestamp
-    pub timestamp: u64, // Unix timestamp in milliseconds
+    /// Event timestamp, set when published if left at 0
+    pub timestamp: Timestamp,
     /// Event ID (optional, for tracking)
     pub id: Option<u64>,
+    /// Key selecting the ordered lane of partitioned subscriptions
+    pub partition_key: Option<alloc::string::String>,
 }
 
 impl Event {
@@ -66,8 +78,9 @@ impl Event {
             payload,
             headers: EventHeaders::new(),
             priority: Priority::Normal,
-            timestamp: 0, // Would be set to current time in real implementation
+            timestamp: 0,
             id: None,
+            partition_key: None,
         }
     }
 
@@ -89,6 +102,30 @@ impl Event {
         self
     }
 
+    /// Set partition key
+    pub fn with_partition_key(mut self, key: alloc::string::String) -> Self {
+        self.partition_key = Some(key);
+        self
+    }
+
+    /// Lane this event is routed to among `lanes` ordered lanes.
+    ///
+    /// Events sharing a partition key always map to the same lane; events
+    /// without one are spread by event ID.
+    pub fn partition(&self, lanes: usize) -> usize {
+        if lanes == 0 {
+            return 0;
+        }
+        let hash = match &self.partition_key {
+            // FNV-1a, stable across processes so replayed events keep their lane
+            Some(key) => key
+                .bytes()
+                .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)),
+            None => self.id.unwrap_or_default(),
+        };
+        (hash % lanes as u64) as usize
+    }
+
     /// Get payload as string (if valid UTF-8)
     pub fn payload_as_string(&self) -> Option<&str> {
         core::str::from_utf8(&self.payload).ok()
@@ -169,7 +206,7 @@ pub struct EventBus {
     /// Configuration
     config: EventBusConfig,
     /// Metrics collector
-    metrics: EventBusMetrics,
+    metrics: std::sync::Arc<EventBusMetrics>,
     /// Subscriber registry
     subscribers: alloc::collections::BTreeMap<u64, Subscriber>,
     /// Publisher registry
@@ -178,6 +215,10 @@ pub struct EventBus {
     next_subscriber_id: core::sync::atomic::AtomicU64,
     /// Next publisher ID
     next_publisher_id: core::sync::atomic::AtomicU64,
+    /// Next ID assigned to published events without one
+    next_event_id: std::sync::Arc<core::sync::atomic::AtomicU64>,
+    /// Delivery queues of live subscriptions
+    subscriptions: std::sync::Arc<crate::delivery::SubscriptionTable>,
     /// Distributed capabilities (optional)
     #[cfg(feature = "distributed")]
     distributed: Option<DistributedEventBus>,
@@ -233,38 +274,161 @@ impl EventBus {
             None
         };
 
+        // Recover undelivered events when a write-ahead log is configured
+        let ledger = crate::persistence::Ledger::open(&config.persistence, config.history_retention)?;
+        let subscriptions = std::sync::Arc::new(crate::delivery::SubscriptionTable::new(ledger, config.backpressure_strategy));
+        let next_event_id = subscriptions.max_recovered_event_id().map_or(1, |id| id + 1);
+
         Ok(Self {
             config,
-            metrics: EventBusMetrics::new(),
+            metrics: std::sync::Arc::new(EventBusMetrics::new().with_queues(subscriptions.clone())),
             subscribers: alloc::collections::BTreeMap::new(),
             publishers: alloc::collections::BTreeMap::new(),
             next_subscriber_id: core::sync::atomic::AtomicU64::new(1),
             next_publisher_id: core::sync::atomic::AtomicU64::new(1),
+            next_event_id: std::sync::Arc::new(core::sync::atomic::AtomicU64::new(next_event_id)),
+            subscriptions,
             #[cfg(feature = "distributed")]
             distributed,
         })
     }
 
     /// Subscribe to events with a topic filter
-    pub async fn subscribe(&mut self, topic_filter: &str, _filter: Filter) -> Result<SubscriberHandle> {
-        // Register subscription in distributed mode if enabled
+    pub async fn subscribe(&mut self, topic_filter: &str, filter: Filter) -> Result<SubscriberHandle> {
+        self.subscribe_with_config(topic_filter, filter, SubscriberConfig::default()).await
+    }
+
+    /// Subscribe to events with a topic filter and subscriber configuration.
+    ///
+    /// With [`DeliveryGuarantee::AtLeastOnce`] every received event must be
+    /// acknowledged through [`SubscriberHandle::ack`], otherwise it is
+    /// redelivered once its visibility timeout expires. With a persistent
+    /// backend, events recovered from the log that match `topic_filter` are
+    /// queued for the new subscriber; if they do not fit in its queue the
+    /// subscription fails with [`EventBusError::QueueFull`] and the events
+    /// stay outstanding.
+    ///
+    /// Events rejected by `filter` are never queued for the subscriber.
+    pub async fn subscribe_with_config(&mut self, topic_filter: &str, filter: Filter, config: SubscriberConfig) -> Result<SubscriberHandle> {
+        #[cfg(feature = "distributed")]
+        self.propose_subscription(topic_filter).await?;
+        self.add_subscription(topic_filter, filter, config, None, None)
+    }
+
+    /// Subscribe to events published at or after `since` (Unix
+    /// milliseconds), replaying logged events before live delivery starts.
+    ///
+    /// See [`EventBus::subscribe_from_with_config`].
+    pub async fn subscribe_from(&mut self, topic_filter: &str, since: Timestamp) -> Result<SubscriberHandle> {
+        self.subscribe_from_with_config(topic_filter, Filter::default(), SubscriberConfig::default(), since).await
+    }
+
+    /// Subscribe to events published at or after `since` (Unix
+    /// milliseconds) with a filter and subscriber configuration.
+    ///
+    /// Matching events still in the write-ahead log, consumed or not, are
+    /// queued in publish order before the subscription receives live
+    /// events. Publishing needs `&mut EventBus`, so no event can be
+    /// published between the replay and the switch to live delivery: each
+    /// event is received exactly once. Consumed events are only kept for
+    /// [`EventBusConfig::history_retention`].
+    ///
+    /// Fails with [`EventBusError::InvalidConfiguration`] without a
+    /// persistent backend, and with [`EventBusError::QueueFull`] if the
+    /// replayed events do not fit in the subscriber's queue.
+    pub async fn subscribe_from_with_config(
+        &mut self,
+        topic_filter: &str,
+        filter: Filter,
+        config: SubscriberConfig,
+        since: Timestamp,
+    ) -> Result<SubscriberHandle> {
+        if !self.subscriptions.is_persistent() {
+            return Err(EventBusError::InvalidConfiguration {
+                field: "persistence",
+                reason: "replay needs a persistent backend",
+            });
+        }
         #[cfg(feature = "distributed")]
+        self.propose_subscription(topic_filter).await?;
+        self.add_subscription(topic_filter, filter, config, None, Some(since))
+    }
+
+    /// Subscribe with `lanes` ordered lanes, returning one handle per lane.
+    ///
+    /// Each matching event goes to a single lane chosen by
+    /// [`Event::partition`], so events with the same partition key are
+    /// received in publish order from one handle while events with other
+    /// keys are consumed in parallel from the rest. Events routed to a lane
+    /// whose handle has unsubscribed are dropped.
+    pub async fn subscribe_partitioned(
+        &mut self,
+        topic_filter: &str,
+        lanes: usize,
+        config: SubscriberConfig,
+    ) -> Result<alloc::vec::Vec<SubscriberHandle>> {
+        if lanes == 0 {
+            return Err(EventBusError::InvalidConfiguration {
+                field: "lanes",
+                reason: "must be greater than 0",
+            });
+        }
+        #[cfg(feature = "distributed")]
+        self.propose_subscription(topic_filter).await?;
+        (0..lanes)
+            .map(|index| {
+                let lane = crate::delivery::Lane { index, count: lanes };
+                self.add_subscription(topic_filter, Filter::default(), config.clone(), Some(lane), None)
+            })
+            .collect()
+    }
+
+    /// Propose a subscription to the cluster via consensus
+    #[cfg(feature = "distributed")]
+    async fn propose_subscription(&mut self, topic_filter: &str) -> Result<()> {
         if let Some(distributed) = &mut self.distributed {
-            // Propose subscription via consensus
             let command = crate::distributed::ConsensusCommand::AddSubscription {
                 topic: topic_filter.to_string(),
                 subscriber: distributed.routing_table.local_node_id.clone(),
             };
             distributed.consensus.propose_command(command).await?;
         }
+        Ok(())
+    }
+
+    /// Create the delivery queue and handle of a subscription, replaying
+    /// history since a timestamp or else recovered events
+    fn add_subscription(
+        &mut self,
+        topic_filter: &str,
+        filter: Filter,
+        config: SubscriberConfig,
+        lane: Option<crate::delivery::Lane>,
+        since: Option<Timestamp>,
+    ) -> Result<SubscriberHandle> {
         let subscriber_id = self.next_subscriber_id.fetch_add(1, core::sync::atomic::Ordering::AcqRel);
         let subscriber_name = alloc::format!("subscriber_{}", subscriber_id);
 
+        let mut queue = crate::delivery::SubscriptionQueue::new(subscriber_id, topic_filter.into(), &config).with_filter(filter);
+        if let Some(lane) = lane {
+            queue = queue.with_lane(lane);
+        }
+        let queue = std::sync::Arc::new(queue);
+        self.subscriptions.insert(queue.clone());
+        let replayed = match since {
+            Some(since) => self.subscriptions.replay_history(&queue, since),
+            None => self.subscriptions.replay(&queue),
+        };
+        if let Err(e) = replayed {
+            self.subscriptions.remove(subscriber_id);
+            return Err(e);
+        }
+
         let subscriber = Subscriber::new(
             subscriber_id,
             subscriber_name,
             topic_filter.into(),
-            SubscriberConfig::default(),
+            config,
         );
 
         self.subscribers.insert(subscriber_id, subscriber);
@@ -272,7 +436,10 @@ impl EventBus {
 
         Ok(SubscriberHandle {
             id: subscriber_id,
-            eventbus: self as *const Self,
+            receiver: crate::delivery::Receiver {
+                queue,
+                table: self.subscriptions.clone(),
+            },
         })
     }
 
@@ -309,26 +476,18 @@ impl EventBus {
 
     /// Publish event locally
     async fn publish_local(&mut self, event: Event) -> Result<()> {
-        self.metrics.record_event_published();
-
-        // Route event to interested subscribers
-        // This is a simplified implementation - real routing would be more sophisticated
-        let interested_subscribers: alloc::vec::Vec<&Subscriber> = self.subscribers.values()
-            .filter(|sub| sub.is_interested(&event))
-            .collect();
-
-        if interested_subscribers.is_empty() {
-            self.metrics.record_event_dropped();
-            return Ok(());
-        }
+        self.local_publisher().publish(event).await
+    }
 
-        // In a real implementation, events would be queued for each subscriber
-        // For now, we just record delivery
-        for _subscriber in interested_subscribers {
-            self.metrics.record_event_delivered();
+    /// Handle publishing to the subscribers on this node without
+    /// borrowing the bus, e.g. so a lock around it need not be held while
+    /// publishing waits for queue space
+    pub fn local_publisher(&self) -> LocalPublisher {
+        LocalPublisher {
+            metrics: self.metrics.clone(),
+            next_event_id: self.next_event_id.clone(),
+            subscriptions: self.subscriptions.clone(),
         }
-
-        Ok(())
     }
 
     /// Publish event in distributed mode
@@ -397,8 +556,8 @@ impl EventBus {
 
     /// Shutdown the eventbus
     pub async fn shutdown(self) -> Result<()> {
-        // Cleanup resources
-        // In a real implementation, this would wait for pending operations
+        // Wake receivers so they observe the closed subscriptions
+        self.subscriptions.close_all();
         Ok(())
     }
 
@@ -433,25 +592,137 @@ impl EventBus {
 #[derive(Debug)]
 pub struct SubscriberHandle {
     id: u64,
-    eventbus: *const EventBus,
+    receiver: crate::delivery::Receiver,
 }
 
 impl SubscriberHandle {
-    /// Receive an event (blocking)
+    /// Subscriber ID
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Receive an event, waiting until one is available.
+    ///
+    /// Returns `None` once the subscription is closed.
     pub async fn receive(&self) -> Option<Event> {
-        // Simplified - in real implementation, this would dequeue from subscriber's queue
-        None
+        self.receiver.receive().await
     }
 
     /// Try to receive an event (non-blocking)
     pub fn try_receive(&self) -> Option<Event> {
-        // Simplified - in real implementation, this would try to dequeue from subscriber's queue
-        None
+        self.receiver.try_receive()
+    }
+
+    /// Acknowledge a received event so it is not redelivered.
+    ///
+    /// Returns `false` if the event was not awaiting acknowledgement, e.g.
+    /// because it was already acknowledged or its visibility timeout expired.
+    pub fn ack(&self, event_id: u64) -> bool {
+        self.receiver.ack(event_id)
+    }
+
+    /// Report that processing a received event failed.
+    ///
+    /// The event is redelivered immediately, or republished on the
+    /// subscription's dead-letter topic once it has failed
+    /// `max_handler_failures` times. Returns `false` if the event was not
+    /// awaiting acknowledgement.
+    pub fn nack(&self, event_id: u64, reason: &str) -> bool {
+        self.receiver.nack(event_id, reason)
+    }
+
+    /// Receive the next event and run `handler` on it, acknowledging the
+    /// event on success and reporting the failure otherwise.
+    ///
+    /// Returns `None` once the subscription is closed.
+    pub async fn handle_next<F, Fut>(&self, handler: F) -> Option<Result<()>>
+    where
+        F: FnOnce(Event) -> Fut,
+        Fut: core::future::Future<Output = Result<()>>,
+    {
+        let event = self.receive().await?;
+        let event_id = event.id;
+        let result = handler(event).await;
+
+        if let Some(event_id) = event_id {
+            match &result {
+                Ok(()) => {
+                    self.ack(event_id);
+                }
+                Err(error) => {
+                    self.nack(event_id, &alloc::format!("{}", error));
+                }
+            }
+        }
+        Some(result)
+    }
+
+    /// Number of received events awaiting acknowledgement
+    pub fn pending_acks(&self) -> usize {
+        self.receiver.queue.pending_acks()
     }
 
     /// Unsubscribe
     pub async fn unsubscribe(self) -> Result<()> {
-        // In a real implementation, this would remove the subscriber from the registry
+        self.receiver
+            .table
+            .remove(self.id)
+            .map(|_| ())
+            .ok_or(EventBusError::SubscriberNotFound { subscriber_id: self.id })
+    }
+}
+
+/// Handle publishing to the subscribers on one node, from
+/// [`EventBus::local_publisher`]
+#[derive(Debug, Clone)]
+pub struct LocalPublisher {
+    metrics: std::sync::Arc<EventBusMetrics>,
+    next_event_id: std::sync::Arc<core::sync::atomic::AtomicU64>,
+    subscriptions: std::sync::Arc<crate::delivery::SubscriptionTable>,
+}
+
+impl LocalPublisher {
+    /// Publish an event to the subscribers on this node
+    pub async fn publish(&self, mut event: Event) -> Result<()> {
+        self.metrics.record_event_published();
+        self.metrics.record_topic_published(&event.topic);
+
+        // Acknowledgements are keyed by event ID
+        if event.id.is_none() {
+            event.id = Some(self.next_event_id.fetch_add(1, core::sync::atomic::Ordering::AcqRel));
+        }
+        if event.timestamp == 0 {
+            event.timestamp = now_millis();
+        }
+
+        // Queue the event for each interested subscriber
+        let routed = match self.subscriptions.publish(&event).await {
+            Ok(routed) => routed,
+            Err(error @ EventBusError::QueueFull { .. }) => {
+                self.metrics.record_event_rejected();
+                self.metrics.record_backpressure_event();
+                return Err(error);
+            }
+            Err(error) => return Err(error),
+        };
+
+        if routed.delivered == 0 && routed.dropped == 0 {
+            self.metrics.record_event_dropped();
+            self.metrics.record_topic_dropped(&event.topic, 1);
+            return Ok(());
+        }
+
+        self.metrics.record_topic_delivered(&event.topic, routed.delivered as u64);
+        self.metrics.record_topic_dropped(&event.topic, routed.dropped as u64);
+
+        for _ in 0..routed.delivered {
+            self.metrics.record_event_delivered();
+        }
+        for _ in 0..routed.dropped {
+            self.metrics.record_event_dropped();
+            self.metrics.record_backpressure_event();
+        }
+
         Ok(())
     }
 }
@@ -471,12 +742,6 @@ impl PublisherHandle {
     }
 }
 
-/// Filter for event matching (placeholder)
-#[derive(Debug, Clone, Default)]
-pub struct Filter {
-    // Placeholder for filter implementation
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -550,4 +815,529 @@ mod tests {
         assert_eq!(metrics.events_published.load(core::sync::atomic::Ordering::Acquire), 1);
         assert_eq!(metrics.events_delivered.load(core::sync::atomic::Ordering::Acquire), 1);
     }
+
+    #[tokio::test]
+    async fn test_at_most_once_receive() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let subscriber = eventbus.subscribe("test.*", Filter::default()).await.unwrap();
+
+        eventbus.publish(Event::new("test.message".into(), b"hello".to_vec())).await.unwrap();
+        eventbus.publish(Event::new("other.message".into(), vec![])).await.unwrap();
+
+        let event = subscriber.receive().await.unwrap();
+        assert_eq!(event.payload, b"hello");
+        assert!(event.id.is_some());
+        assert!(subscriber.try_receive().is_none());
+        assert_eq!(subscriber.pending_acks(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_at_least_once_redelivery_until_ack() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let config = SubscriberConfig {
+            delivery: DeliveryGuarantee::AtLeastOnce {
+                visibility_timeout: Duration::from_millis(50),
+                max_redeliveries: 5,
+            },
+            ..SubscriberConfig::default()
+        };
+        let subscriber = eventbus.subscribe_with_config("orders.*", Filter::default(), config).await.unwrap();
+
+        eventbus.publish(Event::new("orders.created".into(), b"order-1".to_vec())).await.unwrap();
+
+        // Receive without acking; the event stays invisible until the timeout
+        let first = subscriber.receive().await.unwrap();
+        let event_id = first.id.unwrap();
+        assert!(subscriber.try_receive().is_none());
+        assert_eq!(subscriber.pending_acks(), 1);
+
+        // Past the visibility timeout the event is redelivered
+        let second = tokio::time::timeout(Duration::from_secs(1), subscriber.receive()).await.unwrap().unwrap();
+        assert_eq!(second.id, Some(event_id));
+        assert_eq!(second.payload, b"order-1");
+        assert_eq!(second.headers.get(DELIVERY_COUNT_HEADER).map(String::as_str), Some("2"));
+
+        // Once acked it is never delivered again
+        assert!(subscriber.ack(event_id));
+        assert!(!subscriber.ack(event_id));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(subscriber.try_receive().is_none());
+        assert_eq!(subscriber.pending_acks(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_redeliveries_go_to_dead_letter_topic() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let config = SubscriberConfig {
+            delivery: DeliveryGuarantee::AtLeastOnce {
+                visibility_timeout: Duration::from_millis(10),
+                max_redeliveries: 1,
+            },
+            ..SubscriberConfig::default()
+        };
+        let subscriber = eventbus.subscribe_with_config("jobs.*", Filter::default(), config).await.unwrap();
+        let dead_letters = eventbus.subscribe(DEFAULT_DEAD_LETTER_TOPIC, Filter::default()).await.unwrap();
+
+        eventbus.publish(Event::new("jobs.run".into(), b"job".to_vec())).await.unwrap();
+
+        // Initial delivery plus one redelivery, never acked
+        assert!(subscriber.receive().await.is_some());
+        assert!(subscriber.receive().await.is_some());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(subscriber.try_receive().is_none());
+
+        let dead = dead_letters.try_receive().unwrap();
+        assert_eq!(dead.topic, DEFAULT_DEAD_LETTER_TOPIC);
+        assert_eq!(dead.payload, b"job");
+        assert_eq!(dead.headers.get(ORIGINAL_TOPIC_HEADER).map(String::as_str), Some("jobs.run"));
+        assert_eq!(dead.headers.get(DEATH_REASON_HEADER).map(String::as_str), Some(MAX_REDELIVERIES_EXCEEDED));
+    }
+
+    #[tokio::test]
+    async fn test_failing_handler_dead_letters_to_configured_topic() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let config = SubscriberConfig {
+            delivery: DeliveryGuarantee::at_least_once(),
+            dead_letter: DeadLetterConfig {
+                topic: "dlq.payments".into(),
+                max_handler_failures: 3,
+            },
+            ..SubscriberConfig::default()
+        };
+        let subscriber = eventbus.subscribe_with_config("payments.*", Filter::default(), config).await.unwrap();
+        let dead_letters = eventbus.subscribe("dlq.payments", Filter::default()).await.unwrap();
+
+        let event = Event::new("payments.charge".into(), b"42".to_vec())
+            .with_header("tenant".into(), "acme".into());
+        eventbus.publish(event).await.unwrap();
+
+        for attempt in 1..=3 {
+            let result = subscriber
+                .handle_next(|_event| async {
+                    Err(EventBusError::RoutingFailed {
+                        topic: "payments.charge".into(),
+                        reason: "card declined",
+                    })
+                })
+                .await
+                .unwrap();
+            assert!(result.is_err());
+
+            let dead = dead_letters.try_receive();
+            assert_eq!(dead.is_some(), attempt == 3, "attempt {}", attempt);
+            if let Some(dead) = dead {
+                assert_eq!(dead.topic, "dlq.payments");
+                assert_eq!(dead.payload, b"42");
+                assert_eq!(dead.headers.get("tenant").map(String::as_str), Some("acme"));
+                assert_eq!(dead.headers.get(ORIGINAL_TOPIC_HEADER).map(String::as_str), Some("payments.charge"));
+                assert_eq!(dead.headers.get(DELIVERY_COUNT_HEADER).map(String::as_str), None);
+                assert_eq!(
+                    dead.headers.get(DEATH_REASON_HEADER).map(String::as_str),
+                    Some("handler_failed: Routing failed for topic 'payments.charge': card declined")
+                );
+            }
+        }
+
+        assert!(subscriber.try_receive().is_none());
+        assert_eq!(subscriber.pending_acks(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_wal_redelivers_unconsumed_events_after_restart() {
+        let dir = std::env::temp_dir().join(alloc::format!("frys-eventbus-restart-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = EventBusConfig {
+            persistence: Persistence::Wal {
+                dir: dir.to_string_lossy().into_owned(),
+                sync_writes: true,
+            },
+            ..EventBusConfig::default()
+        };
+        let subscriber_config = SubscriberConfig {
+            delivery: DeliveryGuarantee::at_least_once(),
+            ..SubscriberConfig::default()
+        };
+
+        {
+            let mut eventbus = EventBus::new(config.clone()).await.unwrap();
+            let subscriber = eventbus
+                .subscribe_with_config("orders.*", Filter::default(), subscriber_config.clone())
+                .await
+                .unwrap();
+            for payload in [b"first", b"secnd", b"third"] {
+                eventbus.publish(Event::new("orders.created".into(), payload.to_vec())).await.unwrap();
+            }
+
+            // Only the first event is processed before the crash
+            let first = subscriber.receive().await.unwrap();
+            assert!(subscriber.ack(first.id.unwrap()));
+            assert!(subscriber.receive().await.is_some());
+            // Dropped without shutdown, as in a crash
+        }
+
+        let mut eventbus = EventBus::new(config.clone()).await.unwrap();
+        // A backlog that does not fit fails the subscription and stays
+        // outstanding for the next one
+        let small = SubscriberConfig {
+            queue_size: 1,
+            ..subscriber_config.clone()
+        };
+        assert!(matches!(
+            eventbus.subscribe_with_config("orders.*", Filter::default(), small).await,
+            Err(EventBusError::QueueFull { max_size: 1, .. })
+        ));
+        let subscriber = eventbus
+            .subscribe_with_config("orders.*", Filter::default(), subscriber_config.clone())
+            .await
+            .unwrap();
+
+        let second = subscriber.try_receive().unwrap();
+        let third = subscriber.try_receive().unwrap();
+        assert_eq!((second.payload.as_slice(), third.payload.as_slice()), (&b"secnd"[..], &b"third"[..]));
+        assert!(subscriber.try_receive().is_none());
+
+        // New events do not reuse recovered IDs
+        eventbus.publish(Event::new("orders.created".into(), b"fourth".to_vec())).await.unwrap();
+        let fourth = subscriber.try_receive().unwrap();
+        assert!(fourth.id > third.id);
+
+        for event in [&second, &third, &fourth] {
+            assert!(subscriber.ack(event.id.unwrap()));
+        }
+        drop(subscriber);
+        drop(eventbus);
+
+        // Everything was consumed, so nothing is replayed
+        let mut eventbus = EventBus::new(config).await.unwrap();
+        let subscriber = eventbus.subscribe_with_config("orders.*", Filter::default(), subscriber_config).await.unwrap();
+        assert!(subscriber.try_receive().is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_from_replays_history_before_live_events() {
+        let dir = std::env::temp_dir().join(alloc::format!("frys-eventbus-replay-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = EventBusConfig {
+            persistence: Persistence::Wal {
+                dir: dir.to_string_lossy().into_owned(),
+                sync_writes: false,
+            },
+            ..EventBusConfig::default()
+        };
+        let mut eventbus = EventBus::new(config).await.unwrap();
+        let early = eventbus.subscribe("orders.*", Filter::default()).await.unwrap();
+
+        let start = now_millis();
+        let mut old = Event::new("orders.created".into(), b"old".to_vec());
+        old.timestamp = start - 60_000;
+        eventbus.publish(old).await.unwrap();
+        eventbus.publish(Event::new("orders.created".into(), b"first".to_vec())).await.unwrap();
+        eventbus.publish(Event::new("users.created".into(), b"other".to_vec())).await.unwrap();
+        // Consumed events are replayed too
+        assert_eq!(core::iter::from_fn(|| early.try_receive()).count(), 2);
+        eventbus.publish(Event::new("orders.shipped".into(), b"second".to_vec())).await.unwrap();
+
+        let late = eventbus.subscribe_from("orders.*", start).await.unwrap();
+        eventbus.publish(Event::new("orders.created".into(), b"live".to_vec())).await.unwrap();
+
+        let received = core::iter::from_fn(|| late.try_receive()).map(|event| event.payload).collect::<alloc::vec::Vec<_>>();
+        assert_eq!(received, [b"first".to_vec(), b"second".to_vec(), b"live".to_vec()]);
+
+        // History that does not fit the queue fails the subscription
+        // rather than being cut short
+        let small = SubscriberConfig {
+            queue_size: 2,
+            ..SubscriberConfig::default()
+        };
+        assert!(matches!(
+            eventbus.subscribe_from_with_config("orders.*", Filter::default(), small, start).await,
+            Err(EventBusError::QueueFull { max_size: 2, .. })
+        ));
+        eventbus.publish(Event::new("orders.created".into(), b"after".to_vec())).await.unwrap();
+        assert_eq!(late.try_receive().unwrap().payload, b"after".to_vec());
+
+        // Without a persistent backend there is nothing to replay from
+        let mut in_memory = EventBus::new(EventBusConfig::default()).await.unwrap();
+        assert!(matches!(
+            in_memory.subscribe_from("orders.*", start).await,
+            Err(EventBusError::InvalidConfiguration { field: "persistence", .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_stops_delivery() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let subscriber = eventbus.subscribe("test.*", Filter::default()).await.unwrap();
+        let other = eventbus.subscribe("test.*", Filter::default()).await.unwrap();
+
+        subscriber.unsubscribe().await.unwrap();
+        eventbus.publish(Event::new("test.message".into(), vec![])).await.unwrap();
+        assert!(other.try_receive().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_header_filter_skips_queue() {
+        let config = EventBusConfig {
+            backpressure_strategy: BackpressureStrategy::RejectPublish,
+            ..EventBusConfig::default()
+        };
+        let mut eventbus = EventBus::new(config).await.unwrap();
+        let subscriber_config = SubscriberConfig {
+            queue_size: 1,
+            ..SubscriberConfig::default()
+        };
+        let subscriber = eventbus
+            .subscribe_with_config("orders.*", Filter::new().header_eq("region", "eu"), subscriber_config)
+            .await
+            .unwrap();
+
+        // Rejected events take no queue space, so the single slot stays free
+        for region in ["us", "apac", "eu"] {
+            let mut event = Event::new("orders.created".into(), region.as_bytes().to_vec());
+            event.headers.set("region".into(), region.into());
+            eventbus.publish(event).await.unwrap();
+        }
+        eventbus.publish(Event::new("orders.created".into(), vec![])).await.unwrap();
+
+        assert_eq!(subscriber.try_receive().unwrap().payload, b"eu");
+        assert!(subscriber.try_receive().is_none());
+    }
+
+    #[cfg(feature = "filters")]
+    #[tokio::test]
+    async fn test_payload_path_filter() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let large = eventbus
+            .subscribe("orders.*", Filter::new().payload_path("$.order.total", PathPredicate::GreaterThan(1000.0)).unwrap())
+            .await
+            .unwrap();
+        let all = eventbus.subscribe("orders.*", Filter::default()).await.unwrap();
+
+        for payload in [r#"{"order": {"total": 250}}"#, r#"{"order": {"total": 4999.5}}"#, r#"{"order": {}}"#, "binary"] {
+            eventbus.publish(Event::new("orders.created".into(), payload.as_bytes().to_vec())).await.unwrap();
+        }
+
+        assert_eq!(large.try_receive().unwrap().payload, br#"{"order": {"total": 4999.5}}"#);
+        assert!(large.try_receive().is_none());
+        assert_eq!(core::iter::from_fn(|| all.try_receive()).count(), 4);
+    }
+
+    async fn single_slot_bus(strategy: BackpressureStrategy) -> (EventBus, SubscriberHandle) {
+        let config = EventBusConfig {
+            backpressure_strategy: strategy,
+            ..EventBusConfig::default()
+        };
+        let mut eventbus = EventBus::new(config).await.unwrap();
+        let subscriber_config = SubscriberConfig {
+            queue_size: 1,
+            ..SubscriberConfig::default()
+        };
+        let subscriber = eventbus.subscribe_with_config("jobs.*", Filter::default(), subscriber_config).await.unwrap();
+        (eventbus, subscriber)
+    }
+
+    fn job(payload: &[u8]) -> Event {
+        Event::new("jobs.run".into(), payload.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_drop_newest() {
+        let (mut eventbus, subscriber) = single_slot_bus(BackpressureStrategy::DropNewest).await;
+
+        eventbus.publish(job(b"first")).await.unwrap();
+        eventbus.publish(job(b"second")).await.unwrap();
+
+        assert_eq!(subscriber.try_receive().unwrap().payload, b"first");
+        assert!(subscriber.try_receive().is_none());
+        let snapshot = eventbus.metrics().snapshot();
+        assert_eq!(snapshot.events_dropped, 1);
+        assert_eq!(snapshot.events_delivered, 1);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_drop_oldest() {
+        let (mut eventbus, subscriber) = single_slot_bus(BackpressureStrategy::DropOldest).await;
+
+        eventbus.publish(job(b"first")).await.unwrap();
+        eventbus.publish(job(b"second")).await.unwrap();
+
+        assert_eq!(subscriber.try_receive().unwrap().payload, b"second");
+        assert!(subscriber.try_receive().is_none());
+        let snapshot = eventbus.metrics().snapshot();
+        assert_eq!(snapshot.events_dropped, 1);
+        assert_eq!(snapshot.events_delivered, 2);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_reject_publish() {
+        let (mut eventbus, subscriber) = single_slot_bus(BackpressureStrategy::RejectPublish).await;
+        let roomy = eventbus.subscribe("jobs.*", Filter::default()).await.unwrap();
+
+        eventbus.publish(job(b"first")).await.unwrap();
+        assert!(matches!(
+            eventbus.publish(job(b"second")).await,
+            Err(EventBusError::QueueFull { current_size: 1, max_size: 1 })
+        ));
+
+        // A rejected event reaches no subscriber, not even one with room
+        assert_eq!(subscriber.try_receive().unwrap().payload, b"first");
+        assert_eq!(roomy.try_receive().unwrap().payload, b"first");
+        assert!(roomy.try_receive().is_none());
+
+        let snapshot = eventbus.metrics().snapshot();
+        assert_eq!(snapshot.events_rejected, 1);
+        assert_eq!(snapshot.events_dropped, 0);
+
+        // Once drained, publishing succeeds again
+        eventbus.publish(job(b"third")).await.unwrap();
+        assert_eq!(subscriber.try_receive().unwrap().payload, b"third");
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_block_waits_for_space() {
+        let (mut eventbus, subscriber) = single_slot_bus(BackpressureStrategy::Block).await;
+        eventbus.publish(job(b"first")).await.unwrap();
+
+        let consumer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let first = subscriber.receive().await.unwrap();
+            let second = subscriber.receive().await.unwrap();
+            (first.payload, second.payload)
+        });
+
+        // Completes only once the consumer frees the slot
+        tokio::time::timeout(Duration::from_secs(1), eventbus.publish(job(b"second")))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(consumer.await.unwrap(), (b"first".to_vec(), b"second".to_vec()));
+        let snapshot = eventbus.metrics().snapshot();
+        assert_eq!(snapshot.events_dropped, 0);
+        assert_eq!(snapshot.events_delivered, 2);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_local_publisher_does_not_borrow_bus() {
+        let (mut eventbus, subscriber) = single_slot_bus(BackpressureStrategy::Block).await;
+        eventbus.publish(job(b"first")).await.unwrap();
+
+        // Waits for space on its own while the bus stays usable
+        let publisher = eventbus.local_publisher();
+        let blocked = tokio::spawn(async move { publisher.publish(job(b"second")).await });
+        let other = eventbus.subscribe("reports.*", Filter::default()).await.unwrap();
+        eventbus.publish(Event::new("reports.daily".into(), b"report".to_vec())).await.unwrap();
+        assert_eq!(other.try_receive().unwrap().payload, b"report");
+
+        let first = subscriber.receive().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), blocked).await.unwrap().unwrap().unwrap();
+        let second = subscriber.receive().await.unwrap();
+        assert_eq!((first.payload, second.payload), (b"first".to_vec(), b"second".to_vec()));
+        assert_eq!(eventbus.metrics().snapshot().events_delivered, 3);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_block_gives_up_on_closed_queue() {
+        let (mut eventbus, subscriber) = single_slot_bus(BackpressureStrategy::Block).await;
+        eventbus.publish(job(b"first")).await.unwrap();
+
+        let unsubscribe = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            subscriber.unsubscribe().await.unwrap();
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), eventbus.publish(job(b"second")))
+            .await
+            .unwrap()
+            .unwrap();
+        unsubscribe.await.unwrap();
+        assert_eq!(eventbus.metrics().snapshot().events_dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_lanes_preserve_per_key_order() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let lanes = eventbus
+            .subscribe_partitioned("user.*", 2, SubscriberConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(lanes.len(), 2);
+
+        let event = |key: &str, seq: usize| {
+            Event::new("user.updated".into(), alloc::format!("{}:{}", key, seq).into_bytes())
+                .with_partition_key(key.into())
+        };
+        assert_ne!(event("user-1", 0).partition(2), event("user-2", 0).partition(2));
+
+        // Interleave the two keys
+        for seq in 0..10 {
+            eventbus.publish(event("user-1", seq)).await.unwrap();
+            eventbus.publish(event("user-2", seq)).await.unwrap();
+        }
+
+        // Each lane is consumed independently
+        let consumers: alloc::vec::Vec<_> = lanes
+            .into_iter()
+            .map(|lane| {
+                tokio::spawn(async move {
+                    let mut received = alloc::vec::Vec::new();
+                    while let Some(event) = lane.try_receive() {
+                        received.push(String::from_utf8(event.payload).unwrap());
+                    }
+                    received
+                })
+            })
+            .collect();
+
+        for consumer in consumers {
+            let received = consumer.await.unwrap();
+            assert_eq!(received.len(), 10);
+            let key = received[0].split(':').next().unwrap().to_string();
+            let expected: alloc::vec::Vec<String> = (0..10).map(|seq| alloc::format!("{}:{}", key, seq)).collect();
+            assert_eq!(received, expected);
+        }
+        assert_eq!(eventbus.metrics().snapshot().events_delivered, 20);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_requires_lanes() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        assert!(matches!(
+            eventbus.subscribe_partitioned("user.*", 0, SubscriberConfig::default()).await,
+            Err(EventBusError::InvalidConfiguration { field: "lanes", .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_topic_metrics_and_queue_lag() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let fast = eventbus.subscribe("orders.*", Filter::default()).await.unwrap();
+        let slow = eventbus.subscribe("audit.*", Filter::default()).await.unwrap();
+
+        for i in 0..5u8 {
+            eventbus.publish(Event::new("orders.created".into(), alloc::vec![i])).await.unwrap();
+            assert!(fast.try_receive().is_some());
+            eventbus.publish(Event::new("audit.login".into(), alloc::vec![i])).await.unwrap();
+        }
+        eventbus.publish(Event::new("billing.unwatched".into(), b"lost".to_vec())).await.unwrap();
+        slow.try_receive().unwrap();
+
+        let snapshot = eventbus.metrics().snapshot();
+        assert_eq!(snapshot.topics["orders.created"], TopicMetrics { published: 5, delivered: 5, dropped: 0 });
+        assert_eq!(snapshot.topics["audit.login"], TopicMetrics { published: 5, delivered: 5, dropped: 0 });
+        assert_eq!(snapshot.topics["billing.unwatched"], TopicMetrics { published: 1, delivered: 0, dropped: 1 });
+
+        assert_eq!(snapshot.queue_lag[&fast.id()].depth, 0);
+        let backlog = &snapshot.queue_lag[&slow.id()];
+        assert_eq!(backlog.topic_filter, "audit.*");
+        assert_eq!(backlog.depth, 4);
+
+        let slow_id = slow.id();
+        slow.unsubscribe().await.unwrap();
+        assert!(!eventbus.metrics().snapshot().queue_lag.contains_key(&slow_id));
+    }
 }
@@ -0,0 +1,679 @@
+//! Per-subscriber delivery queues with acknowledgement and redelivery
+
+use crate::persistence::Ledger;
+use crate::*;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Header carrying how many times an event has been delivered
+pub const DELIVERY_COUNT_HEADER: &str = "x-delivery-count";
+
+/// Header carrying the topic an event was originally published to
+pub const ORIGINAL_TOPIC_HEADER: &str = "x-original-topic";
+
+/// Header carrying why an event was moved to the dead-letter topic
+pub const DEATH_REASON_HEADER: &str = "x-death-reason";
+
+/// Death reason for events that were never acknowledged
+pub const MAX_REDELIVERIES_EXCEEDED: &str = "max_redeliveries_exceeded";
+
+/// Outcome of routing an event to the interested subscriptions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Routed {
+    /// Subscriptions the event was queued on
+    pub(crate) delivered: usize,
+    /// Events discarded because a queue was full, including evicted ones
+    pub(crate) dropped: usize,
+}
+
+/// Queues of every live subscription, shared by the bus and its handles
+#[derive(Debug, Default)]
+pub(crate) struct SubscriptionTable {
+    queues: RwLock<alloc::collections::BTreeMap<u64, Arc<SubscriptionQueue>>>,
+    /// Outstanding deliveries, persisted when a write-ahead log is configured
+    ledger: Ledger,
+    /// What routing does when a queue is full
+    strategy: BackpressureStrategy,
+}
+
+impl SubscriptionTable {
+    /// Create a table recording deliveries in `ledger`
+    pub(crate) fn new(ledger: Ledger, strategy: BackpressureStrategy) -> Self {
+        Self {
+            queues: RwLock::default(),
+            ledger,
+            strategy,
+        }
+    }
+
+    /// Highest event ID recovered from persistent storage
+    pub(crate) fn max_recovered_event_id(&self) -> Option<u64> {
+        self.ledger.max_event_id()
+    }
+
+    /// Queue recovered events matching a new subscription.
+    ///
+    /// Fails with [`EventBusError::QueueFull`] if they do not fit in the
+    /// queue, leaving the deliveries it replayed outstanding again.
+    pub(crate) fn replay(&self, queue: &SubscriptionQueue) -> Result<()> {
+        self.replay_events(queue, self.ledger.backlog(&queue.topic_filter))
+    }
+
+    /// Queue logged events matching a new subscription that were published
+    /// at or after `since`, whether or not they were consumed.
+    ///
+    /// Fails with [`EventBusError::QueueFull`] if they do not fit in the
+    /// queue, rather than silently skipping part of the history.
+    pub(crate) fn replay_history(&self, queue: &SubscriptionQueue, since: Timestamp) -> Result<()> {
+        self.replay_events(queue, self.ledger.history(&queue.topic_filter, since))
+    }
+
+    fn replay_events(&self, queue: &SubscriptionQueue, events: impl IntoIterator<Item = Event>) -> Result<()> {
+        let mut replayed = alloc::vec::Vec::new();
+        let result = events.into_iter().filter(|event| queue.accepts(event)).try_for_each(|event| {
+            queue.check_capacity()?;
+            self.ledger.delivered(&event, 1)?;
+            replayed.extend(event.id);
+            queue.enqueue(event)
+        });
+        if result.is_err() {
+            // The subscription is dropped, so its deliveries never happen
+            for event_id in replayed {
+                self.ledger.undelivered(event_id);
+            }
+        }
+        result
+    }
+
+    /// Whether published events are logged
+    pub(crate) fn is_persistent(&self) -> bool {
+        self.ledger.is_persistent()
+    }
+
+    /// Add a subscription queue
+    pub(crate) fn insert(&self, queue: Arc<SubscriptionQueue>) {
+        self.queues.write().unwrap_or_else(|e| e.into_inner()).insert(queue.id, queue);
+    }
+
+    /// Remove and close a subscription queue
+    pub(crate) fn remove(&self, subscriber_id: u64) -> Option<Arc<SubscriptionQueue>> {
+        let queue = self.queues.write().unwrap_or_else(|e| e.into_inner()).remove(&subscriber_id);
+        if let Some(queue) = &queue {
+            queue.close();
+        }
+        queue
+    }
+
+    /// Close every subscription queue
+    pub(crate) fn close_all(&self) {
+        for queue in self.queues.read().unwrap_or_else(|e| e.into_inner()).values() {
+            queue.close();
+        }
+    }
+
+    /// Backlog of every subscription, by subscriber ID
+    pub(crate) fn queue_lag(&self) -> alloc::collections::BTreeMap<u64, QueueLag> {
+        self.queues
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|queue| {
+                let state = queue.lock();
+                let lag = QueueLag {
+                    topic_filter: queue.topic_filter.clone(),
+                    depth: state.ready.len() as u64,
+                    pending_acks: state.in_flight.len() as u64,
+                };
+                (queue.id, lag)
+            })
+            .collect()
+    }
+
+    /// Subscriptions whose topic filter matches an event
+    fn interested(&self, event: &Event) -> alloc::vec::Vec<Arc<SubscriptionQueue>> {
+        self.queues
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|q| q.accepts(event))
+            .cloned()
+            .collect()
+    }
+
+    /// Enqueue a published event on every interested subscription,
+    /// applying the backpressure strategy to full queues.
+    ///
+    /// With [`BackpressureStrategy::Block`] this waits until each full queue
+    /// frees space or is closed. With [`BackpressureStrategy::RejectPublish`]
+    /// a full queue fails the whole publish before anything is queued.
+    pub(crate) async fn publish(&self, event: &Event) -> Result<Routed> {
+        let interested = self.interested(event);
+        if self.strategy == BackpressureStrategy::RejectPublish {
+            for queue in &interested {
+                queue.check_capacity()?;
+            }
+        }
+
+        // Log before enqueueing so a receiver cannot consume the event
+        // before its deliveries are recorded
+        self.ledger.delivered(event, interested.len())?;
+
+        let mut routed = Routed::default();
+        for queue in interested {
+            let mut event = event.clone();
+            loop {
+                let space = queue.space.notified();
+                tokio::pin!(space);
+                // Register interest before offering so freed space is not missed
+                space.as_mut().enable();
+
+                match queue.offer(event, self.strategy) {
+                    Offer::Full(returned) if self.strategy == BackpressureStrategy::Block && !queue.is_closed() => {
+                        event = returned;
+                        space.await;
+                    }
+                    offer => {
+                        self.settle(offer, &mut routed);
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(routed)
+    }
+
+    /// Enqueue an event on every interested subscription without waiting
+    /// for space
+    pub(crate) fn route(&self, event: &Event) -> Result<Routed> {
+        let interested = self.interested(event);
+        self.ledger.delivered(event, interested.len())?;
+
+        let mut routed = Routed::default();
+        for queue in interested {
+            let offer = queue.offer(event.clone(), self.strategy);
+            self.settle(offer, &mut routed);
+        }
+        Ok(routed)
+    }
+
+    /// Count an offer and retire the deliveries it discarded
+    fn settle(&self, offer: Offer, routed: &mut Routed) {
+        let discarded = match offer {
+            Offer::Queued => {
+                routed.delivered += 1;
+                return;
+            }
+            Offer::Evicted(oldest) => {
+                routed.delivered += 1;
+                oldest
+            }
+            Offer::Full(event) => event,
+        };
+        routed.dropped += 1;
+        if let Some(event_id) = discarded.id {
+            self.ledger.consumed(event_id);
+        }
+    }
+
+    /// Record that a delivery of an event was consumed
+    pub(crate) fn consumed(&self, event_id: u64) {
+        self.ledger.consumed(event_id);
+    }
+
+    /// Republish dead events on a dead-letter topic, keeping their headers
+    /// and recording why they died.
+    ///
+    /// Events that die on the dead-letter topic itself are dropped rather
+    /// than looped back onto it.
+    pub(crate) fn dead_letter(&self, topic: &str, events: alloc::vec::Vec<DeadEvent>) {
+        for DeadEvent { mut event, reason } in events {
+            let event_id = event.id;
+            if event.topic != topic {
+                let original_topic = ::core::mem::replace(&mut event.topic, topic.into());
+                event.headers.set(ORIGINAL_TOPIC_HEADER.into(), original_topic);
+                event.headers.set(DEATH_REASON_HEADER.into(), reason);
+                // A failed write leaves the original delivery outstanding,
+                // so the event is replayed rather than lost after a restart
+                if self.route(&event).is_err() {
+                    continue;
+                }
+            }
+            if let Some(event_id) = event_id {
+                self.ledger.consumed(event_id);
+            }
+        }
+    }
+}
+
+/// An event bound for the dead-letter topic
+#[derive(Debug)]
+pub(crate) struct DeadEvent {
+    event: Event,
+    reason: alloc::string::String,
+}
+
+/// What happened to an event after a handler failure
+#[derive(Debug)]
+enum NackOutcome {
+    /// The event is visible again for immediate redelivery
+    Redelivered,
+    /// The event must be moved to the dead-letter topic
+    DeadLettered(DeadEvent),
+}
+
+/// Result of offering an event to a subscription queue
+#[derive(Debug)]
+pub(crate) enum Offer {
+    /// The event was queued
+    Queued,
+    /// The event was queued after evicting this older event
+    Evicted(Event),
+    /// The queue is full; the event is handed back
+    Full(Event),
+}
+
+/// An event with its delivery history
+#[derive(Debug)]
+struct Tracked {
+    event: Event,
+    /// Times the event has been delivered
+    deliveries: u32,
+    /// Times a handler failed to process the event
+    failures: u32,
+}
+
+/// An event delivered to an at-least-once subscriber and not yet acknowledged
+#[derive(Debug)]
+struct InFlight {
+    tracked: Tracked,
+    deadline: Instant,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    /// Events waiting to be received
+    ready: alloc::collections::VecDeque<Tracked>,
+    /// Delivered, unacknowledged events by event ID
+    in_flight: alloc::collections::BTreeMap<u64, InFlight>,
+    closed: bool,
+}
+
+impl QueueState {
+    /// Events counted against the queue capacity
+    fn len(&self) -> usize {
+        self.ready.len() + self.in_flight.len()
+    }
+}
+
+/// One of the ordered lanes of a partitioned subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Lane {
+    /// Position of this lane
+    pub(crate) index: usize,
+    /// Number of lanes in the subscription
+    pub(crate) count: usize,
+}
+
+/// Delivery queue of a single subscription
+#[derive(Debug)]
+pub(crate) struct SubscriptionQueue {
+    id: u64,
+    topic_filter: alloc::string::String,
+    /// Lane of a partitioned subscription; `None` receives every match
+    lane: Option<Lane>,
+    /// Content filter applied before queueing
+    filter: Filter,
+    capacity: usize,
+    guarantee: DeliveryGuarantee,
+    dead_letter: DeadLetterConfig,
+    state: Mutex<QueueState>,
+    /// Signalled when an event is queued
+    notify: Notify,
+    /// Signalled when space is freed or the queue is closed
+    space: Notify,
+}
+
+impl SubscriptionQueue {
+    /// Create a queue for a subscription
+    pub(crate) fn new(id: u64, topic_filter: alloc::string::String, config: &SubscriberConfig) -> Self {
+        Self {
+            id,
+            topic_filter,
+            lane: None,
+            filter: Filter::default(),
+            capacity: config.queue_size,
+            guarantee: config.delivery,
+            dead_letter: config.dead_letter.clone(),
+            state: Mutex::new(QueueState::default()),
+            notify: Notify::new(),
+            space: Notify::new(),
+        }
+    }
+
+    /// Make this queue one lane of a partitioned subscription
+    pub(crate) fn with_lane(mut self, lane: Lane) -> Self {
+        self.lane = Some(lane);
+        self
+    }
+
+    /// Only queue events passing `filter`
+    pub(crate) fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Whether an event is routed to this queue
+    fn accepts(&self, event: &Event) -> bool {
+        event.matches_topic(&self.topic_filter)
+            && self.lane.is_none_or(|lane| event.partition(lane.count) == lane.index)
+            && self.filter.matches(event)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Fail with [`EventBusError::QueueFull`] if the queue has no room
+    pub(crate) fn check_capacity(&self) -> Result<()> {
+        let state = self.lock();
+        let current_size = state.len();
+        if current_size >= self.capacity {
+            return Err(EventBusError::QueueFull {
+                current_size,
+                max_size: self.capacity,
+            });
+        }
+        Ok(())
+    }
+
+    /// Queue an event for delivery
+    pub(crate) fn enqueue(&self, event: Event) -> Result<()> {
+        self.check_capacity()?;
+        self.offer(event, BackpressureStrategy::DropNewest);
+        Ok(())
+    }
+
+    /// Queue an event, evicting the oldest waiting event when the queue is
+    /// full and the strategy is [`BackpressureStrategy::DropOldest`]
+    pub(crate) fn offer(&self, event: Event, strategy: BackpressureStrategy) -> Offer {
+        let mut state = self.lock();
+        let mut evicted = None;
+        if state.len() >= self.capacity {
+            // In-flight events await acknowledgement and cannot be evicted
+            if strategy != BackpressureStrategy::DropOldest || state.ready.is_empty() {
+                return Offer::Full(event);
+            }
+            evicted = state.ready.pop_front().map(|oldest| oldest.event);
+        }
+        state.ready.push_back(Tracked { event, deliveries: 0, failures: 0 });
+        drop(state);
+        self.notify.notify_one();
+
+        match evicted {
+            Some(oldest) => Offer::Evicted(oldest),
+            None => Offer::Queued,
+        }
+    }
+
+    /// Take the next event, returning it along with any events that
+    /// exhausted their redeliveries
+    fn dequeue(&self, now: Instant) -> (Option<Event>, alloc::vec::Vec<DeadEvent>) {
+        let mut state = self.lock();
+        let mut dead = alloc::vec::Vec::new();
+        let size_before = state.len();
+
+        if let DeliveryGuarantee::AtLeastOnce { max_redeliveries, .. } = self.guarantee {
+            let expired: alloc::vec::Vec<u64> = state
+                .in_flight
+                .iter()
+                .filter(|(_, in_flight)| in_flight.deadline <= now)
+                .map(|(id, _)| *id)
+                .collect();
+
+            // Redeliver in the order events were originally published
+            for event_id in expired.into_iter().rev() {
+                if let Some(in_flight) = state.in_flight.remove(&event_id) {
+                    if in_flight.tracked.deliveries > max_redeliveries {
+                        dead.push(DeadEvent {
+                            event: in_flight.tracked.event,
+                            reason: MAX_REDELIVERIES_EXCEEDED.into(),
+                        });
+                    } else {
+                        state.ready.push_front(in_flight.tracked);
+                    }
+                }
+            }
+        }
+
+        let next = state.ready.pop_front().map(|mut tracked| {
+            tracked.deliveries += 1;
+            let mut event = tracked.event.clone();
+            if let (DeliveryGuarantee::AtLeastOnce { visibility_timeout, .. }, Some(event_id)) = (self.guarantee, event.id) {
+                event.headers.set(DELIVERY_COUNT_HEADER.into(), alloc::format!("{}", tracked.deliveries));
+                state.in_flight.insert(event_id, InFlight {
+                    tracked,
+                    deadline: now + visibility_timeout,
+                });
+            }
+            event
+        });
+
+        if state.len() < size_before {
+            drop(state);
+            self.space.notify_waiters();
+        }
+        (next, dead)
+    }
+
+    /// Earliest visibility deadline among in-flight events
+    fn next_deadline(&self) -> Option<Instant> {
+        self.lock().in_flight.values().map(|in_flight| in_flight.deadline).min()
+    }
+
+    /// Acknowledge an in-flight event
+    pub(crate) fn ack(&self, event_id: u64) -> bool {
+        let acked = self.lock().in_flight.remove(&event_id).is_some();
+        if acked {
+            self.space.notify_waiters();
+        }
+        acked
+    }
+
+    /// Record a handler failure for an in-flight event.
+    ///
+    /// The event is made visible again immediately, or dead-lettered once
+    /// it has failed `max_handler_failures` times or exhausted its
+    /// redeliveries. Returns `None` if the event was not in flight.
+    fn nack(&self, event_id: u64, reason: &str) -> Option<NackOutcome> {
+        let mut state = self.lock();
+        let mut tracked = state.in_flight.remove(&event_id)?.tracked;
+        tracked.failures += 1;
+
+        let max_redeliveries = match self.guarantee {
+            DeliveryGuarantee::AtLeastOnce { max_redeliveries, .. } => max_redeliveries,
+            DeliveryGuarantee::AtMostOnce => 0,
+        };
+
+        let dead = if tracked.failures >= self.dead_letter.max_handler_failures {
+            Some(alloc::format!("handler_failed: {}", reason))
+        } else if tracked.deliveries > max_redeliveries {
+            Some(MAX_REDELIVERIES_EXCEEDED.into())
+        } else {
+            None
+        };
+
+        match dead {
+            Some(reason) => {
+                drop(state);
+                self.space.notify_waiters();
+                Some(NackOutcome::DeadLettered(DeadEvent { event: tracked.event, reason }))
+            }
+            None => {
+                state.ready.push_front(tracked);
+                drop(state);
+                self.notify.notify_one();
+                Some(NackOutcome::Redelivered)
+            }
+        }
+    }
+
+    /// Number of delivered events awaiting acknowledgement
+    pub(crate) fn pending_acks(&self) -> usize {
+        self.lock().in_flight.len()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Stop delivering events and wake any waiting receivers
+    pub(crate) fn close(&self) {
+        self.lock().closed = true;
+        self.notify.notify_waiters();
+        self.space.notify_waiters();
+    }
+}
+
+/// Receive side of a subscription
+#[derive(Debug, Clone)]
+pub(crate) struct Receiver {
+    pub(crate) queue: Arc<SubscriptionQueue>,
+    pub(crate) table: Arc<SubscriptionTable>,
+}
+
+impl Receiver {
+    /// Take the next event without waiting
+    pub(crate) fn try_receive(&self) -> Option<Event> {
+        let (event, dead) = self.queue.dequeue(Instant::now());
+        if !dead.is_empty() {
+            self.table.dead_letter(&self.queue.dead_letter.topic, dead);
+        }
+        // Without acknowledgements an event is consumed once received
+        if let (Some(event_id), false) = (event.as_ref().and_then(|e| e.id), self.queue.guarantee.requires_ack()) {
+            self.table.consumed(event_id);
+        }
+        event
+    }
+
+    /// Acknowledge an in-flight event
+    pub(crate) fn ack(&self, event_id: u64) -> bool {
+        let acked = self.queue.ack(event_id);
+        if acked {
+            self.table.consumed(event_id);
+        }
+        acked
+    }
+
+    /// Report that handling an in-flight event failed
+    pub(crate) fn nack(&self, event_id: u64, reason: &str) -> bool {
+        match self.queue.nack(event_id, reason) {
+            Some(NackOutcome::DeadLettered(dead)) => {
+                self.table.dead_letter(&self.queue.dead_letter.topic, alloc::vec![dead]);
+                true
+            }
+            Some(NackOutcome::Redelivered) => true,
+            None => false,
+        }
+    }
+
+    /// Wait for the next event, or `None` once the subscription is closed
+    pub(crate) async fn receive(&self) -> Option<Event> {
+        loop {
+            let notified = self.queue.notify.notified();
+            tokio::pin!(notified);
+            // Register interest before checking so a concurrent enqueue is not missed
+            notified.as_mut().enable();
+
+            if let Some(event) = self.try_receive() {
+                return Some(event);
+            }
+            if self.queue.is_closed() {
+                return None;
+            }
+
+            match self.queue.next_deadline() {
+                Some(deadline) => {
+                    let _ = tokio::time::timeout_at(deadline, notified).await;
+                }
+                None => notified.await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::core::time::Duration;
+
+    fn at_least_once(visibility_timeout: Duration, max_redeliveries: u32) -> SubscriberConfig {
+        SubscriberConfig {
+            delivery: DeliveryGuarantee::AtLeastOnce { visibility_timeout, max_redeliveries },
+            ..SubscriberConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_queue_full() {
+        let config = SubscriberConfig {
+            queue_size: 1,
+            ..SubscriberConfig::default()
+        };
+        let queue = SubscriptionQueue::new(1, "a".into(), &config);
+
+        queue.enqueue(Event::new("a".into(), vec![])).unwrap();
+        assert!(matches!(
+            queue.enqueue(Event::new("a".into(), vec![])),
+            Err(EventBusError::QueueFull { current_size: 1, max_size: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_redelivery_after_visibility_timeout() {
+        let queue = SubscriptionQueue::new(1, "a".into(), &at_least_once(Duration::from_secs(10), 1));
+        queue.enqueue(Event::new("a".into(), vec![]).with_id(7)).unwrap();
+
+        let start = Instant::now();
+        let (first, _) = queue.dequeue(start);
+        assert_eq!(first.unwrap().headers.get(DELIVERY_COUNT_HEADER).map(String::as_str), Some("1"));
+        assert!(queue.dequeue(start + Duration::from_secs(5)).0.is_none());
+
+        let (second, dead) = queue.dequeue(start + Duration::from_secs(10));
+        assert_eq!(second.unwrap().headers.get(DELIVERY_COUNT_HEADER).map(String::as_str), Some("2"));
+        assert!(dead.is_empty());
+
+        // Redeliveries exhausted
+        let (third, dead) = queue.dequeue(start + Duration::from_secs(20));
+        assert!(third.is_none());
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].reason, MAX_REDELIVERIES_EXCEEDED);
+        assert_eq!(queue.pending_acks(), 0);
+    }
+
+    #[test]
+    fn test_nack_redelivers_until_failure_limit() {
+        let config = SubscriberConfig {
+            dead_letter: DeadLetterConfig {
+                max_handler_failures: 2,
+                ..DeadLetterConfig::default()
+            },
+            ..at_least_once(Duration::from_secs(10), 5)
+        };
+        let queue = SubscriptionQueue::new(1, "a".into(), &config);
+        queue.enqueue(Event::new("a".into(), vec![]).with_id(7)).unwrap();
+
+        let now = Instant::now();
+        assert!(queue.dequeue(now).0.is_some());
+        assert!(matches!(queue.nack(7, "boom"), Some(NackOutcome::Redelivered)));
+
+        // Visible again without waiting for the visibility timeout
+        assert!(queue.dequeue(now).0.is_some());
+        match queue.nack(7, "boom") {
+            Some(NackOutcome::DeadLettered(dead)) => assert_eq!(dead.reason, "handler_failed: boom"),
+            other => panic!("expected dead letter, got {:?}", other),
+        }
+        assert!(queue.nack(7, "boom").is_none());
+        assert!(queue.dequeue(now).0.is_none());
+    }
+}
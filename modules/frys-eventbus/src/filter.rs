@@ -0,0 +1,281 @@
+//! Subscriber-side content filtering
+//!
+//! A [`Filter`] is evaluated when an event is routed to a subscription,
+//! before it is queued, so events the subscriber is not interested in never
+//! take up space in its queue.
+
+use crate::*;
+
+/// Content filter of a subscription.
+///
+/// Every condition added must hold for an event to be delivered; the
+/// default filter accepts every event.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    conditions: alloc::vec::Vec<Condition>,
+}
+
+/// Single condition of a [`Filter`]
+#[derive(Debug, Clone)]
+enum Condition {
+    /// Header is present with exactly this value
+    HeaderEquals {
+        name: alloc::string::String,
+        value: alloc::string::String,
+    },
+    /// Header is present and matches the regular expression
+    #[cfg(feature = "filters")]
+    HeaderMatches {
+        name: alloc::string::String,
+        pattern: regex::Regex,
+    },
+    /// Some value selected from the JSON payload satisfies the predicate
+    #[cfg(feature = "filters")]
+    Payload {
+        path: JsonPath,
+        predicate: PathPredicate,
+    },
+}
+
+impl Filter {
+    /// Create a filter accepting every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require header `name` to equal `value`
+    pub fn header_eq(mut self, name: impl Into<alloc::string::String>, value: impl Into<alloc::string::String>) -> Self {
+        self.conditions.push(Condition::HeaderEquals {
+            name: name.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Require header `name` to match the regular expression `pattern`
+    #[cfg(feature = "filters")]
+    pub fn header_regex(mut self, name: impl Into<alloc::string::String>, pattern: &str) -> Result<Self> {
+        let pattern = regex::Regex::new(pattern).map_err(|_| EventBusError::FilterCompilationFailed {
+            filter: pattern.into(),
+            reason: "invalid regular expression",
+        })?;
+        self.conditions.push(Condition::HeaderMatches {
+            name: name.into(),
+            pattern,
+        });
+        Ok(self)
+    }
+
+    /// Require a value selected by the JSONPath `path` from the payload to
+    /// satisfy `predicate`.
+    ///
+    /// Paths start at the root `$` and select object members with `.name`
+    /// or `['name']`, array elements with `[index]`, and every child with
+    /// `.*` or `[*]`. Events whose payload is not JSON never match.
+    #[cfg(feature = "filters")]
+    pub fn payload_path(mut self, path: &str, predicate: PathPredicate) -> Result<Self> {
+        self.conditions.push(Condition::Payload {
+            path: JsonPath::parse(path)?,
+            predicate,
+        });
+        Ok(self)
+    }
+
+    /// Whether this filter accepts every event
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Check if an event passes this filter
+    pub fn matches(&self, event: &Event) -> bool {
+        #[cfg(feature = "filters")]
+        let mut payload = None;
+
+        self.conditions.iter().all(|condition| match condition {
+            Condition::HeaderEquals { name, value } => event.headers.get(name) == Some(value),
+            #[cfg(feature = "filters")]
+            Condition::HeaderMatches { name, pattern } => {
+                event.headers.get(name).is_some_and(|value| pattern.is_match(value))
+            }
+            #[cfg(feature = "filters")]
+            Condition::Payload { path, predicate } => {
+                // Parse the payload once for all payload conditions
+                let document = payload.get_or_insert_with(|| serde_json::from_slice::<serde_json::Value>(&event.payload).ok());
+                document
+                    .as_ref()
+                    .is_some_and(|document| path.select(document).into_iter().any(|value| predicate.test(value)))
+            }
+        })
+    }
+}
+
+/// Predicate on a value selected from a JSON payload
+#[cfg(feature = "filters")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathPredicate {
+    /// The path selects at least one value
+    Exists,
+    /// The value equals the given JSON value
+    Equals(serde_json::Value),
+    /// The value is a number greater than the bound
+    GreaterThan(f64),
+    /// The value is a number less than the bound
+    LessThan(f64),
+    /// The value is one of the given JSON values
+    In(alloc::vec::Vec<serde_json::Value>),
+}
+
+#[cfg(feature = "filters")]
+impl PathPredicate {
+    fn test(&self, value: &serde_json::Value) -> bool {
+        match self {
+            PathPredicate::Exists => true,
+            PathPredicate::Equals(expected) => value == expected,
+            PathPredicate::GreaterThan(bound) => value.as_f64().is_some_and(|n| n > *bound),
+            PathPredicate::LessThan(bound) => value.as_f64().is_some_and(|n| n < *bound),
+            PathPredicate::In(values) => values.contains(value),
+        }
+    }
+}
+
+/// Step of a parsed JSONPath
+#[cfg(feature = "filters")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Object member
+    Member(alloc::string::String),
+    /// Array element
+    Index(usize),
+    /// Every member or element
+    Wildcard,
+}
+
+/// Parsed JSONPath expression
+#[cfg(feature = "filters")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct JsonPath {
+    segments: alloc::vec::Vec<Segment>,
+}
+
+#[cfg(feature = "filters")]
+impl JsonPath {
+    fn parse(path: &str) -> Result<Self> {
+        let invalid = |reason| EventBusError::FilterCompilationFailed {
+            filter: path.into(),
+            reason,
+        };
+
+        let mut rest = path.strip_prefix('$').ok_or_else(|| invalid("path must start with '$'"))?;
+        let mut segments = alloc::vec::Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix(".*") {
+                segments.push(Segment::Wildcard);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(invalid("empty member name"));
+                }
+                segments.push(Segment::Member(after[..end].into()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+                let selector = after[..end].trim();
+                let segment = if selector == "*" {
+                    Segment::Wildcard
+                } else if let Some(name) = selector
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| selector.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+                {
+                    Segment::Member(name.into())
+                } else {
+                    Segment::Index(selector.parse().map_err(|_| invalid("expected an index, a quoted name or '*'"))?)
+                };
+                segments.push(segment);
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Values selected from `document`
+    fn select<'a>(&self, document: &'a serde_json::Value) -> alloc::vec::Vec<&'a serde_json::Value> {
+        let mut current = alloc::vec![document];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> alloc::vec::Vec<&'a serde_json::Value> {
+                    match (segment, value) {
+                        (Segment::Member(name), serde_json::Value::Object(map)) => map.get(name).into_iter().collect(),
+                        (Segment::Index(index), serde_json::Value::Array(items)) => items.get(*index).into_iter().collect(),
+                        (Segment::Wildcard, serde_json::Value::Object(map)) => map.values().collect(),
+                        (Segment::Wildcard, serde_json::Value::Array(items)) => items.iter().collect(),
+                        _ => alloc::vec::Vec::new(),
+                    }
+                })
+                .collect();
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(headers: &[(&str, &str)], payload: &str) -> Event {
+        let mut event = Event::new("orders.created".into(), payload.as_bytes().to_vec());
+        for (name, value) in headers {
+            event.headers.set((*name).into(), (*value).into());
+        }
+        event
+    }
+
+    #[test]
+    fn test_header_equality() {
+        let filter = Filter::new().header_eq("region", "eu");
+
+        assert!(Filter::default().matches(&event(&[], "")));
+        assert!(filter.matches(&event(&[("region", "eu")], "")));
+        assert!(!filter.matches(&event(&[("region", "us")], "")));
+        assert!(!filter.matches(&event(&[], "")));
+    }
+
+    #[cfg(feature = "filters")]
+    #[test]
+    fn test_header_regex_and_payload_path() {
+        let filter = Filter::new()
+            .header_regex("source", "^checkout-(web|ios)$")
+            .unwrap()
+            .payload_path("$.items[*].sku", PathPredicate::Equals(serde_json::json!("A-1")))
+            .unwrap()
+            .payload_path("$['total']", PathPredicate::GreaterThan(100.0))
+            .unwrap();
+
+        let order = r#"{"items": [{"sku": "B-2"}, {"sku": "A-1"}], "total": 120.5}"#;
+        assert!(filter.matches(&event(&[("source", "checkout-ios")], order)));
+        assert!(!filter.matches(&event(&[("source", "checkout-android")], order)));
+        assert!(!filter.matches(&event(&[("source", "checkout-web")], r#"{"items": [{"sku": "A-1"}], "total": 80}"#)));
+        assert!(!filter.matches(&event(&[("source", "checkout-web")], "not json")));
+    }
+
+    #[cfg(feature = "filters")]
+    #[test]
+    fn test_invalid_filters_are_rejected() {
+        assert!(matches!(
+            Filter::new().header_regex("source", "(unclosed"),
+            Err(EventBusError::FilterCompilationFailed { .. })
+        ));
+        for path in ["items", "$.", "$[0", "$[x]"] {
+            assert!(
+                matches!(Filter::new().payload_path(path, PathPredicate::Exists), Err(EventBusError::FilterCompilationFailed { .. })),
+                "{}",
+                path
+            );
+        }
+    }
+}
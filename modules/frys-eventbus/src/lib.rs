@@ -94,6 +94,7 @@ pub mod routing;
 pub mod queues;
 pub mod async;
 pub mod distributed;
+pub mod filter;
 
 // Re-exports for convenience
 pub use core::*;
@@ -102,6 +103,7 @@ pub use routing::*;
 pub use queues::*;
 pub use async::*;
 pub use distributed::*;
+pub use filter::*;
 
 // Error types
 mod error;
@@ -115,6 +117,19 @@ pub use config::*;
 mod metrics;
 pub use metrics::*;
 
+// Subscriber delivery queues
+mod delivery;
+pub use delivery::{DEATH_REASON_HEADER, DELIVERY_COUNT_HEADER, MAX_REDELIVERIES_EXCEEDED, ORIGINAL_TOPIC_HEADER};
+
+// Write-ahead log for durable delivery
+mod persistence;
+
+// Typed publish/subscribe
+#[cfg(feature = "typed")]
+mod typed;
+#[cfg(feature = "typed")]
+pub use typed::*;
+
 // Type aliases
 pub type Result<T> = core::result::Result<T, EventBusError>;
 
@@ -122,7 +137,13 @@ pub type Result<T> = core::result::Result<T, EventBusError>;
 pub const DEFAULT_QUEUE_SIZE: usize = 1024;
 pub const DEFAULT_MAX_SUBSCRIBERS: usize = 1000;
 pub const MAX_TOPIC_LENGTH: usize = 256;
+pub const MAX_TOPIC_METRICS: usize = 1000;
 pub const MAX_PAYLOAD_SIZE: usize = 64 * 1024 * 1024; // 64MB
+pub const DEFAULT_VISIBILITY_TIMEOUT_MS: u64 = 30_000;
+pub const DEFAULT_MAX_REDELIVERIES: u32 = 5;
+pub const DEFAULT_DEAD_LETTER_TOPIC: &str = "eventbus.dead_letter";
+pub const DEFAULT_MAX_HANDLER_FAILURES: u32 = 3;
+pub const DEFAULT_HISTORY_RETENTION_SECS: u64 = 60 * 60;
 
 #[cfg(test)]
 mod tests {
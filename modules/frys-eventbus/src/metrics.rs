@@ -1,6 +1,19 @@
 //! Metrics and monitoring for EventBus
 
 use core::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Topic under which [`EventBusMetrics`] counts the events of topics past
+/// its limit
+pub const OVERFLOW_TOPIC: &str = "__overflow__";
+
+/// Event counters of a single topic
+#[derive(Debug, Default)]
+struct TopicCounters {
+    published: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
 
 /// EventBus metrics collector
 #[derive(Debug)]
@@ -14,6 +27,9 @@ pub struct EventBusMetrics {
     /// Total events dropped
     pub events_dropped: AtomicU64,
 
+    /// Publishes rejected because a subscriber queue was full
+    pub events_rejected: AtomicU64,
+
     /// Total subscribers
     pub total_subscribers: AtomicU64,
 
@@ -46,6 +62,15 @@ pub struct EventBusMetrics {
 
     /// Total processing time (nanoseconds)
     pub total_processing_time: AtomicU64,
+
+    /// Event counters by topic
+    topics: RwLock<alloc::collections::BTreeMap<alloc::string::String, TopicCounters>>,
+
+    /// Most topics counted separately before new ones overflow
+    max_topics: usize,
+
+    /// Subscription queues whose depth is reported as queue lag
+    queues: Option<Arc<crate::delivery::SubscriptionTable>>,
 }
 
 impl EventBusMetrics {
@@ -54,6 +79,7 @@ impl EventBusMetrics {
             events_published: AtomicU64::new(0),
             events_delivered: AtomicU64::new(0),
             events_dropped: AtomicU64::new(0),
+            events_rejected: AtomicU64::new(0),
             total_subscribers: AtomicU64::new(0),
             active_subscribers: AtomicU64::new(0),
             total_topics: AtomicU64::new(0),
@@ -65,9 +91,26 @@ impl EventBusMetrics {
             avg_processing_latency: AtomicU64::new(0),
             max_processing_latency: AtomicU64::new(0),
             total_processing_time: AtomicU64::new(0),
+            topics: RwLock::default(),
+            max_topics: crate::MAX_TOPIC_METRICS,
+            queues: None,
         }
     }
 
+    /// Count at most `max_topics` topics separately. Events of further
+    /// topics, such as per-entity ones, are counted under
+    /// [`OVERFLOW_TOPIC`], bounding the memory the counters use.
+    pub fn with_max_topics(mut self, max_topics: usize) -> Self {
+        self.max_topics = max_topics;
+        self
+    }
+
+    /// Report the depth of the queues in `queues` as queue lag
+    pub(crate) fn with_queues(mut self, queues: Arc<crate::delivery::SubscriptionTable>) -> Self {
+        self.queues = Some(queues);
+        self
+    }
+
     /// Record event published
     pub fn record_event_published(&self) {
         self.events_published.fetch_add(1, Ordering::AcqRel);
@@ -83,6 +126,40 @@ impl EventBusMetrics {
         self.events_dropped.fetch_add(1, Ordering::AcqRel);
     }
 
+    /// Record an event published to `topic`
+    pub fn record_topic_published(&self, topic: &str) {
+        self.with_topic(topic, |counters| counters.published.fetch_add(1, Ordering::AcqRel));
+    }
+
+    /// Record `count` deliveries of an event published to `topic`
+    pub fn record_topic_delivered(&self, topic: &str, count: u64) {
+        self.with_topic(topic, |counters| counters.delivered.fetch_add(count, Ordering::AcqRel));
+    }
+
+    /// Record `count` drops of an event published to `topic`
+    pub fn record_topic_dropped(&self, topic: &str, count: u64) {
+        self.with_topic(topic, |counters| counters.dropped.fetch_add(count, Ordering::AcqRel));
+    }
+
+    fn with_topic(&self, topic: &str, record: impl FnOnce(&TopicCounters) -> u64) {
+        if let Some(counters) = self.topics.read().unwrap_or_else(|e| e.into_inner()).get(topic) {
+            record(counters);
+            return;
+        }
+        let mut topics = self.topics.write().unwrap_or_else(|e| e.into_inner());
+        let topic = if topics.len() >= self.max_topics && !topics.contains_key(topic) {
+            OVERFLOW_TOPIC
+        } else {
+            topic
+        };
+        record(topics.entry(topic.into()).or_default());
+    }
+
+    /// Record a publish rejected by backpressure
+    pub fn record_event_rejected(&self) {
+        self.events_rejected.fetch_add(1, Ordering::AcqRel);
+    }
+
     /// Record subscriber added
     pub fn record_subscriber_added(&self) {
         self.total_subscribers.fetch_add(1, Ordering::AcqRel);
@@ -158,6 +235,7 @@ impl EventBusMetrics {
             events_published: self.events_published.load(Ordering::Acquire),
             events_delivered: self.events_delivered.load(Ordering::Acquire),
             events_dropped: self.events_dropped.load(Ordering::Acquire),
+            events_rejected: self.events_rejected.load(Ordering::Acquire),
             total_subscribers: self.total_subscribers.load(Ordering::Acquire),
             active_subscribers: self.active_subscribers.load(Ordering::Acquire),
             total_topics: self.total_topics.load(Ordering::Acquire),
@@ -170,6 +248,21 @@ impl EventBusMetrics {
             max_processing_latency: self.max_processing_latency.load(Ordering::Acquire),
             delivery_rate: self.calculate_delivery_rate(),
             error_rate: self.calculate_error_rate(),
+            topics: self
+                .topics
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|(topic, counters)| {
+                    let metrics = TopicMetrics {
+                        published: counters.published.load(Ordering::Acquire),
+                        delivered: counters.delivered.load(Ordering::Acquire),
+                        dropped: counters.dropped.load(Ordering::Acquire),
+                    };
+                    (topic.clone(), metrics)
+                })
+                .collect(),
+            queue_lag: self.queues.as_ref().map(|queues| queues.queue_lag()).unwrap_or_default(),
         }
     }
 
@@ -202,6 +295,7 @@ pub struct MetricsSnapshot {
     pub events_published: u64,
     pub events_delivered: u64,
     pub events_dropped: u64,
+    pub events_rejected: u64,
     pub total_subscribers: u64,
     pub active_subscribers: u64,
     pub total_topics: u64,
@@ -214,6 +308,32 @@ pub struct MetricsSnapshot {
     pub max_processing_latency: u64,
     pub delivery_rate: f64,
     pub error_rate: f64,
+    /// Event counters by topic
+    pub topics: alloc::collections::BTreeMap<alloc::string::String, TopicMetrics>,
+    /// Events waiting in each subscriber's queue, by subscriber ID
+    pub queue_lag: alloc::collections::BTreeMap<u64, QueueLag>,
+}
+
+/// Event counters of a single topic
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopicMetrics {
+    /// Events published to the topic
+    pub published: u64,
+    /// Deliveries to subscriber queues
+    pub delivered: u64,
+    /// Events dropped for lack of subscribers or queue space
+    pub dropped: u64,
+}
+
+/// Backlog of a single subscriber
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueLag {
+    /// Topic filter of the subscription
+    pub topic_filter: alloc::string::String,
+    /// Events queued and not yet received
+    pub depth: u64,
+    /// Events received and not yet acknowledged
+    pub pending_acks: u64,
 }
 
 impl MetricsSnapshot {
@@ -285,6 +405,36 @@ mod tests {
         assert!(snapshot.is_healthy());
     }
 
+    #[test]
+    fn test_topic_counters() {
+        let metrics = EventBusMetrics::new();
+
+        metrics.record_topic_published("orders");
+        metrics.record_topic_delivered("orders", 2);
+        metrics.record_topic_published("audit");
+        metrics.record_topic_dropped("audit", 1);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.topics["orders"], TopicMetrics { published: 1, delivered: 2, dropped: 0 });
+        assert_eq!(snapshot.topics["audit"], TopicMetrics { published: 1, delivered: 0, dropped: 1 });
+        assert!(snapshot.queue_lag.is_empty());
+    }
+
+    #[test]
+    fn test_topic_counters_overflow() {
+        let metrics = EventBusMetrics::new().with_max_topics(2);
+
+        for order in 0..5 {
+            metrics.record_topic_published(&alloc::format!("orders.{}", order));
+        }
+        metrics.record_topic_delivered("orders.0", 1);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.topics.len(), 3);
+        assert_eq!(snapshot.topics["orders.0"], TopicMetrics { published: 1, delivered: 1, dropped: 0 });
+        assert_eq!(snapshot.topics[OVERFLOW_TOPIC].published, 3);
+    }
+
     #[test]
     fn test_latency_recording() {
         let metrics = EventBusMetrics::new();
@@ -305,6 +455,7 @@ mod tests {
             events_published: 100,
             events_delivered: 98,
             events_dropped: 2,
+            events_rejected: 0,
             total_subscribers: 10,
             active_subscribers: 10,
             total_topics: 5,
@@ -317,6 +468,8 @@ mod tests {
             max_processing_latency: 5000000, // 5ms
             delivery_rate: 0.98,
             error_rate: 0.01,
+            topics: Default::default(),
+            queue_lag: Default::default(),
         };
 
         assert!(healthy.is_healthy());
@@ -0,0 +1,532 @@
+//! Write-ahead log of published events for durability across restarts
+//!
+//! Every event routed to at least one subscriber is appended to the log
+//! together with the number of deliveries it is waiting on. Once every
+//! delivery is consumed (received in at-most-once mode, acknowledged or
+//! dead-lettered in at-least-once mode) a retirement record is appended.
+//! On startup the log is replayed and events that were never retired are
+//! offered to matching subscribers as they subscribe.
+//!
+//! Retired events stay in the log for the configured history retention so
+//! late subscribers can catch up on them with
+//! [`EventBus::subscribe_from`](crate::EventBus::subscribe_from).
+//! Compaction drops them once they are older than that.
+//!
+//! Records are framed by `frys-record-log`, so a torn write at the tail of
+//! the log is detected and discarded on replay.
+
+use crate::*;
+use frys_record_log::{frame, next_record};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use ::core::time::Duration;
+
+/// Name of the log file inside the configured directory
+const WAL_FILE_NAME: &str = "events.wal";
+
+/// Retirements after which the log is rewritten with only live events
+const COMPACTION_THRESHOLD: usize = 1024;
+
+const RECORD_PUBLISHED: u8 = 1;
+const RECORD_RETIRED: u8 = 2;
+
+/// Tracks outstanding deliveries of published events, persisting them when
+/// a write-ahead log is configured
+#[derive(Debug, Default)]
+pub(crate) struct Ledger {
+    wal: Option<Mutex<Wal>>,
+}
+
+/// A logged event, live until fully consumed and then kept as history
+#[derive(Debug)]
+struct LoggedEvent {
+    event: Event,
+    /// Deliveries not yet consumed
+    pending: usize,
+    /// Recovered from the log and offered to new subscribers
+    replayed: bool,
+    /// Fully consumed and kept only for replay
+    retired: bool,
+}
+
+#[derive(Debug)]
+struct Wal {
+    path: PathBuf,
+    file: File,
+    sync_writes: bool,
+    /// How long retired events are kept for replay
+    retention: Duration,
+    events: alloc::collections::BTreeMap<u64, LoggedEvent>,
+    retired_since_compaction: usize,
+}
+
+impl Ledger {
+    /// Create the ledger for a persistence mode, keeping retired events for
+    /// `retention`
+    pub(crate) fn open(persistence: &Persistence, retention: Duration) -> Result<Self> {
+        match persistence {
+            Persistence::InMemory => Ok(Self::default()),
+            Persistence::Wal { dir, sync_writes } => Ok(Self {
+                wal: Some(Mutex::new(Wal::open(Path::new(dir), *sync_writes, retention)?)),
+            }),
+        }
+    }
+
+    fn lock(&self) -> Option<std::sync::MutexGuard<'_, Wal>> {
+        self.wal.as_ref().map(|wal| wal.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Whether events are logged
+    pub(crate) fn is_persistent(&self) -> bool {
+        self.wal.is_some()
+    }
+
+    /// Highest event ID recovered from the log
+    pub(crate) fn max_event_id(&self) -> Option<u64> {
+        self.lock()?.events.keys().next_back().copied()
+    }
+
+    /// Record that an event was queued for `deliveries` more subscribers.
+    ///
+    /// Events queued for nobody are only logged as history, and only if
+    /// history is retained.
+    pub(crate) fn delivered(&self, event: &Event, deliveries: usize) -> Result<()> {
+        let (Some(mut wal), Some(event_id)) = (self.lock(), event.id) else {
+            return Ok(());
+        };
+        if deliveries == 0 && (wal.retention.is_zero() || wal.events.contains_key(&event_id)) {
+            return Ok(());
+        }
+
+        // Re-log the event when it is re-routed, e.g. to a dead-letter topic,
+        // or replayed again after retirement
+        let changed = wal
+            .events
+            .get(&event_id)
+            .is_none_or(|logged| logged.retired || logged.event.topic != event.topic);
+        if changed {
+            wal.append(&encode_published(event))?;
+        }
+        if deliveries == 0 {
+            wal.append(&encode_retired(event_id))?;
+        }
+
+        let logged = wal.events.entry(event_id).or_insert_with(|| LoggedEvent {
+            event: event.clone(),
+            pending: 0,
+            replayed: false,
+            retired: false,
+        });
+        if changed {
+            logged.event = event.clone();
+            logged.replayed = false;
+        }
+        logged.pending += deliveries;
+        logged.retired = logged.pending == 0;
+        Ok(())
+    }
+
+    /// Record that one delivery of an event was consumed.
+    ///
+    /// Failing to log the retirement only means the event is replayed again
+    /// after a restart, so write errors are ignored here.
+    pub(crate) fn consumed(&self, event_id: u64) {
+        self.release(event_id, false);
+    }
+
+    /// Take back a delivery that was recorded but never handed to a
+    /// subscriber.
+    ///
+    /// Unlike [`Ledger::consumed`], an event recovered from the log stays
+    /// outstanding for the next subscriber rather than being retired.
+    pub(crate) fn undelivered(&self, event_id: u64) {
+        self.release(event_id, true);
+    }
+
+    fn release(&self, event_id: u64, keep_recovered: bool) {
+        let Some(mut wal) = self.lock() else {
+            return;
+        };
+        let Some(logged) = wal.events.get_mut(&event_id).filter(|logged| !logged.retired) else {
+            return;
+        };
+
+        logged.pending = logged.pending.saturating_sub(1);
+        if logged.pending == 0 && !(keep_recovered && logged.replayed) {
+            logged.retired = true;
+            if wal.retention.is_zero() {
+                wal.events.remove(&event_id);
+            }
+            let _ = wal.append(&encode_retired(event_id));
+            wal.retired_since_compaction += 1;
+            if wal.retired_since_compaction >= COMPACTION_THRESHOLD {
+                let _ = wal.compact();
+            }
+        }
+    }
+
+    /// Recovered events that should be offered to a new subscriber
+    pub(crate) fn backlog(&self, topic_filter: &str) -> alloc::vec::Vec<Event> {
+        self.lock()
+            .map(|wal| {
+                wal.events
+                    .values()
+                    .filter(|logged| logged.replayed && !logged.retired && logged.event.matches_topic(topic_filter))
+                    .map(|logged| logged.event.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Logged events matching `topic_filter` published at or after `since`
+    /// (Unix milliseconds), in publish order
+    pub(crate) fn history(&self, topic_filter: &str, since: Timestamp) -> alloc::vec::Vec<Event> {
+        self.lock()
+            .map(|wal| {
+                wal.events
+                    .values()
+                    .filter(|logged| logged.event.timestamp >= since && logged.event.matches_topic(topic_filter))
+                    .map(|logged| logged.event.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Number of events awaiting consumption
+    #[cfg(test)]
+    pub(crate) fn live_events(&self) -> usize {
+        self.lock().map_or(0, |wal| wal.events.values().filter(|logged| !logged.retired).count())
+    }
+}
+
+impl Wal {
+    /// Open the log in `dir`, replaying it and compacting away expired events
+    fn open(dir: &Path, sync_writes: bool, retention: Duration) -> Result<Self> {
+        std::fs::create_dir_all(dir).map_err(|e| io_error("create_wal_dir", e))?;
+        let path = dir.join(WAL_FILE_NAME);
+
+        let mut bytes = alloc::vec::Vec::new();
+        if path.exists() {
+            File::open(&path)
+                .and_then(|mut file| file.read_to_end(&mut bytes))
+                .map_err(|e| io_error("read_wal", e))?;
+        }
+
+        let mut events = alloc::collections::BTreeMap::new();
+        for record in decode_records(&bytes) {
+            match record {
+                Record::Published(event) => {
+                    if let Some(event_id) = event.id {
+                        events.insert(event_id, LoggedEvent { event, pending: 0, replayed: true, retired: false });
+                    }
+                }
+                Record::Retired(event_id) => {
+                    if let Some(logged) = events.get_mut(&event_id) {
+                        logged.retired = true;
+                    }
+                }
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| io_error("open_wal", e))?;
+
+        let mut wal = Self {
+            path,
+            file,
+            sync_writes,
+            retention,
+            events,
+            retired_since_compaction: 0,
+        };
+        // Drops expired events and any torn record at the tail
+        wal.compact()?;
+        Ok(wal)
+    }
+
+    /// Append a framed record
+    fn append(&mut self, body: &[u8]) -> Result<()> {
+        self.file.write_all(&frame(body)).map_err(|e| io_error("append_wal", e))?;
+        if self.sync_writes {
+            self.file.sync_data().map_err(|e| io_error("sync_wal", e))?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the log with only live events and retained history
+    fn compact(&mut self) -> Result<()> {
+        let cutoff = now_millis().saturating_sub(u64::try_from(self.retention.as_millis()).unwrap_or(u64::MAX));
+        self.events.retain(|_, logged| !logged.retired || logged.event.timestamp >= cutoff);
+
+        let tmp_path = self.path.with_extension("wal.tmp");
+        let mut contents = alloc::vec::Vec::new();
+        for (event_id, logged) in &self.events {
+            contents.extend_from_slice(&frame(&encode_published(&logged.event)));
+            if logged.retired {
+                contents.extend_from_slice(&frame(&encode_retired(*event_id)));
+            }
+        }
+
+        let mut tmp = File::create(&tmp_path).map_err(|e| io_error("compact_wal", e))?;
+        tmp.write_all(&contents)
+            .and_then(|()| tmp.sync_all())
+            .map_err(|e| io_error("compact_wal", e))?;
+        std::fs::rename(&tmp_path, &self.path).map_err(|e| io_error("compact_wal", e))?;
+
+        self.file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| io_error("open_wal", e))?;
+        self.retired_since_compaction = 0;
+        Ok(())
+    }
+}
+
+fn io_error(operation: &'static str, error: std::io::Error) -> EventBusError {
+    EventBusError::IoError {
+        operation,
+        details: alloc::format!("{}", error),
+    }
+}
+
+/// A decoded log record
+#[derive(Debug)]
+enum Record {
+    Published(Event),
+    Retired(u64),
+}
+
+/// Decode records up to the first torn or corrupt one
+fn decode_records(bytes: &[u8]) -> alloc::vec::Vec<Record> {
+    let mut records = alloc::vec::Vec::new();
+    let mut offset = 0;
+    while let Some((body, next)) = next_record(bytes, offset) {
+        match decode_record(body) {
+            Some(record) => records.push(record),
+            None => break,
+        }
+        offset = next;
+    }
+    records
+}
+
+fn encode_published(event: &Event) -> alloc::vec::Vec<u8> {
+    let mut body = alloc::vec![RECORD_PUBLISHED];
+    body.extend_from_slice(&event.id.unwrap_or_default().to_le_bytes());
+    body.push(event.priority as u8);
+    body.extend_from_slice(&event.timestamp.to_le_bytes());
+    put_bytes(&mut body, event.topic.as_bytes());
+    put_bytes(&mut body, &event.payload);
+    body.extend_from_slice(&(event.headers.headers.len() as u32).to_le_bytes());
+    for (key, value) in &event.headers.headers {
+        put_bytes(&mut body, key.as_bytes());
+        put_bytes(&mut body, value.as_bytes());
+    }
+    if let Some(key) = &event.partition_key {
+        put_bytes(&mut body, key.as_bytes());
+    }
+    body
+}
+
+fn encode_retired(event_id: u64) -> alloc::vec::Vec<u8> {
+    let mut body = alloc::vec![RECORD_RETIRED];
+    body.extend_from_slice(&event_id.to_le_bytes());
+    body
+}
+
+fn put_bytes(body: &mut alloc::vec::Vec<u8>, bytes: &[u8]) {
+    body.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    body.extend_from_slice(bytes);
+}
+
+fn decode_record(body: &[u8]) -> Option<Record> {
+    let mut reader = Reader { bytes: body };
+    match reader.u8()? {
+        RECORD_PUBLISHED => {
+            let id = reader.u64()?;
+            let priority = match reader.u8()? {
+                0 => Priority::Low,
+                1 => Priority::Normal,
+                2 => Priority::High,
+                _ => Priority::Critical,
+            };
+            let timestamp = reader.u64()?;
+            let topic = reader.string()?;
+            let payload = reader.bytes()?.to_vec();
+            let mut headers = EventHeaders::new();
+            for _ in 0..reader.u32()? {
+                let key = reader.string()?;
+                let value = reader.string()?;
+                headers.set(key, value);
+            }
+            // Trailing and optional, so records written without one decode
+            let partition_key = if reader.bytes.is_empty() { None } else { Some(reader.string()?) };
+            Some(Record::Published(Event {
+                topic,
+                payload,
+                headers,
+                priority,
+                timestamp,
+                id: Some(id),
+                partition_key,
+            }))
+        }
+        RECORD_RETIRED => Some(Record::Retired(reader.u64()?)),
+        _ => None,
+    }
+}
+
+/// Cursor over a record body
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let taken = self.bytes.get(..len)?;
+        self.bytes = &self.bytes[len..];
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|b| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(b);
+            u64::from_le_bytes(buf)
+        })
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Option<alloc::string::String> {
+        ::core::str::from_utf8(self.bytes()?).ok().map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        std::env::temp_dir().join(alloc::format!("frys-eventbus-{}-{}-{}", name, std::process::id(), nanos))
+    }
+
+    const RETENTION: Duration = Duration::from_secs(60);
+
+    fn wal(dir: &Path) -> Persistence {
+        Persistence::Wal {
+            dir: dir.to_string_lossy().into_owned(),
+            sync_writes: false,
+        }
+    }
+
+    #[test]
+    fn test_ledger_replays_unretired_events() {
+        let dir = temp_dir("ledger");
+        let ledger = Ledger::open(&wal(&dir), RETENTION).unwrap();
+
+        let first = Event::new("a.b".into(), b"one".to_vec())
+            .with_id(1)
+            .with_priority(Priority::High)
+            .with_header("k".into(), "v".into())
+            .with_partition_key("user-1".into());
+        let second = Event::new("a.c".into(), b"two".to_vec()).with_id(2);
+        ledger.delivered(&first, 2).unwrap();
+        ledger.delivered(&second, 1).unwrap();
+        ledger.consumed(1);
+        ledger.consumed(2);
+        assert_eq!(ledger.live_events(), 1);
+        drop(ledger);
+
+        let reopened = Ledger::open(&wal(&dir), RETENTION).unwrap();
+        assert_eq!(reopened.max_event_id(), Some(1));
+        let backlog = reopened.backlog("a.*");
+        assert_eq!(backlog.len(), 1);
+        assert_eq!(backlog[0].payload, b"one");
+        assert_eq!(backlog[0].priority, Priority::High);
+        assert_eq!(backlog[0].headers.get("k").map(String::as_str), Some("v"));
+        assert_eq!(backlog[0].partition_key.as_deref(), Some("user-1"));
+        assert!(reopened.backlog("x.*").is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retired_events_are_kept_as_history() {
+        let dir = temp_dir("history");
+        let ledger = Ledger::open(&wal(&dir), RETENTION).unwrap();
+        let now = now_millis();
+
+        let mut consumed = Event::new("a.b".into(), b"consumed".to_vec()).with_id(1);
+        consumed.timestamp = now;
+        let mut unrouted = Event::new("a.c".into(), b"unrouted".to_vec()).with_id(2);
+        unrouted.timestamp = now;
+        let mut expired = Event::new("a.d".into(), b"expired".to_vec()).with_id(3);
+        expired.timestamp = now - 2 * 60_000;
+        ledger.delivered(&consumed, 1).unwrap();
+        ledger.delivered(&unrouted, 0).unwrap();
+        ledger.delivered(&expired, 1).unwrap();
+        ledger.consumed(1);
+        ledger.consumed(3);
+        assert_eq!(ledger.live_events(), 0);
+        drop(ledger);
+
+        let reopened = Ledger::open(&wal(&dir), RETENTION).unwrap();
+        assert!(reopened.backlog("a.*").is_empty());
+        let history = reopened.history("a.*", now);
+        assert_eq!(history.iter().map(|event| event.id).collect::<Vec<_>>(), [Some(1), Some(2)]);
+        assert!(reopened.history("a.*", now + 1).is_empty());
+
+        // Without retention nothing is kept once consumed
+        let no_history = temp_dir("no-history");
+        let ledger = Ledger::open(&wal(&no_history), Duration::ZERO).unwrap();
+        ledger.delivered(&consumed, 1).unwrap();
+        ledger.delivered(&unrouted, 0).unwrap();
+        ledger.consumed(1);
+        assert!(ledger.history("a.*", 0).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&no_history).unwrap();
+    }
+
+    #[test]
+    fn test_torn_tail_is_discarded() {
+        let dir = temp_dir("torn");
+        let ledger = Ledger::open(&wal(&dir), RETENTION).unwrap();
+        ledger.delivered(&Event::new("a".into(), b"kept".to_vec()).with_id(1), 1).unwrap();
+        drop(ledger);
+
+        // Simulate a crash halfway through appending a record
+        let mut file = OpenOptions::new().append(true).open(dir.join(WAL_FILE_NAME)).unwrap();
+        let torn = frame(&encode_published(&Event::new("a".into(), b"lost".to_vec()).with_id(2)));
+        file.write_all(&torn[..torn.len() / 2]).unwrap();
+        drop(file);
+
+        let reopened = Ledger::open(&wal(&dir), RETENTION).unwrap();
+        let backlog = reopened.backlog("a");
+        assert_eq!(backlog.len(), 1);
+        assert_eq!(backlog[0].payload, b"kept");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
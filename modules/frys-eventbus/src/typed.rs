@@ -0,0 +1,198 @@
+//! Typed publish/subscribe over JSON payloads
+
+use crate::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Header recording how an event payload is encoded
+pub const CONTENT_TYPE_HEADER: &str = "content-type";
+
+/// Content type of payloads written by [`EventBus::publish_typed`]
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+impl EventBus {
+    /// Serialize `value` as JSON and publish it to `topic`
+    pub async fn publish_typed<T: Serialize>(&mut self, topic: &str, value: &T) -> Result<()> {
+        let payload = serde_json::to_vec(value).map_err(|e| EventBusError::SerializationError {
+            operation: "serialize",
+            details: alloc::format!("{}", e),
+        })?;
+
+        let event = Event::new(topic.into(), payload)
+            .with_header(CONTENT_TYPE_HEADER.into(), JSON_CONTENT_TYPE.into());
+        self.publish(event).await
+    }
+
+    /// Subscribe to events matching `pattern`, decoding payloads as `T`
+    pub async fn subscribe_typed<T: DeserializeOwned>(&mut self, pattern: &str) -> Result<TypedSubscriber<T>> {
+        self.subscribe_typed_with_config(pattern, SubscriberConfig::default()).await
+    }
+
+    /// Subscribe to events matching `pattern` with a subscriber
+    /// configuration, decoding payloads as `T`
+    pub async fn subscribe_typed_with_config<T: DeserializeOwned>(&mut self, pattern: &str, config: SubscriberConfig) -> Result<TypedSubscriber<T>> {
+        let handle = self.subscribe_with_config(pattern, Filter::default(), config).await?;
+        Ok(TypedSubscriber {
+            handle,
+            _marker: ::core::marker::PhantomData,
+        })
+    }
+}
+
+/// A received event with its decoded payload
+#[derive(Debug, Clone)]
+pub struct TypedEvent<T> {
+    /// Decoded payload
+    pub value: T,
+    /// The raw event, for headers and acknowledgement
+    pub event: Event,
+}
+
+/// A received event whose payload could not be decoded
+#[derive(Debug, Clone)]
+pub struct DecodeError {
+    /// Why decoding failed
+    pub error: EventBusError,
+    /// The raw event, e.g. to log, dead-letter or acknowledge it
+    pub event: alloc::boxed::Box<Event>,
+}
+
+impl ::core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for EventBusError {
+    fn from(error: DecodeError) -> Self {
+        error.error
+    }
+}
+
+/// Subscriber handle that decodes JSON payloads
+#[derive(Debug)]
+pub struct TypedSubscriber<T> {
+    handle: SubscriberHandle,
+    _marker: ::core::marker::PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> TypedSubscriber<T> {
+    /// Receive and decode the next event.
+    ///
+    /// A payload that fails to decode yields an error for that event only,
+    /// carrying the event; later events can still be received. Returns
+    /// `None` once the subscription is closed.
+    pub async fn receive(&self) -> Option<::core::result::Result<TypedEvent<T>, DecodeError>> {
+        self.handle.receive().await.map(decode)
+    }
+
+    /// Try to receive and decode an event (non-blocking)
+    pub fn try_receive(&self) -> Option<::core::result::Result<TypedEvent<T>, DecodeError>> {
+        self.handle.try_receive().map(decode)
+    }
+
+    /// Underlying subscriber handle, e.g. to acknowledge events
+    pub fn handle(&self) -> &SubscriberHandle {
+        &self.handle
+    }
+
+    /// Unsubscribe
+    pub async fn unsubscribe(self) -> Result<()> {
+        self.handle.unsubscribe().await
+    }
+}
+
+/// Decode an event payload as JSON
+fn decode<T: DeserializeOwned>(event: Event) -> ::core::result::Result<TypedEvent<T>, DecodeError> {
+    let failed = |details: alloc::string::String, event: Event| DecodeError {
+        error: EventBusError::SerializationError {
+            operation: "deserialize",
+            details: alloc::format!("event {:?} on '{}': {}", event.id, event.topic, details),
+        },
+        event: alloc::boxed::Box::new(event),
+    };
+
+    if let Some(content_type) = event.headers.get(CONTENT_TYPE_HEADER) {
+        if content_type != JSON_CONTENT_TYPE {
+            let details = alloc::format!("unsupported content type '{}'", content_type);
+            return Err(failed(details, event));
+        }
+    }
+
+    match serde_json::from_slice(&event.payload) {
+        Ok(value) => Ok(TypedEvent { value, event }),
+        Err(e) => Err(failed(alloc::format!("{}", e), event)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct UserCreated {
+        user_id: u64,
+        email: alloc::string::String,
+    }
+
+    #[tokio::test]
+    async fn test_publish_and_receive_typed() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let subscriber = eventbus.subscribe_typed::<UserCreated>("user.*").await.unwrap();
+
+        let user = UserCreated {
+            user_id: 123,
+            email: "ada@example.com".into(),
+        };
+        eventbus.publish_typed("user.created", &user).await.unwrap();
+
+        let received = subscriber.receive().await.unwrap().unwrap();
+        assert_eq!(received.value, user);
+        assert_eq!(received.event.topic, "user.created");
+        assert_eq!(received.event.headers.get(CONTENT_TYPE_HEADER).map(String::as_str), Some(JSON_CONTENT_TYPE));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_payload_is_a_per_event_error() {
+        let mut eventbus = EventBus::new(EventBusConfig::default()).await.unwrap();
+        let subscriber = eventbus.subscribe_typed::<UserCreated>("user.*").await.unwrap();
+
+        eventbus.publish(Event::new("user.created".into(), b"{\"user_id\": \"oops\"".to_vec())).await.unwrap();
+        eventbus
+            .publish(Event::new("user.created".into(), b"<xml/>".to_vec()).with_header(CONTENT_TYPE_HEADER.into(), "text/xml".into()))
+            .await
+            .unwrap();
+        eventbus
+            .publish_typed("user.created", &UserCreated { user_id: 7, email: "x@example.com".into() })
+            .await
+            .unwrap();
+
+        match subscriber.try_receive().unwrap() {
+            Err(DecodeError {
+                error: EventBusError::SerializationError { operation, details },
+                event,
+            }) => {
+                assert_eq!(operation, "deserialize");
+                assert!(details.contains("user.created"));
+                assert_eq!(event.payload, b"{\"user_id\": \"oops\"");
+            }
+            other => panic!("expected decode error, got {:?}", other),
+        }
+        match subscriber.try_receive().unwrap() {
+            Err(DecodeError {
+                error: EventBusError::SerializationError { details, .. },
+                event,
+            }) => {
+                assert!(details.contains("text/xml"));
+                assert_eq!(event.payload, b"<xml/>");
+            }
+            other => panic!("expected content type error, got {:?}", other),
+        }
+
+        // The subscriber keeps working after bad events
+        assert_eq!(subscriber.try_receive().unwrap().unwrap().value.user_id, 7);
+    }
+}
@@ -0,0 +1,320 @@
+//! Structured access logging
+//!
+//! An [`AccessLogger`] writes one JSON line per proxied request, naming its
+//! method, path, matched route, upstream, response status and latency. To
+//! bound log volume only a configured fraction of requests is logged; the
+//! decision is made when the request arrives, so unsampled requests cost a
+//! single random draw. Request headers are logged on demand, minus the
+//! configured sensitive ones. Lines are handed to an [`AccessLogSink`].
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use ::core::time::Duration;
+use std::io::Write;
+use std::time::Instant;
+
+/// Access log record of one request
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    /// When the request arrived, in RFC 3339
+    pub timestamp: String,
+    /// Request method
+    pub method: String,
+    /// Request path
+    pub path: String,
+    /// ID of the matched route
+    pub route: Option<String>,
+    /// URL of the selected upstream
+    pub upstream: Option<String>,
+    /// Response status; `None` if the request failed without a response
+    pub status: Option<u16>,
+    /// Time from arrival to response
+    pub latency: Duration,
+    /// Logged request headers, multiple values joined by `, `
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Error the request failed with, if any
+    pub error: Option<String>,
+    /// When the request arrived
+    started: Instant,
+}
+
+impl AccessLogEntry {
+    /// JSON object written to the access log
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut line = serde_json::json!({
+            "timestamp": self.timestamp,
+            "method": self.method,
+            "path": self.path,
+            "route": self.route,
+            "upstream": self.upstream,
+            "status": self.status,
+            "latency_ms": self.latency.as_secs_f64() * 1000.0,
+        });
+        if let Some(headers) = &self.headers {
+            line["headers"] = serde_json::json!(headers);
+        }
+        if let Some(error) = &self.error {
+            line["error"] = serde_json::json!(error);
+        }
+        line
+    }
+}
+
+/// Destination of access log lines
+pub trait AccessLogSink: Send + Sync {
+    /// Write one line, without the trailing newline
+    fn write(&self, line: &str);
+}
+
+/// Sink writing each line to standard output
+#[derive(Debug, Default)]
+pub struct StdoutSink;
+
+impl AccessLogSink for StdoutSink {
+    fn write(&self, line: &str) {
+        // Logging must never fail a request
+        let _ = writeln!(std::io::stdout().lock(), "{}", line);
+    }
+}
+
+/// Writes sampled requests to the access log
+pub struct AccessLogger {
+    /// Access log configuration
+    config: AccessLogConfig,
+    /// Destination of log lines
+    sink: Arc<dyn AccessLogSink>,
+}
+
+impl ::core::fmt::Debug for AccessLogger {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("AccessLogger")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AccessLogger {
+    /// Create a logger writing to standard output
+    pub fn new(config: AccessLogConfig) -> Self {
+        Self {
+            config,
+            sink: Arc::new(StdoutSink),
+        }
+    }
+
+    /// Write log lines to `sink`
+    pub fn with_sink(mut self, sink: Arc<dyn AccessLogSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Access log configuration
+    pub fn config(&self) -> &AccessLogConfig {
+        &self.config
+    }
+
+    /// Start the entry of `request`, or `None` if logging is disabled or the
+    /// request is not sampled
+    pub fn start(&self, request: &hyper::Request<hyper::Body>) -> Option<AccessLogEntry> {
+        if !self.config.enabled || rand::random::<f64>() >= self.config.sample_rate {
+            return None;
+        }
+
+        let headers = self.config.include_headers.then(|| {
+            let mut headers = BTreeMap::<String, String>::new();
+            for (name, value) in request.headers() {
+                let name = name.as_str();
+                if self.config.omit_headers.iter().any(|omitted| omitted.eq_ignore_ascii_case(name)) {
+                    continue;
+                }
+                let value = String::from_utf8_lossy(value.as_bytes());
+                headers
+                    .entry(name.into())
+                    .and_modify(|values| {
+                        values.push_str(", ");
+                        values.push_str(&value);
+                    })
+                    .or_insert_with(|| value.into_owned());
+            }
+            headers
+        });
+
+        Some(AccessLogEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            method: request.method().as_str().into(),
+            path: request.uri().path().into(),
+            route: None,
+            upstream: None,
+            status: None,
+            latency: Duration::ZERO,
+            headers,
+            error: None,
+            started: Instant::now(),
+        })
+    }
+
+    /// Finish `entry` and write it to the sink
+    pub fn finish(&self, mut entry: AccessLogEntry) {
+        entry.latency = entry.started.elapsed();
+        self.sink.write(&entry.to_json().to_string());
+    }
+
+    /// Proxy `request` to an upstream of `pool` chosen by `balancer`,
+    /// sending it with `send`, and log it if sampled.
+    ///
+    /// Failed requests are logged with the error and, where the error maps
+    /// to one, its status code.
+    pub async fn proxy<F, Fut>(
+        &self,
+        request: hyper::Request<hyper::Body>,
+        router: &Router,
+        pool: &UpstreamPool,
+        balancer: &dyn LoadBalancer,
+        send: F,
+    ) -> Result<hyper::Response<hyper::Body>>
+    where
+        F: FnOnce(Upstream, hyper::Request<hyper::Body>) -> Fut,
+        Fut: ::core::future::Future<Output = Result<hyper::Response<hyper::Body>>>,
+    {
+        let Some(mut entry) = self.start(&request) else {
+            router.find_route(&request)?;
+            let upstream = pool.select(balancer, &request).await?;
+            return send(upstream, request).await;
+        };
+
+        let result = async {
+            entry.route = Some(router.find_route(&request)?.id.clone());
+            let upstream = pool.select(balancer, &request).await?;
+            entry.upstream = Some(upstream.url.to_string());
+            send(upstream, request).await
+        }
+        .await;
+
+        match &result {
+            Ok(response) => entry.status = Some(response.status().as_u16()),
+            Err(error) => {
+                entry.status = Some(error.status_code());
+                entry.error = Some(error.to_string());
+            }
+        }
+        self.finish(entry);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{Body, Request, Response};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct CollectingSink {
+        lines: Mutex<Vec<String>>,
+    }
+
+    impl AccessLogSink for CollectingSink {
+        fn write(&self, line: &str) {
+            self.lines.lock().unwrap().push(line.into());
+        }
+    }
+
+    fn gateway() -> (Router, UpstreamPool) {
+        let mut router = Router::new();
+        router
+            .add_route(RouteBuilder::new("api").path("/api").upstream("http://service1:8080", 1).unwrap().build())
+            .unwrap();
+        let pool = UpstreamPool::new(vec![Upstream {
+            url: "http://service1:8080".parse().unwrap(),
+            ..Default::default()
+        }]);
+        (router, pool)
+    }
+
+    fn request(path: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(alloc::format!("http://gateway{}", path))
+            .header("authorization", "Bearer secret")
+            .header("x-request-id", "abc")
+            .header("accept", "text/plain")
+            .header("accept", "application/json")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_access_log_line_fields() {
+        let (router, pool) = gateway();
+        let sink = Arc::new(CollectingSink::default());
+        let logger = AccessLogger::new(AccessLogConfig {
+            include_headers: true,
+            ..Default::default()
+        })
+        .with_sink(sink.clone());
+
+        logger
+            .proxy(request("/api/users"), &router, &pool, &RoundRobinBalancer::new(), |_, _| async {
+                Ok(Response::builder().status(201).body(Body::empty()).unwrap())
+            })
+            .await
+            .unwrap();
+        assert!(logger
+            .proxy(request("/missing"), &router, &pool, &RoundRobinBalancer::new(), |_, _| async {
+                Ok(Response::new(Body::empty()))
+            })
+            .await
+            .is_err());
+
+        let lines = sink.lines.lock().unwrap();
+        assert_eq!(lines.len(), 2);
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["method"], "POST");
+        assert_eq!(line["path"], "/api/users");
+        assert_eq!(line["route"], "api");
+        assert_eq!(line["upstream"], "http://service1:8080/");
+        assert_eq!(line["status"], 201);
+        assert!(line["latency_ms"].as_f64().unwrap() >= 0.0);
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["headers"]["x-request-id"], "abc");
+        assert_eq!(line["headers"]["accept"], "text/plain, application/json");
+        assert!(line["headers"].get("authorization").is_none());
+        assert!(line.get("error").is_none());
+
+        let line: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(line["path"], "/missing");
+        assert!(line["route"].is_null());
+        assert_eq!(line["status"], 404);
+        assert!(line["error"].is_string());
+    }
+
+    #[test]
+    fn test_sampling_honors_rate() {
+        let sink = Arc::new(CollectingSink::default());
+        let logger = AccessLogger::new(AccessLogConfig {
+            sample_rate: 0.25,
+            ..Default::default()
+        })
+        .with_sink(sink.clone());
+
+        for _ in 0..4000 {
+            if let Some(entry) = logger.start(&request("/api")) {
+                logger.finish(entry);
+            }
+        }
+        // Expected 1000, with a standard deviation of about 27
+        let logged = sink.lines.lock().unwrap().len();
+        assert!((850..=1150).contains(&logged), "{}", logged);
+
+        let line: serde_json::Value = serde_json::from_str(&sink.lines.lock().unwrap()[0]).unwrap();
+        assert!(line.get("headers").is_none());
+
+        let logger = AccessLogger::new(AccessLogConfig {
+            sample_rate: 0.0,
+            ..Default::default()
+        });
+        assert!(logger.start(&request("/api")).is_none());
+    }
+}
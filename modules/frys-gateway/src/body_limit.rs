@@ -0,0 +1,185 @@
+//! Request body size limits
+//!
+//! A route may cap the size of request bodies with `max_body_size`, falling
+//! back to the gateway-wide `max_request_size`. A request declaring a larger
+//! `Content-Length` is refused before any of its body is read. Otherwise the
+//! body is counted as it streams through, and reading it fails with
+//! [`GatewayError::PayloadTooLarge`] (answered with 413) at the first chunk
+//! that crosses the limit, so an oversized chunked upload is never buffered
+//! in full to find out.
+
+use crate::*;
+use ::core::pin::Pin;
+use ::core::task::{Context, Poll};
+use futures::Stream;
+use hyper::body::{Bytes, HttpBody};
+use hyper::header::CONTENT_LENGTH;
+use hyper::Body;
+
+/// Apply a body size limit of `limit` bytes to `request`
+pub fn limit_request_body(request: hyper::Request<Body>, limit: usize) -> Result<hyper::Request<LimitedBody>> {
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+    if let Some(size) = declared.filter(|&size| size > limit) {
+        return Err(GatewayError::PayloadTooLarge { limit, size });
+    }
+    Ok(request.map(|body| LimitedBody::new(body, limit)))
+}
+
+/// Request body that fails once more than its limit has been read
+#[derive(Debug)]
+pub struct LimitedBody {
+    inner: Body,
+    limit: usize,
+    received: usize,
+    exceeded: bool,
+}
+
+impl LimitedBody {
+    /// Limit `inner` to `limit` bytes
+    pub fn new(inner: Body, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            received: 0,
+            exceeded: false,
+        }
+    }
+
+    /// Bytes read so far
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Read the whole body, stopping at the chunk that crosses the limit
+    pub async fn to_bytes(mut self) -> Result<Bytes> {
+        let mut bytes = alloc::vec::Vec::new();
+        while let Some(chunk) = futures::StreamExt::next(&mut self).await {
+            bytes.extend_from_slice(&chunk?);
+        }
+        Ok(bytes.into())
+    }
+
+    /// Body to forward upstream. Crossing the limit aborts it, so the
+    /// upstream never sees a complete oversized request.
+    pub fn into_body(self) -> Body {
+        Body::wrap_stream(self)
+    }
+}
+
+impl Stream for LimitedBody {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.exceeded {
+            return Poll::Ready(None);
+        }
+
+        match Pin::new(&mut self.inner).poll_data(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.received += chunk.len();
+                if self.received > self.limit {
+                    self.exceeded = true;
+                    return Poll::Ready(Some(Err(GatewayError::PayloadTooLarge {
+                        limit: self.limit,
+                        size: self.received,
+                    })));
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(GatewayError::RequestParseError {
+                operation: "read_body".into(),
+                message: e.to_string(),
+            }))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::core::time::Duration;
+    use alloc::sync::Arc;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Client, Request, Response, Server, StatusCode};
+
+    /// Gateway answering each request with the size of its body, or the
+    /// status of the error reading it
+    async fn start_gateway(router: Router) -> std::net::SocketAddr {
+        let router = Arc::new(router);
+        let default_limit = GatewayConfig::default().max_request_size;
+        let make_service = make_service_fn(move |_| {
+            let router = router.clone();
+            async move {
+                Ok::<_, ::core::convert::Infallible>(service_fn(move |request: Request<Body>| {
+                    let router = router.clone();
+                    async move {
+                        let result = async {
+                            let limit = router.find_route(&request)?.body_limit(default_limit);
+                            limit_request_body(request, limit)?.into_body().to_bytes().await
+                        }
+                        .await;
+                        let response = match result {
+                            Ok(body) => Response::new(Body::from(body.len().to_string())),
+                            Err(error) => {
+                                let mut response = Response::new(Body::from(error.to_string()));
+                                *response.status_mut() = StatusCode::from_u16(error.status_code()).unwrap();
+                                response
+                            }
+                        };
+                        Ok::<_, ::core::convert::Infallible>(response)
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    fn post(addr: std::net::SocketAddr, path: &str, body: Body) -> Request<Body> {
+        Request::post(format!("http://{}{}", addr, path)).body(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_rejected_without_full_buffering() {
+        let mut router = Router::new();
+        router
+            .add_route(RouteBuilder::new("login").path("/auth/login").upstream("http://auth:8080", 1).unwrap().max_body_size(1024).build())
+            .unwrap();
+        router
+            .add_route(RouteBuilder::new("upload").path("/upload").upstream("http://files:8080", 1).unwrap().build())
+            .unwrap();
+        let addr = start_gateway(router).await;
+        let client = Client::new();
+
+        // Chunked, so the size is only known while streaming. The body is
+        // never finished: the 413 must come from the chunks seen so far.
+        let (mut sender, body) = Body::channel();
+        let response = tokio::spawn(client.request(post(addr, "/auth/login", body)));
+        sender.send_data(Bytes::from(vec![b'a'; 600])).await.unwrap();
+        sender.send_data(Bytes::from(vec![b'a'; 600])).await.unwrap();
+        let response = tokio::time::timeout(Duration::from_secs(5), response)
+            .await
+            .expect("rejected before the body ended")
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        drop(sender);
+
+        // A declared length over the limit is refused up front
+        let response = client.request(post(addr, "/auth/login", Body::from(vec![b'a'; 2048]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // The same body fits the gateway-wide limit of other routes
+        let response = client.request(post(addr, "/upload", Body::from(vec![b'a'; 1200]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, "1200");
+    }
+}
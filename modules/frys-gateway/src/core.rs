@@ -23,6 +23,8 @@ pub struct GatewayConfig {
     pub metrics: MetricsConfig,
     /// Tracing configuration
     pub tracing: TracingConfig,
+    /// Access log configuration
+    pub access_log: AccessLogConfig,
     /// Connection limits
     pub connection_limits: ConnectionLimits,
     /// Request timeout
@@ -46,6 +48,7 @@ impl Default for GatewayConfig {
             health_check: HealthCheckConfig::default(),
             metrics: MetricsConfig::default(),
             tracing: TracingConfig::default(),
+            access_log: AccessLogConfig::default(),
             connection_limits: ConnectionLimits::default(),
             request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT),
             max_request_size: DEFAULT_MAX_REQUEST_SIZE,
@@ -127,6 +130,16 @@ pub struct Route {
     pub timeout: Option<Duration>,
     /// Retry configuration
     pub retry: Option<RetryConfig>,
+    /// Maximum request body size, overriding the gateway-wide
+    /// `max_request_size`
+    pub max_body_size: Option<usize>,
+}
+
+impl Route {
+    /// Request body size limit of this route, or `default` if it sets none
+    pub fn body_limit(&self, default: usize) -> usize {
+        self.max_body_size.unwrap_or(default)
+    }
 }
 
 impl Default for Route {
@@ -147,6 +160,7 @@ impl Default for Route {
             circuit_breaker: None,
             timeout: None,
             retry: None,
+            max_body_size: None,
         }
     }
 }
@@ -174,6 +188,23 @@ pub enum Method {
     TRACE,
 }
 
+impl Method {
+    /// Method name as sent on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::GET => "GET",
+            Method::POST => "POST",
+            Method::PUT => "PUT",
+            Method::DELETE => "DELETE",
+            Method::PATCH => "PATCH",
+            Method::HEAD => "HEAD",
+            Method::OPTIONS => "OPTIONS",
+            Method::CONNECT => "CONNECT",
+            Method::TRACE => "TRACE",
+        }
+    }
+}
+
 /// Upstream service configuration
 #[derive(Debug, Clone)]
 pub struct Upstream {
@@ -298,7 +329,7 @@ pub struct PathParam {
 }
 
 /// Protocol types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Protocol {
     /// HTTP/1.1
     HTTP,
@@ -525,6 +556,33 @@ pub enum TracingBackend {
     },
 }
 
+/// Access log configuration
+#[derive(Debug, Clone)]
+pub struct AccessLogConfig {
+    /// Enable access logging
+    pub enabled: bool,
+    /// Fraction of requests logged (0.0 to 1.0)
+    pub sample_rate: f64,
+    /// Log request headers
+    pub include_headers: bool,
+    /// Headers never logged, matched case-insensitively
+    pub omit_headers: alloc::vec::Vec<alloc::string::String>,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_rate: 1.0,
+            include_headers: false,
+            omit_headers: ["authorization", "proxy-authorization", "cookie", "set-cookie", "x-api-key"]
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
 /// Connection limits
 #[derive(Debug, Clone)]
 pub struct ConnectionLimits {
@@ -592,6 +650,22 @@ pub struct RateLimitConfig {
     pub distributed: bool,
     /// Redis URL for distributed limiting
     pub redis_url: Option<alloc::string::String>,
+    /// Proxies whose `X-Forwarded-For` and `X-Real-IP` headers are
+    /// believed when keying by IP; other peers are keyed by their address
+    pub trusted_proxies: alloc::vec::Vec<std::net::IpAddr>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: DEFAULT_RATE_LIMIT_RPS,
+            burst_size: DEFAULT_RATE_LIMIT_RPS / 10,
+            key_strategy: RateLimitKey::IP,
+            distributed: false,
+            redis_url: None,
+            trusted_proxies: alloc::vec::Vec::new(),
+        }
+    }
 }
 
 /// Rate limiting key strategies
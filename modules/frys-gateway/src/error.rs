@@ -211,6 +211,15 @@ pub enum GatewayError {
         limit: alloc::string::String,
     },
 
+    /// Request body larger than the route allows
+    PayloadTooLarge {
+        /// Body size limit in bytes
+        limit: usize,
+        /// Bytes declared by `Content-Length`, or received before the
+        /// limit was crossed
+        size: usize,
+    },
+
     /// Initialization error
     InitializationError {
         /// Component being initialized
@@ -342,6 +351,24 @@ pub enum ConnectionPhase {
     KeepAlive,
 }
 
+impl GatewayError {
+    /// HTTP status code to answer the client with
+    pub fn status_code(&self) -> u16 {
+        match self {
+            GatewayError::RouteNotFound { .. } => 404,
+            GatewayError::AuthenticationError { .. } => 401,
+            GatewayError::AuthorizationError { .. } => 403,
+            GatewayError::PayloadTooLarge { .. } => 413,
+            GatewayError::RateLimitExceeded { .. } => 429,
+            GatewayError::RequestParseError { .. } | GatewayError::ValidationError { .. } => 400,
+            GatewayError::UpstreamError { .. } | GatewayError::ConnectionError { .. } => 502,
+            GatewayError::CircuitBreakerOpen { .. } | GatewayError::ResourceLimitExceeded { .. } => 503,
+            GatewayError::TimeoutError { .. } => 504,
+            _ => 500,
+        }
+    }
+}
+
 impl fmt::Display for GatewayError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -420,6 +447,9 @@ impl fmt::Display for GatewayError {
             GatewayError::ResourceLimitExceeded { resource, current, limit } => {
                 write!(f, "Resource limit exceeded for '{}': {} > {}", resource, current, limit)
             }
+            GatewayError::PayloadTooLarge { limit, size } => {
+                write!(f, "Payload too large: {} bytes exceeds the limit of {} bytes", size, limit)
+            }
             GatewayError::InitializationError { component, message } => {
                 write!(f, "Initialization error for '{}': {}", component, message)
             }
@@ -609,4 +639,20 @@ mod tests {
         assert!(display.contains("request_processing"));
         assert!(display.contains("unexpected error occurred"));
     }
+
+    #[test]
+    fn test_status_codes() {
+        let unauthenticated = GatewayError::AuthenticationError {
+            scheme: "Bearer".into(),
+            reason: "token has expired".into(),
+        };
+        let forbidden = GatewayError::AuthorizationError {
+            required_permission: "admin".into(),
+            user_permissions: vec!["read".into()],
+        };
+        assert_eq!(unauthenticated.status_code(), 401);
+        assert_eq!(forbidden.status_code(), 403);
+        assert_eq!(GatewayError::TimeoutError { operation: "proxy".into(), timeout_seconds: 30 }.status_code(), 504);
+        assert_eq!(GatewayError::PayloadTooLarge { limit: 1024, size: 4096 }.status_code(), 413);
+    }
 }
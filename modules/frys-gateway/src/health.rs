@@ -0,0 +1,327 @@
+//! Active upstream health checking
+//!
+//! A [`HealthChecker`] probes every upstream of an [`UpstreamPool`] on the
+//! configured interval. Upstreams failing `unhealthy_threshold` checks in a
+//! row leave the pool's active set, so load balancers stop selecting them,
+//! and rejoin it after `healthy_threshold` consecutive successful checks.
+
+use crate::*;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ::core::time::Duration;
+use std::sync::RwLock;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Probe deciding whether an upstream is healthy
+#[async_trait::async_trait]
+pub trait HealthProbe: Send + Sync {
+    /// Run `check` against `upstream`
+    async fn probe(&self, upstream: &Upstream, check: &HealthCheckType) -> bool;
+}
+
+/// Probe over the network.
+///
+/// HTTP checks issue a `GET` and compare the status and, if configured,
+/// the body; TCP checks connect and optionally exchange data. gRPC and
+/// custom checks are not spoken natively and fall back to a TCP connect.
+#[derive(Debug, Default)]
+pub struct NetworkProbe;
+
+impl NetworkProbe {
+    /// Host and port of `upstream`, with `port` overriding the URL's port
+    fn address(upstream: &Upstream, port: Option<u16>) -> Option<alloc::string::String> {
+        let host = upstream.url.host_str()?;
+        let port = port.or_else(|| upstream.url.port_or_known_default())?;
+        Some(alloc::format!("{}:{}", host, port))
+    }
+
+    async fn http(
+        upstream: &Upstream,
+        path: &str,
+        expected_status: u16,
+        expected_body: Option<&str>,
+        headers: &alloc::collections::BTreeMap<alloc::string::String, alloc::string::String>,
+    ) -> std::io::Result<bool> {
+        let address = Self::address(upstream, None).ok_or(std::io::ErrorKind::InvalidInput)?;
+        let mut stream = TcpStream::connect(&address).await?;
+        let mut request = alloc::format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n", path, address);
+        for (name, value) in headers {
+            request.push_str(&alloc::format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        let response = alloc::string::String::from_utf8_lossy(&response);
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok());
+        let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+        Ok(status == Some(expected_status) && expected_body.is_none_or(|expected| body.contains(expected)))
+    }
+
+    async fn tcp(upstream: &Upstream, port: Option<u16>, send: Option<&[u8]>, expected: Option<&[u8]>) -> std::io::Result<bool> {
+        let address = Self::address(upstream, port).ok_or(std::io::ErrorKind::InvalidInput)?;
+        let mut stream = TcpStream::connect(address).await?;
+        if let Some(data) = send {
+            stream.write_all(data).await?;
+        }
+        let Some(expected) = expected else {
+            return Ok(true);
+        };
+        let mut received = alloc::vec![0; expected.len()];
+        stream.read_exact(&mut received).await?;
+        Ok(received == expected)
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthProbe for NetworkProbe {
+    async fn probe(&self, upstream: &Upstream, check: &HealthCheckType) -> bool {
+        let result = match check {
+            HealthCheckType::Http { path, expected_status, expected_body, headers } => {
+                Self::http(upstream, path, *expected_status, expected_body.as_deref(), headers).await
+            }
+            HealthCheckType::Tcp { port, send_data, expected_response } => {
+                Self::tcp(upstream, Some(*port), send_data.as_deref(), expected_response.as_deref()).await
+            }
+            HealthCheckType::Grpc { .. } | HealthCheckType::Custom { .. } => Self::tcp(upstream, None, None, None).await,
+        };
+        result.unwrap_or(false)
+    }
+}
+
+/// Upstreams of a route and the subset currently eligible for traffic
+#[derive(Debug)]
+pub struct UpstreamPool {
+    /// All configured upstreams
+    upstreams: Vec<Upstream>,
+    /// Upstreams currently considered healthy, in configuration order
+    active: RwLock<Vec<Upstream>>,
+    /// Health state per upstream URL
+    tracker: UpstreamHealthTracker,
+}
+
+impl UpstreamPool {
+    /// Create a pool in which every upstream starts out active
+    pub fn new(upstreams: Vec<Upstream>) -> Self {
+        Self {
+            active: RwLock::new(upstreams.clone()),
+            upstreams,
+            tracker: UpstreamHealthTracker::new(),
+        }
+    }
+
+    /// All configured upstreams, healthy or not
+    pub fn upstreams(&self) -> &[Upstream] {
+        &self.upstreams
+    }
+
+    /// Upstreams currently eligible for traffic
+    pub fn active(&self) -> Vec<Upstream> {
+        self.active.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Health state of the upstreams
+    pub fn tracker(&self) -> &UpstreamHealthTracker {
+        &self.tracker
+    }
+
+    /// Record a health check of `upstream`, updating the active set when
+    /// its state changes. Returns the new state if it changed.
+    pub fn record_check(&self, upstream: &Upstream, success: bool, config: &HealthCheckConfig) -> Option<bool> {
+        let changed = self.tracker.record_check(upstream.url.as_str(), success, config);
+        if changed.is_some() {
+            let active = self
+                .upstreams
+                .iter()
+                .filter(|upstream| self.tracker.is_healthy(upstream.url.as_str()))
+                .cloned()
+                .collect();
+            *self.active.write().unwrap_or_else(|e| e.into_inner()) = active;
+        }
+        changed
+    }
+
+    /// Select an upstream for `req` among the active ones
+    pub async fn select(&self, balancer: &dyn LoadBalancer, req: &Request) -> Result<Upstream> {
+        let active = self.active();
+        balancer.select_upstream(req, &active).await.cloned()
+    }
+}
+
+/// Periodically probes the upstreams of a pool
+pub struct HealthChecker {
+    /// Default check settings; upstreams may override them
+    config: HealthCheckConfig,
+    /// Probe used for checks
+    probe: Arc<dyn HealthProbe>,
+}
+
+impl ::core::fmt::Debug for HealthChecker {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("HealthChecker")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HealthChecker {
+    /// Create a checker probing over the network
+    pub fn new(config: HealthCheckConfig) -> Self {
+        Self {
+            config,
+            probe: Arc::new(NetworkProbe),
+        }
+    }
+
+    /// Use `probe` instead of the network probe
+    pub fn with_probe(mut self, probe: Arc<dyn HealthProbe>) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    /// Default check settings
+    pub fn config(&self) -> &HealthCheckConfig {
+        &self.config
+    }
+
+    /// Check every upstream of `pool` once, including inactive ones so they
+    /// can recover. Returns the upstreams whose state changed, with their
+    /// new state.
+    pub async fn check_pool(&self, pool: &UpstreamPool) -> Vec<(Upstream, bool)> {
+        let checks = pool.upstreams().iter().map(|upstream| async move {
+            let config = upstream.health_check.as_ref().unwrap_or(&self.config);
+            let healthy = tokio::time::timeout(config.timeout, self.probe.probe(upstream, &config.check_type))
+                .await
+                .unwrap_or(false);
+            (upstream, config, healthy)
+        });
+
+        futures::future::join_all(checks)
+            .await
+            .into_iter()
+            .filter_map(|(upstream, config, healthy)| {
+                pool.record_check(upstream, healthy, config).map(|state| (upstream.clone(), state))
+            })
+            .collect()
+    }
+
+    /// Check `pool` every `interval`, after the initial delay, until the
+    /// returned future is dropped
+    pub async fn run(&self, pool: &UpstreamPool) {
+        tokio::time::sleep(self.config.initial_delay).await;
+        let mut interval = tokio::time::interval(self.config.interval.max(Duration::from_millis(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.check_pool(pool).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{Body, Request};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    /// Probe reporting every upstream healthy unless marked down
+    #[derive(Default)]
+    struct MockProbe {
+        down: Mutex<HashSet<alloc::string::String>>,
+    }
+
+    impl MockProbe {
+        fn set_down(&self, url: &str, down: bool) {
+            let mut set = self.down.lock().unwrap();
+            if down {
+                set.insert(url.into());
+            } else {
+                set.remove(url);
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HealthProbe for MockProbe {
+        async fn probe(&self, upstream: &Upstream, _check: &HealthCheckType) -> bool {
+            !self.down.lock().unwrap().contains(upstream.url.as_str())
+        }
+    }
+
+    async fn traffic(pool: &UpstreamPool, balancer: &dyn LoadBalancer) -> HashSet<alloc::string::String> {
+        let req = Request::builder().uri("http://gateway/api").body(Body::empty()).unwrap();
+        let mut seen = HashSet::new();
+        for _ in 0..6 {
+            seen.insert(pool.select(balancer, &req).await.unwrap().url.to_string());
+        }
+        seen
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_upstream_leaves_and_rejoins_pool() {
+        let upstream = |url: &str| Upstream {
+            url: url.parse().unwrap(),
+            ..Default::default()
+        };
+        let pool = UpstreamPool::new(vec![upstream("http://service1:8080"), upstream("http://service2:8080")]);
+        let probe = Arc::new(MockProbe::default());
+        let checker = HealthChecker::new(HealthCheckConfig::default()).with_probe(probe.clone());
+        let balancer = RoundRobinBalancer::new();
+        let both: HashSet<_> = ["http://service1:8080/".to_string(), "http://service2:8080/".to_string()].into();
+
+        assert_eq!(traffic(&pool, &balancer).await, both);
+
+        // Two failures are below the unhealthy threshold of three
+        probe.set_down("http://service2:8080/", true);
+        assert!(checker.check_pool(&pool).await.is_empty());
+        assert!(checker.check_pool(&pool).await.is_empty());
+        assert_eq!(traffic(&pool, &balancer).await, both);
+
+        let changes = checker.check_pool(&pool).await;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0.url.as_str(), "http://service2:8080/");
+        assert!(!changes[0].1);
+        assert_eq!(traffic(&pool, &balancer).await, HashSet::from(["http://service1:8080/".to_string()]));
+
+        // Recovery takes two successful checks
+        probe.set_down("http://service2:8080/", false);
+        assert!(checker.check_pool(&pool).await.is_empty());
+        assert_eq!(pool.active().len(), 1);
+        assert_eq!(checker.check_pool(&pool).await.len(), 1);
+        assert_eq!(traffic(&pool, &balancer).await, both);
+    }
+
+    #[tokio::test]
+    async fn test_network_probe_http() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for status in ["200 OK", "503 Service Unavailable"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = [0; 1024];
+                let _ = socket.read(&mut buffer).await.unwrap();
+                let response = alloc::format!("HTTP/1.1 {}\r\nContent-Length: 15\r\n\r\n{{\"status\":\"ok\"}}", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let upstream = Upstream {
+            url: alloc::format!("http://{}", address).parse().unwrap(),
+            ..Default::default()
+        };
+        let check = HealthCheckType::Http {
+            path: "/health".into(),
+            expected_status: 200,
+            expected_body: Some("\"ok\"".into()),
+            headers: Default::default(),
+        };
+        assert!(NetworkProbe.probe(&upstream, &check).await);
+        assert!(!NetworkProbe.probe(&upstream, &check).await);
+    }
+}
@@ -178,16 +178,14 @@
 //! ```rust,no_run
 //! let auth_middleware = Middleware::Auth(AuthConfig {
 //!     jwt_secret: env::var("JWT_SECRET")?,
-//!     required_scopes: vec!["read:users".into(), "write:users".into()],
-//!     role_mapping: HashMap::from([
-//!         ("admin".into(), vec!["read:*".into(), "write:*".into()]),
-//!         ("user".into(), vec!["read:users".into()]),
-//!     ]),
-//!     oidc_config: Some(OIDCConfig {
-//!         issuer: "https://auth.example.com".into(),
-//!         client_id: "gateway".into(),
-//!         client_secret: env::var("OIDC_CLIENT_SECRET")?,
+//!     // RS256/ES256 keys, refreshed every five minutes
+//!     jwks: Some(JwksConfig {
+//!         url: "https://auth.example.com/.well-known/jwks.json".into(),
+//!         ..Default::default()
 //!     }),
+//!     required_claims: vec!["sub".into()],
+//!     required_scopes: vec!["read:users".into(), "write:users".into()],
+//!     ..Default::default()
 //! });
 //! ```
 //!
@@ -200,6 +198,7 @@
 //!     key_strategy: RateLimitKey::IP,
 //!     distributed: true,
 //!     redis_url: Some(env::var("REDIS_URL")?),
+//!     trusted_proxies: vec!["10.0.0.2".parse()?],
 //! });
 //! ```
 //!
@@ -267,19 +266,30 @@
 //! ### Distributed Tracing
 //!
 //! ```rust,no_run
-//! let tracer = Tracer::jaeger(JaegerConfig {
-//!     endpoint: "http://jaeger:14268/api/traces".into(),
+//! let tracer = Tracer::new(TracingConfig {
 //!     service_name: "frys-gateway".into(),
-//!     tags: HashMap::from([
-//!         ("version".into(), env!("CARGO_PKG_VERSION").into()),
-//!     ]),
-//! }).await?;
+//!     sampling_rate: 0.1,
+//!     ..Default::default()
+//! })
+//! .with_exporter(exporter);
 //!
-//! // Traces automatically include:
-//! // - Request/response headers
-//! // - Upstream selection
-//! // - Middleware execution
-//! // - Error details
+//! // The upstream request carries a W3C `traceparent` naming the gateway
+//! // span, whose phases time routing, upstream selection and the upstream
+//! // call
+//! let response = tracer.proxy(request, &router, &pool, &balancer, send_upstream).await?;
+//! ```
+//!
+//! ### Access Logging
+//!
+//! ```rust,no_run
+//! // One JSON line per sampled request: method, path, route, upstream,
+//! // status and latency
+//! let access_log = AccessLogger::new(AccessLogConfig {
+//!     sample_rate: 0.05,
+//!     include_headers: true,
+//!     ..Default::default()
+//! });
+//! let response = access_log.proxy(request, &router, &pool, &balancer, send_upstream).await?;
 //! ```
 //!
 //! ## Performance Goals
@@ -321,18 +331,41 @@
 #![warn(clippy::pedantic)]
 
 // Public API exports
+#[cfg(feature = "http")]
+pub mod access_log;
 pub mod core;
 pub mod handlers;
 pub mod middleware;
 pub mod routing;
+#[cfg(feature = "http")]
+pub mod body_limit;
 pub mod load_balancing;
+#[cfg(feature = "http")]
+pub mod health;
+pub mod protocol;
+pub mod rate_limit;
+pub mod response_cache;
 pub mod security;
+#[cfg(feature = "http")]
+pub mod trace;
 
 // Re-exports for convenience
+#[cfg(feature = "http")]
+pub use access_log::*;
 pub use core::*;
 pub use routing::*;
+#[cfg(feature = "http")]
+pub use body_limit::*;
 pub use load_balancing::*;
+#[cfg(feature = "http")]
+pub use health::*;
 pub use middleware::*;
+pub use protocol::*;
+pub use rate_limit::*;
+pub use response_cache::*;
+pub use security::*;
+#[cfg(feature = "http")]
+pub use trace::*;
 
 // Error types
 mod error;
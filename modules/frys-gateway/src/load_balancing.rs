@@ -440,6 +440,7 @@ pub struct UpstreamHealthTracker {
 struct UpstreamHealth {
     healthy: bool,
     consecutive_failures: u32,
+    consecutive_successes: u32,
     last_check: u64,
     last_success: u64,
     last_failure: u64,
@@ -468,6 +469,7 @@ impl UpstreamHealthTracker {
             .or_insert(UpstreamHealth {
                 healthy: true,
                 consecutive_failures: 0,
+                consecutive_successes: 0,
                 last_check: current_timestamp(),
                 last_success: current_timestamp(),
                 last_failure: 0,
@@ -485,6 +487,7 @@ impl UpstreamHealthTracker {
             .or_insert(UpstreamHealth {
                 healthy: true,
                 consecutive_failures: 0,
+                consecutive_successes: 0,
                 last_check: current_timestamp(),
                 last_success: 0,
                 last_failure: current_timestamp(),
@@ -499,6 +502,44 @@ impl UpstreamHealthTracker {
         }
     }
 
+    /// Record the outcome of an active health check.
+    ///
+    /// An upstream turns unhealthy after `unhealthy_threshold` consecutive
+    /// failed checks and healthy again after `healthy_threshold`
+    /// consecutive successful ones. Returns the new state if it changed.
+    pub fn record_check(&self, upstream_url: &str, success: bool, config: &HealthCheckConfig) -> Option<bool> {
+        let mut health_status = self.health_status.lock().unwrap();
+        let health = health_status.entry(upstream_url.to_string())
+            .or_insert(UpstreamHealth {
+                healthy: true,
+                consecutive_failures: 0,
+                consecutive_successes: 0,
+                last_check: 0,
+                last_success: 0,
+                last_failure: 0,
+            });
+
+        health.last_check = current_timestamp();
+        let was_healthy = health.healthy;
+        if success {
+            health.consecutive_failures = 0;
+            health.consecutive_successes += 1;
+            health.last_success = current_timestamp();
+            if health.consecutive_successes >= config.healthy_threshold {
+                health.healthy = true;
+            }
+        } else {
+            health.consecutive_successes = 0;
+            health.consecutive_failures += 1;
+            health.last_failure = current_timestamp();
+            if health.consecutive_failures >= config.unhealthy_threshold {
+                health.healthy = false;
+            }
+        }
+
+        (health.healthy != was_healthy).then_some(health.healthy)
+    }
+
     /// Mark upstream as healthy (after recovery)
     pub fn mark_healthy(&self, upstream_url: &str) {
         let mut health_status = self.health_status.lock().unwrap();
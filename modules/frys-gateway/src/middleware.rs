@@ -0,0 +1,58 @@
+//! Middleware configuration
+//!
+//! Routes and the gateway as a whole carry an ordered list of
+//! [`Middleware`] entries; each names one processing stage and its settings.
+
+use crate::*;
+
+/// Request/response processing stage
+#[derive(Debug, Clone)]
+pub enum Middleware {
+    /// Token bucket rate limiting, see [`RateLimiter`]
+    RateLimit(RateLimitConfig),
+    /// Bearer token authentication, see [`JwtValidator`]
+    Auth(AuthConfig),
+    /// Cross-origin resource sharing headers
+    Cors(CorsConfig),
+    /// Response compression
+    Compression(CompressionConfig),
+    /// Upstream circuit breaking
+    CircuitBreaker(CircuitBreakerConfig),
+    /// Response caching, see [`ResponseCache`]
+    Cache(CacheConfig),
+}
+
+/// CORS configuration
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Allowed origins, `*` for any
+    pub allowed_origins: alloc::vec::Vec<alloc::string::String>,
+    /// Allowed methods
+    pub allowed_methods: alloc::vec::Vec<alloc::string::String>,
+    /// Allowed request headers, `*` for any
+    pub allowed_headers: alloc::vec::Vec<alloc::string::String>,
+    /// Whether credentials may be sent
+    pub allow_credentials: bool,
+    /// Preflight cache lifetime in seconds
+    pub max_age: u64,
+}
+
+/// Response compression configuration
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    /// Compression level
+    pub level: CompressionLevel,
+    /// Smallest body worth compressing, in bytes
+    pub min_length: usize,
+}
+
+/// Compression levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionLevel {
+    /// Fastest compression
+    Fast,
+    /// Balanced speed and ratio
+    Default,
+    /// Best ratio
+    Best,
+}
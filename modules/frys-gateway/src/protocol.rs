@@ -0,0 +1,668 @@
+//! Downstream protocol detection for serving several protocols on one port
+
+use crate::*;
+
+/// HTTP/2 connection preface sent by prior-knowledge h2c clients
+pub const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Maximum number of bytes buffered while looking for the end of the request head
+pub const MAX_SNIFF_BYTES: usize = 16 * 1024;
+
+/// Time a connection gets to send enough bytes to identify its protocol
+pub const DEFAULT_SNIFF_TIMEOUT: core::time::Duration = core::time::Duration::from_secs(10);
+
+/// Result of inspecting the first bytes of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detection {
+    /// The protocol was identified
+    Detected(DetectedProtocol),
+    /// More bytes are needed to decide
+    NeedMoreData,
+}
+
+/// Protocol spoken by a downstream connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedProtocol {
+    /// Plain HTTP/1.x request
+    Http1,
+    /// HTTP/2 with prior knowledge (cleartext preface or ALPN `h2`)
+    Http2PriorKnowledge,
+    /// HTTP/1.1 request asking to upgrade to h2c
+    Http2Upgrade,
+    /// HTTP/1.1 request asking to upgrade to WebSocket
+    WebSocket,
+}
+
+impl DetectedProtocol {
+    /// Gateway protocol that handles this connection
+    pub fn protocol(&self) -> Protocol {
+        match self {
+            DetectedProtocol::Http1 => Protocol::HTTP,
+            DetectedProtocol::Http2PriorKnowledge | DetectedProtocol::Http2Upgrade => Protocol::HTTP2,
+            DetectedProtocol::WebSocket => Protocol::WebSocket,
+        }
+    }
+}
+
+/// Map a TLS ALPN identifier to a protocol.
+///
+/// `h2` is final; `http/1.1` still needs the request head to be sniffed for
+/// a WebSocket upgrade, so it yields `None` like an unknown identifier.
+pub fn protocol_from_alpn(alpn: &[u8]) -> Option<DetectedProtocol> {
+    match alpn {
+        b"h2" => Some(DetectedProtocol::Http2PriorKnowledge),
+        _ => None,
+    }
+}
+
+/// Detect the protocol from the bytes received so far
+pub fn detect_protocol(prefix: &[u8]) -> Result<Detection> {
+    let preface_len = prefix.len().min(HTTP2_PREFACE.len());
+    if prefix[..preface_len] == HTTP2_PREFACE[..preface_len] {
+        return Ok(if prefix.len() >= HTTP2_PREFACE.len() {
+            Detection::Detected(DetectedProtocol::Http2PriorKnowledge)
+        } else {
+            Detection::NeedMoreData
+        });
+    }
+
+    let head_end = match prefix.windows(4).position(|w| w == b"\r\n\r\n") {
+        Some(end) => end,
+        None if prefix.len() >= MAX_SNIFF_BYTES => {
+            return Err(GatewayError::ResourceLimitExceeded {
+                resource: "request_head_bytes".into(),
+                current: prefix.len().to_string(),
+                limit: MAX_SNIFF_BYTES.to_string(),
+            })
+        }
+        None => return Ok(Detection::NeedMoreData),
+    };
+
+    let head = core::str::from_utf8(&prefix[..head_end]).map_err(|e| GatewayError::RequestParseError {
+        operation: "protocol detection".into(),
+        message: e.to_string(),
+    })?;
+
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    if !request_line.ends_with("HTTP/1.1") && !request_line.ends_with("HTTP/1.0") {
+        return Err(GatewayError::ProtocolError {
+            protocol: "HTTP".into(),
+            message: format!("unrecognized request line '{}'", request_line),
+        });
+    }
+
+    let mut upgrade = None;
+    let mut connection_upgrade = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("upgrade") {
+            upgrade = Some(value.to_ascii_lowercase());
+        } else if name.eq_ignore_ascii_case("connection") {
+            connection_upgrade |= value.split(',').any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+        }
+    }
+
+    let protocol = match upgrade.as_deref() {
+        Some(upgrade) if connection_upgrade && upgrade.split(',').any(|p| p.trim() == "websocket") => {
+            DetectedProtocol::WebSocket
+        }
+        Some(upgrade) if connection_upgrade && upgrade.split(',').any(|p| p.trim() == "h2c") => {
+            DetectedProtocol::Http2Upgrade
+        }
+        _ => DetectedProtocol::Http1,
+    };
+
+    Ok(Detection::Detected(protocol))
+}
+
+#[cfg(feature = "http")]
+pub use listener::*;
+
+#[cfg(feature = "http")]
+mod listener {
+    use super::*;
+    use alloc::sync::Arc;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+    use std::collections::HashMap;
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Stream that replays the bytes consumed during detection before
+    /// reading from the underlying connection
+    #[derive(Debug)]
+    pub struct SniffedStream<S> {
+        /// Bytes read during detection not yet handed out
+        prefix: alloc::vec::Vec<u8>,
+        /// Read position within `prefix`
+        position: usize,
+        /// Underlying connection
+        inner: S,
+    }
+
+    impl<S> SniffedStream<S> {
+        /// Wrap a connection whose first bytes were already read
+        pub fn new(prefix: alloc::vec::Vec<u8>, inner: S) -> Self {
+            Self { prefix, position: 0, inner }
+        }
+
+        /// Bytes consumed during detection
+        pub fn prefix(&self) -> &[u8] {
+            &self.prefix
+        }
+
+        /// Get the underlying connection
+        pub fn into_inner(self) -> S {
+            self.inner
+        }
+    }
+
+    impl<S: AsyncRead + Unpin> AsyncRead for SniffedStream<S> {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            if self.position < self.prefix.len() {
+                let remaining = &self.prefix[self.position..];
+                let n = remaining.len().min(buf.remaining());
+                buf.put_slice(&remaining[..n]);
+                self.position += n;
+                return Poll::Ready(Ok(()));
+            }
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<S: AsyncWrite + Unpin> AsyncWrite for SniffedStream<S> {
+        fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    /// Tells connection handlers that the server is shutting down
+    #[derive(Debug, Clone)]
+    pub struct ShutdownSignal {
+        /// Set to `true` once shutdown begins
+        receiver: tokio::sync::watch::Receiver<bool>,
+    }
+
+    impl ShutdownSignal {
+        /// Signal that never fires, for connections served until they end
+        pub fn never() -> Self {
+            let (_, receiver) = tokio::sync::watch::channel(false);
+            Self { receiver }
+        }
+
+        /// Whether shutdown has begun
+        pub fn is_shutting_down(&self) -> bool {
+            *self.receiver.borrow()
+        }
+
+        /// Wait until shutdown begins
+        pub async fn recv(&mut self) {
+            while !*self.receiver.borrow_and_update() {
+                if self.receiver.changed().await.is_err() {
+                    // The server is gone without shutting down
+                    core::future::pending::<()>().await;
+                }
+            }
+        }
+    }
+
+    /// Handler for connections of a single protocol
+    #[async_trait::async_trait]
+    pub trait ConnectionHandler: Send + Sync {
+        /// Serve a connection; `protocol` tells how it was detected.
+        ///
+        /// Once `shutdown` fires the handler should finish the exchange in
+        /// progress and close the connection: stop keep-alive, send a
+        /// WebSocket close frame, or send HTTP/2 GOAWAY.
+        async fn handle(&self, protocol: DetectedProtocol, stream: SniffedStream<TcpStream>, shutdown: ShutdownSignal) -> Result<()>;
+    }
+
+    /// Accepts connections on one port and dispatches them by protocol
+    #[derive(Clone)]
+    pub struct ProtocolDispatcher {
+        /// Handlers keyed by gateway protocol
+        handlers: HashMap<Protocol, Arc<dyn ConnectionHandler>>,
+        /// Time a connection gets to identify its protocol
+        sniff_timeout: core::time::Duration,
+    }
+
+    impl Default for ProtocolDispatcher {
+        fn default() -> Self {
+            Self {
+                handlers: HashMap::new(),
+                sniff_timeout: DEFAULT_SNIFF_TIMEOUT,
+            }
+        }
+    }
+
+    impl ProtocolDispatcher {
+        /// Create a dispatcher without handlers
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Register the handler for a protocol
+        pub fn with_handler(mut self, protocol: Protocol, handler: Arc<dyn ConnectionHandler>) -> Self {
+            self.handlers.insert(protocol, handler);
+            self
+        }
+
+        /// Close connections that have not identified their protocol within
+        /// `timeout`
+        pub fn with_sniff_timeout(mut self, timeout: core::time::Duration) -> Self {
+            self.sniff_timeout = timeout;
+            self
+        }
+
+        /// Read just enough of the connection to identify its protocol
+        pub async fn detect<S: AsyncRead + Unpin>(stream: S) -> Result<(DetectedProtocol, SniffedStream<S>)> {
+            let mut stream = stream;
+            let mut prefix = alloc::vec::Vec::with_capacity(1024);
+            let mut chunk = [0u8; 1024];
+
+            loop {
+                if let Detection::Detected(protocol) = detect_protocol(&prefix)? {
+                    return Ok((protocol, SniffedStream::new(prefix, stream)));
+                }
+
+                let n = stream.read(&mut chunk).await.map_err(|e| GatewayError::IoError {
+                    operation: "protocol detection".into(),
+                    message: e.to_string(),
+                })?;
+                if n == 0 {
+                    return Err(GatewayError::ConnectionError {
+                        address: "downstream".into(),
+                        phase: ConnectionPhase::ProtocolNegotiation,
+                        message: "connection closed before protocol was detected".into(),
+                    });
+                }
+                prefix.extend_from_slice(&chunk[..n]);
+            }
+        }
+
+        /// Detect the protocol of an accepted connection and hand it to the
+        /// matching handler. A connection that does not identify its
+        /// protocol within the sniff timeout, or before `shutdown` fires,
+        /// is dropped.
+        pub async fn dispatch(&self, stream: TcpStream, shutdown: ShutdownSignal) -> Result<()> {
+            let mut closing = shutdown.clone();
+            let detected = tokio::select! {
+                detected = tokio::time::timeout(self.sniff_timeout, Self::detect(stream)) => detected,
+                _ = closing.recv() => {
+                    return Err(GatewayError::ShutdownError {
+                        component: "connection".into(),
+                        message: "server shut down before the protocol was detected".into(),
+                    });
+                }
+            };
+            let (protocol, stream) = detected.map_err(|_| GatewayError::TimeoutError {
+                operation: "protocol detection".into(),
+                timeout_seconds: self.sniff_timeout.as_secs(),
+            })??;
+            let handler = self.handlers.get(&protocol.protocol()).ok_or_else(|| GatewayError::ProtocolError {
+                protocol: format!("{:?}", protocol.protocol()),
+                message: "no handler registered".into(),
+            })?;
+            handler.handle(protocol, stream, shutdown).await
+        }
+
+        /// Accept connections forever, dispatching each on its own task
+        pub async fn serve(self, listener: TcpListener) -> Result<()> {
+            let dispatcher = Arc::new(self);
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(error) => {
+                        accept_failed(&error).await;
+                        continue;
+                    }
+                };
+                let dispatcher = dispatcher.clone();
+                tokio::spawn(async move {
+                    let _ = dispatcher.dispatch(stream, ShutdownSignal::never()).await;
+                });
+            }
+        }
+
+        /// Accept connections in the background until the returned server
+        /// is shut down
+        pub fn start(self, listener: TcpListener) -> Result<GatewayServer> {
+            let local_addr = listener.local_addr().map_err(|e| GatewayError::NetworkError {
+                operation: "local_addr".into(),
+                message: e.to_string(),
+            })?;
+            let (stop, mut stopped) = tokio::sync::oneshot::channel();
+            let (drain, draining) = tokio::sync::watch::channel(false);
+            let dispatcher = Arc::new(self);
+
+            let accept = tokio::spawn(async move {
+                let mut connections = tokio::task::JoinSet::new();
+                loop {
+                    tokio::select! {
+                        _ = &mut stopped => break,
+                        // Reap finished connections so the set only holds live ones
+                        Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                        accepted = listener.accept() => {
+                            let stream = match accepted {
+                                Ok((stream, _)) => stream,
+                                Err(error) => {
+                                    accept_failed(&error).await;
+                                    continue;
+                                }
+                            };
+                            let dispatcher = dispatcher.clone();
+                            let shutdown = ShutdownSignal { receiver: draining.clone() };
+                            connections.spawn(async move {
+                                let _ = dispatcher.dispatch(stream, shutdown).await;
+                            });
+                        }
+                    }
+                }
+                // The listener is dropped here, so new connections are refused
+                connections
+            });
+
+            Ok(GatewayServer {
+                local_addr,
+                stop,
+                drain,
+                accept,
+            })
+        }
+    }
+
+    /// Log a failed accept and pause briefly, so errors that persist for a
+    /// while, such as running out of file descriptors, do not spin the loop.
+    /// They concern single connections; the listener keeps serving.
+    async fn accept_failed(error: &std::io::Error) {
+        log::warn!("failed to accept a connection: {}", error);
+        tokio::time::sleep(core::time::Duration::from_millis(50)).await;
+    }
+
+    /// Outcome of draining connections on shutdown
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct DrainReport {
+        /// Connections that finished within the timeout
+        pub completed: usize,
+        /// Connections aborted when the timeout ran out
+        pub aborted: usize,
+    }
+
+    /// Dispatcher accepting connections in the background
+    pub struct GatewayServer {
+        /// Address the listener is bound to
+        local_addr: std::net::SocketAddr,
+        /// Tells the accept loop to stop
+        stop: tokio::sync::oneshot::Sender<()>,
+        /// Tells connection handlers to wind down
+        drain: tokio::sync::watch::Sender<bool>,
+        /// Accept loop, returning the connections still in flight
+        accept: tokio::task::JoinHandle<tokio::task::JoinSet<()>>,
+    }
+
+    impl GatewayServer {
+        /// Address the server accepts connections on
+        pub fn local_addr(&self) -> std::net::SocketAddr {
+            self.local_addr
+        }
+
+        /// Stop accepting connections, signal shutdown to those in flight
+        /// and let them finish for up to `timeout`, then abort the rest
+        pub async fn shutdown(self, timeout: std::time::Duration) -> Result<DrainReport> {
+            let _ = self.stop.send(());
+            let mut connections = self.accept.await.map_err(|e| GatewayError::ShutdownError {
+                component: "accept loop".into(),
+                message: e.to_string(),
+            })?;
+            self.drain.send_replace(true);
+
+            let deadline = tokio::time::Instant::now() + timeout;
+            let mut report = DrainReport::default();
+            while !connections.is_empty() {
+                if tokio::time::timeout_at(deadline, connections.join_next()).await.is_err() {
+                    report.aborted = connections.len();
+                    connections.shutdown().await;
+                    break;
+                }
+                report.completed += 1;
+            }
+            Ok(report)
+        }
+    }
+
+    impl core::fmt::Debug for GatewayServer {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("GatewayServer")
+                .field("local_addr", &self.local_addr)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl core::fmt::Debug for ProtocolDispatcher {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("ProtocolDispatcher")
+                .field("protocols", &self.handlers.keys().collect::<alloc::vec::Vec<_>>())
+                .finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_partial_preface_needs_more_data() {
+        assert_eq!(detect_protocol(b"PRI * HT").unwrap(), Detection::NeedMoreData);
+        assert_eq!(detect_protocol(b"GET / HTTP/1.1\r\nHost: a").unwrap(), Detection::NeedMoreData);
+    }
+
+    #[test]
+    fn test_detect_from_alpn() {
+        assert_eq!(protocol_from_alpn(b"h2"), Some(DetectedProtocol::Http2PriorKnowledge));
+        assert_eq!(protocol_from_alpn(b"http/1.1"), None);
+    }
+
+    #[test]
+    fn test_websocket_requires_connection_upgrade() {
+        let request = b"GET /ws HTTP/1.1\r\nHost: a\r\nUpgrade: websocket\r\n\r\n";
+        assert_eq!(
+            detect_protocol(request).unwrap(),
+            Detection::Detected(DetectedProtocol::Http1)
+        );
+    }
+
+    #[test]
+    fn test_oversized_head_rejected() {
+        let request = vec![b'a'; MAX_SNIFF_BYTES];
+        assert!(matches!(
+            detect_protocol(&request),
+            Err(GatewayError::ResourceLimitExceeded { .. })
+        ));
+    }
+
+    #[cfg(feature = "http")]
+    mod dispatch {
+        use super::*;
+        use alloc::sync::Arc;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::{TcpListener, TcpStream};
+
+        /// Replies with the protocol it was dispatched for and the first
+        /// bytes it can read, proving the sniffed prefix is replayed
+        struct EchoProtocol;
+
+        #[async_trait::async_trait]
+        impl ConnectionHandler for EchoProtocol {
+            async fn handle(&self, protocol: DetectedProtocol, mut stream: SniffedStream<TcpStream>, _shutdown: ShutdownSignal) -> Result<()> {
+                let mut head = [0u8; 3];
+                stream.read_exact(&mut head).await.unwrap();
+                let reply = format!("{:?} {}", protocol, String::from_utf8_lossy(&head));
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+                Ok(())
+            }
+        }
+
+        async fn start_gateway() -> std::net::SocketAddr {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let handler: Arc<dyn ConnectionHandler> = Arc::new(EchoProtocol);
+            let dispatcher = ProtocolDispatcher::new()
+                .with_handler(Protocol::HTTP, handler.clone())
+                .with_handler(Protocol::HTTP2, handler.clone())
+                .with_handler(Protocol::WebSocket, handler);
+            tokio::spawn(dispatcher.serve(listener));
+            addr
+        }
+
+        async fn roundtrip(addr: std::net::SocketAddr, chunks: &[&[u8]]) -> String {
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            for chunk in chunks {
+                client.write_all(chunk).await.unwrap();
+                client.flush().await.unwrap();
+                tokio::time::sleep(core::time::Duration::from_millis(5)).await;
+            }
+            let mut reply = String::new();
+            client.read_to_string(&mut reply).await.unwrap();
+            reply
+        }
+
+        #[tokio::test]
+        async fn test_single_port_dispatches_each_protocol() {
+            let addr = start_gateway().await;
+
+            let http1 = roundtrip(addr, &[b"GET /api HTTP/1.1\r\nHost: gw\r\n\r\n"]).await;
+            assert_eq!(http1, "Http1 GET");
+
+            // Prior-knowledge h2c, with the preface split across writes
+            let h2c = roundtrip(addr, &[&HTTP2_PREFACE[..10], &HTTP2_PREFACE[10..]]).await;
+            assert_eq!(h2c, "Http2PriorKnowledge PRI");
+
+            let upgrade = roundtrip(
+                addr,
+                &[b"GET / HTTP/1.1\r\nHost: gw\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABkAAQCAAAAAAIAAAAA\r\n\r\n"],
+            )
+            .await;
+            assert_eq!(upgrade, "Http2Upgrade GET");
+
+            let websocket = roundtrip(
+                addr,
+                &[
+                    b"GET /ws HTTP/1.1\r\nHost: gw\r\nUpgrade: websocket\r\n",
+                    b"Connection: keep-alive, Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+                ],
+            )
+            .await;
+            assert_eq!(websocket, "WebSocket GET");
+        }
+
+        #[tokio::test]
+        async fn test_silent_connection_is_dropped_after_sniff_timeout() {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let dispatcher = ProtocolDispatcher::new()
+                .with_handler(Protocol::HTTP, Arc::new(EchoProtocol))
+                .with_sniff_timeout(core::time::Duration::from_millis(50));
+            tokio::spawn(dispatcher.serve(listener));
+
+            let mut silent = TcpStream::connect(addr).await.unwrap();
+            let mut rest = alloc::vec::Vec::new();
+            let closed = tokio::time::timeout(core::time::Duration::from_secs(1), silent.read_to_end(&mut rest)).await;
+            assert_eq!(closed.unwrap().unwrap(), 0);
+
+            // Other clients are still served
+            assert_eq!(roundtrip(addr, &[b"GET /api HTTP/1.1\r\nHost: gw\r\n\r\n"]).await, "Http1 GET");
+        }
+
+        /// Replies after a delay to `GET /slow` and never to anything else
+        struct SlowProtocol;
+
+        #[async_trait::async_trait]
+        impl ConnectionHandler for SlowProtocol {
+            async fn handle(&self, _protocol: DetectedProtocol, mut stream: SniffedStream<TcpStream>, _shutdown: ShutdownSignal) -> Result<()> {
+                if !stream.prefix().starts_with(b"GET /slow ") {
+                    core::future::pending::<()>().await;
+                }
+                tokio::time::sleep(core::time::Duration::from_millis(300)).await;
+                stream.write_all(b"done").await.unwrap();
+                stream.shutdown().await.unwrap();
+                Ok(())
+            }
+        }
+
+        #[tokio::test]
+        async fn test_shutdown_drains_in_flight_connections() {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let server = ProtocolDispatcher::new()
+                .with_handler(Protocol::HTTP, Arc::new(SlowProtocol))
+                .start(listener)
+                .unwrap();
+            let addr = server.local_addr();
+
+            let slow = tokio::spawn(roundtrip(addr, &[b"GET /slow HTTP/1.1\r\nHost: gw\r\n\r\n"]));
+            let mut stuck = TcpStream::connect(addr).await.unwrap();
+            stuck.write_all(b"GET /stuck HTTP/1.1\r\nHost: gw\r\n\r\n").await.unwrap();
+            tokio::time::sleep(core::time::Duration::from_millis(50)).await;
+
+            let shutdown = tokio::spawn(server.shutdown(core::time::Duration::from_millis(500)));
+            tokio::time::sleep(core::time::Duration::from_millis(50)).await;
+            assert!(TcpStream::connect(addr).await.is_err());
+
+            // The slow request finishes during the drain; the stuck one is aborted
+            assert_eq!(slow.await.unwrap(), "done");
+            let report = shutdown.await.unwrap().unwrap();
+            assert_eq!(report, DrainReport { completed: 1, aborted: 1 });
+            let mut rest = alloc::vec::Vec::new();
+            assert_eq!(stuck.read_to_end(&mut rest).await.unwrap(), 0);
+        }
+
+        /// Keeps the connection open until shutdown, then says goodbye
+        struct KeepAliveProtocol;
+
+        #[async_trait::async_trait]
+        impl ConnectionHandler for KeepAliveProtocol {
+            async fn handle(&self, _protocol: DetectedProtocol, mut stream: SniffedStream<TcpStream>, mut shutdown: ShutdownSignal) -> Result<()> {
+                shutdown.recv().await;
+                stream.write_all(b"bye").await.unwrap();
+                stream.shutdown().await.unwrap();
+                Ok(())
+            }
+        }
+
+        #[tokio::test]
+        async fn test_shutdown_signals_idle_connections() {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let server = ProtocolDispatcher::new()
+                .with_handler(Protocol::HTTP, Arc::new(KeepAliveProtocol))
+                .start(listener)
+                .unwrap();
+            let addr = server.local_addr();
+
+            let idle = tokio::spawn(roundtrip(addr, &[b"GET / HTTP/1.1\r\nHost: gw\r\n\r\n"]));
+            let mut silent = TcpStream::connect(addr).await.unwrap();
+            tokio::time::sleep(core::time::Duration::from_millis(50)).await;
+
+            // Both close long before the timeout instead of being aborted
+            let started = std::time::Instant::now();
+            let report = server.shutdown(core::time::Duration::from_secs(5)).await.unwrap();
+            assert!(started.elapsed() < core::time::Duration::from_secs(1));
+            assert_eq!(report, DrainReport { completed: 2, aborted: 0 });
+            assert_eq!(idle.await.unwrap(), "bye");
+            let mut rest = alloc::vec::Vec::new();
+            assert_eq!(silent.read_to_end(&mut rest).await.unwrap(), 0);
+        }
+    }
+}
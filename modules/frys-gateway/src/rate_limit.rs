@@ -0,0 +1,628 @@
+//! Token bucket rate limiting
+//!
+//! Every rate limit key owns a bucket holding up to `burst_size` tokens and
+//! refilled at `requests_per_second`; a request takes one token or is
+//! refused with [`GatewayError::RateLimitExceeded`].
+//!
+//! Keyed by IP, a request counts against its peer address. Only when the
+//! peer is a configured trusted proxy are forwarding headers believed, and
+//! then the client is the rightmost `X-Forwarded-For` hop that is not a
+//! trusted proxy itself: hops further left were written by the client and
+//! could be anything.
+//!
+//! Local limiters hold at most `LOCAL_BUCKET_LIMIT` buckets; a new key
+//! beyond that evicts the least recently used bucket.
+//!
+//! With `distributed` set, buckets live in Redis and are updated by a Lua
+//! script, so gateway instances pointed at the same Redis share one budget
+//! per key. Should Redis become unreachable, the limiter keeps enforcing the
+//! limit with per-instance buckets and tries Redis again a few seconds later.
+
+use crate::*;
+use ::core::time::Duration;
+use alloc::collections::BTreeMap;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Buckets kept by a local limiter before the least recently used is
+/// dropped
+const LOCAL_BUCKET_LIMIT: usize = 10_000;
+
+#[cfg(feature = "http")]
+impl RateLimitKey {
+    /// Bucket key of `request`. `client_ip` is the peer address; forwarding
+    /// headers are only consulted when it is one of `trusted_proxies`.
+    pub fn resolve<B>(
+        &self,
+        request: &http::Request<B>,
+        client_ip: Option<IpAddr>,
+        trusted_proxies: &[IpAddr],
+    ) -> alloc::string::String {
+        let header = |name: &str| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let key = match self {
+            RateLimitKey::IP => client_ip.map(|peer| {
+                if !trusted_proxies.contains(&peer) {
+                    return peer.to_string();
+                }
+                header("x-forwarded-for")
+                    .and_then(|value| forwarded_client(&value, trusted_proxies))
+                    .or_else(|| header("x-real-ip"))
+                    .unwrap_or_else(|| peer.to_string())
+            }),
+            RateLimitKey::UserID => request
+                .extensions()
+                .get::<Claims>()
+                .and_then(|claims| claims.get("sub"))
+                .and_then(|sub| sub.as_str())
+                .map(Into::into),
+            RateLimitKey::APIKey => header("x-api-key"),
+            RateLimitKey::Header(name) => header(name),
+            RateLimitKey::Path => Some(request.uri().path().to_string()),
+            RateLimitKey::Custom(name) => Some(name.clone()),
+        };
+        key.unwrap_or_else(|| "anonymous".into())
+    }
+}
+
+/// Client named by an `X-Forwarded-For` value: the rightmost hop not in
+/// `trusted_proxies`, or the leftmost if every hop is trusted
+#[cfg(feature = "http")]
+fn forwarded_client(value: &str, trusted_proxies: &[IpAddr]) -> Option<alloc::string::String> {
+    let hops: alloc::vec::Vec<&str> = value.split(',').map(str::trim).filter(|hop| !hop.is_empty()).collect();
+    hops.iter()
+        .rev()
+        .find(|hop| !hop.parse::<IpAddr>().is_ok_and(|ip| trusted_proxies.contains(&ip)))
+        .or_else(|| hops.first())
+        .map(|hop| (*hop).to_string())
+}
+
+/// Bucket capacity and refill rate of `config`
+fn bucket_shape(config: &RateLimitConfig) -> (f64, f64) {
+    (f64::from(config.burst_size.max(1)), f64::from(config.requests_per_second.max(1)))
+}
+
+fn exceeded(key: &str, limit_type: &str, retry_after: Duration) -> GatewayError {
+    GatewayError::RateLimitExceeded {
+        client_id: key.into(),
+        limit_type: limit_type.into(),
+        retry_after_seconds: retry_after.as_secs_f64().ceil().max(1.0) as u64,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// Value of the use counter when the bucket was last checked
+    last_use: u64,
+}
+
+/// Buckets by key, with their keys ordered by last use
+#[derive(Debug, Default)]
+struct Buckets {
+    by_key: HashMap<alloc::string::String, Bucket>,
+    by_use: BTreeMap<u64, alloc::string::String>,
+    uses: u64,
+}
+
+/// Token buckets held in this process
+#[derive(Debug)]
+pub struct LocalRateLimiter {
+    capacity: f64,
+    rate: f64,
+    max_buckets: usize,
+    buckets: Mutex<Buckets>,
+}
+
+impl LocalRateLimiter {
+    /// Create a limiter for `config`, ignoring its distribution settings
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let (capacity, rate) = bucket_shape(config);
+        Self {
+            capacity,
+            rate,
+            max_buckets: LOCAL_BUCKET_LIMIT,
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Hold at most `max_buckets` buckets
+    pub fn with_max_buckets(mut self, max_buckets: usize) -> Self {
+        self.max_buckets = max_buckets.max(1);
+        self
+    }
+
+    /// Take a token from the bucket of `key`
+    pub fn check(&self, key: &str) -> Result<()> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let buckets = &mut *buckets;
+        let last_use = buckets.uses;
+        buckets.uses += 1;
+
+        match buckets.by_key.get_mut(key) {
+            Some(bucket) => {
+                buckets.by_use.remove(&bucket.last_use);
+                bucket.last_use = last_use;
+            }
+            None => {
+                if buckets.by_key.len() >= self.max_buckets {
+                    if let Some((_, victim)) = buckets.by_use.pop_first() {
+                        buckets.by_key.remove(&victim);
+                    }
+                }
+                buckets.by_key.insert(
+                    key.into(),
+                    Bucket {
+                        tokens: self.capacity,
+                        updated: now,
+                        last_use,
+                    },
+                );
+            }
+        }
+        buckets.by_use.insert(last_use, key.into());
+
+        let bucket = buckets.by_key.get_mut(key).expect("bucket inserted above");
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(exceeded(key, "local", Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate)))
+        }
+    }
+}
+
+/// Rate limiter for a [`RateLimitConfig`], distributed through Redis when
+/// the configuration asks for it
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    local: LocalRateLimiter,
+    #[cfg(feature = "distributed")]
+    redis: Option<redis_bucket::RedisRateLimiter>,
+}
+
+impl RateLimiter {
+    /// Create a limiter. Redis is not contacted until the first request.
+    pub fn new(config: RateLimitConfig) -> Result<Self> {
+        if config.distributed && config.redis_url.is_none() {
+            return Err(GatewayError::ConfigError {
+                parameter: "redis_url".into(),
+                reason: "distributed rate limiting requires a Redis URL".into(),
+            });
+        }
+
+        #[cfg(feature = "distributed")]
+        let redis = match (&config.redis_url, config.distributed) {
+            (Some(url), true) => Some(redis_bucket::RedisRateLimiter::new(url, &config)?),
+            _ => None,
+        };
+        #[cfg(not(feature = "distributed"))]
+        if config.distributed {
+            return Err(GatewayError::ConfigError {
+                parameter: "distributed".into(),
+                reason: "distributed rate limiting requires the `distributed` feature".into(),
+            });
+        }
+
+        Ok(Self {
+            local: LocalRateLimiter::new(&config),
+            #[cfg(feature = "distributed")]
+            redis,
+            config,
+        })
+    }
+
+    /// Limiter configuration
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Take a token from the bucket of `key`
+    pub async fn check(&self, key: &str) -> Result<()> {
+        #[cfg(feature = "distributed")]
+        if let Some(redis) = &self.redis {
+            if let Some(decision) = redis.check(key).await {
+                return decision;
+            }
+        }
+        self.local.check(key)
+    }
+
+    /// Take a token from the bucket `request` falls into under the
+    /// configured key strategy
+    #[cfg(feature = "http")]
+    pub async fn check_request<B>(&self, request: &http::Request<B>, client_ip: Option<IpAddr>) -> Result<()> {
+        let key = self.config.key_strategy.resolve(request, client_ip, &self.config.trusted_proxies);
+        self.check(&key).await
+    }
+}
+
+#[cfg(feature = "distributed")]
+mod redis_bucket {
+    use super::*;
+    use redis::aio::MultiplexedConnection;
+
+    /// Prefix of bucket keys in Redis
+    const KEY_PREFIX: &str = "frys:ratelimit:";
+    /// Time allowed to connect to Redis or run the script
+    const REDIS_TIMEOUT: Duration = Duration::from_millis(500);
+    /// Time spent on local buckets after Redis fails
+    const REDIS_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Refills the bucket in `KEYS[1]` by the time passed since its last
+    /// update, on the Redis clock so instances need not agree on the time,
+    /// then takes a token if one is left. Returns whether the request is
+    /// allowed and, if not, the milliseconds until a token is available.
+    const TOKEN_BUCKET_SCRIPT: &str = r"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated')
+local tokens = tonumber(bucket[1]) or capacity
+local updated = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - updated) * rate / 1000)
+local allowed = 0
+local retry_after = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+  allowed = 1
+else
+  retry_after = math.ceil((1 - tokens) * 1000 / rate)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated', tostring(now))
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity * 1000 / rate) + 1000)
+return {allowed, retry_after}
+";
+
+    #[derive(Default)]
+    struct RedisState {
+        connection: Option<MultiplexedConnection>,
+        retry_at: Option<Instant>,
+    }
+
+    pub(super) struct RedisRateLimiter {
+        client: redis::Client,
+        script: redis::Script,
+        capacity: u32,
+        rate: u32,
+        state: Mutex<RedisState>,
+    }
+
+    impl RedisRateLimiter {
+        pub(super) fn new(url: &str, config: &RateLimitConfig) -> Result<Self> {
+            let client = redis::Client::open(url).map_err(|e| GatewayError::ConfigError {
+                parameter: "redis_url".into(),
+                reason: e.to_string(),
+            })?;
+            Ok(Self {
+                client,
+                script: redis::Script::new(TOKEN_BUCKET_SCRIPT),
+                capacity: config.burst_size.max(1),
+                rate: config.requests_per_second.max(1),
+                state: Mutex::new(RedisState::default()),
+            })
+        }
+
+        /// Decision of the shared bucket of `key`, or `None` while Redis
+        /// cannot be reached
+        pub(super) async fn check(&self, key: &str) -> Option<Result<()>> {
+            let mut connection = self.connection().await?;
+            let mut invocation = self.script.key(format!("{}{}", KEY_PREFIX, key));
+            invocation.arg(self.capacity).arg(self.rate);
+            match tokio::time::timeout(REDIS_TIMEOUT, invocation.invoke_async::<_, (u8, u64)>(&mut connection)).await {
+                Ok(Ok((1, _))) => Some(Ok(())),
+                Ok(Ok((_, retry_after_ms))) => Some(Err(exceeded(key, "distributed", Duration::from_millis(retry_after_ms)))),
+                Ok(Err(_)) | Err(_) => {
+                    self.mark_unavailable();
+                    None
+                }
+            }
+        }
+
+        /// Shared connection, connecting first if there is none. The state
+        /// is not locked while connecting, so requests never queue behind a
+        /// slow connect.
+        async fn connection(&self) -> Option<MultiplexedConnection> {
+            {
+                let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(connection) = &state.connection {
+                    return Some(connection.clone());
+                }
+                if state.retry_at.is_some_and(|at| Instant::now() < at) {
+                    return None;
+                }
+            }
+
+            let connected = tokio::time::timeout(REDIS_TIMEOUT, self.client.get_multiplexed_tokio_connection()).await;
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            match connected {
+                Ok(Ok(connection)) => {
+                    // Keep the connection of a request that got there first
+                    let connection = state.connection.get_or_insert(connection).clone();
+                    state.retry_at = None;
+                    Some(connection)
+                }
+                Ok(Err(_)) | Err(_) => {
+                    if state.connection.is_none() {
+                        state.retry_at = Some(Instant::now() + REDIS_RETRY_INTERVAL);
+                    }
+                    state.connection.clone()
+                }
+            }
+        }
+
+        fn mark_unavailable(&self) {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.connection = None;
+            state.retry_at = Some(Instant::now() + REDIS_RETRY_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(distributed: bool, redis_url: Option<alloc::string::String>) -> RateLimitConfig {
+        RateLimitConfig {
+            requests_per_second: 1,
+            burst_size: 10,
+            key_strategy: RateLimitKey::IP,
+            distributed,
+            redis_url,
+            trusted_proxies: alloc::vec::Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_local_bucket_caps_bursts_per_key() {
+        let limiter = LocalRateLimiter::new(&config(false, None));
+        let allowed = (0..30).filter(|_| limiter.check("10.0.0.1").is_ok()).count();
+        assert_eq!(allowed, 10);
+        assert!(limiter.check("10.0.0.2").is_ok());
+
+        let error = limiter.check("10.0.0.1").unwrap_err();
+        assert!(matches!(error, GatewayError::RateLimitExceeded { retry_after_seconds: 1, .. }));
+        assert_eq!(error.status_code(), 429);
+    }
+
+    #[test]
+    fn test_least_recently_used_bucket_is_evicted() {
+        let limiter = LocalRateLimiter::new(&config(false, None)).with_max_buckets(2);
+        while limiter.check("10.0.0.1").is_ok() {}
+        while limiter.check("10.0.0.2").is_ok() {}
+        assert!(limiter.check("10.0.0.1").is_err());
+
+        // 10.0.0.2 was used last before 10.0.0.3 arrived and survives
+        assert!(limiter.check("10.0.0.2").is_err());
+        assert!(limiter.check("10.0.0.3").is_ok());
+        assert!(limiter.check("10.0.0.2").is_err());
+        assert!(limiter.check("10.0.0.1").is_ok());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_key_resolution() {
+        let request = http::Request::builder()
+            .uri("/api/users?page=2")
+            .header("x-forwarded-for", "203.0.113.7, 10.0.0.1")
+            .header("x-api-key", "key-123")
+            .body(())
+            .unwrap();
+        let peer = Some(IpAddr::from([192, 168, 1, 1]));
+        assert_eq!(RateLimitKey::APIKey.resolve(&request, peer, &[]), "key-123");
+        assert_eq!(RateLimitKey::Path.resolve(&request, peer, &[]), "/api/users");
+        assert_eq!(RateLimitKey::UserID.resolve(&request, peer, &[]), "anonymous");
+        assert_eq!(RateLimitKey::IP.resolve(&http::Request::new(()), peer, &[]), "192.168.1.1");
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_forwarded_for_only_believed_from_trusted_proxies() {
+        let request = |forwarded_for: &str| {
+            http::Request::builder()
+                .header("x-forwarded-for", forwarded_for)
+                .header("x-real-ip", "198.51.100.1")
+                .body(())
+                .unwrap()
+        };
+        let proxy = IpAddr::from([10, 0, 0, 2]);
+        let trusted = [proxy, IpAddr::from([10, 0, 0, 1])];
+
+        // A direct client cannot pick its own key
+        let spoofed = request("1.2.3.4");
+        assert_eq!(RateLimitKey::IP.resolve(&spoofed, Some(IpAddr::from([203, 0, 113, 9])), &trusted), "203.0.113.9");
+        assert_eq!(RateLimitKey::IP.resolve(&spoofed, None, &trusted), "anonymous");
+
+        // Behind the proxies, hops left of the client are its own claims
+        let chained = request("1.2.3.4, 203.0.113.7, 10.0.0.1");
+        assert_eq!(RateLimitKey::IP.resolve(&chained, Some(proxy), &trusted), "203.0.113.7");
+        assert_eq!(RateLimitKey::IP.resolve(&chained, Some(proxy), &[proxy]), "10.0.0.1");
+        assert_eq!(RateLimitKey::IP.resolve(&request("10.0.0.1"), Some(proxy), &trusted), "10.0.0.1");
+
+        let direct = http::Request::builder().header("x-real-ip", "198.51.100.1").body(()).unwrap();
+        assert_eq!(RateLimitKey::IP.resolve(&direct, Some(proxy), &trusted), "198.51.100.1");
+    }
+
+    #[cfg(feature = "distributed")]
+    mod distributed {
+        use super::*;
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+        /// Read one RESP command
+        async fn read_command<R: tokio::io::AsyncBufRead + Unpin>(reader: &mut R) -> Option<alloc::vec::Vec<alloc::string::String>> {
+            let mut line = alloc::string::String::new();
+            reader.read_line(&mut line).await.ok().filter(|n| *n > 0)?;
+            let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+            let mut args = alloc::vec::Vec::with_capacity(count);
+            for _ in 0..count {
+                line.clear();
+                reader.read_line(&mut line).await.ok()?;
+                let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+                // Bulk strings may span lines, as scripts do
+                let mut arg = alloc::vec![0u8; len + 2];
+                reader.read_exact(&mut arg).await.ok()?;
+                arg.truncate(len);
+                args.push(alloc::string::String::from_utf8(arg).ok()?);
+            }
+            Some(args)
+        }
+
+        /// Hashes stored by the mock, by key
+        type Store = Arc<Mutex<HashMap<alloc::string::String, HashMap<alloc::string::String, alloc::string::String>>>>;
+
+        /// Run `script` in a Lua interpreter whose `redis.call` supports the
+        /// commands of the token bucket script, and encode its reply
+        fn eval(store: &Store, clock_ms: u64, script: &str, keys: &[alloc::string::String], args: &[alloc::string::String]) -> alloc::string::String {
+            let lua = mlua::Lua::new();
+            let globals = lua.globals();
+            globals.set("KEYS", keys.to_vec()).unwrap();
+            globals.set("ARGV", args.to_vec()).unwrap();
+
+            let store = store.clone();
+            let call = lua
+                .create_function(move |lua, args: mlua::Variadic<alloc::string::String>| {
+                    let mut store = store.lock().unwrap();
+                    let reply = match args[0].to_ascii_uppercase().as_str() {
+                        "TIME" => lua.create_sequence_from([(clock_ms / 1000).to_string(), (clock_ms % 1000 * 1000).to_string()])?,
+                        "HMGET" => {
+                            let hash = store.get(&args[1]);
+                            let fields = args[2..].iter().map(|field| match hash.and_then(|hash| hash.get(field)) {
+                                Some(value) => mlua::Value::String(lua.create_string(value).unwrap()),
+                                None => mlua::Value::Boolean(false),
+                            });
+                            lua.create_sequence_from(fields)?
+                        }
+                        "HSET" => {
+                            let hash = store.entry(args[1].clone()).or_default();
+                            for pair in args[2..].chunks(2) {
+                                hash.insert(pair[0].clone(), pair[1].clone());
+                            }
+                            return Ok(mlua::Value::Integer(1));
+                        }
+                        "PEXPIRE" => return Ok(mlua::Value::Integer(1)),
+                        command => return Err(mlua::Error::RuntimeError(format!("unsupported command {}", command))),
+                    };
+                    Ok(mlua::Value::Table(reply))
+                })
+                .unwrap();
+            let redis = lua.create_table().unwrap();
+            redis.set("call", call).unwrap();
+            globals.set("redis", redis).unwrap();
+
+            // Redis truncates Lua numbers to integers in replies
+            let reply: alloc::vec::Vec<f64> = lua.load(script).eval().unwrap();
+            let items: alloc::string::String = reply.iter().map(|n| format!(":{}\r\n", *n as i64)).collect();
+            format!("*{}\r\n{}", reply.len(), items)
+        }
+
+        /// Redis stand-in running scripts through Lua against an in-memory
+        /// store. Scripts must be loaded with `SCRIPT LOAD` before
+        /// `EVALSHA` finds them, and `TIME` reads `clock_ms`.
+        async fn mock_redis(clock_ms: Arc<AtomicU64>) -> alloc::string::String {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let store = Store::default();
+            let scripts = Arc::new(Mutex::new(HashMap::new()));
+            tokio::spawn(async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    let (store, scripts, clock_ms) = (store.clone(), scripts.clone(), clock_ms.clone());
+                    tokio::spawn(async move {
+                        let (reader, mut writer) = socket.into_split();
+                        let mut reader = BufReader::new(reader);
+                        while let Some(args) = read_command(&mut reader).await {
+                            let reply = match args[0].to_ascii_uppercase().as_str() {
+                                "SCRIPT" => {
+                                    let hash = redis::Script::new(&args[2]).get_hash().to_string();
+                                    scripts.lock().unwrap().insert(hash.clone(), args[2].clone());
+                                    format!("${}\r\n{}\r\n", hash.len(), hash)
+                                }
+                                "EVALSHA" => match scripts.lock().unwrap().get(&args[1]).cloned() {
+                                    Some(script) => {
+                                        let key_count: usize = args[2].parse().unwrap();
+                                        let (keys, argv) = args[3..].split_at(key_count);
+                                        eval(&store, clock_ms.load(Ordering::SeqCst), &script, keys, argv)
+                                    }
+                                    None => "-NOSCRIPT No matching script. Please use EVAL.\r\n".to_string(),
+                                },
+                                _ => "+OK\r\n".to_string(),
+                            };
+                            if writer.write_all(reply.as_bytes()).await.is_err() {
+                                break;
+                            }
+                        }
+                    });
+                }
+            });
+            format!("redis://{}/", address)
+        }
+
+        #[tokio::test]
+        async fn test_instances_share_one_budget() {
+            let clock_ms = Arc::new(AtomicU64::new(1_700_000_000_000));
+            let url = mock_redis(clock_ms.clone()).await;
+            let first = RateLimiter::new(config(true, Some(url.clone()))).unwrap();
+            let second = RateLimiter::new(config(true, Some(url))).unwrap();
+
+            let mut allowed = 0;
+            for _ in 0..15 {
+                allowed += usize::from(first.check("10.0.0.1").await.is_ok());
+                allowed += usize::from(second.check("10.0.0.1").await.is_ok());
+            }
+            assert_eq!(allowed, 10);
+
+            let error = second.check("10.0.0.1").await.unwrap_err();
+            assert!(matches!(
+                &error,
+                GatewayError::RateLimitExceeded { limit_type, retry_after_seconds: 1, .. } if limit_type == "distributed"
+            ));
+
+            // The script refills one token per second of Redis time
+            clock_ms.fetch_add(1500, Ordering::SeqCst);
+            assert!(first.check("10.0.0.1").await.is_ok());
+            assert!(second.check("10.0.0.1").await.is_err());
+            assert!(first.check("10.0.0.2").await.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_unreachable_redis_falls_back_to_local_buckets() {
+            let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("redis://{}/", closed.local_addr().unwrap());
+            drop(closed);
+
+            let limiter = RateLimiter::new(config(true, Some(url))).unwrap();
+            let mut allowed = 0;
+            for _ in 0..30 {
+                allowed += usize::from(limiter.check("10.0.0.1").await.is_ok());
+            }
+            assert_eq!(allowed, 10);
+
+            let error = limiter.check("10.0.0.1").await.unwrap_err();
+            assert!(matches!(&error, GatewayError::RateLimitExceeded { limit_type, .. } if limit_type == "local"));
+        }
+    }
+
+    #[test]
+    fn test_distributed_config_requires_redis_url() {
+        assert!(matches!(
+            RateLimiter::new(config(true, None)),
+            Err(GatewayError::ConfigError { .. })
+        ));
+    }
+}
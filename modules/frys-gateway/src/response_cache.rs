@@ -0,0 +1,408 @@
+//! Upstream response caching
+//!
+//! [`ResponseCache`] sits in front of an upstream call and answers repeated
+//! requests from a `frys-cache` store. Entries are keyed by method, path and
+//! query, plus the request's values of the configured `vary_headers`, and
+//! live for the `s-maxage`/`max-age` the upstream sends, or the configured
+//! `ttl` otherwise.
+//!
+//! `Cache-Control` is honoured both ways: requests marked `no-store` bypass
+//! the cache, requests marked `no-cache` are refetched and refresh the entry,
+//! and responses marked `no-store`, `no-cache` or `private`, setting cookies,
+//! or varying on headers outside `vary_headers` are never stored.
+//!
+//! The cache is shared between clients, so as RFC 9111 section 3.5 requires,
+//! a response to a request carrying `Authorization` is only stored if the
+//! upstream explicitly allows sharing it with `public`, `s-maxage` or
+//! `must-revalidate`.
+
+use crate::*;
+use ::core::time::Duration;
+
+/// Response cache configuration
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Lifetime of responses that carry no `max-age` of their own
+    pub ttl: Duration,
+    /// Request headers whose values select between cached variants
+    pub vary_headers: alloc::vec::Vec<alloc::string::String>,
+    /// Methods whose responses may be cached
+    pub cacheable_methods: alloc::vec::Vec<Method>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            vary_headers: alloc::vec::Vec::new(),
+            cacheable_methods: alloc::vec![Method::GET, Method::HEAD],
+        }
+    }
+}
+
+#[cfg(feature = "caching")]
+pub use self::store::ResponseCache;
+
+#[cfg(feature = "caching")]
+mod store {
+    use super::*;
+    use http::header::{HeaderName, HeaderValue, AUTHORIZATION, CACHE_CONTROL, SET_COOKIE, VARY};
+    use http::HeaderMap;
+    use regex::Regex;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// Prefix of response entries in the store
+    const KEY_PREFIX: &str = "gateway:response:";
+    /// Header telling clients whether the cache answered
+    const CACHE_STATUS_HEADER: &str = "x-cache";
+
+    fn cache_error(operation: &str, error: impl ::core::fmt::Display) -> GatewayError {
+        GatewayError::CacheError {
+            operation: operation.into(),
+            message: error.to_string(),
+        }
+    }
+
+    fn now_millis() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+    }
+
+    /// Response cache backed by a [`frys_cache::CacheManager`]
+    pub struct ResponseCache {
+        config: CacheConfig,
+        store: frys_cache::CacheManager,
+    }
+
+    impl ResponseCache {
+        /// Create a cache with its own in-memory store
+        pub async fn new(config: CacheConfig) -> Result<Self> {
+            let store = frys_cache::CacheBuilder::new()
+                .build()
+                .await
+                .map_err(|e| cache_error("create response store", e))?;
+            Ok(Self::with_store(config, store))
+        }
+
+        /// Create a cache keeping its entries in `store`
+        pub fn with_store(config: CacheConfig, store: frys_cache::CacheManager) -> Self {
+            Self { config, store }
+        }
+
+        /// Cache configuration
+        pub fn config(&self) -> &CacheConfig {
+            &self.config
+        }
+
+        /// Answer `request` from the cache, or from `upstream` on a miss,
+        /// storing the upstream response if it may be cached.
+        ///
+        /// Store failures never fail the request; the upstream answers
+        /// instead.
+        pub async fn handle<B, F, Fut>(&self, request: &http::Request<B>, upstream: F) -> Result<http::Response<alloc::vec::Vec<u8>>>
+        where
+            F: FnOnce() -> Fut,
+            Fut: ::core::future::Future<Output = Result<http::Response<alloc::vec::Vec<u8>>>>,
+        {
+            let method = request.method().as_str();
+            let request_directives = directives(request.headers());
+            if !self.config.cacheable_methods.iter().any(|m| m.as_str() == method) || request_directives.contains("no-store") {
+                return upstream().await;
+            }
+
+            let key = self.cache_key(request);
+            if !request_directives.contains("no-cache") {
+                if let Ok(Some(entry)) = self.store.get(&key).await {
+                    match decode(&entry) {
+                        Some((expires_at, stored_at, response)) if expires_at > now_millis() => {
+                            return Ok(hit(response, stored_at));
+                        }
+                        _ => {
+                            let _ = self.store.delete(&key).await;
+                        }
+                    }
+                }
+            }
+
+            let mut response = upstream().await?;
+            let authorized = request.headers().contains_key(AUTHORIZATION);
+            if let Some(ttl) = self.storable_ttl(&response, authorized) {
+                let stored_at = now_millis();
+                let _ = self.store.put(key, encode(&response, stored_at + ttl.as_millis() as u64, stored_at)).await;
+            }
+            response.headers_mut().insert(CACHE_STATUS_HEADER, HeaderValue::from_static("MISS"));
+            Ok(response)
+        }
+
+        /// Drop every cached response whose path matches `path_pattern`,
+        /// where `*` matches any run of characters. Returns the number of
+        /// entries dropped.
+        pub async fn purge(&self, path_pattern: &str) -> Result<usize> {
+            let pattern = regex::escape(path_pattern).replace(r"\*", ".*");
+            let pattern = Regex::new(&format!("^{}$", pattern)).map_err(|e| cache_error("purge", e))?;
+
+            let mut purged = 0;
+            for key in self.store.keys().await.map_err(|e| cache_error("purge", e))? {
+                let matches = ::core::str::from_utf8(&key).ok().and_then(cached_path).is_some_and(|path| pattern.is_match(path));
+                if matches && self.store.delete(&key).await.map_err(|e| cache_error("purge", e))? {
+                    purged += 1;
+                }
+            }
+            Ok(purged)
+        }
+
+        /// Store key of `request`
+        fn cache_key<B>(&self, request: &http::Request<B>) -> alloc::vec::Vec<u8> {
+            let target = request.uri().path_and_query().map_or("/", |target| target.as_str());
+            let mut key = format!("{}{} {}", KEY_PREFIX, request.method(), target);
+            for name in &self.config.vary_headers {
+                let values: alloc::vec::Vec<&str> =
+                    request.headers().get_all(name.as_str()).iter().filter_map(|value| value.to_str().ok()).collect();
+                key.push_str(&format!("\n{}={}", name.to_ascii_lowercase(), values.join(",")));
+            }
+            key.into_bytes()
+        }
+
+        /// How long `response` may be cached, if at all; `authorized` tells
+        /// whether the request carried credentials
+        fn storable_ttl(&self, response: &http::Response<alloc::vec::Vec<u8>>, authorized: bool) -> Option<Duration> {
+            // Statuses cacheable without explicit freshness (RFC 9111, 4.2.2)
+            let status = response.status().as_u16();
+            if !matches!(status, 200 | 203 | 204 | 206 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501) {
+                return None;
+            }
+
+            let headers = response.headers();
+            let response_directives = directives(headers);
+            if ["no-store", "no-cache", "private"].iter().any(|d| response_directives.contains(d)) || headers.contains_key(SET_COOKIE) {
+                return None;
+            }
+            // Another client must not be served a response to credentials
+            // unless the upstream marked it shareable (RFC 9111, 3.5)
+            if authorized && !["public", "s-maxage", "must-revalidate"].iter().any(|d| response_directives.contains(d)) {
+                return None;
+            }
+            let varies_elsewhere = headers
+                .get_all(VARY)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .any(|name| name == "*" || !self.config.vary_headers.iter().any(|vary| vary.eq_ignore_ascii_case(name)));
+            if varies_elsewhere {
+                return None;
+            }
+
+            let ttl = response_directives
+                .max_age("s-maxage")
+                .or_else(|| response_directives.max_age("max-age"))
+                .unwrap_or(self.config.ttl);
+            (!ttl.is_zero()).then_some(ttl)
+        }
+    }
+
+    /// `Cache-Control` directives of a message
+    struct Directives(alloc::vec::Vec<(alloc::string::String, Option<alloc::string::String>)>);
+
+    impl Directives {
+        fn contains(&self, name: &str) -> bool {
+            self.0.iter().any(|(directive, _)| directive == name)
+        }
+
+        fn max_age(&self, name: &str) -> Option<Duration> {
+            self.0
+                .iter()
+                .find(|(directive, _)| directive == name)
+                .and_then(|(_, value)| value.as_deref()?.parse().ok())
+                .map(Duration::from_secs)
+        }
+    }
+
+    fn directives(headers: &HeaderMap) -> Directives {
+        Directives(
+            headers
+                .get_all(CACHE_CONTROL)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .filter(|directive| !directive.trim().is_empty())
+                .map(|directive| match directive.split_once('=') {
+                    Some((name, value)) => (name.trim().to_ascii_lowercase(), Some(value.trim().trim_matches('"').into())),
+                    None => (directive.trim().to_ascii_lowercase(), None),
+                })
+                .collect(),
+        )
+    }
+
+    /// Path a store key was built from
+    fn cached_path(key: &str) -> Option<&str> {
+        let (_, target) = key.strip_prefix(KEY_PREFIX)?.split_once(' ')?;
+        Some(target.split(['?', '\n']).next().unwrap_or(target))
+    }
+
+    /// Mark a cached response as served from the cache
+    fn hit(mut response: http::Response<alloc::vec::Vec<u8>>, stored_at: u64) -> http::Response<alloc::vec::Vec<u8>> {
+        let age = now_millis().saturating_sub(stored_at) / 1000;
+        let headers = response.headers_mut();
+        headers.insert(http::header::AGE, HeaderValue::from(age));
+        headers.insert(CACHE_STATUS_HEADER, HeaderValue::from_static("HIT"));
+        response
+    }
+
+    /// Serialize a response as expiry, store time, status, length-prefixed
+    /// headers and body
+    fn encode(response: &http::Response<alloc::vec::Vec<u8>>, expires_at: u64, stored_at: u64) -> alloc::vec::Vec<u8> {
+        let mut out = alloc::vec::Vec::with_capacity(response.body().len() + 256);
+        out.extend_from_slice(&expires_at.to_be_bytes());
+        out.extend_from_slice(&stored_at.to_be_bytes());
+        out.extend_from_slice(&response.status().as_u16().to_be_bytes());
+        out.extend_from_slice(&(response.headers().len() as u32).to_be_bytes());
+        for (name, value) in response.headers() {
+            for field in [name.as_str().as_bytes(), value.as_bytes()] {
+                out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+                out.extend_from_slice(field);
+            }
+        }
+        out.extend_from_slice(response.body());
+        out
+    }
+
+    fn decode(mut bytes: &[u8]) -> Option<(u64, u64, http::Response<alloc::vec::Vec<u8>>)> {
+        fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+            if bytes.len() < n {
+                return None;
+            }
+            let (head, rest) = bytes.split_at(n);
+            *bytes = rest;
+            Some(head)
+        }
+        fn take_u32(bytes: &mut &[u8]) -> Option<u32> {
+            Some(u32::from_be_bytes(take(bytes, 4)?.try_into().ok()?))
+        }
+
+        let expires_at = u64::from_be_bytes(take(&mut bytes, 8)?.try_into().ok()?);
+        let stored_at = u64::from_be_bytes(take(&mut bytes, 8)?.try_into().ok()?);
+        let status = u16::from_be_bytes(take(&mut bytes, 2)?.try_into().ok()?);
+        let mut response = http::Response::builder().status(status);
+        for _ in 0..take_u32(&mut bytes)? {
+            let len = take_u32(&mut bytes)? as usize;
+            let name = HeaderName::from_bytes(take(&mut bytes, len)?).ok()?;
+            let len = take_u32(&mut bytes)? as usize;
+            let value = HeaderValue::from_bytes(take(&mut bytes, len)?).ok()?;
+            response = response.header(name, value);
+        }
+        Some((expires_at, stored_at, response.body(bytes.to_vec()).ok()?))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ::core::cell::Cell;
+
+        fn get(path: &str, language: &str) -> http::Request<()> {
+            http::Request::get(path).header("accept-language", language).body(()).unwrap()
+        }
+
+        /// Upstream answering `body` with `cache_control`, counting calls
+        async fn upstream(calls: &Cell<usize>, body: &str, cache_control: &str) -> Result<http::Response<alloc::vec::Vec<u8>>> {
+            calls.set(calls.get() + 1);
+            Ok(http::Response::builder()
+                .header(CACHE_CONTROL, cache_control)
+                .header(VARY, "Accept-Language")
+                .body(body.as_bytes().to_vec())
+                .unwrap())
+        }
+
+        async fn cache() -> ResponseCache {
+            ResponseCache::new(CacheConfig {
+                vary_headers: alloc::vec!["accept-language".into()],
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_repeated_get_is_served_from_cache_until_purged() {
+            let cache = cache().await;
+            let calls = Cell::new(0);
+
+            let first = cache.handle(&get("/users?page=1", "en"), || upstream(&calls, "users", "max-age=60")).await.unwrap();
+            let second = cache.handle(&get("/users?page=1", "en"), || upstream(&calls, "users", "max-age=60")).await.unwrap();
+            assert_eq!(calls.get(), 1);
+            assert_eq!(first.headers()[CACHE_STATUS_HEADER], "MISS");
+            assert_eq!(second.headers()[CACHE_STATUS_HEADER], "HIT");
+            assert_eq!(second.body(), b"users");
+
+            // Another language is another variant
+            cache.handle(&get("/users?page=1", "fr"), || upstream(&calls, "utilisateurs", "max-age=60")).await.unwrap();
+            cache.handle(&get("/orders", "en"), || upstream(&calls, "orders", "max-age=60")).await.unwrap();
+            assert_eq!(calls.get(), 3);
+
+            assert_eq!(cache.purge("/users*").await.unwrap(), 2);
+            let refetched = cache.handle(&get("/users?page=1", "en"), || upstream(&calls, "users", "max-age=60")).await.unwrap();
+            assert_eq!(refetched.headers()[CACHE_STATUS_HEADER], "MISS");
+            cache.handle(&get("/orders", "en"), || upstream(&calls, "orders", "max-age=60")).await.unwrap();
+            assert_eq!(calls.get(), 4);
+        }
+
+        #[tokio::test]
+        async fn test_cache_control_is_honoured() {
+            let cache = cache().await;
+            let calls = Cell::new(0);
+
+            for _ in 0..2 {
+                cache.handle(&get("/private", "en"), || upstream(&calls, "secret", "private, max-age=60")).await.unwrap();
+                cache.handle(&get("/stale", "en"), || upstream(&calls, "stale", "max-age=0")).await.unwrap();
+                let post = http::Request::post("/users").body(()).unwrap();
+                cache.handle(&post, || upstream(&calls, "created", "max-age=60")).await.unwrap();
+            }
+            assert_eq!(calls.get(), 6);
+
+            cache.handle(&get("/users", "en"), || upstream(&calls, "users", "max-age=60")).await.unwrap();
+            let revalidate = http::Request::get("/users").header("accept-language", "en").header(CACHE_CONTROL, "no-cache").body(()).unwrap();
+            let refreshed = cache.handle(&revalidate, || upstream(&calls, "users v2", "max-age=60")).await.unwrap();
+            assert_eq!(refreshed.headers()[CACHE_STATUS_HEADER], "MISS");
+            let cached = cache.handle(&get("/users", "en"), || upstream(&calls, "users v3", "max-age=60")).await.unwrap();
+            assert_eq!(cached.body(), b"users v2");
+            assert_eq!(calls.get(), 8);
+        }
+
+        #[tokio::test]
+        async fn test_authorized_responses_are_not_shared_between_users() {
+            let cache = cache().await;
+            let calls = Cell::new(0);
+            let as_user = |token: &str| {
+                http::Request::get("/me")
+                    .header("accept-language", "en")
+                    .header(AUTHORIZATION, format!("Bearer {}", token))
+                    .body(())
+                    .unwrap()
+            };
+
+            let alice = cache.handle(&as_user("alice"), || upstream(&calls, "alice's profile", "max-age=60")).await.unwrap();
+            let bob = cache.handle(&as_user("bob"), || upstream(&calls, "bob's profile", "max-age=60")).await.unwrap();
+            assert_eq!(alice.body(), b"alice's profile");
+            assert_eq!(bob.body(), b"bob's profile");
+            assert_eq!(bob.headers()[CACHE_STATUS_HEADER], "MISS");
+            let anonymous = cache.handle(&get("/me", "en"), || upstream(&calls, "sign in", "max-age=60")).await.unwrap();
+            assert_eq!(anonymous.body(), b"sign in");
+            assert_eq!(calls.get(), 3);
+
+            // The upstream may explicitly allow sharing
+            for (page, directive) in ["public, max-age=60", "s-maxage=60", "max-age=60, must-revalidate"].into_iter().enumerate() {
+                let path = format!("/docs/{}", page);
+                let request = |token: &str| {
+                    http::Request::get(path.as_str())
+                        .header("accept-language", "en")
+                        .header(AUTHORIZATION, format!("Bearer {}", token))
+                        .body(())
+                        .unwrap()
+                };
+                cache.handle(&request("alice"), || upstream(&calls, "docs", directive)).await.unwrap();
+                let shared = cache.handle(&request("bob"), || upstream(&calls, "docs", directive)).await.unwrap();
+                assert_eq!(shared.headers()[CACHE_STATUS_HEADER], "HIT", "{}", directive);
+            }
+            assert_eq!(calls.get(), 6);
+        }
+    }
+}
@@ -552,6 +552,7 @@ impl RouteBuilder {
                 circuit_breaker: None,
                 timeout: None,
                 retry: None,
+                max_body_size: None,
             },
         }
     }
@@ -633,6 +634,12 @@ impl RouteBuilder {
         self
     }
 
+    /// Set the maximum request body size in bytes
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.route.max_body_size = Some(bytes);
+        self
+    }
+
     /// Build the route
     pub fn build(self) -> Route {
         self.route
@@ -0,0 +1,414 @@
+//! W3C trace context propagation
+//!
+//! The gateway continues the trace of each request it proxies: it reads the
+//! incoming `traceparent` and `tracestate` headers, opens a gateway span as
+//! a child of the caller's span, and forwards a `traceparent` naming the
+//! gateway span as parent, so upstream spans link to it. Requests without a
+//! valid `traceparent` start a new trace, sampled at the configured rate.
+//!
+//! The gateway span covers routing, upstream selection and the upstream
+//! call, each timed as a phase of the span. Finished spans are handed to a
+//! [`SpanExporter`].
+
+use crate::*;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ::core::time::Duration;
+use hyper::header::{HeaderMap, HeaderValue};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Header carrying the trace ID, parent span ID and flags
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Header carrying vendor-specific trace state
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// `sampled` bit of the trace flags
+const FLAG_SAMPLED: u8 = 0x01;
+
+/// Trace and span a request belongs to, as carried by `traceparent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// ID shared by every span of the trace
+    pub trace_id: [u8; 16],
+    /// ID of the span this context names
+    pub span_id: [u8; 8],
+    /// Whether the trace is recorded
+    pub sampled: bool,
+    /// Vendor-specific state, forwarded unchanged
+    pub trace_state: Option<String>,
+}
+
+impl TraceContext {
+    /// Start a new trace
+    pub fn new_root(sampled: bool) -> Self {
+        Self {
+            trace_id: random_id(),
+            span_id: random_id(),
+            sampled,
+            trace_state: None,
+        }
+    }
+
+    /// Parse a `traceparent` value and the accompanying `tracestate`.
+    ///
+    /// Returns `None` for malformed values, the reserved version `ff` and
+    /// all-zero IDs. Versions above `00` may append fields, which are
+    /// ignored.
+    pub fn parse(traceparent: &str, trace_state: Option<&str>) -> Option<Self> {
+        let mut fields = traceparent.trim().split('-');
+        let version = parse_hex::<1>(fields.next()?)?[0];
+        let trace_id = parse_hex::<16>(fields.next()?)?;
+        let span_id = parse_hex::<8>(fields.next()?)?;
+        let flags = parse_hex::<1>(fields.next()?)?[0];
+        if version == 0xff || (version == 0 && fields.next().is_some()) {
+            return None;
+        }
+        if trace_id == [0; 16] || span_id == [0; 8] {
+            return None;
+        }
+
+        Some(Self {
+            trace_id,
+            span_id,
+            sampled: flags & FLAG_SAMPLED != 0,
+            trace_state: trace_state.map(str::trim).filter(|state| !state.is_empty()).map(String::from),
+        })
+    }
+
+    /// Context carried by `headers`, if they hold a valid `traceparent`
+    pub fn extract(headers: &HeaderMap) -> Option<Self> {
+        let traceparent = headers.get(TRACEPARENT_HEADER)?.to_str().ok()?;
+        let trace_state = headers.get(TRACESTATE_HEADER).and_then(|value| value.to_str().ok());
+        Self::parse(traceparent, trace_state)
+    }
+
+    /// Context of a new span in the same trace, whose parent is this one
+    pub fn child(&self) -> Self {
+        Self {
+            span_id: random_id(),
+            ..self.clone()
+        }
+    }
+
+    /// `traceparent` value naming this span
+    pub fn traceparent(&self) -> String {
+        alloc::format!(
+            "00-{}-{}-{:02x}",
+            hex(&self.trace_id),
+            hex(&self.span_id),
+            if self.sampled { FLAG_SAMPLED } else { 0 }
+        )
+    }
+
+    /// Write `traceparent` and `tracestate` into `headers`, replacing any
+    /// present
+    pub fn inject(&self, headers: &mut HeaderMap) {
+        let traceparent = HeaderValue::from_str(&self.traceparent()).expect("traceparent is ASCII hex");
+        headers.insert(TRACEPARENT_HEADER, traceparent);
+        match self.trace_state.as_deref().and_then(|state| HeaderValue::from_str(state).ok()) {
+            Some(state) => {
+                headers.insert(TRACESTATE_HEADER, state);
+            }
+            None => {
+                headers.remove(TRACESTATE_HEADER);
+            }
+        }
+    }
+}
+
+/// Span recorded by the gateway for one proxied request
+#[derive(Debug, Clone)]
+pub struct GatewaySpan {
+    /// Span name
+    pub name: String,
+    /// Context of this span
+    pub context: TraceContext,
+    /// Span of the caller, if the request carried a trace context
+    pub parent_span_id: Option<[u8; 8]>,
+    /// Start time in nanoseconds since the Unix epoch
+    pub start_unix_nanos: u64,
+    /// Time from start to finish
+    pub duration: Duration,
+    /// Time spent in each phase, in order
+    pub phases: Vec<(&'static str, Duration)>,
+    /// Attributes such as the route and upstream
+    pub attributes: alloc::collections::BTreeMap<String, String>,
+    /// Error the request failed with, if any
+    pub error: Option<String>,
+    /// When the span started
+    started: Instant,
+}
+
+impl GatewaySpan {
+    /// Set an attribute
+    pub fn set_attribute(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.attributes.insert(key.into(), value.into());
+    }
+
+    /// Await `future` as phase `name` of the span
+    pub async fn phase<T>(&mut self, name: &'static str, future: impl ::core::future::Future<Output = T>) -> T {
+        let started = Instant::now();
+        let output = future.await;
+        self.phases.push((name, started.elapsed()));
+        output
+    }
+
+    /// Write this span's context into `headers` of the upstream request
+    pub fn inject(&self, headers: &mut HeaderMap) {
+        self.context.inject(headers);
+    }
+}
+
+/// Destination of finished spans
+pub trait SpanExporter: Send + Sync {
+    /// Export a finished, sampled span
+    fn export(&self, span: GatewaySpan);
+}
+
+/// Exporter discarding every span
+#[derive(Debug, Default)]
+pub struct DiscardExporter;
+
+impl SpanExporter for DiscardExporter {
+    fn export(&self, _span: GatewaySpan) {}
+}
+
+/// Creates gateway spans and propagates trace context to upstreams
+pub struct Tracer {
+    /// Tracing configuration
+    config: TracingConfig,
+    /// Destination of finished spans
+    exporter: Arc<dyn SpanExporter>,
+}
+
+impl ::core::fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("Tracer")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Tracer {
+    /// Create a tracer discarding its spans
+    pub fn new(config: TracingConfig) -> Self {
+        Self {
+            config,
+            exporter: Arc::new(DiscardExporter),
+        }
+    }
+
+    /// Hand finished spans to `exporter`
+    pub fn with_exporter(mut self, exporter: Arc<dyn SpanExporter>) -> Self {
+        self.exporter = exporter;
+        self
+    }
+
+    /// Tracing configuration
+    pub fn config(&self) -> &TracingConfig {
+        &self.config
+    }
+
+    /// Start span `name` for a request with `headers`, as a child of the
+    /// trace context they carry or in a new trace
+    pub fn start_span(&self, name: impl Into<String>, headers: &HeaderMap) -> GatewaySpan {
+        let parent = TraceContext::extract(headers);
+        let context = match &parent {
+            Some(parent) => parent.child(),
+            None => TraceContext::new_root(rand::random::<f64>() < self.config.sampling_rate),
+        };
+
+        let mut attributes = self.config.tags.clone();
+        attributes.insert("service.name".into(), self.config.service_name.clone());
+        GatewaySpan {
+            name: name.into(),
+            context,
+            parent_span_id: parent.map(|parent| parent.span_id),
+            start_unix_nanos: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64),
+            duration: Duration::ZERO,
+            phases: Vec::new(),
+            attributes,
+            error: None,
+            started: Instant::now(),
+        }
+    }
+
+    /// Finish `span`, exporting it if its trace is sampled
+    pub fn finish(&self, mut span: GatewaySpan) {
+        span.duration = span.started.elapsed();
+        if span.context.sampled {
+            self.exporter.export(span);
+        }
+    }
+
+    /// Proxy `request` to an upstream of `pool` chosen by `balancer`,
+    /// sending it with `send`.
+    ///
+    /// With tracing enabled, a gateway span covers routing, upstream
+    /// selection and the upstream call, and the request reaches `send`
+    /// carrying the span's `traceparent`. With tracing disabled, trace
+    /// headers pass through untouched.
+    pub async fn proxy<F, Fut>(
+        &self,
+        mut request: hyper::Request<hyper::Body>,
+        router: &Router,
+        pool: &UpstreamPool,
+        balancer: &dyn LoadBalancer,
+        send: F,
+    ) -> Result<hyper::Response<hyper::Body>>
+    where
+        F: FnOnce(Upstream, hyper::Request<hyper::Body>) -> Fut,
+        Fut: ::core::future::Future<Output = Result<hyper::Response<hyper::Body>>>,
+    {
+        if !self.config.enabled {
+            router.find_route(&request)?;
+            let upstream = pool.select(balancer, &request).await?;
+            return send(upstream, request).await;
+        }
+
+        let mut span = self.start_span("gateway.proxy", request.headers());
+        span.set_attribute("http.method", request.method().as_str());
+        span.set_attribute("http.target", request.uri().path());
+
+        let result = async {
+            let route_id = span.phase("route", async { router.find_route(&request).map(|route| route.id.clone()) }).await?;
+            span.set_attribute("gateway.route", route_id);
+
+            let upstream = span.phase("select_upstream", pool.select(balancer, &request)).await?;
+            span.set_attribute("gateway.upstream", upstream.url.as_str());
+
+            span.inject(request.headers_mut());
+            let response = span.phase("upstream", send(upstream, request)).await?;
+            span.set_attribute("http.status_code", response.status().as_str());
+            Ok::<_, GatewayError>(response)
+        }
+        .await;
+
+        if let Err(error) = &result {
+            span.error = Some(error.to_string());
+        }
+        self.finish(span);
+        result
+    }
+}
+
+/// Random non-zero ID
+fn random_id<const N: usize>() -> [u8; N] {
+    loop {
+        let id: [u8; N] = ::core::array::from_fn(|_| rand::random());
+        if id != [0; N] {
+            return id;
+        }
+    }
+}
+
+/// Lowercase hex encoding of `bytes`
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| alloc::format!("{:02x}", byte)).collect()
+}
+
+/// Decode exactly `N` bytes of lowercase hex
+fn parse_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != 2 * N || !s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{Body, Request, Response};
+    use std::sync::Mutex;
+
+    const INCOMING: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[derive(Default)]
+    struct CollectingExporter {
+        spans: Mutex<Vec<GatewaySpan>>,
+    }
+
+    impl SpanExporter for CollectingExporter {
+        fn export(&self, span: GatewaySpan) {
+            self.spans.lock().unwrap().push(span);
+        }
+    }
+
+    #[test]
+    fn test_traceparent_parsing() {
+        let context = TraceContext::parse(INCOMING, Some("vendor=abc")).unwrap();
+        assert_eq!(context.traceparent(), INCOMING);
+        assert!(context.sampled);
+        assert_eq!(context.trace_state.as_deref(), Some("vendor=abc"));
+
+        // Later versions may append fields
+        assert!(TraceContext::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra", None).is_some());
+        for invalid in [
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        ] {
+            assert!(TraceContext::parse(invalid, None).is_none(), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upstream_request_continues_incoming_trace() {
+        let mut router = Router::new();
+        router
+            .add_route(RouteBuilder::new("api").path("/api").upstream("http://service1:8080", 1).unwrap().build())
+            .unwrap();
+        let pool = UpstreamPool::new(vec![Upstream {
+            url: "http://service1:8080".parse().unwrap(),
+            ..Default::default()
+        }]);
+        let exporter = Arc::new(CollectingExporter::default());
+        let tracer = Tracer::new(TracingConfig::default()).with_exporter(exporter.clone());
+
+        let request = Request::builder()
+            .uri("http://gateway/api")
+            .header(TRACEPARENT_HEADER, INCOMING)
+            .header(TRACESTATE_HEADER, "vendor=abc")
+            .body(Body::empty())
+            .unwrap();
+        let forwarded = Arc::new(Mutex::new(None));
+        let seen = forwarded.clone();
+        let response = tracer
+            .proxy(request, &router, &pool, &RoundRobinBalancer::new(), |upstream, request| async move {
+                assert_eq!(upstream.url.as_str(), "http://service1:8080/");
+                *seen.lock().unwrap() = Some(request.headers().clone());
+                Ok(Response::new(Body::empty()))
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let headers = forwarded.lock().unwrap().take().unwrap();
+        let upstream = TraceContext::extract(&headers).unwrap();
+        let incoming = TraceContext::parse(INCOMING, None).unwrap();
+        assert_eq!(upstream.trace_id, incoming.trace_id);
+        assert_ne!(upstream.span_id, incoming.span_id);
+        assert!(upstream.sampled);
+        assert_eq!(headers.get(TRACESTATE_HEADER).unwrap(), "vendor=abc");
+
+        // The upstream's parent is the gateway span, itself a child of the caller
+        let spans = exporter.spans.lock().unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].context.span_id, upstream.span_id);
+        assert_eq!(spans[0].parent_span_id, Some(incoming.span_id));
+        let phases: Vec<_> = spans[0].phases.iter().map(|(name, _)| *name).collect();
+        assert_eq!(phases, ["route", "select_upstream", "upstream"]);
+        assert_eq!(spans[0].attributes["gateway.route"], "api");
+    }
+}
@@ -83,7 +83,7 @@ impl FrysKernel {
         println!("Phase 4: Initializing storage engine...");
         let storage_engine = StorageEngine::new(
             config.storage_path.as_deref(),
-            config.storage_config.clone(),
+            &config.storage_config,
         )?;
         println!("✓ Storage engine initialized");
 
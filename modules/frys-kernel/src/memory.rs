@@ -1,32 +1,212 @@
 //! Memory pool implementation
 
+use crate::utils::align_up;
 use crate::*;
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use std::sync::{Mutex, PoisonError};
 
-/// Memory pool for allocations
+/// Arena size used by slab strategies, which carry no arena size of their own
+const DEFAULT_ARENA_SIZE: usize = 1024 * 1024;
+
+/// Arena allocator handing out `SIMD_ALIGNMENT`-aligned memory.
+///
+/// Allocations are bump-allocated from arenas that are only released as a
+/// whole by [`reset`](MemoryPool::reset). The returned [`PoolBox`] handles
+/// borrow the pool, so the borrow checker rules out any handle surviving a
+/// reset.
 #[derive(Debug)]
 pub struct MemoryPool {
-    allocated: usize,
+    arena_size: usize,
+    max_arenas: usize,
     limit: usize,
+    state: Mutex<PoolState>,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    arenas: Vec<Arena>,
+    /// Arena currently bump-allocated from
+    current: usize,
+    /// Offset of the first free byte in the current arena
+    offset: usize,
+    allocated: usize,
+    peak_usage: usize,
+    allocation_count: u64,
+    reset_count: u64,
+}
+
+/// One contiguous block of pool memory
+#[derive(Debug)]
+struct Arena {
+    ptr: NonNull<u8>,
+    size: usize,
+}
+
+// The arena owns its block exclusively; access is serialized by the pool lock
+unsafe impl Send for Arena {}
+
+impl Arena {
+    fn new(size: usize) -> Result<Self> {
+        let layout = Layout::from_size_align(size, SIMD_ALIGNMENT).map_err(|_| KernelError::MemoryAllocationFailed {
+            requested: size,
+            available: 0,
+        })?;
+        // SAFETY: `size` is never zero, see `MemoryPool::allocate`
+        let ptr = NonNull::new(unsafe { std::alloc::alloc(layout) }).ok_or(KernelError::MemoryAllocationFailed {
+            requested: size,
+            available: 0,
+        })?;
+        Ok(Self { ptr, size })
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `Arena::new` with this exact layout
+        unsafe { std::alloc::dealloc(self.ptr.as_ptr(), Layout::from_size_align_unchecked(self.size, SIMD_ALIGNMENT)) };
+    }
 }
 
 impl MemoryPool {
     /// Create a new memory pool
-    pub fn new(_strategy: MemoryStrategy, limit: usize) -> Result<Self> {
+    pub fn new(strategy: &MemoryStrategy, limit: usize) -> Result<Self> {
+        let (arena_size, max_arenas) = match *strategy {
+            MemoryStrategy::ArenaBased { arena_size, max_arenas } => (arena_size, max_arenas),
+            MemoryStrategy::Hybrid { arena_size, .. } => (arena_size, usize::MAX),
+            MemoryStrategy::SlabBased { .. } => (DEFAULT_ARENA_SIZE, usize::MAX),
+        };
+        if arena_size == 0 {
+            return Err(KernelError::InvalidConfiguration {
+                field: "arena_size".into(),
+                reason: "must be greater than zero".into(),
+            });
+        }
+
         Ok(Self {
-            allocated: 0,
+            arena_size: align_up(arena_size, SIMD_ALIGNMENT),
+            max_arenas,
             limit,
+            state: Mutex::new(PoolState::default()),
         })
     }
 
+    /// Allocate a default-initialized `T`
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::MemoryAllocationFailed`] if the pool limit or arena
+    /// count is exhausted.
+    pub fn alloc<T: Default>(&self) -> Result<PoolBox<'_, T>> {
+        let ptr = self.allocate(Layout::new::<T>())?.cast::<T>();
+        // SAFETY: fresh memory, sized and aligned for `T`
+        unsafe { ptr.as_ptr().write(T::default()) };
+        Ok(PoolBox { ptr, _pool: PhantomData })
+    }
+
+    /// Allocate `len` default-initialized `T`s
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::MemoryAllocationFailed`] if the slice is too large for
+    /// an arena or the pool limit or arena count is exhausted.
+    pub fn alloc_slice<T: Default>(&self, len: usize) -> Result<PoolBox<'_, [T]>> {
+        let layout = Layout::array::<T>(len).map_err(|_| KernelError::MemoryAllocationFailed {
+            requested: usize::MAX,
+            available: self.limit,
+        })?;
+        let ptr = self.allocate(layout)?.cast::<T>();
+        for i in 0..len {
+            // SAFETY: in bounds of the fresh allocation
+            unsafe { ptr.as_ptr().add(i).write(T::default()) };
+        }
+        let slice = NonNull::slice_from_raw_parts(ptr, len);
+        Ok(PoolBox { ptr: slice, _pool: PhantomData })
+    }
+
+    /// Reclaim every allocation at once, keeping the arenas for reuse
+    pub fn reset(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        state.current = 0;
+        state.offset = 0;
+        state.allocated = 0;
+        state.reset_count += 1;
+    }
+
+    /// Bump-allocate `layout`, aligned to at least `SIMD_ALIGNMENT`
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>> {
+        let align = layout.align().max(SIMD_ALIGNMENT);
+        if layout.size() == 0 {
+            // Zero-sized values need no memory, only a well-aligned address
+            return Ok(NonNull::new(align as *mut u8).unwrap_or(NonNull::dangling()));
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        // Arenas are SIMD-aligned, so offsets only need stricter alignment
+        // for over-aligned types
+        let needed = layout.size() + (align - SIMD_ALIGNMENT);
+
+        loop {
+            let current = state.current;
+            if let Some(arena) = state.arenas.get(current) {
+                let base = arena.ptr.as_ptr() as usize;
+                let start = align_up(base + state.offset, align) - base;
+                if start + layout.size() <= arena.size {
+                    let ptr = arena.ptr.as_ptr().wrapping_add(start);
+                    state.offset = align_up(start + layout.size(), SIMD_ALIGNMENT);
+                    state.allocated += layout.size();
+                    state.peak_usage = state.peak_usage.max(state.allocated);
+                    state.allocation_count += 1;
+                    return NonNull::new(ptr).ok_or(KernelError::MemoryAllocationFailed {
+                        requested: layout.size(),
+                        available: 0,
+                    });
+                }
+                // Move on to the next arena, which may have been kept from
+                // before the last reset
+                if current + 1 < state.arenas.len() && state.arenas[current + 1].size >= needed {
+                    state.current += 1;
+                    state.offset = 0;
+                    continue;
+                }
+            }
+
+            let size = self.arena_size.max(align_up(needed, SIMD_ALIGNMENT));
+            let reserved: usize = state.arenas.iter().map(|arena| arena.size).sum();
+            if reserved + size > self.limit {
+                return Err(KernelError::MemoryAllocationFailed {
+                    requested: layout.size(),
+                    available: self.limit.saturating_sub(reserved),
+                });
+            }
+            if state.arenas.len() >= self.max_arenas {
+                return Err(KernelError::ResourceLimitExceeded {
+                    resource: "arenas".into(),
+                    limit: self.max_arenas,
+                    requested: state.arenas.len() + 1,
+                });
+            }
+
+            let arena = Arena::new(size)?;
+            let index = if state.arenas.is_empty() { 0 } else { state.current + 1 };
+            state.arenas.insert(index, arena);
+            state.current = index;
+            state.offset = 0;
+        }
+    }
+
     /// Get memory statistics
     pub fn stats(&self) -> MemoryStats {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
         MemoryStats {
-            total_allocated: self.allocated,
-            peak_usage: self.allocated,
+            total_allocated: state.allocated,
+            peak_usage: state.peak_usage,
             fragmentation_ratio: 0.0,
-            allocation_count: 0,
-            deallocation_count: 0,
-            arena_count: 1,
+            allocation_count: state.allocation_count,
+            deallocation_count: state.reset_count,
+            arena_count: state.arenas.len(),
             active_allocations: 0,
         }
     }
@@ -52,6 +232,48 @@ impl MemoryPool {
     }
 }
 
+/// Value or slice allocated from a [`MemoryPool`].
+///
+/// Dropping the handle runs the value's destructor; the memory itself is
+/// reclaimed when the pool is reset.
+pub struct PoolBox<'a, T: ?Sized> {
+    ptr: NonNull<T>,
+    _pool: PhantomData<&'a mut T>,
+}
+
+impl<T: ?Sized> Deref for PoolBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: initialized on allocation and exclusively owned by the handle
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for PoolBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: as in `deref`
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T: ?Sized> Drop for PoolBox<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the value is initialized and never dropped elsewhere
+        unsafe { core::ptr::drop_in_place(self.ptr.as_ptr()) };
+    }
+}
+
+impl<T: ?Sized + core::fmt::Debug> core::fmt::Debug for PoolBox<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+// A handle is an owning pointer, so it is as thread-safe as `T` itself
+unsafe impl<T: ?Sized + Send> Send for PoolBox<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for PoolBox<'_, T> {}
+
 /// Memory statistics
 #[derive(Debug, Clone, Default)]
 pub struct MemoryStats {
@@ -105,4 +327,52 @@ impl LockFreeMemoryPool {
 pub struct Allocation {
     pub ptr: usize,
     pub size: usize,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> MemoryPool {
+        let strategy = MemoryStrategy::ArenaBased {
+            arena_size: 4096,
+            max_arenas: 2,
+        };
+        MemoryPool::new(&strategy, 64 * 1024).unwrap()
+    }
+
+    #[test]
+    fn test_allocations_are_simd_aligned() {
+        let pool = pool();
+        let byte = pool.alloc::<u8>().unwrap();
+        let mut floats = pool.alloc_slice::<f32>(100).unwrap();
+        let word = pool.alloc::<u64>().unwrap();
+
+        for address in [&*byte as *const u8 as usize, floats.as_ptr() as usize, &*word as *const u64 as usize] {
+            assert_eq!(address % SIMD_ALIGNMENT, 0);
+        }
+        assert!(floats.iter().all(|f| *f == 0.0));
+        floats[99] = 1.5;
+        assert_eq!(floats.iter().sum::<f32>(), 1.5);
+        assert_eq!(*word, 0);
+    }
+
+    #[test]
+    fn test_reset_reclaims_space() {
+        let mut pool = pool();
+        let first = pool.alloc_slice::<u8>(3000).unwrap().as_ptr();
+        // Does not fit next to the first buffer, so a second arena is used
+        pool.alloc_slice::<u8>(3000).unwrap();
+        assert_eq!(pool.stats().arena_count, 2);
+        assert!(matches!(
+            pool.alloc_slice::<u8>(3000),
+            Err(KernelError::ResourceLimitExceeded { .. })
+        ));
+
+        pool.reset();
+        assert_eq!(pool.stats().total_allocated, 0);
+        assert_eq!(pool.alloc_slice::<u8>(3000).unwrap().as_ptr(), first);
+        pool.alloc_slice::<u8>(3000).unwrap();
+        assert_eq!(pool.stats().arena_count, 2);
+    }
+}
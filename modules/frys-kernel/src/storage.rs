@@ -1,36 +1,223 @@
 //! Storage engine implementation
+//!
+//! Writes go to a write-ahead log before they reach the in-memory table, so
+//! after a crash [`StorageEngine::recover`] rebuilds the table by replaying
+//! the log. Log records are framed by `frys-record-log`; a record torn by
+//! a crash fails its checksum and ends the replay.
 
 use crate::*;
+use frys_record_log::{frame, next_record};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Name of the log file inside the storage directory
+const WAL_FILE: &str = "wal.log";
+
+const RECORD_PUT: u8 = 1;
+const RECORD_DELETE: u8 = 2;
 
 /// Storage engine for persistence
 #[derive(Debug)]
-pub struct StorageEngine;
+pub struct StorageEngine {
+    wal_path: Option<PathBuf>,
+    state: Mutex<StorageState>,
+}
+
+#[derive(Debug, Default)]
+struct StorageState {
+    memtable: BTreeMap<Vec<u8>, Vec<u8>>,
+    wal: Option<File>,
+    wal_entries: u64,
+}
 
 impl StorageEngine {
-    pub fn new(_path: Option<&str>, _config: StorageConfig) -> Result<Self> {
-        Ok(Self)
+    /// Open the engine, replaying the log found in the `path` directory.
+    ///
+    /// Without a path, or with the WAL disabled, data lives in memory only.
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::StorageError`] if the directory or log cannot be
+    /// created or read.
+    pub fn new(path: Option<&str>, config: &StorageConfig) -> Result<Self> {
+        let wal_path = match path {
+            Some(dir) if config.enable_wal => {
+                std::fs::create_dir_all(dir).map_err(|e| storage_error("create directory", &e))?;
+                Some(PathBuf::from(dir).join(WAL_FILE))
+            }
+            _ => None,
+        };
+
+        let engine = Self {
+            wal_path,
+            state: Mutex::new(StorageState::default()),
+        };
+        engine.recover()?;
+        Ok(engine)
     }
 
+    /// Store `value` under `key`
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::StorageError`] if the record cannot be logged.
+    pub fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut state = self.lock();
+        state.append(&encode(RECORD_PUT, key, value)?)?;
+        state.memtable.insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    /// Value stored under `key`
+    ///
+    /// # Errors
+    ///
+    /// None yet: reads are served from memory.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.lock().memtable.get(key).cloned())
+    }
+
+    /// Remove `key`, returning whether it was present
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::StorageError`] if the record cannot be logged.
+    pub fn delete(&self, key: &[u8]) -> Result<bool> {
+        let mut state = self.lock();
+        if !state.memtable.contains_key(key) {
+            return Ok(false);
+        }
+        state.append(&encode(RECORD_DELETE, key, &[])?)?;
+        state.memtable.remove(key);
+        Ok(true)
+    }
+
+    /// Rebuild the in-memory table from the log, returning the number of
+    /// records replayed. A torn record at the end of the log is dropped.
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::StorageError`] if the log cannot be read or reopened.
+    pub fn recover(&self) -> Result<u64> {
+        let mut state = self.lock();
+        state.memtable.clear();
+        state.wal = None;
+        state.wal_entries = 0;
+
+        let Some(path) = &self.wal_path else {
+            return Ok(0);
+        };
+
+        let mut bytes = Vec::new();
+        if path.exists() {
+            File::open(path)
+                .and_then(|mut file| file.read_to_end(&mut bytes))
+                .map_err(|e| storage_error("read log", &e))?;
+        }
+
+        let mut offset = 0;
+        while let Some((body, next)) = next_record(&bytes, offset) {
+            match decode(body) {
+                Some((RECORD_PUT, key, value)) => {
+                    state.memtable.insert(key.to_vec(), value.to_vec());
+                }
+                Some((RECORD_DELETE, key, _)) => {
+                    state.memtable.remove(key);
+                }
+                _ => break,
+            }
+            state.wal_entries += 1;
+            offset = next;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| storage_error("open log", &e))?;
+        // New records must not follow a torn one
+        file.set_len(offset as u64).map_err(|e| storage_error("truncate log", &e))?;
+        state.wal = Some(file);
+
+        Ok(state.wal_entries)
+    }
+
+    /// Get storage statistics
     pub fn stats(&self) -> StorageStats {
+        let state = self.lock();
         StorageStats {
-            total_entries: 0,
-            total_size: 0,
-            wal_entries: 0,
+            total_entries: state.memtable.len() as u64,
+            total_size: state.memtable.iter().map(|(key, value)| key.len() + value.len()).sum(),
+            wal_entries: state.wal_entries,
             lsm_levels: 0,
         }
     }
 
+    /// Flush the log to disk
     pub async fn shutdown(&self) -> Result<()> {
+        if let Some(wal) = &self.lock().wal {
+            wal.sync_all().map_err(|e| storage_error("sync log", &e))?;
+        }
         Ok(())
     }
 
+    /// Perform health check
     pub async fn health_check(&self) -> Result<()> {
         Ok(())
     }
 
+    /// Start compaction task
     pub async fn start_compaction_task(&self) -> Result<()> {
         Ok(())
     }
+
+    fn lock(&self) -> MutexGuard<'_, StorageState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl StorageState {
+    /// Append a record to the log. It reaches the disk before returning,
+    /// so it survives a crash of the process or of the machine.
+    fn append(&mut self, body: &[u8]) -> Result<()> {
+        if let Some(wal) = &mut self.wal {
+            record_len(body.len())?;
+            wal.write_all(&frame(body)).map_err(|e| storage_error("append to log", &e))?;
+            wal.sync_data().map_err(|e| storage_error("sync log", &e))?;
+            self.wal_entries += 1;
+        }
+        Ok(())
+    }
+}
+
+fn storage_error(operation: &str, error: &std::io::Error) -> KernelError {
+    KernelError::StorageError(format!("failed to {operation}: {error}"))
+}
+
+/// `len` as a record length field, refusing lengths that do not fit
+fn record_len(len: usize) -> Result<u32> {
+    u32::try_from(len)
+        .map_err(|_| KernelError::StorageError(format!("record of {len} bytes is too large for the log")))
+}
+
+fn encode(tag: u8, key: &[u8], value: &[u8]) -> Result<Vec<u8>> {
+    let key_len = record_len(key.len())?;
+    let mut body = Vec::with_capacity(5 + key.len() + value.len());
+    body.push(tag);
+    body.extend_from_slice(&key_len.to_le_bytes());
+    body.extend_from_slice(key);
+    body.extend_from_slice(value);
+    Ok(body)
+}
+
+fn decode(body: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = body.split_first()?;
+    let key_len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let key = rest.get(4..4 + key_len)?;
+    Some((tag, key, &rest[4 + key_len..]))
 }
 
 /// Storage statistics
@@ -40,4 +227,42 @@ pub struct StorageStats {
     pub total_size: usize,
     pub wal_entries: u64,
     pub lsm_levels: usize,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("frys-kernel-storage-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir = dir.to_str().unwrap();
+
+        {
+            let engine = StorageEngine::new(Some(dir), &StorageConfig::default()).unwrap();
+            engine.put(b"alpha", b"1").unwrap();
+            engine.put(b"beta", b"2").unwrap();
+            engine.put(b"alpha", b"3").unwrap();
+            assert!(engine.delete(b"beta").unwrap());
+            assert!(!engine.delete(b"gamma").unwrap());
+            // Dropped without shutdown, as in a crash
+        }
+        // A crash part-way through appending another record
+        let mut wal = OpenOptions::new().append(true).open(PathBuf::from(dir).join(WAL_FILE)).unwrap();
+        wal.write_all(&[9, 0, 0, 0, 1, 2]).unwrap();
+
+        let engine = StorageEngine::new(Some(dir), &StorageConfig::default()).unwrap();
+        assert_eq!(engine.get(b"alpha").unwrap(), Some(b"3".to_vec()));
+        assert_eq!(engine.get(b"beta").unwrap(), None);
+        assert_eq!(engine.stats().wal_entries, 4);
+
+        // The torn record is gone, so new writes replay cleanly
+        engine.put(b"gamma", b"4").unwrap();
+        assert_eq!(engine.recover().unwrap(), 5);
+        assert_eq!(engine.get(b"gamma").unwrap(), Some(b"4".to_vec()));
+        assert_eq!(engine.stats().total_entries, 2);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,34 +1,477 @@
 //! Thread pool implementation
 
 use crate::*;
+use core::any::Any;
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
 
-/// Thread pool for task execution
+/// Work-stealing thread pool.
+///
+/// Each worker owns a deque: tasks spawned from a worker go to the back of
+/// its own deque and are taken LIFO, tasks from other threads go to a
+/// shared injector queue, and idle workers steal from the front of other
+/// workers' deques. A panicking task is caught and reported by its
+/// [`JoinHandle`] instead of taking the worker down.
 #[derive(Debug)]
-pub struct ThreadPool;
+pub struct ThreadPool {
+    shared: Arc<Shared>,
+    workers: Mutex<Vec<std::thread::JoinHandle<()>>>,
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// How long an idle worker sleeps before looking for work again
+const IDLE_WAIT: Duration = Duration::from_millis(10);
+
+struct Shared {
+    injector: Mutex<VecDeque<Job>>,
+    locals: Vec<Mutex<VecDeque<Job>>>,
+    /// Guards the sleep of idle workers
+    sleep: Mutex<()>,
+    wakeup: Condvar,
+    shutdown: AtomicBool,
+    active_threads: AtomicUsize,
+    total_tasks: AtomicU64,
+    completed_tasks: AtomicU64,
+}
+
+impl core::fmt::Debug for Shared {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Shared")
+            .field("workers", &self.locals.len())
+            .field("shutdown", &self.shutdown.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+std::thread_local! {
+    /// Pool and deque index of the worker running on this thread
+    static WORKER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+impl Shared {
+    fn id(&self) -> usize {
+        core::ptr::from_ref(self) as usize
+    }
+
+    /// Deque index of the calling thread, if it is a worker of this pool
+    fn current_worker(&self) -> Option<usize> {
+        WORKER.with(Cell::get).and_then(|(pool, index)| (pool == self.id()).then_some(index))
+    }
+
+    fn push(&self, job: Job) {
+        self.total_tasks.fetch_add(1, Ordering::Relaxed);
+        match WORKER.with(Cell::get) {
+            Some((pool, index)) if pool == self.id() => lock(&self.locals[index]).push_back(job),
+            _ => lock(&self.injector).push_back(job),
+        }
+        let _sleep = lock(&self.sleep);
+        self.wakeup.notify_one();
+    }
+
+    /// Next job for `worker`: its own newest job, then the oldest injected
+    /// one, then the oldest job of another worker
+    fn find_job(&self, worker: Option<usize>) -> Option<Job> {
+        if let Some(job) = worker.and_then(|index| lock(&self.locals[index]).pop_back()) {
+            return Some(job);
+        }
+        if let Some(job) = lock(&self.injector).pop_front() {
+            return Some(job);
+        }
+        let start = worker.map_or(0, |index| index + 1);
+        (0..self.locals.len())
+            .map(|offset| (start + offset) % self.locals.len())
+            .filter(|victim| Some(*victim) != worker)
+            .find_map(|victim| lock(&self.locals[victim]).pop_front())
+    }
+
+    fn run(&self, job: Job) {
+        job();
+        self.completed_tasks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn queued(&self) -> usize {
+        lock(&self.injector).len() + self.locals.iter().map(|local| lock(local).len()).sum::<usize>()
+    }
+
+    fn wait_idle(&self) {
+        let sleep = lock(&self.sleep);
+        if !self.shutdown.load(Ordering::Acquire) {
+            drop(self.wakeup.wait_timeout(sleep, IDLE_WAIT));
+        }
+    }
+}
+
+fn worker_loop(shared: &Shared, index: usize) {
+    WORKER.with(|worker| worker.set(Some((shared.id(), index))));
+    loop {
+        match shared.find_job(Some(index)) {
+            Some(job) => shared.run(job),
+            // Queued work is still finished after shutdown is requested
+            None if shared.shutdown.load(Ordering::Acquire) => break,
+            None => shared.wait_idle(),
+        }
+    }
+    shared.active_threads.fetch_sub(1, Ordering::Relaxed);
+}
 
 impl ThreadPool {
-    pub fn new(_count: usize, _affinity: CpuAffinityConfig) -> Result<Self> {
-        Ok(Self)
+    /// Start `count` worker threads, pinned to CPU cores as `affinity` asks
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::InvalidConfiguration`] if `count` is zero, or
+    /// [`KernelError::ThreadError`] if a worker cannot be started.
+    pub fn new(count: usize, affinity: &CpuAffinityConfig) -> Result<Self> {
+        if count == 0 {
+            return Err(KernelError::InvalidConfiguration {
+                field: "thread_count".into(),
+                reason: "must be greater than zero".into(),
+            });
+        }
+
+        let shared = Arc::new(Shared {
+            injector: Mutex::new(VecDeque::new()),
+            locals: (0..count).map(|_| Mutex::new(VecDeque::new())).collect(),
+            sleep: Mutex::new(()),
+            wakeup: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            active_threads: AtomicUsize::new(0),
+            total_tasks: AtomicU64::new(0),
+            completed_tasks: AtomicU64::new(0),
+        });
+
+        let cores = std::thread::available_parallelism().map_or(1, core::num::NonZeroUsize::get);
+        let mut workers = Vec::with_capacity(count);
+        for index in 0..count {
+            let core = if affinity.cores.is_empty() { index % cores } else { affinity.cores[index % affinity.cores.len()] };
+            let pin = affinity.enabled && affinity.pin_threads;
+            let worker_shared = shared.clone();
+            let worker = std::thread::Builder::new()
+                .name(format!("frys-worker-{index}"))
+                .spawn(move || {
+                    if pin {
+                        // Best effort: an unpinned worker still runs tasks
+                        pin_current_thread(core);
+                    }
+                    worker_loop(&worker_shared, index);
+                })
+                .map_err(|e| KernelError::ThreadError(format!("failed to start worker {index}: {e}")))?;
+            shared.active_threads.fetch_add(1, Ordering::Relaxed);
+            workers.push(worker);
+        }
+
+        Ok(Self {
+            shared,
+            workers: Mutex::new(workers),
+        })
+    }
+
+    /// Start one worker per `thread_count`, each pinned to its own core
+    ///
+    /// # Errors
+    ///
+    /// As for [`ThreadPool::new`].
+    pub fn from_config(config: &KernelConfig) -> Result<Self> {
+        let affinity = CpuAffinityConfig {
+            enabled: true,
+            cores: Vec::new(),
+            pin_threads: true,
+        };
+        Self::new(config.thread_count, &affinity)
+    }
+
+    /// Run `f` on the pool
+    pub fn spawn<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let slot = Arc::new(Slot::default());
+        let result = slot.clone();
+        self.shared.push(Box::new(move || result.set(catch_unwind(AssertUnwindSafe(f)))));
+        JoinHandle {
+            slot,
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Run `f` with a [`Scope`] whose tasks may borrow from the caller's
+    /// stack. Returns once every task spawned in the scope has finished.
+    ///
+    /// The calling thread runs pool tasks while it waits, so scopes may be
+    /// opened from inside pool tasks.
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        let scope = Scope {
+            shared: &self.shared,
+            pending: Arc::new(AtomicUsize::new(0)),
+            _lifetimes: PhantomData,
+        };
+        let result = catch_unwind(AssertUnwindSafe(|| f(&scope)));
+
+        // Borrowed data must outlive every task, even if `f` panicked
+        let worker = self.shared.current_worker();
+        while scope.pending.load(Ordering::Acquire) > 0 {
+            match self.shared.find_job(worker) {
+                Some(job) => self.shared.run(job),
+                None => std::thread::yield_now(),
+            }
+        }
+
+        match result {
+            Ok(value) => value,
+            Err(payload) => resume_unwind(payload),
+        }
     }
 
+    /// Get thread pool statistics
     pub fn stats(&self) -> ThreadStats {
         ThreadStats {
-            active_threads: 4,
-            total_tasks: 0,
-            completed_tasks: 0,
-            queued_tasks: 0,
+            active_threads: self.shared.active_threads.load(Ordering::Relaxed),
+            total_tasks: self.shared.total_tasks.load(Ordering::Relaxed),
+            completed_tasks: self.shared.completed_tasks.load(Ordering::Relaxed),
+            queued_tasks: self.shared.queued(),
         }
     }
 
+    /// Finish the queued tasks and stop the workers
     pub async fn shutdown(&self) -> Result<()> {
-        Ok(())
+        self.stop()
     }
 
+    /// Perform health check
     pub async fn health_check(&self) -> Result<()> {
+        if self.shared.shutdown.load(Ordering::Acquire) {
+            return Err(KernelError::ThreadError("thread pool is shut down".into()));
+        }
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<()> {
+        {
+            let _sleep = lock(&self.shared.sleep);
+            self.shared.shutdown.store(true, Ordering::Release);
+            self.shared.wakeup.notify_all();
+        }
+
+        let workers = core::mem::take(&mut *lock(&self.workers));
+        let current = std::thread::current().id();
+        for worker in workers {
+            // A task shutting down its own pool cannot wait for itself
+            if worker.thread().id() != current && worker.join().is_err() {
+                return Err(KernelError::ThreadError("worker thread panicked".into()));
+            }
+        }
         Ok(())
     }
 }
 
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Scope for spawning tasks that borrow from the enclosing stack frame,
+/// created by [`ThreadPool::scope`]
+#[derive(Debug)]
+pub struct Scope<'scope, 'env: 'scope> {
+    shared: &'scope Arc<Shared>,
+    pending: Arc<AtomicUsize>,
+    _lifetimes: PhantomData<(&'scope mut &'scope (), &'env mut &'env ())>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Run `f` on the pool before the scope ends
+    pub fn spawn<F, T>(&'scope self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let slot = Arc::new(Slot::default());
+        self.pending.fetch_add(1, Ordering::AcqRel);
+
+        // Tuple fields drop in order, so even a job dropped without running
+        // releases `f` and its result before the guard
+        let task = (f, slot.clone(), PendingGuard(self.pending.clone()));
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            let (f, result, pending) = task;
+            result.set(catch_unwind(AssertUnwindSafe(f)));
+            // The result may borrow from the scope: if the handle is gone,
+            // this drops it, and that must happen before the scope can end
+            drop(result);
+            drop(pending);
+        });
+        // SAFETY: `ThreadPool::scope` does not return before `pending` drops
+        // to zero, and the guard decrementing it is dropped last, after the
+        // closure and this job's reference to the result. Nothing the job
+        // borrows is freed while it may still be used.
+        let job: Job = unsafe { core::mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Job>(job) };
+        self.shared.push(job);
+
+        ScopedJoinHandle {
+            slot,
+            shared: self.shared,
+        }
+    }
+}
+
+/// Marks a scoped task finished when dropped
+struct PendingGuard(Arc<AtomicUsize>);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Result of a task, filled in by the worker that runs it
+struct Slot<T> {
+    result: Mutex<Option<std::thread::Result<T>>>,
+    done: Condvar,
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self {
+            result: Mutex::new(None),
+            done: Condvar::new(),
+        }
+    }
+}
+
+impl<T> Slot<T> {
+    fn set(&self, result: std::thread::Result<T>) {
+        *lock(&self.result) = Some(result);
+        self.done.notify_all();
+    }
+
+    fn is_set(&self) -> bool {
+        lock(&self.result).is_some()
+    }
+
+    /// Wait for the result, running queued jobs of `shared` meanwhile so
+    /// that a task joining another task cannot starve the pool
+    fn wait(&self, shared: &Shared) -> Result<T> {
+        let worker = shared.current_worker();
+        loop {
+            let mut result = lock(&self.result);
+            match result.take() {
+                Some(Ok(value)) => return Ok(value),
+                Some(Err(payload)) => return Err(KernelError::ThreadError(panic_message(payload.as_ref()))),
+                None => drop(result),
+            }
+
+            if let Some(job) = shared.find_job(worker) {
+                shared.run(job);
+            } else {
+                // The task is running elsewhere
+                let result = lock(&self.result);
+                if result.is_none() {
+                    drop(self.done.wait_timeout(result, IDLE_WAIT));
+                }
+            }
+        }
+    }
+}
+
+/// Handle to the result of a task spawned with [`ThreadPool::spawn`]
+#[derive(Debug)]
+pub struct JoinHandle<T> {
+    slot: Arc<Slot<T>>,
+    shared: Arc<Shared>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Wait for the task and return its result, or a
+    /// [`KernelError::ThreadError`] if it panicked.
+    ///
+    /// The calling thread runs queued pool tasks while it waits, so tasks
+    /// may join tasks they spawned.
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::ThreadError`] if the task panicked.
+    pub fn join(self) -> Result<T> {
+        self.slot.wait(&self.shared)
+    }
+
+    /// Whether the task has finished
+    pub fn is_finished(&self) -> bool {
+        self.slot.is_set()
+    }
+}
+
+/// Handle to the result of a task spawned with [`Scope::spawn`]
+#[derive(Debug)]
+pub struct ScopedJoinHandle<'scope, T> {
+    slot: Arc<Slot<T>>,
+    shared: &'scope Arc<Shared>,
+}
+
+impl<T> ScopedJoinHandle<'_, T> {
+    /// Wait for the task and return its result, or a
+    /// [`KernelError::ThreadError`] if it panicked.
+    ///
+    /// Like [`JoinHandle::join`], runs queued pool tasks while waiting.
+    ///
+    /// # Errors
+    ///
+    /// [`KernelError::ThreadError`] if the task panicked.
+    pub fn join(self) -> Result<T> {
+        self.slot.wait(self.shared)
+    }
+}
+
+impl<T> core::fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Slot").field("finished", &self.is_set()).finish()
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic payload");
+    format!("task panicked: {message}")
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Pin the calling thread to `core`, returning whether it worked
+#[cfg(target_os = "linux")]
+fn pin_current_thread(core: usize) -> bool {
+    extern "C" {
+        fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const u64) -> i32;
+    }
+
+    // Matches glibc's 1024-bit `cpu_set_t`
+    let mut mask = [0u64; 16];
+    if core >= mask.len() * 64 {
+        return false;
+    }
+    mask[core / 64] |= 1 << (core % 64);
+    // SAFETY: pid 0 is the calling thread and `mask` outlives the call
+    unsafe { sched_setaffinity(0, core::mem::size_of_val(&mask), mask.as_ptr()) == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_core: usize) -> bool {
+    false
+}
+
 /// Thread statistics
 #[derive(Debug, Clone, Default)]
 pub struct ThreadStats {
@@ -59,4 +502,106 @@ pub struct DequeStats {
     pub pops: u64,
     pub steals: u64,
     pub steals_failed: u64,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_and_join() {
+        let pool = ThreadPool::new(4, &CpuAffinityConfig::default()).unwrap();
+        let handles: Vec<_> = (0..16u64)
+            .map(|i| pool.spawn(move || (i * 1000..(i + 1) * 1000).sum::<u64>()))
+            .collect();
+        let total: u64 = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+        assert_eq!(total, (0..16_000).sum::<u64>());
+
+        // Tasks may spawn onto their own worker's deque
+        let inner = Arc::new(pool);
+        let pool = inner.clone();
+        let nested = inner.spawn(move || pool.spawn(|| 7).join().unwrap() * 6);
+        assert_eq!(nested.join().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_panicking_task_is_reported() {
+        let pool = ThreadPool::new(2, &CpuAffinityConfig::default()).unwrap();
+        let failed = pool.spawn(|| -> u32 { panic!("boom") });
+        let fine = pool.spawn(|| 1);
+
+        match failed.join() {
+            Err(KernelError::ThreadError(message)) => assert!(message.contains("boom")),
+            other => panic!("expected a thread error, got {:?}", other),
+        }
+        // The worker survived the panic
+        assert_eq!(fine.join().unwrap(), 1);
+        assert_eq!(pool.spawn(|| 2).join().unwrap(), 2);
+        assert_eq!(pool.stats().active_threads, 2);
+    }
+
+    #[test]
+    fn test_scope_borrows_stack_data() {
+        let pool = ThreadPool::from_config(&KernelConfig {
+            thread_count: 3,
+            ..KernelConfig::default()
+        })
+        .unwrap();
+        let mut data: Vec<u64> = (1..=100).collect();
+        let offset = 10;
+
+        let sums = pool.scope(|scope| {
+            let handles: Vec<_> = data
+                .chunks_mut(25)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk.iter_mut().for_each(|value| *value += offset);
+                        chunk.iter().sum::<u64>()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+        });
+
+        assert_eq!(sums.iter().sum::<u64>(), 5050 + 100 * offset);
+        assert_eq!(data[0], 11);
+    }
+
+    #[test]
+    fn test_nested_join_on_single_worker() {
+        fn fib(pool: &Arc<ThreadPool>, n: u64) -> u64 {
+            if n < 2 {
+                return n;
+            }
+            let inner = pool.clone();
+            let left = pool.spawn(move || fib(&inner, n - 1));
+            let right = fib(pool, n - 2);
+            left.join().unwrap() + right
+        }
+
+        // The only worker must run the tasks it joins itself
+        let pool = Arc::new(ThreadPool::new(1, &CpuAffinityConfig::default()).unwrap());
+        let inner = pool.clone();
+        assert_eq!(pool.spawn(move || fib(&inner, 15)).join().unwrap(), 610);
+    }
+
+    #[test]
+    fn test_scoped_result_dropped_before_scope_ends() {
+        struct Flag<'a>(&'a AtomicBool);
+
+        impl Drop for Flag<'_> {
+            fn drop(&mut self) {
+                std::thread::sleep(Duration::from_millis(20));
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let pool = ThreadPool::new(2, &CpuAffinityConfig::default()).unwrap();
+        let dropped = AtomicBool::new(false);
+        pool.scope(|scope| {
+            // The handle is dropped, so the worker drops the result
+            drop(scope.spawn(|| Flag(&dropped)));
+        });
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+}
@@ -43,6 +43,7 @@ impl AlertingEngine {
 
         let rule_with_id = AlertRule {
             id: rule_id.clone(),
+            state: AlertState::Inactive,
             ..rule
         };
 
@@ -57,7 +58,7 @@ impl AlertingEngine {
                 rule.name = name;
             }
             if let Some(description) = updates.description {
-                rule.description = description;
+                rule.description = Some(description);
             }
             if let Some(condition) = updates.condition {
                 rule.condition = condition;
@@ -100,6 +101,11 @@ impl AlertingEngine {
         self.rules.get(rule_id).map(|r| r.value().clone())
     }
 
+    /// Get the current state of an alert rule
+    pub fn rule_state(&self, rule_id: &str) -> Option<AlertState> {
+        self.rules.get(rule_id).map(|r| r.state.clone())
+    }
+
     /// Register a notification channel
     pub fn register_channel(&self, name: &str, channel: Box<dyn NotificationChannel>) {
         self.channels.insert(name.to_string(), channel);
@@ -144,20 +150,62 @@ impl AlertingEngine {
 
     /// Evaluate alert rules against metrics/events
     pub async fn evaluate_rules(&self, metrics: &BTreeMap<String, f64>) -> Result<()> {
-        for rule_entry in self.rules.iter() {
-            let rule = rule_entry.value();
-            if !rule.enabled {
-                continue;
-            }
+        self.evaluate_rules_at(metrics, Utc::now()).await
+    }
 
-            // Check if rule should be evaluated (cooldown)
-            if self.should_skip_evaluation(&rule).await {
-                continue;
-            }
+    /// Evaluate alert rules as of `now`, advancing each rule through
+    /// inactive -> pending -> firing and back.
+    ///
+    /// A rule fires once its condition has held continuously for
+    /// `for_duration`, and resolves only after the metric has moved
+    /// `hysteresis` past the threshold, so values oscillating around the
+    /// threshold do not produce repeated notifications.
+    pub async fn evaluate_rules_at(&self, metrics: &BTreeMap<String, f64>, now: DateTime<Utc>) -> Result<()> {
+        let rule_ids: Vec<String> = self.rules.iter().map(|r| r.key().clone()).collect();
+
+        for rule_id in rule_ids {
+            let rule = match self.get_rule(&rule_id) {
+                Some(rule) if rule.enabled => rule,
+                _ => continue,
+            };
+
+            let next_state = match &rule.state {
+                AlertState::Inactive => {
+                    // Check if rule should be evaluated (cooldown)
+                    if self.should_skip_evaluation(&rule, now).await
+                        || !self.evaluate_condition(&rule.condition, metrics)
+                    {
+                        continue;
+                    }
+
+                    if rule.for_duration == 0 {
+                        let alert_id = self.fire_alert(&rule, now).await?;
+                        AlertState::Firing { since: now, alert_id }
+                    } else {
+                        AlertState::Pending { since: now }
+                    }
+                }
+                AlertState::Pending { since } => {
+                    if !self.evaluate_condition(&rule.condition, metrics) {
+                        AlertState::Inactive
+                    } else if now.signed_duration_since(*since) >= Duration::seconds(rule.for_duration as i64) {
+                        let alert_id = self.fire_alert(&rule, now).await?;
+                        AlertState::Firing { since: now, alert_id }
+                    } else {
+                        continue;
+                    }
+                }
+                AlertState::Firing { alert_id, .. } => {
+                    if self.condition_holds(&rule.condition, metrics, rule.hysteresis) {
+                        continue;
+                    }
+                    self.resolve_fired_alert(&rule, alert_id, now).await;
+                    AlertState::Inactive
+                }
+            };
 
-            // Evaluate condition
-            if self.evaluate_condition(&rule.condition, metrics) {
-                self.fire_alert(rule).await?;
+            if let Some(mut rule) = self.rules.get_mut(&rule_id) {
+                rule.state = next_state;
             }
         }
         Ok(())
@@ -184,29 +232,38 @@ impl AlertingEngine {
         self.active_alerts.len() as u64
     }
 
-    /// Fire an alert
-    async fn fire_alert(&self, rule: &AlertRule) -> Result<()> {
-        let alert_id = uuid::Uuid::new_v4().to_string();
-
+    /// Fire an alert, returning its ID
+    async fn fire_alert(&self, rule: &AlertRule, now: DateTime<Utc>) -> Result<String> {
         let alert = Alert {
-            id: alert_id.clone(),
+            id: uuid::Uuid::new_v4().to_string(),
             type_: rule.alert_type.clone(),
             severity: rule.severity.clone(),
             title: rule.name.clone(),
             message: rule.description.clone().unwrap_or_default(),
             source: "monitoring_system".to_string(),
-            timestamp: Utc::now(),
+            timestamp: now,
             acknowledged: false,
+            acknowledged_by: None,
+            acknowledged_at: None,
             resolved: false,
+            resolved_at: None,
             tags: rule.tags.clone(),
             data: BTreeMap::new(),
         };
 
+        Ok(self.raise_alert(alert, &rule.channels).await)
+    }
+
+    /// Activate an alert raised outside rule evaluation and notify the
+    /// given channels, returning its ID
+    pub async fn raise_alert(&self, alert: Alert, channels: &[String]) -> String {
+        let alert_id = alert.id.clone();
+
         // Add to active alerts
         self.active_alerts.insert(alert_id.clone(), alert.clone());
 
         // Send notifications
-        for channel_name in &rule.channels {
+        for channel_name in channels {
             if let Some(channel) = self.channels.get(channel_name) {
                 if let Err(e) = channel.send_notification(&alert).await {
                     eprintln!("Failed to send notification via {}: {}", channel_name, e);
@@ -214,11 +271,31 @@ impl AlertingEngine {
             }
         }
 
-        Ok(())
+        alert_id
+    }
+
+    /// Resolve an alert fired by a rule and notify its channels
+    async fn resolve_fired_alert(&self, rule: &AlertRule, alert_id: &str, now: DateTime<Utc>) {
+        let Some((_, mut alert)) = self.active_alerts.remove(alert_id) else {
+            // Already resolved manually
+            return;
+        };
+        alert.resolved = true;
+        alert.resolved_at = Some(now);
+
+        for channel_name in &rule.channels {
+            if let Some(channel) = self.channels.get(channel_name) {
+                if let Err(e) = channel.send_resolved(&alert).await {
+                    eprintln!("Failed to send resolve notification via {}: {}", channel_name, e);
+                }
+            }
+        }
+
+        self.alert_history.write().await.push(alert);
     }
 
     /// Check if rule evaluation should be skipped due to cooldown
-    async fn should_skip_evaluation(&self, rule: &AlertRule) -> bool {
+    async fn should_skip_evaluation(&self, rule: &AlertRule, now: DateTime<Utc>) -> bool {
         let history = self.alert_history.read().await;
 
         // Check recent alerts from this rule
@@ -228,7 +305,7 @@ impl AlertingEngine {
 
         if let Some(alert) = recent_alert {
             let cooldown_duration = Duration::seconds(rule.cooldown as i64);
-            let time_since_alert = Utc::now().signed_duration_since(alert.timestamp);
+            let time_since_alert = now.signed_duration_since(alert.timestamp);
 
             return time_since_alert < cooldown_duration;
         }
@@ -238,8 +315,19 @@ impl AlertingEngine {
 
     /// Evaluate alert condition
     fn evaluate_condition(&self, condition: &AlertCondition, metrics: &BTreeMap<String, f64>) -> bool {
+        self.condition_holds(condition, metrics, 0.0)
+    }
+
+    /// Evaluate alert condition with thresholds relaxed by `margin` in the
+    /// direction that keeps the condition true
+    fn condition_holds(&self, condition: &AlertCondition, metrics: &BTreeMap<String, f64>, margin: f64) -> bool {
         match condition {
             AlertCondition::Threshold { metric, operator, threshold } => {
+                let threshold = &match operator {
+                    AlertOperator::GreaterThan | AlertOperator::GreaterEqual => *threshold - margin,
+                    AlertOperator::LessThan | AlertOperator::LessEqual => *threshold + margin,
+                    AlertOperator::Equal | AlertOperator::NotEqual => *threshold,
+                };
                 if let Some(value) = metrics.get(metric) {
                     match operator {
                         AlertOperator::GreaterThan => *value > *threshold,
@@ -255,7 +343,7 @@ impl AlertingEngine {
             }
             AlertCondition::Composite { conditions, operator } => {
                 let results: Vec<bool> = conditions.iter()
-                    .map(|cond| self.evaluate_condition(cond, metrics))
+                    .map(|cond| self.condition_holds(cond, metrics, margin))
                     .collect();
 
                 match operator {
@@ -316,6 +404,24 @@ pub struct AlertRule {
     pub tags: Vec<String>,
     pub enabled: bool,
     pub cooldown: u64, // seconds
+    /// How long the condition must hold before the alert fires, in seconds
+    pub for_duration: u64,
+    /// Distance past the threshold the metric must move before a firing
+    /// alert resolves
+    pub hysteresis: f64,
+    /// Current evaluation state
+    pub state: AlertState,
+}
+
+/// Evaluation state of an alert rule
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertState {
+    /// Condition not met
+    Inactive,
+    /// Condition met, waiting for `for_duration` to elapse
+    Pending { since: DateTime<Utc> },
+    /// Alert fired and not yet resolved
+    Firing { since: DateTime<Utc>, alert_id: String },
 }
 
 /// Alert rule update
@@ -404,6 +510,11 @@ pub struct Alert {
 #[async_trait::async_trait]
 pub trait NotificationChannel: Send + Sync {
     async fn send_notification(&self, alert: &Alert) -> Result<()>;
+
+    /// Notify that a previously fired alert has resolved
+    async fn send_resolved(&self, alert: &Alert) -> Result<()> {
+        self.send_notification(alert).await
+    }
 }
 
 /// Email notification channel
@@ -488,6 +599,9 @@ mod tests {
             tags: vec!["system".to_string()],
             enabled: true,
             cooldown: 300,
+            for_duration: 0,
+            hysteresis: 0.0,
+            state: AlertState::Inactive,
         };
 
         let rule_id = engine.create_rule(rule).await.unwrap();
@@ -566,6 +680,9 @@ mod tests {
             tags: vec![],
             enabled: true,
             cooldown: 60,
+            for_duration: 0,
+            hysteresis: 0.0,
+            state: AlertState::Inactive,
         };
 
         assert!(engine.create_rule(valid_rule).await.is_ok());
@@ -586,8 +703,92 @@ mod tests {
             tags: vec![],
             enabled: true,
             cooldown: 60,
+            for_duration: 0,
+            hysteresis: 0.0,
+            state: AlertState::Inactive,
         };
 
         assert!(engine.create_rule(invalid_rule).await.is_err());
     }
+
+    /// Records fire and resolve notifications
+    #[derive(Clone, Default)]
+    struct RecordingChannel {
+        events: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NotificationChannel for RecordingChannel {
+        async fn send_notification(&self, _alert: &Alert) -> Result<()> {
+            self.events.lock().unwrap().push("firing");
+            Ok(())
+        }
+
+        async fn send_resolved(&self, _alert: &Alert) -> Result<()> {
+            self.events.lock().unwrap().push("resolved");
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_firing_resolve_with_hysteresis() {
+        let engine = AlertingEngine::new(30);
+        let channel = RecordingChannel::default();
+        engine.register_channel("recorder", Box::new(channel.clone()));
+
+        let rule_id = engine.create_rule(AlertRule {
+            id: String::new(),
+            name: "High CPU Usage".to_string(),
+            description: None,
+            alert_type: AlertType::Performance,
+            condition: AlertCondition::Threshold {
+                metric: "cpu_usage".to_string(),
+                operator: AlertOperator::GreaterThan,
+                threshold: 90.0,
+            },
+            severity: AlertSeverity::High,
+            channels: vec!["recorder".to_string()],
+            tags: vec![],
+            enabled: true,
+            cooldown: 0,
+            for_duration: 60,
+            hysteresis: 5.0,
+            state: AlertState::Inactive,
+        }).await.unwrap();
+
+        let start = Utc::now();
+        let at = |seconds: i64| start + Duration::seconds(seconds);
+        let cpu = |value: f64| BTreeMap::from([("cpu_usage".to_string(), value)]);
+
+        engine.evaluate_rules_at(&cpu(95.0), at(0)).await.unwrap();
+        assert_eq!(engine.rule_state(&rule_id), Some(AlertState::Pending { since: at(0) }));
+
+        // Dropping below the threshold while pending resets the timer
+        engine.evaluate_rules_at(&cpu(89.0), at(45)).await.unwrap();
+        assert_eq!(engine.rule_state(&rule_id), Some(AlertState::Inactive));
+
+        engine.evaluate_rules_at(&cpu(95.0), at(50)).await.unwrap();
+        engine.evaluate_rules_at(&cpu(95.0), at(100)).await.unwrap();
+        assert!(matches!(engine.rule_state(&rule_id), Some(AlertState::Pending { .. })));
+        assert!(channel.events.lock().unwrap().is_empty());
+
+        engine.evaluate_rules_at(&cpu(96.0), at(110)).await.unwrap();
+        assert!(matches!(engine.rule_state(&rule_id), Some(AlertState::Firing { .. })));
+        assert_eq!(engine.total_alerts(), 1);
+
+        // Oscillating around the threshold stays firing without new notifications
+        for (seconds, value) in [(120, 89.0), (130, 91.0), (140, 88.0), (150, 92.0)] {
+            engine.evaluate_rules_at(&cpu(value), at(seconds)).await.unwrap();
+            assert!(matches!(engine.rule_state(&rule_id), Some(AlertState::Firing { .. })));
+        }
+
+        engine.evaluate_rules_at(&cpu(84.0), at(160)).await.unwrap();
+        assert_eq!(engine.rule_state(&rule_id), Some(AlertState::Inactive));
+        assert_eq!(engine.total_alerts(), 0);
+        assert_eq!(*channel.events.lock().unwrap(), vec!["firing", "resolved"]);
+
+        let history = engine.get_alert_history(10).await;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].resolved_at, Some(at(160)));
+    }
 }
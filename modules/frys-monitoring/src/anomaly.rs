@@ -0,0 +1,390 @@
+//! Statistical anomaly detection
+
+use crate::*;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+
+/// Anomaly detector configuration
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// Number of recent samples in the rolling baseline
+    pub window_size: usize,
+    /// Samples required before anything is flagged
+    pub warmup_samples: usize,
+    /// Absolute z-score above which a sample is anomalous
+    pub z_threshold: f64,
+    /// Consecutive anomalies after which the deviation is taken as a level
+    /// shift and the baseline restarts from them
+    pub adapt_after: usize,
+    /// Minimum time between anomaly alerts for the same metric (seconds)
+    pub alert_cooldown: u64,
+    /// Channels notified when an anomaly alert is raised
+    pub channels: Vec<String>,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            window_size: DEFAULT_ANOMALY_WINDOW,
+            warmup_samples: DEFAULT_ANOMALY_WARMUP,
+            z_threshold: DEFAULT_ANOMALY_Z_THRESHOLD,
+            adapt_after: DEFAULT_ANOMALY_ADAPT_AFTER,
+            alert_cooldown: DEFAULT_ANOMALY_ALERT_COOLDOWN,
+            channels: Vec::new(),
+        }
+    }
+}
+
+/// Flags metric samples that deviate from a rolling mean by more than a
+/// configured number of standard deviations
+pub struct AnomalyDetector {
+    /// Detector configuration
+    config: AnomalyConfig,
+    /// Rolling baselines by metric name
+    baselines: DashMap<String, Baseline>,
+    /// When an alert was last raised, by metric name
+    last_alerts: DashMap<String, DateTime<Utc>>,
+}
+
+impl AnomalyDetector {
+    /// Create a new anomaly detector
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            baselines: DashMap::new(),
+            last_alerts: DashMap::new(),
+        }
+    }
+
+    /// Get the detector configuration
+    pub fn config(&self) -> &AnomalyConfig {
+        &self.config
+    }
+
+    /// Record a sample, returning an anomaly if it deviates from the
+    /// baseline.
+    ///
+    /// Anomalous samples are not added to the baseline, so a spike does not
+    /// widen the band used to judge the samples after it. Once
+    /// `adapt_after` samples in a row are anomalous the metric is taken to
+    /// have shifted level: the baseline restarts from that run and warms up
+    /// again.
+    pub fn observe(&self, metric: &str, value: f64) -> Option<Anomaly> {
+        let mut baseline = self.baselines.entry(metric.to_string()).or_default();
+        let window_size = self.config.window_size.max(1);
+
+        if baseline.len() >= self.config.warmup_samples.max(2) {
+            let mean = baseline.mean();
+            let std_dev = baseline.std_dev();
+            let z_score = if std_dev > 0.0 {
+                (value - mean) / std_dev
+            } else if value == mean {
+                0.0
+            } else {
+                (value - mean).signum() * f64::INFINITY
+            };
+
+            if z_score.abs() > self.config.z_threshold {
+                baseline.anomalous_run.push(value);
+                if baseline.anomalous_run.len() >= self.config.adapt_after.max(1) {
+                    let run = ::core::mem::take(&mut baseline.anomalous_run);
+                    *baseline = Baseline::default();
+                    for sample in run {
+                        baseline.push(sample, window_size);
+                    }
+                }
+
+                return Some(Anomaly {
+                    metric: metric.to_string(),
+                    value,
+                    mean,
+                    std_dev,
+                    z_score,
+                    timestamp: Utc::now(),
+                });
+            }
+        }
+
+        baseline.anomalous_run.clear();
+        baseline.push(value, window_size);
+        None
+    }
+
+    /// Observe a batch of metrics and raise an alert for every anomaly.
+    ///
+    /// At most one alert per metric is raised within `alert_cooldown`;
+    /// every anomaly is still returned.
+    pub async fn detect(&self, metrics: &BTreeMap<String, f64>, alerts: &AlertingEngine) -> Vec<Anomaly> {
+        let anomalies: Vec<Anomaly> = metrics
+            .iter()
+            .filter_map(|(metric, value)| self.observe(metric, *value))
+            .collect();
+
+        let cooldown = chrono::Duration::seconds(self.config.alert_cooldown.min(i64::MAX as u64) as i64);
+        for anomaly in &anomalies {
+            let cooling_down = self
+                .last_alerts
+                .get(&anomaly.metric)
+                .is_some_and(|last| anomaly.timestamp.signed_duration_since(*last) < cooldown);
+            if cooling_down {
+                continue;
+            }
+            self.last_alerts.insert(anomaly.metric.clone(), anomaly.timestamp);
+            alerts.raise_alert(anomaly.to_alert(self.config.z_threshold), &self.config.channels).await;
+        }
+
+        anomalies
+    }
+
+    /// Number of samples in a metric's baseline
+    pub fn baseline_len(&self, metric: &str) -> usize {
+        self.baselines.get(metric).map_or(0, |b| b.len())
+    }
+
+    /// Discard a metric's baseline, restarting its warm-up
+    pub fn reset(&self, metric: &str) {
+        self.baselines.remove(metric);
+        self.last_alerts.remove(metric);
+    }
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new(AnomalyConfig::default())
+    }
+}
+
+/// A sample flagged as anomalous
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    pub metric: String,
+    pub value: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub z_score: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Anomaly {
+    /// Build an alert for this anomaly. Deviations of twice the threshold
+    /// or more are reported as high severity.
+    pub fn to_alert(&self, z_threshold: f64) -> Alert {
+        let severity = if self.z_score.abs() >= 2.0 * z_threshold {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+
+        let mut data = BTreeMap::new();
+        for (key, value) in [("value", self.value), ("mean", self.mean), ("std_dev", self.std_dev), ("z_score", self.z_score)] {
+            if let Some(number) = serde_json::Number::from_f64(value) {
+                data.insert(key.to_string(), serde_json::Value::Number(number));
+            }
+        }
+
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            type_: AlertType::Custom("anomaly".to_string()),
+            severity,
+            title: format!("Anomaly in {}", self.metric),
+            message: format!(
+                "{} = {} deviates from mean {:.3} (z-score {:.2})",
+                self.metric, self.value, self.mean, self.z_score
+            ),
+            source: "anomaly_detector".to_string(),
+            timestamp: self.timestamp,
+            acknowledged: false,
+            acknowledged_by: None,
+            acknowledged_at: None,
+            resolved: false,
+            resolved_at: None,
+            tags: vec!["anomaly".to_string()],
+            data,
+        }
+    }
+}
+
+/// Rolling window with Welford's running mean and sum of squared
+/// deviations, which stay accurate for large values with small variance
+#[derive(Debug, Default)]
+struct Baseline {
+    samples: VecDeque<f64>,
+    mean: f64,
+    m2: f64,
+    /// Anomalous samples seen since the last normal one
+    anomalous_run: Vec<f64>,
+}
+
+impl Baseline {
+    fn push(&mut self, value: f64, window_size: usize) {
+        self.samples.push_back(value);
+        let n = self.samples.len() as f64;
+        let delta = value - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (value - self.mean);
+
+        while self.samples.len() > window_size {
+            if let Some(old) = self.samples.pop_front() {
+                let n = self.samples.len() as f64;
+                let delta = old - self.mean;
+                self.mean -= delta / n;
+                self.m2 -= delta * (old - self.mean);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Sample standard deviation
+    fn std_dev(&self) -> f64 {
+        let n = self.samples.len() as f64;
+        // Removals can leave the sum slightly negative for constant series
+        (self.m2 / (n - 1.0)).max(0.0).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steady(i: usize) -> f64 {
+        100.0 + (i % 5) as f64
+    }
+
+    #[test]
+    fn test_only_spike_is_flagged() {
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            window_size: 20,
+            warmup_samples: 10,
+            z_threshold: 3.0,
+            ..Default::default()
+        });
+
+        let flagged: Vec<usize> = (0..60)
+            .filter(|&i| {
+                let value = if i == 40 { 150.0 } else { steady(i) };
+                detector.observe("latency_ms", value).is_some()
+            })
+            .collect();
+        assert_eq!(flagged, vec![40]);
+
+        let anomaly = detector.observe("latency_ms", 150.0).unwrap();
+        assert!(anomaly.z_score > 3.0);
+        assert!((anomaly.mean - 102.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_no_flags_during_warmup() {
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            window_size: 20,
+            warmup_samples: 10,
+            z_threshold: 3.0,
+            ..Default::default()
+        });
+
+        for i in 0..9 {
+            assert!(detector.observe("cpu", steady(i)).is_none());
+        }
+        // Still warming up, so even a spike joins the baseline
+        assert!(detector.observe("cpu", 500.0).is_none());
+        assert_eq!(detector.baseline_len("cpu"), 10);
+
+        detector.reset("cpu");
+        assert_eq!(detector.baseline_len("cpu"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_detect_raises_alert() {
+        let alerts = AlertingEngine::new(30);
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            window_size: 20,
+            warmup_samples: 5,
+            z_threshold: 3.0,
+            ..Default::default()
+        });
+
+        for i in 0..10 {
+            let metrics = BTreeMap::from([("errors".to_string(), steady(i))]);
+            assert!(detector.detect(&metrics, &alerts).await.is_empty());
+        }
+
+        let metrics = BTreeMap::from([("errors".to_string(), 1000.0)]);
+        let anomalies = detector.detect(&metrics, &alerts).await;
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(alerts.total_alerts(), 1);
+        assert!(matches!(alerts.get_active_alerts()[0].severity, AlertSeverity::High));
+    }
+
+    #[test]
+    fn test_level_shift_is_adapted() {
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            window_size: 20,
+            warmup_samples: 10,
+            z_threshold: 3.0,
+            adapt_after: 5,
+            ..Default::default()
+        });
+        for i in 0..20 {
+            detector.observe("rps", steady(i));
+        }
+
+        // Traffic doubles and stays there
+        let flagged = (0..30).filter(|&i| detector.observe("rps", 2.0 * steady(i)).is_some()).count();
+        assert_eq!(flagged, 5);
+        assert!(detector.observe("rps", 2.0 * steady(3)).is_none());
+        assert!(detector.observe("rps", 100.0).is_some());
+    }
+
+    #[test]
+    fn test_baseline_precision_for_large_values() {
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            window_size: 20,
+            warmup_samples: 10,
+            z_threshold: 3.0,
+            ..Default::default()
+        });
+        let offset = 1.0e9;
+        for i in 0..100 {
+            assert!(detector.observe("bytes", offset + (i % 5) as f64).is_none());
+        }
+
+        let anomaly = detector.observe("bytes", offset + 20.0).unwrap();
+        assert!((anomaly.mean - (offset + 2.0)).abs() < 1e-3);
+        assert!((anomaly.std_dev - 1.451_1).abs() < 1e-3);
+    }
+
+    #[tokio::test]
+    async fn test_alerts_deduplicated_per_metric() {
+        let alerts = AlertingEngine::new(30);
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            window_size: 20,
+            warmup_samples: 5,
+            z_threshold: 3.0,
+            alert_cooldown: 300,
+            ..Default::default()
+        });
+
+        for i in 0..10 {
+            let metrics = BTreeMap::from([("errors".to_string(), steady(i)), ("cpu".to_string(), steady(i))]);
+            detector.detect(&metrics, &alerts).await;
+        }
+        for _ in 0..3 {
+            let metrics = BTreeMap::from([("errors".to_string(), 1000.0)]);
+            assert_eq!(detector.detect(&metrics, &alerts).await.len(), 1);
+        }
+        assert_eq!(alerts.total_alerts(), 1);
+
+        let metrics = BTreeMap::from([("cpu".to_string(), 1000.0)]);
+        detector.detect(&metrics, &alerts).await;
+        assert_eq!(alerts.total_alerts(), 2);
+    }
+}
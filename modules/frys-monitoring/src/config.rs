@@ -29,6 +29,8 @@ pub struct MonitoringConfig {
     pub http_config: Option<HttpConfig>,
     /// Alerting configuration
     pub alerting_config: Option<AlertingConfig>,
+    /// Remote write configuration for pushing metrics
+    pub remote_write: Option<RemoteWriteConfig>,
 }
 
 impl Default for MonitoringConfig {
@@ -46,6 +48,7 @@ impl Default for MonitoringConfig {
             enable_compression: true,
             http_config: Some(HttpConfig::default()),
             alerting_config: Some(AlertingConfig::default()),
+            remote_write: None,
         }
     }
 }
@@ -166,6 +169,45 @@ pub struct WebhookConfig {
     pub retry_config: RetryConfig,
 }
 
+/// Remote write configuration for pushing metrics
+#[derive(Debug, Clone)]
+pub struct RemoteWriteConfig {
+    /// Endpoint URL metrics are posted to
+    pub url: String,
+    /// Wire protocol spoken by the endpoint
+    pub protocol: RemoteWriteProtocol,
+    /// Push interval in seconds
+    pub interval_seconds: u64,
+    /// HTTP headers to include, e.g. for authentication
+    pub headers: std::collections::HashMap<String, String>,
+    /// Request timeout in seconds
+    pub timeout_seconds: u64,
+    /// Retry configuration
+    pub retry_config: RetryConfig,
+}
+
+impl Default for RemoteWriteConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:9090/api/v1/write".to_string(),
+            protocol: RemoteWriteProtocol::PrometheusRemoteWrite,
+            interval_seconds: 15,
+            headers: std::collections::HashMap::new(),
+            timeout_seconds: 10,
+            retry_config: RetryConfig::default(),
+        }
+    }
+}
+
+/// Remote write wire protocols
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteWriteProtocol {
+    /// Prometheus remote write 1.0: snappy-compressed protobuf
+    PrometheusRemoteWrite,
+    /// OTLP over HTTP with JSON encoding
+    OtlpHttp,
+}
+
 /// Retry configuration
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
@@ -422,6 +464,11 @@ impl MonitoringConfigBuilder {
         self
     }
 
+    pub fn remote_write(mut self, config: RemoteWriteConfig) -> Self {
+        self.config.remote_write = Some(config);
+        self
+    }
+
     pub fn build(self) -> MonitoringConfig {
         self.config
     }
@@ -486,6 +533,18 @@ mod tests {
         assert_eq!(config.aggregation_window_minutes, 5);
     }
 
+    #[test]
+    fn test_remote_write_config_defaults() {
+        let config = MonitoringConfig::default();
+        assert!(config.remote_write.is_none());
+
+        let config = RemoteWriteConfig::default();
+        assert_eq!(config.url, "http://localhost:9090/api/v1/write");
+        assert_eq!(config.protocol, RemoteWriteProtocol::PrometheusRemoteWrite);
+        assert_eq!(config.interval_seconds, 15);
+        assert_eq!(config.retry_config.max_retries, 3);
+    }
+
     #[test]
     fn test_retry_config_defaults() {
         let config = RetryConfig::default();
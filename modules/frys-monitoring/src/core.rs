@@ -16,10 +16,12 @@ pub struct MonitoringSystem {
     metrics: MetricsRegistry,
     /// Alerting engine
     alerts: AlertingEngine,
+    /// Service level objectives
+    slos: SloTracker,
     /// Tracing system
     tracing: TracingSystem,
     /// Storage backend
-    storage: Box<dyn StorageBackend>,
+    storage: Box<dyn TimeSeriesStorage>,
     /// HTTP server for metrics endpoint
     http_server: Option<HttpServer>,
     /// Health check system
@@ -36,6 +38,7 @@ impl MonitoringSystem {
         let storage = Self::create_storage_backend(&config).await?;
         let metrics = MetricsRegistry::new(config.retention_days);
         let alerts = AlertingEngine::new(config.evaluation_interval);
+        let slos = SloTracker::new(SloConfig::default(), &metrics);
         let tracing = TracingSystem::new();
         let health_checks = HealthCheckSystem::new();
 
@@ -43,6 +46,7 @@ impl MonitoringSystem {
             config,
             metrics,
             alerts,
+            slos,
             tracing,
             storage,
             http_server: None,
@@ -64,13 +68,18 @@ impl MonitoringSystem {
         &self.alerts
     }
 
+    /// Get SLO tracker
+    pub fn slos(&self) -> &SloTracker {
+        &self.slos
+    }
+
     /// Get tracing system
     pub fn tracing(&self) -> &TracingSystem {
         &self.tracing
     }
 
     /// Get storage backend
-    pub fn storage(&self) -> &dyn StorageBackend {
+    pub fn storage(&self) -> &dyn TimeSeriesStorage {
         self.storage.as_ref()
     }
 
@@ -185,6 +194,34 @@ impl MonitoringSystem {
         output
     }
 
+    /// Evaluate every SLO against the current metrics, alerting on
+    /// predicted error budget exhaustion
+    pub async fn evaluate_slos(&self) -> Vec<SloStatus> {
+        self.slos.evaluate(&self.metrics, &self.alerts).await
+    }
+
+    /// Decide buffered traces once they have waited for their late spans,
+    /// until the returned future is dropped
+    pub async fn run_trace_sampling(&self) {
+        self.tracing.run().await;
+    }
+
+    /// Downsample stored metrics that have aged past the rollup policy
+    pub async fn rollup_storage(&self) -> Result<RollupStats> {
+        self.storage.rollup(Utc::now()).await
+    }
+
+    /// Push metrics to the configured remote write endpoint until the
+    /// returned future is dropped
+    #[cfg(feature = "remote_write")]
+    pub async fn run_remote_write(&self) -> Result<()> {
+        let config = self.config.remote_write.clone().ok_or_else(|| {
+            MonitoringError::FeatureNotEnabled("Remote write is not configured".to_string())
+        })?;
+        RemoteWriter::new(config)?.run(&self.metrics).await;
+        Ok(())
+    }
+
     /// Shutdown the monitoring system
     pub async fn shutdown(self) -> Result<()> {
         // Stop HTTP server
@@ -199,10 +236,10 @@ impl MonitoringSystem {
     }
 
     /// Create storage backend based on configuration
-    async fn create_storage_backend(config: &MonitoringConfig) -> Result<Box<dyn StorageBackend>> {
+    async fn create_storage_backend(config: &MonitoringConfig) -> Result<Box<dyn TimeSeriesStorage>> {
         match config.storage_backend {
             StorageBackend::Memory => {
-                Ok(Box::new(MemoryStorage::new()))
+                Ok(Box::new(MemoryStorage::with_rollup_policy(RollupPolicy::with_retention_days(config.retention_days))))
             }
             #[cfg(feature = "storage")]
             StorageBackend::RocksDB => {
@@ -71,11 +71,13 @@
 //!     // Create an alert rule
 //!     monitoring.alerts().create_rule(AlertRule {
 //!         name: "High Error Rate".to_string(),
-//!         condition: AlertCondition::GreaterThan {
+//!         condition: AlertCondition::Threshold {
 //!             metric: "error_rate".to_string(),
+//!             operator: AlertOperator::GreaterThan,
 //!             threshold: 0.05,
-//!             duration: Duration::from_secs(300),
 //!         },
+//!         for_duration: 300,
+//!         hysteresis: 0.01,
 //!         severity: AlertSeverity::Critical,
 //!         channels: vec![NotificationChannel::Email("admin@frys.io".to_string())],
 //!     }).await?;
@@ -105,8 +107,12 @@ mod alerts;
 mod tracing;
 mod dashboard;
 mod storage;
+mod anomaly;
+mod slo;
 mod api;
 mod config;
+#[cfg(feature = "remote_write")]
+mod remote_write;
 
 // Public API
 pub use core::*;
@@ -115,8 +121,12 @@ pub use alerts::*;
 pub use tracing::*;
 pub use dashboard::*;
 pub use storage::*;
+pub use anomaly::*;
+pub use slo::*;
 pub use api::*;
 pub use config::*;
+#[cfg(feature = "remote_write")]
+pub use remote_write::*;
 
 // Error types
 mod error;
@@ -135,6 +145,17 @@ pub const DEFAULT_SCRAPE_INTERVAL: u64 = 15; // seconds
 pub const DEFAULT_ALERT_EVALUATION_INTERVAL: u64 = 30; // seconds
 pub const MAX_METRICS_PER_REQUEST: usize = 10000;
 pub const MAX_ALERT_RULES: usize = 1000;
+pub const DEFAULT_ANOMALY_WINDOW: usize = 60;
+pub const DEFAULT_ANOMALY_WARMUP: usize = 30;
+pub const DEFAULT_ANOMALY_Z_THRESHOLD: f64 = 3.0;
+pub const DEFAULT_ANOMALY_ADAPT_AFTER: usize = 10;
+pub const DEFAULT_ANOMALY_ALERT_COOLDOWN: u64 = 300; // 5 minutes
+pub const DEFAULT_SLO_BURN_RATE_WINDOW: u64 = 3600; // seconds
+pub const DEFAULT_TRACE_LATENCY_THRESHOLD_MS: u64 = 1000;
+pub const DEFAULT_TRACE_DECISION_WAIT: u64 = 30; // seconds
+pub const DEFAULT_TRACE_FLUSH_INTERVAL: u64 = 1; // seconds
+pub const MAX_KEPT_TRACES: usize = 10000;
+pub const DEFAULT_HISTOGRAM_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
 
 #[cfg(test)]
 mod tests {
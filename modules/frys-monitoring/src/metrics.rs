@@ -6,8 +6,8 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 /// Metrics registry for managing all metrics
 pub struct MetricsRegistry {
@@ -19,6 +19,8 @@ pub struct MetricsRegistry {
     retention_days: u32,
     /// Global labels
     global_labels: BTreeMap<String, String>,
+    /// When the registry was created, the start of every cumulative count
+    created_at: DateTime<Utc>,
 }
 
 impl MetricsRegistry {
@@ -29,6 +31,7 @@ impl MetricsRegistry {
             metadata: DashMap::new(),
             retention_days,
             global_labels: BTreeMap::new(),
+            created_at: Utc::now(),
         }
     }
 
@@ -46,15 +49,16 @@ impl MetricsRegistry {
         gauge
     }
 
-    /// Register a histogram metric
+    /// Register a histogram metric. An empty bucket list selects the
+    /// default Prometheus buckets.
     pub fn register_histogram(
         &self,
         name: &str,
         help: &str,
         labels: &[&str],
-        buckets: &[f64],
+        buckets: Vec<f64>,
     ) -> Histogram {
-        let histogram = Histogram::new(name, labels, buckets);
+        let histogram = Histogram::new(name, labels, &buckets);
         self.register_metric(name, help, MetricType::Histogram, labels, Box::new(histogram.clone()));
         histogram
     }
@@ -90,11 +94,45 @@ impl MetricsRegistry {
         self.metrics.len() as u64
     }
 
+    /// Number of distinct label combinations recorded by each metric
+    pub fn cardinality(&self) -> BTreeMap<String, usize> {
+        self.metrics
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().cardinality()))
+            .collect()
+    }
+
     /// Set global labels
     pub fn set_global_labels(&mut self, labels: BTreeMap<String, String>) {
         self.global_labels = labels;
     }
 
+    /// Labels attached to every exported series
+    pub fn global_labels(&self) -> &BTreeMap<String, String> {
+        &self.global_labels
+    }
+
+    /// When the registry was created. Counters and histograms count from
+    /// this time on.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Current value of every series, with the global labels added to
+    /// series that do not set them
+    pub fn samples(&self) -> Vec<SeriesSample> {
+        let mut samples = Vec::new();
+        for entry in self.metrics.iter() {
+            for mut sample in entry.value().samples() {
+                for (label, value) in &self.global_labels {
+                    sample.labels.entry(label.clone()).or_insert_with(|| value.clone());
+                }
+                samples.push(sample);
+            }
+        }
+        samples
+    }
+
     /// Get metrics in Prometheus format
     pub fn prometheus_format(&self) -> String {
         let mut output = String::new();
@@ -118,6 +156,35 @@ impl MetricsRegistry {
         output
     }
 
+    /// Export all metrics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        self.prometheus_format()
+    }
+
+    /// Export all metrics in the OpenMetrics text format, which unlike the
+    /// Prometheus format carries histogram exemplars. Serve it as
+    /// `application/openmetrics-text; version=1.0.0`.
+    pub fn to_openmetrics(&self) -> String {
+        let mut output = String::new();
+
+        for entry in self.metrics.iter() {
+            let name = entry.key();
+            if let Some(metadata) = self.metadata.get(name) {
+                // Counter families are named without their `_total` suffix
+                let family = match metadata.metric_type {
+                    MetricType::Counter => name.strip_suffix("_total").unwrap_or(name),
+                    _ => name,
+                };
+                output.push_str(&format!("# HELP {} {}\n", family, metadata.help));
+                output.push_str(&format!("# TYPE {} {}\n", family, metadata.metric_type.to_string()));
+            }
+            output.push_str(&entry.value().openmetrics_format());
+        }
+
+        output.push_str("# EOF\n");
+        output
+    }
+
     /// Clean up old metrics data (placeholder)
     pub async fn cleanup_old_data(&self) -> Result<()> {
         // In a real implementation, this would remove data older than retention_days
@@ -155,7 +222,7 @@ pub struct MetricMetadata {
 }
 
 /// Metric types
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MetricType {
     Counter,
     Gauge,
@@ -177,17 +244,74 @@ impl ToString for MetricType {
 /// Metric trait
 pub trait Metric: Send + Sync + dyn_clone::DynClone {
     fn prometheus_format(&self) -> String;
+
+    /// Series in the OpenMetrics text format, without the family's
+    /// `# HELP`/`# TYPE` lines
+    fn openmetrics_format(&self) -> String {
+        self.prometheus_format()
+    }
+
     fn metric_type(&self) -> MetricType;
     fn name(&self) -> &str;
+
+    /// Number of distinct label combinations recorded; an unlabeled metric
+    /// is a single series
+    fn cardinality(&self) -> usize {
+        1
+    }
+
+    /// Current value of the series with the given labels, for metrics
+    /// holding one number per series
+    fn value(&self, _label_values: &[(&str, &str)]) -> Option<f64> {
+        None
+    }
+
+    /// Current value of every series, flattening histograms and summaries
+    /// into their `_bucket`, `_sum` and `_count` series
+    fn samples(&self) -> Vec<SeriesSample> {
+        Vec::new()
+    }
+}
+
+/// Current value of one flattened series, e.g. a single histogram bucket
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesSample {
+    /// Series name, including any `_bucket`/`_sum`/`_count` suffix
+    pub name: String,
+    /// Type of the metric family the series belongs to, if declared
+    pub metric_type: Option<MetricType>,
+    /// Series labels
+    pub labels: BTreeMap<String, String>,
+    /// Current value
+    pub value: f64,
 }
 
+impl SeriesSample {
+    fn new(name: String, metric_type: MetricType, names: &[String], values: &[String], value: f64) -> Self {
+        Self {
+            name,
+            metric_type: Some(metric_type),
+            labels: names.iter().cloned().zip(values.iter().cloned()).collect(),
+            value,
+        }
+    }
+}
+
+/// Label value of the series collecting label combinations past a
+/// counter's cardinality limit
+pub const OVERFLOW_LABEL_VALUE: &str = "__overflow__";
+
 /// Counter metric
 #[derive(Debug, Clone)]
 pub struct Counter {
     name: String,
     labels: Vec<String>,
     value: Arc<AtomicU64>,
-    label_values: DashMap<Vec<String>, Arc<AtomicU64>>,
+    label_values: Arc<DashMap<Vec<String>, Arc<AtomicU64>>>,
+    /// Most label combinations kept before new ones overflow
+    cardinality_limit: Arc<AtomicUsize>,
+    /// Whether the overflow warning has been logged
+    overflow_warned: Arc<AtomicBool>,
 }
 
 impl Counter {
@@ -196,27 +320,25 @@ impl Counter {
             name: name.to_string(),
             labels: labels.iter().map(|s| s.to_string()).collect(),
             value: Arc::new(AtomicU64::new(0)),
-            label_values: DashMap::new(),
+            label_values: Arc::new(DashMap::new()),
+            cardinality_limit: Arc::new(AtomicUsize::new(usize::MAX)),
+            overflow_warned: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub fn inc(&self, label_values: &[(&str, &str)]) {
-        if self.labels.is_empty() {
-            self.value.fetch_add(1, Ordering::Relaxed);
-        } else {
-            let key = self.labels.iter()
-                .map(|label| {
-                    label_values.iter()
-                        .find(|(k, _)| k == label)
-                        .map(|(_, v)| v.to_string())
-                        .unwrap_or_default()
-                })
-                .collect::<Vec<_>>();
+    /// Keep at most `limit` label combinations. Further combinations are
+    /// counted in a single series with every label set to
+    /// [`OVERFLOW_LABEL_VALUE`], bounding the memory the counter uses.
+    ///
+    /// The limit is shared by all clones of the counter, including the one
+    /// held by the registry.
+    pub fn with_cardinality_limit(self, limit: usize) -> Self {
+        self.cardinality_limit.store(limit, Ordering::Relaxed);
+        self
+    }
 
-            let counter = self.label_values.entry(key)
-                .or_insert_with(|| Arc::new(AtomicU64::new(0)));
-            counter.fetch_add(1, Ordering::Relaxed);
-        }
+    pub fn inc(&self, label_values: &[(&str, &str)]) {
+        self.add(1, label_values);
     }
 
     pub fn add(&self, value: u64, label_values: &[(&str, &str)]) {
@@ -232,10 +354,34 @@ impl Counter {
                 })
                 .collect::<Vec<_>>();
 
-            let counter = self.label_values.entry(key)
-                .or_insert_with(|| Arc::new(AtomicU64::new(0)));
-            counter.fetch_add(value, Ordering::Relaxed);
+            self.series(key).fetch_add(value, Ordering::Relaxed);
+        }
+    }
+
+    /// Series of label combination `key`, or the overflow series if `key`
+    /// is new and the counter is at its cardinality limit
+    fn series(&self, key: Vec<String>) -> Arc<AtomicU64> {
+        if let Some(counter) = self.label_values.get(&key) {
+            return counter.clone();
         }
+
+        let key = if self.label_values.len() >= self.cardinality_limit.load(Ordering::Relaxed) {
+            if !self.overflow_warned.swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "Metric {} exceeded {} label combinations, counting new ones as {}",
+                    self.name,
+                    self.cardinality_limit.load(Ordering::Relaxed),
+                    OVERFLOW_LABEL_VALUE
+                );
+            }
+            vec![OVERFLOW_LABEL_VALUE.to_string(); self.labels.len()]
+        } else {
+            key
+        };
+        self.label_values
+            .entry(key)
+            .or_insert_with(|| Arc::new(AtomicU64::new(0)))
+            .clone()
     }
 
     pub fn get(&self, label_values: &[(&str, &str)]) -> u64 {
@@ -258,12 +404,13 @@ impl Counter {
     }
 }
 
-impl Metric for Counter {
-    fn prometheus_format(&self) -> String {
+impl Counter {
+    /// Series in the text exposition format, named `name`
+    fn format(&self, name: &str) -> String {
         let mut output = String::new();
 
         if self.labels.is_empty() {
-            output.push_str(&format!("{} {}\n", self.name, self.value.load(Ordering::Relaxed)));
+            output.push_str(&format!("{} {}\n", name, self.value.load(Ordering::Relaxed)));
         } else {
             for entry in self.label_values.iter() {
                 let labels_str = entry.key().iter()
@@ -274,7 +421,7 @@ impl Metric for Counter {
 
                 output.push_str(&format!(
                     "{}{{{}}} {}\n",
-                    self.name,
+                    name,
                     labels_str,
                     entry.value().load(Ordering::Relaxed)
                 ));
@@ -283,6 +430,21 @@ impl Metric for Counter {
 
         output
     }
+}
+
+impl Metric for Counter {
+    fn prometheus_format(&self) -> String {
+        self.format(&self.name)
+    }
+
+    /// OpenMetrics counter series always end in `_total`
+    fn openmetrics_format(&self) -> String {
+        if self.name.ends_with("_total") {
+            self.format(&self.name)
+        } else {
+            self.format(&format!("{}_total", self.name))
+        }
+    }
 
     fn metric_type(&self) -> MetricType {
         MetricType::Counter
@@ -291,6 +453,32 @@ impl Metric for Counter {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn cardinality(&self) -> usize {
+        if self.labels.is_empty() {
+            1
+        } else {
+            self.label_values.len()
+        }
+    }
+
+    fn value(&self, label_values: &[(&str, &str)]) -> Option<f64> {
+        Some(self.get(label_values) as f64)
+    }
+
+    fn samples(&self) -> Vec<SeriesSample> {
+        if self.labels.is_empty() {
+            let value = self.value.load(Ordering::Relaxed) as f64;
+            return vec![SeriesSample::new(self.name.clone(), MetricType::Counter, &[], &[], value)];
+        }
+        self.label_values
+            .iter()
+            .map(|entry| {
+                let value = entry.value().load(Ordering::Relaxed) as f64;
+                SeriesSample::new(self.name.clone(), MetricType::Counter, &self.labels, entry.key(), value)
+            })
+            .collect()
+    }
 }
 
 /// Gauge metric
@@ -299,7 +487,7 @@ pub struct Gauge {
     name: String,
     labels: Vec<String>,
     value: Arc<AtomicU64>,
-    label_values: DashMap<Vec<String>, Arc<AtomicU64>>,
+    label_values: Arc<DashMap<Vec<String>, Arc<AtomicU64>>>,
 }
 
 impl Gauge {
@@ -308,7 +496,7 @@ impl Gauge {
             name: name.to_string(),
             labels: labels.iter().map(|s| s.to_string()).collect(),
             value: Arc::new(AtomicU64::new(0)),
-            label_values: DashMap::new(),
+            label_values: Arc::new(DashMap::new()),
         }
     }
 
@@ -428,54 +616,223 @@ impl Metric for Gauge {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn cardinality(&self) -> usize {
+        if self.labels.is_empty() {
+            1
+        } else {
+            self.label_values.len()
+        }
+    }
+
+    fn value(&self, label_values: &[(&str, &str)]) -> Option<f64> {
+        Some(self.get(label_values))
+    }
+
+    fn samples(&self) -> Vec<SeriesSample> {
+        if self.labels.is_empty() {
+            let value = self.value.load(Ordering::Relaxed) as f64 / 1000.0;
+            return vec![SeriesSample::new(self.name.clone(), MetricType::Gauge, &[], &[], value)];
+        }
+        self.label_values
+            .iter()
+            .map(|entry| {
+                let value = entry.value().load(Ordering::Relaxed) as f64 / 1000.0;
+                SeriesSample::new(self.name.clone(), MetricType::Gauge, &self.labels, entry.key(), value)
+            })
+            .collect()
+    }
 }
 
-/// Histogram metric (simplified implementation)
+/// Histogram metric with cumulative buckets
 #[derive(Debug, Clone)]
 pub struct Histogram {
     name: String,
     labels: Vec<String>,
+    /// Sorted finite upper bounds; `+Inf` is implicit
     buckets: Vec<f64>,
-    count: Arc<AtomicU64>,
-    sum: Arc<AtomicU64>,
+    series: Arc<DashMap<Vec<String>, Arc<HistogramSeries>>>,
+}
+
+/// Observations of a histogram for one label set
+#[derive(Debug)]
+struct HistogramSeries {
+    /// Per-bucket (non-cumulative) counts, with a final `+Inf` bucket
+    bucket_counts: Vec<AtomicU64>,
+    count: AtomicU64,
+    /// Sum of observations, stored as `f64` bits
+    sum: AtomicU64,
+    /// Most recent exemplar of each bucket, with a final `+Inf` bucket
+    exemplars: Vec<Mutex<Option<Exemplar>>>,
+}
+
+/// Sample observation linking a histogram bucket to a trace
+#[derive(Debug, Clone)]
+struct Exemplar {
+    trace_id: String,
+    value: f64,
+    timestamp: DateTime<Utc>,
+}
+
+impl HistogramSeries {
+    fn new(buckets: usize) -> Self {
+        Self {
+            bucket_counts: (0..=buckets).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0f64.to_bits()),
+            exemplars: (0..=buckets).map(|_| Mutex::new(None)).collect(),
+        }
+    }
+
+    fn exemplar(&self, bucket: usize) -> Option<Exemplar> {
+        self.exemplars[bucket].lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn sum(&self) -> f64 {
+        f64::from_bits(self.sum.load(Ordering::Relaxed))
+    }
 }
 
 impl Histogram {
     pub fn new(name: &str, labels: &[&str], buckets: &[f64]) -> Self {
+        let mut buckets: Vec<f64> = if buckets.is_empty() {
+            DEFAULT_HISTOGRAM_BUCKETS.to_vec()
+        } else {
+            buckets.iter().copied().filter(|b| b.is_finite()).collect()
+        };
+        buckets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::core::cmp::Ordering::Equal));
+        buckets.dedup();
+
         Self {
             name: name.to_string(),
             labels: labels.iter().map(|s| s.to_string()).collect(),
-            buckets: buckets.to_vec(),
-            count: Arc::new(AtomicU64::new(0)),
-            sum: Arc::new(AtomicU64::new(0)),
+            buckets,
+            series: Arc::new(DashMap::new()),
         }
     }
 
-    pub fn observe(&self, value: f64, _label_values: &[(&str, &str)]) {
-        // Simplified implementation - in a real histogram,
-        // this would update bucket counts
-        self.count.fetch_add(1, Ordering::Relaxed);
-        let int_value = (value * 1000.0) as u64;
-        self.sum.fetch_add(int_value, Ordering::Relaxed);
+    /// Finite bucket upper bounds
+    pub fn buckets(&self) -> &[f64] {
+        &self.buckets
+    }
+
+    pub fn observe(&self, value: f64, label_values: &[(&str, &str)]) {
+        self.record(value, label_values);
+    }
+
+    /// Observe a value and keep `trace_id` as the exemplar of its bucket,
+    /// replacing the bucket's previous exemplar. Exemplars are exported by
+    /// [`Metric::openmetrics_format`] only, as the Prometheus text format
+    /// does not allow them.
+    pub fn observe_with_exemplar(&self, value: f64, trace_id: &str, label_values: &[(&str, &str)]) {
+        let (series, bucket) = self.record(value, label_values);
+        *series.exemplars[bucket].lock().unwrap_or_else(|e| e.into_inner()) = Some(Exemplar {
+            trace_id: trace_id.to_string(),
+            value,
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Count an observation, returning its series and bucket index
+    fn record(&self, value: f64, label_values: &[(&str, &str)]) -> (Arc<HistogramSeries>, usize) {
+        let key = self.label_key(label_values);
+        let series = self.series.entry(key)
+            .or_insert_with(|| Arc::new(HistogramSeries::new(self.buckets.len())))
+            .clone();
+
+        let bucket = self.buckets.iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.buckets.len());
+        series.bucket_counts[bucket].fetch_add(1, Ordering::Relaxed);
+        series.count.fetch_add(1, Ordering::Relaxed);
+
+        let mut current = series.sum.load(Ordering::Relaxed);
+        loop {
+            let updated = (f64::from_bits(current) + value).to_bits();
+            match series.sum.compare_exchange_weak(current, updated, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        (series, bucket)
+    }
+
+    pub fn get_count(&self, label_values: &[(&str, &str)]) -> u64 {
+        self.series.get(&self.label_key(label_values))
+            .map(|s| s.count.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    pub fn get_sum(&self, label_values: &[(&str, &str)]) -> f64 {
+        self.series.get(&self.label_key(label_values))
+            .map(|s| s.sum())
+            .unwrap_or(0.0)
+    }
+
+    fn label_key(&self, label_values: &[(&str, &str)]) -> Vec<String> {
+        self.labels.iter()
+            .map(|label| {
+                label_values.iter()
+                    .find(|(k, _)| k == label)
+                    .map(|(_, v)| v.to_string())
+                    .unwrap_or_default()
+            })
+            .collect()
     }
 }
 
-impl Metric for Histogram {
-    fn prometheus_format(&self) -> String {
+impl Histogram {
+    /// Series in the text exposition format, with the bucket exemplars if
+    /// `exemplars` is set
+    fn format(&self, exemplars: bool) -> String {
         let mut output = String::new();
-        let count = self.count.load(Ordering::Relaxed);
-        let sum = self.sum.load(Ordering::Relaxed) as f64 / 1000.0;
 
-        output.push_str(&format!("{}_count {}\n", self.name, count));
-        output.push_str(&format!("{}_sum {}\n", self.name, sum));
+        for entry in self.series.iter() {
+            let labels: Vec<String> = entry.key().iter()
+                .zip(self.labels.iter())
+                .map(|(value, label)| format!("{}=\"{}\"", label, value))
+                .collect();
+            let series = entry.value();
+
+            let mut cumulative = 0;
+            let bounds = self.buckets.iter().map(|b| b.to_string()).chain(::core::iter::once("+Inf".to_string()));
+            for (bucket, (bound, bucket_count)) in bounds.zip(series.bucket_counts.iter()).enumerate() {
+                cumulative += bucket_count.load(Ordering::Relaxed);
+                let mut bucket_labels = labels.clone();
+                bucket_labels.push(format!("le=\"{}\"", bound));
+                output.push_str(&format!("{}_bucket{{{}}} {}", self.name, bucket_labels.join(","), cumulative));
+                if let Some(exemplar) = series.exemplar(bucket).filter(|_| exemplars) {
+                    output.push_str(&format!(
+                        " # {{trace_id=\"{}\"}} {} {:.3}",
+                        escape_label_value(&exemplar.trace_id),
+                        exemplar.value,
+                        exemplar.timestamp.timestamp_millis() as f64 / 1000.0
+                    ));
+                }
+                output.push('\n');
+            }
 
-        // Simplified bucket output
-        for bucket in &self.buckets {
-            output.push_str(&format!("{}_bucket{{le=\"{}\"}} {}\n", self.name, bucket, count));
+            let labels_str = if labels.is_empty() {
+                String::new()
+            } else {
+                format!("{{{}}}", labels.join(","))
+            };
+            output.push_str(&format!("{}_sum{} {}\n", self.name, labels_str, series.sum()));
+            output.push_str(&format!("{}_count{} {}\n", self.name, labels_str, series.count.load(Ordering::Relaxed)));
         }
 
         output
     }
+}
+
+impl Metric for Histogram {
+    fn prometheus_format(&self) -> String {
+        self.format(false)
+    }
+
+    fn openmetrics_format(&self) -> String {
+        self.format(true)
+    }
 
     fn metric_type(&self) -> MetricType {
         MetricType::Histogram
@@ -484,6 +841,36 @@ impl Metric for Histogram {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn cardinality(&self) -> usize {
+        if self.labels.is_empty() {
+            1
+        } else {
+            self.series.len()
+        }
+    }
+
+    fn samples(&self) -> Vec<SeriesSample> {
+        let mut samples = Vec::new();
+        let sample = |suffix: &str, values: &[String], value| {
+            SeriesSample::new(format!("{}{}", self.name, suffix), MetricType::Histogram, &self.labels, values, value)
+        };
+
+        for entry in self.series.iter() {
+            let series = entry.value();
+            let mut cumulative = 0;
+            let bounds = self.buckets.iter().map(|b| b.to_string()).chain(::core::iter::once("+Inf".to_string()));
+            for (bound, bucket_count) in bounds.zip(series.bucket_counts.iter()) {
+                cumulative += bucket_count.load(Ordering::Relaxed);
+                let mut bucket = sample("_bucket", entry.key(), cumulative as f64);
+                bucket.labels.insert("le".to_string(), bound);
+                samples.push(bucket);
+            }
+            samples.push(sample("_sum", entry.key(), series.sum()));
+            samples.push(sample("_count", entry.key(), series.count.load(Ordering::Relaxed) as f64));
+        }
+        samples
+    }
 }
 
 /// Summary metric (simplified implementation)
@@ -539,10 +926,24 @@ impl Metric for Summary {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn samples(&self) -> Vec<SeriesSample> {
+        let count = self.count.load(Ordering::Relaxed) as f64;
+        let sum = self.sum.load(Ordering::Relaxed) as f64 / 1000.0;
+        vec![
+            SeriesSample::new(format!("{}_count", self.name), MetricType::Summary, &[], &[], count),
+            SeriesSample::new(format!("{}_sum", self.name), MetricType::Summary, &[], &[], sum),
+        ]
+    }
 }
 
 dyn_clone::clone_trait_object!(Metric);
 
+/// Escape a label value for the text exposition formats
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -559,6 +960,26 @@ mod tests {
         assert_eq!(counter.get(&[("method", "POST")]), 1);
     }
 
+    #[test]
+    fn test_counter_cardinality_limit() {
+        let registry = MetricsRegistry::new(30);
+        let counter = registry
+            .register_counter("requests_by_user", "Requests by user", &["user"])
+            .with_cardinality_limit(3);
+
+        for i in 0..10 {
+            counter.inc(&[("user", &format!("user-{}", i))]);
+        }
+        counter.inc(&[("user", "user-0")]);
+
+        assert_eq!(counter.get(&[("user", "user-0")]), 2);
+        assert_eq!(counter.get(&[("user", "user-5")]), 0);
+        assert_eq!(counter.get(&[("user", OVERFLOW_LABEL_VALUE)]), 7);
+        // The limited series plus the overflow series
+        assert_eq!(counter.cardinality(), 4);
+        assert_eq!(registry.cardinality().get("requests_by_user"), Some(&4));
+    }
+
     #[test]
     fn test_gauge() {
         let gauge = Gauge::new("test_gauge", &["service"]);
@@ -584,6 +1005,90 @@ mod tests {
         assert!(registry.get_metric("requests_total").is_some());
     }
 
+    #[test]
+    fn test_histogram_prometheus_export() {
+        let registry = MetricsRegistry::new(30);
+        let histogram = registry.register_histogram(
+            "request_latency_seconds",
+            "Request latency",
+            &["route"],
+            vec![1.0, 0.1, 0.5],
+        );
+
+        for value in [0.05, 0.1, 0.3, 0.7, 2.0] {
+            histogram.observe(value, &[("route", "/api")]);
+        }
+
+        assert_eq!(histogram.buckets(), &[0.1, 0.5, 1.0]);
+        assert_eq!(histogram.get_count(&[("route", "/api")]), 5);
+        assert!((histogram.get_sum(&[("route", "/api")]) - 3.15).abs() < 1e-9);
+
+        let output = registry.to_prometheus();
+        assert!(output.contains("# TYPE request_latency_seconds histogram"));
+        assert!(output.contains("request_latency_seconds_bucket{route=\"/api\",le=\"0.1\"} 2\n"));
+        assert!(output.contains("request_latency_seconds_bucket{route=\"/api\",le=\"0.5\"} 3\n"));
+        assert!(output.contains("request_latency_seconds_bucket{route=\"/api\",le=\"1\"} 4\n"));
+        assert!(output.contains("request_latency_seconds_bucket{route=\"/api\",le=\"+Inf\"} 5\n"));
+        assert!(output.contains("request_latency_seconds_sum{route=\"/api\"} 3.15"));
+        assert!(output.contains("request_latency_seconds_count{route=\"/api\"} 5\n"));
+    }
+
+    #[test]
+    fn test_histogram_exemplars() {
+        let histogram = Histogram::new("request_latency_seconds", &["route"], &[0.1, 0.5, 1.0]);
+        histogram.observe_with_exemplar(0.3, "4bf92f3577b34da6", &[("route", "/api")]);
+        histogram.observe_with_exemplar(0.4, "a3ce929d0e0e4736", &[("route", "/api")]);
+        histogram.observe(0.05, &[("route", "/api")]);
+
+        // The Prometheus text format does not allow exemplars
+        assert!(!histogram.prometheus_format().contains(" # "));
+
+        let output = histogram.openmetrics_format();
+        let line = |le: &str| {
+            let prefix = format!("request_latency_seconds_bucket{{route=\"/api\",le=\"{}\"}} ", le);
+            output.lines().find(|line| line.starts_with(&prefix)).unwrap().to_string()
+        };
+
+        // Only the most recent exemplar of the 0.5 bucket is kept
+        let bucket = line("0.5");
+        assert!(bucket.starts_with("request_latency_seconds_bucket{route=\"/api\",le=\"0.5\"} 3 # {trace_id=\"a3ce929d0e0e4736\"} 0.4 "));
+        let timestamp: f64 = bucket.rsplit(' ').next().unwrap().parse().unwrap();
+        assert!(timestamp > 0.0);
+
+        assert_eq!(line("0.1"), "request_latency_seconds_bucket{route=\"/api\",le=\"0.1\"} 1");
+        assert_eq!(line("1"), "request_latency_seconds_bucket{route=\"/api\",le=\"1\"} 3");
+        assert!(!output.contains("4bf92f3577b34da6"));
+    }
+
+    #[test]
+    fn test_openmetrics_format() {
+        let registry = MetricsRegistry::new(30);
+        registry.register_counter("http_requests_total", "Requests", &[]).inc(&[]);
+        registry.register_counter("jobs", "Jobs", &[]).add(2, &[]);
+        let histogram = registry.register_histogram("latency_seconds", "Latency", &[], vec![1.0]);
+        histogram.observe_with_exemplar(0.5, "a\"b\\c", &[]);
+
+        let output = registry.to_openmetrics();
+        assert!(output.contains("# TYPE http_requests counter\nhttp_requests_total 1\n"));
+        assert!(output.contains("# TYPE jobs counter\njobs_total 2\n"));
+        assert!(output.contains("latency_seconds_bucket{le=\"1\"} 1 # {trace_id=\"a\\\"b\\\\c\"} 0.5 "));
+        assert!(output.ends_with("# EOF\n"));
+        assert!(!registry.to_prometheus().contains("trace_id"));
+    }
+
+    #[test]
+    fn test_histogram_default_buckets() {
+        let histogram = Histogram::new("latency", &[], &[]);
+        histogram.observe(0.02, &[]);
+
+        assert_eq!(histogram.buckets(), &DEFAULT_HISTOGRAM_BUCKETS);
+        let output = histogram.prometheus_format();
+        assert!(output.contains("latency_bucket{le=\"0.01\"} 0\n"));
+        assert!(output.contains("latency_bucket{le=\"0.025\"} 1\n"));
+        assert!(output.contains("latency_bucket{le=\"10\"} 1\n"));
+        assert!(output.contains("latency_count 1\n"));
+    }
+
     #[test]
     fn test_prometheus_format() {
         let registry = MetricsRegistry::new(30);
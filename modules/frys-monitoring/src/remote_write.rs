@@ -0,0 +1,461 @@
+//! Push-based metrics export
+//!
+//! Where the scrape endpoint cannot be reached, a [`RemoteWriter`]
+//! periodically serializes everything in a [`MetricsRegistry`] and posts it
+//! to a Prometheus remote write or OTLP/HTTP receiver.
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use chrono::{DateTime, Utc};
+use prost::Message;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Prometheus remote write 1.0 protobuf messages
+pub mod prompb {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    /// Body of a remote write request
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WriteRequest {
+        /// Series being written
+        #[prost(message, repeated, tag = "1")]
+        pub timeseries: Vec<TimeSeries>,
+    }
+
+    /// Samples of one series, identified by its sorted labels
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TimeSeries {
+        /// Labels sorted by name, including `__name__`
+        #[prost(message, repeated, tag = "1")]
+        pub labels: Vec<Label>,
+        /// Samples in timestamp order
+        #[prost(message, repeated, tag = "2")]
+        pub samples: Vec<Sample>,
+    }
+
+    /// Label name/value pair
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Label {
+        /// Label name
+        #[prost(string, tag = "1")]
+        pub name: String,
+        /// Label value
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    /// Sample value
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sample {
+        /// Sample value
+        #[prost(double, tag = "1")]
+        pub value: f64,
+        /// Milliseconds since the Unix epoch
+        #[prost(int64, tag = "2")]
+        pub timestamp: i64,
+    }
+}
+
+/// Read the current value of every series in `registry`
+pub fn collect_samples(registry: &MetricsRegistry) -> Vec<SeriesSample> {
+    registry.samples()
+}
+
+/// Encode samples as a snappy-compressed Prometheus remote write request
+pub fn encode_remote_write(samples: &[SeriesSample], timestamp: DateTime<Utc>) -> Result<Vec<u8>> {
+    let timeseries = samples
+        .iter()
+        .map(|sample| {
+            // Remote write requires labels sorted by name
+            let mut labels = BTreeMap::new();
+            labels.insert("__name__", sample.name.as_str());
+            labels.extend(sample.labels.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+            prompb::TimeSeries {
+                labels: labels
+                    .into_iter()
+                    .map(|(name, value)| prompb::Label {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })
+                    .collect(),
+                samples: vec![prompb::Sample {
+                    value: sample.value,
+                    timestamp: timestamp.timestamp_millis(),
+                }],
+            }
+        })
+        .collect();
+
+    let request = prompb::WriteRequest { timeseries }.encode_to_vec();
+    snap::raw::Encoder::new()
+        .compress_vec(&request)
+        .map_err(|e| MonitoringError::SerializationError(format!("snappy compression failed: {}", e)))
+}
+
+/// Encode samples as an OTLP/HTTP JSON export request.
+///
+/// Counters become monotonic cumulative sums counting from `start_time`;
+/// every other series, including the flattened components of histograms
+/// and summaries, becomes a gauge.
+pub fn encode_otlp(samples: &[SeriesSample], start_time: DateTime<Utc>, timestamp: DateTime<Utc>) -> Result<Vec<u8>> {
+    let unix_nano = |time: DateTime<Utc>| time.timestamp_nanos_opt().unwrap_or_default().to_string();
+    let start_time_unix_nano = unix_nano(start_time);
+    let time_unix_nano = unix_nano(timestamp);
+
+    let mut families: BTreeMap<&str, Vec<serde_json::Value>> = BTreeMap::new();
+    let mut monotonic = BTreeMap::new();
+    for sample in samples {
+        let attributes: Vec<_> = sample
+            .labels
+            .iter()
+            .map(|(key, value)| serde_json::json!({ "key": key, "value": { "stringValue": value } }))
+            .collect();
+        let is_counter = sample.metric_type == Some(MetricType::Counter);
+        let mut point = serde_json::json!({
+            "attributes": attributes,
+            "timeUnixNano": time_unix_nano,
+            "asDouble": sample.value,
+        });
+        if is_counter {
+            point["startTimeUnixNano"] = serde_json::json!(start_time_unix_nano);
+        }
+        families.entry(&sample.name).or_default().push(point);
+        monotonic.insert(sample.name.as_str(), is_counter);
+    }
+
+    let metrics: Vec<_> = families
+        .into_iter()
+        .map(|(name, data_points)| {
+            if monotonic[name] {
+                serde_json::json!({
+                    "name": name,
+                    "sum": {
+                        "dataPoints": data_points,
+                        "aggregationTemporality": 2,
+                        "isMonotonic": true,
+                    },
+                })
+            } else {
+                serde_json::json!({ "name": name, "gauge": { "dataPoints": data_points } })
+            }
+        })
+        .collect();
+
+    let request = serde_json::json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": [] },
+            "scopeMetrics": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics,
+            }],
+        }],
+    });
+    serde_json::to_vec(&request).map_err(|e| MonitoringError::SerializationError(e.to_string()))
+}
+
+/// Counters of a [`RemoteWriter`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RemoteWriteStats {
+    /// Pushes accepted by the endpoint
+    pub successful_pushes: u64,
+    /// Pushes dropped after exhausting their retries
+    pub failed_pushes: u64,
+    /// Requests retried after a failure
+    pub retries: u64,
+}
+
+/// Periodically pushes collected metrics to a remote endpoint
+pub struct RemoteWriter {
+    /// Endpoint, protocol and retry settings
+    config: RemoteWriteConfig,
+    /// HTTP client
+    client: reqwest::Client,
+    /// Pushes accepted by the endpoint
+    successful_pushes: AtomicU64,
+    /// Pushes dropped after exhausting their retries
+    failed_pushes: AtomicU64,
+    /// Requests retried after a failure
+    retries: AtomicU64,
+}
+
+impl RemoteWriter {
+    /// Create a writer for the configured endpoint
+    pub fn new(config: RemoteWriteConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .map_err(|e| MonitoringError::NetworkError(e.to_string()))?;
+
+        Ok(Self {
+            config,
+            client,
+            successful_pushes: AtomicU64::new(0),
+            failed_pushes: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+        })
+    }
+
+    /// Endpoint, protocol and retry settings
+    pub fn config(&self) -> &RemoteWriteConfig {
+        &self.config
+    }
+
+    /// Push counters so far
+    pub fn stats(&self) -> RemoteWriteStats {
+        RemoteWriteStats {
+            successful_pushes: self.successful_pushes.load(Ordering::Relaxed),
+            failed_pushes: self.failed_pushes.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Push the current value of every series in `registry` every
+    /// `interval_seconds`, until the returned future is dropped. Pushes
+    /// that still fail after their retries are dropped and counted in
+    /// [`RemoteWriteStats::failed_pushes`].
+    pub async fn run(&self, registry: &MetricsRegistry) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_seconds.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.push(registry).await {
+                eprintln!("Failed to push metrics to {}: {}", self.config.url, e);
+            }
+        }
+    }
+
+    /// Push the current value of every series in `registry` once.
+    ///
+    /// Connection failures, `429 Too Many Requests` and server errors are
+    /// retried with exponential backoff; other responses fail immediately.
+    pub async fn push(&self, registry: &MetricsRegistry) -> Result<()> {
+        let samples = collect_samples(registry);
+        let body = match self.config.protocol {
+            RemoteWriteProtocol::PrometheusRemoteWrite => encode_remote_write(&samples, Utc::now())?,
+            RemoteWriteProtocol::OtlpHttp => encode_otlp(&samples, registry.created_at(), Utc::now())?,
+        };
+
+        let retry = &self.config.retry_config;
+        let mut delay = retry.initial_delay_seconds as f64;
+        let mut attempt = 0;
+        loop {
+            match self.send(body.clone()).await {
+                Ok(()) => {
+                    self.successful_pushes.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err((_, true)) if attempt < retry.max_retries => {
+                    attempt += 1;
+                    self.retries.fetch_add(1, Ordering::Relaxed);
+                    tokio::time::sleep(Duration::from_secs_f64(delay)).await;
+                    delay = (delay * retry.backoff_multiplier).min(retry.max_delay_seconds as f64);
+                }
+                Err((error, _)) => {
+                    self.failed_pushes.fetch_add(1, Ordering::Relaxed);
+                    return Err(error);
+                }
+            }
+        }
+    }
+
+    /// Post one request body, reporting whether a failure may be retried
+    async fn send(&self, body: Vec<u8>) -> std::result::Result<(), (MonitoringError, bool)> {
+        let mut request = self.client.post(&self.config.url).body(body);
+        request = match self.config.protocol {
+            RemoteWriteProtocol::PrometheusRemoteWrite => request
+                .header("Content-Type", "application/x-protobuf")
+                .header("Content-Encoding", "snappy")
+                .header("X-Prometheus-Remote-Write-Version", "0.1.0"),
+            RemoteWriteProtocol::OtlpHttp => request.header("Content-Type", "application/json"),
+        };
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| (MonitoringError::NetworkError(e.to_string()), true))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let retryable = status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+        Err((
+            MonitoringError::NetworkError(format!("{} rejected metrics with status {}", self.config.url, status)),
+            retryable,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Receiver answering each request with the next status in `statuses`
+    /// and handing back the request bodies
+    async fn mock_receiver(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<Vec<(String, Vec<u8>)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/v1/write", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = Vec::new();
+                let header_end = loop {
+                    let mut chunk = [0; 4096];
+                    let read = socket.read(&mut chunk).await.unwrap();
+                    buffer.extend_from_slice(&chunk[..read]);
+                    if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&buffer[..header_end]).to_lowercase();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map_or(0, |value| value.trim().parse().unwrap());
+                while buffer.len() < header_end + length {
+                    let mut chunk = [0; 4096];
+                    let read = socket.read(&mut chunk).await.unwrap();
+                    buffer.extend_from_slice(&chunk[..read]);
+                }
+                let response = format!("HTTP/1.1 {} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+                requests.push((head, buffer[header_end..header_end + length].to_vec()));
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn registry() -> MetricsRegistry {
+        let mut registry = MetricsRegistry::new(30);
+        registry.set_global_labels(BTreeMap::from([("instance".to_string(), "node-1".to_string())]));
+        let requests = registry.register_counter("http_requests_total", "Requests", &["method"]);
+        requests.add(3, &[("method", "GET")]);
+        let queue = registry.register_gauge("queue_depth", "Queued jobs", &[]);
+        queue.set(7.0, &[]);
+        registry
+    }
+
+    fn config(url: String, protocol: RemoteWriteProtocol) -> RemoteWriteConfig {
+        RemoteWriteConfig {
+            url,
+            protocol,
+            retry_config: RetryConfig {
+                max_retries: 2,
+                initial_delay_seconds: 0,
+                ..RetryConfig::default()
+            },
+            ..RemoteWriteConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_write_payload_decodes_after_retry() {
+        let (url, receiver) = mock_receiver(vec![503, 200]).await;
+        let writer = RemoteWriter::new(config(url, RemoteWriteProtocol::PrometheusRemoteWrite)).unwrap();
+
+        writer.push(&registry()).await.unwrap();
+
+        let requests = receiver.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].1, requests[1].1);
+        let (head, body) = &requests[1];
+        assert!(head.contains("content-encoding: snappy"));
+        assert!(head.contains("content-type: application/x-protobuf"));
+
+        let decoded = snap::raw::Decoder::new().decompress_vec(body).unwrap();
+        let mut series: Vec<_> = prompb::WriteRequest::decode(decoded.as_slice())
+            .unwrap()
+            .timeseries
+            .into_iter()
+            .map(|series| {
+                let labels: Vec<_> = series.labels.into_iter().map(|l| (l.name, l.value)).collect();
+                (labels, series.samples[0].value)
+            })
+            .collect();
+        series.sort_by(|a, b| a.0.cmp(&b.0));
+        let label = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(
+            series,
+            vec![
+                (vec![label("__name__", "http_requests_total"), label("instance", "node-1"), label("method", "GET")], 3.0),
+                (vec![label("__name__", "queue_depth"), label("instance", "node-1")], 7.0),
+            ]
+        );
+        assert_eq!(
+            writer.stats(),
+            RemoteWriteStats {
+                successful_pushes: 1,
+                failed_pushes: 0,
+                retries: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_otlp_payload_and_permanent_failure() {
+        let (url, receiver) = mock_receiver(vec![200, 400]).await;
+        let writer = RemoteWriter::new(config(url, RemoteWriteProtocol::OtlpHttp)).unwrap();
+        let registry = registry();
+
+        writer.push(&registry).await.unwrap();
+        assert!(writer.push(&registry).await.is_err());
+
+        let requests = receiver.await.unwrap();
+        assert!(requests[0].0.contains("content-type: application/json"));
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].1).unwrap();
+        let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        assert_eq!(metrics[0]["name"], "http_requests_total");
+        assert_eq!(metrics[0]["sum"]["isMonotonic"], true);
+        let point = &metrics[0]["sum"]["dataPoints"][0];
+        assert_eq!(point["asDouble"], 3.0);
+        let nanos = |field: &str| point[field].as_str().unwrap().parse::<i64>().unwrap();
+        assert_eq!(nanos("startTimeUnixNano"), registry.created_at().timestamp_nanos_opt().unwrap());
+        assert!(nanos("startTimeUnixNano") <= nanos("timeUnixNano"));
+        assert_eq!(point["attributes"][1], serde_json::json!({ "key": "method", "value": { "stringValue": "GET" } }));
+        assert_eq!(metrics[1]["name"], "queue_depth");
+        assert_eq!(metrics[1]["gauge"]["dataPoints"][0]["asDouble"], 7.0);
+        // A client error is not retried
+        assert_eq!(writer.stats().failed_pushes, 1);
+        assert_eq!(writer.stats().retries, 0);
+    }
+
+    #[test]
+    fn test_collect_samples_from_registry() {
+        let registry = registry();
+        let latency = registry.register_histogram("latency_seconds", "Latency", &["route"], vec![0.5]);
+        latency.observe_with_exemplar(0.2, "abc", &[("route", "/a\"b")]);
+        latency.observe(1.3, &[("route", "/a\"b")]);
+
+        let mut samples: Vec<_> = collect_samples(&registry)
+            .into_iter()
+            .filter(|sample| sample.name.starts_with("latency_seconds"))
+            .collect();
+        samples.sort_by(|a, b| a.name.cmp(&b.name).then(a.value.total_cmp(&b.value)));
+
+        let values: Vec<_> = samples.iter().map(|sample| (sample.name.as_str(), sample.value)).collect();
+        assert_eq!(
+            values,
+            vec![
+                ("latency_seconds_bucket", 1.0),
+                ("latency_seconds_bucket", 2.0),
+                ("latency_seconds_count", 2.0),
+                ("latency_seconds_sum", 1.5),
+            ]
+        );
+        // Label values are taken as recorded, without any escaping
+        assert_eq!(samples[0].labels["route"], "/a\"b");
+        assert_eq!(samples[0].labels["le"], "0.5");
+        assert_eq!(samples[1].labels["le"], "+Inf");
+        assert_eq!(samples[0].labels["instance"], "node-1");
+        assert!(samples.iter().all(|sample| sample.metric_type == Some(MetricType::Histogram)));
+    }
+}
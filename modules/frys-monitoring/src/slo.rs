@@ -0,0 +1,452 @@
+//! Service level objectives and error budgets
+//!
+//! An [`Slo`] requires that a share of a service's requests, its
+//! `objective`, succeed over a rolling window. Success is measured by two
+//! counters, one of good requests and one of all requests, and the
+//! [`SloTracker`] samples both on every evaluation to see how much each
+//! grew over the window.
+//!
+//! The error budget is the share allowed to fail, `1 - objective`. The
+//! remaining budget is measured over the whole window, while the burn rate,
+//! how fast the budget is being spent relative to an even spend across the
+//! window, is measured over a shorter recent lookback. When the recent burn
+//! rate would use up the remaining budget before a window has passed, an
+//! alert is raised; it is resolved once the prediction no longer holds.
+
+use crate::*;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::time::Duration;
+
+/// A series of a registered counter or gauge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricRef {
+    /// Metric name
+    pub name: String,
+    /// Label values selecting the series
+    pub labels: Vec<(String, String)>,
+}
+
+impl MetricRef {
+    /// Refer to the unlabeled series of `name`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            labels: Vec::new(),
+        }
+    }
+
+    /// Select the series where `label` is `value`
+    pub fn with_label(mut self, label: &str, value: &str) -> Self {
+        self.labels.push((label.to_string(), value.to_string()));
+        self
+    }
+
+    /// Current value in `metrics`, or `None` if the metric is not
+    /// registered or holds no single value per series
+    pub fn read(&self, metrics: &MetricsRegistry) -> Option<f64> {
+        let labels: Vec<(&str, &str)> = self.labels.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        metrics.get_metric(&self.name)?.value(&labels)
+    }
+}
+
+/// Service level objective over a rolling window
+#[derive(Debug, Clone)]
+pub struct Slo {
+    /// Share of requests that must succeed, e.g. 0.999
+    pub objective: f64,
+    /// Rolling window the objective applies to
+    pub window: Duration,
+    /// Counter of successful requests
+    pub good: MetricRef,
+    /// Counter of all requests
+    pub total: MetricRef,
+}
+
+/// SLO tracker configuration
+#[derive(Debug, Clone)]
+pub struct SloConfig {
+    /// Lookback the burn rate is measured over, capped at the SLO window
+    pub burn_rate_window: Duration,
+    /// Channels notified when a budget exhaustion alert is raised
+    pub channels: Vec<String>,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            burn_rate_window: Duration::from_secs(DEFAULT_SLO_BURN_RATE_WINDOW),
+            channels: Vec::new(),
+        }
+    }
+}
+
+/// State of an SLO at one evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloStatus {
+    /// Name the SLO was registered under
+    pub name: String,
+    /// Required success ratio
+    pub objective: f64,
+    /// Share of requests in the window that succeeded, 1.0 without traffic
+    pub success_ratio: f64,
+    /// Share of the window's error budget not yet spent; 0 or below once
+    /// the objective is missed
+    pub error_budget_remaining: f64,
+    /// Recent error rate as a multiple of the rate the budget allows
+    pub burn_rate: f64,
+    /// Time until the remaining budget is spent at the current burn rate,
+    /// `None` while nothing is burning
+    pub time_to_exhaustion: Option<Duration>,
+    /// Evaluation time
+    pub timestamp: DateTime<Utc>,
+}
+
+impl SloStatus {
+    /// Whether the budget is predicted to run out within `window`
+    pub fn exhaustion_predicted(&self, window: Duration) -> bool {
+        self.time_to_exhaustion.is_some_and(|remaining| remaining < window)
+    }
+
+    /// Build an alert predicting budget exhaustion. An already exhausted
+    /// budget is reported as critical.
+    pub fn to_alert(&self) -> Alert {
+        let severity = if self.error_budget_remaining <= 0.0 {
+            AlertSeverity::Critical
+        } else {
+            AlertSeverity::High
+        };
+
+        let mut data = BTreeMap::new();
+        for (key, value) in [
+            ("objective", self.objective),
+            ("success_ratio", self.success_ratio),
+            ("error_budget_remaining", self.error_budget_remaining),
+            ("burn_rate", self.burn_rate),
+        ] {
+            if let Some(number) = serde_json::Number::from_f64(value) {
+                data.insert(key.to_string(), serde_json::Value::Number(number));
+            }
+        }
+
+        let exhaustion = match self.time_to_exhaustion {
+            Some(remaining) if !remaining.is_zero() => format!("runs out in {}s", remaining.as_secs()),
+            _ => "is exhausted".to_string(),
+        };
+
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            type_: AlertType::Custom("slo".to_string()),
+            severity,
+            title: format!("Error budget of {} burning", self.name),
+            message: format!(
+                "{} burns its error budget at {:.2}x; {:.1}% left {}",
+                self.name,
+                self.burn_rate,
+                self.error_budget_remaining.max(0.0) * 100.0,
+                exhaustion
+            ),
+            source: "slo_tracker".to_string(),
+            timestamp: self.timestamp,
+            acknowledged: false,
+            acknowledged_by: None,
+            acknowledged_at: None,
+            resolved: false,
+            resolved_at: None,
+            tags: vec!["slo".to_string()],
+            data,
+        }
+    }
+}
+
+/// Tracks SLOs by name, exporting their state as gauges labeled `slo`
+pub struct SloTracker {
+    /// Tracker configuration
+    config: SloConfig,
+    /// Registered SLOs by name
+    slos: DashMap<String, TrackedSlo>,
+    /// Exported success ratio
+    success_ratio: Gauge,
+    /// Exported remaining error budget, floored at 0
+    error_budget_remaining: Gauge,
+    /// Exported burn rate
+    burn_rate: Gauge,
+}
+
+impl SloTracker {
+    /// Create a tracker exporting its gauges to `metrics`
+    pub fn new(config: SloConfig, metrics: &MetricsRegistry) -> Self {
+        Self {
+            config,
+            slos: DashMap::new(),
+            success_ratio: metrics.register_gauge("slo_success_ratio", "Share of requests in the SLO window that succeeded", &["slo"]),
+            error_budget_remaining: metrics.register_gauge("slo_error_budget_remaining", "Share of the SLO error budget left", &["slo"]),
+            burn_rate: metrics.register_gauge("slo_burn_rate", "Rate the SLO error budget is spent, relative to an even spend", &["slo"]),
+        }
+    }
+
+    /// Get the tracker configuration
+    pub fn config(&self) -> &SloConfig {
+        &self.config
+    }
+
+    /// Track `slo` under `name`, replacing an SLO of the same name. The
+    /// objective must lie strictly between 0 and 1 so that there is an
+    /// error budget to spend.
+    pub fn register(&self, name: &str, slo: Slo) -> Result<()> {
+        if !(slo.objective > 0.0 && slo.objective < 1.0) {
+            return Err(MonitoringError::ValidationError(format!(
+                "SLO {} objective must be between 0 and 1 exclusive, got {}",
+                name, slo.objective
+            )));
+        }
+        self.slos.insert(name.to_string(), TrackedSlo::new(slo));
+        Ok(())
+    }
+
+    /// Stop tracking the SLO `name`
+    pub fn remove(&self, name: &str) -> Option<Slo> {
+        self.slos.remove(name).map(|(_, tracked)| tracked.slo)
+    }
+
+    /// Status of `name` at its last evaluation
+    pub fn status(&self, name: &str) -> Option<SloStatus> {
+        self.slos.get(name)?.status.clone()
+    }
+
+    /// Status of every evaluated SLO, by name
+    pub fn statuses(&self) -> BTreeMap<String, SloStatus> {
+        self.slos
+            .iter()
+            .filter_map(|entry| Some((entry.key().clone(), entry.status.clone()?)))
+            .collect()
+    }
+
+    /// Sample the counters of every SLO, update the exported gauges and
+    /// raise or resolve budget exhaustion alerts
+    pub async fn evaluate(&self, metrics: &MetricsRegistry, alerts: &AlertingEngine) -> Vec<SloStatus> {
+        self.evaluate_at(metrics, alerts, Utc::now()).await
+    }
+
+    /// Evaluate every SLO as of `now`. SLOs whose counters cannot be read
+    /// are skipped.
+    pub async fn evaluate_at(&self, metrics: &MetricsRegistry, alerts: &AlertingEngine, now: DateTime<Utc>) -> Vec<SloStatus> {
+        let names: Vec<String> = self.slos.iter().map(|entry| entry.key().clone()).collect();
+        let mut statuses = Vec::new();
+
+        for name in names {
+            // The entry is not held across the alert notifications below
+            let (status, window, firing) = {
+                let Some(mut tracked) = self.slos.get_mut(&name) else { continue };
+                let Some(status) = tracked.observe(&name, metrics, self.config.burn_rate_window, now) else { continue };
+                (status, tracked.slo.window, tracked.alert_id.clone())
+            };
+
+            let labels = [("slo", name.as_str())];
+            self.success_ratio.set(status.success_ratio, &labels);
+            // Gauges cannot hold negative values
+            self.error_budget_remaining.set(status.error_budget_remaining.max(0.0), &labels);
+            self.burn_rate.set(status.burn_rate, &labels);
+
+            let alert_id = match (status.exhaustion_predicted(window), firing) {
+                (true, None) => Some(alerts.raise_alert(status.to_alert(), &self.config.channels).await),
+                (false, Some(alert_id)) => {
+                    // Resolving an alert that is no longer active is a no-op
+                    let _ = alerts.resolve_alert(&alert_id).await;
+                    None
+                }
+                (_, firing) => firing,
+            };
+
+            if let Some(mut tracked) = self.slos.get_mut(&name) {
+                tracked.alert_id = alert_id;
+            }
+            statuses.push(status);
+        }
+
+        statuses
+    }
+}
+
+/// An SLO with its counter samples
+struct TrackedSlo {
+    slo: Slo,
+    /// Cumulative (timestamp, good, total) readings, oldest first, reaching
+    /// back at least one window when there is that much history
+    samples: VecDeque<(DateTime<Utc>, f64, f64)>,
+    /// Last computed status
+    status: Option<SloStatus>,
+    /// Active budget exhaustion alert
+    alert_id: Option<String>,
+}
+
+impl TrackedSlo {
+    fn new(slo: Slo) -> Self {
+        Self {
+            slo,
+            samples: VecDeque::new(),
+            status: None,
+            alert_id: None,
+        }
+    }
+
+    /// Record the current counter values and compute the status
+    fn observe(&mut self, name: &str, metrics: &MetricsRegistry, burn_rate_window: Duration, now: DateTime<Utc>) -> Option<SloStatus> {
+        let good = self.slo.good.read(metrics)?;
+        let total = self.slo.total.read(metrics)?;
+        self.samples.push_back((now, good, total));
+
+        let window = self.slo.window;
+        // Keep one sample at least a window old as the baseline
+        while self.samples.len() > 1 && age(self.samples[1].0, now) >= window {
+            self.samples.pop_front();
+        }
+
+        let budget = 1.0 - self.slo.objective;
+        let success_ratio = self.success_ratio(window, now);
+        let burn_rate = (1.0 - self.success_ratio(burn_rate_window.min(window), now)) / budget;
+        let error_budget_remaining = 1.0 - (1.0 - success_ratio) / budget;
+
+        let time_to_exhaustion = if error_budget_remaining <= 0.0 {
+            Some(Duration::ZERO)
+        } else if burn_rate > 0.0 {
+            // Too far out to represent is as good as never
+            Duration::try_from_secs_f64(window.as_secs_f64() * error_budget_remaining / burn_rate).ok()
+        } else {
+            None
+        };
+
+        let status = SloStatus {
+            name: name.to_string(),
+            objective: self.slo.objective,
+            success_ratio,
+            error_budget_remaining,
+            burn_rate,
+            time_to_exhaustion,
+            timestamp: now,
+        };
+        self.status = Some(status.clone());
+        Some(status)
+    }
+
+    /// Share of the requests counted over the last `lookback` that were
+    /// good, measured from the newest sample at least that old, or the
+    /// oldest sample if history is shorter
+    fn success_ratio(&self, lookback: Duration, now: DateTime<Utc>) -> f64 {
+        let (Some(&(_, good, total)), Some(&(_, base_good, base_total))) = (
+            self.samples.back(),
+            self.samples.iter().rev().find(|(at, _, _)| age(*at, now) >= lookback).or(self.samples.front()),
+        ) else {
+            return 1.0;
+        };
+
+        // Counters restarted in between count from zero
+        let (good, total) = if total < base_total {
+            (good, total)
+        } else {
+            (good - base_good, total - base_total)
+        };
+        if total > 0.0 {
+            (good / total).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Time elapsed from `at` to `now`, zero if `at` is later
+fn age(at: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    now.signed_duration_since(at).to_std().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_budget_and_burn_rate_from_counts() {
+        let metrics = MetricsRegistry::new(DEFAULT_RETENTION_DAYS);
+        let alerts = AlertingEngine::new(30);
+        let good = metrics.register_counter("http_requests_good", "Successful requests", &["service"]);
+        let total = metrics.register_counter("http_requests_total", "All requests", &["service"]);
+
+        let tracker = SloTracker::new(
+            SloConfig {
+                burn_rate_window: Duration::from_secs(600),
+                channels: vec![],
+            },
+            &metrics,
+        );
+        tracker.register("checkout", Slo {
+            objective: 0.99,
+            window: Duration::from_secs(3600),
+            good: MetricRef::new("http_requests_good").with_label("service", "checkout"),
+            total: MetricRef::new("http_requests_total").with_label("service", "checkout"),
+        })
+        .unwrap();
+
+        let start = Utc::now();
+        let at = |minutes| start + chrono::Duration::minutes(minutes);
+        let labels = [("service", "checkout")];
+        let feed = |good_count, total_count| {
+            good.add(good_count, &labels);
+            total.add(total_count, &labels);
+        };
+
+        tracker.evaluate_at(&metrics, &alerts, at(0)).await;
+
+        // 5 failures in 10000 requests: a twentieth of the allowed rate
+        feed(9995, 10000);
+        let status = tracker.evaluate_at(&metrics, &alerts, at(50)).await.remove(0);
+        assert!((status.success_ratio - 0.9995).abs() < 1e-9);
+        assert!((status.error_budget_remaining - 0.95).abs() < 1e-9);
+        assert!((status.burn_rate - 0.05).abs() < 1e-9);
+        assert!(!status.exhaustion_predicted(Duration::from_secs(3600)));
+        assert_eq!(alerts.total_alerts(), 0);
+
+        // 20 failures in the last 1000 requests burn at twice the allowed
+        // rate, spending the rest of the budget in about 23 minutes
+        feed(980, 1000);
+        let status = tracker.evaluate_at(&metrics, &alerts, at(60)).await.remove(0);
+        assert!((status.success_ratio - 10975.0 / 11000.0).abs() < 1e-9);
+        assert!((status.error_budget_remaining - (1.0 - 25.0 / 110.0)).abs() < 1e-9);
+        assert!((status.burn_rate - 2.0).abs() < 1e-9);
+        let exhaustion = status.time_to_exhaustion.unwrap().as_secs_f64();
+        assert!((exhaustion - 3600.0 * (1.0 - 25.0 / 110.0) / 2.0).abs() < 1e-3);
+        assert_eq!(alerts.total_alerts(), 1);
+        assert_eq!(tracker.status("checkout"), Some(status));
+
+        // Still burning: the alert is not raised again
+        tracker.evaluate_at(&metrics, &alerts, at(61)).await;
+        assert_eq!(alerts.total_alerts(), 1);
+
+        let exported = metrics.get_metric("slo_burn_rate").unwrap();
+        assert!((exported.value(&[("slo", "checkout")]).unwrap() - 2.0).abs() < 0.01);
+
+        // Quiet for a burn window: nothing is burning and the alert resolves
+        tracker.evaluate_at(&metrics, &alerts, at(75)).await;
+        assert_eq!(tracker.statuses()["checkout"].burn_rate, 0.0);
+        assert_eq!(alerts.total_alerts(), 0);
+    }
+
+    #[test]
+    fn test_objective_must_leave_an_error_budget() {
+        let metrics = MetricsRegistry::new(DEFAULT_RETENTION_DAYS);
+        let tracker = SloTracker::new(SloConfig::default(), &metrics);
+        let slo = |objective| Slo {
+            objective,
+            window: Duration::from_secs(3600),
+            good: MetricRef::new("good"),
+            total: MetricRef::new("total"),
+        };
+
+        for objective in [0.0, 1.0, 1.5, -0.5, f64::NAN] {
+            assert!(matches!(tracker.register("api", slo(objective)), Err(MonitoringError::ValidationError(_))));
+        }
+        assert!(tracker.status("api").is_none());
+        assert!(tracker.register("api", slo(0.999)).is_ok());
+    }
+}
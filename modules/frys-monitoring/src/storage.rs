@@ -0,0 +1,416 @@
+//! Time-series storage with retention rollups
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::RwLock;
+
+/// Storage for monitoring events and metric samples
+#[async_trait::async_trait]
+pub trait TimeSeriesStorage: Send + Sync {
+    /// Store a monitoring event
+    async fn store_event(&self, event: &MonitoringEvent) -> Result<()>;
+
+    /// Store a raw metric sample
+    async fn store_sample(&self, metric: &str, timestamp: DateTime<Utc>, value: f64) -> Result<()>;
+
+    /// Query `[start, end)` of a metric, choosing the finest resolution
+    /// still retained for `start`
+    async fn query_range(&self, metric: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<RangeQueryResult>;
+
+    /// Downsample samples that have aged past the rollup policy as of `now`
+    async fn rollup(&self, now: DateTime<Utc>) -> Result<RollupStats>;
+
+    /// Approximate storage size in bytes
+    async fn size(&self) -> Result<u64>;
+
+    /// Flush pending writes
+    async fn flush(&self) -> Result<()>;
+}
+
+/// Storage resolution of a series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Resolution {
+    /// Samples as recorded
+    Raw,
+    /// One-minute aggregates
+    Minute,
+    /// One-hour aggregates
+    Hour,
+}
+
+impl Resolution {
+    /// Bucket width, or `None` for raw samples
+    pub fn bucket_width(self) -> Option<Duration> {
+        match self {
+            Resolution::Raw => None,
+            Resolution::Minute => Some(Duration::minutes(1)),
+            Resolution::Hour => Some(Duration::hours(1)),
+        }
+    }
+
+    /// Start of the bucket containing `timestamp`
+    fn bucket_start(self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        match self.bucket_width() {
+            None => timestamp,
+            Some(width) => floor_to(timestamp, width),
+        }
+    }
+}
+
+/// Aggregate of the samples in a time bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    /// Bucket start, or the sample time for raw samples
+    pub timestamp: DateTime<Utc>,
+    /// Smallest sample
+    pub min: f64,
+    /// Largest sample
+    pub max: f64,
+    /// Sum of samples
+    pub sum: f64,
+    /// Number of samples
+    pub count: u64,
+}
+
+impl Aggregate {
+    /// Aggregate of a single sample
+    pub fn from_sample(timestamp: DateTime<Utc>, value: f64) -> Self {
+        Self { timestamp, min: value, max: value, sum: value, count: 1 }
+    }
+
+    /// Mean of the aggregated samples
+    pub fn avg(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    /// Fold another aggregate into this one
+    fn merge(&mut self, other: &Aggregate) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        self.count += other.count;
+    }
+}
+
+/// Result of a range query
+#[derive(Debug, Clone)]
+pub struct RangeQueryResult {
+    /// Resolution the points were returned at
+    pub resolution: Resolution,
+    /// Points ordered by timestamp
+    pub points: Vec<Aggregate>,
+}
+
+/// Ages at which samples are downsampled and dropped
+#[derive(Debug, Clone)]
+pub struct RollupPolicy {
+    /// Raw samples older than this are rolled into one-minute aggregates
+    pub raw_retention: Duration,
+    /// One-minute aggregates older than this are rolled into one-hour aggregates
+    pub minute_retention: Duration,
+    /// One-hour aggregates older than this are dropped
+    pub retention: Duration,
+}
+
+impl RollupPolicy {
+    /// Policy keeping hourly aggregates for `retention_days`
+    pub fn with_retention_days(retention_days: u32) -> Self {
+        Self {
+            retention: Duration::days(i64::from(retention_days)),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for RollupPolicy {
+    fn default() -> Self {
+        Self {
+            raw_retention: Duration::hours(6),
+            minute_retention: Duration::days(2),
+            retention: Duration::days(i64::from(DEFAULT_RETENTION_DAYS)),
+        }
+    }
+}
+
+/// Counts from a rollup pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollupStats {
+    /// Raw samples folded into one-minute aggregates
+    pub raw_rolled_up: u64,
+    /// One-minute aggregates folded into one-hour aggregates
+    pub minutes_rolled_up: u64,
+    /// One-hour aggregates dropped past retention
+    pub hours_expired: u64,
+}
+
+/// Samples of one metric at every resolution.
+///
+/// Tiers cover disjoint time ranges: raw samples from `raw_watermark`,
+/// minute aggregates from `minute_watermark` up to `raw_watermark`, and
+/// hour aggregates before `minute_watermark`.
+#[derive(Debug, Default)]
+struct Series {
+    raw: BTreeMap<DateTime<Utc>, f64>,
+    minutes: BTreeMap<DateTime<Utc>, Aggregate>,
+    hours: BTreeMap<DateTime<Utc>, Aggregate>,
+    raw_watermark: Option<DateTime<Utc>>,
+    minute_watermark: Option<DateTime<Utc>>,
+}
+
+impl Series {
+    /// Finest resolution that still holds data at `timestamp`
+    fn resolution_at(&self, timestamp: DateTime<Utc>) -> Resolution {
+        match (self.minute_watermark, self.raw_watermark) {
+            (Some(minute), _) if timestamp < minute => Resolution::Hour,
+            (_, Some(raw)) if timestamp < raw => Resolution::Minute,
+            _ => Resolution::Raw,
+        }
+    }
+
+    /// Points in `[start, end)` at `resolution`, downsampling finer tiers
+    /// on the fly
+    fn points(&self, resolution: Resolution, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Aggregate> {
+        if resolution == Resolution::Raw {
+            return self.raw.range(start..end).map(|(ts, v)| Aggregate::from_sample(*ts, *v)).collect();
+        }
+
+        let mut buckets: BTreeMap<DateTime<Utc>, Aggregate> = BTreeMap::new();
+        let mut add = |aggregate: Aggregate| merge_into(&mut buckets, resolution, aggregate);
+
+        let first_bucket = resolution.bucket_start(start);
+        if resolution == Resolution::Hour {
+            self.hours.range(first_bucket..end).for_each(|(_, a)| add(*a));
+        }
+        self.minutes.range(first_bucket..end).for_each(|(_, a)| add(*a));
+        self.raw.range(first_bucket..end).for_each(|(ts, v)| add(Aggregate::from_sample(*ts, *v)));
+
+        buckets.into_values().collect()
+    }
+
+    /// Fold aged tiers into coarser ones
+    fn rollup(&mut self, policy: &RollupPolicy, now: DateTime<Utc>, stats: &mut RollupStats) {
+        let raw_cutoff = floor_to(now - policy.raw_retention, Duration::minutes(1));
+        let kept_raw = self.raw.split_off(&raw_cutoff);
+        for (timestamp, value) in ::core::mem::replace(&mut self.raw, kept_raw) {
+            merge_into(&mut self.minutes, Resolution::Minute, Aggregate::from_sample(timestamp, value));
+            stats.raw_rolled_up += 1;
+        }
+        self.raw_watermark = self.raw_watermark.max(Some(raw_cutoff));
+
+        let minute_cutoff = floor_to(now - policy.minute_retention, Duration::hours(1)).min(raw_cutoff);
+        let kept_minutes = self.minutes.split_off(&minute_cutoff);
+        for (_, aggregate) in ::core::mem::replace(&mut self.minutes, kept_minutes) {
+            merge_into(&mut self.hours, Resolution::Hour, aggregate);
+            stats.minutes_rolled_up += 1;
+        }
+        self.minute_watermark = self.minute_watermark.max(Some(minute_cutoff));
+
+        let expiry_cutoff = floor_to(now - policy.retention, Duration::hours(1));
+        let kept_hours = self.hours.split_off(&expiry_cutoff);
+        stats.hours_expired += ::core::mem::replace(&mut self.hours, kept_hours).len() as u64;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.raw.is_empty() && self.minutes.is_empty() && self.hours.is_empty()
+    }
+}
+
+/// In-memory storage backend
+pub struct MemoryStorage {
+    /// Recorded events
+    events: RwLock<Vec<MonitoringEvent>>,
+    /// Metric series by name
+    series: RwLock<BTreeMap<String, Series>>,
+    /// Rollup policy
+    policy: RollupPolicy,
+}
+
+impl MemoryStorage {
+    /// Create a new in-memory storage with the default rollup policy
+    pub fn new() -> Self {
+        Self::with_rollup_policy(RollupPolicy::default())
+    }
+
+    /// Create a new in-memory storage with a custom rollup policy
+    pub fn with_rollup_policy(policy: RollupPolicy) -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+            series: RwLock::new(BTreeMap::new()),
+            policy,
+        }
+    }
+
+    /// Get the rollup policy
+    pub fn rollup_policy(&self) -> &RollupPolicy {
+        &self.policy
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl TimeSeriesStorage for MemoryStorage {
+    async fn store_event(&self, event: &MonitoringEvent) -> Result<()> {
+        self.events.write().await.push(event.clone());
+        Ok(())
+    }
+
+    async fn store_sample(&self, metric: &str, timestamp: DateTime<Utc>, value: f64) -> Result<()> {
+        let mut series = self.series.write().await;
+        let series = series.entry(metric.to_string()).or_default();
+
+        // Late samples land directly in whichever tier covers them
+        match series.resolution_at(timestamp) {
+            Resolution::Raw => {
+                series.raw.insert(timestamp, value);
+            }
+            Resolution::Minute => merge_into(&mut series.minutes, Resolution::Minute, Aggregate::from_sample(timestamp, value)),
+            Resolution::Hour => merge_into(&mut series.hours, Resolution::Hour, Aggregate::from_sample(timestamp, value)),
+        }
+        Ok(())
+    }
+
+    async fn query_range(&self, metric: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<RangeQueryResult> {
+        let series = self.series.read().await;
+        let Some(series) = series.get(metric) else {
+            return Ok(RangeQueryResult { resolution: Resolution::Raw, points: Vec::new() });
+        };
+
+        let resolution = series.resolution_at(start);
+        Ok(RangeQueryResult {
+            resolution,
+            points: series.points(resolution, start, end),
+        })
+    }
+
+    async fn rollup(&self, now: DateTime<Utc>) -> Result<RollupStats> {
+        let mut stats = RollupStats::default();
+        let mut series = self.series.write().await;
+        for entry in series.values_mut() {
+            entry.rollup(&self.policy, now, &mut stats);
+        }
+        series.retain(|_, entry| !entry.is_empty());
+        Ok(stats)
+    }
+
+    async fn size(&self) -> Result<u64> {
+        let series = self.series.read().await;
+        let points: usize = series.values().map(|s| s.raw.len()).sum();
+        let aggregates: usize = series.values().map(|s| s.minutes.len() + s.hours.len()).sum();
+        let events = self.events.read().await.len();
+
+        Ok((points * ::core::mem::size_of::<(DateTime<Utc>, f64)>()
+            + aggregates * ::core::mem::size_of::<Aggregate>()
+            + events * ::core::mem::size_of::<MonitoringEvent>()) as u64)
+    }
+
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Merge an aggregate into the bucket covering it
+fn merge_into(tier: &mut BTreeMap<DateTime<Utc>, Aggregate>, resolution: Resolution, aggregate: Aggregate) {
+    let bucket = resolution.bucket_start(aggregate.timestamp);
+    tier.entry(bucket)
+        .and_modify(|existing| existing.merge(&aggregate))
+        .or_insert(Aggregate { timestamp: bucket, ..aggregate });
+}
+
+/// Round `timestamp` down to a multiple of `width` since the epoch
+fn floor_to(timestamp: DateTime<Utc>, width: Duration) -> DateTime<Utc> {
+    let width_ms = width.num_milliseconds().max(1);
+    let millis = timestamp.timestamp_millis();
+    DateTime::from_timestamp_millis(millis - millis.rem_euclid(width_ms)).unwrap_or(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, minute, second).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rollup_downsamples_old_ranges() {
+        let storage = MemoryStorage::with_rollup_policy(RollupPolicy {
+            raw_retention: Duration::hours(1),
+            minute_retention: Duration::hours(2),
+            retention: Duration::days(30),
+        });
+
+        // Three hours of samples at a 15s scrape interval
+        let start = at(9, 0, 0);
+        for i in 0..(3 * 60 * 4) {
+            storage.store_sample("cpu", start + Duration::seconds(i * 15), i as f64).await.unwrap();
+        }
+
+        let stats = storage.rollup(at(12, 0, 0)).await.unwrap();
+        assert_eq!(stats.raw_rolled_up, 2 * 60 * 4);
+        assert_eq!(stats.minutes_rolled_up, 60);
+        assert_eq!(stats.hours_expired, 0);
+
+        // Recent range stays raw
+        let recent = storage.query_range("cpu", at(11, 50, 0), at(12, 0, 0)).await.unwrap();
+        assert_eq!(recent.resolution, Resolution::Raw);
+        assert_eq!(recent.points.len(), 40);
+        assert!(recent.points.iter().all(|p| p.count == 1));
+
+        // Older range is served from one-minute aggregates
+        let minutes = storage.query_range("cpu", at(10, 30, 0), at(10, 40, 0)).await.unwrap();
+        assert_eq!(minutes.resolution, Resolution::Minute);
+        assert_eq!(minutes.points.len(), 10);
+        let first = minutes.points[0];
+        assert_eq!(first.timestamp, at(10, 30, 0));
+        assert_eq!(first.count, 4);
+        assert_eq!((first.min, first.max, first.avg()), (360.0, 363.0, 361.5));
+
+        // Oldest range is served from one-hour aggregates
+        let hours = storage.query_range("cpu", at(9, 0, 0), at(10, 0, 0)).await.unwrap();
+        assert_eq!(hours.resolution, Resolution::Hour);
+        assert_eq!(hours.points.len(), 1);
+        assert_eq!(hours.points[0].count, 240);
+        assert_eq!((hours.points[0].min, hours.points[0].max), (0.0, 239.0));
+
+        // A range spanning tiers is returned at a single resolution
+        let spanning = storage.query_range("cpu", at(10, 58, 0), at(11, 2, 0)).await.unwrap();
+        assert_eq!(spanning.resolution, Resolution::Minute);
+        assert_eq!(
+            spanning.points.iter().map(|p| p.timestamp).collect::<Vec<_>>(),
+            vec![at(10, 58, 0), at(10, 59, 0), at(11, 0, 0), at(11, 1, 0)]
+        );
+        assert!(spanning.points.iter().all(|p| p.count == 4));
+    }
+
+    #[tokio::test]
+    async fn test_rollup_expires_past_retention() {
+        let storage = MemoryStorage::with_rollup_policy(RollupPolicy {
+            raw_retention: Duration::minutes(10),
+            minute_retention: Duration::minutes(30),
+            retention: Duration::hours(2),
+        });
+        storage.store_sample("cpu", at(0, 0, 0), 1.0).await.unwrap();
+        storage.store_sample("cpu", at(11, 59, 0), 2.0).await.unwrap();
+
+        let stats = storage.rollup(at(12, 0, 0)).await.unwrap();
+        assert_eq!(stats.hours_expired, 1);
+
+        let all = storage.query_range("cpu", at(0, 0, 0), at(12, 0, 0)).await.unwrap();
+        assert_eq!(all.resolution, Resolution::Hour);
+        assert_eq!(all.points.len(), 1);
+        assert_eq!(all.points[0].sum, 2.0);
+    }
+}
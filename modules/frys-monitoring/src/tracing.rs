@@ -0,0 +1,500 @@
+//! Distributed tracing with tail-based sampling
+//!
+//! Head sampling decides whether to keep a trace as it starts, before
+//! anything is known about it, and so drops most slow and failing traces
+//! along with the uninteresting ones. The [`TailSampler`] instead buffers
+//! the spans of each trace for `decision_wait` after its first span, so
+//! that children ending after their root are still seen, and decides then:
+//! a trace with an erroring span or lasting longer than the latency
+//! threshold is always kept, any other trace is kept at the base rate. The
+//! base rate decision hashes the trace ID, so every collector seeing part
+//! of a trace decides the same way.
+//!
+//! Traces are decided by [`TailSampler::flush_expired`], which
+//! [`TracingSystem::run`] calls periodically. A span arriving after its
+//! trace was decided follows that decision.
+
+use crate::*;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A finished span
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    /// ID shared by every span of the trace
+    pub trace_id: String,
+    /// ID of this span
+    pub span_id: String,
+    /// Span this one is a child of; `None` for the root span
+    pub parent_span_id: Option<String>,
+    /// Operation name
+    pub name: String,
+    /// Start time
+    pub start: DateTime<Utc>,
+    /// End time
+    pub end: DateTime<Utc>,
+    /// Error the operation failed with, if any
+    pub error: Option<String>,
+    /// Attributes such as the route or status code
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Span {
+    /// Create a root span of `trace_id`
+    pub fn new(trace_id: &str, span_id: &str, name: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+            parent_span_id: None,
+            name: name.to_string(),
+            start,
+            end,
+            error: None,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Make this span a child of `parent_span_id`
+    pub fn with_parent(mut self, parent_span_id: &str) -> Self {
+        self.parent_span_id = Some(parent_span_id.to_string());
+        self
+    }
+
+    /// Mark the operation as failed with `error`
+    pub fn with_error(mut self, error: &str) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// Set an attribute
+    pub fn with_attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Whether this is the root span of its trace
+    pub fn is_root(&self) -> bool {
+        self.parent_span_id.is_none()
+    }
+
+    /// Time from start to end, zero if they are out of order
+    pub fn duration(&self) -> Duration {
+        (self.end - self.start).to_std().unwrap_or_default()
+    }
+}
+
+/// Tail-based sampling configuration
+#[derive(Debug, Clone)]
+pub struct TailSamplingConfig {
+    /// Share of traces kept that neither failed nor were slow (0.0 to 1.0)
+    pub base_rate: f64,
+    /// Traces lasting longer than this are always kept
+    pub latency_threshold: Duration,
+    /// How long the spans of a trace are buffered after its first span
+    /// before the trace is decided
+    pub decision_wait: Duration,
+}
+
+impl Default for TailSamplingConfig {
+    fn default() -> Self {
+        Self {
+            base_rate: 0.1,
+            latency_threshold: Duration::from_millis(DEFAULT_TRACE_LATENCY_THRESHOLD_MS),
+            decision_wait: Duration::from_secs(DEFAULT_TRACE_DECISION_WAIT),
+        }
+    }
+}
+
+/// Whether a trace was kept, and why
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingDecision {
+    /// Kept because a span failed
+    Error,
+    /// Kept because the trace exceeded the latency threshold
+    Slow,
+    /// Kept at the base rate
+    Sampled,
+    /// Dropped
+    Dropped,
+}
+
+impl SamplingDecision {
+    /// Whether the trace is kept
+    pub fn is_kept(self) -> bool {
+        self != SamplingDecision::Dropped
+    }
+}
+
+/// A decided trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    /// Trace ID
+    pub trace_id: String,
+    /// Spans in the order they were recorded
+    pub spans: Vec<Span>,
+    /// Sampling decision
+    pub decision: SamplingDecision,
+}
+
+impl Trace {
+    /// Time from the earliest span start to the latest span end
+    pub fn duration(&self) -> Duration {
+        trace_duration(&self.spans)
+    }
+
+    /// Root span, if it was recorded
+    pub fn root(&self) -> Option<&Span> {
+        self.spans.iter().find(|span| span.is_root())
+    }
+}
+
+/// Spans of a trace awaiting its decision
+struct PendingTrace {
+    /// Spans recorded so far
+    spans: Vec<Span>,
+    /// When the first span was recorded
+    first_seen: DateTime<Utc>,
+}
+
+/// Buffers spans by trace and decides whether to keep each trace once it
+/// has waited `decision_wait`
+pub struct TailSampler {
+    /// Sampler configuration
+    config: TailSamplingConfig,
+    /// Undecided traces by ID
+    pending: DashMap<String, PendingTrace>,
+    /// Recently decided traces by ID, with when they were decided, so that
+    /// late spans follow the decision
+    decided: DashMap<String, (SamplingDecision, DateTime<Utc>)>,
+}
+
+impl TailSampler {
+    /// Create a new tail sampler
+    pub fn new(config: TailSamplingConfig) -> Self {
+        Self {
+            config,
+            pending: DashMap::new(),
+            decided: DashMap::new(),
+        }
+    }
+
+    /// Get the sampler configuration
+    pub fn config(&self) -> &TailSamplingConfig {
+        &self.config
+    }
+
+    /// Number of traces awaiting a decision
+    pub fn pending_traces(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Buffer `span` until its trace is decided.
+    ///
+    /// A span arriving within `decision_wait` after its trace was decided
+    /// is not buffered but returned, as a trace of its own carrying the
+    /// earlier decision.
+    pub fn record(&self, span: Span) -> Option<Trace> {
+        if let Some(decided) = self.decided.get(&span.trace_id) {
+            return Some(Trace {
+                trace_id: span.trace_id.clone(),
+                spans: vec![span],
+                decision: decided.0,
+            });
+        }
+
+        self.pending
+            .entry(span.trace_id.clone())
+            .or_insert_with(|| PendingTrace {
+                spans: Vec::new(),
+                first_seen: Utc::now(),
+            })
+            .spans
+            .push(span);
+        None
+    }
+
+    /// Decide every trace whose first span was recorded `decision_wait`
+    /// before `now`, with the spans seen so far
+    pub fn flush_expired(&self, now: DateTime<Utc>) -> Vec<Trace> {
+        let waited = |since: DateTime<Utc>| (now - since).to_std().is_ok_and(|waited| waited >= self.config.decision_wait);
+        self.decided.retain(|_, (_, decided_at)| !waited(*decided_at));
+
+        let expired: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|entry| waited(entry.first_seen))
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|trace_id| self.pending.remove(&trace_id))
+            .map(|(trace_id, pending)| {
+                let trace = self.complete(trace_id, pending.spans);
+                self.decided.insert(trace.trace_id.clone(), (trace.decision, now));
+                trace
+            })
+            .collect()
+    }
+
+    /// Decide whether to keep the trace `trace_id` made of `spans`
+    pub fn decide(&self, trace_id: &str, spans: &[Span]) -> SamplingDecision {
+        if spans.iter().any(|span| span.error.is_some()) {
+            SamplingDecision::Error
+        } else if trace_duration(spans) > self.config.latency_threshold {
+            SamplingDecision::Slow
+        } else if self.config.base_rate >= 1.0 || sampling_point(trace_id) < self.config.base_rate {
+            SamplingDecision::Sampled
+        } else {
+            SamplingDecision::Dropped
+        }
+    }
+
+    fn complete(&self, trace_id: String, spans: Vec<Span>) -> Trace {
+        let decision = self.decide(&trace_id, &spans);
+        Trace {
+            trace_id,
+            spans,
+            decision,
+        }
+    }
+}
+
+impl Default for TailSampler {
+    fn default() -> Self {
+        Self::new(TailSamplingConfig::default())
+    }
+}
+
+/// Collects spans and retains the traces its tail sampler keeps
+pub struct TracingSystem {
+    /// Sampler deciding which traces are kept
+    sampler: TailSampler,
+    /// Most recently kept traces, oldest first
+    kept: Mutex<VecDeque<Trace>>,
+}
+
+impl TracingSystem {
+    /// Create a tracing system with the default sampling configuration
+    pub fn new() -> Self {
+        Self::with_sampling(TailSamplingConfig::default())
+    }
+
+    /// Create a tracing system sampling with `config`
+    pub fn with_sampling(config: TailSamplingConfig) -> Self {
+        Self {
+            sampler: TailSampler::new(config),
+            kept: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Get the tail sampler
+    pub fn sampler(&self) -> &TailSampler {
+        &self.sampler
+    }
+
+    /// Record a finished span, returning the decision on its trace if the
+    /// trace was already decided
+    pub fn record_span(&self, span: Span) -> Option<SamplingDecision> {
+        let trace = self.sampler.record(span)?;
+        let decision = trace.decision;
+        self.retain(trace);
+        Some(decision)
+    }
+
+    /// Decide the traces that have waited `decision_wait` by `now`
+    pub fn flush_expired(&self, now: DateTime<Utc>) {
+        for trace in self.sampler.flush_expired(now) {
+            self.retain(trace);
+        }
+    }
+
+    /// Decide the traces that have waited `decision_wait` every
+    /// `DEFAULT_TRACE_FLUSH_INTERVAL` seconds, until the returned future is
+    /// dropped
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(DEFAULT_TRACE_FLUSH_INTERVAL));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.flush_expired(Utc::now());
+        }
+    }
+
+    /// Kept traces, oldest first
+    pub fn kept_traces(&self) -> Vec<Trace> {
+        self.kept.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
+    /// Kept trace with ID `trace_id`
+    pub fn trace(&self, trace_id: &str) -> Option<Trace> {
+        self.kept
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|trace| trace.trace_id == trace_id)
+            .cloned()
+    }
+
+    fn retain(&self, trace: Trace) {
+        if !trace.decision.is_kept() {
+            return;
+        }
+        let mut kept = self.kept.lock().unwrap_or_else(|e| e.into_inner());
+        // Late spans join their trace if it is still retained
+        if let Some(existing) = kept.iter_mut().find(|existing| existing.trace_id == trace.trace_id) {
+            existing.spans.extend(trace.spans);
+            return;
+        }
+        if kept.len() >= MAX_KEPT_TRACES {
+            kept.pop_front();
+        }
+        kept.push_back(trace);
+    }
+}
+
+impl Default for TracingSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time from the earliest start to the latest end of `spans`
+fn trace_duration(spans: &[Span]) -> Duration {
+    let start = spans.iter().map(|span| span.start).min();
+    let end = spans.iter().map(|span| span.end).max();
+    match (start, end) {
+        (Some(start), Some(end)) => (end - start).to_std().unwrap_or_default(),
+        _ => Duration::ZERO,
+    }
+}
+
+/// Position of `trace_id` in `[0, 1)`, uniform over trace IDs
+fn sampling_point(trace_id: &str) -> f64 {
+    // FNV-1a, then the SplitMix64 finalizer: FNV alone barely spreads the
+    // last bytes into the high bits, and trace IDs often differ only there
+    let mut hash = trace_id
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decide a trace of a root span and one child with a fresh sampler
+    fn trace(config: &TailSamplingConfig, trace_id: &str, duration_ms: i64, error: Option<&str>) -> Trace {
+        let sampler = TailSampler::new(config.clone());
+        let start = Utc::now();
+        let end = start + chrono::Duration::milliseconds(duration_ms);
+        let mut child = Span::new(trace_id, "child", "db.query", start, end).with_parent("root");
+        if let Some(error) = error {
+            child = child.with_error(error);
+        }
+        assert!(sampler.record(Span::new(trace_id, "root", "GET /api", start, end)).is_none());
+        assert!(sampler.record(child).is_none());
+
+        let mut traces = sampler.flush_expired(Utc::now() + chrono::Duration::seconds(DEFAULT_TRACE_DECISION_WAIT as i64));
+        assert_eq!(traces.len(), 1);
+        assert_eq!(sampler.pending_traces(), 0);
+        traces.remove(0)
+    }
+
+    #[test]
+    fn test_tail_sampling_decisions() {
+        let config = TailSamplingConfig {
+            base_rate: 0.5,
+            latency_threshold: Duration::from_millis(500),
+            ..Default::default()
+        };
+
+        // Fast, successful traces are kept at the base rate, consistently
+        let fast: Vec<Trace> = (0..2000).map(|i| trace(&config, &format!("fast-{:032x}", i), 20, None)).collect();
+        let kept = fast.iter().filter(|trace| trace.decision.is_kept()).count();
+        assert!((850..=1150).contains(&kept), "{}", kept);
+        assert!(fast.iter().all(|trace| matches!(trace.decision, SamplingDecision::Sampled | SamplingDecision::Dropped)));
+        assert_eq!(trace(&config, "fast-0", 20, None).decision, trace(&config, "fast-0", 20, None).decision);
+
+        // Failing and slow traces are always kept
+        let dropped = fast.iter().find(|trace| !trace.decision.is_kept()).unwrap();
+        let errored = trace(&config, &dropped.trace_id, 20, Some("connection reset"));
+        assert_eq!(errored.decision, SamplingDecision::Error);
+        assert_eq!(errored.spans.len(), 2);
+        assert_eq!(trace(&config, &dropped.trace_id, 900, None).decision, SamplingDecision::Slow);
+
+        let never = TailSamplingConfig {
+            base_rate: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(trace(&never, "a", 20, None).decision, SamplingDecision::Dropped);
+        assert_eq!(trace(&never, "a", 20, Some("timeout")).decision, SamplingDecision::Error);
+    }
+
+    #[test]
+    fn test_orphaned_trace_decided_after_wait() {
+        let tracing = TracingSystem::with_sampling(TailSamplingConfig {
+            base_rate: 0.0,
+            decision_wait: Duration::from_secs(30),
+            ..Default::default()
+        });
+        let now = Utc::now();
+        let span = Span::new("orphan", "child", "rpc", now, now).with_parent("lost").with_error("unavailable");
+        assert_eq!(tracing.record_span(span), None);
+
+        tracing.flush_expired(now);
+        assert!(tracing.kept_traces().is_empty());
+        assert_eq!(tracing.sampler().pending_traces(), 1);
+
+        tracing.flush_expired(now + chrono::Duration::seconds(31));
+        assert_eq!(tracing.sampler().pending_traces(), 0);
+        let trace = tracing.trace("orphan").unwrap();
+        assert_eq!(trace.decision, SamplingDecision::Error);
+        assert!(trace.root().is_none());
+    }
+
+    #[test]
+    fn test_late_spans_follow_trace_decision() {
+        let tracing = TracingSystem::with_sampling(TailSamplingConfig {
+            base_rate: 0.0,
+            decision_wait: Duration::from_secs(30),
+            ..Default::default()
+        });
+        let now = Utc::now();
+        let at = |seconds| now + chrono::Duration::seconds(seconds);
+
+        // The root span alone does not decide the trace
+        assert_eq!(tracing.record_span(Span::new("kept", "root", "GET /api", now, now)), None);
+        let child = Span::new("kept", "child", "rpc", now, now).with_parent("root").with_error("unavailable");
+        assert_eq!(tracing.record_span(child), None);
+        assert_eq!(tracing.record_span(Span::new("dropped", "root", "GET /api", now, now)), None);
+        tracing.flush_expired(at(31));
+        assert_eq!(tracing.trace("kept").unwrap().spans.len(), 2);
+        assert!(tracing.trace("dropped").is_none());
+
+        // Spans after the decision join their trace instead of starting a
+        // fragment sampled on its own
+        let late = Span::new("kept", "late", "cleanup", now, now).with_parent("root");
+        assert_eq!(tracing.record_span(late), Some(SamplingDecision::Error));
+        let late = Span::new("dropped", "late", "cleanup", now, now).with_parent("root").with_error("timeout");
+        assert_eq!(tracing.record_span(late), Some(SamplingDecision::Dropped));
+        assert_eq!(tracing.trace("kept").unwrap().spans.len(), 3);
+        assert!(tracing.trace("dropped").is_none());
+        assert_eq!(tracing.sampler().pending_traces(), 0);
+        assert_eq!(tracing.kept_traces().len(), 1);
+
+        // Decisions are forgotten a wait later
+        tracing.flush_expired(at(62));
+        let late = Span::new("dropped", "late", "cleanup", now, now).with_parent("root");
+        assert_eq!(tracing.record_span(late), None);
+        assert_eq!(tracing.sampler().pending_traces(), 1);
+    }
+}
@@ -0,0 +1,202 @@
+//! Micro-batching of concurrent inference calls
+//!
+//! Accelerators are used far better by one call over many inputs than by
+//! many calls over one. A [`MicroBatcher`] queues single inference calls
+//! for one model and runs them together: the first queued call opens a
+//! batch, which closes after the batching window or once it holds
+//! `max_batch_size` inputs, and is then sent to the backend in a single
+//! [`InferenceEngine::infer_batch`] call. A batch takes a concurrency
+//! permit only once it has closed, so filling a batch never holds one.
+//!
+//! Each caller gets the result of its own input. If the batched call
+//! fails, its inputs are retried one by one, so one bad input fails only
+//! its own call. Batches hold a read lock on the model, letting
+//! fine-tuning wait for in-flight batches rather than fail.
+
+use crate::*;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ::core::sync::atomic::{AtomicU64, Ordering};
+use ::core::time::Duration;
+use tokio::sync::{mpsc, oneshot, RwLock, Semaphore};
+
+/// Model shared between the plugin's cache and its micro-batchers
+pub type SharedModel = Arc<RwLock<Model>>;
+
+/// Queued call waiting for its batch
+struct PendingInference {
+    input: serde_json::Value,
+    reply: oneshot::Sender<Result<InferResult>>,
+}
+
+#[derive(Debug, Default)]
+struct BatchCounters {
+    batches: AtomicU64,
+    requests: AtomicU64,
+}
+
+/// Handle coalescing concurrent inference calls on one model into batches.
+///
+/// Clones share the queue. The batching task stops once every handle is
+/// dropped.
+#[derive(Clone)]
+pub struct MicroBatcher {
+    sender: mpsc::UnboundedSender<PendingInference>,
+    counters: Arc<BatchCounters>,
+}
+
+impl MicroBatcher {
+    /// Start batching calls to `model` on `engine`. Must be called within a
+    /// Tokio runtime.
+    pub fn new(engine: Arc<InferenceEngine>, model: SharedModel, max_batch_size: usize, window: Duration) -> Self {
+        Self::with_permits(engine, model, max_batch_size, window, Arc::new(Semaphore::new(Semaphore::MAX_PERMITS)))
+    }
+
+    /// Like [`new`](Self::new), but every batch waits for one of `permits`
+    /// before it is run
+    pub fn with_permits(
+        engine: Arc<InferenceEngine>,
+        model: SharedModel,
+        max_batch_size: usize,
+        window: Duration,
+        permits: Arc<Semaphore>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let counters = Arc::new(BatchCounters::default());
+        tokio::spawn(run_batches(engine, model, receiver, max_batch_size.max(1), window, permits, counters.clone()));
+        Self { sender, counters }
+    }
+
+    /// Queue one inference and wait for its result
+    pub async fn infer(&self, input: &str) -> Result<InferResult> {
+        let input = serde_json::from_str(input)
+            .map_err(|_| AIPluginError::InvalidInput("Invalid JSON input".to_string()))?;
+        let (reply, result) = oneshot::channel();
+        self.sender
+            .send(PendingInference { input, reply })
+            .map_err(|_| AIPluginError::BackendError("batching task stopped".to_string()))?;
+        result
+            .await
+            .map_err(|_| AIPluginError::BackendError("batching task stopped".to_string()))?
+    }
+
+    /// Backend calls made so far
+    pub fn batches_run(&self) -> u64 {
+        self.counters.batches.load(Ordering::Relaxed)
+    }
+
+    /// Inference calls served so far
+    pub fn requests_served(&self) -> u64 {
+        self.counters.requests.load(Ordering::Relaxed)
+    }
+}
+
+async fn run_batches(
+    engine: Arc<InferenceEngine>,
+    model: SharedModel,
+    mut receiver: mpsc::UnboundedReceiver<PendingInference>,
+    max_batch_size: usize,
+    window: Duration,
+    permits: Arc<Semaphore>,
+    counters: Arc<BatchCounters>,
+) {
+    while let Some(first) = receiver.recv().await {
+        let mut batch = alloc::vec![first];
+        let deadline = tokio::time::Instant::now() + window;
+        while batch.len() < max_batch_size {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(pending)) => batch.push(pending),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let (inputs, replies): (Vec<_>, Vec<_>) =
+            batch.into_iter().map(|pending| (pending.input, pending.reply)).unzip();
+        let Ok(_permit) = permits.acquire().await else {
+            for reply in replies {
+                let _ = reply.send(Err(AIPluginError::ResourceLimitExceeded));
+            }
+            continue;
+        };
+        counters.batches.fetch_add(1, Ordering::Relaxed);
+        counters.requests.fetch_add(replies.len() as u64, Ordering::Relaxed);
+
+        let model = model.read().await;
+        let results = match engine.infer_batch(&model, &inputs).await {
+            Ok(results) if results.len() == inputs.len() => results.into_iter().map(Ok).collect(),
+            // Find out which inputs fail rather than failing them all
+            _ => {
+                let mut results = Vec::with_capacity(inputs.len());
+                for input in &inputs {
+                    results.push(engine.infer(&model, input).await);
+                }
+                results
+            }
+        };
+        drop(model);
+
+        for (reply, result) in replies.into_iter().zip(results) {
+            // The caller may have given up waiting
+            let _ = reply.send(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn custom_model() -> (Arc<InferenceEngine>, SharedModel) {
+        let engine = InferenceEngine::new(&AIPluginConfig::default()).await.unwrap();
+        let model = engine.load_custom_model("echo").await.unwrap();
+        (Arc::new(engine), Arc::new(RwLock::new(model)))
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_are_coalesced() {
+        let (engine, model) = custom_model().await;
+        let batcher = MicroBatcher::new(engine.clone(), model.clone(), 8, Duration::from_millis(20));
+
+        let inputs: Vec<String> = (0..20).map(|i| format!("{{\"n\": {}}}", i)).collect();
+        let results = futures::future::join_all(inputs.iter().map(|input| batcher.infer(input))).await;
+
+        for (input, result) in inputs.iter().zip(results) {
+            let value: serde_json::Value = serde_json::from_str(input).unwrap();
+            assert_eq!(result.unwrap(), engine.infer(&*model.read().await, &value).await.unwrap());
+        }
+        assert_eq!(batcher.requests_served(), 20);
+        assert!(batcher.batches_run() >= 3, "batches hold at most 8 calls");
+        assert!(batcher.batches_run() <= 4, "{} batches for 20 calls", batcher.batches_run());
+    }
+
+    #[tokio::test]
+    async fn test_filling_batch_holds_no_permit() {
+        let (engine, model) = custom_model().await;
+        let permits = Arc::new(Semaphore::new(1));
+        let batcher = MicroBatcher::with_permits(engine, model, 8, Duration::from_millis(200), permits.clone());
+
+        let pending = tokio::spawn({
+            let batcher = batcher.clone();
+            async move { batcher.infer("{}").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // The batch is still open, so the only permit is free
+        assert_eq!(permits.available_permits(), 1);
+
+        let held = permits.clone().acquire_owned().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        // The closed batch waits for a permit before running
+        assert!(!pending.is_finished());
+        drop(held);
+        assert!(pending.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_input_is_rejected_before_queueing() {
+        let (engine, model) = custom_model().await;
+        let batcher = MicroBatcher::new(engine, model, 8, Duration::from_millis(1));
+
+        assert!(matches!(batcher.infer("not json").await, Err(AIPluginError::InvalidInput(_))));
+        assert_eq!(batcher.requests_served(), 0);
+    }
+}
@@ -1,10 +1,21 @@
 //! Core AI plugin implementation
+//!
+//! [`AIPlugin`] caches loaded models, evicting the least recently used one
+//! when full, and serves inference through them. Every cached model has a
+//! [`MicroBatcher`], so concurrent [`AIPlugin::infer`] calls on the same
+//! model are coalesced into batched backend calls. Every backend call
+//! passes through the request queue, which caps concurrent calls, and is
+//! recorded in the plugin metrics.
 
 use crate::*;
+use ::core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use ::core::time::Duration;
 use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use frys_plugin_system::*;
+use std::sync::Mutex;
 
 /// Main AI Plugin structure
 pub struct AIPlugin {
@@ -12,20 +23,22 @@ pub struct AIPlugin {
     config: AIPluginConfig,
     /// Loaded models cache
     model_cache: BTreeMap<String, CachedModel>,
-    /// Inference engine
-    inference_engine: InferenceEngine,
+    /// Inference engine, shared with micro-batchers
+    inference_engine: Arc<InferenceEngine>,
     /// Training engine (optional)
     training_engine: Option<TrainingEngine>,
     /// Metrics collector
-    metrics: AIMetrics,
+    metrics: Mutex<AIMetrics>,
     /// Request queue for rate limiting
     request_queue: RequestQueue,
+    /// Logical clock ordering model accesses for LRU eviction
+    access_clock: AtomicU64,
 }
 
 impl AIPlugin {
     /// Create a new AI plugin
     pub async fn new(config: AIPluginConfig) -> Result<Self> {
-        let inference_engine = InferenceEngine::new(&config).await?;
+        let inference_engine = Arc::new(InferenceEngine::new(&config).await?);
         let training_engine = if config.enable_training {
             Some(TrainingEngine::new(&config).await?)
         } else {
@@ -37,17 +50,25 @@ impl AIPlugin {
             model_cache: BTreeMap::new(),
             inference_engine,
             training_engine,
-            metrics: AIMetrics::new(),
+            metrics: Mutex::new(AIMetrics::new()),
             request_queue: RequestQueue::new(config.max_concurrent_requests),
+            access_clock: AtomicU64::new(0),
         })
     }
 
-    /// Load a model into the plugin
+    /// Load a model into the plugin.
+    ///
+    /// When the cache already holds `model_cache_size` models, the least
+    /// recently used one is unloaded to make room.
     pub async fn load_model(&mut self, model_name: &str, model_type: ModelType) -> Result<()> {
         // Check if model is already loaded
-        if self.model_cache.contains_key(model_name) {
+        let access = self.tick();
+        if let Some(cached) = self.model_cache.get(model_name) {
+            cached.last_access.store(access, Ordering::Relaxed);
+            self.metrics().record_cache_operation(true);
             return Ok(());
         }
+        self.metrics().record_cache_operation(false);
 
         // Load model based on type
         let model = match model_type {
@@ -57,73 +78,103 @@ impl AIPlugin {
             ModelType::Custom => self.load_custom_model(model_name).await?,
         };
 
+        while self.model_cache.len() >= self.config.model_cache_size.max(1) {
+            self.evict_least_recently_used();
+        }
+
         // Cache the model
-        self.model_cache.insert(model_name.to_string(), CachedModel {
-            model,
-            model_type,
-            load_time: self.current_timestamp(),
-            last_used: self.current_timestamp(),
-            usage_count: 0,
-        });
+        let cached = self.cache_entry(model, model_type, access);
+        self.model_cache.insert(model_name.to_string(), cached);
 
-        self.metrics.record_model_loaded(model_name, model_type);
+        self.metrics().record_model_loaded(model_name, model_type);
         Ok(())
     }
 
+    /// Load a model if needed and run a throwaway inference through it, so
+    /// lazy initialization such as kernel compilation happens before
+    /// traffic arrives
+    pub async fn warmup_model(&mut self, model_name: &str, model_type: ModelType) -> Result<()> {
+        self.load_model(model_name, model_type).await?;
+        let model = self.model_cache.get(model_name)
+            .map(|cached| cached.model.clone())
+            .ok_or_else(|| AIPluginError::ModelNotFound(model_name.to_string()))?;
+        let model = model.read().await;
+        self.inference_engine.warmup(&model).await
+    }
+
     /// Unload a model from the plugin
     pub async fn unload_model(&mut self, model_name: &str) -> Result<()> {
         if let Some(_) = self.model_cache.remove(model_name) {
-            self.metrics.record_model_unloaded(model_name);
+            self.metrics().record_model_unloaded(model_name);
             Ok(())
         } else {
             Err(AIPluginError::ModelNotFound(model_name.to_string()))
         }
     }
 
-    /// Perform inference with a loaded model
-    pub async fn infer(&mut self, model_name: &str, input: &str) -> Result<String> {
-        // Rate limiting
-        let _permit = self.request_queue.acquire().await?;
-
-        // Get model from cache
-        let model = self.model_cache.get_mut(model_name)
-            .ok_or_else(|| AIPluginError::ModelNotFound(model_name.to_string()))?;
-
-        model.last_used = self.current_timestamp();
-        model.usage_count += 1;
-
-        // Parse input
-        let input_data: serde_json::Value = serde_json::from_str(input)
-            .map_err(|_| AIPluginError::InvalidInput("Invalid JSON input".to_string()))?;
+    /// Perform inference with a loaded model.
+    ///
+    /// Concurrent calls on the same model are coalesced into batches of up
+    /// to `default_batch_size` inputs arriving within
+    /// `DEFAULT_BATCH_WINDOW_MS` of each other.
+    pub async fn infer(&self, model_name: &str, input: &str) -> Result<InferResult> {
+        // Get model from cache; its batcher takes a request queue permit
+        // per batch
+        let model = self.touch(model_name, 1)?;
 
         // Perform inference
         let start_time = self.current_timestamp();
-        let result = match model.model_type {
-            ModelType::NLP => self.inference_engine.infer_nlp(&model.model, &input_data).await?,
-            ModelType::Vision => self.inference_engine.infer_vision(&model.model, &input_data).await?,
-            ModelType::Embedding => self.inference_engine.infer_embedding(&model.model, &input_data).await?,
-            ModelType::Custom => self.inference_engine.infer_custom(&model.model, &input_data).await?,
-        };
+        let result = model.batcher.infer(input).await;
         let inference_time = self.current_timestamp() - start_time;
 
         // Record metrics
-        self.metrics.record_inference(model_name, inference_time, true);
+        self.metrics().record_inference(model_name, inference_time, result.is_ok());
 
-        Ok(result)
+        result
     }
 
-    /// Get batch inference results
-    pub async fn infer_batch(&mut self, model_name: &str, inputs: &[String]) -> Result<Vec<String>> {
-        let mut results = Vec::new();
+    /// Perform inference on several inputs, results in input order.
+    ///
+    /// Inputs are split into batches of at most `default_batch_size`, each
+    /// run in a single backend call.
+    pub async fn infer_batch(&self, model_name: &str, inputs: Vec<&str>) -> Result<Vec<InferResult>> {
+        let input_data = inputs
+            .iter()
+            .map(|input| serde_json::from_str(input))
+            .collect::<::core::result::Result<Vec<serde_json::Value>, _>>()
+            .map_err(|_| AIPluginError::InvalidInput("Invalid JSON input".to_string()))?;
+
+        let batch_size = self.config.default_batch_size.max(1);
+        let mut results = Vec::with_capacity(input_data.len());
+        for batch in input_data.chunks(batch_size) {
+            let _permit = self.request_queue.acquire().await?;
+            let cached = self.touch(model_name, batch.len())?;
 
-        for input in inputs {
-            let result = self.infer(model_name, input).await?;
-            results.push(result);
+            let start_time = self.current_timestamp();
+            let model = cached.model.read().await;
+            let batch_results = self.inference_engine.infer_batch(&model, batch).await;
+            drop(model);
+            let inference_time = self.current_timestamp() - start_time;
+
+            self.metrics().record_inference(model_name, inference_time, batch_results.is_ok());
+            results.extend(batch_results?);
         }
 
         Ok(results)
     }
 
+    /// Micro-batcher of a loaded model, for callers that share it across
+    /// tasks without the plugin.
+    ///
+    /// Bypasses the plugin metrics; prefer [`infer`](Self::infer), which
+    /// batches through the same queue.
+    pub fn micro_batcher(&self, model_name: &str) -> Result<MicroBatcher> {
+        let model = self.model_cache.get(model_name)
+            .ok_or_else(|| AIPluginError::ModelNotFound(model_name.to_string()))?;
+
+        Ok(model.batcher.clone())
+    }
+
     /// Generate embeddings for text
     pub async fn generate_embeddings(&mut self, model_name: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
         let model = self.model_cache.get(model_name)
@@ -133,27 +184,29 @@ impl AIPlugin {
             return Err(AIPluginError::InvalidModelType("Model is not an embedding model".to_string()));
         }
 
-        self.inference_engine.generate_embeddings(&model.model, texts).await
+        let model = model.model.read().await;
+        self.inference_engine.generate_embeddings(&model, texts).await
     }
 
-    /// Fine-tune a model
+    /// Fine-tune a model, once the inference batches in flight on it finish
     pub async fn fine_tune(&mut self, model_name: &str, training_data: &[TrainingExample]) -> Result<()> {
         let training_engine = self.training_engine.as_ref()
             .ok_or(AIPluginError::TrainingNotSupported)?;
 
+        let now = self.current_timestamp();
         let model = self.model_cache.get_mut(model_name)
             .ok_or_else(|| AIPluginError::ModelNotFound(model_name.to_string()))?;
 
-        training_engine.fine_tune(&mut model.model, training_data).await?;
-        model.load_time = self.current_timestamp(); // Update modification time
+        training_engine.fine_tune(&mut *model.model.write().await, training_data).await?;
+        model.load_time = now; // Update modification time
 
-        self.metrics.record_training(model_name, training_data.len());
+        self.metrics().record_training(model_name, training_data.len());
         Ok(())
     }
 
-    /// Get plugin metrics
-    pub fn get_metrics(&self) -> &AIMetrics {
-        &self.metrics
+    /// Snapshot of the plugin metrics
+    pub fn get_metrics(&self) -> AIMetrics {
+        self.metrics().clone()
     }
 
     /// Get loaded models
@@ -167,8 +220,8 @@ impl AIPlugin {
             name: model_name.to_string(),
             model_type: cached.model_type,
             load_time: cached.load_time,
-            last_used: cached.last_used,
-            usage_count: cached.usage_count,
+            last_used: cached.last_used.load(Ordering::Relaxed),
+            usage_count: cached.usage_count.load(Ordering::Relaxed),
         })
     }
 
@@ -179,7 +232,7 @@ impl AIPlugin {
 
         let mut to_remove = Vec::new();
         for (name, model) in &self.model_cache {
-            if current_time - model.last_used > max_age && self.model_cache.len() > self.config.model_cache_size / 2 {
+            if current_time - model.last_used.load(Ordering::Relaxed) > max_age && self.model_cache.len() > self.config.model_cache_size / 2 {
                 to_remove.push(name.clone());
             }
         }
@@ -229,6 +282,62 @@ impl AIPlugin {
         self.inference_engine.load_custom_model(model_name).await
     }
 
+    /// Unload the least recently used model. Its memory is freed once no
+    /// micro-batcher holds it any more.
+    fn evict_least_recently_used(&mut self) -> Option<String> {
+        let victim = self.model_cache.iter()
+            .min_by_key(|(_, cached)| cached.last_access.load(Ordering::Relaxed))
+            .map(|(name, _)| name.clone())?;
+
+        self.model_cache.remove(&victim);
+        let mut metrics = self.metrics();
+        metrics.record_model_unloaded(&victim);
+        metrics.record_model_evicted(&victim);
+        Some(victim)
+    }
+
+    /// Cache entry for a freshly loaded model, with its micro-batcher
+    fn cache_entry(&self, model: Model, model_type: ModelType, access: u64) -> CachedModel {
+        let model = Arc::new(tokio::sync::RwLock::new(model));
+        let batcher = MicroBatcher::with_permits(
+            self.inference_engine.clone(),
+            model.clone(),
+            self.config.default_batch_size,
+            Duration::from_millis(DEFAULT_BATCH_WINDOW_MS),
+            self.request_queue.semaphore.clone(),
+        );
+        CachedModel {
+            model,
+            batcher,
+            model_type,
+            load_time: self.current_timestamp(),
+            last_used: AtomicU64::new(self.current_timestamp()),
+            last_access: AtomicU64::new(access),
+            usage_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Look up a loaded model and record `uses` inferences on it
+    fn touch(&self, model_name: &str, uses: usize) -> Result<&CachedModel> {
+        let access = self.tick();
+        let cached = self.model_cache.get(model_name)
+            .ok_or_else(|| AIPluginError::ModelNotFound(model_name.to_string()))?;
+        cached.last_access.store(access, Ordering::Relaxed);
+        cached.last_used.store(self.current_timestamp(), Ordering::Relaxed);
+        cached.usage_count.fetch_add(uses, Ordering::Relaxed);
+        Ok(cached)
+    }
+
+    /// Advance the access clock
+    fn tick(&self) -> u64 {
+        self.access_clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Metrics collector, locked
+    fn metrics(&self) -> std::sync::MutexGuard<'_, AIMetrics> {
+        self.metrics.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     fn current_timestamp(&self) -> u64 {
         0 // Placeholder - would use actual timestamp
     }
@@ -267,22 +376,28 @@ impl Plugin for AIPlugin {
 
 /// Cached model information
 struct CachedModel {
-    model: Model,
+    /// The model, read-locked by inference and write-locked by fine-tuning
+    model: SharedModel,
+    /// Coalesces concurrent inference calls on the model
+    batcher: MicroBatcher,
     model_type: ModelType,
     load_time: u64,
-    last_used: u64,
-    usage_count: usize,
+    last_used: AtomicU64,
+    /// Access clock value at the last load or inference
+    last_access: AtomicU64,
+    usage_count: AtomicUsize,
 }
 
 /// Request queue for rate limiting
 struct RequestQueue {
-    semaphore: tokio::sync::Semaphore,
+    /// Shared with the micro-batchers, which take a permit per batch
+    semaphore: Arc<tokio::sync::Semaphore>,
 }
 
 impl RequestQueue {
     fn new(max_concurrent: usize) -> Self {
         Self {
-            semaphore: tokio::sync::Semaphore::new(max_concurrent),
+            semaphore: Arc::new(tokio::sync::Semaphore::new(max_concurrent)),
         }
     }
 
@@ -349,4 +464,83 @@ mod tests {
         let result = plugin.load_model("test-model", ModelType::Custom).await;
         assert!(result.is_err()); // Expected to fail without actual model
     }
+
+    #[tokio::test]
+    async fn test_batched_results_match_individual() {
+        let config = AIPluginConfig {
+            default_batch_size: 3,
+            ..Default::default()
+        };
+        let mut plugin = AIPlugin::new(config).await.unwrap();
+        plugin.load_model("echo", ModelType::Custom).await.unwrap();
+
+        let inputs: Vec<String> = (0..10).map(|i| format!("{{\"n\": {}}}", i)).collect();
+        let batched = plugin.infer_batch("echo", inputs.iter().map(String::as_str).collect()).await.unwrap();
+
+        assert_eq!(batched.len(), inputs.len());
+        for (input, result) in inputs.iter().zip(&batched) {
+            assert_eq!(result, &plugin.infer("echo", input).await.unwrap());
+        }
+        assert_eq!(plugin.get_model_info("echo").unwrap().usage_count, 20);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_infer_calls_are_coalesced() {
+        let config = AIPluginConfig {
+            default_batch_size: 8,
+            max_concurrent_requests: 16,
+            enable_training: true,
+            ..Default::default()
+        };
+        let mut plugin = AIPlugin::new(config).await.unwrap();
+        plugin.load_model("echo", ModelType::Custom).await.unwrap();
+
+        let inputs: Vec<String> = (0..20).map(|i| format!("{{\"n\": {}}}", i)).collect();
+        let results = futures::future::join_all(inputs.iter().map(|input| plugin.infer("echo", input))).await;
+        assert!(results.iter().all(Result::is_ok));
+        assert!(plugin.infer("echo", "not json").await.is_err());
+
+        let batcher = plugin.micro_batcher("echo").unwrap();
+        assert_eq!(batcher.requests_served(), 20);
+        assert!(batcher.batches_run() <= 4, "{} batches for 20 calls", batcher.batches_run());
+        let overall = plugin.get_metrics().get_overall_metrics();
+        assert_eq!((overall.successful_inferences, overall.failed_inferences), (20, 1));
+
+        // Fine-tuning waits for batches instead of failing while they share the model
+        let examples = [TrainingExample {
+            input: "{}".to_string(),
+            target: "ok".to_string(),
+            weight: None,
+        }];
+        plugin.fine_tune("echo", &examples).await.unwrap();
+        assert!(batcher.infer("{}").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_model_is_evicted() {
+        let config = AIPluginConfig {
+            model_cache_size: 2,
+            ..Default::default()
+        };
+        let mut plugin = AIPlugin::new(config).await.unwrap();
+
+        plugin.warmup_model("bert", ModelType::Custom).await.unwrap();
+        plugin.load_model("gpt2", ModelType::Custom).await.unwrap();
+        // Touching bert leaves gpt2 as the least recently used
+        plugin.infer("bert", "{}").await.unwrap();
+        plugin.load_model("t5", ModelType::Custom).await.unwrap();
+
+        let mut loaded = plugin.get_loaded_models();
+        loaded.sort_unstable();
+        assert_eq!(loaded, ["bert", "t5"]);
+
+        let overall = plugin.get_metrics().get_overall_metrics();
+        assert_eq!(overall.loaded_models, ["bert", "t5"]);
+        assert_eq!(overall.total_model_evictions, 1);
+        assert_eq!(plugin.get_metrics().get_model_metrics("gpt2").unwrap().eviction_count, 1);
+        assert_eq!((overall.cache_hits, overall.cache_misses), (0, 3));
+
+        plugin.load_model("t5", ModelType::Custom).await.unwrap();
+        assert_eq!(plugin.get_metrics().get_overall_metrics().cache_hits, 1);
+    }
 }
@@ -51,6 +51,21 @@ impl EmbeddingGenerator {
     }
 }
 
+/// Lets the AI system's semantic cache embed requests with this generator
+#[cfg(feature = "semantic-cache")]
+#[async_trait::async_trait]
+impl frys_ai_system::TextEmbedder for EmbeddingGenerator {
+    async fn embed(&self, text: &str) -> frys_ai_system::Result<Vec<f32>> {
+        let mut embeddings = self
+            .generate_text_embeddings(&[text.to_string()])
+            .await
+            .map_err(|e| frys_ai_system::AIError::InferenceError { reason: e.to_string() })?;
+        embeddings.pop().ok_or_else(|| frys_ai_system::AIError::InferenceError {
+            reason: "no embedding generated".to_string(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
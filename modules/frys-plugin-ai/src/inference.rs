@@ -2,6 +2,9 @@
 
 use crate::*;
 
+/// Output of a single inference
+pub type InferResult = String;
+
 /// Inference engine for running AI models
 pub struct InferenceEngine {
     /// Backend configuration
@@ -19,43 +22,88 @@ impl InferenceEngine {
         })
     }
 
+    /// Run inference with the backend matching the model's type
+    pub async fn infer(&self, model: &Model, input: &serde_json::Value) -> Result<InferResult> {
+        single(self.infer_batch(model, ::core::slice::from_ref(input)).await)
+    }
+
+    /// Run a batch of inputs through `model` in one backend invocation,
+    /// returning one result per input in input order
+    pub async fn infer_batch(&self, model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<InferResult>> {
+        match model.model_type {
+            ModelType::NLP => self.infer_nlp_batch(model, inputs).await,
+            ModelType::Vision => self.infer_vision_batch(model, inputs).await,
+            ModelType::Embedding => self.infer_embedding_batch(model, inputs).await,
+            ModelType::Custom => self.infer_custom_batch(model, inputs).await,
+        }
+    }
+
+    /// Run a throwaway inference so backends initialize lazily built state,
+    /// such as compiled kernels or weights uploaded to the device
+    pub async fn warmup(&self, model: &Model) -> Result<()> {
+        self.infer(model, &serde_json::json!({})).await.map(|_| ())
+    }
+
     /// Run NLP inference
     pub async fn infer_nlp(&self, model: &Model, input: &serde_json::Value) -> Result<String> {
+        single(self.infer_nlp_batch(model, ::core::slice::from_ref(input)).await)
+    }
+
+    /// Run NLP inference on a batch in one backend call
+    async fn infer_nlp_batch(&self, model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
         match self.backend {
-            BackendType::PyTorch => self.infer_nlp_pytorch(model, input).await,
-            BackendType::TensorFlow => self.infer_nlp_tensorflow(model, input).await,
-            BackendType::ONNX => self.infer_nlp_onnx(model, input).await,
-            BackendType::CPU => self.infer_nlp_cpu(model, input).await,
-            BackendType::Custom => self.infer_nlp_custom(model, input).await,
+            BackendType::PyTorch => self.infer_nlp_pytorch(model, inputs).await,
+            BackendType::TensorFlow => self.infer_nlp_tensorflow(model, inputs).await,
+            BackendType::ONNX => self.infer_nlp_onnx(model, inputs).await,
+            BackendType::CPU => self.infer_nlp_cpu(model, inputs).await,
+            BackendType::Custom => self.infer_nlp_custom(model, inputs).await,
         }
     }
 
     /// Run vision inference
     pub async fn infer_vision(&self, model: &Model, input: &serde_json::Value) -> Result<String> {
+        single(self.infer_vision_batch(model, ::core::slice::from_ref(input)).await)
+    }
+
+    /// Run vision inference on a batch in one backend call
+    async fn infer_vision_batch(&self, model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
         match self.backend {
-            BackendType::PyTorch => self.infer_vision_pytorch(model, input).await,
-            BackendType::TensorFlow => self.infer_vision_tensorflow(model, input).await,
-            BackendType::ONNX => self.infer_vision_onnx(model, input).await,
-            BackendType::CPU => self.infer_vision_cpu(model, input).await,
-            BackendType::Custom => self.infer_vision_custom(model, input).await,
+            BackendType::PyTorch => self.infer_vision_pytorch(model, inputs).await,
+            BackendType::TensorFlow => self.infer_vision_tensorflow(model, inputs).await,
+            BackendType::ONNX => self.infer_vision_onnx(model, inputs).await,
+            BackendType::CPU => self.infer_vision_cpu(model, inputs).await,
+            BackendType::Custom => self.infer_vision_custom(model, inputs).await,
         }
     }
 
     /// Run embedding inference
     pub async fn infer_embedding(&self, model: &Model, input: &serde_json::Value) -> Result<String> {
+        single(self.infer_embedding_batch(model, ::core::slice::from_ref(input)).await)
+    }
+
+    /// Run embedding inference on a batch in one backend call
+    async fn infer_embedding_batch(&self, model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
         match self.backend {
-            BackendType::PyTorch => self.infer_embedding_pytorch(model, input).await,
-            BackendType::TensorFlow => self.infer_embedding_tensorflow(model, input).await,
-            BackendType::ONNX => self.infer_embedding_onnx(model, input).await,
-            BackendType::CPU => self.infer_embedding_cpu(model, input).await,
-            BackendType::Custom => self.infer_embedding_custom(model, input).await,
+            BackendType::PyTorch => self.infer_embedding_pytorch(model, inputs).await,
+            BackendType::TensorFlow => self.infer_embedding_tensorflow(model, inputs).await,
+            BackendType::ONNX => self.infer_embedding_onnx(model, inputs).await,
+            BackendType::CPU => self.infer_embedding_cpu(model, inputs).await,
+            BackendType::Custom => self.infer_embedding_custom(model, inputs).await,
         }
     }
 
     /// Run custom model inference
     pub async fn infer_custom(&self, model: &Model, input: &serde_json::Value) -> Result<String> {
+        single(self.infer_custom_batch(model, ::core::slice::from_ref(input)).await)
+    }
+
+    /// Run custom model inference on a batch in one call
+    async fn infer_custom_batch(&self, model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
         // Custom inference implementation
-        Ok(format!("Custom inference result for model {}", model.id))
+        Ok(inputs
+            .iter()
+            .map(|input| format!("Custom inference result for model {} on {}", model.id, input))
+            .collect())
     }
 
     /// Generate embeddings for texts
@@ -66,128 +114,101 @@ impl InferenceEngine {
     }
 
     // Backend-specific implementations (placeholders)
-    async fn infer_nlp_pytorch(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(feature = "ml")]
-        {
+    async fn infer_nlp_pytorch(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(feature = "ml") {
             // PyTorch NLP inference implementation
-            Ok("PyTorch NLP result".to_string())
-        }
-        #[cfg(not(feature = "ml"))]
-        {
+            Ok(vec!["PyTorch NLP result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("PyTorch backend not enabled".to_string()))
         }
     }
 
-    async fn infer_nlp_tensorflow(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(feature = "ml")]
-        {
-            Ok("TensorFlow NLP result".to_string())
-        }
-        #[cfg(not(feature = "ml"))]
-        {
+    async fn infer_nlp_tensorflow(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(feature = "ml") {
+            Ok(vec!["TensorFlow NLP result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("TensorFlow backend not enabled".to_string()))
         }
     }
 
-    async fn infer_nlp_onnx(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        Ok("ONNX NLP result".to_string())
+    async fn infer_nlp_onnx(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        Ok(vec!["ONNX NLP result".to_string(); inputs.len()])
     }
 
-    async fn infer_nlp_cpu(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        Ok("CPU NLP result".to_string())
+    async fn infer_nlp_cpu(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        Ok(vec!["CPU NLP result".to_string(); inputs.len()])
     }
 
-    async fn infer_nlp_custom(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        Ok("Custom NLP result".to_string())
+    async fn infer_nlp_custom(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        Ok(vec!["Custom NLP result".to_string(); inputs.len()])
     }
 
-    async fn infer_vision_pytorch(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(all(feature = "ml", feature = "vision"))]
-        {
-            Ok("PyTorch vision result".to_string())
-        }
-        #[cfg(not(all(feature = "ml", feature = "vision")))]
-        {
+    async fn infer_vision_pytorch(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(all(feature = "ml", feature = "vision")) {
+            Ok(vec!["PyTorch vision result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("PyTorch vision backend not enabled".to_string()))
         }
     }
 
-    async fn infer_vision_tensorflow(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(all(feature = "ml", feature = "vision"))]
-        {
-            Ok("TensorFlow vision result".to_string())
-        }
-        #[cfg(not(all(feature = "ml", feature = "vision")))]
-        {
+    async fn infer_vision_tensorflow(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(all(feature = "ml", feature = "vision")) {
+            Ok(vec!["TensorFlow vision result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("TensorFlow vision backend not enabled".to_string()))
         }
     }
 
-    async fn infer_vision_onnx(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(feature = "vision")]
-        {
-            Ok("ONNX vision result".to_string())
-        }
-        #[cfg(not(feature = "vision"))]
-        {
+    async fn infer_vision_onnx(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(feature = "vision") {
+            Ok(vec!["ONNX vision result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("Vision features not enabled".to_string()))
         }
     }
 
-    async fn infer_vision_cpu(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(feature = "vision")]
-        {
-            Ok("CPU vision result".to_string())
-        }
-        #[cfg(not(feature = "vision"))]
-        {
+    async fn infer_vision_cpu(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(feature = "vision") {
+            Ok(vec!["CPU vision result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("Vision features not enabled".to_string()))
         }
     }
 
-    async fn infer_vision_custom(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        Ok("Custom vision result".to_string())
+    async fn infer_vision_custom(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        Ok(vec!["Custom vision result".to_string(); inputs.len()])
     }
 
-    async fn infer_embedding_pytorch(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(all(feature = "ml", feature = "embeddings"))]
-        {
-            Ok("PyTorch embedding result".to_string())
-        }
-        #[cfg(not(all(feature = "ml", feature = "embeddings")))]
-        {
+    async fn infer_embedding_pytorch(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(all(feature = "ml", feature = "embeddings")) {
+            Ok(vec!["PyTorch embedding result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("PyTorch embeddings not enabled".to_string()))
         }
     }
 
-    async fn infer_embedding_tensorflow(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(all(feature = "ml", feature = "embeddings"))]
-        {
-            Ok("TensorFlow embedding result".to_string())
-        }
-        #[cfg(not(all(feature = "ml", feature = "embeddings")))]
-        {
+    async fn infer_embedding_tensorflow(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(all(feature = "ml", feature = "embeddings")) {
+            Ok(vec!["TensorFlow embedding result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("TensorFlow embeddings not enabled".to_string()))
         }
     }
 
-    async fn infer_embedding_onnx(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        #[cfg(feature = "embeddings")]
-        {
-            Ok("ONNX embedding result".to_string())
-        }
-        #[cfg(not(feature = "embeddings"))]
-        {
+    async fn infer_embedding_onnx(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        if cfg!(feature = "embeddings") {
+            Ok(vec!["ONNX embedding result".to_string(); inputs.len()])
+        } else {
             Err(AIPluginError::FeatureNotEnabled("Embedding features not enabled".to_string()))
         }
     }
 
-    async fn infer_embedding_cpu(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        Ok("CPU embedding result".to_string())
+    async fn infer_embedding_cpu(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        Ok(vec!["CPU embedding result".to_string(); inputs.len()])
     }
 
-    async fn infer_embedding_custom(&self, _model: &Model, _input: &serde_json::Value) -> Result<String> {
-        Ok("Custom embedding result".to_string())
+    async fn infer_embedding_custom(&self, _model: &Model, inputs: &[serde_json::Value]) -> Result<Vec<String>> {
+        Ok(vec!["Custom embedding result".to_string(); inputs.len()])
     }
 
     // Model loading methods
@@ -318,6 +339,13 @@ impl InferenceEngine {
     }
 }
 
+/// The only result of a batch of one
+fn single(results: Result<Vec<String>>) -> Result<String> {
+    results?
+        .pop()
+        .ok_or_else(|| AIPluginError::BackendError("backend returned no result".to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,6 +60,7 @@ pub use frys_plugin_system as plugin_system;
 mod core;
 mod models;
 mod inference;
+mod batching;
 mod training;
 mod embeddings;
 mod nlp;
@@ -71,6 +72,7 @@ mod config;
 pub use core::*;
 pub use models::*;
 pub use inference::*;
+pub use batching::*;
 pub use training::*;
 pub use embeddings::*;
 pub use nlp::*;
@@ -93,6 +95,7 @@ pub const DEFAULT_MODEL_CACHE_SIZE: usize = 50;
 pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 8;
 pub const DEFAULT_EMBEDDING_DIMENSION: usize = 768;
 pub const DEFAULT_BATCH_SIZE: usize = 32;
+pub const DEFAULT_BATCH_WINDOW_MS: u64 = 5;
 
 #[cfg(test)]
 mod tests {
@@ -1,6 +1,6 @@
 //! Metrics and monitoring for AI plugin
 
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::String;
 
 /// AI metrics collector
@@ -24,6 +24,10 @@ pub struct AIMetrics {
     cache_hits: u64,
     /// Cache misses
     cache_misses: u64,
+    /// Models evicted to make room in the cache
+    model_evictions: u64,
+    /// Models currently loaded
+    loaded_models: BTreeSet<String>,
     /// Model-specific metrics
     model_metrics: BTreeMap<String, ModelMetrics>,
 }
@@ -41,6 +45,8 @@ impl AIMetrics {
             training_sessions: 0,
             cache_hits: 0,
             cache_misses: 0,
+            model_evictions: 0,
+            loaded_models: BTreeSet::new(),
             model_metrics: BTreeMap::new(),
         }
     }
@@ -64,6 +70,7 @@ impl AIMetrics {
     /// Record model loading
     pub fn record_model_loaded(&mut self, model_name: &str, model_type: ModelType) {
         self.model_loads += 1;
+        self.loaded_models.insert(model_name.to_string());
         let model_metric = self.model_metrics.entry(model_name.to_string()).or_insert_with(ModelMetrics::new);
         model_metric.load_count += 1;
     }
@@ -71,10 +78,18 @@ impl AIMetrics {
     /// Record model unloading
     pub fn record_model_unloaded(&mut self, model_name: &str) {
         self.model_unloads += 1;
+        self.loaded_models.remove(model_name);
         let model_metric = self.model_metrics.entry(model_name.to_string()).or_insert_with(ModelMetrics::new);
         model_metric.unload_count += 1;
     }
 
+    /// Record a model evicted from a full cache
+    pub fn record_model_evicted(&mut self, model_name: &str) {
+        self.model_evictions += 1;
+        let model_metric = self.model_metrics.entry(model_name.to_string()).or_insert_with(ModelMetrics::new);
+        model_metric.eviction_count += 1;
+    }
+
     /// Record training session
     pub fn record_training(&mut self, model_name: &str, examples_count: usize) {
         self.training_sessions += 1;
@@ -119,9 +134,13 @@ impl AIMetrics {
             average_inference_time_ms: avg_inference_time,
             success_rate,
             cache_hit_rate,
+            cache_hits: self.cache_hits,
+            cache_misses: self.cache_misses,
             total_model_loads: self.model_loads,
             total_model_unloads: self.model_unloads,
+            total_model_evictions: self.model_evictions,
             total_training_sessions: self.training_sessions,
+            loaded_models: self.loaded_models.iter().cloned().collect(),
         }
     }
 
@@ -137,7 +156,8 @@ impl AIMetrics {
             .collect()
     }
 
-    /// Reset all metrics
+    /// Reset all metrics. Loaded models are state rather than counters and
+    /// are kept.
     pub fn reset(&mut self) {
         self.total_inferences = 0;
         self.successful_inferences = 0;
@@ -148,6 +168,7 @@ impl AIMetrics {
         self.training_sessions = 0;
         self.cache_hits = 0;
         self.cache_misses = 0;
+        self.model_evictions = 0;
         self.model_metrics.clear();
     }
 }
@@ -161,9 +182,13 @@ pub struct OverallMetrics {
     pub average_inference_time_ms: f64,
     pub success_rate: f64,
     pub cache_hit_rate: f64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
     pub total_model_loads: u64,
     pub total_model_unloads: u64,
+    pub total_model_evictions: u64,
     pub total_training_sessions: u64,
+    pub loaded_models: Vec<String>,
 }
 
 impl OverallMetrics {
@@ -183,6 +208,7 @@ pub struct ModelMetrics {
     pub total_inference_time: u64,
     pub load_count: u64,
     pub unload_count: u64,
+    pub eviction_count: u64,
     pub training_sessions: u64,
     pub total_training_examples: usize,
     pub average_inference_time: f64,
@@ -198,6 +224,7 @@ impl ModelMetrics {
             total_inference_time: 0,
             load_count: 0,
             unload_count: 0,
+            eviction_count: 0,
             training_sessions: 0,
             total_training_examples: 0,
             average_inference_time: 0.0,
@@ -331,9 +358,13 @@ mod tests {
             average_inference_time_ms: 50.0,
             success_rate: 0.95,
             cache_hit_rate: 0.8,
+            cache_hits: 80,
+            cache_misses: 20,
             total_model_loads: 5,
             total_model_unloads: 2,
+            total_model_evictions: 1,
             total_training_sessions: 3,
+            loaded_models: vec!["bert".to_string()],
         };
 
         let json = metrics.to_json().unwrap();
@@ -1,5 +1,6 @@
 //! Configuration for network plugin
 
+use crate::*;
 use alloc::string::String;
 
 /// Main configuration for network plugin
@@ -25,6 +26,20 @@ pub struct NetworkPluginConfig {
     pub connection_timeout_secs: u64,
     /// Request timeout in seconds
     pub request_timeout_secs: u64,
+    /// Idle HTTP connections kept per host (0 disables pooling)
+    pub pool_max_idle_per_host: usize,
+    /// Seconds an idle HTTP connection stays reusable
+    pub pool_idle_timeout_secs: u64,
+    /// Attempts per HTTP request, retries of transient failures included
+    pub retry_max_attempts: u32,
+    /// Backoff before the first retry (milliseconds)
+    pub retry_base_delay_ms: u64,
+    /// Longest backoff between retries (milliseconds)
+    pub retry_max_delay_ms: u64,
+    /// Also retry requests that are not idempotent, such as POST
+    pub retry_non_idempotent: bool,
+    /// Largest HTTP response body the client reads (bytes)
+    pub max_response_body_bytes: usize,
     /// Rate limit (requests per minute)
     pub rate_limit_requests: u32,
     /// Circuit breaker failure threshold
@@ -51,11 +66,18 @@ impl Default for NetworkPluginConfig {
             enable_load_balancing: false,
             enable_service_discovery: false,
             enable_api_gateway: false,
-            max_connections: 1000,
-            connection_timeout_secs: 30,
-            request_timeout_secs: 60,
-            rate_limit_requests: 100,
-            circuit_breaker_threshold: 5,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            connection_timeout_secs: DEFAULT_CONNECTION_TIMEOUT_SECS,
+            request_timeout_secs: DEFAULT_REQUEST_TIMEOUT_SECS,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout_secs: DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+            retry_max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            retry_base_delay_ms: DEFAULT_RETRY_BASE_DELAY_MS,
+            retry_max_delay_ms: DEFAULT_RETRY_MAX_DELAY_MS,
+            retry_non_idempotent: false,
+            max_response_body_bytes: DEFAULT_MAX_RESPONSE_BODY_BYTES,
+            rate_limit_requests: DEFAULT_RATE_LIMIT_REQUESTS,
+            circuit_breaker_threshold: DEFAULT_CIRCUIT_BREAKER_THRESHOLD,
             enable_tls: false,
             tls_cert_path: None,
             tls_key_path: None,
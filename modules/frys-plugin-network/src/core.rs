@@ -134,6 +134,11 @@ impl NetworkPlugin {
         }
     }
 
+    /// HTTP client connection pool usage, if the client is enabled
+    pub fn http_pool_stats(&self) -> Option<PoolStats> {
+        self.http_client.as_ref().map(HttpClient::pool_stats)
+    }
+
     /// Start HTTP server
     pub async fn start_http_server(&mut self, address: &str, routes: RouteHandler) -> Result<()> {
         #[cfg(feature = "http")]
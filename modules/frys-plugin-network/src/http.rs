@@ -0,0 +1,814 @@
+//! HTTP/1.1 client with per-host connection pooling
+//!
+//! Opening a TCP connection costs a round trip before the first byte of a
+//! request is sent. [`HttpClient`] keeps connections alive after a response
+//! has been read in full and hands them to the next request for the same
+//! host. Each host keeps at most `pool_max_idle_per_host` idle connections,
+//! and a connection idle for longer than `pool_idle_timeout_secs` is closed
+//! instead of reused. `get` and `post` retry transient failures as the
+//! client's [`RetryPolicy`] allows.
+//!
+//! `https` URLs are served over TLS, verified against the Mozilla root
+//! certificates unless [`HttpClient::with_tls_config`] says otherwise.
+//! Response bodies larger than `max_response_body_bytes` are refused before
+//! they are read.
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use ::core::pin::Pin;
+use ::core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use ::core::task::{Context, Poll};
+use ::core::time::Duration;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, ReadBuf};
+use tokio::net::TcpStream;
+
+/// Connection to a host, encrypted for `https` URLs
+enum Transport {
+    Plain(TcpStream),
+    #[cfg(feature = "http")]
+    Tls(Box<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "http")]
+            Transport::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "http")]
+            Transport::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "http")]
+            Transport::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "http")]
+            Transport::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Buffered connection to one host
+type PooledStream = BufReader<Transport>;
+
+/// Usage of the connection pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Connections currently serving a request
+    pub active: usize,
+    /// Connections kept alive for reuse
+    pub idle: usize,
+    /// Connections opened so far
+    pub created: u64,
+}
+
+/// Connection kept alive between requests
+struct IdleConnection {
+    stream: PooledStream,
+    idle_since: Instant,
+}
+
+/// Idle connections keyed by origin, e.g. `https://host:port`
+struct ConnectionPool {
+    max_idle_per_host: usize,
+    idle_timeout: Duration,
+    idle: Mutex<BTreeMap<String, Vec<IdleConnection>>>,
+    active: AtomicUsize,
+    created: AtomicU64,
+}
+
+impl ConnectionPool {
+    fn new(max_idle_per_host: usize, idle_timeout: Duration) -> Self {
+        Self {
+            max_idle_per_host,
+            idle_timeout,
+            idle: Mutex::new(BTreeMap::new()),
+            active: AtomicUsize::new(0),
+            created: AtomicU64::new(0),
+        }
+    }
+
+    /// Most recently used live connection to `origin`, if any. Expired
+    /// connections met on the way are closed.
+    fn checkout(&self, origin: &str) -> Option<PooledStream> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let connections = idle.get_mut(origin)?;
+        connections.retain(|connection| connection.idle_since.elapsed() < self.idle_timeout);
+        let connection = connections.pop();
+        if connections.is_empty() {
+            idle.remove(origin);
+        }
+        connection.map(|connection| connection.stream)
+    }
+
+    /// Keep `stream` for the next request to `origin`, unless the host
+    /// already has its share of idle connections
+    fn checkin(&self, origin: &str, stream: PooledStream) {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let connections = idle.entry(origin.to_string()).or_default();
+        if connections.len() < self.max_idle_per_host {
+            connections.push(IdleConnection {
+                stream,
+                idle_since: Instant::now(),
+            });
+        }
+    }
+
+    fn stats(&self) -> PoolStats {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        PoolStats {
+            active: self.active.load(Ordering::Relaxed),
+            idle: idle
+                .values()
+                .flatten()
+                .filter(|connection| connection.idle_since.elapsed() < self.idle_timeout)
+                .count(),
+            created: self.created.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Connection checked out for one request. Dropping it closes the
+/// connection unless [`Lease::release`] returned it to the pool.
+struct Lease<'a> {
+    pool: &'a ConnectionPool,
+    origin: &'a str,
+    stream: Option<PooledStream>,
+}
+
+impl<'a> Lease<'a> {
+    fn new(pool: &'a ConnectionPool, origin: &'a str, stream: PooledStream) -> Self {
+        pool.active.fetch_add(1, Ordering::Relaxed);
+        Self {
+            pool,
+            origin,
+            stream: Some(stream),
+        }
+    }
+
+    fn stream(&mut self) -> &mut PooledStream {
+        self.stream.as_mut().expect("stream is only taken on release")
+    }
+
+    /// Hand the connection back to the pool
+    fn release(mut self) {
+        if let Some(stream) = self.stream.take() {
+            self.pool.checkin(self.origin, stream);
+        }
+    }
+}
+
+impl Drop for Lease<'_> {
+    fn drop(&mut self) {
+        self.pool.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Parsed `http://` or `https://` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    tls: bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self> {
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else if let Some(rest) = url.strip_prefix("https://") {
+            if cfg!(not(feature = "http")) {
+                return Err(NetworkPluginError::FeatureNotEnabled(format!("TLS requires the http feature: {}", url)));
+            }
+            (true, rest)
+        } else {
+            return Err(NetworkPluginError::InvalidRequest(format!("not an http URL: {}", url)));
+        };
+
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(index) if rest[index..].starts_with('/') => (&rest[..index], rest[index..].to_string()),
+            Some(index) => (&rest[..index], format!("/{}", &rest[index..])),
+            None => (rest, "/".to_string()),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| NetworkPluginError::InvalidRequest(format!("invalid port in URL: {}", url)))?;
+                (host, port)
+            }
+            None => (authority, if tls { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(NetworkPluginError::InvalidRequest(format!("missing host in URL: {}", url)));
+        }
+
+        Ok(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+
+    fn authority(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// Key of the connections that can serve this target
+    fn origin(&self) -> String {
+        format!("{}://{}", if self.tls { "https" } else { "http" }, self.authority())
+    }
+}
+
+/// HTTP client reusing connections per host
+pub struct HttpClient {
+    pool: ConnectionPool,
+    retry: RetryPolicy,
+    connection_timeout: Duration,
+    request_timeout: Duration,
+    max_body: usize,
+    #[cfg(feature = "http")]
+    tls: tokio_rustls::TlsConnector,
+}
+
+impl HttpClient {
+    /// Create a client with the timeouts and pool limits of `config`
+    pub async fn new(config: &NetworkPluginConfig) -> Result<Self> {
+        if config.pool_idle_timeout_secs == 0 && config.pool_max_idle_per_host > 0 {
+            return Err(NetworkPluginError::InvalidConfiguration(
+                "pool_idle_timeout_secs must be positive when connections are pooled".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            pool: ConnectionPool::new(config.pool_max_idle_per_host, Duration::from_secs(config.pool_idle_timeout_secs)),
+            retry: RetryPolicy::from_config(config),
+            connection_timeout: Duration::from_secs(config.connection_timeout_secs),
+            request_timeout: Duration::from_secs(config.request_timeout_secs),
+            max_body: config.max_response_body_bytes,
+            #[cfg(feature = "http")]
+            tls: tokio_rustls::TlsConnector::from(alloc::sync::Arc::new(default_tls_config())),
+        })
+    }
+
+    /// Replace the retry policy taken from the configuration
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Replace the TLS settings, e.g. to trust a private certificate
+    /// authority instead of the Mozilla roots
+    #[cfg(feature = "http")]
+    pub fn with_tls_config(mut self, config: alloc::sync::Arc<tokio_rustls::rustls::ClientConfig>) -> Self {
+        self.tls = tokio_rustls::TlsConnector::from(config);
+        self
+    }
+
+    /// GET `url` and return the body of a successful response
+    pub async fn get(&self, url: &str) -> Result<String> {
+        let response = self.request_with_retry("GET", url, None).await?;
+        Self::into_body(url, response)
+    }
+
+    /// POST `body` to `url` and return the body of a successful response
+    pub async fn post(&self, url: &str, body: &str) -> Result<String> {
+        let response = self.request_with_retry("POST", url, Some(body.as_bytes())).await?;
+        Self::into_body(url, response)
+    }
+
+    /// Send a request, retrying transient failures as the retry policy
+    /// allows, and return the last response or error
+    pub async fn request_with_retry(&self, method: &str, url: &str, body: Option<&[u8]>) -> Result<HttpResponse> {
+        let attempts = self.retry.attempts_for(method);
+        let mut attempt = 1;
+        loop {
+            let result = self.request(method, url, body).await;
+            let transient = match &result {
+                Ok(response) => RetryPolicy::is_retryable_status(response.status_code),
+                Err(error) => RetryPolicy::is_retryable_error(error),
+            };
+            if !transient || attempt >= attempts {
+                return result;
+            }
+            tokio::time::sleep(self.retry.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Send a request once and return the response whatever its status
+    pub async fn request(&self, method: &str, url: &str, body: Option<&[u8]>) -> Result<HttpResponse> {
+        let target = Target::parse(url)?;
+        let origin = target.origin();
+        let request = Self::encode(method, &target, body);
+
+        if let Some(stream) = self.pool.checkout(&origin) {
+            let lease = Lease::new(&self.pool, &origin, stream);
+            match self.exchange(lease, method, &request).await {
+                Ok(response) => return Ok(response),
+                // The server may have closed the connection while it sat in
+                // the pool; only requests safe to repeat are sent again
+                Err(NetworkPluginError::ConnectionFailed(_)) if is_idempotent(method) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        let stream = self.connect(&target).await?;
+        let lease = Lease::new(&self.pool, &origin, stream);
+        self.exchange(lease, method, &request).await
+    }
+
+    /// Connection pool usage
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
+    /// Health check
+    pub async fn health_check(&self) -> bool {
+        true
+    }
+
+    async fn connect(&self, target: &Target) -> Result<PooledStream> {
+        let stream = tokio::time::timeout(self.connection_timeout, self.open(target))
+            .await
+            .map_err(|_| NetworkPluginError::Timeout(format!("connecting to {}", target.authority())))??;
+        self.pool.created.fetch_add(1, Ordering::Relaxed);
+        Ok(BufReader::new(stream))
+    }
+
+    /// Open a TCP connection to `target`, with a TLS session on top for
+    /// `https`
+    async fn open(&self, target: &Target) -> Result<Transport> {
+        let stream = TcpStream::connect((target.host.as_str(), target.port))
+            .await
+            .map_err(|e| NetworkPluginError::ConnectionFailed(format!("{}: {}", target.authority(), e)))?;
+        // Requests are written in one piece; do not hold them back
+        let _ = stream.set_nodelay(true);
+        if !target.tls {
+            return Ok(Transport::Plain(stream));
+        }
+
+        #[cfg(feature = "http")]
+        {
+            let server_name = tokio_rustls::rustls::ServerName::try_from(target.host.as_str())
+                .map_err(|_| NetworkPluginError::InvalidRequest(format!("invalid TLS server name: {}", target.host)))?;
+            let stream = self.tls
+                .connect(server_name, stream)
+                .await
+                .map_err(|e| NetworkPluginError::TlsError(format!("{}: {}", target.authority(), e)))?;
+            Ok(Transport::Tls(Box::new(stream)))
+        }
+        #[cfg(not(feature = "http"))]
+        unreachable!("https URLs are refused without the http feature")
+    }
+
+    /// Send `request` on the leased connection and read the response,
+    /// returning the connection to the pool if it can be reused
+    async fn exchange(&self, mut lease: Lease<'_>, method: &str, request: &[u8]) -> Result<HttpResponse> {
+        let exchange = round_trip(lease.stream(), method, request, self.max_body);
+        let (response, keep_alive) = tokio::time::timeout(self.request_timeout, exchange)
+            .await
+            .map_err(|_| NetworkPluginError::Timeout(format!("no response from {} within {:?}", lease.origin, self.request_timeout)))??;
+        if keep_alive {
+            lease.release();
+        }
+        Ok(response)
+    }
+
+    fn encode(method: &str, target: &Target, body: Option<&[u8]>) -> Vec<u8> {
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: frys-plugin-network/{}\r\nConnection: keep-alive\r\n",
+            method,
+            target.path,
+            target.authority(),
+            env!("CARGO_PKG_VERSION"),
+        );
+        if let Some(body) = body {
+            request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+        request.push_str("\r\n");
+
+        let mut request = request.into_bytes();
+        if let Some(body) = body {
+            request.extend_from_slice(body);
+        }
+        request
+    }
+
+    fn into_body(url: &str, response: HttpResponse) -> Result<String> {
+        if !(200..300).contains(&response.status_code) {
+            return Err(NetworkPluginError::InvalidResponse(format!("HTTP {} from {}", response.status_code, url)));
+        }
+        Ok(String::from_utf8_lossy(&response.body.unwrap_or_default()).into_owned())
+    }
+}
+
+/// Whether repeating a `method` request has the same effect as sending it once
+pub(crate) fn is_idempotent(method: &str) -> bool {
+    matches!(method, "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS" | "TRACE")
+}
+
+/// TLS settings trusting the Mozilla root certificates
+#[cfg(feature = "http")]
+fn default_tls_config() -> tokio_rustls::rustls::ClientConfig {
+    use tokio_rustls::rustls;
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+    rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth()
+}
+
+fn io_error(error: std::io::Error) -> NetworkPluginError {
+    NetworkPluginError::ConnectionFailed(error.to_string())
+}
+
+fn body_too_large(max_body: usize) -> NetworkPluginError {
+    NetworkPluginError::InvalidResponse(format!("response body exceeds {} bytes", max_body))
+}
+
+/// Write a request and read its response, refusing bodies over
+/// `max_body` bytes. The flag tells whether the connection may carry
+/// another request.
+async fn round_trip(stream: &mut PooledStream, method: &str, request: &[u8], max_body: usize) -> Result<(HttpResponse, bool)> {
+    stream.get_mut().write_all(request).await.map_err(io_error)?;
+    stream.get_mut().flush().await.map_err(io_error)?;
+
+    let mut line = String::new();
+    if stream.read_line(&mut line).await.map_err(io_error)? == 0 {
+        return Err(NetworkPluginError::ConnectionFailed("connection closed before the response".to_string()));
+    }
+    let mut parts = line.trim_end().splitn(3, ' ');
+    let version = parts.next().unwrap_or_default().to_string();
+    let status_code: u16 = parts
+        .next()
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| NetworkPluginError::ProtocolError(format!("malformed status line: {:?}", line.trim_end())))?;
+
+    let mut headers = BTreeMap::new();
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await.map_err(io_error)? == 0 {
+            return Err(NetworkPluginError::ProtocolError("connection closed inside the headers".to_string()));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| NetworkPluginError::ProtocolError(format!("malformed header: {:?}", header)))?;
+        headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+
+    let connection = headers.get("connection").map(|value| value.to_ascii_lowercase());
+    let mut keep_alive = match version.as_str() {
+        "HTTP/1.1" => connection.as_deref() != Some("close"),
+        _ => connection.as_deref() == Some("keep-alive"),
+    };
+
+    let body = if method == "HEAD" || status_code == 204 || status_code == 304 || (100..200).contains(&status_code) {
+        Vec::new()
+    } else if headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"))
+    {
+        read_chunked(stream, max_body).await?
+    } else if let Some(length) = headers.get("content-length") {
+        let length: usize = length
+            .parse()
+            .map_err(|_| NetworkPluginError::ProtocolError(format!("invalid content-length: {}", length)))?;
+        if length > max_body {
+            return Err(body_too_large(max_body));
+        }
+        let mut body = alloc::vec![0; length];
+        stream.read_exact(&mut body).await.map_err(io_error)?;
+        body
+    } else {
+        // The body runs until the server closes the connection
+        keep_alive = false;
+        let mut body = Vec::new();
+        let limit = u64::try_from(max_body).unwrap_or(u64::MAX).saturating_add(1);
+        (&mut *stream).take(limit).read_to_end(&mut body).await.map_err(io_error)?;
+        if body.len() > max_body {
+            return Err(body_too_large(max_body));
+        }
+        body
+    };
+
+    Ok((
+        HttpResponse {
+            status_code,
+            headers,
+            body: Some(body),
+        },
+        keep_alive,
+    ))
+}
+
+/// Read a `Transfer-Encoding: chunked` body of at most `max_body` bytes,
+/// trailers included
+async fn read_chunked(stream: &mut PooledStream, max_body: usize) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await.map_err(io_error)?;
+        let size = line.trim_end().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size.trim(), 16)
+            .map_err(|_| NetworkPluginError::ProtocolError(format!("invalid chunk size: {:?}", line.trim_end())))?;
+        if size == 0 {
+            break;
+        }
+        let start = body.len();
+        let end = start.checked_add(size).filter(|&end| end <= max_body).ok_or_else(|| body_too_large(max_body))?;
+        body.resize(end, 0);
+        stream.read_exact(&mut body[start..]).await.map_err(io_error)?;
+        let mut crlf = [0; 2];
+        stream.read_exact(&mut crlf).await.map_err(io_error)?;
+    }
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await.map_err(io_error)? == 0 || line.trim_end().is_empty() {
+            return Ok(body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use tokio::net::TcpListener;
+
+    /// Keep-alive server answering every request with "ok", counting the
+    /// connections it accepts
+    async fn mock_host() -> (String, Arc<AtomicUsize>) {
+        let (url, accepted, _) = flaky_host(0).await;
+        (url, accepted)
+    }
+
+    /// Like [`mock_host`], but the first `failures` requests get a 503.
+    /// Returns the URL and the connection and request counters.
+    async fn flaky_host(failures: usize) -> (String, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (counter, served) = (accepted.clone(), requests.clone());
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(serve(BufReader::new(socket), served.clone(), failures));
+            }
+        });
+        (format!("http://{}/status", address), accepted, requests)
+    }
+
+    /// Answer the requests on one connection, the first `failures` of all
+    /// counted in `served` with a 503
+    async fn serve<S: AsyncRead + AsyncWrite + Unpin>(mut socket: BufReader<S>, served: Arc<AtomicUsize>, failures: usize) {
+        let mut line = String::new();
+        loop {
+            // Requests carry no body: read up to the blank line
+            loop {
+                line.clear();
+                match socket.read_line(&mut line).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(_) if line == "\r\n" => break,
+                    Ok(_) => {}
+                }
+            }
+            let reply: &[u8] = if served.fetch_add(1, Ordering::SeqCst) < failures {
+                b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n"
+            } else {
+                b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok"
+            };
+            if socket.get_mut().write_all(reply).await.is_err() || socket.get_mut().flush().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Server sending `reply` to the first request on each connection and
+    /// closing it
+    async fn canned_host(reply: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut socket = BufReader::new(socket);
+                    let mut line = String::new();
+                    while socket.read_line(&mut line).await.is_ok_and(|read| read > 0) && line != "\r\n" {
+                        line.clear();
+                    }
+                    let _ = socket.get_mut().write_all(reply).await;
+                    let _ = socket.get_mut().shutdown().await;
+                });
+            }
+        });
+        format!("http://{}/", address)
+    }
+
+    fn config(max_idle_per_host: usize, idle_timeout_secs: u64) -> NetworkPluginConfig {
+        NetworkPluginConfig {
+            pool_max_idle_per_host: max_idle_per_host,
+            pool_idle_timeout_secs: idle_timeout_secs,
+            connection_timeout_secs: 5,
+            request_timeout_secs: 5,
+            retry_base_delay_ms: 1,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_to_one_host_reuse_connections() {
+        let (url, accepted) = mock_host().await;
+        let client = HttpClient::new(&config(4, 90)).await.unwrap();
+
+        for _ in 0..20 {
+            assert_eq!(client.get(&url).await.unwrap(), "ok");
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+        assert_eq!(client.pool_stats(), PoolStats { active: 0, idle: 1, created: 1 });
+    }
+
+    #[tokio::test]
+    async fn test_idle_limit_bounds_pooled_connections() {
+        let (url, accepted) = mock_host().await;
+        let client = HttpClient::new(&config(2, 90)).await.unwrap();
+
+        for _ in 0..5 {
+            let results = futures::future::join_all((0..6).map(|_| client.get(&url))).await;
+            assert!(results.into_iter().all(|body| body.unwrap() == "ok"));
+        }
+
+        let stats = client.pool_stats();
+        assert_eq!(stats.active, 0);
+        assert!(stats.idle <= 2);
+        assert_eq!(stats.created, accepted.load(Ordering::SeqCst) as u64);
+        assert!(stats.created < 30, "{} connections for 30 requests", stats.created);
+    }
+
+    #[tokio::test]
+    async fn test_pooling_disabled_opens_a_connection_per_request() {
+        let (url, accepted) = mock_host().await;
+        let client = HttpClient::new(&config(0, 90)).await.unwrap();
+
+        for _ in 0..3 {
+            client.get(&url).await.unwrap();
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+        assert_eq!(client.pool_stats().idle, 0);
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        let (url, _, requests) = flaky_host(2).await;
+        let client = HttpClient::new(&config(4, 90)).await.unwrap();
+
+        assert_eq!(client.get(&url).await.unwrap(), "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_final_failure_is_surfaced_after_last_attempt() {
+        let (url, _, requests) = flaky_host(usize::MAX).await;
+        let client = HttpClient::new(&config(4, 90)).await.unwrap();
+
+        let error = client.get(&url).await.unwrap_err();
+        assert!(error.to_string().contains("503"), "{}", error);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_post_is_not_retried_by_default() {
+        let (url, _, requests) = flaky_host(2).await;
+        let client = HttpClient::new(&config(4, 90)).await.unwrap();
+
+        assert!(client.post(&url, "").await.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let client = client.with_retry_policy(RetryPolicy {
+            retry_non_idempotent: true,
+            base_delay: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        });
+        assert_eq!(client.post(&url, "").await.unwrap(), "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_bodies_over_the_limit_are_refused() {
+        let client = HttpClient::new(&NetworkPluginConfig {
+            max_response_body_bytes: 16,
+            ..config(4, 90)
+        })
+        .await
+        .unwrap();
+
+        for reply in [
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\n"[..],
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\n",
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n8\r\n01234567\r\n9\r\n012345678\r\n0\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n0123456789abcdefg",
+        ] {
+            let url = canned_host(reply).await;
+            let error = client.get(&url).await.unwrap_err();
+            assert!(error.to_string().contains("exceeds 16 bytes"), "{}", error);
+        }
+
+        let url = canned_host(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n0123456789abcdef").await;
+        assert_eq!(client.get(&url).await.unwrap(), "0123456789abcdef");
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_https_requests_reuse_tls_connections() {
+        use tokio_rustls::rustls;
+
+        let certificate = rcgen::generate_simple_self_signed(alloc::vec!["localhost".to_string()]).unwrap();
+        let der = rustls::Certificate(certificate.serialize_der().unwrap());
+        let key = rustls::PrivateKey(certificate.serialize_private_key_der());
+        let server = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(alloc::vec![der.clone()], key)
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://localhost:{}/status", listener.local_addr().unwrap().port());
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(stream) = acceptor.accept(socket).await {
+                        serve(BufReader::new(stream), Arc::default(), 0).await;
+                    }
+                });
+            }
+        });
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(&der).unwrap();
+        let tls = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let client = HttpClient::new(&config(4, 90)).await.unwrap().with_tls_config(Arc::new(tls));
+        for _ in 0..3 {
+            assert_eq!(client.get(&url).await.unwrap(), "ok");
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        // The Mozilla roots do not vouch for a self-signed certificate
+        let client = HttpClient::new(&config(4, 90)).await.unwrap();
+        assert!(matches!(client.get(&url).await, Err(NetworkPluginError::TlsError(_))));
+    }
+
+    #[test]
+    fn test_target_parsing() {
+        let target = Target::parse("http://example.com:8080/a/b?c=d").unwrap();
+        assert_eq!(target.authority(), "example.com:8080");
+        assert_eq!(target.path, "/a/b?c=d");
+        assert_eq!(Target::parse("http://example.com?q=1").unwrap().path, "/?q=1");
+        assert_eq!(Target::parse("http://example.com").unwrap().port, 80);
+        #[cfg(feature = "http")]
+        assert_eq!(Target::parse("https://example.com").unwrap().origin(), "https://example.com:443");
+        assert!(Target::parse("ftp://example.com").is_err());
+    }
+}
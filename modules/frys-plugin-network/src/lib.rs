@@ -71,6 +71,9 @@ mod service_discovery;
 mod api_gateway;
 mod security;
 mod monitoring;
+mod retry;
+#[cfg(feature = "websocket")]
+mod ws_client;
 mod config;
 
 // Public API
@@ -83,6 +86,9 @@ pub use service_discovery::*;
 pub use api_gateway::*;
 pub use security::*;
 pub use monitoring::*;
+pub use retry::*;
+#[cfg(feature = "websocket")]
+pub use ws_client::*;
 pub use config::*;
 
 // Error types
@@ -101,6 +107,13 @@ pub const DEFAULT_CONNECTION_TIMEOUT_SECS: u64 = 30;
 pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 60;
 pub const DEFAULT_RATE_LIMIT_REQUESTS: u32 = 100;
 pub const DEFAULT_CIRCUIT_BREAKER_THRESHOLD: u32 = 5;
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 16;
+pub const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+pub const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 3;
+pub const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 100;
+pub const DEFAULT_RETRY_MAX_DELAY_MS: u64 = 5000;
+pub const DEFAULT_MAX_RESPONSE_BODY_BYTES: usize = 16 * 1024 * 1024; // 16MB
+pub const DEFAULT_WS_MAX_QUEUED_MESSAGES: usize = 1000;
 
 #[cfg(test)]
 mod tests {
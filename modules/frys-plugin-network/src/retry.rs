@@ -0,0 +1,113 @@
+//! Retry policy for transient HTTP failures
+//!
+//! A reset connection or a 502/503/504 from an overloaded upstream often
+//! succeeds when tried again a little later. [`RetryPolicy`] decides which
+//! failures are worth another attempt and how long to wait before it:
+//! exponential backoff from `base_delay`, capped at `max_delay`, with random
+//! jitter so clients that failed together do not retry in lockstep.
+//! Requests whose method is not idempotent are sent once unless
+//! `retry_non_idempotent` is set, since the server may have acted on them
+//! before failing.
+
+use crate::*;
+use ::core::time::Duration;
+use rand::Rng;
+
+/// When and how often to retry a failed request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts per request, the first included
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub base_delay: Duration,
+    /// Upper bound on any delay
+    pub max_delay: Duration,
+    /// Retry methods such as POST that may not be safe to repeat
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_DELAY_MS),
+            max_delay: Duration::from_millis(DEFAULT_RETRY_MAX_DELAY_MS),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy described by `config`
+    pub fn from_config(config: &NetworkPluginConfig) -> Self {
+        Self {
+            max_attempts: config.retry_max_attempts.max(1),
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+            retry_non_idempotent: config.retry_non_idempotent,
+        }
+    }
+
+    /// Attempts allowed for a `method` request
+    pub fn attempts_for(&self, method: &str) -> u32 {
+        if self.retry_non_idempotent || is_idempotent(method) {
+            self.max_attempts.max(1)
+        } else {
+            1
+        }
+    }
+
+    /// Whether a response with `status_code` signals a transient failure
+    pub fn is_retryable_status(status_code: u16) -> bool {
+        matches!(status_code, 502..=504)
+    }
+
+    /// Whether `error` signals a transient failure
+    pub fn is_retryable_error(error: &NetworkPluginError) -> bool {
+        matches!(error, NetworkPluginError::ConnectionFailed(_))
+    }
+
+    /// Delay before retry number `retry` (1 for the first): half of the
+    /// exponential step is fixed and half is random
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        let step = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let half = step / 2;
+        half + half.mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_within_bounds() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            ..RetryPolicy::default()
+        };
+
+        for (retry, step) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (40, 1000)] {
+            let delay = policy.backoff(retry);
+            assert!(delay >= Duration::from_millis(step / 2), "retry {}: {:?}", retry, delay);
+            assert!(delay <= Duration::from_millis(step), "retry {}: {:?}", retry, delay);
+        }
+    }
+
+    #[test]
+    fn test_only_idempotent_methods_are_retried_by_default() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.attempts_for("GET"), 3);
+        assert_eq!(policy.attempts_for("POST"), 1);
+
+        let policy = RetryPolicy {
+            retry_non_idempotent: true,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.attempts_for("POST"), 3);
+        assert!(RetryPolicy::is_retryable_status(503));
+        assert!(!RetryPolicy::is_retryable_status(500));
+    }
+}
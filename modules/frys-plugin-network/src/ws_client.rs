@@ -0,0 +1,438 @@
+//! WebSocket client that reconnects on its own
+//!
+//! A [`WsClient`] keeps one connection open from a background task. When
+//! the connection drops it reconnects after a delay taken from its
+//! [`RetryPolicy`]: exponential backoff with jitter, giving up after
+//! `max_attempts` consecutive failed reconnects. Once reconnected it
+//! subscribes again to every topic the caller had subscribed to, then
+//! sends the messages queued while it was away. The queue holds at most
+//! `max_queued` messages; beyond that the oldest is dropped and reported
+//! as [`WsEvent::MessageDropped`]. Every connection attempt, handshake
+//! included, is bounded by `connect_timeout`.
+//!
+//! Topics are subscribed with a `subscribe <topic>` text frame and dropped
+//! with `unsubscribe <topic>`. Connection changes and incoming text frames
+//! reach the caller as [`WsEvent`]s.
+
+use crate::*;
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::string::String;
+use ::core::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Connection change or message seen by a [`WsClient`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsEvent {
+    /// Connected, with subscriptions restored
+    Connected,
+    /// The connection dropped
+    Disconnected(String),
+    /// Waiting `delay` before reconnect attempt `attempt` (1 for the first)
+    Reconnecting {
+        /// Attempt about to be made
+        attempt: u32,
+        /// Delay before it
+        delay: Duration,
+    },
+    /// Every reconnect attempt failed; the client has stopped
+    GaveUp {
+        /// Attempts made
+        attempts: u32,
+    },
+    /// Text frame from the server
+    Message(String),
+    /// A message queued while disconnected was discarded to make room
+    MessageDropped(String),
+}
+
+/// Limits of a [`WsClient`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsClientConfig {
+    /// Time allowed for each connection attempt, handshake included
+    pub connect_timeout: Duration,
+    /// Messages kept while disconnected
+    pub max_queued: usize,
+}
+
+impl Default for WsClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECTION_TIMEOUT_SECS),
+            max_queued: DEFAULT_WS_MAX_QUEUED_MESSAGES,
+        }
+    }
+}
+
+/// Request from the caller to the connection task
+enum Command {
+    Subscribe(String),
+    Unsubscribe(String),
+    Send(String),
+    Close,
+}
+
+/// Why a connected session ended
+enum SessionEnd {
+    Dropped(String),
+    Closed,
+}
+
+/// WebSocket client that reconnects and resubscribes after a disconnect
+pub struct WsClient {
+    commands: mpsc::UnboundedSender<Command>,
+    events: mpsc::UnboundedReceiver<WsEvent>,
+    task: JoinHandle<()>,
+}
+
+impl ::core::fmt::Debug for WsClient {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("WsClient").finish_non_exhaustive()
+    }
+}
+
+impl WsClient {
+    /// Connect to `url`, reconnecting as `retry` allows whenever the
+    /// connection drops. Fails if the first connection cannot be made.
+    pub async fn connect(url: &str, retry: RetryPolicy) -> Result<Self> {
+        Self::connect_with_config(url, retry, WsClientConfig::default()).await
+    }
+
+    /// Connect to `url` like [`connect`](Self::connect), within the limits
+    /// of `config`
+    pub async fn connect_with_config(url: &str, retry: RetryPolicy, config: WsClientConfig) -> Result<Self> {
+        let socket = open(url, config.connect_timeout).await?;
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (event_tx, events) = mpsc::unbounded_channel();
+        let connection = Connection {
+            url: url.to_string(),
+            retry,
+            config,
+            commands: command_rx,
+            events: event_tx,
+            topics: BTreeSet::new(),
+            queued: VecDeque::new(),
+        };
+        let task = tokio::spawn(connection.run(socket));
+        Ok(Self { commands, events, task })
+    }
+
+    /// Subscribe to `topic`, now and after every reconnect
+    pub fn subscribe(&self, topic: &str) -> Result<()> {
+        self.command(Command::Subscribe(topic.to_string()))
+    }
+
+    /// Stop receiving `topic`
+    pub fn unsubscribe(&self, topic: &str) -> Result<()> {
+        self.command(Command::Unsubscribe(topic.to_string()))
+    }
+
+    /// Send a text frame, once reconnected if the connection is down
+    pub fn send(&self, text: &str) -> Result<()> {
+        self.command(Command::Send(text.to_string()))
+    }
+
+    /// Next connection change or message, or `None` once the client has
+    /// stopped and every event was taken
+    pub async fn next_event(&mut self) -> Option<WsEvent> {
+        self.events.recv().await
+    }
+
+    /// Close the connection and stop reconnecting
+    pub async fn close(mut self) -> Result<()> {
+        // The task may already have given up
+        let _ = self.commands.send(Command::Close);
+        (&mut self.task)
+            .await
+            .map_err(|e| NetworkPluginError::ConnectionFailed(format!("WebSocket client task failed: {}", e)))
+    }
+
+    fn command(&self, command: Command) -> Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| NetworkPluginError::ConnectionFailed("WebSocket client has stopped".to_string()))
+    }
+}
+
+impl Drop for WsClient {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn open(url: &str, timeout: Duration) -> Result<Socket> {
+    let (socket, _) = tokio::time::timeout(timeout, tokio_tungstenite::connect_async(url))
+        .await
+        .map_err(|_| NetworkPluginError::Timeout(format!("connecting to {} took over {:?}", url, timeout)))?
+        .map_err(|e| NetworkPluginError::ConnectionFailed(format!("{}: {}", url, e)))?;
+    Ok(socket)
+}
+
+/// State of the background connection task
+struct Connection {
+    url: String,
+    retry: RetryPolicy,
+    config: WsClientConfig,
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::UnboundedSender<WsEvent>,
+    /// Topics to restore after a reconnect
+    topics: BTreeSet<String>,
+    /// Frames sent while disconnected, oldest first
+    queued: VecDeque<String>,
+}
+
+impl Connection {
+    async fn run(mut self, mut socket: Socket) {
+        loop {
+            let reason = match self.session(&mut socket).await {
+                SessionEnd::Dropped(reason) => reason,
+                SessionEnd::Closed => return,
+            };
+            self.emit(WsEvent::Disconnected(reason));
+            match self.reconnect().await {
+                Some(reconnected) => socket = reconnected,
+                None => return,
+            }
+        }
+    }
+
+    /// Serve one connection until it drops or the caller closes it
+    async fn session(&mut self, socket: &mut Socket) -> SessionEnd {
+        for topic in &self.topics {
+            if let Err(e) = socket.send(Message::Text(alloc::format!("subscribe {}", topic))).await {
+                return SessionEnd::Dropped(e.to_string());
+            }
+        }
+        while let Some(text) = self.queued.front() {
+            if let Err(e) = socket.send(Message::Text(text.clone())).await {
+                return SessionEnd::Dropped(e.to_string());
+            }
+            self.queued.pop_front();
+        }
+        self.emit(WsEvent::Connected);
+
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => {
+                    // Subscriptions are restored from `topics`; only
+                    // messages need queueing if the send fails
+                    let (frame, message) = match command {
+                        Some(Command::Subscribe(topic)) => {
+                            let frame = alloc::format!("subscribe {}", topic);
+                            self.topics.insert(topic);
+                            (frame, false)
+                        }
+                        Some(Command::Unsubscribe(topic)) => {
+                            let frame = alloc::format!("unsubscribe {}", topic);
+                            self.topics.remove(&topic);
+                            (frame, false)
+                        }
+                        Some(Command::Send(text)) => (text, true),
+                        Some(Command::Close) | None => {
+                            let _ = socket.close(None).await;
+                            return SessionEnd::Closed;
+                        }
+                    };
+                    if let Err(e) = socket.send(Message::Text(frame.clone())).await {
+                        if message {
+                            self.enqueue(frame);
+                        }
+                        return SessionEnd::Dropped(e.to_string());
+                    }
+                }
+                message = socket.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.emit(WsEvent::Message(text)),
+                    Some(Ok(Message::Close(frame))) => {
+                        let reason = frame.map_or_else(|| "closed by server".to_string(), |frame| frame.reason.into_owned());
+                        return SessionEnd::Dropped(reason);
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return SessionEnd::Dropped(e.to_string()),
+                    None => return SessionEnd::Dropped("connection closed".to_string()),
+                },
+            }
+        }
+    }
+
+    /// Reconnect with backoff, or `None` if the caller closed the client
+    /// or every attempt failed
+    async fn reconnect(&mut self) -> Option<Socket> {
+        let attempts = self.retry.max_attempts.max(1);
+        for attempt in 1..=attempts {
+            let delay = self.retry.backoff(attempt);
+            self.emit(WsEvent::Reconnecting { attempt, delay });
+
+            let sleep = tokio::time::sleep(delay);
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    () = &mut sleep => break,
+                    command = self.commands.recv() => match command {
+                        Some(Command::Subscribe(topic)) => {
+                            self.topics.insert(topic);
+                        }
+                        Some(Command::Unsubscribe(topic)) => {
+                            self.topics.remove(&topic);
+                        }
+                        Some(Command::Send(text)) => self.enqueue(text),
+                        Some(Command::Close) | None => return None,
+                    },
+                }
+            }
+
+            if let Ok(socket) = open(&self.url, self.config.connect_timeout).await {
+                return Some(socket);
+            }
+        }
+        self.emit(WsEvent::GaveUp { attempts });
+        None
+    }
+
+    /// Queue a message for the next connection, dropping the oldest once
+    /// the queue is full
+    fn enqueue(&mut self, text: String) {
+        if self.queued.len() >= self.config.max_queued.max(1) {
+            if let Some(dropped) = self.queued.pop_front() {
+                self.emit(WsEvent::MessageDropped(dropped));
+            }
+        }
+        self.queued.push_back(text);
+    }
+
+    fn emit(&self, event: WsEvent) {
+        // The caller may have stopped listening
+        let _ = self.events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn next_event(client: &mut WsClient) -> WsEvent {
+        tokio::time::timeout(Duration::from_secs(5), client.next_event())
+            .await
+            .expect("event within timeout")
+            .expect("client running")
+    }
+
+    async fn next_text(socket: &mut WebSocketStream<TcpStream>) -> String {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => text,
+            other => panic!("expected a text frame, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_reconnects_and_resubscribes_after_drop() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut received = Vec::new();
+            for session in 1..=2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let frame = next_text(&mut socket).await;
+                let topic = frame.strip_prefix("subscribe ").expect("subscription first").to_string();
+                received.push(frame);
+                socket.send(Message::Text(format!("{}:{}", topic, session))).await.unwrap();
+                if session == 1 {
+                    // Drop the connection without a close handshake
+                    drop(socket);
+                } else {
+                    received.push(next_text(&mut socket).await);
+                }
+            }
+            received
+        });
+
+        let retry = RetryPolicy {
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            ..RetryPolicy::default()
+        };
+        let mut client = WsClient::connect(&url, retry).await.unwrap();
+        assert_eq!(next_event(&mut client).await, WsEvent::Connected);
+        client.subscribe("prices").unwrap();
+        assert_eq!(next_event(&mut client).await, WsEvent::Message("prices:1".to_string()));
+
+        assert!(matches!(next_event(&mut client).await, WsEvent::Disconnected(_)));
+        client.send("hello again").unwrap();
+        match next_event(&mut client).await {
+            WsEvent::Reconnecting { attempt, delay } => {
+                assert_eq!(attempt, 1);
+                assert!(delay <= Duration::from_millis(10));
+            }
+            other => panic!("expected a reconnect, got {:?}", other),
+        }
+        assert_eq!(next_event(&mut client).await, WsEvent::Connected);
+        assert_eq!(next_event(&mut client).await, WsEvent::Message("prices:2".to_string()));
+
+        let received = server.await.unwrap();
+        assert_eq!(received, ["subscribe prices", "subscribe prices", "hello again"]);
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_queue_drops_oldest_message_when_full() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(tokio_tungstenite::accept_async(stream).await.unwrap());
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            [next_text(&mut socket).await, next_text(&mut socket).await]
+        });
+
+        let retry = RetryPolicy {
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_millis(200),
+            ..RetryPolicy::default()
+        };
+        let config = WsClientConfig {
+            max_queued: 2,
+            ..WsClientConfig::default()
+        };
+        let mut client = WsClient::connect_with_config(&url, retry, config).await.unwrap();
+        assert_eq!(next_event(&mut client).await, WsEvent::Connected);
+        assert!(matches!(next_event(&mut client).await, WsEvent::Disconnected(_)));
+        assert!(matches!(next_event(&mut client).await, WsEvent::Reconnecting { .. }));
+
+        for text in ["one", "two", "three"] {
+            client.send(text).unwrap();
+        }
+        assert_eq!(next_event(&mut client).await, WsEvent::MessageDropped("one".to_string()));
+        assert_eq!(next_event(&mut client).await, WsEvent::Connected);
+        assert_eq!(server.await.unwrap(), ["two", "three"]);
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_times_out_without_handshake() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        // Accept the TCP connection but never answer the upgrade
+        let _server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            ::core::future::pending::<()>().await;
+            drop(stream);
+        });
+
+        let config = WsClientConfig {
+            connect_timeout: Duration::from_millis(50),
+            ..WsClientConfig::default()
+        };
+        let result = WsClient::connect_with_config(&url, RetryPolicy::default(), config).await;
+        assert!(matches!(result, Err(NetworkPluginError::Timeout(_))));
+    }
+}
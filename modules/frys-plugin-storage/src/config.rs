@@ -32,6 +32,8 @@ pub struct StoragePluginConfig {
     pub cache_size_mb: usize,
     /// Chunk size for large file operations
     pub chunk_size: usize,
+    /// Store identical content once, keyed by its hash
+    pub enable_deduplication: bool,
     /// Database URL (for database backend)
     pub database_url: Option<String>,
     /// Cloud storage configuration
@@ -50,6 +52,7 @@ impl Default for StoragePluginConfig {
             enable_caching: true,
             cache_size_mb: 100,
             chunk_size: 64 * 1024,
+            enable_deduplication: false,
             database_url: None,
             cloud_config: None,
             distributed_nodes: None,
@@ -4,6 +4,8 @@ use crate::*;
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
+#[cfg(feature = "dedup")]
+use crate::dedup;
 
 /// Main Storage Plugin structure
 pub struct StoragePlugin {
@@ -84,6 +86,10 @@ impl StoragePlugin {
             None
         };
 
+        if config.enable_deduplication && !cfg!(feature = "dedup") {
+            return Err(StoragePluginError::FeatureNotEnabled("Deduplication not enabled".to_string()));
+        }
+
         Ok(Self {
             config,
             backends,
@@ -109,14 +115,34 @@ impl StoragePlugin {
             }
         }
 
-        // Process data (compression, encryption)
-        let processed_data = self.process_data_for_storage(data).await?;
+        // Chunks of a streamed object stored under the key are stale now
+        #[cfg(feature = "file")]
+        let stale_chunks = self.streamed_chunks(key).await?;
 
-        // Store using active backend
-        let backend = self.backends.get_mut(&self.active_backend)
-            .ok_or(StoragePluginError::BackendNotAvailable)?;
+        #[cfg(feature = "dedup")]
+        let deduplicated = if self.config.enable_deduplication {
+            Some(self.store_deduplicated(key, data).await?)
+        } else {
+            None
+        };
+        #[cfg(not(feature = "dedup"))]
+        let deduplicated = None;
+
+        let (storage_key, stored_size) = match deduplicated {
+            Some(stored) => stored,
+            None => {
+                // Process data (compression, encryption)
+                let processed_data = self.process_data_for_storage(data).await?;
 
-        let storage_key = backend.store(key, &processed_data).await?;
+                // Store using active backend
+                let backend = self.backends.get_mut(&self.active_backend)
+                    .ok_or(StoragePluginError::BackendNotAvailable)?;
+
+                (backend.store(key, &processed_data).await?, processed_data.len())
+            }
+        };
+        #[cfg(feature = "file")]
+        self.delete_chunks(key, 0..stale_chunks).await?;
         let duration = self.current_timestamp() - start_time;
 
         // Update cache
@@ -125,7 +151,7 @@ impl StoragePlugin {
         }
 
         // Record metrics
-        self.metrics.record_store_operation(duration, processed_data.len());
+        self.metrics.record_store_operation(duration, stored_size);
 
         Ok(storage_key)
     }
@@ -143,11 +169,21 @@ impl StoragePlugin {
             }
         }
 
+        // Follow deduplication references to the blob
+        #[cfg(feature = "dedup")]
+        let blob_key = if self.config.enable_deduplication {
+            self.reference_at(key).await?.map(|hash| dedup::blob_key(&hash))
+        } else {
+            None
+        };
+        #[cfg(not(feature = "dedup"))]
+        let blob_key: Option<String> = None;
+
         // Retrieve from backend
         let backend = self.backends.get_mut(&self.active_backend)
             .ok_or(StoragePluginError::BackendNotAvailable)?;
 
-        let mut raw_data = backend.retrieve(key).await?;
+        let mut raw_data = backend.retrieve(blob_key.as_deref().unwrap_or(key)).await?;
         let duration = self.current_timestamp() - start_time;
 
         // Process data (decryption, decompression)
@@ -168,11 +204,27 @@ impl StoragePlugin {
     pub async fn delete(&mut self, key: &str) -> Result<()> {
         let start_time = self.current_timestamp();
 
+        #[cfg(feature = "dedup")]
+        if self.config.enable_deduplication {
+            if let Some(hash) = dedup::managed_hash(key) {
+                if self.blob_refcount(hash).await? > 0 {
+                    return Err(StoragePluginError::BlobInUse(key.to_string()));
+                }
+            } else if dedup::is_managed_key(key) {
+                return Err(StoragePluginError::ReservedKey(key.to_string()));
+            }
+            self.drop_reference(key).await?;
+        }
+        #[cfg(feature = "file")]
+        let chunks = self.streamed_chunks(key).await?;
+
         // Delete from backend
         let backend = self.backends.get_mut(&self.active_backend)
             .ok_or(StoragePluginError::BackendNotAvailable)?;
 
         backend.delete(key).await?;
+        #[cfg(feature = "file")]
+        self.delete_chunks(key, 0..chunks).await?;
         let duration = self.current_timestamp() - start_time;
 
         // Remove from cache
@@ -186,6 +238,131 @@ impl StoragePlugin {
         Ok(())
     }
 
+    /// Store everything `reader` yields under `key`, one chunk of
+    /// `chunk_size` bytes at a time.
+    ///
+    /// The object must be read back with [`StoragePlugin::retrieve_stream`].
+    #[cfg(feature = "file")]
+    pub async fn store_stream<R>(&mut self, key: &str, mut reader: R) -> Result<String>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        let start_time = self.current_timestamp();
+        #[cfg(feature = "dedup")]
+        if self.config.enable_deduplication && dedup::is_managed_key(key) {
+            return Err(StoragePluginError::ReservedKey(key.to_string()));
+        }
+        let chunk_size = if self.config.chunk_size == 0 { DEFAULT_CHUNK_SIZE } else { self.config.chunk_size };
+        let previous_chunks = self.streamed_chunks(key).await?;
+
+        let mut buffer = alloc::vec![0; chunk_size];
+        let mut crc = Crc32::new();
+        let mut manifest = Manifest {
+            total_len: 0,
+            crc: 0,
+            chunks: Vec::new(),
+        };
+        let mut stored_size = 0;
+        loop {
+            let len = read_chunk(&mut reader, &mut buffer).await?;
+            if len == 0 {
+                break;
+            }
+            let chunk = &buffer[..len];
+            crc.update(chunk);
+            manifest.chunks.push(ChunkInfo {
+                len,
+                crc: Crc32::checksum(chunk),
+            });
+            manifest.total_len += len as u64;
+
+            let processed_chunk = self.process_data_for_storage(chunk).await?;
+            stored_size += processed_chunk.len();
+            let backend = self.backends.get_mut(&self.active_backend)
+                .ok_or(StoragePluginError::BackendNotAvailable)?;
+            backend.store(&chunk_key(key, manifest.chunks.len() - 1), &processed_chunk).await?;
+        }
+        manifest.crc = crc.finish();
+
+        let processed_manifest = self.process_data_for_storage(&manifest.encode()).await?;
+        stored_size += processed_manifest.len();
+        let backend = self.backends.get_mut(&self.active_backend)
+            .ok_or(StoragePluginError::BackendNotAvailable)?;
+        let storage_key = backend.store(key, &processed_manifest).await?;
+        // A shorter stream leaves the previous object's later chunks behind
+        self.delete_chunks(key, manifest.chunks.len()..previous_chunks).await?;
+
+        // The manifest replaced whatever deduplicated object the key held
+        #[cfg(feature = "dedup")]
+        if self.config.enable_deduplication {
+            self.drop_reference(key).await?;
+        }
+        let duration = self.current_timestamp() - start_time;
+
+        // Whatever was cached under this key is stale now
+        if let Some(cache) = &mut self.cache_manager {
+            cache.remove(key);
+        }
+
+        self.metrics.record_store_operation(duration, stored_size);
+
+        Ok(storage_key)
+    }
+
+    /// Read an object stored with [`StoragePlugin::store_stream`].
+    ///
+    /// Chunks are fetched as the reader is polled and verified against their
+    /// checksums before any of their bytes are returned.
+    #[cfg(feature = "file")]
+    pub async fn retrieve_stream(&mut self, key: &str) -> Result<ObjectReader<'_>> {
+        let start_time = self.current_timestamp();
+        let manifest = self.retrieve_chunk(key).await?;
+        let manifest = Manifest::decode(key, &manifest)?;
+        let duration = self.current_timestamp() - start_time;
+
+        self.metrics.record_retrieve_operation(duration, manifest.total_len as usize);
+
+        Ok(ObjectReader::new(self, key, manifest))
+    }
+
+    /// Retrieve and decode one stored piece of a streamed object, bypassing
+    /// the cache
+    #[cfg(feature = "file")]
+    pub(crate) async fn retrieve_chunk(&mut self, key: &str) -> Result<Vec<u8>> {
+        let backend = self.backends.get_mut(&self.active_backend)
+            .ok_or(StoragePluginError::BackendNotAvailable)?;
+
+        let mut raw_data = backend.retrieve(key).await?;
+        self.process_data_for_retrieval(&mut raw_data).await
+    }
+
+    /// Number of chunks the object at `key` was streamed in, or zero if it
+    /// was not stored with [`StoragePlugin::store_stream`]
+    #[cfg(feature = "file")]
+    async fn streamed_chunks(&mut self, key: &str) -> Result<usize> {
+        let backend = self.backends.get_mut(&self.active_backend)
+            .ok_or(StoragePluginError::BackendNotAvailable)?;
+        if !backend.exists(key).await? {
+            return Ok(0);
+        }
+        let mut raw_data = backend.retrieve(key).await?;
+        Ok(self.process_data_for_retrieval(&mut raw_data).await
+            .ok()
+            .and_then(|stored| Manifest::decode(key, &stored).ok())
+            .map_or(0, |manifest| manifest.chunks.len()))
+    }
+
+    /// Delete the chunks in `indices` of the streamed object at `key`
+    #[cfg(feature = "file")]
+    async fn delete_chunks(&mut self, key: &str, indices: ::core::ops::Range<usize>) -> Result<()> {
+        let backend = self.backends.get_mut(&self.active_backend)
+            .ok_or(StoragePluginError::BackendNotAvailable)?;
+        for index in indices {
+            backend.delete(&chunk_key(key, index)).await?;
+        }
+        Ok(())
+    }
+
     /// List stored keys with optional prefix
     pub async fn list(&self, prefix: Option<&str>) -> Result<Vec<String>> {
         let backend = self.backends.get(&self.active_backend)
@@ -302,6 +479,102 @@ impl StoragePlugin {
         Ok(processed_data)
     }
 
+    /// Store `data` once under its content hash and record the reference to
+    /// it from `key`. Returns the storage key and the bytes written.
+    #[cfg(feature = "dedup")]
+    async fn store_deduplicated(&mut self, key: &str, data: &[u8]) -> Result<(String, usize)> {
+        if dedup::is_managed_key(key) {
+            return Err(StoragePluginError::ReservedKey(key.to_string()));
+        }
+        let hash = dedup::content_hash(data);
+
+        // Overwriting a key gives up the reference it held
+        let previous = self.reference_at(key).await?;
+        if previous.as_deref() == Some(hash.as_str()) {
+            return Ok((key.to_string(), 0));
+        }
+        if let Some(previous) = previous {
+            self.release_blob(&previous).await?;
+        }
+
+        let refs = self.blob_refcount(&hash).await?;
+        let mut stored_size = 0;
+        if refs == 0 {
+            let processed_data = self.process_data_for_storage(data).await?;
+            stored_size += processed_data.len();
+            self.active_backend_mut()?.store(&dedup::blob_key(&hash), &processed_data).await?;
+        }
+        self.set_blob_refcount(&hash, refs + 1).await?;
+
+        stored_size += hash.len();
+        let backend = self.active_backend_mut()?;
+        backend.store(&dedup::reference_key(key), hash.as_bytes()).await?;
+        let storage_key = backend.store(key, &[]).await?;
+        Ok((storage_key, stored_size))
+    }
+
+    /// Content hash `key` refers to, if it holds a deduplicated object
+    #[cfg(feature = "dedup")]
+    async fn reference_at(&mut self, key: &str) -> Result<Option<String>> {
+        let reference_key = dedup::reference_key(key);
+        let backend = self.active_backend_mut()?;
+        if !backend.exists(&reference_key).await? {
+            return Ok(None);
+        }
+        dedup::parse_hash(&backend.retrieve(&reference_key).await?)
+            .map(Some)
+            .ok_or_else(|| StoragePluginError::CorruptedData(format!("reference at {}", reference_key)))
+    }
+
+    /// Release the blob `key` refers to, if any, and forget the reference
+    #[cfg(feature = "dedup")]
+    async fn drop_reference(&mut self, key: &str) -> Result<()> {
+        if let Some(hash) = self.reference_at(key).await? {
+            self.release_blob(&hash).await?;
+            self.active_backend_mut()?.delete(&dedup::reference_key(key)).await?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "dedup")]
+    async fn blob_refcount(&mut self, hash: &str) -> Result<u64> {
+        let key = dedup::refcount_key(hash);
+        let backend = self.active_backend_mut()?;
+        if !backend.exists(&key).await? {
+            return Ok(0);
+        }
+        let count = backend.retrieve(&key).await?;
+        ::core::str::from_utf8(&count)
+            .ok()
+            .and_then(|count| count.parse().ok())
+            .ok_or_else(|| StoragePluginError::CorruptedData(format!("reference count at {}", key)))
+    }
+
+    #[cfg(feature = "dedup")]
+    async fn set_blob_refcount(&mut self, hash: &str, refs: u64) -> Result<()> {
+        let key = dedup::refcount_key(hash);
+        self.active_backend_mut()?.store(&key, refs.to_string().as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Drop one reference to a blob, deleting it with the last
+    #[cfg(feature = "dedup")]
+    async fn release_blob(&mut self, hash: &str) -> Result<()> {
+        let refs = self.blob_refcount(hash).await?;
+        if refs > 1 {
+            return self.set_blob_refcount(hash, refs - 1).await;
+        }
+        let backend = self.active_backend_mut()?;
+        backend.delete(&dedup::blob_key(hash)).await?;
+        backend.delete(&dedup::refcount_key(hash)).await
+    }
+
+    #[cfg(feature = "dedup")]
+    fn active_backend_mut(&mut self) -> Result<&mut Box<dyn StorageBackend>> {
+        self.backends.get_mut(&self.active_backend)
+            .ok_or(StoragePluginError::BackendNotAvailable)
+    }
+
     fn current_timestamp(&self) -> u64 {
         0 // Placeholder - would use actual timestamp
     }
@@ -424,4 +697,174 @@ mod tests {
         assert!(capabilities.contains(&"storage.store".to_string()));
         assert!(capabilities.contains(&"storage.retrieve".to_string()));
     }
+
+    type Objects = std::rc::Rc<::core::cell::RefCell<BTreeMap<String, Vec<u8>>>>;
+
+    /// Backend keeping objects in a map the test can reach into
+    struct MemoryBackend(Objects);
+
+    #[async_trait::async_trait(?Send)]
+    impl StorageBackend for MemoryBackend {
+        async fn store(&mut self, key: &str, data: &[u8]) -> Result<String> {
+            self.0.borrow_mut().insert(key.to_string(), data.to_vec());
+            Ok(key.to_string())
+        }
+
+        async fn retrieve(&mut self, key: &str) -> Result<Vec<u8>> {
+            self.0.borrow().get(key).cloned().ok_or_else(|| StoragePluginError::KeyNotFound(key.to_string()))
+        }
+
+        async fn delete(&mut self, key: &str) -> Result<()> {
+            self.0.borrow_mut().remove(key);
+            Ok(())
+        }
+
+        async fn list(&self, prefix: Option<&str>) -> Result<Vec<String>> {
+            Ok(self.0.borrow().keys().filter(|key| key.starts_with(prefix.unwrap_or(""))).cloned().collect())
+        }
+
+        async fn exists(&self, key: &str) -> Result<bool> {
+            Ok(self.0.borrow().contains_key(key))
+        }
+
+        async fn get_stats(&self) -> Result<BackendStats> {
+            Ok(BackendStats {
+                total_objects: self.0.borrow().len() as u64,
+                total_size_bytes: self.0.borrow().values().map(|data| data.len() as u64).sum(),
+                average_object_size: 0.0,
+                operations_count: 0,
+                error_count: 0,
+            })
+        }
+    }
+
+    /// Plugin over an empty [`MemoryBackend`], without cache, compression
+    /// or encryption
+    fn memory_plugin(config: StoragePluginConfig) -> (StoragePlugin, Objects) {
+        let objects = Objects::default();
+        let mut backends = BTreeMap::new();
+        backends.insert("memory".to_string(), Box::new(MemoryBackend(objects.clone())) as Box<dyn StorageBackend>);
+        let plugin = StoragePlugin {
+            config,
+            backends,
+            active_backend: "memory".to_string(),
+            cache_manager: None,
+            compression_manager: None,
+            encryption_manager: None,
+            metrics: StorageMetrics::new(),
+        };
+        (plugin, objects)
+    }
+
+    #[cfg(feature = "file")]
+    #[tokio::test]
+    async fn test_streamed_object_round_trip_and_corruption() {
+        use tokio::io::AsyncReadExt;
+
+        let (mut plugin, objects) = memory_plugin(StoragePluginConfig {
+            chunk_size: 1024,
+            ..Default::default()
+        });
+
+        let data: Vec<u8> = (0..5000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+        plugin.store_stream("blob", &data[..]).await.unwrap();
+        assert!(objects.borrow().contains_key("blob.chunk4"));
+        assert!(!objects.borrow().contains_key("blob.chunk5"));
+
+        let mut read_back = Vec::new();
+        {
+            let mut reader = plugin.retrieve_stream("blob").await.unwrap();
+            assert_eq!(reader.len(), 5000);
+            reader.read_to_end(&mut read_back).await.unwrap();
+        }
+        assert_eq!(read_back, data);
+
+        objects.borrow_mut().get_mut("blob.chunk2").unwrap()[10] ^= 0xFF;
+        let mut reader = plugin.retrieve_stream("blob").await.unwrap();
+        let mut read_back = Vec::new();
+        let error = reader.read_to_end(&mut read_back).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        let cause = error.get_ref().and_then(|e| e.downcast_ref::<StoragePluginError>());
+        assert!(matches!(cause, Some(StoragePluginError::CorruptedData(_))));
+        assert_eq!(read_back.len(), 2048, "nothing of the tampered chunk is handed out");
+    }
+
+    #[cfg(feature = "file")]
+    #[tokio::test]
+    async fn test_replaced_and_deleted_streams_leave_no_chunks() {
+        let (mut plugin, objects) = memory_plugin(StoragePluginConfig {
+            chunk_size: 1024,
+            ..Default::default()
+        });
+        let keys = |objects: &Objects| objects.borrow().keys().cloned().collect::<Vec<String>>();
+
+        plugin.store_stream("blob", &[7u8; 5000][..]).await.unwrap();
+        plugin.store_stream("blob", &[8u8; 1500][..]).await.unwrap();
+        assert_eq!(keys(&objects), ["blob", "blob.chunk0", "blob.chunk1"]);
+
+        plugin.delete("blob").await.unwrap();
+        assert!(objects.borrow().is_empty());
+
+        plugin.store_stream("blob", &[7u8; 3000][..]).await.unwrap();
+        plugin.store("blob", b"small enough to store whole").await.unwrap();
+        assert_eq!(keys(&objects), ["blob"]);
+    }
+
+    #[cfg(feature = "dedup")]
+    #[tokio::test]
+    async fn test_identical_content_is_stored_once() {
+        let (mut plugin, objects) = memory_plugin(StoragePluginConfig {
+            enable_deduplication: true,
+            ..Default::default()
+        });
+        let data = b"nightly backup of the same tree".to_vec();
+
+        assert_eq!(plugin.store("monday", &data).await.unwrap(), "monday");
+        assert_eq!(plugin.store("tuesday", &data).await.unwrap(), "tuesday");
+
+        let blobs: Vec<String> = objects
+            .borrow()
+            .keys()
+            .filter(|key| dedup::managed_hash(key).is_some() && !key.ends_with(".refs"))
+            .cloned()
+            .collect();
+        assert_eq!(blobs.len(), 1);
+        let refs_key = format!("{}.refs", blobs[0]);
+        assert_eq!(objects.borrow()[&refs_key], b"2");
+        assert!(matches!(plugin.delete(&blobs[0]).await, Err(StoragePluginError::BlobInUse(_))));
+
+        plugin.delete("monday").await.unwrap();
+        assert_eq!(plugin.retrieve("tuesday").await.unwrap(), data);
+        assert_eq!(objects.borrow()[&refs_key], b"1");
+
+        plugin.delete("tuesday").await.unwrap();
+        assert!(objects.borrow().is_empty());
+    }
+
+    #[cfg(feature = "dedup")]
+    #[tokio::test]
+    async fn test_stored_content_is_never_taken_for_a_reference() {
+        let (mut plugin, objects) = memory_plugin(StoragePluginConfig {
+            enable_deduplication: true,
+            ..Default::default()
+        });
+        plugin.store("secret", b"only for its owner").await.unwrap();
+        let hash = dedup::content_hash(b"only for its owner");
+
+        // Content naming another blob is just content
+        plugin.store("probe", hash.as_bytes()).await.unwrap();
+        assert_eq!(plugin.retrieve("probe").await.unwrap(), hash.as_bytes());
+
+        // Reference records cannot be forged or removed through the API
+        let forged = dedup::reference_key("probe");
+        assert!(matches!(plugin.store(&forged, hash.as_bytes()).await, Err(StoragePluginError::ReservedKey(_))));
+        assert!(matches!(plugin.delete(&forged).await, Err(StoragePluginError::ReservedKey(_))));
+
+        // Without deduplication nothing is followed at all
+        let (mut plain, _) = memory_plugin(StoragePluginConfig::default());
+        plain.store(&forged, hash.as_bytes()).await.unwrap();
+        plain.store("probe", b"").await.unwrap();
+        assert_eq!(plain.retrieve("probe").await.unwrap(), b"");
+        assert!(objects.borrow().contains_key(&dedup::blob_key(&hash)));
+    }
 }
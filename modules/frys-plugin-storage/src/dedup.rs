@@ -0,0 +1,78 @@
+//! Content-addressed deduplication
+//!
+//! With `enable_deduplication` set, [`StoragePlugin::store`] hashes each
+//! object with BLAKE3 and keeps its bytes once, under `blob.{hash}`. The
+//! caller's key holds an empty object, and the hash it refers to is kept
+//! apart from any content under `blob.ref.{key}`, which `retrieve` follows
+//! transparently. Every blob has a reference count under
+//! `blob.{hash}.refs`; deleting a key releases its reference, and the blob
+//! goes away with the last one. Callers cannot write the keys
+//! deduplication manages.
+
+use alloc::string::String;
+
+/// Prefix of the keys recording which blob a caller's key refers to
+const REFERENCE_KEY_PREFIX: &str = "blob.ref.";
+
+/// BLAKE3 digest of `data` in hex
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    blake3::hash(data).to_hex().to_string()
+}
+
+/// Key holding the blob with content `hash`
+pub(crate) fn blob_key(hash: &str) -> String {
+    format!("blob.{}", hash)
+}
+
+/// Key holding the reference count of the blob with content `hash`
+pub(crate) fn refcount_key(hash: &str) -> String {
+    format!("blob.{}.refs", hash)
+}
+
+/// Key recording the content hash the caller's `key` refers to
+pub(crate) fn reference_key(key: &str) -> String {
+    format!("{}{}", REFERENCE_KEY_PREFIX, key)
+}
+
+/// Content hash stored under a reference key
+pub(crate) fn parse_hash(raw: &[u8]) -> Option<String> {
+    let hash = ::core::str::from_utf8(raw).ok()?;
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then(|| hash.to_string())
+}
+
+/// Content hash of the blob or reference count stored at `key`, if `key`
+/// is one deduplication manages itself
+pub(crate) fn managed_hash(key: &str) -> Option<&str> {
+    let hash = key.strip_prefix("blob.")?;
+    let hash = hash.strip_suffix(".refs").unwrap_or(hash);
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+}
+
+/// Whether `key` is a blob, reference count or reference that callers may
+/// not write or delete
+pub(crate) fn is_managed_key(key: &str) -> bool {
+    managed_hash(key).is_some() || key.starts_with(REFERENCE_KEY_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_round_trip() {
+        let hash = content_hash(b"backup");
+        assert_eq!(parse_hash(hash.as_bytes()), Some(hash));
+        assert_eq!(parse_hash(b"not-a-hash"), None);
+        assert_eq!(parse_hash(b"plain data"), None);
+    }
+
+    #[test]
+    fn test_managed_keys() {
+        let hash = content_hash(b"backup");
+        assert_eq!(managed_hash(&blob_key(&hash)), Some(hash.as_str()));
+        assert_eq!(managed_hash(&refcount_key(&hash)), Some(hash.as_str()));
+        assert_eq!(managed_hash("blob.user-key"), None);
+        assert!(is_managed_key(&reference_key("monday")));
+        assert!(!is_managed_key("blob.user-key"));
+    }
+}
@@ -29,6 +29,12 @@ pub enum StoragePluginError {
     InvalidConfiguration(String),
     /// Feature not enabled
     FeatureNotEnabled(String),
+    /// Stored data failed its integrity check
+    CorruptedData(String),
+    /// Blob still referenced by other keys
+    BlobInUse(String),
+    /// Key is managed by the plugin itself and cannot be written directly
+    ReservedKey(String),
 }
 
 impl core::fmt::Display for StoragePluginError {
@@ -46,6 +52,9 @@ impl core::fmt::Display for StoragePluginError {
             StoragePluginError::AuthenticationError(msg) => write!(f, "Authentication error: {}", msg),
             StoragePluginError::InvalidConfiguration(msg) => write!(f, "Invalid configuration: {}", msg),
             StoragePluginError::FeatureNotEnabled(feature) => write!(f, "Feature not enabled: {}", feature),
+            StoragePluginError::CorruptedData(msg) => write!(f, "Corrupted data: {}", msg),
+            StoragePluginError::BlobInUse(key) => write!(f, "Blob still referenced: {}", key),
+            StoragePluginError::ReservedKey(key) => write!(f, "Key reserved for deduplication: {}", key),
         }
     }
 }
@@ -7,6 +7,7 @@
 //! - **Database Integration**: SQL and NoSQL database support
 //! - **Cloud Storage**: Integration with major cloud providers
 //! - **Distributed Storage**: Multi-node storage with replication
+//! - **Deduplication**: Content-addressed storage of identical objects
 //! - **Data Compression**: Automatic compression and decompression
 //! - **Encryption**: End-to-end data encryption
 //! - **Caching**: Intelligent storage caching and prefetching
@@ -72,6 +73,10 @@ mod caching;
 mod backup;
 mod metrics;
 mod config;
+#[cfg(feature = "file")]
+mod streaming;
+#[cfg(feature = "dedup")]
+mod dedup;
 
 // Public API
 pub use core::*;
@@ -85,6 +90,8 @@ pub use caching::*;
 pub use backup::*;
 pub use metrics::*;
 pub use config::*;
+#[cfg(feature = "file")]
+pub use streaming::*;
 
 // Error types
 mod error;
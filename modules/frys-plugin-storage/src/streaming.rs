@@ -0,0 +1,274 @@
+//! Chunked streaming of large objects
+//!
+//! [`StoragePlugin::store_stream`] cuts its input into chunks of
+//! `chunk_size` bytes, sends each through compression and encryption on its
+//! own and stores it under `{key}.chunk{n}`. The object key itself holds a
+//! manifest listing the length and CRC-32 of every chunk and of the whole
+//! object. [`StoragePlugin::retrieve_stream`] returns an [`ObjectReader`]
+//! fetching one chunk at a time and checking it against the manifest before
+//! handing out any of its bytes, so neither direction holds more than a
+//! chunk in memory. Deleting or overwriting the object removes the chunks
+//! its manifest lists.
+
+use crate::*;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use ::core::future::Future;
+use ::core::pin::Pin;
+use ::core::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+/// First line of every manifest
+const MANIFEST_HEADER: &str = "FRYS-STREAM 1";
+
+/// Key of chunk `index` of the object at `key`
+pub(crate) fn chunk_key(key: &str, index: usize) -> String {
+    format!("{}.chunk{}", key, index)
+}
+
+/// Incremental CRC-32 (IEEE)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u32::from(byte);
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> u32 {
+        !self.0
+    }
+
+    pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+        let mut crc = Self::new();
+        crc.update(bytes);
+        crc.finish()
+    }
+}
+
+/// Length and checksum of one chunk, before compression and encryption
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ChunkInfo {
+    pub(crate) len: usize,
+    pub(crate) crc: u32,
+}
+
+/// Layout of a streamed object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Manifest {
+    pub(crate) total_len: u64,
+    pub(crate) crc: u32,
+    pub(crate) chunks: Vec<ChunkInfo>,
+}
+
+impl Manifest {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut text = format!("{}\n{} {} {:08x}\n", MANIFEST_HEADER, self.total_len, self.chunks.len(), self.crc);
+        for chunk in &self.chunks {
+            text.push_str(&format!("{} {:08x}\n", chunk.len, chunk.crc));
+        }
+        text.into_bytes()
+    }
+
+    pub(crate) fn decode(key: &str, bytes: &[u8]) -> Result<Self> {
+        let malformed = || StoragePluginError::CorruptedData(format!("{} is not a streamed object manifest", key));
+        let text = ::core::str::from_utf8(bytes).map_err(|_| malformed())?;
+        let mut lines = text.lines();
+        if lines.next() != Some(MANIFEST_HEADER) {
+            return Err(malformed());
+        }
+
+        let mut summary = lines.next().ok_or_else(malformed)?.split(' ');
+        let total_len = summary.next().and_then(|n| n.parse().ok()).ok_or_else(malformed)?;
+        let count: usize = summary.next().and_then(|n| n.parse().ok()).ok_or_else(malformed)?;
+        let crc = summary.next().and_then(|n| u32::from_str_radix(n, 16).ok()).ok_or_else(malformed)?;
+
+        let chunks = lines
+            .map(|line| {
+                let (len, crc) = line.split_once(' ')?;
+                Some(ChunkInfo {
+                    len: len.parse().ok()?,
+                    crc: u32::from_str_radix(crc, 16).ok()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(malformed)?;
+        if chunks.len() != count || chunks.iter().map(|chunk| chunk.len as u64).sum::<u64>() != total_len {
+            return Err(malformed());
+        }
+
+        Ok(Self { total_len, crc, chunks })
+    }
+}
+
+/// Fill `buffer` from `reader`, stopping early only at end of input.
+/// Returns the number of bytes read.
+pub(crate) async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]).await {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) => return Err(StoragePluginError::IoError(e.to_string())),
+        }
+    }
+    Ok(filled)
+}
+
+/// Chunk fetch in flight, holding the plugin until it completes
+type PendingChunk<'a> = Pin<Box<dyn Future<Output = (&'a mut StoragePlugin, Result<Vec<u8>>)> + 'a>>;
+
+enum ReadState<'a> {
+    Idle(&'a mut StoragePlugin),
+    Fetching(PendingChunk<'a>),
+    Done,
+    Failed,
+}
+
+/// Reader over a streamed object, returned by
+/// [`StoragePlugin::retrieve_stream`].
+///
+/// Fails with [`std::io::ErrorKind::InvalidData`] wrapping
+/// [`StoragePluginError::CorruptedData`] when a chunk does not match its
+/// checksum.
+pub struct ObjectReader<'a> {
+    key: String,
+    manifest: Manifest,
+    state: ReadState<'a>,
+    next_chunk: usize,
+    chunk: Vec<u8>,
+    position: usize,
+    crc: Crc32,
+}
+
+impl<'a> ObjectReader<'a> {
+    pub(crate) fn new(plugin: &'a mut StoragePlugin, key: &str, manifest: Manifest) -> Self {
+        Self {
+            key: key.to_string(),
+            manifest,
+            state: ReadState::Idle(plugin),
+            next_chunk: 0,
+            chunk: Vec::new(),
+            position: 0,
+            crc: Crc32::new(),
+        }
+    }
+
+    /// Size of the object in bytes
+    pub fn len(&self) -> u64 {
+        self.manifest.total_len
+    }
+
+    /// Whether the object is empty
+    pub fn is_empty(&self) -> bool {
+        self.manifest.total_len == 0
+    }
+
+    /// Check a fetched chunk against the manifest
+    fn verify(&mut self, index: usize, data: &[u8]) -> Result<()> {
+        let expected = self.manifest.chunks[index];
+        if data.len() != expected.len || Crc32::checksum(data) != expected.crc {
+            return Err(StoragePluginError::CorruptedData(format!("chunk {} of {} fails its checksum", index, self.key)));
+        }
+        self.crc.update(data);
+        if index + 1 == self.manifest.chunks.len() && self.crc.finish() != self.manifest.crc {
+            return Err(StoragePluginError::CorruptedData(format!("{} fails its checksum", self.key)));
+        }
+        Ok(())
+    }
+}
+
+fn io_error(error: StoragePluginError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+impl AsyncRead for ObjectReader<'_> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.position < this.chunk.len() {
+                let n = buf.remaining().min(this.chunk.len() - this.position);
+                buf.put_slice(&this.chunk[this.position..this.position + n]);
+                this.position += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            match ::core::mem::replace(&mut this.state, ReadState::Done) {
+                ReadState::Done => return Poll::Ready(Ok(())),
+                ReadState::Failed => {
+                    this.state = ReadState::Failed;
+                    let error = StoragePluginError::CorruptedData(format!("{} failed an earlier read", this.key));
+                    return Poll::Ready(Err(io_error(error)));
+                }
+                ReadState::Idle(_) if this.next_chunk == this.manifest.chunks.len() => {
+                    return Poll::Ready(Ok(()));
+                }
+                ReadState::Idle(plugin) => {
+                    let key = chunk_key(&this.key, this.next_chunk);
+                    this.state = ReadState::Fetching(Box::pin(async move {
+                        let result = plugin.retrieve_chunk(&key).await;
+                        (plugin, result)
+                    }));
+                }
+                ReadState::Fetching(mut pending) => match pending.as_mut().poll(cx) {
+                    Poll::Pending => {
+                        this.state = ReadState::Fetching(pending);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready((plugin, result)) => {
+                        let index = this.next_chunk;
+                        let data = result.and_then(|data| this.verify(index, &data).map(|()| data));
+                        match data {
+                            Ok(data) => {
+                                this.state = ReadState::Idle(plugin);
+                                this.next_chunk += 1;
+                                this.chunk = data;
+                                this.position = 0;
+                            }
+                            Err(error) => {
+                                this.state = ReadState::Failed;
+                                return Poll::Ready(Err(io_error(error)));
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_is_incremental() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+        assert_eq!(Crc32::checksum(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = Manifest {
+            total_len: 7,
+            crc: 0xDEAD_BEEF,
+            chunks: alloc::vec![ChunkInfo { len: 4, crc: 1 }, ChunkInfo { len: 3, crc: 2 }],
+        };
+        assert_eq!(Manifest::decode("k", &manifest.encode()).unwrap(), manifest);
+        assert!(Manifest::decode("k", b"plain object").is_err());
+    }
+}
@@ -481,6 +481,187 @@ impl BrokerStats {
     }
 }
 
+/// Module name under which plugins import host functions
+pub const HOST_MODULE: &str = "env";
+
+/// Type of a host function argument or result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostValueType {
+    /// 64-bit integer, passed as a WASM `i64`
+    Int,
+    /// UTF-8 string, passed as a pointer and length into plugin memory
+    String,
+    /// Raw bytes, passed as a pointer and length into plugin memory
+    Bytes,
+}
+
+impl HostValueType {
+    /// Whether `value` has this type
+    pub fn matches(&self, value: &MessageValue) -> bool {
+        matches!(
+            (self, value),
+            (HostValueType::Int, MessageValue::Int(_))
+                | (HostValueType::String, MessageValue::String(_))
+                | (HostValueType::Bytes, MessageValue::Bytes(_))
+        )
+    }
+}
+
+/// Host function callable from plugins, given the calling plugin's ID
+pub type HostFunction = alloc::sync::Arc<dyn Fn(&PluginId, &[MessageValue]) -> Result<MessageValue> + Send + Sync>;
+
+/// A function the host exposes to plugins
+#[derive(Clone)]
+pub struct HostImport {
+    /// Argument types
+    pub params: alloc::vec::Vec<HostValueType>,
+    /// Result type, if the function returns a value. A `Null` result is
+    /// passed to the plugin as -1.
+    pub result: Option<HostValueType>,
+    /// Permission a plugin needs to import and call the function
+    pub permission: Option<PluginPermission>,
+    /// Capability a plugin needs to call the function
+    pub capability: Option<PluginCapability>,
+    /// Host-side implementation
+    function: HostFunction,
+}
+
+impl alloc::fmt::Debug for HostImport {
+    fn fmt(&self, f: &mut alloc::fmt::Formatter<'_>) -> alloc::fmt::Result {
+        f.debug_struct("HostImport")
+            .field("params", &self.params)
+            .field("result", &self.result)
+            .field("permission", &self.permission)
+            .field("capability", &self.capability)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Registry of host functions plugins may import from [`HOST_MODULE`]
+#[derive(Debug, Clone, Default)]
+pub struct HostImports {
+    imports: alloc::collections::BTreeMap<alloc::string::String, HostImport>,
+}
+
+impl HostImports {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a host function, replacing any with the same name
+    pub fn register<F>(
+        &mut self,
+        name: &str,
+        params: &[HostValueType],
+        result: Option<HostValueType>,
+        permission: Option<PluginPermission>,
+        function: F,
+    ) where
+        F: Fn(&PluginId, &[MessageValue]) -> Result<MessageValue> + Send + Sync + 'static,
+    {
+        self.imports.insert(
+            name.into(),
+            HostImport {
+                params: params.to_vec(),
+                result,
+                permission,
+                capability: None,
+                function: alloc::sync::Arc::new(function),
+            },
+        );
+    }
+
+    /// Restrict host function `name` to plugins granted `capability`.
+    /// Returns whether the function is registered.
+    pub fn require_capability(&mut self, name: &str, capability: PluginCapability) -> bool {
+        match self.imports.get_mut(name) {
+            Some(import) => {
+                import.capability = Some(capability);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Expose `host_log(level, message)`, handing each message to `sink`.
+    /// Every plugin may log.
+    pub fn register_log<F>(&mut self, sink: F)
+    where
+        F: Fn(&PluginId, i64, &str) + Send + Sync + 'static,
+    {
+        self.register(
+            "host_log",
+            &[HostValueType::Int, HostValueType::String],
+            None,
+            None,
+            move |plugin_id, args| {
+                if let [MessageValue::Int(level), MessageValue::String(message)] = args {
+                    sink(plugin_id, *level, message);
+                }
+                Ok(MessageValue::Null)
+            },
+        );
+    }
+
+    /// Expose `host_get_config(key)`, looking keys up in `config`. Plugins
+    /// need the `Read` permission to import it.
+    pub fn register_config(&mut self, config: alloc::collections::BTreeMap<alloc::string::String, alloc::string::String>) {
+        self.register(
+            "host_get_config",
+            &[HostValueType::String],
+            Some(HostValueType::String),
+            Some(PluginPermission::Read),
+            move |_, args| match args {
+                [MessageValue::String(key)] => Ok(config
+                    .get(key)
+                    .map_or(MessageValue::Null, |value| MessageValue::String(value.clone()))),
+                _ => Ok(MessageValue::Null),
+            },
+        );
+    }
+
+    /// Look up a host function
+    pub fn get(&self, name: &str) -> Option<&HostImport> {
+        self.imports.get(name)
+    }
+
+    /// Iterate over the registered functions by name
+    pub fn iter(&self) -> impl Iterator<Item = (&alloc::string::String, &HostImport)> {
+        self.imports.iter()
+    }
+
+    /// Call a host function on behalf of the plugin holding `grants`,
+    /// checking its permission and capability and then the arguments
+    /// against the function's signature
+    pub fn call(&self, grants: &PluginGrants, name: &str, args: &[MessageValue]) -> Result<MessageValue> {
+        let plugin_id = &grants.plugin_id;
+        let error = |reason: alloc::string::String| PluginError::CommunicationError {
+            from_plugin: plugin_id.clone(),
+            to_plugin: "host".into(),
+            reason,
+        };
+
+        let import = self
+            .get(name)
+            .ok_or_else(|| error(alloc::format!("unknown host function '{}'", name)))?;
+        if let Some(permission) = &import.permission {
+            grants.require_permission(permission, name)?;
+        }
+        if let Some(capability) = &import.capability {
+            grants.require_capability(capability, name)?;
+        }
+        if args.len() != import.params.len() || !import.params.iter().zip(args).all(|(ty, arg)| ty.matches(arg)) {
+            return Err(error(alloc::format!(
+                "'{}' expects {:?}, got {:?}",
+                name, import.params, args
+            )));
+        }
+
+        (import.function)(plugin_id, args)
+    }
+}
+
 /// Get current timestamp (simplified)
 fn current_timestamp() -> u64 {
     // In a real implementation, this would use system time
@@ -581,6 +762,60 @@ mod tests {
         assert_eq!(stats.messages_per_second(), 0.0);
     }
 
+    #[test]
+    fn test_host_import_checks_arguments() {
+        let mut imports = HostImports::new();
+        imports.register_config(alloc::collections::BTreeMap::from([("region".into(), "eu".into())]));
+        let plugin = PluginGrants::new("test-plugin").with_permission(PluginPermission::Read);
+
+        let value = imports.call(&plugin, "host_get_config", &[MessageValue::String("region".into())]).unwrap();
+        assert!(matches!(value, MessageValue::String(ref region) if region == "eu"));
+        let missing = imports.call(&plugin, "host_get_config", &[MessageValue::String("zone".into())]).unwrap();
+        assert!(matches!(missing, MessageValue::Null));
+
+        assert!(imports.call(&plugin, "host_get_config", &[MessageValue::Int(1)]).is_err());
+        assert!(imports.call(&plugin, "host_fetch", &[]).is_err());
+    }
+
+    #[test]
+    fn test_host_call_requires_granted_permission() {
+        let store = alloc::sync::Arc::new(std::sync::Mutex::new(alloc::collections::BTreeMap::new()));
+        let writes = store.clone();
+        let mut imports = HostImports::new();
+        imports.register(
+            "host_put",
+            &[HostValueType::String, HostValueType::String],
+            None,
+            Some(PluginPermission::Write),
+            move |_, args| {
+                if let [MessageValue::String(key), MessageValue::String(value)] = args {
+                    writes.lock().unwrap().insert(key.clone(), value.clone());
+                }
+                Ok(MessageValue::Null)
+            },
+        );
+        let args = [MessageValue::String("mode".into()), MessageValue::String("fast".into())];
+
+        let reader = PluginGrants::new("reader").with_permission(PluginPermission::Read);
+        assert!(matches!(
+            imports.call(&reader, "host_put", &args),
+            Err(PluginError::PermissionDenied { ref plugin_id, ref operation, .. }) if plugin_id == "reader" && operation == "host_put"
+        ));
+        assert!(store.lock().unwrap().is_empty());
+
+        let writer = PluginGrants::new("writer").with_permission(PluginPermission::Write);
+        assert!(imports.call(&writer, "host_put", &args).is_ok());
+        assert_eq!(store.lock().unwrap().get("mode").map(alloc::string::String::as_str), Some("fast"));
+
+        // Capabilities are checked on top of the permission
+        assert!(imports.require_capability("host_put", PluginCapability::DatabaseAccess));
+        assert!(matches!(imports.call(&writer, "host_put", &args), Err(PluginError::PermissionDenied { .. })));
+        let admin = PluginGrants::new("admin")
+            .with_permission(PluginPermission::Admin)
+            .with_capability(PluginCapability::DatabaseAccess);
+        assert!(imports.call(&admin, "host_put", &args).is_ok());
+    }
+
     #[test]
     fn test_broker_stats() {
         let mut stats = BrokerStats::default();
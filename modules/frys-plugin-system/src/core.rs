@@ -43,6 +43,80 @@ impl core::fmt::Display for PluginVersion {
     }
 }
 
+/// Version requirement of a dependency, in Cargo's syntax
+///
+/// A requirement is a comma-separated list of comparators that must all
+/// hold: `^1.2` (the default when no operator is given), `~1.2.3`,
+/// `=1.2.3`, `>=1.0`, `>1.0`, `<2.0`, `<=1.4` or `*`. Missing minor and
+/// patch numbers are zero for `>`, `>=`, `<` and `<=`, and wildcards for
+/// the other operators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRequirement {
+    /// Bounds that must all hold, as `(inclusive, lower, version)`
+    bounds: alloc::vec::Vec<(bool, bool, PluginVersion)>,
+}
+
+impl VersionRequirement {
+    /// Parse a requirement such as `^1.2, <1.5`
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut bounds = alloc::vec::Vec::new();
+
+        for comparator in s.split(',').map(str::trim) {
+            if comparator == "*" || comparator.is_empty() {
+                continue;
+            }
+            let (op, version) = match comparator.find(|c: char| c.is_ascii_digit()) {
+                Some(start) => (comparator[..start].trim(), &comparator[start..]),
+                None => return None,
+            };
+
+            let mut parts = version.split('.');
+            let major: u32 = parts.next()?.parse().ok()?;
+            let minor: Option<u32> = parts.next().map(str::parse).transpose().ok()?;
+            let patch: Option<u32> = parts.next().map(str::parse).transpose().ok()?;
+            if parts.next().is_some() {
+                return None;
+            }
+            let floor = PluginVersion::new(major, minor.unwrap_or(0), patch.unwrap_or(0));
+
+            match op {
+                ">=" => bounds.push((true, true, floor)),
+                ">" => bounds.push((false, true, floor)),
+                "<" => bounds.push((false, false, floor)),
+                "<=" => bounds.push((true, false, floor)),
+                "=" | "~" | "^" | "" => {
+                    // Exclusive upper bound: the first version the
+                    // requirement no longer accepts
+                    let ceiling = match (op, minor, patch) {
+                        ("=", None, _) | ("~", None, _) => PluginVersion::new(major + 1, 0, 0),
+                        ("=", Some(minor), None) | ("~", Some(minor), _) => PluginVersion::new(major, minor + 1, 0),
+                        ("=", Some(minor), Some(patch)) => PluginVersion::new(major, minor, patch + 1),
+                        (_, _, _) if major > 0 => PluginVersion::new(major + 1, 0, 0),
+                        (_, None, _) => PluginVersion::new(1, 0, 0),
+                        (_, Some(minor), _) if minor > 0 || patch.is_none() => PluginVersion::new(0, minor + 1, 0),
+                        (_, Some(minor), patch) => PluginVersion::new(0, minor, patch.unwrap_or(0) + 1),
+                    };
+                    bounds.push((true, true, floor));
+                    bounds.push((false, false, ceiling));
+                }
+                _ => return None,
+            }
+        }
+
+        Some(Self { bounds })
+    }
+
+    /// Whether `version` satisfies the requirement
+    pub fn matches(&self, version: &PluginVersion) -> bool {
+        self.bounds.iter().all(|(inclusive, lower, bound)| match (lower, inclusive) {
+            (true, true) => version >= bound,
+            (true, false) => version > bound,
+            (false, true) => version <= bound,
+            (false, false) => version < bound,
+        })
+    }
+}
+
 /// Plugin metadata
 #[derive(Debug, Clone)]
 pub struct PluginMetadata {
@@ -111,6 +185,90 @@ pub enum PluginPermission {
     Admin,
 }
 
+/// Permissions and capabilities granted to a plugin, checked each time it
+/// calls into the host
+///
+/// `Admin` implies every other permission; capabilities must be granted
+/// individually.
+#[derive(Debug, Clone, Default)]
+pub struct PluginGrants {
+    /// Plugin the grants belong to
+    pub plugin_id: PluginId,
+    /// Granted permissions
+    pub permissions: alloc::vec::Vec<PluginPermission>,
+    /// Granted capabilities
+    pub capabilities: alloc::vec::Vec<PluginCapability>,
+}
+
+impl PluginGrants {
+    /// Create grants for `plugin_id` allowing nothing
+    pub fn new(plugin_id: impl Into<PluginId>) -> Self {
+        Self {
+            plugin_id: plugin_id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Grant `permission`
+    pub fn with_permission(mut self, permission: PluginPermission) -> Self {
+        self.permissions.push(permission);
+        self
+    }
+
+    /// Grant `capability`
+    pub fn with_capability(mut self, capability: PluginCapability) -> Self {
+        self.capabilities.push(capability);
+        self
+    }
+
+    /// Whether `permission` is granted
+    pub fn has_permission(&self, permission: &PluginPermission) -> bool {
+        self.permissions.iter().any(|granted| granted == permission || *granted == PluginPermission::Admin)
+    }
+
+    /// Whether `capability` is granted
+    pub fn has_capability(&self, capability: &PluginCapability) -> bool {
+        self.capabilities.contains(capability)
+    }
+
+    /// Check that `operation` may proceed under `permission`, logging the
+    /// attempt if it may not
+    pub fn require_permission(&self, permission: &PluginPermission, operation: &str) -> Result<()> {
+        if self.has_permission(permission) {
+            return Ok(());
+        }
+        self.deny(operation, alloc::format!("{:?} permission", permission))
+    }
+
+    /// Check that `operation` may proceed under `capability`, logging the
+    /// attempt if it may not
+    pub fn require_capability(&self, capability: &PluginCapability, operation: &str) -> Result<()> {
+        if self.has_capability(capability) {
+            return Ok(());
+        }
+        self.deny(operation, alloc::format!("{:?} capability", capability))
+    }
+
+    fn deny(&self, operation: &str, required: alloc::string::String) -> Result<()> {
+        log::warn!("plugin '{}' denied '{}': requires {}", self.plugin_id, operation, required);
+        Err(PluginError::PermissionDenied {
+            plugin_id: self.plugin_id.clone(),
+            operation: operation.into(),
+            required,
+        })
+    }
+}
+
+impl From<&PluginMetadata> for PluginGrants {
+    fn from(metadata: &PluginMetadata) -> Self {
+        Self {
+            plugin_id: metadata.id.clone(),
+            permissions: metadata.permissions.clone(),
+            capabilities: metadata.capabilities.clone(),
+        }
+    }
+}
+
 /// Resource limits for plugins
 #[derive(Debug, Clone)]
 pub struct ResourceLimits {
@@ -214,6 +372,7 @@ pub struct PluginManagerBuilder {
     default_resource_limits: ResourceLimits,
     enable_metrics: bool,
     plugin_paths: alloc::vec::Vec<alloc::string::String>,
+    host_imports: HostImports,
 }
 
 impl PluginManagerBuilder {
@@ -227,6 +386,7 @@ impl PluginManagerBuilder {
             default_resource_limits: ResourceLimits::default(),
             enable_metrics: true,
             plugin_paths: alloc::vec::Vec::new(),
+            host_imports: HostImports::new(),
         }
     }
 
@@ -272,6 +432,12 @@ impl PluginManagerBuilder {
         self
     }
 
+    /// Set the host functions plugins may import
+    pub fn with_host_imports(mut self, imports: HostImports) -> Self {
+        self.host_imports = imports;
+        self
+    }
+
     /// Build the plugin manager
     pub async fn build(self) -> Result<PluginManager> {
         let manager = PluginManager::new(self).await?;
@@ -290,12 +456,16 @@ impl Default for PluginManagerBuilder {
 pub struct PluginManager {
     /// Plugin contexts
     plugins: alloc::collections::BTreeMap<PluginId, PluginContext>,
+    /// Running instances, with the path each plugin was loaded from
+    instances: alloc::collections::BTreeMap<PluginId, (alloc::string::String, PluginSlot)>,
     /// Plugin loader
     loader: PluginLoader,
     /// Plugin registry
     registry: Option<PluginRegistry>,
     /// Security manager
     security_manager: SecurityManager,
+    /// Host functions exposed to plugins
+    host_imports: alloc::sync::Arc<HostImports>,
     /// Configuration
     config: PluginManagerConfig,
     /// Statistics
@@ -315,7 +485,8 @@ struct PluginManagerConfig {
 impl PluginManager {
     /// Create a new plugin manager
     async fn new(builder: PluginManagerBuilder) -> Result<Self> {
-        let loader = PluginLoader::new(builder.enable_sandbox);
+        let host_imports = alloc::sync::Arc::new(builder.host_imports);
+        let loader = PluginLoader::with_host_imports(builder.enable_sandbox, host_imports.clone());
         let registry = if let Some(endpoint) = builder.registry_endpoint {
             Some(PluginRegistry::new(endpoint))
         } else {
@@ -334,9 +505,11 @@ impl PluginManager {
 
         Ok(Self {
             plugins: alloc::collections::BTreeMap::new(),
+            instances: alloc::collections::BTreeMap::new(),
             loader,
             registry,
             security_manager,
+            host_imports,
             config,
             stats: PluginStats::default(),
         })
@@ -353,7 +526,7 @@ impl PluginManager {
         }
 
         // Load plugin
-        let context = self.loader.load_plugin(path, &self.config.default_resource_limits).await?;
+        let (context, instance) = self.loader.load_plugin(path, &self.config.default_resource_limits).await?;
 
         let plugin_id = context.metadata.id.clone();
 
@@ -362,15 +535,94 @@ impl PluginManager {
             return Err(PluginError::AlreadyLoaded { id: plugin_id });
         }
 
+        // Resolve dependencies against the loaded plugins, fetching missing
+        // ones from the registry
+        let loaded = self.plugins.iter()
+            .map(|(id, context)| (id.clone(), context.metadata.version.clone()))
+            .collect();
+        let dependencies = DependencyResolver::new(&self.registry)
+            .resolve(&context.metadata, &loaded)
+            .await?;
+        if self.plugins.len() + dependencies.len() >= self.config.max_plugins {
+            return Err(PluginError::MaxPluginsExceeded {
+                current: self.plugins.len() + dependencies.len(),
+                max: self.config.max_plugins,
+            });
+        }
+
+        // Dependencies come first in the resolved order. None is registered
+        // until all of them loaded, and the files installed for this load are
+        // removed again if one fails.
+        let mut installed = alloc::vec::Vec::new();
+        let loaded = match self.load_dependencies(&dependencies, &mut installed).await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                #[cfg(feature = "std")]
+                for dependency_path in &installed {
+                    let _ = std::fs::remove_file(dependency_path);
+                }
+                return Err(e);
+            }
+        };
+        for (dependency_id, dependency_path, dependency_context, dependency_instance) in loaded {
+            self.instances.insert(dependency_id.clone(), (dependency_path, PluginSlot::new(dependency_instance)));
+            self.plugins.insert(dependency_id, dependency_context);
+            self.stats.plugins_loaded += 1;
+        }
+
+        self.instances.insert(plugin_id.clone(), (path.into(), PluginSlot::new(instance)));
         self.plugins.insert(plugin_id.clone(), context);
         self.stats.plugins_loaded += 1;
 
         Ok(plugin_id)
     }
 
+    /// Install and load `dependencies` in order, recording every file
+    /// written in `installed`
+    async fn load_dependencies(
+        &self,
+        dependencies: &[PluginMetadata],
+        installed: &mut alloc::vec::Vec<alloc::string::String>,
+    ) -> Result<alloc::vec::Vec<(PluginId, alloc::string::String, PluginContext, Box<dyn PluginInstance>)>> {
+        let mut loaded = alloc::vec::Vec::with_capacity(dependencies.len());
+        for dependency in dependencies {
+            let dependency_path = self.install_dependency(dependency).await?;
+            installed.push(dependency_path.clone());
+            let (context, instance) = self.loader
+                .load_plugin(&dependency_path, &self.config.default_resource_limits)
+                .await?;
+            loaded.push((dependency.id.clone(), dependency_path, context, instance));
+        }
+        Ok(loaded)
+    }
+
+    /// Download a dependency from the registry into the first plugin path
+    async fn install_dependency(&self, metadata: &PluginMetadata) -> Result<alloc::string::String> {
+        let dir = self.config.plugin_paths.first().map_or(".", |path| path.as_str());
+        let path = dependency_path(dir, &metadata.id)?;
+        let registry = self.registry.as_ref().ok_or_else(|| PluginError::DependencyError {
+            plugin_id: metadata.id.clone(),
+            dependency: metadata.id.clone(),
+            reason: "no registry configured".into(),
+        })?;
+        let binary = registry.download_plugin(&metadata.id, &metadata.version).await?;
+
+        #[cfg(feature = "std")]
+        {
+            std::fs::write(&path, &binary)
+                .map_err(|e| PluginError::LoadFailed {
+                    path: path.clone(),
+                    reason: alloc::format!("write failed: {}", e),
+                })?;
+        }
+
+        Ok(path)
+    }
+
     /// Unload a plugin
     pub async fn unload_plugin(&mut self, plugin_id: &PluginId) -> Result<bool> {
         if let Some(context) = self.plugins.remove(plugin_id) {
+            self.instances.remove(plugin_id);
             self.loader.unload_plugin(&context).await?;
             self.stats.plugins_unloaded += 1;
             Ok(true)
@@ -393,7 +645,9 @@ impl PluginManager {
         self.security_manager.check_execution(context, function)?;
 
         // Execute plugin
-        let result = self.loader.execute_plugin(context, function, input).await?;
+        let (_, slot) = self.instances.get(plugin_id)
+            .ok_or_else(|| PluginError::PluginNotFound { id: plugin_id.clone() })?;
+        let result = slot.execute(function, input).await?;
 
         // Update statistics
         if self.config.enable_metrics {
@@ -408,11 +662,43 @@ impl PluginManager {
         Ok(result)
     }
 
-    /// Get plugin metadata
+    /// Reload a plugin from the path it was loaded from.
+    ///
+    /// The state the running version exports is imported into the new one
+    /// before the two are swapped. Calls already running finish on the old
+    /// version; calls starting after the swap run on the new one.
+    pub async fn reload_plugin(&self, plugin_id: &PluginId) -> Result<()> {
+        if !self.config.enable_hot_reload {
+            return Err(PluginError::ConfigError {
+                parameter: "hot_reload".into(),
+                reason: "hot reload is not enabled".into(),
+            });
+        }
+
+        let (path, slot) = self.instances.get(plugin_id)
+            .ok_or_else(|| PluginError::PluginNotFound { id: plugin_id.clone() })?;
+        let (context, instance) = self.loader.load_plugin(path, &self.config.default_resource_limits).await?;
+        if context.metadata.id != *plugin_id {
+            return Err(PluginError::LoadFailed {
+                path: path.clone(),
+                reason: alloc::format!("expected plugin '{}', found '{}'", plugin_id, context.metadata.id),
+            });
+        }
+
+        slot.replace(instance).await?;
+        Ok(())
+    }
+
+    /// Get plugin metadata as of when the plugin was loaded
     pub fn get_plugin_metadata(&self, plugin_id: &PluginId) -> Option<&PluginMetadata> {
         self.plugins.get(plugin_id).map(|ctx| &ctx.metadata)
     }
 
+    /// Version of the plugin currently running, which changes on reload
+    pub fn plugin_version(&self, plugin_id: &PluginId) -> Option<PluginVersion> {
+        self.instances.get(plugin_id).map(|(_, slot)| slot.current().metadata().version.clone())
+    }
+
     /// List all loaded plugins
     pub fn list_plugins(&self) -> alloc::vec::Vec<&PluginId> {
         self.plugins.keys().collect()
@@ -422,6 +708,11 @@ impl PluginManager {
     pub fn stats(&self) -> &PluginStats {
         &self.stats
     }
+
+    /// Host functions exposed to plugins
+    pub fn host_imports(&self) -> &HostImports {
+        &self.host_imports
+    }
 }
 
 /// Plugin statistics
@@ -461,38 +752,23 @@ impl PluginStats {
     }
 }
 
-// Placeholder implementations (would be implemented in separate modules)
-#[derive(Debug)]
-struct PluginLoader;
-impl PluginLoader {
-    fn new(_sandbox: bool) -> Self { Self }
-    async fn load_plugin(&self, _path: &str, _limits: &ResourceLimits) -> Result<PluginContext> {
-        // Implementation would load actual plugin
-        Err(PluginError::LoadFailed {
-            path: _path.into(),
-            reason: "not implemented".into(),
-        })
-    }
-    async fn unload_plugin(&self, _context: &PluginContext) -> Result<()> {
-        Ok(())
+/// Path a dependency is installed at in `dir`. The plugin id comes from
+/// the registry, so one that could name a file outside `dir` is rejected.
+fn dependency_path(dir: &str, plugin_id: &str) -> Result<alloc::string::String> {
+    let is_file_name = !plugin_id.is_empty()
+        && !plugin_id.contains(['/', '\\', '\0'])
+        && !plugin_id.contains("..");
+    if !is_file_name {
+        return Err(PluginError::DependencyError {
+            plugin_id: plugin_id.into(),
+            dependency: plugin_id.into(),
+            reason: "plugin id is not a valid file name".into(),
+        });
     }
-    async fn execute_plugin(&self, _context: &PluginContext, _function: &str, _input: &[u8]) -> Result<PluginResult> {
-        Err(PluginError::ExecutionFailed {
-            plugin_id: "test".into(),
-            function: _function.into(),
-            reason: "not implemented".into(),
-        })
-    }
-}
-
-#[derive(Debug)]
-struct PluginRegistry {
-    endpoint: alloc::string::String,
-}
-impl PluginRegistry {
-    fn new(endpoint: alloc::string::String) -> Self { Self { endpoint } }
+    Ok(alloc::format!("{}/{}.wasm", dir, plugin_id))
 }
 
+// Placeholder implementations (would be implemented in separate modules)
 #[derive(Debug)]
 struct SecurityManager;
 impl SecurityManager {
@@ -518,6 +794,41 @@ mod tests {
         assert_eq!(parsed, version);
     }
 
+    #[test]
+    fn test_version_requirement() {
+        let v = |s: &str| PluginVersion::parse(s).unwrap();
+        for (requirement, version, expected) in [
+            ("1.2", "1.9.0", true),
+            ("1.2", "2.0.0", false),
+            ("^0.2.3", "0.2.9", true),
+            ("^0.2.3", "0.3.0", false),
+            ("~1.2.3", "1.2.7", true),
+            ("~1.2.3", "1.3.0", false),
+            ("=1.2", "1.2.4", true),
+            (">=1.0, <1.5", "1.5.0", false),
+            (">1.0.0", "1.0.0", false),
+            ("*", "0.0.1", true),
+        ] {
+            let parsed = VersionRequirement::parse(requirement).unwrap();
+            assert_eq!(parsed.matches(&v(version)), expected, "{} vs {}", requirement, version);
+        }
+
+        assert!(VersionRequirement::parse("1.x").is_none());
+        assert!(VersionRequirement::parse("!=1.0").is_none());
+    }
+
+    #[test]
+    fn test_dependency_path_stays_in_plugin_dir() {
+        assert_eq!(dependency_path("plugins", "codec").unwrap(), "plugins/codec.wasm");
+        for plugin_id in ["", "../escape", "nested/codec", "nested\\codec", "..", "codec\0"] {
+            assert!(
+                matches!(dependency_path("plugins", plugin_id), Err(PluginError::DependencyError { .. })),
+                "{:?}",
+                plugin_id
+            );
+        }
+    }
+
     #[test]
     fn test_resource_limits() {
         let limits = ResourceLimits::default();
@@ -35,6 +35,13 @@ pub enum PluginError {
         violation: alloc::string::String,
     },
 
+    /// Plugin lacks the permission or capability an operation requires
+    PermissionDenied {
+        plugin_id: alloc::string::String,
+        operation: alloc::string::String,
+        required: alloc::string::String,
+    },
+
     /// Resource limit exceeded
     ResourceLimitExceeded {
         plugin_id: alloc::string::String,
@@ -69,6 +76,14 @@ pub enum PluginError {
         reason: alloc::string::String,
     },
 
+    /// A declared dependency conflicts with the loaded version, is not
+    /// available, or is part of a cycle
+    UnsatisfiedDependency {
+        plugin_id: alloc::string::String,
+        dependency: alloc::string::String,
+        reason: alloc::string::String,
+    },
+
     /// Communication error
     CommunicationError {
         from_plugin: alloc::string::String,
@@ -125,6 +140,9 @@ impl fmt::Display for PluginError {
             PluginError::SecurityViolation { plugin_id, violation } => {
                 write!(f, "Security violation in plugin '{}': {}", plugin_id, violation)
             }
+            PluginError::PermissionDenied { plugin_id, operation, required } => {
+                write!(f, "Plugin '{}' denied '{}': requires {}", plugin_id, operation, required)
+            }
             PluginError::ResourceLimitExceeded { plugin_id, resource, limit, actual } => {
                 write!(f, "Plugin '{}' exceeded {} limit: {} > {}", plugin_id, resource, actual, limit)
             }
@@ -140,6 +158,9 @@ impl fmt::Display for PluginError {
             PluginError::DependencyError { plugin_id, dependency, reason } => {
                 write!(f, "Dependency error in plugin '{}' for '{}': {}", plugin_id, dependency, reason)
             }
+            PluginError::UnsatisfiedDependency { plugin_id, dependency, reason } => {
+                write!(f, "Plugin '{}' has unsatisfied dependency '{}': {}", plugin_id, dependency, reason)
+            }
             PluginError::CommunicationError { from_plugin, to_plugin, reason } => {
                 write!(f, "Communication error {} -> {}: {}", from_plugin, to_plugin, reason)
             }
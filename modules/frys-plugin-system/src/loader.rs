@@ -29,6 +29,90 @@ pub trait PluginInstance {
 
     /// Check if plugin is still alive
     fn is_alive(&self) -> bool;
+
+    /// Compiled WASM module, for plugins run by a [`WasmSandbox`]
+    #[cfg(feature = "wasm")]
+    fn wasm_module(&self) -> Option<&wasmtime::Module> {
+        None
+    }
+
+    /// Snapshot of the plugin's state, handed to the next version when the
+    /// plugin is reloaded. `None` if the plugin keeps no state.
+    async fn export_state(&self) -> Result<Option<alloc::vec::Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Take over state exported by the previous version. Returns `false`
+    /// if the plugin does not import state.
+    async fn import_state(&self, _state: &[u8]) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// A loaded plugin whose instance can be replaced while it is in use.
+///
+/// Every call runs on the instance that was current when it started, so a
+/// reload never splits a call across two versions.
+pub struct PluginSlot {
+    instance: std::sync::RwLock<alloc::sync::Arc<dyn PluginInstance>>,
+    /// Held shared by every call and exclusively by a reload, so state is
+    /// exported only once the calls in flight have finished
+    calls: tokio::sync::RwLock<()>,
+}
+
+impl PluginSlot {
+    /// Create a slot holding `instance`
+    pub fn new(instance: Box<dyn PluginInstance>) -> Self {
+        Self {
+            instance: std::sync::RwLock::new(alloc::sync::Arc::from(instance)),
+            calls: tokio::sync::RwLock::new(()),
+        }
+    }
+
+    /// The current instance
+    pub fn current(&self) -> alloc::sync::Arc<dyn PluginInstance> {
+        self.instance.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Execute a function on the current instance
+    pub async fn execute(&self, function: &str, input: &[u8]) -> Result<PluginResult> {
+        let _call = self.calls.read().await;
+        let instance = self.current();
+        instance.execute(function, input).await
+    }
+
+    /// Move the current instance's state into `replacement` and swap it in,
+    /// returning the previous instance.
+    ///
+    /// Waits for calls in flight and holds back new ones until the swap is
+    /// done, so no call mutates state after it was exported. Fails, keeping
+    /// the current instance, if it has state that `replacement` does not
+    /// import.
+    pub async fn replace(&self, replacement: Box<dyn PluginInstance>) -> Result<alloc::sync::Arc<dyn PluginInstance>> {
+        let _reload = self.calls.write().await;
+        let previous = self.current();
+        if let Some(state) = previous.export_state().await? {
+            if !replacement.import_state(&state).await? {
+                return Err(PluginError::InitializationFailed {
+                    plugin_id: previous.metadata().id.clone(),
+                    reason: "replacement does not import the plugin's state".into(),
+                });
+            }
+        }
+
+        *self.instance.write().unwrap_or_else(|e| e.into_inner()) = alloc::sync::Arc::from(replacement);
+        Ok(previous)
+    }
+}
+
+impl alloc::fmt::Debug for PluginSlot {
+    fn fmt(&self, f: &mut alloc::fmt::Formatter<'_>) -> alloc::fmt::Result {
+        let instance = self.current();
+        f.debug_struct("PluginSlot")
+            .field("plugin_id", &instance.metadata().id)
+            .field("version", &instance.metadata().version)
+            .finish()
+    }
 }
 
 /// Plugin format types
@@ -56,11 +140,19 @@ pub struct PluginLoader {
 impl PluginLoader {
     /// Create a new plugin loader
     pub fn new(sandbox_enabled: bool) -> Self {
+        Self::with_host_imports(sandbox_enabled, alloc::sync::Arc::new(HostImports::new()))
+    }
+
+    /// Create a plugin loader exposing `host_imports` to the plugins it
+    /// loads
+    pub fn with_host_imports(sandbox_enabled: bool, host_imports: alloc::sync::Arc<HostImports>) -> Self {
         let mut loaders = alloc::vec::Vec::new();
 
         // Add WASM loader if available
         #[cfg(feature = "wasm")]
-        loaders.push(Box::new(WasmPluginLoader::new(sandbox_enabled)));
+        loaders.push(Box::new(WasmPluginLoader::with_host_imports(sandbox_enabled, host_imports)));
+        #[cfg(not(feature = "wasm"))]
+        let _ = host_imports;
 
         // Add native loader if available
         #[cfg(feature = "native")]
@@ -73,7 +165,7 @@ impl PluginLoader {
     }
 
     /// Load a plugin from path
-    pub async fn load_plugin(&self, path: &str, resource_limits: &ResourceLimits) -> Result<PluginContext> {
+    pub async fn load_plugin(&self, path: &str, resource_limits: &ResourceLimits) -> Result<(PluginContext, Box<dyn PluginInstance>)> {
         // Detect plugin format from file extension
         let format = self.detect_format(path)?;
 
@@ -102,7 +194,7 @@ impl PluginLoader {
             error_count: 0,
         };
 
-        Ok(context)
+        Ok((context, instance))
     }
 
     /// Unload a plugin
@@ -168,20 +260,24 @@ impl PluginLoader {
 #[cfg(feature = "wasm")]
 #[derive(Debug)]
 pub struct WasmPluginLoader {
-    /// WASM engine
-    engine: wasmtime::Engine,
     /// Enable sandboxing
     sandbox_enabled: bool,
+    /// Host functions exposed to loaded plugins
+    host_imports: alloc::sync::Arc<HostImports>,
 }
 
 #[cfg(feature = "wasm")]
 impl WasmPluginLoader {
     /// Create a new WASM plugin loader
     pub fn new(sandbox_enabled: bool) -> Self {
-        let engine = wasmtime::Engine::default();
+        Self::with_host_imports(sandbox_enabled, alloc::sync::Arc::new(HostImports::new()))
+    }
+
+    /// Create a WASM plugin loader exposing `host_imports` to its plugins
+    pub fn with_host_imports(sandbox_enabled: bool, host_imports: alloc::sync::Arc<HostImports>) -> Self {
         Self {
-            engine,
             sandbox_enabled,
+            host_imports,
         }
     }
 }
@@ -197,35 +293,34 @@ impl PluginLoaderTrait for WasmPluginLoader {
                 reason: alloc::format!("{}", e),
             })?;
 
-        // Create WASM module
-        let module = wasmtime::Module::from_binary(&self.engine, &wasm_bytes)
-            .map_err(|e| PluginError::LoadFailed {
-                path: path.into(),
-                reason: alloc::format!("WASM compilation failed: {}", e),
-            })?;
-
-        // Create store with resource limits
-        let mut store = wasmtime::Store::new(&self.engine, ());
-
-        // Apply resource limits if sandboxing is enabled
-        if self.sandbox_enabled {
-            // Implementation would set up resource limits
-        }
+        // Apply the plugin's resource limits if sandboxing is enabled;
+        // memory stays capped at MAX_PLUGIN_MEMORY either way
+        let limits = if self.sandbox_enabled {
+            SandboxConfig::from(&metadata.resource_limits)
+        } else {
+            SandboxConfig {
+                max_cpu_time: 0,
+                max_execution_time: 0,
+                ..SandboxConfig::default()
+            }
+        };
+        // The declared permissions decide which host imports it may use
+        let config = SandboxConfig {
+            allow_network: metadata.permissions.contains(&PluginPermission::Network),
+            allow_fs: metadata.permissions.contains(&PluginPermission::Read)
+                || metadata.permissions.contains(&PluginPermission::Write),
+            ..limits
+        };
+        let sandbox = WasmSandbox::new(config).with_host_imports(self.host_imports.clone());
 
-        // Instantiate module
-        let instance = wasmtime::Instance::new(&mut store, &module, &[])
-            .map_err(|e| PluginError::LoadFailed {
-                path: path.into(),
-                reason: alloc::format!("WASM instantiation failed: {}", e),
-            })?;
+        // Create WASM module
+        let module = sandbox.compile(path, &wasm_bytes)?;
 
         // Create WASM plugin instance
         let plugin = WasmPluginInstance {
             metadata: metadata.clone(),
             module,
-            store,
-            instance,
-            resource_usage: ResourceUsage::default(),
+            sandbox,
         };
 
         Ok(Box::new(plugin))
@@ -250,12 +345,9 @@ pub struct WasmPluginInstance {
     metadata: PluginMetadata,
     /// WASM module
     module: wasmtime::Module,
-    /// WASM store
-    store: wasmtime::Store<()>,
-    /// WASM instance
-    instance: wasmtime::Instance,
-    /// Resource usage
-    resource_usage: ResourceUsage,
+    /// Sandbox every call runs in, holding the engine the module was
+    /// compiled for
+    sandbox: WasmSandbox,
 }
 
 #[cfg(feature = "wasm")]
@@ -266,21 +358,31 @@ impl PluginInstance for WasmPluginInstance {
     }
 
     async fn execute(&self, function: &str, input: &[u8]) -> Result<PluginResult> {
-        // Implementation would call WASM function
-        Err(PluginError::ExecutionFailed {
-            plugin_id: self.metadata.id.clone(),
-            function: function.into(),
-            reason: "WASM execution not implemented".into(),
-        })
+        self.sandbox.execute(self, function, input).await
     }
 
     fn resource_usage(&self) -> ResourceUsage {
-        self.resource_usage.clone()
+        self.sandbox.resource_usage()
     }
 
     fn is_alive(&self) -> bool {
         true // WASM instances are always alive until dropped
     }
+
+    fn wasm_module(&self) -> Option<&wasmtime::Module> {
+        Some(&self.module)
+    }
+
+    /// Every call runs in a fresh store, so nothing outlives a call and
+    /// there is no state to hand over
+    async fn export_state(&self) -> Result<Option<alloc::vec::Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Refuses state, since the next call's fresh store would drop it
+    async fn import_state(&self, _state: &[u8]) -> Result<bool> {
+        Ok(false)
+    }
 }
 
 /// Native plugin loader
@@ -333,6 +435,128 @@ mod tests {
         assert!(loader.detect_format("plugin.unknown").is_err());
     }
 
+    /// Plugin counting `increment` calls; version 2 counts in tens.
+    /// `slow_increment` yields to the runtime before counting.
+    struct Counter {
+        metadata: PluginMetadata,
+        step: u64,
+        count: std::sync::Mutex<u64>,
+        stateful: bool,
+    }
+
+    impl Counter {
+        fn boxed(major: u32, step: u64) -> Box<dyn PluginInstance> {
+            Self::with_state(major, step, true)
+        }
+
+        fn with_state(major: u32, step: u64, stateful: bool) -> Box<dyn PluginInstance> {
+            Box::new(Self {
+                metadata: PluginMetadata {
+                    id: "counter".into(),
+                    name: "Counter".into(),
+                    version: PluginVersion::new(major, 0, 0),
+                    description: alloc::string::String::new(),
+                    author: alloc::string::String::new(),
+                    license: "MIT".into(),
+                    dependencies: alloc::vec::Vec::new(),
+                    capabilities: alloc::vec::Vec::new(),
+                    permissions: alloc::vec::Vec::new(),
+                    resource_limits: ResourceLimits::default(),
+                },
+                step,
+                count: std::sync::Mutex::new(0),
+                stateful,
+            })
+        }
+    }
+
+    #[async_trait::async_trait(?Send)]
+    impl PluginInstance for Counter {
+        fn metadata(&self) -> &PluginMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, function: &str, _input: &[u8]) -> Result<PluginResult> {
+            if function == "slow_increment" {
+                tokio::time::sleep(core::time::Duration::from_millis(20)).await;
+            }
+            let mut count = self.count.lock().unwrap();
+            *count += self.step;
+            Ok(PluginResult {
+                success: true,
+                data: count.to_le_bytes().to_vec(),
+                execution_time: 0,
+                resource_usage: ResourceUsage::default(),
+                error: None,
+            })
+        }
+
+        fn resource_usage(&self) -> ResourceUsage {
+            ResourceUsage::default()
+        }
+
+        fn is_alive(&self) -> bool {
+            true
+        }
+
+        async fn export_state(&self) -> Result<Option<alloc::vec::Vec<u8>>> {
+            Ok(self.stateful.then(|| self.count.lock().unwrap().to_le_bytes().to_vec()))
+        }
+
+        async fn import_state(&self, state: &[u8]) -> Result<bool> {
+            if self.stateful {
+                *self.count.lock().unwrap() = u64::from_le_bytes(state.try_into().unwrap());
+            }
+            Ok(self.stateful)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_carries_state_over() {
+        let slot = PluginSlot::new(Counter::boxed(1, 1));
+        for _ in 0..3 {
+            slot.execute("increment", &[]).await.unwrap();
+        }
+
+        let previous = slot.replace(Counter::boxed(2, 10)).await.unwrap();
+        assert_eq!(previous.metadata().version, PluginVersion::new(1, 0, 0));
+        assert_eq!(slot.current().metadata().version, PluginVersion::new(2, 0, 0));
+
+        let result = slot.execute("increment", &[]).await.unwrap();
+        assert_eq!(u64::from_le_bytes(result.data.try_into().unwrap()), 13);
+    }
+
+    #[tokio::test]
+    async fn test_reload_waits_for_calls_in_flight() {
+        let slot = PluginSlot::new(Counter::boxed(1, 1));
+
+        // The call is already running when the reload starts, so its
+        // increment must be part of the exported state
+        let (call, reload) = tokio::join!(slot.execute("slow_increment", &[]), async {
+            tokio::task::yield_now().await;
+            slot.replace(Counter::boxed(2, 10)).await
+        });
+        call.unwrap();
+        reload.unwrap();
+
+        let result = slot.execute("increment", &[]).await.unwrap();
+        assert_eq!(u64::from_le_bytes(result.data.try_into().unwrap()), 11);
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejects_replacement_dropping_state() {
+        let slot = PluginSlot::new(Counter::boxed(1, 1));
+        slot.execute("increment", &[]).await.unwrap();
+
+        let result = slot.replace(Counter::with_state(2, 10, false)).await;
+        assert!(matches!(result, Err(PluginError::InitializationFailed { .. })));
+        assert_eq!(slot.current().metadata().version, PluginVersion::new(1, 0, 0));
+
+        // A stateless plugin hands nothing over, so anything may replace it
+        let slot = PluginSlot::new(Counter::with_state(1, 1, false));
+        slot.replace(Counter::boxed(2, 10)).await.unwrap();
+    }
+
     #[test]
     fn test_plugin_loader_creation() {
         let loader = PluginLoader::new(true);
@@ -404,69 +404,145 @@ impl PluginMarketplace {
     }
 }
 
+/// Where the dependency resolver finds plugins that are not loaded yet
+#[async_trait::async_trait(?Send)]
+pub trait PluginSource {
+    /// Metadata of the available version of `plugin_id`, if there is one
+    async fn find_plugin(&self, plugin_id: &PluginId) -> Result<Option<PluginMetadata>>;
+}
+
+#[async_trait::async_trait(?Send)]
+impl PluginSource for PluginRegistry {
+    async fn find_plugin(&self, plugin_id: &PluginId) -> Result<Option<PluginMetadata>> {
+        match self.get_plugin(plugin_id).await {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(PluginError::PluginNotFound { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl PluginSource for alloc::collections::BTreeMap<PluginId, PluginMetadata> {
+    async fn find_plugin(&self, plugin_id: &PluginId) -> Result<Option<PluginMetadata>> {
+        Ok(self.get(plugin_id).cloned())
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<S: PluginSource> PluginSource for Option<S> {
+    async fn find_plugin(&self, plugin_id: &PluginId) -> Result<Option<PluginMetadata>> {
+        match self {
+            Some(source) => source.find_plugin(plugin_id).await,
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<S: PluginSource + ?Sized> PluginSource for &S {
+    async fn find_plugin(&self, plugin_id: &PluginId) -> Result<Option<PluginMetadata>> {
+        (**self).find_plugin(plugin_id).await
+    }
+}
+
 /// Plugin dependency resolver
 #[derive(Debug)]
-pub struct DependencyResolver {
-    /// Registry for dependency lookup
-    registry: PluginRegistry,
-    /// Resolved dependencies
-    resolved: alloc::collections::BTreeMap<PluginId, PluginVersion>,
+pub struct DependencyResolver<S = PluginRegistry> {
+    /// Source of plugins that are not loaded yet
+    source: S,
+    /// Plugins to load, dependencies first
+    resolved: alloc::vec::Vec<(PluginId, PluginVersion)>,
 }
 
-impl DependencyResolver {
+impl<S: PluginSource> DependencyResolver<S> {
     /// Create a new dependency resolver
-    pub fn new(registry: PluginRegistry) -> Self {
+    pub fn new(source: S) -> Self {
         Self {
-            registry,
-            resolved: alloc::collections::BTreeMap::new(),
+            source,
+            resolved: alloc::vec::Vec::new(),
         }
     }
 
-    /// Resolve all dependencies for a plugin
-    pub async fn resolve(&mut self, plugin_id: &PluginId, version: &PluginVersion) -> Result<alloc::vec::Vec<PluginDependency>> {
-        let mut to_resolve = alloc::vec::Vec::new();
-        let mut resolved_deps = alloc::vec::Vec::new();
-
-        to_resolve.push((plugin_id.clone(), version.clone()));
-
-        while let Some((id, ver)) = to_resolve.pop() {
-            if self.resolved.contains_key(&id) {
-                continue; // Already resolved
+    /// Resolve the dependencies of `root` against the `loaded` plugins.
+    ///
+    /// Each declared dependency must be satisfied by its loaded version or,
+    /// failing that, by the version the source offers. Returns the plugins
+    /// that have to be loaded before `root`, dependencies first. Optional
+    /// dependencies the source does not have are skipped; any other
+    /// conflict, missing plugin or cycle is an
+    /// [`PluginError::UnsatisfiedDependency`].
+    pub async fn resolve(
+        &mut self,
+        root: &PluginMetadata,
+        loaded: &alloc::collections::BTreeMap<PluginId, PluginVersion>,
+    ) -> Result<alloc::vec::Vec<PluginMetadata>> {
+        self.resolved.clear();
+        let mut to_load = alloc::vec::Vec::new();
+        let mut selected = loaded.clone();
+        // Depth-first path from the root, with the next dependency to visit
+        let mut path = alloc::vec![(root.clone(), 0)];
+
+        while let Some((metadata, next)) = path.last_mut() {
+            let Some(dependency) = metadata.dependencies.get(*next).cloned() else {
+                let (metadata, _) = path.pop().expect("path is not empty");
+                if !path.is_empty() {
+                    selected.insert(metadata.id.clone(), metadata.version.clone());
+                    self.resolved.push((metadata.id.clone(), metadata.version.clone()));
+                    to_load.push(metadata);
+                }
+                continue;
+            };
+            *next += 1;
+
+            let dependent = metadata.id.clone();
+            let error = |reason: alloc::string::String| PluginError::UnsatisfiedDependency {
+                plugin_id: dependent.clone(),
+                dependency: dependency.plugin_id.clone(),
+                reason,
+            };
+
+            let requirement = VersionRequirement::parse(&dependency.version_range).ok_or_else(|| PluginError::DependencyError {
+                plugin_id: dependent.clone(),
+                dependency: dependency.plugin_id.clone(),
+                reason: alloc::format!("invalid version requirement '{}'", dependency.version_range),
+            })?;
+
+            if let Some(start) = path.iter().position(|(metadata, _)| metadata.id == dependency.plugin_id) {
+                let mut cycle: alloc::vec::Vec<&str> = path[start..].iter().map(|(metadata, _)| metadata.id.as_str()).collect();
+                cycle.push(&dependency.plugin_id);
+                return Err(error(alloc::format!("circular dependency {}", cycle.join(" -> "))));
             }
 
-            // Get plugin metadata
-            let metadata = self.registry.get_plugin(&id).await?;
-
-            // Check version compatibility
-            if metadata.version < ver {
-                return Err(PluginError::Incompatible {
-                    plugin_id: id,
-                    required_version: ver.to_string(),
-                    current_version: metadata.version.to_string(),
-                });
+            if let Some(version) = selected.get(&dependency.plugin_id) {
+                if !requirement.matches(version) {
+                    return Err(error(alloc::format!(
+                        "requires {}, but version {} is already loaded",
+                        dependency.version_range, version
+                    )));
+                }
+                continue;
             }
 
-            self.resolved.insert(id.clone(), ver);
-
-            // Add dependencies to resolution queue
-            for dep in &metadata.dependencies {
-                if !self.resolved.contains_key(&dep.plugin_id) {
-                    // Parse version requirement
-                    let dep_version = PluginVersion::parse(&dep.version_range)
-                        .unwrap_or(PluginVersion::new(0, 0, 0));
-                    to_resolve.push((dep.plugin_id.clone(), dep_version));
+            match self.source.find_plugin(&dependency.plugin_id).await? {
+                Some(candidate) if requirement.matches(&candidate.version) => path.push((candidate, 0)),
+                Some(candidate) => {
+                    return Err(error(alloc::format!(
+                        "requires {}, but only version {} is available",
+                        dependency.version_range, candidate.version
+                    )));
                 }
-
-                resolved_deps.push(dep.clone());
+                None if dependency.optional => {}
+                None => return Err(error("not loaded and not available".into())),
             }
         }
 
-        Ok(resolved_deps)
+        Ok(to_load)
     }
 
     /// Get resolution order (dependencies first)
     pub fn resolution_order(&self) -> alloc::vec::Vec<(&PluginId, &PluginVersion)> {
-        self.resolved.iter().collect()
+        self.resolved.iter().map(|(id, version)| (id, version)).collect()
     }
 
     /// Clear resolved dependencies
@@ -536,6 +612,74 @@ mod tests {
         assert!(resolver.resolution_order().is_empty());
     }
 
+    fn plugin(id: &str, version: &str, dependencies: &[(&str, &str)]) -> PluginMetadata {
+        PluginMetadata {
+            id: id.into(),
+            name: id.into(),
+            version: PluginVersion::parse(version).unwrap(),
+            description: alloc::string::String::new(),
+            author: alloc::string::String::new(),
+            license: "MIT".into(),
+            dependencies: dependencies
+                .iter()
+                .map(|(plugin_id, version_range)| PluginDependency {
+                    plugin_id: (*plugin_id).into(),
+                    version_range: (*version_range).into(),
+                    optional: false,
+                })
+                .collect(),
+            capabilities: alloc::vec::Vec::new(),
+            permissions: alloc::vec::Vec::new(),
+            resource_limits: ResourceLimits::default(),
+        }
+    }
+
+    fn source(plugins: &[PluginMetadata]) -> alloc::collections::BTreeMap<PluginId, PluginMetadata> {
+        plugins.iter().map(|metadata| (metadata.id.clone(), metadata.clone())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_resolves_dependency_chain() {
+        let available = source(&[plugin("db", "1.3.0", &[("codec", ">=2.0")]), plugin("codec", "2.1.0", &[])]);
+        let loaded = alloc::collections::BTreeMap::from([("logging".into(), PluginVersion::new(1, 4, 0))]);
+        let app = plugin("app", "1.0.0", &[("db", "^1.2"), ("logging", "~1.4")]);
+
+        let mut resolver = DependencyResolver::new(&available);
+        let to_load = resolver.resolve(&app, &loaded).await.unwrap();
+
+        let ids: alloc::vec::Vec<&str> = to_load.iter().map(|metadata| metadata.id.as_str()).collect();
+        assert_eq!(ids, ["codec", "db"]);
+        assert_eq!(resolver.resolution_order().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_version_conflict_is_unsatisfied() {
+        let available = source(&[plugin("db", "1.3.0", &[("codec", "^2")])]);
+        let loaded = alloc::collections::BTreeMap::from([("codec".into(), PluginVersion::new(1, 9, 0))]);
+        let app = plugin("app", "1.0.0", &[("db", "1")]);
+
+        let error = DependencyResolver::new(&available).resolve(&app, &loaded).await.unwrap_err();
+        assert!(matches!(
+            error,
+            PluginError::UnsatisfiedDependency { ref plugin_id, ref dependency, .. } if plugin_id == "db" && dependency == "codec"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_circular_dependency_is_unsatisfied() {
+        let available = source(&[plugin("db", "1.0.0", &[("codec", "*")]), plugin("codec", "1.0.0", &[("db", "*")])]);
+        let app = plugin("app", "1.0.0", &[("db", "*")]);
+
+        let error = DependencyResolver::new(&available)
+            .resolve(&app, &alloc::collections::BTreeMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            PluginError::UnsatisfiedDependency { ref reason, .. } if reason.ends_with("db -> codec -> db")
+        ));
+    }
+
     #[test]
     fn test_plugin_marketplace() {
         let registry = PluginRegistry::new("https://registry.example.com".into());
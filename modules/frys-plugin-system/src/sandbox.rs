@@ -5,11 +5,13 @@ use crate::*;
 /// Sandbox configuration
 #[derive(Debug, Clone)]
 pub struct SandboxConfig {
-    /// Maximum memory usage
+    /// Maximum linear memory in bytes, capped at [`MAX_PLUGIN_MEMORY`]
     pub max_memory: usize,
-    /// Maximum CPU time per execution
+    /// Maximum CPU time per execution in milliseconds, metered as fuel;
+    /// 0 disables the limit
     pub max_cpu_time: u64,
-    /// Maximum execution time
+    /// Maximum wall-clock time per execution in milliseconds; 0 disables
+    /// the limit
     pub max_execution_time: u64,
     /// Allow network access
     pub allow_network: bool,
@@ -38,6 +40,17 @@ impl Default for SandboxConfig {
     }
 }
 
+impl From<&ResourceLimits> for SandboxConfig {
+    fn from(limits: &ResourceLimits) -> Self {
+        Self {
+            max_memory: usize::try_from(limits.max_memory).unwrap_or(usize::MAX).min(MAX_PLUGIN_MEMORY),
+            max_cpu_time: limits.max_cpu_time,
+            max_execution_time: limits.max_execution_time,
+            ..Self::default()
+        }
+    }
+}
+
 /// Plugin sandbox trait
 #[async_trait::async_trait(?Send)]
 pub trait PluginSandbox {
@@ -54,14 +67,44 @@ pub trait PluginSandbox {
     async fn reset(&self) -> Result<()>;
 }
 
+/// Fuel granted per millisecond of `max_cpu_time`, roughly the number of
+/// WASM instructions run in that time
+#[cfg(feature = "wasm")]
+pub const FUEL_PER_CPU_MS: u64 = 1_000_000;
+
+/// How often a sandbox advances its engine's epoch. Execution deadlines
+/// are enforced to this granularity.
+#[cfg(feature = "wasm")]
+const EPOCH_TICK_MS: u64 = 5;
+
+/// Fuel and epoch deadline of executions without a CPU or time limit
+#[cfg(feature = "wasm")]
+const UNLIMITED: u64 = 1 << 56;
+
 /// WASM-based sandbox implementation
+///
+/// Every execution instantiates the module in a fresh store, so limits
+/// apply per call:
+///
+/// - linear memory may not grow past `max_memory`, and never past
+///   [`MAX_PLUGIN_MEMORY`]
+/// - `max_cpu_time` becomes the store's fuel
+/// - `max_execution_time` becomes an epoch deadline that interrupts the
+///   instance
+///
+/// Each violation traps the instance and is reported as
+/// [`PluginError::ResourceLimitExceeded`].
 #[cfg(feature = "wasm")]
 #[derive(Debug)]
 pub struct WasmSandbox {
     /// Sandbox configuration
     config: SandboxConfig,
-    /// WASM engine
+    /// WASM engine, with fuel metering and epoch interruption enabled
     engine: wasmtime::Engine,
+    /// Advances the engine's epoch while the sandbox has a time limit
+    _ticker: Option<EpochTicker>,
+    /// Host functions plugins may import
+    host_imports: alloc::sync::Arc<HostImports>,
     /// Resource usage tracker
     resource_tracker: ResourceTracker,
 }
@@ -70,31 +113,198 @@ pub struct WasmSandbox {
 impl WasmSandbox {
     /// Create a new WASM sandbox
     pub fn new(config: SandboxConfig) -> Self {
-        let engine = wasmtime::Engine::default();
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(true).epoch_interruption(true);
+        let engine = wasmtime::Engine::new(&engine_config)
+            .expect("fuel metering and epoch interruption are supported on every target");
+        let ticker = (config.max_execution_time > 0).then(|| EpochTicker::start(engine.clone()));
         let resource_tracker = ResourceTracker::new();
 
         Self {
             config,
             engine,
+            _ticker: ticker,
+            host_imports: alloc::sync::Arc::new(HostImports::new()),
             resource_tracker,
         }
     }
 
+    /// Expose `imports` to the plugins run in this sandbox
+    pub fn with_host_imports(mut self, imports: alloc::sync::Arc<HostImports>) -> Self {
+        self.host_imports = imports;
+        self
+    }
+
+    /// Compile a WASM module (binary or text) for this sandbox's engine
+    pub fn compile(&self, path: &str, wasm: &[u8]) -> Result<wasmtime::Module> {
+        wasmtime::Module::new(&self.engine, wasm).map_err(|e| PluginError::LoadFailed {
+            path: path.into(),
+            reason: alloc::format!("WASM compilation failed: {}", e),
+        })
+    }
+
+    /// Run `function` of `module` on `input` within the sandbox limits.
+    ///
+    /// The module must export its `memory`, and an `alloc(len) -> ptr`
+    /// function when `input` is non-empty. `function` takes the pointer and
+    /// length of the input and returns its output as `ptr << 32 | len`.
+    /// The module may import the sandbox's host functions from
+    /// [`HOST_MODULE`]; each call is checked against `grants`, and a
+    /// denied call fails the execution with [`PluginError::PermissionDenied`].
+    pub fn run(&self, grants: &PluginGrants, module: &wasmtime::Module, function: &str, input: &[u8]) -> Result<PluginResult> {
+        let plugin_id = grants.plugin_id.as_str();
+        let started = std::time::Instant::now();
+        let linker = self.link_host_imports(grants, module)?;
+        let mut store = self.create_limited_store(plugin_id)?;
+        let outcome = call_export(&linker, &mut store, module, function, input);
+        let execution_time = started.elapsed().as_millis() as u64;
+
+        let fuel_consumed = store.fuel_consumed().unwrap_or(0);
+        self.resource_tracker.record_memory(store.data().peak_memory as u64);
+        self.resource_tracker.record_cpu_time(fuel_consumed / FUEL_PER_CPU_MS);
+
+        match outcome {
+            Ok(data) => Ok(PluginResult {
+                success: true,
+                data,
+                execution_time,
+                resource_usage: self.resource_usage(),
+                error: None,
+            }),
+            Err(error) => Err(self.execution_error(plugin_id, function, &store, &error, execution_time)),
+        }
+    }
+
+    /// Effective memory limit, never above `MAX_PLUGIN_MEMORY`
+    fn memory_limit(&self) -> usize {
+        if self.config.max_memory == 0 {
+            MAX_PLUGIN_MEMORY
+        } else {
+            self.config.max_memory.min(MAX_PLUGIN_MEMORY)
+        }
+    }
+
     /// Create store with limits
-    fn create_limited_store(&self) -> wasmtime::Store<()> {
-        let mut store = wasmtime::Store::new(&self.engine, ());
+    fn create_limited_store(&self, plugin_id: &str) -> Result<wasmtime::Store<SandboxLimiter>> {
+        let mut store = wasmtime::Store::new(&self.engine, SandboxLimiter::new(self.memory_limit()));
+        store.limiter(|limiter| limiter as &mut dyn wasmtime::ResourceLimiter);
+
+        let fuel = if self.config.max_cpu_time > 0 {
+            self.config.max_cpu_time.saturating_mul(FUEL_PER_CPU_MS)
+        } else {
+            UNLIMITED
+        };
+        store.add_fuel(fuel).map_err(|e| PluginError::SandboxError {
+            plugin_id: plugin_id.into(),
+            reason: alloc::format!("failed to add fuel: {}", e),
+        })?;
+
+        let ticks = if self.config.max_execution_time > 0 {
+            self.config.max_execution_time.div_ceil(EPOCH_TICK_MS)
+        } else {
+            UNLIMITED
+        };
+        store.set_epoch_deadline(ticks);
 
-        // Set memory limit
-        if self.config.max_memory > 0 {
-            // Implementation would set memory limit
+        Ok(store)
+    }
+
+    /// Define the host functions `module` imports, refusing those the
+    /// sandbox does not grant the permission for. Calls are further checked
+    /// against the plugin's own `grants`.
+    fn link_host_imports(&self, grants: &PluginGrants, module: &wasmtime::Module) -> Result<wasmtime::Linker<SandboxLimiter>> {
+        let plugin_id = grants.plugin_id.as_str();
+        let mut linker = wasmtime::Linker::new(&self.engine);
+
+        for import in module.imports().filter(|import| import.module() == HOST_MODULE) {
+            let Some(host_import) = self.host_imports.get(import.name()) else {
+                // Left undefined, so instantiation reports the unknown import
+                continue;
+            };
+            if let Some(permission) = &host_import.permission {
+                if !self.check_permission(permission) {
+                    return Err(PluginError::SecurityViolation {
+                        plugin_id: plugin_id.into(),
+                        violation: alloc::format!("import '{}' requires {:?} permission", import.name(), permission),
+                    });
+                }
+            }
+
+            let name = alloc::string::String::from(import.name());
+            let caller_grants = grants.clone();
+            let imports = self.host_imports.clone();
+            let host_import = host_import.clone();
+            linker
+                .func_new(HOST_MODULE, import.name(), host_func_type(&host_import), move |mut caller, params, results| {
+                    let args = read_host_args(&mut caller, &host_import.params, params)?;
+                    let value = imports.call(&caller_grants, &name, &args).map_err(|e| {
+                        let message = wasmtime::Error::msg(alloc::format!("{}", e));
+                        if matches!(e, PluginError::PermissionDenied { .. }) {
+                            caller.data_mut().denied = Some(e);
+                        }
+                        message
+                    })?;
+                    if host_import.result.is_some() {
+                        results[0] = write_host_result(&mut caller, value)?;
+                    }
+                    Ok(())
+                })
+                .map_err(|e| PluginError::SandboxError {
+                    plugin_id: plugin_id.into(),
+                    reason: alloc::format!("failed to link '{}': {}", import.name(), e),
+                })?;
         }
 
-        // Set execution time limit
-        if self.config.max_execution_time > 0 {
-            // Implementation would set execution time limit
+        Ok(linker)
+    }
+
+    /// Translate a failed call into the limit it violated, if any
+    fn execution_error(
+        &self,
+        plugin_id: &str,
+        function: &str,
+        store: &wasmtime::Store<SandboxLimiter>,
+        error: &wasmtime::Error,
+        execution_time: u64,
+    ) -> PluginError {
+        let exceeded = |resource: &str, limit: alloc::string::String, actual: alloc::string::String| {
+            PluginError::ResourceLimitExceeded {
+                plugin_id: plugin_id.into(),
+                resource: resource.into(),
+                limit,
+                actual,
+            }
+        };
+
+        if let Some(denied) = &store.data().denied {
+            return denied.clone();
         }
 
-        store
+        if let Some(requested) = store.data().refused_memory {
+            return exceeded(
+                "memory",
+                alloc::format!("{} bytes", self.memory_limit()),
+                alloc::format!("{} bytes", requested),
+            );
+        }
+
+        match error.downcast_ref::<wasmtime::Trap>() {
+            Some(wasmtime::Trap::OutOfFuel) => exceeded(
+                "cpu_time",
+                alloc::format!("{}ms", self.config.max_cpu_time),
+                alloc::format!("{}ms", store.fuel_consumed().unwrap_or(0) / FUEL_PER_CPU_MS),
+            ),
+            Some(wasmtime::Trap::Interrupt) => exceeded(
+                "execution_time",
+                alloc::format!("{}ms", self.config.max_execution_time),
+                alloc::format!("{}ms", execution_time),
+            ),
+            _ => PluginError::ExecutionFailed {
+                plugin_id: plugin_id.into(),
+                function: function.into(),
+                reason: alloc::format!("{}", error),
+            },
+        }
     }
 
     /// Check if network access is allowed
@@ -128,26 +338,215 @@ impl WasmSandbox {
     }
 }
 
+/// Instantiate `module` in `store`, copy `input` into it and call `function`
+#[cfg(feature = "wasm")]
+fn call_export(
+    linker: &wasmtime::Linker<SandboxLimiter>,
+    store: &mut wasmtime::Store<SandboxLimiter>,
+    module: &wasmtime::Module,
+    function: &str,
+    input: &[u8],
+) -> wasmtime::Result<alloc::vec::Vec<u8>> {
+    let instance = linker.instantiate(&mut *store, module)?;
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("plugin does not export 'memory'"))?;
+
+    let (ptr, len) = if input.is_empty() {
+        (0, 0)
+    } else {
+        let allocate = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+        let len = i32::try_from(input.len())?;
+        let ptr = allocate.call(&mut *store, len)?;
+        memory.write(&mut *store, ptr as u32 as usize, input)?;
+        (ptr, len)
+    };
+
+    let func = instance.get_typed_func::<(i32, i32), i64>(&mut *store, function)?;
+    let packed = func.call(&mut *store, (ptr, len))?;
+
+    let mut output = alloc::vec![0; packed as u32 as usize];
+    memory.read(&*store, (packed >> 32) as u32 as usize, &mut output)?;
+    Ok(output)
+}
+
+/// WASM signature of a host function: integers are `i64`, strings and
+/// bytes a pointer and length, and a result an `i64`
+#[cfg(feature = "wasm")]
+fn host_func_type(import: &HostImport) -> wasmtime::FuncType {
+    let params = import.params.iter().flat_map(|param| match param {
+        HostValueType::Int => alloc::vec![wasmtime::ValType::I64],
+        HostValueType::String | HostValueType::Bytes => alloc::vec![wasmtime::ValType::I32, wasmtime::ValType::I32],
+    });
+    let results = import.result.map(|_| wasmtime::ValType::I64);
+    wasmtime::FuncType::new(params, results)
+}
+
+/// Copy `len` bytes at `ptr` out of the calling plugin's memory
+#[cfg(feature = "wasm")]
+fn read_plugin_memory(
+    caller: &mut wasmtime::Caller<'_, SandboxLimiter>,
+    ptr: i32,
+    len: i32,
+) -> wasmtime::Result<alloc::vec::Vec<u8>> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(wasmtime::Extern::into_memory)
+        .ok_or_else(|| wasmtime::Error::msg("plugin does not export 'memory'"))?;
+    let (ptr, len) = (ptr as u32 as usize, len as u32 as usize);
+    // Check the range before allocating, so a bogus length cannot make
+    // the host allocate gigabytes
+    if !matches!(ptr.checked_add(len), Some(end) if end <= memory.data_size(&*caller)) {
+        return Err(wasmtime::Error::msg("host call argument is out of bounds"));
+    }
+
+    let mut bytes = alloc::vec![0; len];
+    memory.read(&*caller, ptr, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Unmarshal the WASM arguments of a host call
+#[cfg(feature = "wasm")]
+fn read_host_args(
+    caller: &mut wasmtime::Caller<'_, SandboxLimiter>,
+    types: &[HostValueType],
+    params: &[wasmtime::Val],
+) -> wasmtime::Result<alloc::vec::Vec<MessageValue>> {
+    // The linker has already checked the values against the signature
+    let mut params = params.iter();
+    let mut next = || params.next().cloned().ok_or_else(|| wasmtime::Error::msg("missing host call argument"));
+
+    let mut args = alloc::vec::Vec::with_capacity(types.len());
+    for ty in types {
+        args.push(match ty {
+            HostValueType::Int => MessageValue::Int(next()?.unwrap_i64()),
+            HostValueType::String | HostValueType::Bytes => {
+                let (ptr, len) = (next()?.unwrap_i32(), next()?.unwrap_i32());
+                let bytes = read_plugin_memory(caller, ptr, len)?;
+                if *ty == HostValueType::Bytes {
+                    MessageValue::Bytes(bytes)
+                } else {
+                    MessageValue::String(alloc::string::String::from_utf8(bytes)?)
+                }
+            }
+        });
+    }
+    Ok(args)
+}
+
+/// Marshal the result of a host call, copying strings and bytes into
+/// memory obtained from the plugin's `alloc`
+#[cfg(feature = "wasm")]
+fn write_host_result(
+    caller: &mut wasmtime::Caller<'_, SandboxLimiter>,
+    value: MessageValue,
+) -> wasmtime::Result<wasmtime::Val> {
+    let bytes = match value {
+        MessageValue::Null => return Ok(wasmtime::Val::I64(-1)),
+        MessageValue::Int(n) => return Ok(wasmtime::Val::I64(n)),
+        MessageValue::String(s) => s.into_bytes(),
+        MessageValue::Bytes(bytes) => bytes,
+        other => return Err(wasmtime::Error::msg(alloc::format!("cannot pass {:?} to a plugin", other))),
+    };
+
+    let allocate = caller
+        .get_export("alloc")
+        .and_then(wasmtime::Extern::into_func)
+        .ok_or_else(|| wasmtime::Error::msg("plugin does not export 'alloc'"))?
+        .typed::<i32, i32>(&*caller)?;
+    let len = i32::try_from(bytes.len())?;
+    let ptr = allocate.call(&mut *caller, len)?;
+    let memory = caller
+        .get_export("memory")
+        .and_then(wasmtime::Extern::into_memory)
+        .ok_or_else(|| wasmtime::Error::msg("plugin does not export 'memory'"))?;
+    memory.write(&mut *caller, ptr as u32 as usize, &bytes)?;
+
+    Ok(wasmtime::Val::I64(i64::from(ptr as u32) << 32 | i64::from(len as u32)))
+}
+
+/// Store data enforcing the memory limit of one execution
+#[cfg(feature = "wasm")]
+#[derive(Debug)]
+struct SandboxLimiter {
+    max_memory: usize,
+    peak_memory: usize,
+    /// Size of the refused growth, once the instance tried to exceed the limit
+    refused_memory: Option<usize>,
+    /// Host call refused for lack of a permission or capability
+    denied: Option<PluginError>,
+}
+
+#[cfg(feature = "wasm")]
+impl SandboxLimiter {
+    fn new(max_memory: usize) -> Self {
+        Self {
+            max_memory,
+            peak_memory: 0,
+            refused_memory: None,
+            denied: None,
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl wasmtime::ResourceLimiter for SandboxLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> wasmtime::Result<bool> {
+        if desired > self.max_memory {
+            // Trap rather than fail the grow, so the plugin cannot carry on
+            self.refused_memory = Some(desired);
+            return Err(wasmtime::Error::msg("memory limit exceeded"));
+        }
+        self.peak_memory = self.peak_memory.max(desired);
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: u32, _desired: u32, _maximum: Option<u32>) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Background thread advancing an engine's epoch every `EPOCH_TICK_MS`
+/// until dropped
+#[cfg(feature = "wasm")]
+#[derive(Debug)]
+struct EpochTicker {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(feature = "wasm")]
+impl EpochTicker {
+    fn start(engine: wasmtime::Engine) -> Self {
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            while !stopped.load(std::sync::atomic::Ordering::Relaxed) {
+                std::thread::sleep(std::time::Duration::from_millis(EPOCH_TICK_MS));
+                engine.increment_epoch();
+            }
+        });
+        Self { stop }
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
 #[cfg(feature = "wasm")]
 #[async_trait::async_trait(?Send)]
 impl PluginSandbox for WasmSandbox {
     async fn execute(&self, plugin: &dyn PluginInstance, function: &str, input: &[u8]) -> Result<PluginResult> {
-        let start_time = current_timestamp();
-
-        // Create limited store
-        let mut store = self.create_limited_store();
-
-        // Implementation would execute WASM function with limits
-        // For now, return placeholder result
-        let result = PluginResult {
-            success: false,
-            data: alloc::vec::Vec::new(),
-            execution_time: current_timestamp() - start_time,
-            resource_usage: self.resource_usage(),
-            error: Some("WASM execution not implemented".into()),
-        };
+        let plugin_id = &plugin.metadata().id;
+        let module = plugin.wasm_module().ok_or_else(|| PluginError::SandboxError {
+            plugin_id: plugin_id.clone(),
+            reason: "not a WASM plugin".into(),
+        })?;
 
-        Ok(result)
+        self.run(&PluginGrants::from(plugin.metadata()), module, function, input)
     }
 
     fn check_permission(&self, permission: &PluginPermission) -> bool {
@@ -374,12 +773,6 @@ impl SandboxFactory {
     }
 }
 
-/// Get current timestamp (simplified)
-fn current_timestamp() -> u64 {
-    // In a real implementation, this would use system time
-    0
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -447,6 +840,155 @@ mod tests {
         assert!(!sandbox.check_permission(&PluginPermission::Admin));
     }
 
+    /// Plugin with a well-behaved `echo` and two functions that run away
+    #[cfg(feature = "wasm")]
+    const RUNAWAY_PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+              (i64.extend_i32_u (local.get $len))))
+          (func (export "grow") (param i32 i32) (result i64)
+            (loop $more
+              (br_if $more (i32.ne (memory.grow (i32.const 1)) (i32.const -1))))
+            (i64.const 0))
+          (func (export "spin") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))
+    "#;
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_wasm_memory_limit_traps() {
+        let sandbox = WasmSandbox::new(SandboxConfig {
+            max_memory: 1024 * 1024,
+            ..SandboxConfig::default()
+        });
+        let module = sandbox.compile("runaway.wat", RUNAWAY_PLUGIN.as_bytes()).unwrap();
+
+        let error = sandbox.run(&PluginGrants::new("runaway"), &module, "grow", &[]).unwrap_err();
+        assert!(matches!(
+            error,
+            PluginError::ResourceLimitExceeded { ref resource, .. } if resource == "memory"
+        ));
+        assert_eq!(sandbox.resource_usage().memory_usage, 1024 * 1024);
+
+        // The trapped instance is gone; the next call starts afresh
+        let result = sandbox.run(&PluginGrants::new("runaway"), &module, "echo", b"ok").unwrap();
+        assert_eq!(result.data, b"ok");
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_wasm_execution_deadline_interrupts() {
+        let sandbox = WasmSandbox::new(SandboxConfig {
+            max_cpu_time: 0,
+            max_execution_time: 50,
+            ..SandboxConfig::default()
+        });
+        let module = sandbox.compile("runaway.wat", RUNAWAY_PLUGIN.as_bytes()).unwrap();
+
+        let error = sandbox.run(&PluginGrants::new("runaway"), &module, "spin", &[]).unwrap_err();
+        assert!(matches!(
+            error,
+            PluginError::ResourceLimitExceeded { ref resource, .. } if resource == "execution_time"
+        ));
+
+        let result = sandbox.run(&PluginGrants::new("runaway"), &module, "echo", b"ok").unwrap();
+        assert!(result.success);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_wasm_plugin_calls_host_log() {
+        let received = alloc::sync::Arc::new(std::sync::Mutex::new(alloc::vec::Vec::new()));
+        let sink = received.clone();
+        let mut imports = HostImports::new();
+        imports.register_log(move |plugin_id, level, message| {
+            sink.lock().unwrap().push((plugin_id.clone(), level, alloc::string::String::from(message)));
+        });
+        imports.register_config(alloc::collections::BTreeMap::new());
+        let sandbox = WasmSandbox::new(SandboxConfig::default()).with_host_imports(alloc::sync::Arc::new(imports));
+
+        let logger = sandbox
+            .compile(
+                "logger.wat",
+                br#"
+                (module
+                  (import "env" "host_log" (func $log (param i64 i32 i32)))
+                  (memory (export "memory") 1)
+                  (data (i32.const 16) "hello host")
+                  (func (export "run") (param i32 i32) (result i64)
+                    (call $log (i64.const 2) (i32.const 16) (i32.const 10))
+                    (i64.const 0)))
+                "#,
+            )
+            .unwrap();
+        sandbox.run(&PluginGrants::new("logger"), &logger, "run", &[]).unwrap();
+        assert_eq!(*received.lock().unwrap(), [("logger".into(), 2, "hello host".into())]);
+
+        // host_get_config needs the Read permission, which this sandbox lacks
+        let reader = sandbox
+            .compile(
+                "reader.wat",
+                br#"
+                (module
+                  (import "env" "host_get_config" (func (param i32 i32) (result i64)))
+                  (memory (export "memory") 1)
+                  (func (export "run") (param i32 i32) (result i64) (i64.const 0)))
+                "#,
+            )
+            .unwrap();
+        assert!(matches!(
+            sandbox.run(&PluginGrants::new("reader"), &reader, "run", &[]),
+            Err(PluginError::SecurityViolation { .. })
+        ));
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_host_call_checks_plugin_permissions() {
+        let writes = alloc::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = writes.clone();
+        let mut imports = HostImports::new();
+        imports.register("host_write", &[HostValueType::Int], None, Some(PluginPermission::Write), move |_, _| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(MessageValue::Null)
+        });
+        let config = SandboxConfig {
+            allow_fs: true,
+            ..SandboxConfig::default()
+        };
+        let sandbox = WasmSandbox::new(config).with_host_imports(alloc::sync::Arc::new(imports));
+        let writer = sandbox
+            .compile(
+                "writer.wat",
+                br#"
+                (module
+                  (import "env" "host_write" (func $write (param i64)))
+                  (memory (export "memory") 1)
+                  (func (export "run") (param i32 i32) (result i64)
+                    (call $write (i64.const 7))
+                    (i64.const 0)))
+                "#,
+            )
+            .unwrap();
+
+        // The sandbox links the import, but this plugin may only read
+        let read_only = PluginGrants::new("writer").with_permission(PluginPermission::Read);
+        assert!(matches!(
+            sandbox.run(&read_only, &writer, "run", &[]),
+            Err(PluginError::PermissionDenied { ref operation, .. }) if operation == "host_write"
+        ));
+        assert_eq!(writes.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+        let read_write = read_only.with_permission(PluginPermission::Write);
+        assert!(sandbox.run(&read_write, &writer, "run", &[]).unwrap().success);
+        assert_eq!(writes.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+
     #[test]
     fn test_sandbox_factory() {
         let config = SandboxConfig::default();
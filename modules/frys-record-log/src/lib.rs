@@ -0,0 +1,116 @@
+//! # Frys Record Log
+//!
+//! Record framing shared by the append-only logs of Frys modules, such as
+//! the vector index's operation log.
+//!
+//! Each record is framed as `[len: u32][crc32: u32][body]`, both header
+//! fields little-endian, and records are appended back to back. A crash
+//! can leave the last record torn; [`next_record`] stops at the first
+//! record that is cut short or fails its checksum, so readers replay the
+//! intact prefix and discard the rest.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use frys_record_log::{frame, next_record};
+//!
+//! let mut log = frame(b"first");
+//! log.extend(frame(b"second"));
+//! log.extend_from_slice(&frame(b"torn")[..6]);
+//!
+//! let mut offset = 0;
+//! let mut bodies = Vec::new();
+//! while let Some((body, next)) = next_record(&log, offset) {
+//!     bodies.push(body);
+//!     offset = next;
+//! }
+//! assert_eq!(bodies, [&b"first"[..], &b"second"[..]]);
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Bytes preceding each record body: its length and its checksum
+pub const HEADER_LEN: usize = 8;
+
+/// `body` framed as a log record
+pub fn frame(body: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(HEADER_LEN + body.len());
+    record.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32(body).to_le_bytes());
+    record.extend_from_slice(body);
+    record
+}
+
+/// Body of the record at `offset` and the offset after it, or `None` at
+/// the end of the log or at a torn record
+pub fn next_record(bytes: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let header = bytes.get(offset..offset.checked_add(HEADER_LEN)?)?;
+    let len = u32::from_le_bytes(header[..4].try_into().ok()?) as usize;
+    let crc = u32::from_le_bytes(header[4..].try_into().ok()?);
+    let end = (offset + HEADER_LEN).checked_add(len)?;
+    let body = bytes.get(offset + HEADER_LEN..end)?;
+    (crc32(body) == crc).then_some((body, end))
+}
+
+/// CRC-32 (IEEE) checksum
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        let mut log = frame(b"one");
+        log.extend(frame(b""));
+        log.extend(frame(b"three"));
+
+        let (body, offset) = next_record(&log, 0).unwrap();
+        assert_eq!(body, b"one");
+        let (body, offset) = next_record(&log, offset).unwrap();
+        assert!(body.is_empty());
+        let (body, offset) = next_record(&log, offset).unwrap();
+        assert_eq!(body, b"three");
+        assert_eq!(offset, log.len());
+        assert!(next_record(&log, offset).is_none());
+    }
+
+    #[test]
+    fn test_torn_and_corrupt_records_end_the_log() {
+        let record = frame(b"payload");
+        for len in 0..record.len() {
+            assert!(next_record(&record[..len], 0).is_none(), "{} bytes", len);
+        }
+
+        let mut corrupt = record.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(next_record(&corrupt, 0).is_none());
+
+        // A garbage length must not overflow
+        let mut huge = record;
+        huge[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(next_record(&huge, usize::MAX - 4).is_none());
+        assert!(next_record(&huge, 0).is_none());
+    }
+}
@@ -1,6 +1,8 @@
 //! Vector search algorithms implementation
 
 use crate::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 /// Algorithm selector based on dataset characteristics
 #[derive(Debug)]
@@ -99,18 +101,17 @@ impl AlgorithmSelector {
 #[cfg(feature = "hnsw")]
 pub mod hnsw {
     use super::*;
-    use space::HNSW;
 
     /// HNSW index implementation
     #[derive(Debug)]
     pub struct HNSWIndex {
-        /// Space HNSW index
-        index: HNSW<VectorElement>,
-        /// Vector store
-        vectors: alloc::vec::Vec<Vector>,
-        /// Metadata store
+        /// Proximity graph over the vectors
+        index: HnswGraph,
+        /// Metadata store, by graph node
         metadata: alloc::vec::Vec<VectorMetadata>,
-        /// ID mapping
+        /// ID of each graph node
+        index_to_id: alloc::vec::Vec<VectorId>,
+        /// Live graph node of each ID
         id_to_index: alloc::collections::BTreeMap<VectorId, usize>,
         /// Configuration
         config: HNSWConfig,
@@ -125,6 +126,8 @@ pub mod hnsw {
         pub ef_construction: usize,
         /// Normalization factor for level generation
         pub level_norm_factor: VectorElement,
+        /// Distance metric the graph is built and searched with
+        pub metric: Metric,
     }
 
     impl Default for HNSWConfig {
@@ -133,6 +136,7 @@ pub mod hnsw {
                 max_connections: DEFAULT_M,
                 ef_construction: DEFAULT_EF_CONSTRUCTION,
                 level_norm_factor: 1.0 / (DEFAULT_M as VectorElement).ln(),
+                metric: Metric::Cosine,
             }
         }
     }
@@ -140,47 +144,75 @@ pub mod hnsw {
     impl HNSWIndex {
         /// Create a new HNSW index
         pub fn new(config: HNSWConfig) -> Self {
-            let index = HNSW::new(config.max_connections, config.ef_construction, config.level_norm_factor);
+            let index = HnswGraph::new(config.max_connections, config.ef_construction, config.level_norm_factor, config.metric);
 
             Self {
                 index,
-                vectors: alloc::vec::Vec::new(),
                 metadata: alloc::vec::Vec::new(),
+                index_to_id: alloc::vec::Vec::new(),
                 id_to_index: alloc::collections::BTreeMap::new(),
                 config,
             }
         }
 
-        /// Insert a vector into the index
+        /// Insert a vector into the index, replacing any vector with the same ID
         pub fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
-            let index = self.vectors.len();
+            let index = self.index.insert(vector)?;
+            self.delete(&id);
 
-            // Store vector and metadata
-            self.vectors.push(vector.clone());
             self.metadata.push(metadata);
+            self.index_to_id.push(id.clone());
             self.id_to_index.insert(id, index);
 
-            // Insert into HNSW index
-            self.index.insert(vector.as_slice(), index);
+            Ok(())
+        }
 
+        /// Delete a vector, returning whether it existed. Its graph node is
+        /// tombstoned rather than unlinked, until [`compact`](Self::compact)
+        /// drops it.
+        pub fn delete(&mut self, id: &VectorId) -> bool {
+            match self.id_to_index.remove(id) {
+                Some(index) => self.index.delete(index),
+                None => false,
+            }
+        }
+
+        /// Rebuild the graph without its tombstoned nodes. Takes time
+        /// proportional to rebuilding the index, so it only runs when the
+        /// index is optimized.
+        pub fn compact(&mut self) -> Result<()> {
+            let remap = self.index.compact()?;
+            let mut metadata = alloc::vec::Vec::with_capacity(self.index.len());
+            let mut index_to_id = alloc::vec::Vec::with_capacity(self.index.len());
+            for ((new, meta), id) in remap.iter().zip(self.metadata.drain(..)).zip(self.index_to_id.drain(..)) {
+                if new.is_some() {
+                    metadata.push(meta);
+                    index_to_id.push(id);
+                }
+            }
+            for index in self.id_to_index.values_mut() {
+                *index = remap[*index].expect("live IDs map to live nodes");
+            }
+            self.metadata = metadata;
+            self.index_to_id = index_to_id;
             Ok(())
         }
 
+
         /// Search for nearest neighbors
         pub fn search(&self, query: &Vector, k: usize, ef: usize) -> Result<alloc::vec::Vec<SearchResult>> {
-            let neighbors = self.index.search(query.as_slice(), k, ef);
+            let neighbors = self.index.search(query, k, ef)?;
 
             let results = neighbors.into_iter()
                 .map(|(index, distance)| {
-                    let id = self.id_to_index.iter()
-                        .find(|(_, &idx)| idx == index)
-                        .map(|(id, _)| id.clone())
-                        .unwrap_or_else(|| format!("unknown-{}", index));
-
                     SearchResult {
-                        id,
-                        score: 1.0 - distance, // Convert distance to similarity score
+                        id: self.index_to_id[index].clone(),
+                        score: match self.config.metric {
+                            Metric::Cosine => 1.0 - distance,
+                            _ => 1.0 / (1.0 + distance),
+                        },
                         distance,
+                        metric: self.config.metric,
                         vector: None,
                         metadata: Some(self.metadata[index].clone()),
                     }
@@ -190,13 +222,19 @@ pub mod hnsw {
             Ok(results)
         }
 
+        /// Describe the shape of the graph
+        pub fn graph_diagnostics(&self) -> GraphDiagnostics {
+            self.index.diagnostics()
+        }
+
         /// Get statistics
         pub fn stats(&self) -> IndexStats {
+            let dims = self.index.dimensions().unwrap_or(0);
             IndexStats {
-                total_vectors: self.vectors.len() as u64,
-                memory_usage: (self.vectors.len() * self.vectors[0].dims() * 4) as u64, // Rough estimate
+                total_vectors: self.id_to_index.len() as u64,
+                memory_usage: (self.index.len() * dims * 4) as u64, // Rough estimate
                 build_time_ms: 0, // Would track actual build time
-                avg_dimensions: self.vectors.first().map(|v| v.dims()).unwrap_or(0),
+                avg_dimensions: dims,
                 disk_usage: 0, // Would calculate actual disk usage
                 last_updated: current_timestamp(),
             }
@@ -222,6 +260,8 @@ pub mod ivf {
         id_to_index: alloc::collections::BTreeMap<VectorId, usize>,
         /// Number of centroids
         num_centroids: usize,
+        /// Distance metric
+        metric: Metric,
     }
 
     impl IVFIndex {
@@ -241,6 +281,7 @@ pub mod ivf {
                 metadata: alloc::vec::Vec::new(),
                 id_to_index: alloc::collections::BTreeMap::new(),
                 num_centroids,
+                metric,
             })
         }
 
@@ -280,6 +321,7 @@ pub mod ivf {
                         id,
                         score: if distance > 0.0 { 1.0 / (1.0 + distance) } else { 1.0 },
                         distance,
+                        metric: self.metric,
                         vector: None,
                         metadata: Some(self.metadata[actual_index].clone()),
                     }
@@ -303,17 +345,31 @@ pub mod ivf {
     }
 }
 
-/// Flat (brute force) search for small datasets or exact search
+/// Flat (brute force) search for small datasets or exact search.
+///
+/// At [`Precision::Int8`], vectors inserted before [`train`](Self::train)
+/// are kept at full precision; training learns a [`ScalarQuantizer`] from a
+/// sample of them, encodes them all and frees the originals.
 #[derive(Debug)]
 pub struct FlatIndex {
-    /// Vector store
+    /// Full-precision vector store
     vectors: alloc::vec::Vec<Vector>,
+    /// Quantized vectors, `dimensions` bytes each, once trained
+    codes: alloc::vec::Vec<u8>,
+    /// Quantizer of `codes`, once trained
+    quantizer: Option<ScalarQuantizer>,
+    /// Precision vectors are stored at
+    precision: Precision,
     /// Metadata store
     metadata: alloc::vec::Vec<VectorMetadata>,
     /// ID mapping
     id_to_index: alloc::collections::BTreeMap<VectorId, usize>,
     /// Distance metric
     metric: Metric,
+    /// Kernel scoring the scan
+    kernel: DistanceKernel,
+    /// Copy of `vectors` on the kernel's device
+    resident: ResidentRows,
 }
 
 impl FlatIndex {
@@ -321,41 +377,104 @@ impl FlatIndex {
     pub fn new(metric: Metric) -> Self {
         Self {
             vectors: alloc::vec::Vec::new(),
+            codes: alloc::vec::Vec::new(),
+            quantizer: None,
+            precision: Precision::F32,
             metadata: alloc::vec::Vec::new(),
             id_to_index: alloc::collections::BTreeMap::new(),
             metric,
+            kernel: DistanceKernel::cpu(),
+            resident: ResidentRows::new(),
+        }
+    }
+
+    /// Score the scan with `kernel`
+    pub fn with_kernel(mut self, kernel: DistanceKernel) -> Self {
+        self.kernel = kernel;
+        self.resident.clear();
+        self
+    }
+
+    /// Store vectors at `precision` once trained
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Whether vectors are stored quantized
+    pub fn is_trained(&self) -> bool {
+        self.quantizer.is_some()
+    }
+
+    /// Dimensionality of the indexed vectors, if any were inserted
+    fn dimensions(&self) -> Option<usize> {
+        match &self.quantizer {
+            Some(quantizer) => Some(quantizer.dimensions()),
+            None => self.vectors.first().map(Vector::dims),
         }
     }
 
     /// Insert a vector into the index
     pub fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
-        let index = self.vectors.len();
+        let index = self.metadata.len();
 
         // Validate dimensions
-        if !self.vectors.is_empty() && self.vectors[0].dims() != vector.dims() {
+        if let Some(expected) = self.dimensions().filter(|expected| *expected != vector.dims()) {
             return Err(VectorSearchError::InvalidDimensions {
-                expected: self.vectors[0].dims(),
+                expected,
                 actual: vector.dims(),
             });
         }
 
-        self.vectors.push(vector);
+        match &self.quantizer {
+            Some(quantizer) => self.codes.extend(quantizer.encode(vector.as_slice())),
+            None => self.vectors.push(vector),
+        }
         self.metadata.push(metadata);
         self.id_to_index.insert(id, index);
 
         Ok(())
     }
 
+    /// Train the scalar quantizer on a sample of the vectors inserted so
+    /// far, then encode them. Does nothing at [`Precision::F32`] or once
+    /// trained.
+    pub fn train(&mut self) -> Result<()> {
+        if self.precision == Precision::F32 || self.is_trained() {
+            return Ok(());
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let sample_size = self.vectors.len().min(DEFAULT_SQ_TRAINING_SAMPLE);
+        let sample: alloc::vec::Vec<&[VectorElement]> = rand::seq::index::sample(&mut rng, self.vectors.len(), sample_size)
+            .into_iter()
+            .map(|i| self.vectors[i].as_slice())
+            .collect();
+        let quantizer = ScalarQuantizer::train(&sample)?;
+
+        self.codes = ::core::mem::take(&mut self.vectors)
+            .iter()
+            .flat_map(|vector| quantizer.encode(vector.as_slice()))
+            .collect();
+        self.quantizer = Some(quantizer);
+        self.resident.clear();
+        Ok(())
+    }
+
     /// Search for nearest neighbors
     pub fn search(&self, query: &Vector, k: usize) -> Result<alloc::vec::Vec<SearchResult>> {
-        // Calculate distances to all vectors
-        let mut distances: alloc::vec::Vec<(usize, VectorElement)> = self.vectors.iter()
-            .enumerate()
-            .map(|(index, vector)| {
-                let distance = self.metric.distance(query, vector).unwrap_or(VectorElement::INFINITY);
-                (index, distance)
-            })
-            .collect();
+        // Calculate distances to all vectors in one batch
+        let distances = match &self.quantizer {
+            Some(quantizer) => {
+                let rows: alloc::vec::Vec<&[u8]> = self.codes.chunks_exact(quantizer.dimensions().max(1)).collect();
+                quantizer.distances(self.metric, query.as_slice(), &rows)
+            }
+            None => {
+                let rows: alloc::vec::Vec<&[VectorElement]> = self.vectors.iter().map(Vector::as_slice).collect();
+                self.kernel.resident_distances(self.metric, query.as_slice(), &rows, &self.resident)
+            }
+        };
+        let mut distances: alloc::vec::Vec<(usize, VectorElement)> = distances.into_iter().enumerate().collect();
 
         // Sort by distance (ascending for distance metrics, descending for similarity)
         if self.metric.lower_is_better() {
@@ -385,6 +504,7 @@ impl FlatIndex {
                     id,
                     score,
                     distance,
+                    metric: self.metric,
                     vector: None,
                     metadata: Some(self.metadata[index].clone()),
                 }
@@ -396,11 +516,12 @@ impl FlatIndex {
 
     /// Get statistics
     pub fn stats(&self) -> IndexStats {
+        let dimensions = self.dimensions().unwrap_or(0);
         IndexStats {
-            total_vectors: self.vectors.len() as u64,
-            memory_usage: (self.vectors.len() * self.vectors.first().map(|v| v.dims()).unwrap_or(0) * 4) as u64,
+            total_vectors: self.metadata.len() as u64,
+            memory_usage: (self.vectors.len() * dimensions * 4 + self.codes.len()) as u64,
             build_time_ms: 0,
-            avg_dimensions: self.vectors.first().map(|v| v.dims()).unwrap_or(0),
+            avg_dimensions: dimensions,
             disk_usage: 0,
             last_updated: current_timestamp(),
         }
@@ -416,13 +537,18 @@ impl AlgorithmFactory {
     pub fn create_index(algorithm: Algorithm, config: &EngineConfig) -> Result<Box<dyn VectorIndex>> {
         match algorithm {
             Algorithm::Flat => {
-                let index = FlatIndex::new(config.metric);
+                let index = FlatIndex::new(config.metric)
+                    .with_kernel(DistanceKernel::new(config.gpu_enabled))
+                    .with_precision(config.storage_precision);
                 Ok(Box::new(index))
             }
             Algorithm::HNSW => {
                 #[cfg(feature = "hnsw")]
                 {
-                    let hnsw_config = hnsw::HNSWConfig::default();
+                    let hnsw_config = hnsw::HNSWConfig {
+                        metric: config.metric,
+                        ..hnsw::HNSWConfig::default()
+                    };
                     let index = hnsw::HNSWIndex::new(hnsw_config);
                     Ok(Box::new(index))
                 }
@@ -431,7 +557,16 @@ impl AlgorithmFactory {
                     algorithm: "HNSW".into(),
                 })
             }
-            Algorithm::IVF | Algorithm::IVFPQ => {
+            Algorithm::IVFPQ => {
+                let index = IvfPqIndex::new(config.dimensions, config.metric, config.ivf.clone())?
+                    .with_kernel(DistanceKernel::new(config.gpu_enabled));
+                Ok(Box::new(index))
+            }
+            Algorithm::LSH => {
+                let index = LshIndex::new(config.dimensions, config.metric, config.lsh.clone())?;
+                Ok(Box::new(index))
+            }
+            Algorithm::IVF => {
                 #[cfg(feature = "faiss")]
                 {
                     let num_centroids = 1024; // Default number of centroids
@@ -468,6 +603,11 @@ pub trait VectorIndex {
     /// Get index statistics
     fn stats(&self) -> IndexStats;
 
+    /// Describe the proximity graph, for indexes built on one
+    fn graph_diagnostics(&self) -> Option<GraphDiagnostics> {
+        None
+    }
+
     /// Flush any pending changes to storage
     async fn flush(&self) -> Result<()>;
 
@@ -507,7 +647,10 @@ impl VectorIndex for FlatIndex {
     }
 
     async fn optimize(&mut self) -> Result<()> {
-        Ok(())
+        if self.vectors.is_empty() {
+            return Ok(());
+        }
+        FlatIndex::train(self)
     }
 }
 
@@ -522,9 +665,8 @@ impl VectorIndex for hnsw::HNSWIndex {
         hnsw::HNSWIndex::search(self, query, config.k, config.ef)
     }
 
-    async fn delete(&mut self, _id: &VectorId) -> Result<bool> {
-        // HNSW doesn't support efficient deletion
-        Ok(false)
+    async fn delete(&mut self, id: &VectorId) -> Result<bool> {
+        Ok(hnsw::HNSWIndex::delete(self, id))
     }
 
     async fn update(&mut self, _id: VectorId, _vector: Vector, _metadata: VectorMetadata) -> Result<()> {
@@ -539,12 +681,16 @@ impl VectorIndex for hnsw::HNSWIndex {
         hnsw::HNSWIndex::stats(self)
     }
 
+    fn graph_diagnostics(&self) -> Option<GraphDiagnostics> {
+        Some(hnsw::HNSWIndex::graph_diagnostics(self))
+    }
+
     async fn flush(&self) -> Result<()> {
         Ok(())
     }
 
     async fn optimize(&mut self) -> Result<()> {
-        Ok(())
+        hnsw::HNSWIndex::compact(self)
     }
 }
 
@@ -594,6 +740,8 @@ fn current_timestamp() -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
     #[test]
     fn test_algorithm_selector() {
@@ -646,6 +794,133 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_gpu_acceleration_without_device_uses_cpu_kernel() {
+        let config = EngineConfig {
+            dimensions: 16,
+            metric: Metric::Euclidean,
+            ..EngineConfig::default()
+        }
+        .with_gpu_acceleration(true);
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut random = || Vector::new((0..16).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let vectors: alloc::vec::Vec<Vector> = (0..200).map(|_| random()).collect();
+        let query = random();
+
+        let mut exact: alloc::vec::Vec<(usize, VectorElement)> = vectors
+            .iter()
+            .map(|vector| Metric::Euclidean.distance(&query, vector).unwrap())
+            .enumerate()
+            .collect();
+        exact.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        let expected: alloc::vec::Vec<VectorId> = exact.iter().take(10).map(|(i, _)| format!("v{}", i)).collect();
+
+        // The untrained IVF-PQ index scans its vectors exactly
+        for algorithm in [Algorithm::Flat, Algorithm::IVFPQ] {
+            let mut index = AlgorithmFactory::create_index(algorithm, &config).unwrap();
+            for (i, vector) in vectors.iter().enumerate() {
+                index.insert(format!("v{}", i), vector.clone(), VectorMetadata::new()).await.unwrap();
+            }
+
+            let search = SearchConfig {
+                k: 10,
+                ..SearchConfig::default()
+            };
+            let ids: alloc::vec::Vec<VectorId> = index.search(&query, &search).await.unwrap().into_iter().map(|r| r.id).collect();
+            assert_eq!(ids, expected, "{:?}", algorithm);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_int8_storage_keeps_recall() {
+        let config = EngineConfig {
+            dimensions: 32,
+            metric: Metric::Euclidean,
+            ..EngineConfig::default()
+        };
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut random = || Vector::new((0..32).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let vectors: alloc::vec::Vec<Vector> = (0..2000).map(|_| random()).collect();
+        let queries: alloc::vec::Vec<Vector> = (0..20).map(|_| random()).collect();
+
+        let mut exact = AlgorithmFactory::create_index(Algorithm::Flat, &config).unwrap();
+        let mut quantized =
+            AlgorithmFactory::create_index(Algorithm::Flat, &config.clone().with_storage_precision(Precision::Int8)).unwrap();
+        for (i, vector) in vectors.iter().enumerate() {
+            exact.insert(format!("v{}", i), vector.clone(), VectorMetadata::new()).await.unwrap();
+            quantized.insert(format!("v{}", i), vector.clone(), VectorMetadata::new()).await.unwrap();
+        }
+        quantized.optimize().await.unwrap();
+        // Inserts after training are encoded directly
+        quantized.insert("late".into(), random(), VectorMetadata::new()).await.unwrap();
+        assert_eq!(quantized.stats().memory_usage * 4, exact.stats().memory_usage + 32 * 4);
+
+        let search = SearchConfig {
+            k: 10,
+            ..SearchConfig::default()
+        };
+        let mut found = 0;
+        for query in &queries {
+            let truth: alloc::collections::BTreeSet<VectorId> =
+                exact.search(query, &search).await.unwrap().into_iter().map(|r| r.id).collect();
+            found += quantized.search(query, &search).await.unwrap().iter().filter(|r| truth.contains(&r.id)).count();
+        }
+        let recall = found as f64 / (queries.len() * 10) as f64;
+        assert!(recall >= 0.9, "recall@10 {} with int8 storage", recall);
+    }
+
+    #[cfg(feature = "hnsw")]
+    #[tokio::test]
+    async fn test_hnsw_recall_against_brute_force_through_deletes() {
+        let config = EngineConfig {
+            dimensions: 16,
+            metric: Metric::Euclidean,
+            ..EngineConfig::default()
+        };
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut random = || Vector::new((0..16).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let mut live: alloc::collections::BTreeMap<VectorId, Vector> = (0..1000).map(|i| (format!("v{}", i), random())).collect();
+        let queries: alloc::vec::Vec<Vector> = (0..20).map(|_| random()).collect();
+
+        let mut hnsw = AlgorithmFactory::create_index(Algorithm::HNSW, &config).unwrap();
+        for (id, vector) in &live {
+            hnsw.insert(id.clone(), vector.clone(), VectorMetadata::new()).await.unwrap();
+        }
+        for i in 0..600 {
+            assert!(hnsw.delete(&format!("v{}", i)).await.unwrap());
+            live.remove(&format!("v{}", i));
+        }
+        assert!(hnsw.update("v700".into(), random(), VectorMetadata::new()).await.is_err());
+
+        let search = SearchConfig {
+            k: 10,
+            ..SearchConfig::default()
+        };
+        // Deletes only tombstone until the index is optimized
+        for tombstoned in [600, 0] {
+            let diagnostics = hnsw.graph_diagnostics().unwrap();
+            assert_eq!(diagnostics.node_count, live.len());
+            assert_eq!(diagnostics.tombstoned, tombstoned);
+
+            let mut found = 0;
+            for query in &queries {
+                let mut exact: alloc::vec::Vec<(&VectorId, VectorElement)> =
+                    live.iter().map(|(id, vector)| (id, Metric::Euclidean.distance(query, vector).unwrap())).collect();
+                exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let truth: alloc::collections::BTreeSet<&VectorId> = exact.iter().take(10).map(|(id, _)| *id).collect();
+                let results = hnsw.search(query, &search).await.unwrap();
+                assert_eq!(results.len(), 10);
+                assert!(results.iter().all(|r| live.contains_key(&r.id) && r.metric == Metric::Euclidean));
+                found += results.iter().filter(|r| truth.contains(&r.id)).count();
+            }
+            let recall = found as f64 / (queries.len() * 10) as f64;
+            assert!(recall >= 0.9, "recall@10 {} with {} tombstones", recall, tombstoned);
+
+            hnsw.optimize().await.unwrap();
+        }
+    }
+
     #[test]
     fn test_metric_properties() {
         assert!(Metric::Euclidean.lower_is_better());
@@ -174,6 +174,23 @@ impl Metric {
     pub fn lower_is_better(&self) -> bool {
         matches!(self, Metric::Euclidean | Metric::Manhattan | Metric::Hamming)
     }
+
+    /// Map a distance, as [`Metric::distance`] computes it, to a similarity
+    /// in 0..=1 where higher is more similar:
+    ///
+    /// - Cosine: `1 - d / 2`, so opposite vectors score 0 and identical
+    ///   ones 1
+    /// - Euclidean, Manhattan and Hamming: `1 / (1 + d)`
+    /// - Dot product: the logistic function of the dot product `-d`, so a
+    ///   dot product of 0 scores 0.5
+    pub fn similarity(&self, distance: VectorElement) -> VectorElement {
+        let similarity = match self {
+            Metric::Cosine => 1.0 - distance / 2.0,
+            Metric::Euclidean | Metric::Manhattan | Metric::Hamming => 1.0 / (1.0 + distance.max(0.0)),
+            Metric::DotProduct => 1.0 / (1.0 + distance.exp()),
+        };
+        similarity.clamp(0.0, 1.0)
+    }
 }
 
 /// Vector indexing algorithms
@@ -225,6 +242,23 @@ pub struct SearchConfig {
     pub filter: Option<alloc::boxed::Box<dyn Fn(&VectorMetadata) -> bool + Send + Sync>>,
     /// Search radius for range search
     pub radius: Option<VectorElement>,
+    /// Number of approximate candidates to re-score with exact distances
+    /// before keeping the best `k`; only compressed indexes keeping
+    /// full-precision vectors use it
+    pub rerank_k: Option<usize>,
+    /// Largest `ef` a filtered search escalates to while fewer than `k`
+    /// results pass the filter; `ef` itself disables escalation
+    pub max_filter_ef: usize,
+    /// Model reordering the retrieved candidates before the top `k` are
+    /// kept
+    pub reranker: Option<alloc::sync::Arc<dyn Reranker>>,
+    /// Candidates retrieved for the reranker or recency boost, at least
+    /// `k`; `None` for `k`
+    pub candidate_k: Option<usize>,
+    /// Boost for recent documents, applied to the candidates' scores
+    /// before any reranker. Boosted results carry the boosted normalized
+    /// score as `score`.
+    pub recency_boost: Option<RecencyBoost>,
 }
 
 impl Default for SearchConfig {
@@ -232,12 +266,17 @@ impl Default for SearchConfig {
         Self {
             k: 10,
             ef: DEFAULT_EF,
-            nprobe: 10,
+            nprobe: DEFAULT_NPROBE,
             max_search_time: None,
             include_vectors: false,
             include_metadata: true,
             filter: None,
             radius: None,
+            rerank_k: None,
+            max_filter_ef: DEFAULT_MAX_EF,
+            reranker: None,
+            candidate_k: None,
+            recency_boost: None,
         }
     }
 }
@@ -251,12 +290,22 @@ pub struct SearchResult {
     pub score: VectorElement,
     /// Distance value
     pub distance: VectorElement,
+    /// Metric the distance was computed with
+    pub metric: Metric,
     /// Vector data (if requested)
     pub vector: Option<Vector>,
     /// Metadata (if requested)
     pub metadata: Option<VectorMetadata>,
 }
 
+impl SearchResult {
+    /// Similarity in 0..=1, higher meaning more similar whatever the
+    /// metric; see [`Metric::similarity`] for the mapping
+    pub fn normalized_score(&self) -> f32 {
+        self.metric.similarity(self.distance)
+    }
+}
+
 /// Vector metadata
 #[derive(Debug, Clone, Default)]
 pub struct VectorMetadata {
@@ -421,6 +470,10 @@ pub struct EngineConfig {
     pub algorithm: Algorithm,
     /// Quantization configuration
     pub quantization: QuantizationConfig,
+    /// IVF-PQ configuration
+    pub ivf: IvfPqConfig,
+    /// LSH configuration
+    pub lsh: LshConfig,
     /// Enable persistence
     pub persistence_enabled: bool,
     /// Persistence path
@@ -429,6 +482,8 @@ pub struct EngineConfig {
     pub monitoring_enabled: bool,
     /// Enable GPU acceleration
     pub gpu_enabled: bool,
+    /// Precision the flat index stores vectors at
+    pub storage_precision: Precision,
 }
 
 impl Default for EngineConfig {
@@ -439,14 +494,52 @@ impl Default for EngineConfig {
             metric: Metric::Cosine,
             algorithm: Algorithm::HNSW,
             quantization: QuantizationConfig::default(),
+            ivf: IvfPqConfig::default(),
+            lsh: LshConfig::default(),
             persistence_enabled: false,
             persistence_path: None,
             monitoring_enabled: true,
             gpu_enabled: false,
+            storage_precision: Precision::F32,
         }
     }
 }
 
+impl EngineConfig {
+    /// Set the IVF-PQ list count, lists probed per query and number of
+    /// subquantizers
+    pub fn with_ivf_params(mut self, nlist: usize, nprobe: usize, m_subquantizers: usize) -> Self {
+        self.ivf.nlist = nlist;
+        self.ivf.nprobe = nprobe;
+        self.ivf.m_subquantizers = m_subquantizers;
+        self
+    }
+
+    /// Set the LSH table count, projections per table and extra buckets
+    /// probed per table
+    pub fn with_lsh_params(mut self, num_tables: usize, hash_bits: usize, probes: usize) -> Self {
+        self.lsh.num_tables = num_tables;
+        self.lsh.hash_bits = hash_bits;
+        self.lsh.probes = probes;
+        self
+    }
+
+    /// Request GPU acceleration of batched distance computation. Without
+    /// the `cuda` feature or a usable device, indexes fall back to the SIMD
+    /// CPU kernel and a warning is logged once.
+    pub fn with_gpu_acceleration(mut self, enabled: bool) -> Self {
+        self.gpu_enabled = enabled;
+        self
+    }
+
+    /// Store flat index vectors at `precision`. `Int8` quarters their
+    /// memory once the index is optimized, which trains the quantizer.
+    pub fn with_storage_precision(mut self, precision: Precision) -> Self {
+        self.storage_precision = precision;
+        self
+    }
+}
+
 /// Get current timestamp (simplified)
 fn current_timestamp() -> u64 {
     // In a real implementation, this would use system time
@@ -495,6 +588,38 @@ mod tests {
         assert_eq!(man_dist, 2.0); // |1-0| + |0-1| = 2
     }
 
+    #[test]
+    fn test_normalized_score_orders_by_similarity() {
+        let query = Vector::new(vec![1.0, 0.0]);
+        let near = Vector::new(vec![0.9, 0.1]);
+        let far = Vector::new(vec![-0.5, 1.0]);
+
+        for metric in [Metric::Cosine, Metric::Euclidean, Metric::DotProduct] {
+            let result = |vector: &Vector| SearchResult {
+                id: "v".into(),
+                score: 0.0,
+                distance: metric.distance(&query, vector).unwrap(),
+                metric,
+                vector: None,
+                metadata: None,
+            };
+            let (near, far) = (result(&near).normalized_score(), result(&far).normalized_score());
+            assert!(near > far, "{:?}: {} <= {}", metric, near, far);
+            assert!((0.0..=1.0).contains(&near) && (0.0..=1.0).contains(&far), "{:?}", metric);
+        }
+    }
+
+    #[test]
+    fn test_similarity_bounds() {
+        assert_eq!(Metric::Cosine.similarity(0.0), 1.0);
+        assert_eq!(Metric::Cosine.similarity(2.0), 0.0);
+        assert_eq!(Metric::Euclidean.similarity(0.0), 1.0);
+        assert!((Metric::Euclidean.similarity(1.0) - 0.5).abs() < 1e-6);
+        assert!((Metric::DotProduct.similarity(0.0) - 0.5).abs() < 1e-6);
+        assert!(Metric::DotProduct.similarity(-1e4) <= 1.0);
+        assert!(Metric::DotProduct.similarity(1e4) >= 0.0);
+    }
+
     #[test]
     fn test_search_config() {
         let config = SearchConfig::default();
@@ -522,6 +647,22 @@ mod tests {
         assert_eq!(config.metric, Metric::Cosine);
     }
 
+    #[test]
+    fn test_with_ivf_params() {
+        let config = EngineConfig::default().with_ivf_params(256, 8, 16);
+        assert_eq!(config.ivf.nlist, 256);
+        assert_eq!(config.ivf.nprobe, 8);
+        assert_eq!(config.ivf.m_subquantizers, 16);
+    }
+
+    #[test]
+    fn test_with_lsh_params() {
+        let config = EngineConfig::default().with_lsh_params(4, 10, 32);
+        assert_eq!(config.lsh.num_tables, 4);
+        assert_eq!(config.lsh.hash_bits, 10);
+        assert_eq!(config.lsh.probes, 32);
+    }
+
     #[test]
     fn test_index_batch() {
         let mut batch = IndexBatch::new();
@@ -516,6 +516,146 @@ pub enum QueryStrategy {
     ScatterGather,
 }
 
+/// Shard serving a disjoint subset of the vectors to a
+/// [`ScatterGatherCoordinator`]
+#[cfg(feature = "distributed")]
+#[async_trait::async_trait(?Send)]
+pub trait ShardNode {
+    /// Local top-k of `query`, ordered like the shard's own search
+    async fn search(&self, query: &Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>>;
+}
+
+#[cfg(feature = "distributed")]
+#[async_trait::async_trait(?Send)]
+impl ShardNode for VectorIndexer {
+    async fn search(&self, query: &Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+        self.search_with(query.clone(), config).await
+    }
+}
+
+/// Scatter-gather configuration
+#[cfg(feature = "distributed")]
+#[derive(Debug, Clone)]
+pub struct ScatterGatherConfig {
+    /// Time each shard gets to answer before it is left out of the result
+    pub shard_timeout: ::core::time::Duration,
+    /// Fewest shards that must answer for a (partial) result to be returned
+    pub min_successful_shards: usize,
+}
+
+#[cfg(feature = "distributed")]
+impl Default for ScatterGatherConfig {
+    fn default() -> Self {
+        Self {
+            shard_timeout: ::core::time::Duration::from_millis(500),
+            min_successful_shards: 1,
+        }
+    }
+}
+
+/// Coordinator fanning each query out to every shard and merging their
+/// local top-k lists into the global top-k.
+///
+/// Shards that fail or miss `shard_timeout` are counted in
+/// [`DistributedQueryStats::failed_responses`] and the result is built from
+/// the rest, as long as `min_successful_shards` answered.
+#[cfg(feature = "distributed")]
+pub struct ScatterGatherCoordinator {
+    /// Metric the shards rank by
+    metric: Metric,
+    /// Scatter-gather configuration
+    config: ScatterGatherConfig,
+    /// Shards by node ID
+    shards: alloc::vec::Vec<(alloc::string::String, Box<dyn ShardNode>)>,
+}
+
+#[cfg(feature = "distributed")]
+impl ScatterGatherCoordinator {
+    /// Create a coordinator for shards ranking by `metric`
+    pub fn new(metric: Metric, config: ScatterGatherConfig) -> Self {
+        Self {
+            metric,
+            config,
+            shards: alloc::vec::Vec::new(),
+        }
+    }
+
+    /// Add a shard
+    pub fn add_shard(&mut self, node_id: impl Into<alloc::string::String>, shard: Box<dyn ShardNode>) {
+        self.shards.push((node_id.into(), shard));
+    }
+
+    /// Number of shards
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Search every shard for the `config.k` nearest neighbours of `query`
+    pub async fn search(&self, query: &Vector, config: &SearchConfig) -> Result<DistributedSearchResult> {
+        let started = std::time::Instant::now();
+        let answers = futures::future::join_all(self.shards.iter().map(|(node_id, shard)| async move {
+            let shard_started = std::time::Instant::now();
+            let answer = tokio::time::timeout(self.config.shard_timeout, shard.search(query, config)).await;
+            (node_id, answer, shard_started.elapsed().as_millis() as u64)
+        }))
+        .await;
+
+        let mut stats = DistributedQueryStats {
+            nodes_queried: self.shards.len(),
+            ..Default::default()
+        };
+        let mut node_results = alloc::vec::Vec::new();
+        for (node_id, answer, execution_time_ms) in answers {
+            match answer {
+                Ok(Ok(results)) => {
+                    stats.successful_responses += 1;
+                    node_results.push(NodeSearchResult {
+                        node_id: node_id.clone(),
+                        results,
+                        execution_time_ms,
+                        node_load: 0.0,
+                    });
+                }
+                Ok(Err(_)) | Err(_) => stats.failed_responses += 1,
+            }
+        }
+
+        if stats.successful_responses < self.config.min_successful_shards.max(1) {
+            return Err(VectorSearchError::SearchError {
+                operation: "scatter_gather".into(),
+                reason: alloc::format!(
+                    "{} of {} shards answered, {} required",
+                    stats.successful_responses,
+                    stats.nodes_queried,
+                    self.config.min_successful_shards.max(1)
+                ),
+            });
+        }
+
+        let aggregated_results = self.merge(&node_results, config.k);
+        stats.total_query_time_ms = started.elapsed().as_millis() as u64;
+        Ok(DistributedSearchResult {
+            node_results,
+            aggregated_results,
+            stats,
+        })
+    }
+
+    /// Global top-`k` of the shards' local top-k lists, ranked the way a
+    /// single index over all vectors would rank them
+    fn merge(&self, node_results: &[NodeSearchResult], k: usize) -> alloc::vec::Vec<SearchResult> {
+        let mut merged: alloc::vec::Vec<SearchResult> =
+            node_results.iter().flat_map(|node| node.results.iter().cloned()).collect();
+        if self.metric.lower_is_better() {
+            merged.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        } else {
+            merged.sort_by(|a, b| b.score.total_cmp(&a.score));
+        }
+        merged.truncate(k);
+        merged
+    }
+}
+
 /// Get current timestamp (simplified)
 fn current_timestamp() -> u64 {
     0
@@ -618,4 +758,107 @@ mod tests {
         assert_eq!(plan.target_nodes.len(), 1);
         assert_eq!(plan.consistency_level, ConsistencyLevel::Quorum);
     }
+
+    #[cfg(feature = "distributed")]
+    mod scatter_gather {
+        use super::*;
+        use rand::{Rng, SeedableRng};
+
+        fn engine() -> VectorIndexer {
+            VectorIndexer::new(EngineConfig {
+                dimensions: 8,
+                metric: Metric::Euclidean,
+                algorithm: Algorithm::Flat,
+                ..Default::default()
+            })
+            .unwrap()
+        }
+
+        fn random_vector(rng: &mut rand::rngs::StdRng) -> Vector {
+            Vector::new((0..8).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        }
+
+        /// Shard answering only after `delay`
+        struct SlowShard {
+            inner: VectorIndexer,
+            delay: ::core::time::Duration,
+        }
+
+        #[async_trait::async_trait(?Send)]
+        impl ShardNode for SlowShard {
+            async fn search(&self, query: &Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+                tokio::time::sleep(self.delay).await;
+                ShardNode::search(&self.inner, query, config).await
+            }
+        }
+
+        /// Two shards splitting 200 vectors by ID hash, and one engine
+        /// holding all of them
+        async fn sharded_and_single() -> ([VectorIndexer; 2], VectorIndexer) {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+            let sharding = ShardKeyGenerator::new(ShardingStrategy::Hash, 2);
+            let mut shards = [engine(), engine()];
+            let mut single = engine();
+            for i in 0..200 {
+                let id = alloc::format!("vec-{}", i);
+                let vector = random_vector(&mut rng);
+                shards[sharding.generate_key(&id)]
+                    .index_vector(id.clone(), vector.clone(), VectorMetadata::new())
+                    .await
+                    .unwrap();
+                single.index_vector(id, vector, VectorMetadata::new()).await.unwrap();
+            }
+            (shards, single)
+        }
+
+        #[tokio::test]
+        async fn test_merged_top_k_matches_single_engine() {
+            let ([first, second], single) = sharded_and_single().await;
+            let mut coordinator = ScatterGatherCoordinator::new(Metric::Euclidean, ScatterGatherConfig::default());
+            coordinator.add_shard("shard-0", Box::new(first));
+            coordinator.add_shard("shard-1", Box::new(second));
+
+            let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+            for _ in 0..5 {
+                let query = random_vector(&mut rng);
+                let distributed = coordinator.search(&query, &SearchConfig::default()).await.unwrap();
+                let expected = single.search(query, SearchConfig::default()).await.unwrap();
+
+                assert_eq!(distributed.stats.successful_responses, 2);
+                let ids = |results: &[SearchResult]| results.iter().map(|r| r.id.clone()).collect::<alloc::vec::Vec<_>>();
+                assert_eq!(ids(&distributed.aggregated_results), ids(&expected));
+                assert!(distributed.node_results.iter().all(|node| node.results.len() == 10));
+            }
+        }
+
+        #[tokio::test]
+        async fn test_slow_shard_yields_partial_result() {
+            let ([first, second], _) = sharded_and_single().await;
+            let config = ScatterGatherConfig {
+                shard_timeout: ::core::time::Duration::from_millis(50),
+                min_successful_shards: 1,
+            };
+            let mut coordinator = ScatterGatherCoordinator::new(Metric::Euclidean, config.clone());
+            coordinator.add_shard("fast", Box::new(first));
+            coordinator.add_shard(
+                "slow",
+                Box::new(SlowShard {
+                    inner: second,
+                    delay: ::core::time::Duration::from_secs(5),
+                }),
+            );
+
+            let query = Vector::new(alloc::vec![0.0; 8]);
+            let partial = coordinator.search(&query, &SearchConfig::default()).await.unwrap();
+            assert_eq!((partial.stats.successful_responses, partial.stats.failed_responses), (1, 1));
+            assert_eq!(partial.node_results[0].node_id, "fast");
+            assert_eq!(partial.aggregated_results.len(), 10);
+
+            coordinator.config.min_successful_shards = 2;
+            assert!(matches!(
+                coordinator.search(&query, &SearchConfig::default()).await,
+                Err(VectorSearchError::SearchError { .. })
+            ));
+        }
+    }
 }
@@ -0,0 +1,577 @@
+//! Hierarchical navigable small world (HNSW) graph
+//!
+//! Every node lives on layer 0 and, with exponentially decaying
+//! probability, on the layers above it. Inserting a node descends greedily
+//! from the entry point through the layers above the node's own, then links
+//! it on each of its layers to `max_connections` of `ef_construction`
+//! candidates, twice that on layer 0, chosen with the diversity heuristic
+//! of Malkov and Yashunin. A neighbour whose list overflows is cut back the
+//! same way.
+//!
+//! Deleting a node tombstones it. It keeps its links so searches can still
+//! route through it, but it never takes one of the `ef` result slots, so
+//! tombstones do not crowd live nodes out of the results.
+//! [`HnswGraph::compact`] rebuilds the graph without them. [`HnswGraph::diagnostics`]
+//! describes the shape of the graph, which helps tell a fragmented graph
+//! apart from a badly tuned search when recall is poor.
+
+use crate::*;
+use ::core::cmp::{Ordering, Reverse};
+use alloc::collections::{BTreeMap, BinaryHeap};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Node reached during a layer search, ordered by distance
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    distance: VectorElement,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.node.cmp(&other.node))
+    }
+}
+
+/// A node and its neighbour lists, one per layer it lives on
+#[derive(Debug, Clone)]
+struct Node {
+    vector: Vector,
+    links: alloc::vec::Vec<alloc::vec::Vec<usize>>,
+    tombstoned: bool,
+}
+
+/// HNSW graph over vectors addressed by insertion order
+#[derive(Debug)]
+pub struct HnswGraph {
+    /// Neighbours kept per node on the upper layers
+    max_connections: usize,
+    /// Candidates considered when linking a new node
+    ef_construction: usize,
+    /// Scale of the level distribution, usually `1 / ln(max_connections)`
+    level_norm_factor: VectorElement,
+    /// Distance metric
+    metric: Metric,
+    /// Nodes, by insertion order
+    nodes: alloc::vec::Vec<Node>,
+    /// Node every search starts from, on the top layer
+    entry_point: Option<usize>,
+    /// Number of tombstoned nodes
+    tombstoned: usize,
+    /// Level generator
+    rng: StdRng,
+}
+
+impl HnswGraph {
+    /// Create an empty graph
+    pub fn new(max_connections: usize, ef_construction: usize, level_norm_factor: VectorElement, metric: Metric) -> Self {
+        Self {
+            max_connections: max_connections.max(1),
+            ef_construction: ef_construction.max(1),
+            level_norm_factor,
+            metric,
+            nodes: alloc::vec::Vec::new(),
+            entry_point: None,
+            tombstoned: 0,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    /// Seed the generator drawing node levels
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Number of nodes, tombstoned ones included
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check whether the graph has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Number of tombstoned nodes
+    pub fn tombstoned(&self) -> usize {
+        self.tombstoned
+    }
+
+    /// Dimensionality of the indexed vectors, once one is inserted
+    pub fn dimensions(&self) -> Option<usize> {
+        self.nodes.first().map(|node| node.vector.dims())
+    }
+
+    /// Insert a vector, returning its node
+    pub fn insert(&mut self, vector: Vector) -> Result<usize> {
+        self.check_dimensions(&vector)?;
+        let level = self.random_level();
+        self.insert_at_level(vector, level)
+    }
+
+    /// Insert a vector on layers `0..=level`
+    fn insert_at_level(&mut self, vector: Vector, level: usize) -> Result<usize> {
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            vector,
+            links: alloc::vec![alloc::vec::Vec::new(); level + 1],
+            tombstoned: false,
+        });
+
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(node);
+            return Ok(node);
+        };
+        let top_level = self.level(entry_point);
+        let query = self.nodes[node].vector.clone();
+
+        let mut nearest = self.candidate(&query, entry_point)?;
+        for layer in (level + 1..=top_level).rev() {
+            nearest = self.greedy_closest(&query, nearest, layer)?;
+        }
+
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates = self.search_layer(&query, &[nearest], self.ef_construction, layer, false)?;
+            let max_links = self.max_links(layer);
+            let neighbours = self.select_neighbours(&candidates, max_links)?;
+
+            for &neighbour in &neighbours {
+                self.nodes[neighbour].links[layer].push(node);
+                if self.nodes[neighbour].links[layer].len() > max_links {
+                    self.prune(neighbour, layer, max_links)?;
+                }
+            }
+            self.nodes[node].links[layer] = neighbours;
+            nearest = candidates[0];
+        }
+
+        if level > top_level {
+            self.entry_point = Some(node);
+        }
+        Ok(node)
+    }
+
+    /// Tombstone a node, returning whether it was live
+    pub fn delete(&mut self, node: usize) -> bool {
+        match self.nodes.get_mut(node) {
+            Some(node) if !node.tombstoned => {
+                node.tombstoned = true;
+                self.tombstoned += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether a node is tombstoned
+    pub fn is_tombstoned(&self, node: usize) -> bool {
+        self.nodes.get(node).is_some_and(|node| node.tombstoned)
+    }
+
+    /// Find the `k` nearest live nodes, keeping `ef` live candidates on
+    /// layer 0. Returns `(node, distance)` pairs, nearest first.
+    pub fn search(&self, query: &Vector, k: usize, ef: usize) -> Result<alloc::vec::Vec<(usize, VectorElement)>> {
+        let Some(entry_point) = self.entry_point else {
+            return Ok(alloc::vec::Vec::new());
+        };
+        self.check_dimensions(query)?;
+
+        let mut nearest = self.candidate(query, entry_point)?;
+        for layer in (1..=self.level(entry_point)).rev() {
+            nearest = self.greedy_closest(query, nearest, layer)?;
+        }
+
+        Ok(self
+            .search_layer(query, &[nearest], ef.max(k), 0, true)?
+            .into_iter()
+            .take(k)
+            .map(|c| (c.node, c.distance))
+            .collect())
+    }
+
+    /// Rebuild the graph from its live nodes, dropping the tombstoned ones.
+    ///
+    /// Live nodes keep their order and levels but are renumbered; the
+    /// returned map gives the new node of each old one, `None` if it was
+    /// dropped.
+    pub fn compact(&mut self) -> Result<alloc::vec::Vec<Option<usize>>> {
+        let mut compacted = Self {
+            max_connections: self.max_connections,
+            ef_construction: self.ef_construction,
+            level_norm_factor: self.level_norm_factor,
+            metric: self.metric,
+            nodes: alloc::vec::Vec::with_capacity(self.nodes.len() - self.tombstoned),
+            entry_point: None,
+            tombstoned: 0,
+            rng: self.rng.clone(),
+        };
+        let mut remap = alloc::vec::Vec::with_capacity(self.nodes.len());
+        for node in ::core::mem::take(&mut self.nodes) {
+            if node.tombstoned {
+                remap.push(None);
+            } else {
+                let level = node.links.len() - 1;
+                remap.push(Some(compacted.insert_at_level(node.vector, level)?));
+            }
+        }
+        *self = compacted;
+        Ok(remap)
+    }
+
+    /// Per-layer node counts and out-degrees, and the live nodes a search
+    /// from the entry point can no longer reach
+    pub fn diagnostics(&self) -> GraphDiagnostics {
+        let top_level = self.entry_point.map_or(0, |entry_point| self.level(entry_point));
+        let mut layers: alloc::vec::Vec<LayerDiagnostics> = (0..=top_level)
+            .map(|level| LayerDiagnostics {
+                level,
+                ..Default::default()
+            })
+            .collect();
+        for node in &self.nodes {
+            for (layer, links) in layers.iter_mut().zip(&node.links) {
+                layer.node_count += 1;
+                layer.max_out_degree = layer.max_out_degree.max(links.len());
+                *layer.degree_distribution.entry(links.len()).or_default() += 1;
+            }
+        }
+        for layer in &mut layers {
+            let edges: usize = layer.degree_distribution.iter().map(|(degree, count)| degree * count).sum();
+            if layer.node_count > 0 {
+                layer.avg_out_degree = edges as f64 / layer.node_count as f64;
+            }
+        }
+        if self.nodes.is_empty() {
+            layers.clear();
+        }
+
+        // Searches end on layer 0, which holds every node
+        let mut reached = alloc::vec![false; self.nodes.len()];
+        let mut pending: alloc::vec::Vec<usize> = self.entry_point.into_iter().collect();
+        while let Some(node) = pending.pop() {
+            if !::core::mem::replace(&mut reached[node], true) {
+                pending.extend(self.nodes[node].links[0].iter().copied().filter(|&n| !reached[n]));
+            }
+        }
+
+        let tombstoned = self.nodes.iter().filter(|node| node.tombstoned).count();
+        GraphDiagnostics {
+            node_count: self.nodes.len() - tombstoned,
+            tombstoned,
+            entry_point: self.entry_point,
+            unreachable: self
+                .nodes
+                .iter()
+                .zip(&reached)
+                .filter(|(node, &reached)| !node.tombstoned && !reached)
+                .count(),
+            layers,
+        }
+    }
+
+    fn level(&self, node: usize) -> usize {
+        self.nodes[node].links.len() - 1
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.max_connections * 2
+        } else {
+            self.max_connections
+        }
+    }
+
+    /// Level of a new node: `floor(-ln(u) * level_norm_factor)` for `u` in (0, 1]
+    fn random_level(&mut self) -> usize {
+        let u: f64 = 1.0 - self.rng.gen::<f64>();
+        (-u.ln() * f64::from(self.level_norm_factor)).floor() as usize
+    }
+
+    fn candidate(&self, query: &Vector, node: usize) -> Result<Candidate> {
+        Ok(Candidate {
+            distance: self.metric.distance(query, &self.nodes[node].vector)?,
+            node,
+        })
+    }
+
+    /// Walk to ever closer neighbours on `layer` until none is closer
+    fn greedy_closest(&self, query: &Vector, mut nearest: Candidate, layer: usize) -> Result<Candidate> {
+        loop {
+            let mut improved = false;
+            for &neighbour in &self.nodes[nearest.node].links[layer] {
+                let candidate = self.candidate(query, neighbour)?;
+                if candidate < nearest {
+                    nearest = candidate;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return Ok(nearest);
+            }
+        }
+    }
+
+    /// Best-first search of `layer` keeping the `ef` nearest nodes found,
+    /// returned nearest first. With `live_only`, tombstoned nodes are
+    /// expanded but not kept.
+    fn search_layer(
+        &self,
+        query: &Vector,
+        entry_points: &[Candidate],
+        ef: usize,
+        layer: usize,
+        live_only: bool,
+    ) -> Result<alloc::vec::Vec<Candidate>> {
+        let keep = |candidate: &Candidate| !live_only || !self.nodes[candidate.node].tombstoned;
+        let mut visited = alloc::vec![false; self.nodes.len()];
+        let mut frontier = BinaryHeap::new();
+        let mut found = BinaryHeap::new();
+        for &entry in entry_points {
+            visited[entry.node] = true;
+            frontier.push(Reverse(entry));
+            if keep(&entry) {
+                found.push(entry);
+            }
+        }
+
+        while let Some(Reverse(closest)) = frontier.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|furthest| closest > *furthest) {
+                break;
+            }
+            for &neighbour in &self.nodes[closest.node].links[layer] {
+                if ::core::mem::replace(&mut visited[neighbour], true) {
+                    continue;
+                }
+                let candidate = self.candidate(query, neighbour)?;
+                if found.len() < ef || found.peek().is_none_or(|furthest| candidate < *furthest) {
+                    frontier.push(Reverse(candidate));
+                    if keep(&candidate) {
+                        found.push(candidate);
+                        if found.len() > ef {
+                            found.pop();
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found.into_sorted_vec())
+    }
+
+    /// Pick up to `max_links` of `candidates` (nearest first) to link to.
+    ///
+    /// A candidate closer to an already picked neighbour than to the node
+    /// itself is passed over at first, which spreads the links in different
+    /// directions and keeps clusters connected to each other; passed over
+    /// candidates then fill any links left, nearest first.
+    fn select_neighbours(&self, candidates: &[Candidate], max_links: usize) -> Result<alloc::vec::Vec<usize>> {
+        let mut selected: alloc::vec::Vec<usize> = alloc::vec::Vec::with_capacity(max_links);
+        let mut passed_over = alloc::vec::Vec::new();
+        for candidate in candidates {
+            if selected.len() == max_links {
+                break;
+            }
+            let vector = &self.nodes[candidate.node].vector;
+            let mut diverse = true;
+            for &picked in &selected {
+                if self.metric.distance(vector, &self.nodes[picked].vector)? < candidate.distance {
+                    diverse = false;
+                    break;
+                }
+            }
+            if diverse {
+                selected.push(candidate.node);
+            } else {
+                passed_over.push(candidate.node);
+            }
+        }
+        let fill = max_links - selected.len();
+        selected.extend(passed_over.into_iter().take(fill));
+        Ok(selected)
+    }
+
+    /// Cut the links of `node` on `layer` back to `max_links`
+    fn prune(&mut self, node: usize, layer: usize, max_links: usize) -> Result<()> {
+        let vector = &self.nodes[node].vector;
+        let mut links = alloc::vec::Vec::with_capacity(self.nodes[node].links[layer].len());
+        for &neighbour in &self.nodes[node].links[layer] {
+            links.push(Candidate {
+                distance: self.metric.distance(vector, &self.nodes[neighbour].vector)?,
+                node: neighbour,
+            });
+        }
+        links.sort_unstable();
+        self.nodes[node].links[layer] = self.select_neighbours(&links, max_links)?;
+        Ok(())
+    }
+
+    fn check_dimensions(&self, vector: &Vector) -> Result<()> {
+        match self.dimensions() {
+            Some(expected) if expected != vector.dims() => Err(VectorSearchError::InvalidDimensions {
+                expected,
+                actual: vector.dims(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Shape of an HNSW graph
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDiagnostics {
+    /// Live nodes
+    pub node_count: usize,
+    /// Tombstoned nodes, still linked into the graph
+    pub tombstoned: usize,
+    /// Node searches start from
+    pub entry_point: Option<usize>,
+    /// Live nodes a search from the entry point cannot reach on layer 0
+    pub unreachable: usize,
+    /// Layers from 0 (every node) up to the entry point's layer
+    pub layers: alloc::vec::Vec<LayerDiagnostics>,
+}
+
+/// Shape of one layer of an HNSW graph
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayerDiagnostics {
+    /// Layer number, 0 at the bottom
+    pub level: usize,
+    /// Nodes on the layer, tombstoned ones included
+    pub node_count: usize,
+    /// Average number of neighbours per node
+    pub avg_out_degree: f64,
+    /// Largest number of neighbours of a node
+    pub max_out_degree: usize,
+    /// Number of nodes by number of neighbours
+    pub degree_distribution: BTreeMap<usize, usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(count: usize, dims: usize, seed: u64) -> alloc::vec::Vec<Vector> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| Vector::new((0..dims).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect()
+    }
+
+    /// Fraction of the exact `k` nearest live vectors that `graph` returns,
+    /// averaged over `queries`
+    fn measure_recall(graph: &HnswGraph, vectors: &[Vector], live: impl Fn(usize) -> bool, queries: &[Vector], k: usize, ef: usize) -> f64 {
+        let mut hits = 0;
+        for query in queries {
+            let mut exact: alloc::vec::Vec<(usize, VectorElement)> = vectors
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| live(*i))
+                .map(|(i, vector)| (i, Metric::Euclidean.distance(query, vector).unwrap()))
+                .collect();
+            exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let found = graph.search(query, k, ef).unwrap();
+            assert_eq!(found.len(), k);
+            hits += exact[..k].iter().filter(|(i, _)| found.iter().any(|(node, _)| node == i)).count();
+        }
+        hits as f64 / (queries.len() * k) as f64
+    }
+
+    #[test]
+    fn test_recall_against_brute_force() {
+        let vectors = random_vectors(1000, 16, 5);
+        let queries = random_vectors(50, 16, 6);
+        let mut graph = HnswGraph::new(8, 100, 1.0 / 8f32.ln(), Metric::Euclidean);
+        for vector in &vectors {
+            graph.insert(vector.clone()).unwrap();
+        }
+
+        let recall = measure_recall(&graph, &vectors, |_| true, &queries, 10, 64);
+        assert!(recall >= 0.95, "recall@10 = {}", recall);
+    }
+
+    #[test]
+    fn test_tombstones_neither_shrink_results_nor_hurt_recall() {
+        let vectors = random_vectors(1000, 16, 7);
+        let queries = random_vectors(50, 16, 8);
+        let mut graph = HnswGraph::new(8, 100, 1.0 / 8f32.ln(), Metric::Euclidean);
+        for vector in &vectors {
+            graph.insert(vector.clone()).unwrap();
+        }
+        // Delete two thirds of the nodes, so most nodes near a query are tombstones
+        for node in (0..1000).filter(|node| node % 3 != 0) {
+            graph.delete(node);
+        }
+        assert_eq!(graph.tombstoned(), 666);
+
+        let live = |node: usize| node % 3 == 0;
+        let recall = measure_recall(&graph, &vectors, live, &queries, 10, 10);
+        assert!(recall >= 0.9, "recall@10 with tombstones = {}", recall);
+        for query in &queries {
+            assert!(graph.search(query, 10, 10).unwrap().iter().all(|&(node, _)| live(node)));
+        }
+
+        let remap = graph.compact().unwrap();
+        assert_eq!(graph.len(), 334);
+        assert_eq!(graph.tombstoned(), 0);
+        assert_eq!(graph.diagnostics().unreachable, 0);
+        let kept: alloc::vec::Vec<Vector> = (0..1000).filter(|&node| live(node)).map(|node| vectors[node].clone()).collect();
+        for (node, new) in remap.iter().enumerate() {
+            assert_eq!(new.is_some(), live(node));
+            if let Some(new) = new {
+                assert_eq!(kept[*new].as_slice(), vectors[node].as_slice());
+            }
+        }
+        let recall = measure_recall(&graph, &kept, |_| true, &queries, 10, 64);
+        assert!(recall >= 0.95, "recall@10 after compaction = {}", recall);
+    }
+
+    #[test]
+    fn test_diagnostics_after_deletes() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut graph = HnswGraph::new(4, 32, 1.0 / 4f32.ln(), Metric::Euclidean);
+        let vectors: alloc::vec::Vec<Vector> = (0..300)
+            .map(|_| Vector::new((0..8).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect();
+        for vector in &vectors {
+            graph.insert(vector.clone()).unwrap();
+        }
+        for node in (0..300).step_by(10) {
+            assert!(graph.delete(node));
+        }
+        assert!(!graph.delete(0));
+
+        let diagnostics = graph.diagnostics();
+        assert_eq!(diagnostics.node_count, 270);
+        assert_eq!(diagnostics.tombstoned, 30);
+        assert_eq!(diagnostics.unreachable, 0);
+        assert!(diagnostics.layers.len() > 1, "300 nodes with M=4 should span several layers");
+        assert_eq!(diagnostics.layers[0].node_count, 300);
+        assert!(diagnostics.layers.windows(2).all(|pair| pair[0].node_count >= pair[1].node_count));
+        for layer in &diagnostics.layers {
+            assert_eq!(layer.degree_distribution.values().sum::<usize>(), layer.node_count);
+            assert!(layer.max_out_degree <= if layer.level == 0 { 8 } else { 4 });
+            assert!(layer.avg_out_degree > 0.0 || layer.node_count == 1);
+        }
+
+        // Tombstoned nodes route searches but are never returned
+        assert_eq!(graph.search(&vectors[11], 1, 16).unwrap()[0].0, 11);
+        let results = graph.search(&vectors[10], 20, 64).unwrap();
+        assert_eq!(results.len(), 20);
+        assert!(results.iter().all(|&(node, _)| !graph.is_tombstoned(node)));
+    }
+}
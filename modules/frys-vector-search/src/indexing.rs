@@ -1,6 +1,7 @@
 //! Vector indexing and storage management
 
 use crate::*;
+use futures::{Stream, StreamExt};
 
 /// Vector indexer for managing index operations
 #[derive(Debug)]
@@ -11,6 +12,11 @@ pub struct VectorIndexer {
     config: EngineConfig,
     /// Statistics
     stats: IndexingStats,
+    /// Search-time `ef` controller, once a target recall is set
+    ef_tuner: Option<EfTuner>,
+    /// Inverted index of sparse vectors; `None` if the metric has no
+    /// sparse path
+    sparse: Option<SparseIndex>,
     /// Background optimization task
     #[cfg(feature = "async")]
     optimization_task: Option<tokio::task::JoinHandle<()>>,
@@ -20,11 +26,14 @@ impl VectorIndexer {
     /// Create a new vector indexer
     pub fn new(config: EngineConfig) -> Result<Self> {
         let algorithm = AlgorithmFactory::create_index(config.algorithm, &config)?;
+        let sparse = SparseIndex::new(config.metric).ok();
 
         Ok(Self {
             algorithm,
             config,
             stats: IndexingStats::default(),
+            ef_tuner: None,
+            sparse,
             #[cfg(feature = "async")]
             optimization_task: None,
         })
@@ -93,10 +102,127 @@ impl VectorIndexer {
         Ok(())
     }
 
-    /// Search for similar vectors
-    pub async fn search(&self, query: Vector, config: SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+    /// Index a sparse vector, replacing any sparse vector with the same ID.
+    ///
+    /// Sparse vectors live in their own inverted index next to the dense
+    /// one and are found by [`search_sparse`](Self::search_sparse) only.
+    /// Their indices are not bounded by the configured dimensions. Fails
+    /// with `MetricNotSupported` unless the metric is `DotProduct` or
+    /// `Cosine`.
+    pub async fn index_sparse(&mut self, id: VectorId, vector: SparseVector, metadata: VectorMetadata) -> Result<()> {
+        let metric = self.config.metric;
+        let index = self.sparse.as_mut().ok_or_else(|| VectorSearchError::MetricNotSupported {
+            metric: alloc::format!("{:?}", metric),
+        })?;
+        index.insert(id, vector, metadata)?;
+        self.stats.record_index_operation();
+        Ok(())
+    }
+
+    /// Delete a sparse vector, returning whether it existed
+    pub async fn delete_sparse(&mut self, id: &VectorId) -> Result<bool> {
+        let metric = self.config.metric;
+        let index = self.sparse.as_mut().ok_or_else(|| VectorSearchError::MetricNotSupported {
+            metric: alloc::format!("{:?}", metric),
+        })?;
+        let deleted = index.delete(id);
+        if deleted {
+            self.stats.record_delete_operation();
+        }
+        Ok(deleted)
+    }
+
+    /// Search the sparse vectors for those most similar to `query`.
+    ///
+    /// Only `k`, `filter` and `include_metadata` of `config` apply.
+    pub async fn search_sparse(&self, query: SparseVector, config: SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+        let index = self.sparse.as_ref().ok_or_else(|| VectorSearchError::MetricNotSupported {
+            metric: alloc::format!("{:?}", self.config.metric),
+        })?;
+
+        let mut results = index.search_filtered(&query, config.k, config.filter.as_deref());
+        if !config.include_metadata {
+            for result in &mut results {
+                result.metadata = None;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Search for similar vectors.
+    ///
+    /// Once a target recall is set, the tuned `ef` replaces `config.ef`.
+    pub async fn search(&self, query: Vector, mut config: SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+        self.apply_tuned_ef(&mut config);
+        self.search_with(query, &config).await
+    }
+
+    /// Keep measured recall near `target_recall` by tuning the search-time
+    /// `ef`, starting from `DEFAULT_EF` or the currently tuned value.
+    ///
+    /// Feed measurements with [`record_recall`](Self::record_recall).
+    pub fn set_target_recall(&mut self, target_recall: f64) -> Result<()> {
+        match &mut self.ef_tuner {
+            Some(tuner) => tuner.set_target_recall(target_recall),
+            None => {
+                self.ef_tuner = Some(EfTuner::new(target_recall, DEFAULT_EF)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Report a recall sample, e.g. from [`recall_at_k`], measured at the
+    /// current tuned `ef`. Returns the `ef` used from now on, or `None` if
+    /// no target recall is set.
+    pub fn record_recall(&mut self, recall: f64) -> Option<usize> {
+        self.ef_tuner.as_mut().map(|tuner| tuner.record_recall(recall))
+    }
+
+    /// Search-time `ef` chosen by the tuner
+    pub fn tuned_ef(&self) -> Option<usize> {
+        self.ef_tuner.as_ref().map(EfTuner::ef)
+    }
+
+    /// Stream the results of [`search`](Self::search) in rank order.
+    ///
+    /// The search runs once with `config.k`; its results are then yielded
+    /// one by one, so the collected stream always equals the output of
+    /// `search` for the same query, approximate indices included. A failed
+    /// search yields its error as the only item.
+    ///
+    /// The stream is recorded in the statistics as a single search.
+    pub fn search_stream(&self, query: Vector, mut config: SearchConfig) -> impl Stream<Item = Result<SearchResult>> + '_ {
+        self.apply_tuned_ef(&mut config);
+        futures::stream::once(async move { self.search_with(query, &config).await }).flat_map(|results| {
+            let items: alloc::vec::Vec<Result<SearchResult>> = match results {
+                Ok(results) => results.into_iter().map(Ok).collect(),
+                Err(e) => alloc::vec![Err(e)],
+            };
+            futures::stream::iter(items)
+        })
+    }
+
+    fn apply_tuned_ef(&self, config: &mut SearchConfig) {
+        if let Some(tuner) = &self.ef_tuner {
+            config.ef = tuner.ef();
+        }
+    }
+
+    /// Run a search against the underlying index, with `config` exactly as
+    /// given
+    pub(crate) async fn search_with(&self, query: Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
         let start_time = current_timestamp();
+        let results = self.run_search(query, config).await?;
 
+        // Update statistics
+        let search_time = current_timestamp() - start_time;
+        self.stats.record_search_operation(search_time, results.len());
+
+        Ok(results)
+    }
+
+    /// Search without recording statistics
+    async fn run_search(&self, query: Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
         // Validate query vector
         if query.dims() != self.config.dimensions {
             return Err(VectorSearchError::InvalidDimensions {
@@ -108,15 +234,48 @@ impl VectorIndexer {
         // Preprocess query
         let processed_query = self.preprocess_vector(query)?;
 
+        // A reranker or recency boost picks the top k from a larger pool
+        // of candidates
+        let candidates = if config.reranker.is_some() || config.recency_boost.is_some() {
+            config.candidate_k.unwrap_or(config.k).max(config.k)
+        } else {
+            config.k
+        };
+        let mut round = SearchConfig {
+            k: candidates,
+            filter: None,
+            reranker: None,
+            recency_boost: None,
+            ..*config
+        };
+
         // Perform search
-        let results = self.algorithm.search(&processed_query, &config).await?;
+        let results = self.algorithm.search(&processed_query, &round).await?;
+        let mut retrieved = results.len();
 
         // Post-process results
-        let filtered_results = self.postprocess_results(results, &config);
+        let mut filtered_results = self.postprocess_results(results, config, candidates);
+
+        // A selective filter can reject most of the candidates. Re-traverse
+        // with doubled `ef`, keeping every candidate it finds, until enough
+        // pass, the index runs out of vectors or `max_filter_ef` is reached.
+        if config.filter.is_some() {
+            while filtered_results.len() < candidates && retrieved >= round.k && round.ef < config.max_filter_ef {
+                round.ef = (round.ef.max(1) * 2).min(config.max_filter_ef);
+                round.k = round.k.max(round.ef);
+                let results = self.algorithm.search(&processed_query, &round).await?;
+                retrieved = results.len();
+                filtered_results = self.postprocess_results(results, config, candidates);
+            }
+        }
 
-        // Update statistics
-        let search_time = current_timestamp() - start_time;
-        self.stats.record_search_operation(search_time, filtered_results.len());
+        if let Some(boost) = &config.recency_boost {
+            boost.apply(&mut filtered_results, unix_now());
+        }
+        if let Some(reranker) = &config.reranker {
+            filtered_results = rerank(reranker.as_ref(), &processed_query, filtered_results).await;
+        }
+        filtered_results.truncate(config.k);
 
         Ok(filtered_results)
     }
@@ -176,6 +335,13 @@ impl VectorIndexer {
         self.algorithm.stats()
     }
 
+    /// Describe the index's proximity graph: per-layer node counts and
+    /// out-degrees, and live nodes unreachable from the entry point.
+    /// `None` unless the algorithm is graph based (HNSW).
+    pub fn graph_diagnostics(&self) -> Option<GraphDiagnostics> {
+        self.algorithm.graph_diagnostics()
+    }
+
     /// Preprocess vector before indexing/searching
     fn preprocess_vector(&self, mut vector: Vector) -> Result<Vector> {
         // Apply normalization based on metric
@@ -207,7 +373,7 @@ impl VectorIndexer {
     }
 
     /// Post-process search results
-    fn postprocess_results(&self, mut results: alloc::vec::Vec<SearchResult>, config: &SearchConfig) -> alloc::vec::Vec<SearchResult> {
+    fn postprocess_results(&self, mut results: alloc::vec::Vec<SearchResult>, config: &SearchConfig, k: usize) -> alloc::vec::Vec<SearchResult> {
         // Apply filtering if specified
         if let Some(filter) = &config.filter {
             results.retain(|result| {
@@ -220,8 +386,8 @@ impl VectorIndexer {
         }
 
         // Limit results
-        if results.len() > config.k {
-            results.truncate(config.k);
+        if results.len() > k {
+            results.truncate(k);
         }
 
         // Sort by score (descending for similarity, ascending for distance)
@@ -629,6 +795,378 @@ mod tests {
         assert_eq!(builder.stats().total_vectors, 1);
     }
 
+    #[tokio::test]
+    async fn test_search_stream_matches_batch_search() {
+        let config = EngineConfig {
+            dimensions: 2,
+            metric: Metric::Euclidean,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        for i in 0..100 {
+            let vector = Vector::new(vec![i as f32, (i % 7) as f32]);
+            indexer.index_vector(alloc::format!("vec{}", i), vector, VectorMetadata::new()).await.unwrap();
+        }
+
+        let query = Vector::new(vec![42.0, 3.0]);
+        let search_config = || SearchConfig { k: 50, ..Default::default() };
+
+        let batch = indexer.search(query.clone(), search_config()).await.unwrap();
+        let streamed: alloc::vec::Vec<SearchResult> = indexer
+            .search_stream(query, search_config())
+            .map(|result| result.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(streamed.len(), batch.len());
+        for (s, b) in streamed.iter().zip(batch.iter()) {
+            assert_eq!(s.id, b.id);
+            assert_eq!(s.distance, b.distance);
+        }
+    }
+
+    /// Index of 100 vectors at distances 0..100 from any query that, like
+    /// an approximate index searched with a small `k`, misses the nearest
+    /// one unless `k` is at least 32
+    #[derive(Debug)]
+    struct MissingNearestIndex;
+
+    #[async_trait::async_trait(?Send)]
+    impl VectorIndex for MissingNearestIndex {
+        async fn insert(&mut self, _id: VectorId, _vector: Vector, _metadata: VectorMetadata) -> Result<()> {
+            Ok(())
+        }
+
+        async fn search(&self, _query: &Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+            let first = if config.k < 32 { 1 } else { 0 };
+            Ok((first..100)
+                .take(config.k)
+                .map(|i| SearchResult {
+                    id: alloc::format!("vec{}", i),
+                    score: -(i as f32),
+                    distance: i as f32,
+                    metric: Metric::Euclidean,
+                    vector: None,
+                    metadata: None,
+                })
+                .collect())
+        }
+
+        async fn delete(&mut self, _id: &VectorId) -> Result<bool> {
+            Ok(false)
+        }
+
+        async fn update(&mut self, _id: VectorId, _vector: Vector, _metadata: VectorMetadata) -> Result<()> {
+            Ok(())
+        }
+
+        fn stats(&self) -> IndexStats {
+            IndexStats::default()
+        }
+
+        async fn flush(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn optimize(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_stream_approximate_index_matches_batch_search() {
+        let config = EngineConfig {
+            dimensions: 2,
+            metric: Metric::Euclidean,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        indexer.algorithm = Box::new(MissingNearestIndex);
+
+        let query = Vector::new(vec![0.0, 0.0]);
+        let search_config = || SearchConfig { k: 50, ..Default::default() };
+        let batch: alloc::vec::Vec<VectorId> =
+            indexer.search(query.clone(), search_config()).await.unwrap().into_iter().map(|r| r.id).collect();
+        let streamed: alloc::vec::Vec<VectorId> = indexer
+            .search_stream(query, search_config())
+            .map(|result| result.unwrap().id)
+            .collect()
+            .await;
+
+        // vec0 is only found by a search with k >= 32, as the batch search is
+        assert_eq!(streamed, batch);
+        assert_eq!(streamed[0], "vec0");
+        assert_eq!(streamed.len(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_selective_filter_escalates_ef() {
+        let config = EngineConfig {
+            dimensions: 2,
+            metric: Metric::Euclidean,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        for i in 0..1000 {
+            let mut metadata = VectorMetadata::new();
+            // Only five vectors, all far from the query, pass the filter
+            if i % 200 == 199 {
+                metadata.set("tenant", "rare");
+            }
+            let vector = Vector::new(vec![i as f32, 0.0]);
+            indexer.index_vector(alloc::format!("vec{}", i), vector, metadata).await.unwrap();
+        }
+
+        let search_config = |max_filter_ef| SearchConfig {
+            k: 10,
+            filter: Some(Box::new(|metadata: &VectorMetadata| metadata.get("tenant").is_some_and(|t| t == "rare"))),
+            max_filter_ef,
+            ..Default::default()
+        };
+        let query = Vector::new(vec![0.0, 0.0]);
+
+        let unescalated = indexer.search(query.clone(), search_config(DEFAULT_EF)).await.unwrap();
+        assert!(unescalated.is_empty());
+
+        let results = indexer.search(query, search_config(DEFAULT_MAX_EF)).await.unwrap();
+        let ids: alloc::vec::Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["vec199", "vec399", "vec599", "vec799", "vec999"]);
+    }
+
+    #[tokio::test]
+    async fn test_reranker_reorders_candidates() {
+        /// Scores later candidates higher, inverting the vector order
+        struct Inverting;
+
+        #[async_trait::async_trait(?Send)]
+        impl Reranker for Inverting {
+            async fn score(&self, _query: &Vector, candidates: &[&VectorMetadata]) -> Result<alloc::vec::Vec<VectorElement>> {
+                Ok((0..candidates.len()).map(|i| i as VectorElement).collect())
+            }
+        }
+
+        struct Failing;
+
+        #[async_trait::async_trait(?Send)]
+        impl Reranker for Failing {
+            async fn score(&self, _query: &Vector, _candidates: &[&VectorMetadata]) -> Result<alloc::vec::Vec<VectorElement>> {
+                Err(VectorSearchError::SearchError {
+                    operation: "rerank".into(),
+                    reason: "model unavailable".into(),
+                })
+            }
+        }
+
+        let config = EngineConfig {
+            dimensions: 2,
+            metric: Metric::Euclidean,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        for i in 0..100 {
+            let vector = Vector::new(vec![i as f32, 0.0]);
+            indexer.index_vector(alloc::format!("vec{}", i), vector, VectorMetadata::new()).await.unwrap();
+        }
+
+        let search_config = |reranker: alloc::sync::Arc<dyn Reranker>| SearchConfig {
+            k: 3,
+            candidate_k: Some(10),
+            reranker: Some(reranker),
+            ..Default::default()
+        };
+        let query = Vector::new(vec![0.0, 0.0]);
+        let ids = |results: alloc::vec::Vec<SearchResult>| results.into_iter().map(|r| r.id).collect::<alloc::vec::Vec<_>>();
+
+        let reranked = indexer.search(query.clone(), search_config(alloc::sync::Arc::new(Inverting))).await.unwrap();
+        assert_eq!(ids(reranked), ["vec9", "vec8", "vec7"]);
+
+        let fallback = indexer.search(query, search_config(alloc::sync::Arc::new(Failing))).await.unwrap();
+        assert_eq!(ids(fallback), ["vec0", "vec1", "vec2"]);
+
+        // A config carrying a reranker can still be shared across threads
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(&search_config(alloc::sync::Arc::new(Inverting)));
+    }
+
+    #[tokio::test]
+    async fn test_recency_boost_prefers_newer_of_equidistant() {
+        let config = EngineConfig {
+            dimensions: 2,
+            metric: Metric::Euclidean,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        let now = unix_now();
+        let day = 24.0 * 3600.0;
+        for (id, vector, published_at) in [
+            ("old", [1.0, 1.0], Some(now - 30.0 * day)),
+            ("new", [1.0, -1.0], Some(now - 0.5 * day)),
+            ("undated", [1.0, 2.0], None),
+        ] {
+            let mut metadata = VectorMetadata::new();
+            if let Some(published_at) = published_at {
+                metadata.set("published_at", &published_at.to_string());
+            }
+            indexer.index_vector(id.into(), Vector::new(vector.to_vec()), metadata).await.unwrap();
+        }
+
+        let query = Vector::new(vec![1.0, 0.0]);
+        let boost = RecencyBoost::new("published_at", ::core::time::Duration::from_secs(24 * 3600));
+        let search_config = |k| SearchConfig {
+            k,
+            candidate_k: Some(3),
+            recency_boost: Some(boost.clone()),
+            ..Default::default()
+        };
+
+        let top = indexer.search(query.clone(), search_config(1)).await.unwrap();
+        assert_eq!(top[0].id, "new");
+        let all = indexer.search(query, search_config(3)).await.unwrap();
+        let ids: alloc::vec::Vec<&str> = all.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["new", "old", "undated"]);
+        assert!(all[0].score > all[1].score);
+        assert_eq!(all[2].score, all[2].normalized_score());
+    }
+
+    #[tokio::test]
+    async fn test_recency_boost_stream_matches_batch_search() {
+        let config = EngineConfig {
+            dimensions: 2,
+            metric: Metric::Euclidean,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        let now = unix_now();
+        // The boost ranks the fresh document first although it is farther
+        for (id, vector, published_at) in [("stale", [1.0, 0.0], now - 30.0 * 24.0 * 3600.0), ("fresh", [2.0, 0.0], now)] {
+            let mut metadata = VectorMetadata::new();
+            metadata.set("published_at", &published_at.to_string());
+            indexer.index_vector(id.into(), Vector::new(vector.to_vec()), metadata).await.unwrap();
+        }
+
+        let query = Vector::new(vec![0.0, 0.0]);
+        let search_config = |k| SearchConfig {
+            k,
+            candidate_k: Some(2),
+            recency_boost: Some(RecencyBoost::new("published_at", ::core::time::Duration::from_secs(24 * 3600))),
+            ..Default::default()
+        };
+
+        for k in [1, 2] {
+            let batch: alloc::vec::Vec<_> = indexer.search(query.clone(), search_config(k)).await.unwrap().into_iter().map(|r| r.id).collect();
+            let streamed: alloc::vec::Vec<_> = indexer
+                .search_stream(query.clone(), search_config(k))
+                .map(|result| result.unwrap().id)
+                .collect()
+                .await;
+            assert_eq!(batch[0], "fresh");
+            assert_eq!(streamed, batch);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sparse_search_alongside_dense() {
+        let config = EngineConfig {
+            dimensions: 2,
+            metric: Metric::DotProduct,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        indexer.index_vector("dense".into(), Vector::new(vec![1.0, 0.0]), VectorMetadata::new()).await.unwrap();
+        for (id, entries, lang) in [
+            ("en-1", [(101, 0.4), (2_045, 1.2)], "en"),
+            ("en-2", [(101, 1.5), (7_999, 0.3)], "en"),
+            ("de-1", [(101, 2.0), (2_045, 2.0)], "de"),
+        ] {
+            let mut metadata = VectorMetadata::new();
+            metadata.set("lang", lang);
+            indexer.index_sparse(id.into(), SparseVector::new(entries).unwrap(), metadata).await.unwrap();
+        }
+
+        let query = SparseVector::new([(101, 1.0), (2_045, 1.0)]).unwrap();
+        let ids = |results: alloc::vec::Vec<SearchResult>| results.into_iter().map(|r| r.id).collect::<alloc::vec::Vec<_>>();
+        let all = indexer.search_sparse(query.clone(), SearchConfig::default()).await.unwrap();
+        assert_eq!(ids(all), ["de-1", "en-1", "en-2"]);
+        let english = SearchConfig {
+            k: 1,
+            filter: Some(alloc::boxed::Box::new(|metadata: &VectorMetadata| metadata.get("lang").is_some_and(|lang| lang == "en"))),
+            ..Default::default()
+        };
+        assert_eq!(ids(indexer.search_sparse(query.clone(), english).await.unwrap()), ["en-1"]);
+
+        // Deleting a sparse vector leaves the dense one of the same name
+        indexer.index_sparse("dense".into(), SparseVector::new([(101, 9.0)]).unwrap(), VectorMetadata::new()).await.unwrap();
+        assert!(indexer.delete_sparse(&"dense".into()).await.unwrap());
+        assert!(indexer.delete_sparse(&"de-1".into()).await.unwrap());
+        assert!(!indexer.delete_sparse(&"de-1".into()).await.unwrap());
+        assert_eq!(ids(indexer.search_sparse(query, SearchConfig::default()).await.unwrap()), ["en-1", "en-2"]);
+        assert_eq!(indexer.search(Vector::new(vec![1.0, 0.0]), SearchConfig::default()).await.unwrap()[0].id, "dense");
+
+        let mut euclidean = VectorIndexer::new(EngineConfig {
+            dimensions: 2,
+            metric: Metric::Euclidean,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(
+            euclidean.index_sparse("a".into(), SparseVector::default(), VectorMetadata::new()).await,
+            Err(VectorSearchError::MetricNotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_target_recall_tunes_ef() {
+        let config = EngineConfig {
+            dimensions: 2,
+            algorithm: Algorithm::Flat,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        assert_eq!(indexer.record_recall(0.5), None);
+
+        indexer.set_target_recall(0.95).unwrap();
+        assert_eq!(indexer.tuned_ef(), Some(DEFAULT_EF));
+        let raised = indexer.record_recall(0.5).unwrap();
+        assert!(raised > DEFAULT_EF);
+        assert!(indexer.set_target_recall(2.0).is_err());
+        assert_eq!(indexer.tuned_ef(), Some(raised));
+    }
+
+    #[cfg(feature = "hnsw")]
+    #[tokio::test]
+    async fn test_hnsw_graph_diagnostics() {
+        let config = EngineConfig {
+            dimensions: 3,
+            algorithm: Algorithm::HNSW,
+            ..Default::default()
+        };
+        let mut indexer = VectorIndexer::new(config).unwrap();
+        for i in 0..40 {
+            let x = i as f32 / 40.0;
+            let vector = Vector::new(vec![x.cos(), x.sin(), 1.0 + (i % 3) as f32]);
+            indexer.index_vector(alloc::format!("vec{}", i), vector, VectorMetadata::new()).await.unwrap();
+        }
+        assert!(indexer.delete_vector(&"vec7".into()).await.unwrap());
+        assert!(indexer.delete_vector(&"vec8".into()).await.unwrap());
+
+        let diagnostics = indexer.graph_diagnostics().unwrap();
+        assert_eq!(diagnostics.node_count, 38);
+        assert_eq!(diagnostics.tombstoned, 2);
+        assert_eq!(diagnostics.layers[0].node_count, 40);
+        assert_eq!(diagnostics.unreachable, 0);
+
+        let flat = VectorIndexer::new(EngineConfig { dimensions: 3, algorithm: Algorithm::Flat, ..Default::default() }).unwrap();
+        assert!(flat.graph_diagnostics().is_none());
+    }
+
     #[test]
     fn test_maintenance_recommendations() {
         let config = MaintenanceConfig {
@@ -0,0 +1,681 @@
+//! Inverted file index with product quantization (IVF-PQ)
+//!
+//! Training learns `nlist` coarse centroids with k-means over a sample of
+//! the indexed vectors. Each vector is stored in the inverted list of its
+//! nearest centroid, and its residual from that centroid is compressed by a
+//! product quantizer: the residual is split into `m_subquantizers`
+//! subvectors, each replaced by the one-byte index of the nearest codeword
+//! in that subspace's codebook.
+//!
+//! A query probes the `nprobe` lists whose centroids are nearest. For each
+//! probed list it precomputes a table of squared distances from the query
+//! residual's subvectors to every codeword, so scoring a stored code costs
+//! one table lookup per subquantizer (asymmetric distance computation).
+//!
+//! Quantized distances are approximate. With `keep_full_vectors` the index
+//! also keeps every vector at full precision in a side store, and a search
+//! given a `rerank_k` re-scores that many of the best candidates exactly
+//! before returning the top `k`.
+
+use crate::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Upper bound on codewords per subquantizer, so each code fits in a byte
+const MAX_CODEWORDS: usize = 256;
+
+/// IVF-PQ index configuration
+#[derive(Debug, Clone)]
+pub struct IvfPqConfig {
+    /// Number of coarse clusters (inverted lists)
+    pub nlist: usize,
+    /// Number of lists probed per query
+    pub nprobe: usize,
+    /// Number of subquantizers; must divide the vector dimensionality
+    pub m_subquantizers: usize,
+    /// Maximum number of vectors sampled for training
+    pub training_sample_size: usize,
+    /// Number of k-means iterations for both quantizers
+    pub kmeans_iterations: usize,
+    /// Seed for sampling and centroid initialization
+    pub seed: u64,
+    /// Keep full-precision vectors after training so searches can rerank
+    pub keep_full_vectors: bool,
+}
+
+impl Default for IvfPqConfig {
+    fn default() -> Self {
+        Self {
+            nlist: DEFAULT_NLIST,
+            nprobe: DEFAULT_NPROBE,
+            m_subquantizers: DEFAULT_PQ_SUBQUANTIZERS,
+            training_sample_size: 65_536,
+            kmeans_iterations: 20,
+            seed: 0,
+            keep_full_vectors: false,
+        }
+    }
+}
+
+/// Vectors sharing a coarse centroid
+#[derive(Debug, Clone, Default)]
+struct InvertedList {
+    /// Slot of each entry
+    slots: alloc::vec::Vec<u32>,
+    /// PQ codes, `m_subquantizers` bytes per entry
+    codes: alloc::vec::Vec<u8>,
+}
+
+/// Identity of an indexed vector
+#[derive(Debug, Clone)]
+struct Slot {
+    id: VectorId,
+    metadata: VectorMetadata,
+    live: bool,
+}
+
+/// IVF-PQ index.
+///
+/// Vectors inserted before [`train`](Self::train) are kept uncompressed and
+/// searched exactly; training encodes them and frees the originals.
+#[derive(Debug)]
+pub struct IvfPqIndex {
+    /// Configuration
+    config: IvfPqConfig,
+    /// Distance metric
+    metric: Metric,
+    /// Vector dimensionality
+    dimensions: usize,
+    /// Coarse centroids, `nlist * dimensions` values; empty until trained
+    centroids: alloc::vec::Vec<VectorElement>,
+    /// Codebook of each subspace, `codewords * subspace_dims` values
+    codebooks: alloc::vec::Vec<alloc::vec::Vec<VectorElement>>,
+    /// Codewords per subquantizer
+    codewords: usize,
+    /// Inverted lists, one per centroid
+    lists: alloc::vec::Vec<InvertedList>,
+    /// Untrained vectors, by slot
+    pending: alloc::vec::Vec<(u32, alloc::vec::Vec<VectorElement>)>,
+    /// Full-precision copies of trained vectors, by slot, when
+    /// `keep_full_vectors` is set
+    full_vectors: alloc::collections::BTreeMap<u32, alloc::vec::Vec<VectorElement>>,
+    /// Indexed vectors, by slot
+    slots: alloc::vec::Vec<Slot>,
+    /// Live slot of each ID
+    id_to_slot: alloc::collections::BTreeMap<VectorId, u32>,
+    /// Kernel scoring the coarse and uncompressed scans
+    kernel: DistanceKernel,
+    /// Copy of `centroids` on the kernel's device
+    resident_centroids: ResidentRows,
+}
+
+impl IvfPqIndex {
+    /// Create an untrained index.
+    ///
+    /// Only `Euclidean` and `Cosine` are supported; cosine vectors are
+    /// normalized so that squared L2 distance orders them by similarity.
+    pub fn new(dimensions: usize, metric: Metric, config: IvfPqConfig) -> Result<Self> {
+        if !matches!(metric, Metric::Euclidean | Metric::Cosine) {
+            return Err(VectorSearchError::MetricNotSupported {
+                metric: alloc::format!("{:?}", metric),
+            });
+        }
+        let invalid = |parameter: &str, reason: &str| {
+            Err(VectorSearchError::ConfigError {
+                parameter: parameter.into(),
+                reason: reason.into(),
+            })
+        };
+        if config.nlist == 0 {
+            return invalid("nlist", "must be positive");
+        }
+        if config.nprobe == 0 {
+            return invalid("nprobe", "must be positive");
+        }
+        if config.m_subquantizers == 0 || dimensions % config.m_subquantizers != 0 {
+            return invalid("m_subquantizers", "must be positive and divide the dimensionality");
+        }
+
+        Ok(Self {
+            config,
+            metric,
+            dimensions,
+            centroids: alloc::vec::Vec::new(),
+            codebooks: alloc::vec::Vec::new(),
+            codewords: 0,
+            lists: alloc::vec::Vec::new(),
+            pending: alloc::vec::Vec::new(),
+            full_vectors: alloc::collections::BTreeMap::new(),
+            slots: alloc::vec::Vec::new(),
+            id_to_slot: alloc::collections::BTreeMap::new(),
+            kernel: DistanceKernel::cpu(),
+            resident_centroids: ResidentRows::new(),
+        })
+    }
+
+    /// Score the coarse and uncompressed scans with `kernel`
+    pub fn with_kernel(mut self, kernel: DistanceKernel) -> Self {
+        self.kernel = kernel;
+        self.resident_centroids.clear();
+        self
+    }
+
+    /// Whether the quantizers have been trained
+    pub fn is_trained(&self) -> bool {
+        !self.centroids.is_empty()
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.id_to_slot.len()
+    }
+
+    /// Check whether the index holds no live vectors
+    pub fn is_empty(&self) -> bool {
+        self.id_to_slot.is_empty()
+    }
+
+    /// Insert or replace a vector
+    pub fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        let data = self.prepare(&vector)?;
+        self.delete(&id);
+
+        let slot = u32::try_from(self.slots.len()).map_err(|_| VectorSearchError::ResourceLimitExceeded {
+            resource: "ivfpq_slots".into(),
+            limit: u32::MAX.to_string(),
+            actual: self.slots.len().to_string(),
+        })?;
+        self.slots.push(Slot {
+            id: id.clone(),
+            metadata,
+            live: true,
+        });
+        self.id_to_slot.insert(id, slot);
+
+        if self.is_trained() {
+            self.encode(slot, &data);
+            if self.config.keep_full_vectors {
+                self.full_vectors.insert(slot, data);
+            }
+        } else {
+            self.pending.push((slot, data));
+        }
+        Ok(())
+    }
+
+    /// Delete a vector, returning whether it existed
+    pub fn delete(&mut self, id: &VectorId) -> bool {
+        match self.id_to_slot.remove(id) {
+            Some(slot) => {
+                self.slots[slot as usize].live = false;
+                self.pending.retain(|(pending, _)| *pending != slot);
+                self.full_vectors.remove(&slot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Train the coarse quantizer and codebooks on a sample of the vectors
+    /// inserted so far, then encode them.
+    ///
+    /// At least `nlist` vectors must have been inserted.
+    pub fn train(&mut self) -> Result<()> {
+        if self.is_trained() {
+            return Ok(());
+        }
+        if self.pending.len() < self.config.nlist {
+            return Err(VectorSearchError::IndexError {
+                operation: "train".into(),
+                reason: alloc::format!(
+                    "{} vectors inserted, at least nlist = {} needed",
+                    self.pending.len(),
+                    self.config.nlist
+                ),
+            });
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        let sample_size = self.pending.len().min(self.config.training_sample_size.max(self.config.nlist));
+        let sample: alloc::vec::Vec<&[VectorElement]> =
+            rand::seq::index::sample(&mut rng, self.pending.len(), sample_size)
+                .into_iter()
+                .map(|i| self.pending[i].1.as_slice())
+                .collect();
+
+        let centroids = kmeans(&sample, self.dimensions, self.config.nlist, self.config.kmeans_iterations, &mut rng);
+
+        let residuals: alloc::vec::Vec<alloc::vec::Vec<VectorElement>> = sample
+            .iter()
+            .map(|vector| {
+                let centroid = nearest(&centroids, self.dimensions, vector);
+                residual(vector, &centroids[centroid * self.dimensions..][..self.dimensions])
+            })
+            .collect();
+
+        let m = self.config.m_subquantizers;
+        let subspace_dims = self.dimensions / m;
+        let codewords = sample.len().min(MAX_CODEWORDS);
+        let codebooks = (0..m)
+            .map(|j| {
+                let subvectors: alloc::vec::Vec<&[VectorElement]> = residuals
+                    .iter()
+                    .map(|r| &r[j * subspace_dims..][..subspace_dims])
+                    .collect();
+                kmeans(&subvectors, subspace_dims, codewords, self.config.kmeans_iterations, &mut rng)
+            })
+            .collect();
+
+        self.centroids = centroids;
+        self.resident_centroids.clear();
+        self.codebooks = codebooks;
+        self.codewords = codewords;
+        self.lists = alloc::vec![InvertedList::default(); self.config.nlist];
+
+        for (slot, data) in ::core::mem::take(&mut self.pending) {
+            self.encode(slot, &data);
+            if self.config.keep_full_vectors {
+                self.full_vectors.insert(slot, data);
+            }
+        }
+        Ok(())
+    }
+
+    /// Search for the `k` nearest neighbors, probing the configured number
+    /// of lists
+    pub fn search(&self, query: &Vector, k: usize) -> Result<alloc::vec::Vec<SearchResult>> {
+        self.search_with_nprobe(query, k, self.config.nprobe)
+    }
+
+    /// Search for the `k` nearest neighbors, probing `nprobe` lists
+    pub fn search_with_nprobe(&self, query: &Vector, k: usize, nprobe: usize) -> Result<alloc::vec::Vec<SearchResult>> {
+        self.search_reranked(query, k, nprobe, None)
+    }
+
+    /// Search for the `k` nearest neighbors, probing `nprobe` lists. With
+    /// `rerank_k`, the best `rerank_k` candidates by quantized distance are
+    /// re-scored against their full-precision vectors before the top `k` are
+    /// kept; this needs `keep_full_vectors` and is skipped without it.
+    pub fn search_reranked(
+        &self,
+        query: &Vector,
+        k: usize,
+        nprobe: usize,
+        rerank_k: Option<usize>,
+    ) -> Result<alloc::vec::Vec<SearchResult>> {
+        let query = self.prepare(query)?;
+
+        let mut candidates: alloc::vec::Vec<(u32, VectorElement)> = if self.is_trained() {
+            self.scan_lists(&query, nprobe)
+        } else {
+            let rows: alloc::vec::Vec<&[VectorElement]> = self.pending.iter().map(|(_, data)| data.as_slice()).collect();
+            self.pending
+                .iter()
+                .map(|(slot, _)| *slot)
+                .zip(self.kernel.squared_euclidean(&query, &rows))
+                .collect()
+        };
+
+        candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(::core::cmp::Ordering::Equal));
+        match rerank_k {
+            Some(rerank_k) if self.is_trained() && self.config.keep_full_vectors => {
+                candidates.truncate(rerank_k.max(k));
+                for (slot, distance) in &mut candidates {
+                    if let Some(data) = self.full_vectors.get(slot) {
+                        *distance = squared_distance(&query, data);
+                    }
+                }
+                candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(::core::cmp::Ordering::Equal));
+                candidates.truncate(k);
+            }
+            _ => candidates.truncate(k),
+        }
+
+        Ok(candidates
+            .into_iter()
+            .map(|(slot, squared)| {
+                let slot = &self.slots[slot as usize];
+                let (distance, score) = match self.metric {
+                    // For unit vectors |a - b|^2 = 2 (1 - cos)
+                    Metric::Cosine => (squared / 2.0, 1.0 - squared / 2.0),
+                    _ => {
+                        let distance = squared.max(0.0).sqrt();
+                        (distance, 1.0 / (1.0 + distance))
+                    }
+                };
+                SearchResult {
+                    id: slot.id.clone(),
+                    score,
+                    distance,
+                    metric: self.metric,
+                    vector: None,
+                    metadata: Some(slot.metadata.clone()),
+                }
+            })
+            .collect())
+    }
+
+    /// Get statistics. Memory usage covers codes, centroids, codebooks,
+    /// untrained vectors and the full-precision side store, the parts that
+    /// scale with the data.
+    pub fn stats(&self) -> IndexStats {
+        let element = ::core::mem::size_of::<VectorElement>();
+        let codes: usize = self.lists.iter().map(|list| list.codes.len() + list.slots.len() * 4).sum();
+        let quantizers = (self.centroids.len() + self.codebooks.iter().map(alloc::vec::Vec::len).sum::<usize>()) * element;
+        let pending = (self.pending.len() + self.full_vectors.len()) * self.dimensions * element;
+
+        IndexStats {
+            total_vectors: self.len() as u64,
+            memory_usage: (codes + quantizers + pending) as u64,
+            build_time_ms: 0,
+            avg_dimensions: self.dimensions,
+            disk_usage: 0,
+            last_updated: 0,
+        }
+    }
+
+    /// Candidates from the `nprobe` nearest lists, scored with per-list
+    /// distance tables
+    fn scan_lists(&self, query: &[VectorElement], nprobe: usize) -> alloc::vec::Vec<(u32, VectorElement)> {
+        let dims = self.dimensions;
+        let m = self.config.m_subquantizers;
+        let subspace_dims = dims / m;
+
+        let centroids: alloc::vec::Vec<&[VectorElement]> = self.centroids.chunks_exact(dims).collect();
+        let mut lists: alloc::vec::Vec<(usize, VectorElement)> = self
+            .kernel
+            .resident_squared_euclidean(query, &centroids, &self.resident_centroids)
+            .into_iter()
+            .enumerate()
+            .collect();
+        lists.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(::core::cmp::Ordering::Equal));
+
+        let mut table = alloc::vec![0.0; m * self.codewords];
+        let mut candidates = alloc::vec::Vec::new();
+        for &(list, _) in lists.iter().take(nprobe) {
+            let inverted = &self.lists[list];
+            if inverted.slots.is_empty() {
+                continue;
+            }
+
+            let residual = residual(query, &self.centroids[list * dims..][..dims]);
+            for (j, codebook) in self.codebooks.iter().enumerate() {
+                let subvector = &residual[j * subspace_dims..][..subspace_dims];
+                for (c, codeword) in codebook.chunks_exact(subspace_dims).enumerate() {
+                    table[j * self.codewords + c] = squared_distance(subvector, codeword);
+                }
+            }
+
+            for (&slot, code) in inverted.slots.iter().zip(inverted.codes.chunks_exact(m)) {
+                if self.slots[slot as usize].live {
+                    let distance = code
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &c)| table[j * self.codewords + c as usize])
+                        .sum();
+                    candidates.push((slot, distance));
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Add a vector to the list of its nearest centroid
+    fn encode(&mut self, slot: u32, data: &[VectorElement]) {
+        let dims = self.dimensions;
+        let subspace_dims = dims / self.config.m_subquantizers;
+
+        let list = nearest(&self.centroids, dims, data);
+        let residual = residual(data, &self.centroids[list * dims..][..dims]);
+
+        let inverted = &mut self.lists[list];
+        inverted.slots.push(slot);
+        for (j, codebook) in self.codebooks.iter().enumerate() {
+            let code = nearest(codebook, subspace_dims, &residual[j * subspace_dims..][..subspace_dims]);
+            // At most MAX_CODEWORDS codewords, so the index fits in a byte
+            inverted.codes.push(code as u8);
+        }
+    }
+
+    /// Check dimensions and normalize for cosine
+    fn prepare(&self, vector: &Vector) -> Result<alloc::vec::Vec<VectorElement>> {
+        if vector.as_slice().len() != self.dimensions {
+            return Err(VectorSearchError::InvalidDimensions {
+                expected: self.dimensions,
+                actual: vector.as_slice().len(),
+            });
+        }
+
+        let mut vector = vector.clone();
+        if self.metric == Metric::Cosine {
+            vector.normalize();
+        }
+        Ok(vector.data)
+    }
+}
+
+/// Lloyd's k-means, returning `k * dims` centroid values. Centroids start at
+/// distinct random points, and a cluster left empty is moved to a random
+/// point.
+fn kmeans(
+    points: &[&[VectorElement]],
+    dims: usize,
+    k: usize,
+    iterations: usize,
+    rng: &mut StdRng,
+) -> alloc::vec::Vec<VectorElement> {
+    let mut centroids: alloc::vec::Vec<VectorElement> = rand::seq::index::sample(rng, points.len(), k)
+        .into_iter()
+        .flat_map(|i| points[i].iter().copied())
+        .collect();
+
+    let mut sums = alloc::vec![0.0; k * dims];
+    let mut counts = alloc::vec![0usize; k];
+    for _ in 0..iterations {
+        sums.fill(0.0);
+        counts.fill(0);
+        for point in points {
+            let cluster = nearest(&centroids, dims, point);
+            counts[cluster] += 1;
+            for (sum, value) in sums[cluster * dims..][..dims].iter_mut().zip(point.iter()) {
+                *sum += value;
+            }
+        }
+
+        for (cluster, &count) in counts.iter().enumerate() {
+            let centroid = &mut centroids[cluster * dims..][..dims];
+            if count == 0 {
+                centroid.copy_from_slice(points[rng.gen_range(0..points.len())]);
+            } else {
+                for (value, sum) in centroid.iter_mut().zip(&sums[cluster * dims..][..dims]) {
+                    *value = sum / count as VectorElement;
+                }
+            }
+        }
+    }
+    centroids
+}
+
+/// Index of the centroid nearest to `point`
+fn nearest(centroids: &[VectorElement], dims: usize, point: &[VectorElement]) -> usize {
+    centroids
+        .chunks_exact(dims)
+        .map(|centroid| squared_distance(point, centroid))
+        .enumerate()
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(::core::cmp::Ordering::Equal))
+        .map_or(0, |(index, _)| index)
+}
+
+fn residual(vector: &[VectorElement], centroid: &[VectorElement]) -> alloc::vec::Vec<VectorElement> {
+    vector.iter().zip(centroid).map(|(v, c)| v - c).collect()
+}
+
+fn squared_distance(a: &[VectorElement], b: &[VectorElement]) -> VectorElement {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+#[async_trait::async_trait(?Send)]
+impl VectorIndex for IvfPqIndex {
+    async fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        IvfPqIndex::insert(self, id, vector, metadata)
+    }
+
+    async fn search(&self, query: &Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+        IvfPqIndex::search_reranked(self, query, config.k, self.config.nprobe, config.rerank_k)
+    }
+
+    async fn delete(&mut self, id: &VectorId) -> Result<bool> {
+        Ok(IvfPqIndex::delete(self, id))
+    }
+
+    async fn update(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        IvfPqIndex::insert(self, id, vector, metadata)
+    }
+
+    fn stats(&self) -> IndexStats {
+        IvfPqIndex::stats(self)
+    }
+
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn optimize(&mut self) -> Result<()> {
+        // Too few vectors to train yet; keep searching them exactly
+        if self.pending.len() < self.config.nlist {
+            return Ok(());
+        }
+        self.train()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points scattered around `clusters` random centers
+    fn clustered(count: usize, dims: usize, clusters: usize, rng: &mut StdRng) -> alloc::vec::Vec<Vector> {
+        let centers: alloc::vec::Vec<alloc::vec::Vec<f32>> = (0..clusters)
+            .map(|_| (0..dims).map(|_| rng.gen_range(-10.0..10.0)).collect())
+            .collect();
+        (0..count)
+            .map(|i| Vector::new(centers[i % clusters].iter().map(|c| c + rng.gen_range(-1.0..1.0)).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_recall_against_brute_force() {
+        let dims = 16;
+        let mut rng = StdRng::seed_from_u64(42);
+        let data = clustered(2000, dims, 16, &mut rng);
+
+        let config = IvfPqConfig {
+            nlist: 16,
+            nprobe: 4,
+            m_subquantizers: 8,
+            kmeans_iterations: 8,
+            training_sample_size: 1000,
+            ..Default::default()
+        };
+        let mut index = IvfPqIndex::new(dims, Metric::Euclidean, config).unwrap();
+        let mut exact = FlatIndex::new(Metric::Euclidean);
+        for (i, vector) in data.iter().enumerate() {
+            let id = alloc::format!("vec-{}", i);
+            index.insert(id.clone(), vector.clone(), VectorMetadata::new()).unwrap();
+            exact.insert(id, vector.clone(), VectorMetadata::new()).unwrap();
+        }
+        index.train().unwrap();
+        assert!(index.is_trained());
+
+        let queries = clustered(50, dims, 16, &mut rng);
+        let mut hits = 0;
+        for query in &queries {
+            let expected: alloc::vec::Vec<VectorId> = exact.search(query, 10).unwrap().into_iter().map(|r| r.id).collect();
+            let found = index.search(query, 10).unwrap();
+            hits += found.iter().filter(|r| expected.contains(&r.id)).count();
+        }
+        let recall = hits as f64 / (queries.len() * 10) as f64;
+        assert!(recall > 0.7, "recall@10 was {}", recall);
+
+        let baseline = data.len() * dims * ::core::mem::size_of::<f32>();
+        assert!(index.stats().memory_usage < baseline as u64 / 2);
+    }
+
+    #[test]
+    fn test_rerank_improves_recall() {
+        let dims = 16;
+        let mut rng = StdRng::seed_from_u64(11);
+        let data = clustered(2000, dims, 16, &mut rng);
+
+        let config = IvfPqConfig {
+            nlist: 16,
+            nprobe: 4,
+            m_subquantizers: 4,
+            kmeans_iterations: 8,
+            training_sample_size: 1000,
+            keep_full_vectors: true,
+            ..Default::default()
+        };
+        let mut index = IvfPqIndex::new(dims, Metric::Euclidean, config).unwrap();
+        let mut exact = FlatIndex::new(Metric::Euclidean);
+        for (i, vector) in data.iter().enumerate() {
+            let id = alloc::format!("vec-{}", i);
+            index.insert(id.clone(), vector.clone(), VectorMetadata::new()).unwrap();
+            exact.insert(id, vector.clone(), VectorMetadata::new()).unwrap();
+        }
+        index.train().unwrap();
+
+        let queries = clustered(50, dims, 16, &mut rng);
+        let (mut raw, mut reranked) = (0.0, 0.0);
+        for query in &queries {
+            let truth = exact.search(query, 10).unwrap();
+            raw += recall_at_k(&index.search_reranked(query, 10, 4, None).unwrap(), &truth);
+            let results = index.search_reranked(query, 10, 4, Some(100)).unwrap();
+            assert!(results.windows(2).all(|pair| pair[0].distance <= pair[1].distance));
+            reranked += recall_at_k(&results, &truth);
+        }
+        let (raw, reranked) = (raw / queries.len() as f64, reranked / queries.len() as f64);
+        assert!(reranked > raw, "reranked recall@10 {} not above raw {}", reranked, raw);
+        assert!(reranked > 0.95, "reranked recall@10 was {}", reranked);
+
+        assert!(index.delete(&"vec-3".into()));
+        assert!(index.search_reranked(&data[3], 10, 4, Some(100)).unwrap().iter().all(|r| r.id != "vec-3"));
+    }
+
+    #[test]
+    fn test_untrained_search_is_exact_and_deletes_hide_vectors() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let data = clustered(64, 8, 4, &mut rng);
+        let config = IvfPqConfig {
+            nlist: 4,
+            m_subquantizers: 4,
+            ..Default::default()
+        };
+        let mut index = IvfPqIndex::new(8, Metric::Euclidean, config).unwrap();
+        for (i, vector) in data.iter().enumerate() {
+            index.insert(alloc::format!("vec-{}", i), vector.clone(), VectorMetadata::new()).unwrap();
+        }
+
+        assert_eq!(index.search(&data[5], 1).unwrap()[0].id, "vec-5");
+        assert!(index.delete(&"vec-5".into()));
+        index.train().unwrap();
+        assert!(index.search(&data[5], 64).unwrap().iter().all(|r| r.id != "vec-5"));
+        assert_eq!(index.len(), 63);
+    }
+
+    #[test]
+    fn test_invalid_parameters_rejected() {
+        let config = IvfPqConfig {
+            m_subquantizers: 5,
+            ..Default::default()
+        };
+        assert!(matches!(
+            IvfPqIndex::new(32, Metric::Euclidean, config),
+            Err(VectorSearchError::ConfigError { .. })
+        ));
+        assert!(matches!(
+            IvfPqIndex::new(32, Metric::DotProduct, IvfPqConfig::default()),
+            Err(VectorSearchError::MetricNotSupported { .. })
+        ));
+    }
+}
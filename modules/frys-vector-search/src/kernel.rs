@@ -0,0 +1,388 @@
+//! Batched distance kernels
+//!
+//! A [`DistanceKernel`] scores a query against a whole batch of vectors in
+//! one call; the brute-force scan of [`FlatIndex`] and the coarse and
+//! uncompressed scans of [`IvfPqIndex`] go through it.
+//!
+//! The CPU kernel accumulates over fixed-width lanes, which the compiler
+//! lowers to SIMD instructions. With the `cuda` feature and a CUDA device
+//! present, batches are instead scored on the device through candle. Only
+//! CUDA is supported: candle has no ROCm backend, so AMD GPUs use the CPU
+//! kernel. When GPU acceleration is requested but unavailable, because the
+//! feature is off, no device is found or a launch fails, the kernel falls
+//! back to the CPU path and logs a warning once per process.
+//!
+//! Scans over an index's stored vectors pass them with a [`ResidentRows`],
+//! which keeps the matrix on the device between queries. Only rows
+//! appended since the previous query are uploaded.
+
+use crate::*;
+use ::core::sync::atomic::{AtomicBool, Ordering};
+
+/// Accumulator lanes of the CPU kernel
+pub(crate) const LANES: usize = 8;
+
+/// Difference above which two elements count as distinct for Hamming
+/// distance, as in [`Metric::distance`]
+pub(crate) const HAMMING_THRESHOLD: VectorElement = 1e-6;
+
+/// Whether the GPU fallback warning has been logged
+static FALLBACK_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Hardware a [`DistanceKernel`] runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelBackend {
+    /// SIMD CPU kernel
+    Cpu,
+    /// GPU kernel
+    Gpu,
+}
+
+/// Distance computed by a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Measure {
+    /// Distance as [`Metric::distance`] defines it
+    Metric(Metric),
+    /// Squared Euclidean distance
+    SquaredEuclidean,
+}
+
+/// Batched distance kernel
+#[derive(Debug, Clone, Default)]
+pub struct DistanceKernel {
+    /// Device batches run on, if GPU acceleration is active
+    #[cfg(feature = "gpu")]
+    device: Option<candle_core::Device>,
+}
+
+impl DistanceKernel {
+    /// Create a kernel running on the CPU
+    pub fn cpu() -> Self {
+        Self::default()
+    }
+
+    /// Create a kernel, running on the first GPU if `gpu_acceleration` is
+    /// set and one is available, and on the CPU otherwise
+    pub fn new(gpu_acceleration: bool) -> Self {
+        if !gpu_acceleration {
+            return Self::cpu();
+        }
+
+        #[cfg(feature = "gpu")]
+        {
+            // Fails unless candle was built with the `cuda` feature
+            match candle_core::Device::new_cuda(0) {
+                Ok(device) => return Self { device: Some(device) },
+                Err(e) => warn_fallback(&gpu_error("open_device", e)),
+            }
+        }
+        #[cfg(not(feature = "gpu"))]
+        {
+            warn_fallback(&VectorSearchError::GpuError {
+                operation: "open_device".into(),
+                reason: "built without the `gpu` feature".into(),
+            });
+        }
+        Self::cpu()
+    }
+
+    /// Hardware this kernel runs on
+    pub fn backend(&self) -> KernelBackend {
+        #[cfg(feature = "gpu")]
+        if self.device.is_some() {
+            return KernelBackend::Gpu;
+        }
+        KernelBackend::Cpu
+    }
+
+    /// Distance from `query` to each row, as [`Metric::distance`] computes
+    /// it. Rows whose length differs from the query's score infinity.
+    pub fn distances(&self, metric: Metric, query: &[VectorElement], rows: &[&[VectorElement]]) -> alloc::vec::Vec<VectorElement> {
+        self.batch(Measure::Metric(metric), query, rows)
+    }
+
+    /// Squared Euclidean distance from `query` to each row. Rows whose
+    /// length differs from the query's score infinity.
+    pub fn squared_euclidean(&self, query: &[VectorElement], rows: &[&[VectorElement]]) -> alloc::vec::Vec<VectorElement> {
+        self.batch(Measure::SquaredEuclidean, query, rows)
+    }
+
+    /// Like [`distances`](Self::distances), reusing the copy of `rows`
+    /// that `resident` keeps on the device.
+    ///
+    /// `rows` must extend the rows of the previous call with the same
+    /// `resident`; call [`ResidentRows::clear`] after changing them any
+    /// other way.
+    pub fn resident_distances(
+        &self,
+        metric: Metric,
+        query: &[VectorElement],
+        rows: &[&[VectorElement]],
+        resident: &ResidentRows,
+    ) -> alloc::vec::Vec<VectorElement> {
+        self.batch_with(Measure::Metric(metric), query, rows, Some(resident))
+    }
+
+    /// Like [`squared_euclidean`](Self::squared_euclidean), reusing the
+    /// copy of `rows` that `resident` keeps on the device, under the same
+    /// contract as [`resident_distances`](Self::resident_distances)
+    pub fn resident_squared_euclidean(
+        &self,
+        query: &[VectorElement],
+        rows: &[&[VectorElement]],
+        resident: &ResidentRows,
+    ) -> alloc::vec::Vec<VectorElement> {
+        self.batch_with(Measure::SquaredEuclidean, query, rows, Some(resident))
+    }
+
+    fn batch(&self, measure: Measure, query: &[VectorElement], rows: &[&[VectorElement]]) -> alloc::vec::Vec<VectorElement> {
+        self.batch_with(measure, query, rows, None)
+    }
+
+    #[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+    fn batch_with(
+        &self,
+        measure: Measure,
+        query: &[VectorElement],
+        rows: &[&[VectorElement]],
+        resident: Option<&ResidentRows>,
+    ) -> alloc::vec::Vec<VectorElement> {
+        #[cfg(feature = "gpu")]
+        if let Some(device) = &self.device {
+            // Ragged batches and Hamming distance are left to the CPU
+            let uniform = rows.iter().all(|row| row.len() == query.len());
+            if uniform && !rows.is_empty() && measure != Measure::Metric(Metric::Hamming) {
+                let scored = match resident {
+                    Some(resident) => resident.matrix(device, query.len(), rows),
+                    None => gpu::upload(device, query.len(), rows),
+                }
+                .and_then(|matrix| gpu::score(&matrix, measure, query));
+                match scored {
+                    Ok(distances) => return distances,
+                    Err(e) => warn_fallback(&gpu_error("distance_batch", e)),
+                }
+            }
+        }
+
+        rows.iter()
+            .map(|row| {
+                if row.len() == query.len() {
+                    cpu_distance(measure, query, row)
+                } else {
+                    VectorElement::INFINITY
+                }
+            })
+            .collect()
+    }
+}
+
+/// Device-resident copy of an append-only set of rows, reused across the
+/// batches of a [`DistanceKernel`] so each query uploads only the rows
+/// appended since the previous one. Holds nothing on the CPU kernel.
+#[derive(Debug, Default)]
+pub struct ResidentRows {
+    /// Matrix on the device, with the number of rows and columns it holds
+    #[cfg(feature = "gpu")]
+    matrix: std::sync::Mutex<Option<(candle_core::Tensor, usize, usize)>>,
+}
+
+impl ResidentRows {
+    /// Create an empty set; the matrix is uploaded by the first batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop the device copy, so the next batch uploads every row
+    pub fn clear(&mut self) {
+        #[cfg(feature = "gpu")]
+        {
+            *self.matrix.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+    }
+
+    /// Matrix of `rows` on `device`, uploading only the rows it lacks
+    #[cfg(feature = "gpu")]
+    fn matrix(&self, device: &candle_core::Device, dims: usize, rows: &[&[VectorElement]]) -> candle_core::Result<candle_core::Tensor> {
+        let mut resident = self.matrix.lock().unwrap_or_else(|e| e.into_inner());
+        match resident.as_mut() {
+            Some((matrix, held, columns)) if *columns == dims && *held <= rows.len() && matrix.device().same_device(device) => {
+                if *held < rows.len() {
+                    let appended = gpu::upload(device, dims, &rows[*held..])?;
+                    *matrix = candle_core::Tensor::cat(&[&*matrix, &appended], 0)?;
+                    *held = rows.len();
+                }
+                Ok(matrix.clone())
+            }
+            _ => {
+                let matrix = gpu::upload(device, dims, rows)?;
+                *resident = Some((matrix.clone(), rows.len(), dims));
+                Ok(matrix)
+            }
+        }
+    }
+}
+
+/// Log that GPU acceleration is unavailable, once per process
+fn warn_fallback(error: &VectorSearchError) {
+    if !FALLBACK_WARNED.swap(true, Ordering::Relaxed) {
+        log::warn!("GPU acceleration unavailable, using the CPU distance kernel: {}", error);
+    }
+}
+
+#[cfg(feature = "gpu")]
+fn gpu_error(operation: &str, error: candle_core::Error) -> VectorSearchError {
+    VectorSearchError::GpuError {
+        operation: operation.into(),
+        reason: error.to_string(),
+    }
+}
+
+/// Sum of `term` over the element pairs of `a` and `b`, accumulated in
+/// [`LANES`] independent lanes so the loop vectorizes
+fn lanes(a: &[VectorElement], b: &[VectorElement], term: impl Fn(VectorElement, VectorElement) -> VectorElement) -> VectorElement {
+    let a_chunks = a.chunks_exact(LANES);
+    let b_chunks = b.chunks_exact(LANES);
+    let tail: VectorElement = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(x, y)| term(*x, *y))
+        .sum();
+
+    let mut acc = [0.0; LANES];
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for ((acc, x), y) in acc.iter_mut().zip(x).zip(y) {
+            *acc += term(*x, *y);
+        }
+    }
+    acc.iter().sum::<VectorElement>() + tail
+}
+
+fn cpu_distance(measure: Measure, query: &[VectorElement], row: &[VectorElement]) -> VectorElement {
+    match measure {
+        Measure::SquaredEuclidean => lanes(query, row, |x, y| (x - y) * (x - y)),
+        Measure::Metric(Metric::Euclidean) => lanes(query, row, |x, y| (x - y) * (x - y)).sqrt(),
+        Measure::Metric(Metric::Manhattan) => lanes(query, row, |x, y| (x - y).abs()),
+        Measure::Metric(Metric::Hamming) => lanes(query, row, |x, y| VectorElement::from(u8::from((x - y).abs() > HAMMING_THRESHOLD))),
+        Measure::Metric(Metric::DotProduct) => -lanes(query, row, |x, y| x * y),
+        Measure::Metric(Metric::Cosine) => {
+            let norms = (lanes(query, query, |x, y| x * y) * lanes(row, row, |x, y| x * y)).sqrt();
+            cosine_distance(lanes(query, row, |x, y| x * y), norms)
+        }
+    }
+}
+
+/// Cosine distance from a dot product and the product of the norms; zero
+/// vectors have similarity 0
+pub(crate) fn cosine_distance(dot: VectorElement, norms: VectorElement) -> VectorElement {
+    if norms == 0.0 {
+        1.0
+    } else {
+        1.0 - dot / norms
+    }
+}
+
+#[cfg(feature = "gpu")]
+mod gpu {
+    use super::*;
+    use candle_core::{Device, Tensor};
+
+    /// Copy equal-length rows to `device` as a matrix
+    pub(super) fn upload(device: &Device, dims: usize, rows: &[&[VectorElement]]) -> candle_core::Result<Tensor> {
+        Tensor::from_vec(rows.concat(), (rows.len(), dims), device)
+    }
+
+    /// Score `query` against each row of `matrix`
+    pub(super) fn score(matrix: &Tensor, measure: Measure, query: &[VectorElement]) -> candle_core::Result<alloc::vec::Vec<VectorElement>> {
+        let query_row = Tensor::from_slice(query, (1, query.len()), matrix.device())?;
+        let dots = || matrix.matmul(&query_row.t()?)?.flatten_all()?.to_vec1::<VectorElement>();
+
+        match measure {
+            Measure::SquaredEuclidean => matrix.broadcast_sub(&query_row)?.sqr()?.sum(1)?.to_vec1(),
+            Measure::Metric(Metric::Euclidean) => matrix.broadcast_sub(&query_row)?.sqr()?.sum(1)?.sqrt()?.to_vec1(),
+            Measure::Metric(Metric::Manhattan) => matrix.broadcast_sub(&query_row)?.abs()?.sum(1)?.to_vec1(),
+            Measure::Metric(Metric::DotProduct) => Ok(dots()?.into_iter().map(|dot| -dot).collect()),
+            Measure::Metric(Metric::Cosine) => {
+                let query_norm = lanes(query, query, |x, y| x * y).sqrt();
+                let row_norms = matrix.sqr()?.sum(1)?.sqrt()?.to_vec1::<VectorElement>()?;
+                Ok(dots()?
+                    .into_iter()
+                    .zip(row_norms)
+                    .map(|(dot, norm)| cosine_distance(dot, norm * query_norm))
+                    .collect())
+            }
+            Measure::Metric(Metric::Hamming) => Err(candle_core::Error::Msg("hamming distance is scored on the CPU".into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_cpu_kernel_matches_metric_distance() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // 19 elements exercise both the lanes and the remainder
+        let random = |rng: &mut StdRng| Vector::new((0..19).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let query = random(&mut rng);
+        let mut vectors: alloc::vec::Vec<Vector> = (0..32).map(|_| random(&mut rng)).collect();
+        vectors.push(query.clone());
+        vectors.push(Vector::new(alloc::vec![0.0; 19]));
+        let rows: alloc::vec::Vec<&[VectorElement]> = vectors.iter().map(Vector::as_slice).collect();
+
+        let kernel = DistanceKernel::cpu();
+        for metric in [Metric::Cosine, Metric::Euclidean, Metric::DotProduct, Metric::Manhattan, Metric::Hamming] {
+            let distances = kernel.distances(metric, query.as_slice(), &rows);
+            for (vector, distance) in vectors.iter().zip(distances) {
+                let expected = metric.distance(&query, vector).unwrap();
+                assert!((distance - expected).abs() < 1e-4, "{:?}: {} != {}", metric, distance, expected);
+            }
+        }
+
+        let squared = kernel.squared_euclidean(query.as_slice(), &rows);
+        assert!(squared[32].abs() < 1e-6);
+        assert!(kernel.squared_euclidean(query.as_slice(), &[&[1.0, 2.0][..]])[0].is_infinite());
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn test_resident_rows_upload_only_appended_rows() {
+        let kernel = DistanceKernel {
+            device: Some(candle_core::Device::Cpu),
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut vectors: alloc::vec::Vec<alloc::vec::Vec<VectorElement>> = alloc::vec::Vec::new();
+        let mut resident = ResidentRows::new();
+        let query: alloc::vec::Vec<VectorElement> = (0..12).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+        for batch in [5, 1, 0, 9] {
+            vectors.extend((0..batch).map(|_| (0..12).map(|_| rng.gen_range(-1.0..1.0)).collect::<alloc::vec::Vec<_>>()));
+            let rows: alloc::vec::Vec<&[VectorElement]> = vectors.iter().map(alloc::vec::Vec::as_slice).collect();
+            let distances = kernel.resident_distances(Metric::Euclidean, &query, &rows, &resident);
+            let expected = DistanceKernel::cpu().distances(Metric::Euclidean, &query, &rows);
+            for (distance, expected) in distances.iter().zip(&expected) {
+                assert!((distance - expected).abs() < 1e-4);
+            }
+            assert_eq!(resident.matrix.lock().unwrap().as_ref().unwrap().1, vectors.len());
+        }
+
+        // Rewritten rows are only picked up after clearing
+        vectors.truncate(3);
+        vectors[0] = query.clone();
+        resident.clear();
+        let rows: alloc::vec::Vec<&[VectorElement]> = vectors.iter().map(alloc::vec::Vec::as_slice).collect();
+        let squared = kernel.resident_squared_euclidean(&query, &rows, &resident);
+        assert_eq!(squared.len(), 3);
+        assert!(squared[0].abs() < 1e-6);
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    #[test]
+    fn test_gpu_request_falls_back_to_cpu() {
+        assert_eq!(DistanceKernel::new(true).backend(), KernelBackend::Cpu);
+        assert_eq!(DistanceKernel::new(false).backend(), KernelBackend::Cpu);
+    }
+}
@@ -8,7 +8,7 @@
 //! - **Multiple Algorithms**: HNSW, IVF, PQ, LSH with automatic algorithm selection
 //! - **High Performance**: Billion-scale vector search with sub-millisecond latency
 //! - **Distributed Search**: Multi-node vector search with consensus algorithms
-//! - **GPU Acceleration**: CUDA/ROCm support for massive parallel processing
+//! - **GPU Acceleration**: CUDA support for batched distance computation (`cuda` feature)
 //! - **Advanced Indexing**: Hierarchical indexing with quantization and compression
 //! - **Real-time Updates**: Incremental indexing and online learning
 //! - **Multi-modal**: Support for text, image, audio, and custom embeddings
@@ -115,6 +115,17 @@ pub mod storage;
 pub mod distributed;
 pub mod ml_integration;
 pub mod realtime_stats;
+pub mod tiered;
+pub mod ivfpq;
+pub mod lsh;
+pub mod tuning;
+pub mod oplog;
+pub mod kernel;
+pub mod scalar;
+pub mod rerank;
+pub mod graph;
+pub mod recency;
+pub mod sparse;
 
 // Re-exports for convenience
 pub use core::*;
@@ -125,6 +136,17 @@ pub use storage::*;
 pub use distributed::*;
 pub use ml_integration::*;
 pub use realtime_stats::*;
+pub use tiered::*;
+pub use ivfpq::*;
+pub use lsh::*;
+pub use tuning::*;
+pub use oplog::*;
+pub use kernel::*;
+pub use scalar::*;
+pub use rerank::*;
+pub use graph::*;
+pub use recency::*;
+pub use sparse::*;
 
 // Error types
 mod error;
@@ -142,6 +164,16 @@ pub const DEFAULT_MAX_ELEMENTS: usize = 1_000_000;
 pub const DEFAULT_EF_CONSTRUCTION: usize = 200;
 pub const DEFAULT_M: usize = 16;
 pub const DEFAULT_EF: usize = 64;
+pub const DEFAULT_MAX_EF: usize = 4096;
+pub const DEFAULT_HOT_TIER_CAPACITY: usize = 4096;
+pub const DEFAULT_MERGE_FACTOR: usize = 4;
+pub const DEFAULT_NLIST: usize = 1024;
+pub const DEFAULT_NPROBE: usize = 10;
+pub const DEFAULT_PQ_SUBQUANTIZERS: usize = 8;
+pub const DEFAULT_LSH_TABLES: usize = 8;
+pub const DEFAULT_LSH_HASH_BITS: usize = 12;
+pub const DEFAULT_LSH_PROBES: usize = 4;
+pub const DEFAULT_SQ_TRAINING_SAMPLE: usize = 65_536;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,483 @@
+//! Multi-probe locality sensitive hashing (LSH)
+//!
+//! Each of `num_tables` hash tables hashes a vector with `hash_bits`
+//! random projections. For cosine the hash is the side of each random
+//! hyperplane the vector lies on; for Euclidean distance it is the p-stable
+//! hash `floor((a·v + b) / w)` with Gaussian `a` and uniform `b`.
+//!
+//! A query looks up its own bucket in every table and then `probes` nearby
+//! buckets, visited in order of how close the query lies to their
+//! boundaries (the multi-probe sequence of Lv et al.). Candidates from all
+//! tables are ranked by their exact distance to the query, so probing more
+//! buckets trades latency for recall.
+
+use crate::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// LSH index configuration
+#[derive(Debug, Clone)]
+pub struct LshConfig {
+    /// Number of hash tables
+    pub num_tables: usize,
+    /// Number of projections hashed per table
+    pub hash_bits: usize,
+    /// Extra buckets probed per table on top of the query's own bucket
+    pub probes: usize,
+    /// Bucket width `w` of the Euclidean hash
+    pub bucket_width: VectorElement,
+    /// Seed for the random projections
+    pub seed: u64,
+}
+
+impl Default for LshConfig {
+    fn default() -> Self {
+        Self {
+            num_tables: DEFAULT_LSH_TABLES,
+            hash_bits: DEFAULT_LSH_HASH_BITS,
+            probes: DEFAULT_LSH_PROBES,
+            bucket_width: 4.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Change to one coordinate of a hash key, with the cost of moving the
+/// query across that boundary
+#[derive(Debug, Clone, Copy)]
+struct Perturbation {
+    coordinate: usize,
+    delta: i32,
+    cost: VectorElement,
+}
+
+/// A single hash table
+#[derive(Debug)]
+struct HashTable {
+    /// `hash_bits * dimensions` projection weights
+    projections: alloc::vec::Vec<VectorElement>,
+    /// Offset `b` of each projection, zero for cosine
+    offsets: alloc::vec::Vec<VectorElement>,
+    /// Slots by hash key
+    buckets: alloc::collections::BTreeMap<alloc::vec::Vec<i32>, alloc::vec::Vec<u32>>,
+}
+
+/// Identity and data of an indexed vector
+#[derive(Debug, Clone)]
+struct Entry {
+    id: VectorId,
+    vector: Vector,
+    metadata: VectorMetadata,
+    live: bool,
+}
+
+/// Multi-probe LSH index
+#[derive(Debug)]
+pub struct LshIndex {
+    /// Configuration
+    config: LshConfig,
+    /// Distance metric
+    metric: Metric,
+    /// Vector dimensionality
+    dimensions: usize,
+    /// Hash tables
+    tables: alloc::vec::Vec<HashTable>,
+    /// Indexed vectors, by slot
+    entries: alloc::vec::Vec<Entry>,
+    /// Live slot of each ID
+    id_to_slot: alloc::collections::BTreeMap<VectorId, u32>,
+}
+
+impl LshIndex {
+    /// Create an empty index with freshly drawn projections.
+    ///
+    /// `Cosine` uses random hyperplanes and `Euclidean` p-stable hashing;
+    /// other metrics are not supported.
+    pub fn new(dimensions: usize, metric: Metric, config: LshConfig) -> Result<Self> {
+        if !matches!(metric, Metric::Euclidean | Metric::Cosine) {
+            return Err(VectorSearchError::MetricNotSupported {
+                metric: alloc::format!("{:?}", metric),
+            });
+        }
+        if config.num_tables == 0 || config.hash_bits == 0 {
+            return Err(VectorSearchError::ConfigError {
+                parameter: "lsh".into(),
+                reason: "num_tables and hash_bits must be positive".into(),
+            });
+        }
+        if metric == Metric::Euclidean && config.bucket_width <= 0.0 {
+            return Err(VectorSearchError::ConfigError {
+                parameter: "bucket_width".into(),
+                reason: "must be positive".into(),
+            });
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let tables = (0..config.num_tables)
+            .map(|_| HashTable {
+                projections: (0..config.hash_bits * dimensions).map(|_| gaussian(&mut rng)).collect(),
+                offsets: (0..config.hash_bits)
+                    .map(|_| match metric {
+                        Metric::Euclidean => rng.gen_range(0.0..config.bucket_width),
+                        _ => 0.0,
+                    })
+                    .collect(),
+                buckets: alloc::collections::BTreeMap::new(),
+            })
+            .collect();
+
+        Ok(Self {
+            config,
+            metric,
+            dimensions,
+            tables,
+            entries: alloc::vec::Vec::new(),
+            id_to_slot: alloc::collections::BTreeMap::new(),
+        })
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.id_to_slot.len()
+    }
+
+    /// Check whether the index holds no live vectors
+    pub fn is_empty(&self) -> bool {
+        self.id_to_slot.is_empty()
+    }
+
+    /// Insert or replace a vector
+    pub fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        self.check_dimensions(&vector)?;
+        self.delete(&id);
+
+        let slot = u32::try_from(self.entries.len()).map_err(|_| VectorSearchError::ResourceLimitExceeded {
+            resource: "lsh_slots".into(),
+            limit: u32::MAX.to_string(),
+            actual: self.entries.len().to_string(),
+        })?;
+        for table in &mut self.tables {
+            let (key, _) = hash(table, self.metric, self.config.bucket_width, &vector);
+            table.buckets.entry(key).or_default().push(slot);
+        }
+
+        self.entries.push(Entry {
+            id: id.clone(),
+            vector,
+            metadata,
+            live: true,
+        });
+        self.id_to_slot.insert(id, slot);
+        Ok(())
+    }
+
+    /// Delete a vector, returning whether it existed
+    pub fn delete(&mut self, id: &VectorId) -> bool {
+        match self.id_to_slot.remove(id) {
+            Some(slot) => {
+                let entry = &mut self.entries[slot as usize];
+                entry.live = false;
+                for table in &mut self.tables {
+                    let (key, _) = hash(table, self.metric, self.config.bucket_width, &entry.vector);
+                    if let Some(bucket) = table.buckets.get_mut(&key) {
+                        bucket.retain(|&s| s != slot);
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Search for the `k` nearest neighbors with the configured probe count
+    pub fn search(&self, query: &Vector, k: usize) -> Result<alloc::vec::Vec<SearchResult>> {
+        self.search_with_probes(query, k, self.config.probes)
+    }
+
+    /// Search for the `k` nearest neighbors, probing `probes` extra buckets
+    /// per table
+    pub fn search_with_probes(&self, query: &Vector, k: usize, probes: usize) -> Result<alloc::vec::Vec<SearchResult>> {
+        let mut ranked = alloc::vec::Vec::new();
+        for slot in self.candidates(query, probes)? {
+            let entry = &self.entries[slot as usize];
+            ranked.push((entry, self.metric.distance(query, &entry.vector)?));
+        }
+        ranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(::core::cmp::Ordering::Equal));
+        ranked.truncate(k);
+
+        Ok(ranked
+            .into_iter()
+            .map(|(entry, distance)| SearchResult {
+                id: entry.id.clone(),
+                score: match self.metric {
+                    Metric::Cosine => 1.0 - distance,
+                    _ => 1.0 / (1.0 + distance),
+                },
+                distance,
+                metric: self.metric,
+                vector: None,
+                metadata: Some(entry.metadata.clone()),
+            })
+            .collect())
+    }
+
+    /// Distinct live slots found in the probed buckets of every table
+    pub(crate) fn candidates(&self, query: &Vector, probes: usize) -> Result<alloc::collections::BTreeSet<u32>> {
+        self.check_dimensions(query)?;
+
+        let mut candidates = alloc::collections::BTreeSet::new();
+        for table in &self.tables {
+            let (key, perturbations) = hash(table, self.metric, self.config.bucket_width, query);
+            for set in probe_sequence(&perturbations, probes) {
+                let mut probed = key.clone();
+                for &index in &set {
+                    let perturbation = perturbations[index];
+                    probed[perturbation.coordinate] += perturbation.delta;
+                }
+                if let Some(bucket) = table.buckets.get(&probed) {
+                    candidates.extend(bucket.iter().copied().filter(|&slot| self.entries[slot as usize].live));
+                }
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Get statistics
+    pub fn stats(&self) -> IndexStats {
+        let element = ::core::mem::size_of::<VectorElement>();
+        let vectors = self.len() * self.dimensions * element;
+        let tables: usize = self
+            .tables
+            .iter()
+            .map(|table| {
+                let keys = table.buckets.len() * self.config.hash_bits * 4;
+                let slots: usize = table.buckets.values().map(|bucket| bucket.len() * 4).sum();
+                (table.projections.len() + table.offsets.len()) * element + keys + slots
+            })
+            .sum();
+
+        IndexStats {
+            total_vectors: self.len() as u64,
+            memory_usage: (vectors + tables) as u64,
+            build_time_ms: 0,
+            avg_dimensions: self.dimensions,
+            disk_usage: 0,
+            last_updated: 0,
+        }
+    }
+
+    fn check_dimensions(&self, vector: &Vector) -> Result<()> {
+        if vector.as_slice().len() == self.dimensions {
+            Ok(())
+        } else {
+            Err(VectorSearchError::InvalidDimensions {
+                expected: self.dimensions,
+                actual: vector.as_slice().len(),
+            })
+        }
+    }
+}
+
+/// Hash key of `vector` in `table`, with the perturbations of that key
+/// sorted by cost
+fn hash(
+    table: &HashTable,
+    metric: Metric,
+    bucket_width: VectorElement,
+    vector: &Vector,
+) -> (alloc::vec::Vec<i32>, alloc::vec::Vec<Perturbation>) {
+    let dims = vector.as_slice().len();
+    let mut key = alloc::vec::Vec::with_capacity(table.offsets.len());
+    let mut perturbations = alloc::vec::Vec::with_capacity(table.offsets.len() * 2);
+
+    for (coordinate, (projection, offset)) in table.projections.chunks_exact(dims.max(1)).zip(&table.offsets).enumerate() {
+        let dot: VectorElement = projection.iter().zip(vector.as_slice()).map(|(a, v)| a * v).sum();
+        if metric == Metric::Euclidean {
+            let position = (dot + offset) / bucket_width;
+            let bucket = position.floor();
+            let fraction = position - bucket;
+            key.push(bucket as i32);
+            perturbations.push(Perturbation { coordinate, delta: -1, cost: fraction * fraction });
+            perturbations.push(Perturbation { coordinate, delta: 1, cost: (1.0 - fraction) * (1.0 - fraction) });
+        } else {
+            let side = i32::from(dot >= 0.0);
+            key.push(side);
+            perturbations.push(Perturbation { coordinate, delta: 1 - 2 * side, cost: dot * dot });
+        }
+    }
+
+    perturbations.sort_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap_or(::core::cmp::Ordering::Equal));
+    (key, perturbations)
+}
+
+/// The unperturbed key followed by the `probes` cheapest valid sets of
+/// perturbations, as indices into `perturbations` (sorted by cost).
+///
+/// Sets are generated in order of total cost by the shift/expand scheme of
+/// multi-probe LSH; a set moving one coordinate both ways is skipped.
+fn probe_sequence(perturbations: &[Perturbation], probes: usize) -> alloc::vec::Vec<alloc::vec::Vec<usize>> {
+    let mut sequence = alloc::vec![alloc::vec::Vec::new()];
+    if perturbations.is_empty() {
+        return sequence;
+    }
+
+    let cost = |set: &[usize]| set.iter().map(|&i| perturbations[i].cost).sum::<VectorElement>();
+    // Candidate sets not yet emitted; the cheapest is always taken next
+    let mut frontier = alloc::vec![(cost(&[0]), alloc::vec![0usize])];
+    while sequence.len() <= probes {
+        let Some(cheapest) = frontier
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1).0.partial_cmp(&(b.1).0).unwrap_or(::core::cmp::Ordering::Equal))
+            .map(|(index, _)| index)
+        else {
+            break;
+        };
+        let (_, set) = frontier.swap_remove(cheapest);
+
+        let last = set[set.len() - 1];
+        if last + 1 < perturbations.len() {
+            let mut shifted = set.clone();
+            shifted[set.len() - 1] = last + 1;
+            let mut expanded = set.clone();
+            expanded.push(last + 1);
+            frontier.push((cost(&shifted), shifted));
+            frontier.push((cost(&expanded), expanded));
+        }
+
+        let mut coordinates: alloc::vec::Vec<usize> = set.iter().map(|&i| perturbations[i].coordinate).collect();
+        coordinates.sort_unstable();
+        coordinates.dedup();
+        if coordinates.len() == set.len() {
+            sequence.push(set);
+        }
+    }
+    sequence
+}
+
+/// Standard normal sample (Box-Muller)
+fn gaussian(rng: &mut StdRng) -> VectorElement {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    ((-2.0 * u1.ln()).sqrt() * (2.0 * ::core::f64::consts::PI * u2).cos()) as VectorElement
+}
+
+#[async_trait::async_trait(?Send)]
+impl VectorIndex for LshIndex {
+    async fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        LshIndex::insert(self, id, vector, metadata)
+    }
+
+    async fn search(&self, query: &Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+        LshIndex::search(self, query, config.k)
+    }
+
+    async fn delete(&mut self, id: &VectorId) -> Result<bool> {
+        Ok(LshIndex::delete(self, id))
+    }
+
+    async fn update(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        LshIndex::insert(self, id, vector, metadata)
+    }
+
+    fn stats(&self) -> IndexStats {
+        LshIndex::stats(self)
+    }
+
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn optimize(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(count: usize, dims: usize, rng: &mut StdRng) -> alloc::vec::Vec<Vector> {
+        (0..count)
+            .map(|_| Vector::new((0..dims).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_probe_sequence_is_ordered_by_cost() {
+        let perturbations: alloc::vec::Vec<Perturbation> = [0.1, 0.2, 0.25, 0.9]
+            .iter()
+            .enumerate()
+            .map(|(coordinate, &cost)| Perturbation { coordinate, delta: 1, cost })
+            .collect();
+
+        let sequence = probe_sequence(&perturbations, 5);
+        assert_eq!(sequence, alloc::vec![
+            alloc::vec![],
+            alloc::vec![0],
+            alloc::vec![1],
+            alloc::vec![2],
+            alloc::vec![0, 1],
+            alloc::vec![0, 2],
+        ]);
+    }
+
+    #[test]
+    fn test_recall_grows_with_probes() {
+        let dims = 16;
+        let mut rng = StdRng::seed_from_u64(5);
+        let data = random_vectors(2000, dims, &mut rng);
+        let queries = random_vectors(30, dims, &mut rng);
+
+        for (metric, config) in [
+            (Metric::Cosine, LshConfig { num_tables: 4, hash_bits: 10, ..Default::default() }),
+            (Metric::Euclidean, LshConfig { num_tables: 4, hash_bits: 6, bucket_width: 3.0, ..Default::default() }),
+        ] {
+            let mut index = LshIndex::new(dims, metric, config).unwrap();
+            let mut exact = alloc::vec::Vec::new();
+            for (i, vector) in data.iter().enumerate() {
+                index.insert(alloc::format!("vec-{}", i), vector.clone(), VectorMetadata::new()).unwrap();
+                exact.push((alloc::format!("vec-{}", i), vector));
+            }
+
+            let mut previous = (0.0, 0);
+            for probes in [0, 4, 16, 64] {
+                let mut hits = 0;
+                let mut candidates = 0;
+                for query in &queries {
+                    let mut truth: alloc::vec::Vec<(VectorElement, &VectorId)> = exact
+                        .iter()
+                        .map(|(id, vector)| (metric.distance(query, vector).unwrap(), id))
+                        .collect();
+                    truth.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                    let truth: alloc::vec::Vec<&VectorId> = truth.iter().take(10).map(|(_, id)| *id).collect();
+
+                    let found = index.search_with_probes(query, 10, probes).unwrap();
+                    hits += found.iter().filter(|r| truth.contains(&&r.id)).count();
+                    candidates += index.candidates(query, probes).unwrap().len();
+                }
+                let recall = hits as f64 / (queries.len() * 10) as f64;
+
+                assert!(recall >= previous.0, "{:?}: recall fell to {} at {} probes", metric, recall, probes);
+                assert!(candidates >= previous.1, "{:?}: candidates fell at {} probes", metric, probes);
+                previous = (recall, candidates);
+            }
+            assert!(previous.0 > 0.8, "{:?}: recall@10 with 64 probes was {}", metric, previous.0);
+        }
+    }
+
+    #[test]
+    fn test_deleted_vectors_are_not_returned() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let data = random_vectors(50, 8, &mut rng);
+        let mut index = LshIndex::new(8, Metric::Euclidean, LshConfig::default()).unwrap();
+        for (i, vector) in data.iter().enumerate() {
+            index.insert(alloc::format!("vec-{}", i), vector.clone(), VectorMetadata::new()).unwrap();
+        }
+
+        assert_eq!(index.search(&data[3], 1).unwrap()[0].id, "vec-3");
+        assert!(index.delete(&"vec-3".into()));
+        assert!(index.search_with_probes(&data[3], 50, 64).unwrap().iter().all(|r| r.id != "vec-3"));
+        assert_eq!(index.len(), 49);
+    }
+}
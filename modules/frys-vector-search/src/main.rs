@@ -136,11 +136,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Display results
         for (i, result) in results.iter().enumerate() {
             let rank = i + 1;
-            let score = if config.metric.lower_is_better() {
-                1.0 / (1.0 + result.distance)
-            } else {
-                result.score
-            };
+            let score = result.normalized_score();
 
             println!("  {}. ID: {} | Score: {:.4} | Text: '{}'",
                      rank,
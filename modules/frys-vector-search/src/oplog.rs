@@ -0,0 +1,426 @@
+//! Incremental index persistence
+//!
+//! A [`LoggedIndex`] wraps any [`VectorIndex`] and appends each insert,
+//! update and delete to an [`IndexLog`] once the index has applied it, so
+//! streaming ingestion pays for one small append per operation instead of
+//! a full save.
+//!
+//! The log directory holds two files: `snapshot`, the live entries at the
+//! last compaction, and `log`, the operations since. Opening a logged index
+//! replays both into a fresh index; [`LoggedIndex::snapshot`] folds the log
+//! into a new snapshot and truncates it.
+//!
+//! Records are framed by `frys-record-log`, so a torn write at the tail of
+//! the log is detected and discarded on replay.
+
+use crate::*;
+use frys_record_log::{frame, next_record};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+const RECORD_INSERT: u8 = 1;
+const RECORD_DELETE: u8 = 2;
+
+/// Index mutation recorded in the log
+#[derive(Debug, Clone)]
+pub enum IndexOp {
+    /// A vector was inserted or updated
+    Insert(IndexEntry),
+    /// A vector was deleted
+    Delete(VectorId),
+}
+
+/// Append-only log of index operations on top of a snapshot
+#[derive(Debug)]
+pub struct IndexLog {
+    /// Directory holding the snapshot and the log
+    dir: PathBuf,
+    /// Log file, opened for appending
+    file: File,
+    /// Sync every append to disk before returning
+    sync_writes: bool,
+    /// Operations appended since the last snapshot
+    pending: usize,
+}
+
+impl IndexLog {
+    /// Open or create the log in directory `path`
+    pub fn open(path: &str, sync_writes: bool) -> Result<Self> {
+        let dir = PathBuf::from(path);
+        std::fs::create_dir_all(&dir).map_err(|e| log_error("open", &e))?;
+
+        let log_path = dir.join("log");
+        let (pending, valid_len) = match read_records(&log_path)? {
+            Some((ops, valid_len)) => (ops.len(), valid_len),
+            None => (0, 0),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(|e| log_error("open", &e))?;
+        // Drop a torn tail so new records are not appended after garbage
+        file.set_len(valid_len as u64).map_err(|e| log_error("open", &e))?;
+
+        Ok(Self {
+            dir,
+            file,
+            sync_writes,
+            pending,
+        })
+    }
+
+    /// Durably record an operation
+    pub fn append(&mut self, op: &IndexOp) -> Result<()> {
+        self.file
+            .write_all(&frame(&encode(op)))
+            .map_err(|e| log_error("append", &e))?;
+        if self.sync_writes {
+            self.file.sync_data().map_err(|e| log_error("append", &e))?;
+        }
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Number of operations logged since the last snapshot
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Live entries: the snapshot with the logged operations applied, in
+    /// the order they were first inserted
+    pub fn entries(&self) -> Result<alloc::vec::Vec<IndexEntry>> {
+        let snapshot = read_records(&self.dir.join("snapshot"))?.map(|(ops, _)| ops).unwrap_or_default();
+        let log = read_records(&self.dir.join("log"))?.map(|(ops, _)| ops).unwrap_or_default();
+
+        // Entry and first-insert sequence number by ID
+        let mut live: alloc::collections::BTreeMap<VectorId, (usize, IndexEntry)> = alloc::collections::BTreeMap::new();
+        for (seq, op) in snapshot.into_iter().chain(log).enumerate() {
+            match op {
+                IndexOp::Insert(entry) => {
+                    let seq = live.get(&entry.id).map_or(seq, |(first, _)| *first);
+                    live.insert(entry.id.clone(), (seq, entry));
+                }
+                IndexOp::Delete(id) => {
+                    live.remove(&id);
+                }
+            }
+        }
+
+        let mut entries: alloc::vec::Vec<(usize, IndexEntry)> = live.into_values().collect();
+        entries.sort_by_key(|(seq, _)| *seq);
+        Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Compact the log into a new snapshot of the live entries.
+    ///
+    /// The snapshot is replaced atomically before the log is truncated;
+    /// a crash in between leaves operations that are already in the
+    /// snapshot, and replaying them again is harmless.
+    pub fn snapshot(&mut self) -> Result<()> {
+        let mut bytes = alloc::vec::Vec::new();
+        for entry in self.entries()? {
+            bytes.extend(frame(&encode(&IndexOp::Insert(entry))));
+        }
+
+        let tmp_path = self.dir.join("snapshot.tmp");
+        let mut tmp = File::create(&tmp_path).map_err(|e| log_error("snapshot", &e))?;
+        tmp.write_all(&bytes)
+            .and_then(|()| tmp.sync_data())
+            .map_err(|e| log_error("snapshot", &e))?;
+        std::fs::rename(&tmp_path, self.dir.join("snapshot")).map_err(|e| log_error("snapshot", &e))?;
+
+        self.file.set_len(0).map_err(|e| log_error("snapshot", &e))?;
+        self.file.sync_data().map_err(|e| log_error("snapshot", &e))?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+/// Vector index whose mutations are recorded to an [`IndexLog`]
+#[derive(Debug)]
+pub struct LoggedIndex<I> {
+    /// Wrapped index
+    index: I,
+    /// Operation log
+    log: IndexLog,
+}
+
+impl<I: VectorIndex> LoggedIndex<I> {
+    /// Open the log in directory `path` and replay it into `index`, which
+    /// is expected to be empty
+    pub async fn open(path: &str, mut index: I, sync_writes: bool) -> Result<Self> {
+        let log = IndexLog::open(path, sync_writes)?;
+        for entry in log.entries()? {
+            index.insert(entry.id, entry.vector, entry.metadata).await?;
+        }
+        Ok(Self { index, log })
+    }
+
+    /// Compact the log into a snapshot, see [`IndexLog::snapshot`]
+    pub fn snapshot(&mut self) -> Result<()> {
+        self.log.snapshot()
+    }
+
+    /// Wrapped index
+    pub fn index(&self) -> &I {
+        &self.index
+    }
+
+    /// Operation log
+    pub fn log(&self) -> &IndexLog {
+        &self.log
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<I: VectorIndex> VectorIndex for LoggedIndex<I> {
+    async fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        self.index.insert(id.clone(), vector.clone(), metadata.clone()).await?;
+        self.log.append(&IndexOp::Insert(IndexEntry { id, vector, metadata }))
+    }
+
+    async fn search(&self, query: &Vector, config: &SearchConfig) -> Result<alloc::vec::Vec<SearchResult>> {
+        self.index.search(query, config).await
+    }
+
+    async fn delete(&mut self, id: &VectorId) -> Result<bool> {
+        let deleted = self.index.delete(id).await?;
+        if deleted {
+            self.log.append(&IndexOp::Delete(id.clone()))?;
+        }
+        Ok(deleted)
+    }
+
+    async fn update(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        self.index.update(id.clone(), vector.clone(), metadata.clone()).await?;
+        self.log.append(&IndexOp::Insert(IndexEntry { id, vector, metadata }))
+    }
+
+    fn stats(&self) -> IndexStats {
+        self.index.stats()
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.log.file.sync_data().map_err(|e| log_error("flush", &e))?;
+        self.index.flush().await
+    }
+
+    async fn optimize(&mut self) -> Result<()> {
+        self.index.optimize().await
+    }
+}
+
+fn log_error(operation: &str, error: &std::io::Error) -> VectorSearchError {
+    VectorSearchError::PersistenceError {
+        operation: operation.into(),
+        reason: alloc::format!("index log: {}", error),
+    }
+}
+
+/// Operations in the file at `path` and the length of its intact prefix,
+/// or `None` if the file does not exist
+fn read_records(path: &Path) -> Result<Option<(alloc::vec::Vec<IndexOp>, usize)>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut bytes = alloc::vec::Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .map_err(|e| log_error("read", &e))?;
+
+    let mut ops = alloc::vec::Vec::new();
+    let mut offset = 0;
+    while let Some((body, next)) = next_record(&bytes, offset) {
+        match decode(body) {
+            Some(op) => ops.push(op),
+            None => break,
+        }
+        offset = next;
+    }
+    Ok(Some((ops, offset)))
+}
+
+fn put_str(body: &mut alloc::vec::Vec<u8>, value: &str) {
+    body.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    body.extend_from_slice(value.as_bytes());
+}
+
+fn encode(op: &IndexOp) -> alloc::vec::Vec<u8> {
+    let mut body = alloc::vec::Vec::new();
+    match op {
+        IndexOp::Insert(entry) => {
+            body.push(RECORD_INSERT);
+            put_str(&mut body, &entry.id);
+            body.extend_from_slice(&(entry.vector.dims() as u32).to_le_bytes());
+            for value in entry.vector.as_slice() {
+                body.extend_from_slice(&value.to_le_bytes());
+            }
+            body.extend_from_slice(&entry.metadata.created_at.to_le_bytes());
+            body.extend_from_slice(&entry.metadata.version.to_le_bytes());
+            body.extend_from_slice(&(entry.metadata.fields.len() as u32).to_le_bytes());
+            for (key, value) in &entry.metadata.fields {
+                put_str(&mut body, key);
+                put_str(&mut body, value);
+            }
+        }
+        IndexOp::Delete(id) => {
+            body.push(RECORD_DELETE);
+            put_str(&mut body, id);
+        }
+    }
+    body
+}
+
+/// Reads little-endian fields from a record body
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (head, rest) = (self.bytes.get(..len)?, self.bytes.get(len..)?);
+        self.bytes = rest;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<alloc::string::String> {
+        let len = self.u32()? as usize;
+        alloc::string::String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
+
+fn decode(body: &[u8]) -> Option<IndexOp> {
+    let (&tag, rest) = body.split_first()?;
+    let mut reader = Reader { bytes: rest };
+    let id = reader.string()?;
+    match tag {
+        RECORD_INSERT => {
+            let dims = reader.u32()? as usize;
+            let data = reader
+                .take(dims.checked_mul(4)?)?
+                .chunks_exact(4)
+                .map(|chunk| VectorElement::from_le_bytes(chunk.try_into().expect("chunks of four bytes")))
+                .collect();
+            let mut metadata = VectorMetadata::new();
+            metadata.created_at = reader.u64()?;
+            metadata.version = reader.u32()?;
+            for _ in 0..reader.u32()? {
+                let key = reader.string()?;
+                let value = reader.string()?;
+                metadata.fields.insert(key, value);
+            }
+            Some(IndexOp::Insert(IndexEntry {
+                id,
+                vector: Vector::new(data),
+                metadata,
+            }))
+        }
+        RECORD_DELETE => Some(IndexOp::Delete(id)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn temp_dir(name: &str) -> alloc::string::String {
+        let path = std::env::temp_dir().join(alloc::format!("frys-vector-oplog-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        path.to_str().unwrap().into()
+    }
+
+    fn lsh() -> LshIndex {
+        LshIndex::new(8, Metric::Euclidean, LshConfig { seed: 7, ..Default::default() }).unwrap()
+    }
+
+    async fn results(index: &impl VectorIndex, queries: &[Vector]) -> alloc::vec::Vec<alloc::vec::Vec<(VectorId, VectorElement)>> {
+        let config = SearchConfig { k: 5, ..Default::default() };
+        let mut all = alloc::vec::Vec::new();
+        for query in queries {
+            let found = index.search(query, &config).await.unwrap();
+            all.push(found.into_iter().map(|r| (r.id, r.distance)).collect());
+        }
+        all
+    }
+
+    #[tokio::test]
+    async fn test_replay_reproduces_index_without_full_save() {
+        let path = temp_dir("replay");
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut vector = || Vector::new((0..8).map(|_| rng.gen_range(-1.0..1.0)).collect());
+        let queries: alloc::vec::Vec<Vector> = (0..10).map(|_| vector()).collect();
+
+        let mut index = LoggedIndex::open(&path, lsh(), false).await.unwrap();
+        for i in 0..200 {
+            let mut metadata = VectorMetadata::new();
+            metadata.set("batch", &alloc::format!("{}", i / 50));
+            index.insert(alloc::format!("vec-{}", i), vector(), metadata).await.unwrap();
+        }
+        for i in (0..200).step_by(7) {
+            assert!(index.delete(&alloc::format!("vec-{}", i)).await.unwrap());
+        }
+        index.update("vec-1".into(), queries[0].clone(), VectorMetadata::new()).await.unwrap();
+        let expected = results(&index, &queries).await;
+        assert_eq!(index.log().pending(), 200 + 29 + 1);
+        drop(index);
+
+        let replayed = LoggedIndex::open(&path, lsh(), false).await.unwrap();
+        assert_eq!(results(&replayed, &queries).await, expected);
+        assert_eq!(replayed.index().len(), 200 - 29);
+        assert_eq!(replayed.log().entries().unwrap()[2].metadata.get("batch"), Some(&"0".into()));
+
+        // Compaction keeps the same live entries in a snapshot
+        let mut replayed = replayed;
+        replayed.snapshot().unwrap();
+        assert_eq!(replayed.log().pending(), 0);
+        drop(replayed);
+
+        let mut compacted = LoggedIndex::open(&path, lsh(), false).await.unwrap();
+        assert_eq!(results(&compacted, &queries).await, expected);
+        compacted.insert("late".into(), vector(), VectorMetadata::new()).await.unwrap();
+        drop(compacted);
+
+        let reopened = LoggedIndex::open(&path, lsh(), false).await.unwrap();
+        assert_eq!(reopened.log().pending(), 1);
+        assert_eq!(reopened.index().len(), 200 - 29 + 1);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[tokio::test]
+    async fn test_torn_tail_is_discarded() {
+        let path = temp_dir("torn");
+        let mut index = LoggedIndex::open(&path, lsh(), false).await.unwrap();
+        index.insert("a".into(), Vector::new(alloc::vec![1.0; 8]), VectorMetadata::new()).await.unwrap();
+        drop(index);
+
+        // Simulate a crash part-way through a second record
+        let entry = IndexEntry {
+            id: "b".into(),
+            vector: Vector::new(alloc::vec![2.0; 8]),
+            metadata: VectorMetadata::new(),
+        };
+        let record = frame(&encode(&IndexOp::Insert(entry)));
+        let mut file = OpenOptions::new().append(true).open(Path::new(&path).join("log")).unwrap();
+        file.write_all(&record[..record.len() / 2]).unwrap();
+
+        let mut index = LoggedIndex::open(&path, lsh(), false).await.unwrap();
+        assert_eq!(index.index().len(), 1);
+        index.insert("c".into(), Vector::new(alloc::vec![3.0; 8]), VectorMetadata::new()).await.unwrap();
+        let ids: alloc::vec::Vec<VectorId> = index.log().entries().unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, ["a", "c"]);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
@@ -0,0 +1,89 @@
+//! Recency-biased scoring
+//!
+//! For feeds and news, a slightly less similar document can be worth more
+//! than an old one. Set as `SearchConfig::recency_boost`, a [`RecencyBoost`]
+//! reads each candidate's age from a metadata field holding a Unix
+//! timestamp in seconds and scales its normalized score by
+//! `1 + 0.5^(age / half_life)`: a brand-new document counts double, one a
+//! half-life old half as much again. Candidates without a readable
+//! timestamp keep their score.
+
+use crate::*;
+use ::core::cmp::Ordering;
+use ::core::time::Duration;
+
+/// Score boost decaying with the age of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecencyBoost {
+    /// Metadata field holding the document's Unix timestamp in seconds
+    pub metadata_field: alloc::string::String,
+    /// Age at which the boost has halved
+    pub half_life: Duration,
+}
+
+impl RecencyBoost {
+    /// Boost documents by the timestamp in `metadata_field`
+    pub fn new(metadata_field: impl Into<alloc::string::String>, half_life: Duration) -> Self {
+        Self {
+            metadata_field: metadata_field.into(),
+            half_life,
+        }
+    }
+
+    /// Factor applied to the score of a document with `metadata` at time
+    /// `now`, in Unix seconds: between 1 (no boost) and 2
+    pub fn factor(&self, metadata: &VectorMetadata, now: f64) -> VectorElement {
+        let half_life = self.half_life.as_secs_f64();
+        let timestamp = metadata
+            .get(&self.metadata_field)
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|timestamp| timestamp.is_finite());
+        match timestamp {
+            Some(timestamp) if half_life > 0.0 => {
+                // Timestamps in the future count as brand new
+                let age = (now - timestamp).max(0.0);
+                (1.0 + 0.5f64.powf(age / half_life)) as VectorElement
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Replace each score with the boosted normalized score and re-sort,
+    /// keeping the vector order between equal scores
+    pub(crate) fn apply(&self, results: &mut [SearchResult], now: f64) {
+        for result in results.iter_mut() {
+            let factor = result.metadata.as_ref().map_or(1.0, |metadata| self.factor(metadata, now));
+            result.score = result.normalized_score() * factor;
+        }
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    }
+}
+
+/// Current time in Unix seconds
+pub(crate) fn unix_now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boost_halves_every_half_life() {
+        let boost = RecencyBoost::new("published_at", Duration::from_secs(3600));
+        let at = |timestamp: &str| {
+            let mut metadata = VectorMetadata::new();
+            metadata.set("published_at", timestamp);
+            metadata
+        };
+        let now = 1_700_000_000.0;
+
+        assert_eq!(boost.factor(&at("1700000000"), now), 2.0);
+        assert_eq!(boost.factor(&at("1699996400"), now), 1.5);
+        assert_eq!(boost.factor(&at("1700003600"), now), 2.0);
+        assert_eq!(boost.factor(&at("yesterday"), now), 1.0);
+        assert_eq!(boost.factor(&VectorMetadata::new(), now), 1.0);
+    }
+}
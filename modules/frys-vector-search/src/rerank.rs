@@ -0,0 +1,43 @@
+//! Reranking of retrieved candidates
+//!
+//! Vector distance is a cheap first pass. A [`Reranker`], such as a
+//! cross-encoder, scores each retrieved candidate against the query more
+//! precisely. Set as `SearchConfig::reranker`, it reorders the best
+//! `candidate_k` results of the vector search, and the final top `k` are
+//! taken from its ordering. A reranker that fails or returns the wrong
+//! number of scores leaves the vector ordering in place.
+
+use crate::*;
+use ::core::cmp::Ordering;
+
+/// Scores search candidates against the query
+#[async_trait::async_trait(?Send)]
+pub trait Reranker: Send + Sync {
+    /// Relevance of each candidate to `query`, higher first, in the order
+    /// the candidates are given
+    async fn score(&self, query: &Vector, candidates: &[&VectorMetadata]) -> Result<alloc::vec::Vec<VectorElement>>;
+}
+
+/// Order `results` by the scores of `reranker`, keeping ties and, if the
+/// reranker fails, the whole list in vector order
+pub(crate) async fn rerank(reranker: &dyn Reranker, query: &Vector, results: alloc::vec::Vec<SearchResult>) -> alloc::vec::Vec<SearchResult> {
+    let missing = VectorMetadata::new();
+    let candidates: alloc::vec::Vec<&VectorMetadata> =
+        results.iter().map(|result| result.metadata.as_ref().unwrap_or(&missing)).collect();
+
+    let scores = match reranker.score(query, &candidates).await {
+        Ok(scores) if scores.len() == results.len() => scores,
+        Ok(scores) => {
+            log::warn!("reranker returned {} scores for {} candidates, keeping vector order", scores.len(), results.len());
+            return results;
+        }
+        Err(error) => {
+            log::warn!("reranker failed, keeping vector order: {}", error);
+            return results;
+        }
+    };
+
+    let mut scored: alloc::vec::Vec<(VectorElement, SearchResult)> = scores.into_iter().zip(results).collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    scored.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,180 @@
+//! Scalar quantization
+//!
+//! A [`ScalarQuantizer`] learns the range of each dimension from a sample
+//! of vectors and stores every element as one byte: its position on 256
+//! evenly spaced steps between that dimension's minimum and maximum. This
+//! takes a quarter of the memory of `f32` storage.
+//!
+//! Queries stay at full precision. Each stored code is dequantized as it
+//! is compared, in the same fixed-width lanes as the CPU
+//! [`DistanceKernel`], so the scan vectorizes without decoding rows into a
+//! buffer first. Values outside the trained range are clamped to it.
+
+use crate::*;
+use crate::kernel::{cosine_distance, HAMMING_THRESHOLD, LANES};
+
+/// Highest code of a quantized element
+const MAX_CODE: VectorElement = 255.0;
+
+/// Precision vectors are stored at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// 32-bit floats
+    #[default]
+    F32,
+    /// One byte per element, scalar quantized per dimension
+    Int8,
+}
+
+/// Per-dimension scalar quantizer to 8-bit codes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarQuantizer {
+    /// Smallest trained value of each dimension
+    min: alloc::vec::Vec<VectorElement>,
+    /// Value of one code step in each dimension; zero for constant ones
+    step: alloc::vec::Vec<VectorElement>,
+}
+
+impl ScalarQuantizer {
+    /// Learn the range of each dimension from `sample`
+    pub fn train(sample: &[&[VectorElement]]) -> Result<Self> {
+        let Some(first) = sample.first() else {
+            return Err(VectorSearchError::IndexError {
+                operation: "train".into(),
+                reason: "scalar quantizer needs at least one sample vector".into(),
+            });
+        };
+
+        let mut min = first.to_vec();
+        let mut max = first.to_vec();
+        for vector in sample {
+            if vector.len() != min.len() {
+                return Err(VectorSearchError::InvalidDimensions {
+                    expected: min.len(),
+                    actual: vector.len(),
+                });
+            }
+            for ((min, max), value) in min.iter_mut().zip(&mut max).zip(*vector) {
+                *min = min.min(*value);
+                *max = max.max(*value);
+            }
+        }
+
+        let step = min.iter().zip(&max).map(|(min, max)| (max - min) / MAX_CODE).collect();
+        Ok(Self { min, step })
+    }
+
+    /// Dimensionality the quantizer was trained on
+    pub fn dimensions(&self) -> usize {
+        self.min.len()
+    }
+
+    /// Quantize `vector`, clamping values outside the trained range
+    pub fn encode(&self, vector: &[VectorElement]) -> alloc::vec::Vec<u8> {
+        vector
+            .iter()
+            .zip(self.min.iter().zip(&self.step))
+            .map(|(value, (min, step))| {
+                if *step == 0.0 {
+                    0
+                } else {
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let code = ((value - min) / step).round().clamp(0.0, MAX_CODE) as u8;
+                    code
+                }
+            })
+            .collect()
+    }
+
+    /// Approximate vector a code stands for
+    pub fn decode(&self, code: &[u8]) -> alloc::vec::Vec<VectorElement> {
+        code.iter()
+            .zip(self.min.iter().zip(&self.step))
+            .map(|(code, (min, step))| min + step * VectorElement::from(*code))
+            .collect()
+    }
+
+    /// Distance from `query` to the vector of each code, as
+    /// [`Metric::distance`] computes it. Codes or queries whose length
+    /// differs from the trained dimensionality score infinity.
+    pub fn distances(&self, metric: Metric, query: &[VectorElement], codes: &[&[u8]]) -> alloc::vec::Vec<VectorElement> {
+        let dims = self.dimensions();
+        let query_norm = if metric == Metric::Cosine {
+            query.iter().map(|x| x * x).sum::<VectorElement>().sqrt()
+        } else {
+            0.0
+        };
+
+        codes
+            .iter()
+            .map(|code| {
+                if query.len() != dims || code.len() != dims {
+                    return VectorElement::INFINITY;
+                }
+                match metric {
+                    Metric::Euclidean => self.lanes(query, code, |x, y| (x - y) * (x - y)).sqrt(),
+                    Metric::Manhattan => self.lanes(query, code, |x, y| (x - y).abs()),
+                    Metric::Hamming => self.lanes(query, code, |x, y| VectorElement::from(u8::from((x - y).abs() > HAMMING_THRESHOLD))),
+                    Metric::DotProduct => -self.lanes(query, code, |x, y| x * y),
+                    Metric::Cosine => {
+                        let norm = self.lanes(query, code, |_, y| y * y).sqrt();
+                        cosine_distance(self.lanes(query, code, |x, y| x * y), query_norm * norm)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Sum of `term` over the query elements and the dequantized elements
+    /// of `code`, accumulated in [`LANES`] independent lanes
+    fn lanes(&self, query: &[VectorElement], code: &[u8], term: impl Fn(VectorElement, VectorElement) -> VectorElement) -> VectorElement {
+        let dequantize = |i: usize| self.min[i] + self.step[i] * VectorElement::from(code[i]);
+        let full = query.len() - query.len() % LANES;
+
+        let mut acc = [0.0; LANES];
+        for start in (0..full).step_by(LANES) {
+            for (lane, acc) in acc.iter_mut().enumerate() {
+                let i = start + lane;
+                *acc += term(query[i], dequantize(i));
+            }
+        }
+        let tail: VectorElement = (full..query.len()).map(|i| term(query[i], dequantize(i))).sum();
+        acc.iter().sum::<VectorElement>() + tail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_quantized_distances_track_full_precision() {
+        let mut rng = StdRng::seed_from_u64(3);
+        // 19 elements exercise both the lanes and the remainder
+        let vectors: alloc::vec::Vec<Vector> =
+            (0..64).map(|_| Vector::new((0..19).map(|_| rng.gen_range(-2.0..2.0)).collect())).collect();
+        let rows: alloc::vec::Vec<&[VectorElement]> = vectors.iter().map(Vector::as_slice).collect();
+        let quantizer = ScalarQuantizer::train(&rows).unwrap();
+
+        let codes: alloc::vec::Vec<alloc::vec::Vec<u8>> = rows.iter().map(|row| quantizer.encode(row)).collect();
+        let code_rows: alloc::vec::Vec<&[u8]> = codes.iter().map(alloc::vec::Vec::as_slice).collect();
+        for (row, code) in rows.iter().zip(&codes) {
+            for (value, decoded) in row.iter().zip(quantizer.decode(code)) {
+                assert!((value - decoded).abs() <= 4.0 / MAX_CODE / 2.0 + 1e-5);
+            }
+        }
+
+        let query = &vectors[0];
+        for metric in [Metric::Cosine, Metric::Euclidean, Metric::DotProduct, Metric::Manhattan] {
+            let distances = quantizer.distances(metric, query.as_slice(), &code_rows);
+            for (code, distance) in codes.iter().zip(distances) {
+                let expected = metric.distance(query, &Vector::new(quantizer.decode(code))).unwrap();
+                assert!((distance - expected).abs() < 1e-3, "{:?}: {} != {}", metric, distance, expected);
+            }
+        }
+        assert!(quantizer.distances(Metric::Euclidean, &[1.0], &code_rows)[0].is_infinite());
+        assert!(ScalarQuantizer::train(&[]).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Sparse vectors and their inverted index
+//!
+//! Learned sparse embeddings such as SPLADE have tens of thousands of
+//! dimensions of which only a few dozen are nonzero. A [`SparseVector`]
+//! keeps just the nonzero `(index, value)` pairs, and a [`SparseIndex`]
+//! keeps a posting list per dimension naming the vectors nonzero in it.
+//! A query walks the posting lists of its own nonzero dimensions only,
+//! accumulating dot products, so its cost grows with the overlap between
+//! query and data rather than with the dimensionality.
+//!
+//! Only vectors with a positive dot product with the query are candidates:
+//! those sharing no nonzero dimension with it, or whose shared dimensions
+//! cancel out or point away from it, are never returned.
+
+use crate::*;
+use ::core::cmp::Ordering;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// Vector stored as its nonzero entries, in increasing index order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SparseVector {
+    /// Dimensions holding a nonzero value, strictly increasing
+    indices: Vec<u32>,
+    /// Value of each dimension in `indices`
+    values: Vec<VectorElement>,
+}
+
+impl SparseVector {
+    /// Create a vector from `(index, value)` pairs in any order.
+    ///
+    /// Zero values are dropped. Fails on a repeated index or a value that
+    /// is not finite.
+    pub fn new(entries: impl IntoIterator<Item = (u32, VectorElement)>) -> Result<Self> {
+        let mut entries: Vec<(u32, VectorElement)> = entries.into_iter().filter(|&(_, value)| value != 0.0).collect();
+        entries.sort_by_key(|&(index, _)| index);
+
+        if let Some(&(index, value)) = entries.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(VectorSearchError::IndexError {
+                operation: "sparse_vector".into(),
+                reason: alloc::format!("value {} at index {} is not finite", value, index),
+            });
+        }
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(VectorSearchError::IndexError {
+                operation: "sparse_vector".into(),
+                reason: alloc::format!("index {} is given twice", pair[0].0),
+            });
+        }
+
+        Ok(Self {
+            indices: entries.iter().map(|&(index, _)| index).collect(),
+            values: entries.iter().map(|&(_, value)| value).collect(),
+        })
+    }
+
+    /// Nonzero entries of a dense vector
+    pub fn from_dense(vector: &Vector) -> Self {
+        let (indices, values) = vector
+            .as_slice()
+            .iter()
+            .enumerate()
+            .filter(|(_, &value)| value != 0.0)
+            .map(|(index, &value)| (index as u32, value))
+            .unzip();
+        Self { indices, values }
+    }
+
+    /// Dense vector of `dims` dimensions holding these entries.
+    ///
+    /// Fails if an entry lies beyond `dims`.
+    pub fn to_dense(&self, dims: usize) -> Result<Vector> {
+        if let Some(&last) = self.indices.last() {
+            if last as usize >= dims {
+                return Err(VectorSearchError::InvalidDimensions {
+                    expected: dims,
+                    actual: last as usize + 1,
+                });
+            }
+        }
+        let mut data = alloc::vec![0.0; dims];
+        for (index, value) in self.iter() {
+            data[index as usize] = value;
+        }
+        Ok(Vector::new(data))
+    }
+
+    /// Nonzero dimensions, in increasing order
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Values of the nonzero dimensions
+    pub fn values(&self) -> &[VectorElement] {
+        &self.values
+    }
+
+    /// Number of nonzero entries
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Check whether every entry is zero
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// `(index, value)` pairs in increasing index order
+    pub fn iter(&self) -> impl Iterator<Item = (u32, VectorElement)> + '_ {
+        self.indices.iter().copied().zip(self.values.iter().copied())
+    }
+
+    /// Dot product, merging the two index lists
+    pub fn dot(&self, other: &SparseVector) -> VectorElement {
+        let (mut i, mut j) = (0, 0);
+        let mut dot = 0.0;
+        while i < self.indices.len() && j < other.indices.len() {
+            match self.indices[i].cmp(&other.indices[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    dot += self.values[i] * other.values[j];
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        dot
+    }
+
+    /// Euclidean norm
+    pub fn norm(&self) -> VectorElement {
+        self.values.iter().map(|value| value * value).sum::<VectorElement>().sqrt()
+    }
+}
+
+/// Identity and data of an indexed sparse vector
+#[derive(Debug, Clone)]
+struct Entry {
+    id: VectorId,
+    vector: SparseVector,
+    norm: VectorElement,
+    metadata: VectorMetadata,
+}
+
+/// Inverted index over the nonzero dimensions of sparse vectors
+#[derive(Debug)]
+pub struct SparseIndex {
+    /// Similarity metric
+    metric: Metric,
+    /// `(slot, value)` of every vector nonzero in a dimension, by dimension
+    postings: BTreeMap<u32, Vec<(u32, VectorElement)>>,
+    /// Indexed vectors by slot; `None` once deleted or replaced
+    entries: Vec<Option<Entry>>,
+    /// Live slot of each ID
+    id_to_slot: BTreeMap<VectorId, u32>,
+}
+
+impl SparseIndex {
+    /// Create an empty index ranking by `DotProduct` or `Cosine`; other
+    /// metrics are not supported.
+    pub fn new(metric: Metric) -> Result<Self> {
+        if !matches!(metric, Metric::DotProduct | Metric::Cosine) {
+            return Err(VectorSearchError::MetricNotSupported {
+                metric: alloc::format!("{:?}", metric),
+            });
+        }
+
+        Ok(Self {
+            metric,
+            postings: BTreeMap::new(),
+            entries: Vec::new(),
+            id_to_slot: BTreeMap::new(),
+        })
+    }
+
+    /// Similarity metric
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.id_to_slot.len()
+    }
+
+    /// Check whether the index holds no live vectors
+    pub fn is_empty(&self) -> bool {
+        self.id_to_slot.is_empty()
+    }
+
+    /// Insert or replace a vector
+    pub fn insert(&mut self, id: VectorId, vector: SparseVector, metadata: VectorMetadata) -> Result<()> {
+        self.delete(&id);
+
+        let slot = u32::try_from(self.entries.len()).map_err(|_| VectorSearchError::ResourceLimitExceeded {
+            resource: "sparse_slots".into(),
+            limit: u32::MAX.to_string(),
+            actual: self.entries.len().to_string(),
+        })?;
+        for (index, value) in vector.iter() {
+            self.postings.entry(index).or_default().push((slot, value));
+        }
+
+        self.entries.push(Some(Entry {
+            id: id.clone(),
+            norm: vector.norm(),
+            vector,
+            metadata,
+        }));
+        self.id_to_slot.insert(id, slot);
+        Ok(())
+    }
+
+    /// Delete a vector, returning whether it existed
+    pub fn delete(&mut self, id: &VectorId) -> bool {
+        let Some(slot) = self.id_to_slot.remove(id) else {
+            return false;
+        };
+        if let Some(entry) = self.entries[slot as usize].take() {
+            for &index in entry.vector.indices() {
+                if let Some(posting) = self.postings.get_mut(&index) {
+                    posting.retain(|&(s, _)| s != slot);
+                    if posting.is_empty() {
+                        self.postings.remove(&index);
+                    }
+                }
+            }
+        }
+
+        // Renumber the slots once most of them are free
+        if self.entries.len() > 2 * self.id_to_slot.len() {
+            self.compact();
+        }
+        true
+    }
+
+    /// Drop the free slots, keeping the live vectors in insertion order
+    fn compact(&mut self) {
+        let entries = ::core::mem::take(&mut self.entries);
+        self.postings.clear();
+        for entry in entries.into_iter().flatten() {
+            let slot = self.entries.len() as u32;
+            for (index, value) in entry.vector.iter() {
+                self.postings.entry(index).or_default().push((slot, value));
+            }
+            self.id_to_slot.insert(entry.id.clone(), slot);
+            self.entries.push(Some(entry));
+        }
+    }
+
+    /// Number of slots, live or free
+    #[cfg(test)]
+    fn slots(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Search for the `k` most similar vectors
+    pub fn search(&self, query: &SparseVector, k: usize) -> Vec<SearchResult> {
+        self.search_filtered(query, k, None)
+    }
+
+    /// Search for the `k` most similar vectors whose metadata passes
+    /// `filter`. Every candidate is scored, so a filter never costs recall.
+    pub(crate) fn search_filtered(
+        &self,
+        query: &SparseVector,
+        k: usize,
+        filter: Option<&(dyn Fn(&VectorMetadata) -> bool + Send + Sync)>,
+    ) -> Vec<SearchResult> {
+        let mut dots: BTreeMap<u32, VectorElement> = BTreeMap::new();
+        for (index, value) in query.iter() {
+            for &(slot, stored) in self.postings.get(&index).into_iter().flatten() {
+                *dots.entry(slot).or_insert(0.0) += value * stored;
+            }
+        }
+
+        let query_norm = query.norm();
+        let mut ranked: Vec<(&Entry, VectorElement)> = dots
+            .into_iter()
+            .filter(|&(_, dot)| dot > 0.0)
+            .filter_map(|(slot, dot)| Some((self.entries[slot as usize].as_ref()?, dot)))
+            .filter(|(entry, _)| filter.is_none_or(|filter| filter(&entry.metadata)))
+            .filter_map(|(entry, dot)| match self.metric {
+                Metric::Cosine if query_norm > 0.0 && entry.norm > 0.0 => Some((entry, 1.0 - dot / (query_norm * entry.norm))),
+                Metric::Cosine => None,
+                _ => Some((entry, -dot)),
+            })
+            .collect();
+        // Stable, so equal distances keep insertion order
+        ranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        ranked.truncate(k);
+
+        ranked
+            .into_iter()
+            .map(|(entry, distance)| SearchResult {
+                id: entry.id.clone(),
+                score: match self.metric {
+                    Metric::Cosine => 1.0 - distance,
+                    _ => -distance,
+                },
+                distance,
+                metric: self.metric,
+                vector: None,
+                metadata: Some(entry.metadata.clone()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sparse(entries: &[(u32, VectorElement)]) -> SparseVector {
+        SparseVector::new(entries.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn test_sparse_vector_construction() {
+        let vector = sparse(&[(30_000, 0.5), (7, 1.5), (12, 0.0)]);
+        assert_eq!(vector.indices(), [7, 30_000]);
+        assert_eq!(vector.values(), [1.5, 0.5]);
+        assert_eq!(vector.dot(&sparse(&[(7, 2.0), (8, 4.0)])), 3.0);
+        assert!(SparseVector::new([(3, 1.0), (3, 2.0)]).is_err());
+        assert!(SparseVector::new([(3, VectorElement::NAN)]).is_err());
+
+        let dense = Vector::new(alloc::vec![0.0, 2.0, 0.0, -1.0]);
+        assert_eq!(SparseVector::from_dense(&dense).to_dense(4).unwrap(), dense);
+        assert!(vector.to_dense(100).is_err());
+    }
+
+    #[test]
+    fn test_ranking_matches_dense_reference() {
+        let dims = 16;
+        let documents = [
+            sparse(&[(0, 1.0), (3, 2.0), (9, 0.5)]),
+            sparse(&[(3, 0.2), (4, 3.0)]),
+            sparse(&[(0, 4.0), (9, 1.0), (15, 2.0)]),
+            sparse(&[(1, 1.0), (2, 1.0)]),
+            sparse(&[(3, 1.0), (9, 3.0), (12, 0.1)]),
+            sparse(&[(0, 0.1), (4, 0.1), (9, 0.1)]),
+            // Overlaps the query but points away from it
+            sparse(&[(0, -2.0), (3, 1.0)]),
+        ];
+        let query = sparse(&[(0, 1.0), (3, 0.5), (9, 2.0)]);
+        let dense_query = query.to_dense(dims).unwrap();
+
+        for metric in [Metric::DotProduct, Metric::Cosine] {
+            let mut index = SparseIndex::new(metric).unwrap();
+            for (i, document) in documents.iter().enumerate() {
+                index.insert(alloc::format!("doc-{}", i), document.clone(), VectorMetadata::new()).unwrap();
+            }
+
+            // Dense reference over the documents similar to the query
+            let mut reference: Vec<(VectorElement, VectorId)> = documents
+                .iter()
+                .enumerate()
+                .filter(|(_, document)| document.dot(&query) > 0.0)
+                .map(|(i, document)| {
+                    let distance = metric.distance(&dense_query, &document.to_dense(dims).unwrap()).unwrap();
+                    (distance, alloc::format!("doc-{}", i))
+                })
+                .collect();
+            reference.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            let results = index.search(&query, 10);
+            let ids: Vec<&VectorId> = results.iter().map(|result| &result.id).collect();
+            let expected: Vec<&VectorId> = reference.iter().map(|(_, id)| id).collect();
+            assert_eq!(ids, expected, "{:?}", metric);
+            for (result, (distance, _)) in results.iter().zip(&reference) {
+                assert!((result.distance - distance).abs() < 1e-5, "{:?}", metric);
+            }
+            assert!(results.iter().all(|result| result.id != "doc-3" && result.id != "doc-6"));
+        }
+    }
+
+    #[test]
+    fn test_replaced_and_deleted_vectors() {
+        let mut index = SparseIndex::new(Metric::DotProduct).unwrap();
+        index.insert("a".into(), sparse(&[(1, 1.0)]), VectorMetadata::new()).unwrap();
+        index.insert("b".into(), sparse(&[(1, 2.0)]), VectorMetadata::new()).unwrap();
+        index.insert("a".into(), sparse(&[(1, 3.0)]), VectorMetadata::new()).unwrap();
+
+        let results = index.search(&sparse(&[(1, 1.0)]), 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].id.as_str(), results[0].score), ("a", 3.0));
+
+        assert!(index.delete(&"a".into()));
+        assert!(!index.delete(&"a".into()));
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.search(&sparse(&[(1, 1.0)]), 10)[0].id, "b");
+        assert!(SparseIndex::new(Metric::Euclidean).is_err());
+    }
+
+    #[test]
+    fn test_deleted_slots_are_compacted() {
+        let mut index = SparseIndex::new(Metric::DotProduct).unwrap();
+        for round in 0..100 {
+            for id in ["a", "b", "c"] {
+                index.insert(id.into(), sparse(&[(1, 1.0 + round as VectorElement)]), VectorMetadata::new()).unwrap();
+            }
+        }
+        index.insert("d".into(), sparse(&[(2, 1.0)]), VectorMetadata::new()).unwrap();
+
+        assert_eq!(index.len(), 4);
+        assert!(index.slots() <= 8, "{}", index.slots());
+        let ids: Vec<VectorId> = index.search(&sparse(&[(1, 1.0)]), 10).into_iter().map(|result| result.id).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert_eq!(index.search(&sparse(&[(2, 1.0)]), 10)[0].id, "d");
+    }
+}
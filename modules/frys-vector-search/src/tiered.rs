@@ -0,0 +1,383 @@
+//! Tiered (LSM-style) indexing with merge-on-read search
+
+use crate::*;
+use ::core::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Tiered index configuration
+#[derive(Debug, Clone)]
+pub struct TieredIndexConfig {
+    /// Distance metric
+    pub metric: Metric,
+    /// Number of vectors the hot tier holds before it is sealed
+    pub hot_tier_capacity: usize,
+    /// Number of sealed tiers merged together in one compaction
+    pub merge_factor: usize,
+}
+
+impl Default for TieredIndexConfig {
+    fn default() -> Self {
+        Self {
+            metric: Metric::Cosine,
+            hot_tier_capacity: DEFAULT_HOT_TIER_CAPACITY,
+            merge_factor: DEFAULT_MERGE_FACTOR,
+        }
+    }
+}
+
+/// A single immutable-once-sealed tier
+#[derive(Debug)]
+struct Tier {
+    /// Generation number, increasing with every new tier
+    generation: u64,
+    /// Searchable index over the tier's vectors
+    index: FlatIndex,
+    /// Source entries, kept for merging
+    entries: alloc::vec::Vec<IndexEntry>,
+}
+
+impl Tier {
+    fn new(generation: u64, metric: Metric) -> Self {
+        Self {
+            generation,
+            index: FlatIndex::new(metric),
+            entries: alloc::vec::Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, entry: IndexEntry) -> Result<()> {
+        self.index.insert(entry.id.clone(), entry.vector.clone(), entry.metadata.clone())?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Index that absorbs inserts in a small hot tier and periodically merges
+/// sealed tiers into larger ones.
+///
+/// Searches query every tier and merge the results. Updates and deletes
+/// shadow older copies, which are dropped when their tier is merged.
+#[derive(Debug)]
+pub struct TieredIndex {
+    /// Configuration
+    config: TieredIndexConfig,
+    /// Tier receiving new inserts
+    hot: Tier,
+    /// Sealed tiers, oldest first
+    sealed: alloc::vec::Vec<Tier>,
+    /// Generation of the tier holding the live copy of each vector
+    live: alloc::collections::BTreeMap<VectorId, u64>,
+    /// Next tier generation
+    next_generation: u64,
+    /// Dimensionality of indexed vectors
+    dimensions: Option<usize>,
+}
+
+impl TieredIndex {
+    /// Create an empty tiered index
+    pub fn new(config: TieredIndexConfig) -> Result<Self> {
+        if config.hot_tier_capacity == 0 || config.merge_factor < 2 {
+            return Err(VectorSearchError::ConfigError {
+                parameter: "tiered_index".into(),
+                reason: "hot_tier_capacity must be positive and merge_factor at least 2".into(),
+            });
+        }
+
+        Ok(Self {
+            hot: Tier::new(0, config.metric),
+            sealed: alloc::vec::Vec::new(),
+            live: alloc::collections::BTreeMap::new(),
+            next_generation: 1,
+            dimensions: None,
+            config,
+        })
+    }
+
+    /// Insert or replace a vector
+    pub fn insert(&mut self, id: VectorId, vector: Vector, metadata: VectorMetadata) -> Result<()> {
+        match self.dimensions {
+            Some(expected) if expected != vector.dims() => {
+                return Err(VectorSearchError::InvalidDimensions {
+                    expected,
+                    actual: vector.dims(),
+                })
+            }
+            _ => self.dimensions = Some(vector.dims()),
+        }
+
+        // Replacing a vector still in the hot tier requires a fresh tier so
+        // the hot tier never holds two copies of one ID.
+        if self.live.get(&id) == Some(&self.hot.generation) {
+            self.seal_hot();
+        }
+
+        self.live.insert(id.clone(), self.hot.generation);
+        self.hot.insert(IndexEntry { id, vector, metadata })?;
+
+        if self.hot.len() >= self.config.hot_tier_capacity {
+            self.seal_hot();
+        }
+        Ok(())
+    }
+
+    /// Delete a vector, returning whether it existed
+    pub fn delete(&mut self, id: &VectorId) -> bool {
+        self.live.remove(id).is_some()
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    /// Check whether the index holds no live vectors
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    /// Number of non-empty tiers, including the hot tier
+    pub fn tier_count(&self) -> usize {
+        self.sealed.len() + usize::from(self.hot.len() > 0)
+    }
+
+    /// Search every tier and merge the results into a single top-k
+    pub fn search(&self, query: &Vector, k: usize) -> Result<alloc::vec::Vec<SearchResult>> {
+        let mut merged = alloc::vec::Vec::new();
+
+        for tier in self.sealed.iter().chain(::core::iter::once(&self.hot)) {
+            if tier.len() == 0 {
+                continue;
+            }
+
+            // Over-fetch by the number of shadowed copies so they cannot
+            // crowd live results out of the tier's top-k.
+            let shadowed = tier.entries.iter().filter(|e| !self.is_live(&e.id, tier.generation)).count();
+            let results = tier.index.search(query, k + shadowed)?;
+            merged.extend(results.into_iter().filter(|r| self.is_live(&r.id, tier.generation)));
+        }
+
+        merged.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(::core::cmp::Ordering::Equal));
+        merged.truncate(k);
+        Ok(merged)
+    }
+
+    /// Merge the smallest sealed tiers once enough of them have accumulated.
+    ///
+    /// Returns whether a merge happened.
+    pub fn compact(&mut self) -> Result<bool> {
+        if self.sealed.len() < self.config.merge_factor {
+            return Ok(false);
+        }
+
+        self.sealed.sort_by_key(|tier| (tier.len(), tier.generation));
+        let inputs: alloc::vec::Vec<Tier> = self.sealed.drain(..self.config.merge_factor).collect();
+
+        let mut merged = Tier::new(self.allocate_generation(), self.config.metric);
+        for tier in inputs {
+            for entry in tier.entries {
+                if self.is_live(&entry.id, tier.generation) {
+                    self.live.insert(entry.id.clone(), merged.generation);
+                    merged.insert(entry)?;
+                }
+            }
+        }
+
+        if merged.len() > 0 {
+            self.sealed.push(merged);
+        }
+        self.sealed.sort_by_key(|tier| tier.generation);
+        Ok(true)
+    }
+
+    /// Compact until fewer than `merge_factor` sealed tiers remain
+    pub fn compact_all(&mut self) -> Result<usize> {
+        let mut merges = 0;
+        while self.compact()? {
+            merges += 1;
+        }
+        Ok(merges)
+    }
+
+    fn is_live(&self, id: &VectorId, generation: u64) -> bool {
+        self.live.get(id) == Some(&generation)
+    }
+
+    fn seal_hot(&mut self) {
+        let fresh = Tier::new(self.allocate_generation(), self.config.metric);
+        let sealed = ::core::mem::replace(&mut self.hot, fresh);
+        if sealed.len() > 0 {
+            self.sealed.push(sealed);
+        }
+    }
+
+    fn allocate_generation(&mut self) -> u64 {
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        generation
+    }
+}
+
+/// Background thread that periodically compacts a shared tiered index
+#[derive(Debug)]
+pub struct BackgroundMerger {
+    /// Stop signal
+    stop: Arc<AtomicBool>,
+    /// Worker thread
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl BackgroundMerger {
+    /// Start compacting `index` every `interval`
+    pub fn start(index: Arc<RwLock<TieredIndex>>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker_stop = stop.clone();
+
+        let handle = std::thread::spawn(move || {
+            while !worker_stop.load(Ordering::Acquire) {
+                if let Ok(mut index) = index.write() {
+                    // A failed merge leaves the input tiers in place; retry next tick.
+                    let _ = index.compact();
+                }
+                std::thread::sleep(interval);
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the worker and wait for it to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for BackgroundMerger {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors
+    fn vectors(count: usize, dims: usize, seed: u64) -> alloc::vec::Vec<Vector> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                Vector::new(
+                    (0..dims)
+                        .map(|_| {
+                            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                            ((state >> 33) as f32 / u32::MAX as f32) * 2.0 - 1.0
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn tiered() -> TieredIndex {
+        TieredIndex::new(TieredIndexConfig {
+            metric: Metric::Euclidean,
+            hot_tier_capacity: 16,
+            merge_factor: 4,
+        })
+        .unwrap()
+    }
+
+    fn ids(results: &[SearchResult]) -> alloc::vec::Vec<VectorId> {
+        results.iter().map(|r| r.id.clone()).collect()
+    }
+
+    #[test]
+    fn test_tiered_search_matches_single_index() {
+        let mut tiered = tiered();
+        let mut single = FlatIndex::new(Metric::Euclidean);
+
+        for (i, vector) in vectors(200, 8, 7).into_iter().enumerate() {
+            let id = alloc::format!("vec-{}", i);
+            tiered.insert(id.clone(), vector.clone(), VectorMetadata::new()).unwrap();
+            single.insert(id, vector, VectorMetadata::new()).unwrap();
+        }
+        assert!(tiered.tier_count() > 1);
+
+        for query in vectors(5, 8, 99) {
+            let expected = ids(&single.search(&query, 10).unwrap());
+            assert_eq!(ids(&tiered.search(&query, 10).unwrap()), expected);
+
+            tiered.compact_all().unwrap();
+            assert_eq!(ids(&tiered.search(&query, 10).unwrap()), expected);
+        }
+    }
+
+    #[test]
+    fn test_updates_and_deletes_shadow_older_tiers() {
+        let mut index = tiered();
+        let data = vectors(40, 4, 3);
+        for (i, vector) in data.iter().enumerate() {
+            index.insert(alloc::format!("vec-{}", i), vector.clone(), VectorMetadata::new()).unwrap();
+        }
+
+        // Move vec-0 onto vec-1's position and delete vec-1
+        index.insert("vec-0".into(), data[1].clone(), VectorMetadata::new()).unwrap();
+        assert!(index.delete(&"vec-1".into()));
+
+        let results = index.search(&data[1], 3).unwrap();
+        assert_eq!(results[0].id, "vec-0");
+        assert!(results.iter().all(|r| r.id != "vec-1"));
+        assert_eq!(results.iter().filter(|r| r.id == "vec-0").count(), 1);
+        assert_eq!(index.len(), 39);
+
+        index.compact_all().unwrap();
+        assert_eq!(index.search(&data[1], 3).unwrap()[0].id, "vec-0");
+    }
+
+    #[test]
+    fn test_dimension_mismatch_rejected() {
+        let mut index = tiered();
+        index.insert("a".into(), Vector::zeros(4), VectorMetadata::new()).unwrap();
+        let result = index.insert("b".into(), Vector::zeros(3), VectorMetadata::new());
+        assert!(matches!(result, Err(VectorSearchError::InvalidDimensions { .. })));
+    }
+
+    #[test]
+    fn test_background_merge_reduces_tiers() {
+        let index = Arc::new(RwLock::new(tiered()));
+        {
+            let mut index = index.write().unwrap();
+            for (i, vector) in vectors(256, 4, 11).into_iter().enumerate() {
+                index.insert(alloc::format!("vec-{}", i), vector, VectorMetadata::new()).unwrap();
+            }
+        }
+        let initial_tiers = index.read().unwrap().tier_count();
+        assert_eq!(initial_tiers, 16);
+
+        let merger = BackgroundMerger::start(index.clone(), Duration::from_millis(1));
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while index.read().unwrap().tier_count() >= 4 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        merger.stop();
+
+        let index = index.read().unwrap();
+        assert!(index.tier_count() < 4);
+        assert_eq!(index.len(), 256);
+    }
+}
@@ -0,0 +1,198 @@
+//! Online tuning of the search-time `ef` parameter
+//!
+//! Recall is measured by comparing approximate results with exact ones for
+//! a sample of queries ([`recall_at_k`]). An [`EfTuner`] turns those
+//! periodic measurements into `ef` adjustments: it raises `ef` in
+//! proportion to the shortfall while recall is below target, and lowers it
+//! slowly once recall is comfortably above, so searches stay as cheap as the
+//! target allows. Samples taken at the same `ef` are smoothed to damp noise.
+
+use crate::*;
+
+/// Weight of a new recall sample in the smoothed recall
+const RECALL_SMOOTHING: f64 = 0.5;
+
+/// Fraction of the current `ef` given up per sample while above target
+const EF_DECREASE_RATE: f64 = 0.05;
+
+/// Fraction of `approximate` results that also appear in `exact`, the
+/// ground truth for the same query and `k`
+pub fn recall_at_k(approximate: &[SearchResult], exact: &[SearchResult]) -> f64 {
+    if exact.is_empty() {
+        return 1.0;
+    }
+    let hits = approximate.iter().filter(|result| exact.iter().any(|truth| truth.id == result.id)).count();
+    hits as f64 / exact.len() as f64
+}
+
+/// Feedback controller keeping measured recall near a target
+#[derive(Debug, Clone)]
+pub struct EfTuner {
+    /// Recall to maintain
+    target_recall: f64,
+    /// Current search-time `ef`
+    ef: usize,
+    /// Smallest `ef` the tuner will choose
+    min_ef: usize,
+    /// Largest `ef` the tuner will choose
+    max_ef: usize,
+    /// Surplus over the target tolerated before `ef` is lowered
+    tolerance: f64,
+    /// Proportional gain applied to the relative shortfall
+    gain: f64,
+    /// Exponentially smoothed recall of the samples taken at the current
+    /// `ef`, once one has arrived
+    smoothed_recall: Option<f64>,
+}
+
+impl EfTuner {
+    /// Create a tuner starting from `initial_ef`
+    pub fn new(target_recall: f64, initial_ef: usize) -> Result<Self> {
+        check_target(target_recall)?;
+        Ok(Self {
+            target_recall,
+            ef: initial_ef.clamp(1, DEFAULT_MAX_EF),
+            min_ef: 1,
+            max_ef: DEFAULT_MAX_EF,
+            tolerance: 0.02,
+            gain: 4.0,
+            smoothed_recall: None,
+        })
+    }
+
+    /// Limit the `ef` values the tuner may choose
+    pub fn with_ef_range(mut self, min_ef: usize, max_ef: usize) -> Self {
+        self.min_ef = min_ef.max(1);
+        self.max_ef = max_ef.max(self.min_ef);
+        self.ef = self.ef.clamp(self.min_ef, self.max_ef);
+        self
+    }
+
+    /// Current search-time `ef`
+    pub fn ef(&self) -> usize {
+        self.ef
+    }
+
+    /// Recall the tuner aims for
+    pub fn target_recall(&self) -> f64 {
+        self.target_recall
+    }
+
+    /// Change the target, keeping the current `ef` as the starting point
+    pub fn set_target_recall(&mut self, target_recall: f64) -> Result<()> {
+        check_target(target_recall)?;
+        self.target_recall = target_recall;
+        Ok(())
+    }
+
+    /// Smoothed recall of the samples taken at the current `ef`
+    pub fn smoothed_recall(&self) -> Option<f64> {
+        self.smoothed_recall
+    }
+
+    /// Feed a recall measurement taken at the current `ef`, returning the
+    /// `ef` to use from now on
+    pub fn record_recall(&mut self, recall: f64) -> usize {
+        let recall = recall.clamp(0.0, 1.0);
+        let smoothed = match self.smoothed_recall {
+            Some(previous) => previous + RECALL_SMOOTHING * (recall - previous),
+            None => recall,
+        };
+        self.smoothed_recall = Some(smoothed);
+
+        let ef = self.ef as f64;
+        let shortfall = self.target_recall - smoothed;
+        let next = if shortfall > 0.0 {
+            (ef * (1.0 + self.gain * shortfall / self.target_recall)).ceil().max(ef + 1.0)
+        } else if -shortfall > self.tolerance {
+            (ef * (1.0 - EF_DECREASE_RATE)).floor().min(ef - 1.0)
+        } else {
+            ef
+        };
+
+        let next = (next.max(0.0) as usize).clamp(self.min_ef, self.max_ef);
+        if next != self.ef {
+            // Samples taken at the old ef say little about the new one
+            self.smoothed_recall = None;
+            self.ef = next;
+        }
+        self.ef
+    }
+}
+
+fn check_target(target_recall: f64) -> Result<()> {
+    if target_recall > 0.0 && target_recall <= 1.0 {
+        Ok(())
+    } else {
+        Err(VectorSearchError::ConfigError {
+            parameter: "target_recall".into(),
+            reason: alloc::format!("{} is not in (0, 1]", target_recall),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str) -> SearchResult {
+        SearchResult {
+            id: id.into(),
+            score: 0.0,
+            distance: 0.0,
+            metric: Metric::Euclidean,
+            vector: None,
+            metadata: None,
+        }
+    }
+
+    /// Recall of a simulated index as a function of `ef`; 0.95 needs ef 120
+    fn simulated_recall(ef: usize) -> f64 {
+        1.0 - (-(ef as f64) / 40.0).exp()
+    }
+
+    #[test]
+    fn test_recall_at_k() {
+        let exact = [result("a"), result("b"), result("c"), result("d")];
+        let approximate = [result("a"), result("c"), result("x"), result("y")];
+        assert_eq!(recall_at_k(&approximate, &exact), 0.5);
+        assert_eq!(recall_at_k(&[], &[]), 1.0);
+    }
+
+    #[test]
+    fn test_ef_converges_up_to_target() {
+        let mut tuner = EfTuner::new(0.95, 8).unwrap();
+        assert!(simulated_recall(tuner.ef()) < 0.95);
+
+        let mut previous = tuner.ef();
+        while simulated_recall(tuner.ef()) < 0.95 {
+            let ef = tuner.record_recall(simulated_recall(tuner.ef()));
+            assert!(ef > previous, "ef must grow while recall is below target");
+            previous = ef;
+        }
+
+        // Keep feeding samples: recall stays at the target without ef
+        // running away
+        for _ in 0..50 {
+            tuner.record_recall(simulated_recall(tuner.ef()));
+        }
+        assert!(simulated_recall(tuner.ef()) >= 0.95 - 0.01);
+        assert!(tuner.ef() < 150, "ef settled at {}", tuner.ef());
+    }
+
+    #[test]
+    fn test_ef_shrinks_while_recall_exceeds_target() {
+        let mut tuner = EfTuner::new(0.95, 1000).unwrap();
+        for _ in 0..100 {
+            tuner.record_recall(simulated_recall(tuner.ef()));
+        }
+        assert!(tuner.ef() < 200, "ef settled at {}", tuner.ef());
+        assert!(simulated_recall(tuner.ef()) >= 0.95);
+    }
+
+    #[test]
+    fn test_invalid_target_rejected() {
+        assert!(EfTuner::new(0.0, 10).is_err());
+        assert!(EfTuner::new(1.5, 10).is_err());
+    }
+}
@@ -116,8 +116,8 @@ pub struct AuthConfig {
     pub required_claims: alloc::vec::Vec<alloc::string::String>,
     /// API key header name
     pub api_key_header: alloc::string::String,
-    /// Valid API keys
-    pub api_keys: alloc::collections::HashSet<alloc::string::String>,
+    /// Valid API keys, each mapped to the ID of the user it authenticates
+    pub api_keys: alloc::collections::HashMap<alloc::string::String, alloc::string::String>,
     /// OAuth2 configuration
     pub oauth_config: Option<OAuthConfig>,
     /// Custom authentication
@@ -131,7 +131,7 @@ impl Default for AuthConfig {
             jwt_secret: alloc::string::String::new(),
             required_claims: alloc::vec::Vec::new(),
             api_key_header: "X-API-Key".into(),
-            api_keys: alloc::collections::HashSet::new(),
+            api_keys: alloc::collections::HashMap::new(),
             oauth_config: None,
             custom_auth: None,
         }
@@ -194,6 +194,11 @@ pub struct RateLimitConfig {
     pub distributed: bool,
     /// Redis URL for distributed limiting
     pub redis_url: Option<alloc::string::String>,
+    /// What happens to messages over the limit
+    pub on_exceeded: RateLimitAction,
+    /// Close the connection once this many messages went over the limit
+    /// within one time window
+    pub max_violations: Option<u32>,
 }
 
 impl Default for RateLimitConfig {
@@ -205,10 +210,21 @@ impl Default for RateLimitConfig {
             time_window_seconds: 60,
             distributed: false,
             redis_url: None,
+            on_exceeded: RateLimitAction::Drop,
+            max_violations: None,
         }
     }
 }
 
+/// Handling of messages over the rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitAction {
+    /// Discard the message
+    Drop,
+    /// Stop reading until the limit allows the message
+    Delay,
+}
+
 /// Rate limiting key strategies
 #[derive(Debug, Clone)]
 pub enum RateLimitKey {
@@ -388,6 +404,16 @@ impl Message {
         }
     }
 
+    /// Create a binary message carrying `parts` as length-prefixed frames
+    pub fn framed(parts: &[&[u8]]) -> Self {
+        Self::binary(crate::framing::encode_frames(parts))
+    }
+
+    /// Split the payload into its length-prefixed frames
+    pub fn frames(&self) -> crate::Result<alloc::vec::Vec<&[u8]>> {
+        crate::framing::FrameReader::new(&self.payload).read_all()
+    }
+
     /// Add a header
     pub fn with_header(mut self, key: impl Into<alloc::string::String>, value: impl Into<alloc::string::String>) -> Self {
         self.headers.insert(key.into(), value.into());
@@ -869,6 +895,8 @@ mod tests {
         assert_eq!(config.burst_size, 20);
         assert_eq!(config.time_window_seconds, 60);
         assert!(!config.distributed);
+        assert_eq!(config.on_exceeded, RateLimitAction::Drop);
+        assert_eq!(config.max_violations, None);
     }
 
     #[test]
@@ -0,0 +1,187 @@
+//! Length-prefixed framing for binary messages
+//!
+//! Protocols that pack several segments into one binary message prefix each
+//! segment with its length as a big-endian `u32`. [`Message::framed`] builds
+//! such a payload and [`FrameReader`] splits a received one back into its
+//! segments, refusing frames cut short by the end of the payload and frames
+//! declaring more than the reader's maximum size.
+
+use crate::*;
+
+/// Size of the length prefix in front of every frame
+pub const FRAME_LENGTH_PREFIX_SIZE: usize = 4;
+
+/// Concatenate `parts`, each prefixed with its length
+///
+/// # Panics
+///
+/// Panics if a part is longer than `u32::MAX` bytes.
+pub fn encode_frames(parts: &[&[u8]]) -> alloc::vec::Vec<u8> {
+    let total = parts.iter().map(|part| FRAME_LENGTH_PREFIX_SIZE + part.len()).sum();
+    let mut payload = alloc::vec::Vec::with_capacity(total);
+    for part in parts {
+        let len = u32::try_from(part.len()).expect("frame longer than u32::MAX bytes");
+        payload.extend_from_slice(&len.to_be_bytes());
+        payload.extend_from_slice(part);
+    }
+    payload
+}
+
+/// Reader splitting a binary payload into length-prefixed frames
+///
+/// Yields the frames in order. After the first error it yields nothing more,
+/// as the position of any later frame is unknown.
+#[derive(Debug, Clone)]
+pub struct FrameReader<'a> {
+    payload: &'a [u8],
+    offset: usize,
+    max_frame_size: usize,
+    failed: bool,
+}
+
+impl<'a> FrameReader<'a> {
+    /// Read frames from `payload`, allowing frames up to
+    /// `DEFAULT_MAX_MESSAGE_SIZE` bytes
+    pub fn new(payload: &'a [u8]) -> Self {
+        Self {
+            payload,
+            offset: 0,
+            max_frame_size: DEFAULT_MAX_MESSAGE_SIZE,
+            failed: false,
+        }
+    }
+
+    /// Set the largest frame length accepted
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Bytes not read yet
+    pub fn remaining(&self) -> usize {
+        self.payload.len() - self.offset
+    }
+
+    /// Read the next frame, or `None` at the end of the payload
+    pub fn next_frame(&mut self) -> Result<Option<&'a [u8]>> {
+        if self.failed || self.remaining() == 0 {
+            return Ok(None);
+        }
+
+        let result = self.read_frame();
+        self.failed = result.is_err();
+        result.map(Some)
+    }
+
+    /// Read all remaining frames
+    pub fn read_all(mut self) -> Result<alloc::vec::Vec<&'a [u8]>> {
+        let mut frames = alloc::vec::Vec::new();
+        while let Some(frame) = self.next_frame()? {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+
+    fn read_frame(&mut self) -> Result<&'a [u8]> {
+        let start = self.offset;
+        let remaining = self.remaining();
+        if remaining < FRAME_LENGTH_PREFIX_SIZE {
+            return Err(WebSocketError::ProtocolError {
+                violation: "truncated_frame".into(),
+                message: alloc::format!(
+                    "length prefix at byte {} needs {} bytes but only {} remain",
+                    start,
+                    FRAME_LENGTH_PREFIX_SIZE,
+                    remaining
+                ),
+            });
+        }
+
+        let mut prefix = [0u8; FRAME_LENGTH_PREFIX_SIZE];
+        prefix.copy_from_slice(&self.payload[start..start + FRAME_LENGTH_PREFIX_SIZE]);
+        let len = u32::from_be_bytes(prefix) as usize;
+        if len > self.max_frame_size {
+            return Err(WebSocketError::ProtocolError {
+                violation: "oversized_frame".into(),
+                message: alloc::format!(
+                    "frame at byte {} declares {} bytes, over the limit of {}",
+                    start,
+                    len,
+                    self.max_frame_size
+                ),
+            });
+        }
+
+        let body = remaining - FRAME_LENGTH_PREFIX_SIZE;
+        if len > body {
+            return Err(WebSocketError::ProtocolError {
+                violation: "truncated_frame".into(),
+                message: alloc::format!("frame at byte {} declares {} bytes but only {} remain", start, len, body),
+            });
+        }
+
+        let data_start = start + FRAME_LENGTH_PREFIX_SIZE;
+        self.offset = data_start + len;
+        Ok(&self.payload[data_start..self.offset])
+    }
+}
+
+impl<'a> Iterator for FrameReader<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_framed_message_round_trip() {
+        let parts: [&[u8]; 4] = [b"header", b"", &[0, 1, 2, 255], b"trailer"];
+        let msg = Message::framed(&parts);
+        assert_eq!(msg.message_type, MessageType::Binary);
+        assert_eq!(msg.payload.len(), parts.iter().map(|p| p.len() + FRAME_LENGTH_PREFIX_SIZE).sum::<usize>());
+
+        let frames = msg.frames().unwrap();
+        assert_eq!(frames, parts);
+
+        let mut reader = FrameReader::new(&msg.payload);
+        assert_eq!(reader.next_frame().unwrap(), Some(&b"header"[..]));
+        assert_eq!(reader.by_ref().count(), 3);
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(reader.next_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_and_oversized_frames_rejected() {
+        let payload = encode_frames(&[b"first", b"second"]);
+
+        // Cut inside the second frame's data
+        let mut reader = FrameReader::new(&payload[..payload.len() - 2]);
+        assert_eq!(reader.next_frame().unwrap(), Some(&b"first"[..]));
+        match reader.next_frame() {
+            Err(WebSocketError::ProtocolError { violation, message }) => {
+                assert_eq!(violation, "truncated_frame");
+                assert_eq!(message, "frame at byte 9 declares 6 bytes but only 4 remain");
+            }
+            other => panic!("expected a truncated frame, got {:?}", other),
+        }
+        assert!(reader.next().is_none());
+
+        // Cut inside the second frame's length prefix
+        assert!(matches!(
+            FrameReader::new(&payload[..11]).read_all(),
+            Err(WebSocketError::ProtocolError { ref violation, .. }) if violation == "truncated_frame"
+        ));
+
+        // A declared length over the limit is refused before looking for the data
+        let result = FrameReader::new(&payload).with_max_frame_size(5).read_all();
+        assert!(matches!(
+            result,
+            Err(WebSocketError::ProtocolError { ref violation, .. }) if violation == "oversized_frame"
+        ));
+    }
+}
@@ -276,6 +276,7 @@ pub mod pubsub;
 pub mod broadcast;
 pub mod security;
 pub mod monitoring;
+pub mod framing;
 
 // Re-exports for convenience
 pub use core::*;
@@ -283,6 +284,8 @@ pub use server::*;
 pub use client::*;
 pub use pubsub::*;
 pub use broadcast::*;
+pub use security::*;
+pub use framing::*;
 
 // Error types
 mod error;
@@ -0,0 +1,269 @@
+//! Handshake authentication
+//!
+//! A [`HandshakeAuthenticator`] checks the credentials of an HTTP upgrade
+//! request against the configured [`AuthConfig`] before a WebSocket is
+//! established; [`accept`] rejects upgrades it refuses with
+//! `401 Unauthorized`.
+//!
+//! Browsers cannot set headers on WebSocket requests, so bearer tokens
+//! are also read from the `access_token` query parameter.
+
+use crate::*;
+use alloc::sync::Arc;
+
+/// Query parameter carrying a bearer token
+pub const ACCESS_TOKEN_PARAM: &str = "access_token";
+
+/// Validates credentials the authenticator cannot check by itself:
+/// OAuth2 access tokens and custom authentication headers.
+pub trait TokenValidator: Send + Sync {
+    /// Validate `token`, returning the ID of the user it belongs to
+    fn validate(&self, token: &str) -> Result<alloc::string::String>;
+}
+
+/// Authenticates WebSocket upgrade requests
+#[derive(Clone)]
+pub struct HandshakeAuthenticator {
+    /// Accepted credentials
+    config: AuthConfig,
+    /// Validator for OAuth2 and custom credentials
+    validator: Option<Arc<dyn TokenValidator>>,
+}
+
+impl core::fmt::Debug for HandshakeAuthenticator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HandshakeAuthenticator")
+            .field("method", &self.config.method)
+            .field("has_validator", &self.validator.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl HandshakeAuthenticator {
+    /// Create an authenticator accepting the credentials of `config`
+    pub fn new(config: AuthConfig) -> Self {
+        Self { config, validator: None }
+    }
+
+    /// Validate OAuth2 and custom credentials with `validator`
+    #[must_use]
+    pub fn with_validator(mut self, validator: Arc<dyn TokenValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Accepted credentials
+    pub fn config(&self) -> &AuthConfig {
+        &self.config
+    }
+
+    /// Scheme announced in the `WWW-Authenticate` header of a refusal
+    pub fn challenge(&self) -> &str {
+        match &self.config.method {
+            AuthMethod::None | AuthMethod::Jwt | AuthMethod::OAuth2 => "Bearer",
+            AuthMethod::ApiKey => "ApiKey",
+            AuthMethod::Custom(scheme) => scheme,
+        }
+    }
+
+    /// Authenticate an upgrade request.
+    ///
+    /// Returns the ID of the authenticated user, or `None` if no
+    /// authentication is configured.
+    pub fn authenticate<B>(&self, request: &http::Request<B>) -> Result<Option<alloc::string::String>> {
+        match &self.config.method {
+            AuthMethod::None => Ok(None),
+            AuthMethod::Jwt => {
+                let token = bearer_token(request).ok_or_else(|| failure("jwt", "missing bearer token"))?;
+                self.verify_jwt(&token).map(Some)
+            }
+            AuthMethod::ApiKey => {
+                let key = header(request, &self.config.api_key_header)
+                    .ok_or_else(|| failure("api_key", "missing API key"))?;
+                // Compare against every key so timing reveals neither the
+                // matching key nor how much of it was guessed
+                let mut user = None;
+                for (candidate, user_id) in &self.config.api_keys {
+                    if constant_time_eq(candidate.as_bytes(), key.as_bytes()) {
+                        user = Some(user_id.clone());
+                    }
+                }
+                user.map(Some).ok_or_else(|| failure("api_key", "invalid API key"))
+            }
+            AuthMethod::OAuth2 => {
+                let token = bearer_token(request).ok_or_else(|| failure("oauth2", "missing bearer token"))?;
+                self.validator("oauth2")?.validate(&token).map(Some)
+            }
+            AuthMethod::Custom(scheme) => {
+                let header_name = self.config.custom_auth.as_ref().map_or("Authorization", |custom| &custom.header_name);
+                let credentials = header(request, header_name)
+                    .ok_or_else(|| failure(scheme, &alloc::format!("missing {} header", header_name)))?;
+                self.validator(scheme)?.validate(credentials).map(Some)
+            }
+        }
+    }
+
+    /// Registered validator, required by `scheme`
+    fn validator(&self, scheme: &str) -> Result<&Arc<dyn TokenValidator>> {
+        self.validator.as_ref().ok_or_else(|| WebSocketError::ConfigurationError {
+            parameter: "auth_config.method".into(),
+            reason: alloc::format!("{} authentication needs a token validator", scheme),
+        })
+    }
+
+    /// Verify an HS256 JWT and return its subject
+    #[cfg(feature = "authentication")]
+    fn verify_jwt(&self, token: &str) -> Result<alloc::string::String> {
+        use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+
+        let claims = decode::<serde_json::Map<alloc::string::String, serde_json::Value>>(
+            token,
+            &DecodingKey::from_secret(self.config.jwt_secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .map_err(|e| failure("jwt", &e.to_string()))?
+        .claims;
+
+        if let Some(missing) = self.config.required_claims.iter().find(|claim| !claims.contains_key(*claim)) {
+            return Err(failure("jwt", &alloc::format!("missing required claim '{}'", missing)));
+        }
+        claims
+            .get("sub")
+            .and_then(serde_json::Value::as_str)
+            .map(Into::into)
+            .ok_or_else(|| failure("jwt", "token has no subject"))
+    }
+
+    /// Verify an HS256 JWT and return its subject
+    #[cfg(not(feature = "authentication"))]
+    fn verify_jwt(&self, _token: &str) -> Result<alloc::string::String> {
+        Err(WebSocketError::ConfigurationError {
+            parameter: "auth_config.method".into(),
+            reason: "JWT authentication needs the `authentication` feature".into(),
+        })
+    }
+}
+
+/// Authentication failure of `scheme`
+fn failure(scheme: &str, reason: &str) -> WebSocketError {
+    WebSocketError::AuthenticationError {
+        scheme: scheme.into(),
+        reason: reason.into(),
+    }
+}
+
+/// Whether `a` equals `b`, in time depending only on their lengths
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Value of header `name`, if present and valid UTF-8
+fn header<'a, B>(request: &'a http::Request<B>, name: &str) -> Option<&'a str> {
+    request.headers().get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Bearer token of the `Authorization` header or `access_token` parameter
+fn bearer_token<B>(request: &http::Request<B>) -> Option<alloc::string::String> {
+    if let Some(token) = header(request, "Authorization").and_then(|value| value.strip_prefix("Bearer ")) {
+        return Some(token.trim().into());
+    }
+    request.uri().query()?.split('&').find_map(|pair| {
+        pair.strip_prefix(ACCESS_TOKEN_PARAM)
+            .and_then(|rest| rest.strip_prefix('='))
+            .filter(|token| !token.is_empty())
+            .map(Into::into)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str, headers: &[(&str, &str)]) -> http::Request<()> {
+        let mut builder = http::Request::get(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_api_key_authentication() {
+        let authenticator = HandshakeAuthenticator::new(AuthConfig {
+            method: AuthMethod::ApiKey,
+            api_keys: [
+                ("0123456789abcdef".into(), "billing-service".into()),
+                ("fedcba9876543210".into(), "reporting-service".into()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+
+        let user = authenticator.authenticate(&request("/ws", &[("X-API-Key", "0123456789abcdef")])).unwrap();
+        assert_eq!(user.as_deref(), Some("billing-service"));
+        let user = authenticator.authenticate(&request("/ws", &[("X-API-Key", "fedcba9876543210")])).unwrap();
+        assert_eq!(user.as_deref(), Some("reporting-service"));
+        for wrong in ["wrong", "0123456789abcdeF", "0123456789abcdef0"] {
+            assert!(matches!(
+                authenticator.authenticate(&request("/ws", &[("X-API-Key", wrong)])),
+                Err(WebSocketError::AuthenticationError { .. })
+            ));
+        }
+        assert!(authenticator.authenticate(&request("/ws", &[])).is_err());
+    }
+
+    #[test]
+    fn test_oauth_token_from_query_parameter() {
+        struct Tokens;
+        impl TokenValidator for Tokens {
+            fn validate(&self, token: &str) -> Result<alloc::string::String> {
+                if token == "good" {
+                    Ok("alice".into())
+                } else {
+                    Err(failure("oauth2", "unknown token"))
+                }
+            }
+        }
+        let authenticator = HandshakeAuthenticator::new(AuthConfig {
+            method: AuthMethod::OAuth2,
+            ..Default::default()
+        });
+        assert!(matches!(
+            authenticator.authenticate(&request("/ws", &[("Authorization", "Bearer good")])),
+            Err(WebSocketError::ConfigurationError { .. })
+        ));
+
+        let authenticator = authenticator.with_validator(Arc::new(Tokens));
+        let user = authenticator.authenticate(&request("/ws?room=1&access_token=good", &[])).unwrap();
+        assert_eq!(user.as_deref(), Some("alice"));
+        assert!(authenticator.authenticate(&request("/ws", &[("Authorization", "Bearer bad")])).is_err());
+    }
+
+    #[cfg(feature = "authentication")]
+    #[test]
+    fn test_jwt_authentication() {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        let authenticator = HandshakeAuthenticator::new(AuthConfig {
+            method: AuthMethod::Jwt,
+            jwt_secret: "secret".into(),
+            required_claims: alloc::vec!["websocket".into()],
+            ..Default::default()
+        });
+        let token = |claims: serde_json::Value| {
+            encode(&Header::default(), &claims, &EncodingKey::from_secret(b"secret")).unwrap()
+        };
+        let valid = token(serde_json::json!({"sub": "bob", "exp": 4_000_000_000u64, "websocket": true}));
+        let missing_claim = token(serde_json::json!({"sub": "bob", "exp": 4_000_000_000u64}));
+
+        let user = authenticator
+            .authenticate(&request("/ws", &[("Authorization", &alloc::format!("Bearer {}", valid))]))
+            .unwrap();
+        assert_eq!(user.as_deref(), Some("bob"));
+        assert!(authenticator
+            .authenticate(&request(&alloc::format!("/ws?access_token={}", missing_claim), &[]))
+            .is_err());
+        assert!(authenticator.authenticate(&request("/ws?access_token=garbage", &[])).is_err());
+    }
+}
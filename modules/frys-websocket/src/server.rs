@@ -0,0 +1,522 @@
+//! Server-side connection handling
+//!
+//! [`accept`] performs the upgrade handshake, authenticating the request
+//! with a [`HandshakeAuthenticator`]. [`read_loop`] then drives the
+//! inbound side of the connection: every message read from the client
+//! passes the connection's [`RateLimiter`] before it reaches the
+//! application.
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::WebSocketStream;
+
+/// Buckets kept by a rate limiter before the least recently used is
+/// dropped
+const RATE_LIMIT_BUCKET_LIMIT: usize = 10_000;
+
+/// Accept a WebSocket connection from `remote_addr` over `stream`.
+///
+/// If an authenticator is given, the upgrade request must carry valid
+/// credentials: requests without them are answered with
+/// `401 Unauthorized`, counted as `auth_failures`, and no WebSocket is
+/// established. A misconfigured authenticator answers
+/// `500 Internal Server Error`. Refusals carry only the status reason;
+/// the cause is returned to the caller instead. The returned connection
+/// carries the authenticated user ID and is `Authenticated`, or
+/// `Connected` if no authentication was required.
+pub async fn accept<S>(
+    stream: S,
+    remote_addr: impl Into<alloc::string::String>,
+    authenticator: Option<&HandshakeAuthenticator>,
+    stats: &Mutex<WebSocketStats>,
+) -> Result<(WebSocketStream<S>, ConnectionInfo)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut connection = ConnectionInfo::new(remote_addr);
+    let mut rejection = None;
+    // The error response type is fixed by tungstenite
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, response: Response| -> std::result::Result<Response, ErrorResponse> {
+        connection.user_agent = request
+            .headers()
+            .get(http::header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(Into::into);
+        match authenticator.map_or(Ok(None), |authenticator| authenticator.authenticate(request)) {
+            Ok(user_id) => {
+                if let Some(user_id) = user_id {
+                    connection.set_user_id(user_id);
+                }
+                Ok(response)
+            }
+            Err(error) => {
+                // Why credentials were refused is not for the client to learn
+                let status = if matches!(error, WebSocketError::AuthenticationError { .. }) {
+                    http::StatusCode::UNAUTHORIZED
+                } else {
+                    http::StatusCode::INTERNAL_SERVER_ERROR
+                };
+                let mut refusal = ErrorResponse::new(status.canonical_reason().map(Into::into));
+                *refusal.status_mut() = status;
+                if status == http::StatusCode::UNAUTHORIZED {
+                    let challenge = authenticator.map_or("Bearer", HandshakeAuthenticator::challenge);
+                    if let Ok(challenge) = http::HeaderValue::from_str(challenge) {
+                        refusal.headers_mut().insert(http::header::WWW_AUTHENTICATE, challenge);
+                    }
+                }
+                rejection = Some(error);
+                Err(refusal)
+            }
+        }
+    };
+
+    let result = tokio_tungstenite::accept_hdr_async(stream, callback).await;
+    let mut stats = stats.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(error) = rejection {
+        stats.record_error(if matches!(error, WebSocketError::AuthenticationError { .. }) {
+            ErrorType::Authentication
+        } else {
+            ErrorType::Connection
+        });
+        return Err(error);
+    }
+    let socket = result.map_err(|e| {
+        stats.record_error(ErrorType::Connection);
+        WebSocketError::ConnectionError {
+            address: connection.remote_addr.clone(),
+            phase: ConnectionPhase::WebSocketHandshake,
+            message: e.to_string(),
+        }
+    })?;
+
+    stats.record_connection();
+    connection.set_state(if connection.user_id.is_some() {
+        ConnectionState::Authenticated
+    } else {
+        ConnectionState::Connected
+    });
+    Ok((socket, connection))
+}
+
+/// Verdict of a [`RateLimiter`] on an inbound message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDecision {
+    /// Deliver the message now
+    Allow,
+    /// Deliver the message after waiting
+    Wait(Duration),
+    /// Discard the message
+    Reject,
+    /// Discard the message and close the connection
+    Close,
+}
+
+/// Token bucket and recent violations of one rate limit key
+#[derive(Debug)]
+struct Bucket {
+    /// Available tokens; negative while delayed messages hold reservations
+    tokens: f64,
+    /// Last refill
+    refilled_at: Instant,
+    /// Start of the current violation window
+    window_start: Instant,
+    /// Messages over the limit in the current window
+    violations: u32,
+    /// Value of the use counter when the bucket was last checked
+    last_use: u64,
+}
+
+/// Buckets by key, with their keys ordered by last use
+#[derive(Debug, Default)]
+struct Buckets {
+    by_key: HashMap<alloc::string::String, Bucket>,
+    by_use: BTreeMap<u64, alloc::string::String>,
+    uses: u64,
+}
+
+/// Token bucket rate limiter for inbound messages.
+///
+/// Each key gets a bucket holding up to `burst_size` tokens, refilled at
+/// `messages_per_second`; a message takes one token. Keys are derived
+/// from the connection and message according to the configured
+/// [`RateLimitKey`], so connections sharing a user or address share a
+/// bucket. At most `max_buckets` keys are tracked; a new key beyond that
+/// evicts the least recently used bucket.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Limits being enforced
+    config: RateLimitConfig,
+    /// Buckets tracked before the least recently used is dropped
+    max_buckets: usize,
+    /// Buckets keyed by rate limit key
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Create a limiter enforcing `config`
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            max_buckets: RATE_LIMIT_BUCKET_LIMIT,
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Track at most `max_buckets` buckets
+    pub fn with_max_buckets(mut self, max_buckets: usize) -> Self {
+        self.max_buckets = max_buckets.max(1);
+        self
+    }
+
+    /// Limits being enforced
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Rate limit key of a message read from `connection`.
+    ///
+    /// Keys that cannot be derived (an anonymous user, a missing header)
+    /// fall back to the connection ID.
+    pub fn key(&self, connection: &ConnectionInfo, message: &Message) -> alloc::string::String {
+        let key = match &self.config.key_strategy {
+            RateLimitKey::Connection => None,
+            RateLimitKey::User => connection.user_id.clone(),
+            RateLimitKey::IP => Some(
+                connection
+                    .remote_addr
+                    .parse::<std::net::SocketAddr>()
+                    .map_or_else(|_| connection.remote_addr.clone(), |addr| addr.ip().to_string()),
+            ),
+            RateLimitKey::Header(name) => message.headers.get(name).cloned(),
+            RateLimitKey::Path => message.target.clone(),
+            RateLimitKey::Custom(name) => connection.get_metadata(name).cloned(),
+        };
+        key.unwrap_or_else(|| connection.id.clone())
+    }
+
+    /// Take a token for a message read from `connection`
+    pub fn check(&self, connection: &ConnectionInfo, message: &Message) -> RateDecision {
+        self.check_key(&self.key(connection, message), Instant::now())
+    }
+
+    /// Take a token from the bucket of `key` at time `now`
+    fn check_key(&self, key: &str, now: Instant) -> RateDecision {
+        let capacity = f64::from(self.config.burst_size.max(1));
+        let rate = f64::from(self.config.messages_per_second);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let buckets = &mut *buckets;
+        let last_use = buckets.uses;
+        buckets.uses += 1;
+
+        match buckets.by_key.get_mut(key) {
+            Some(bucket) => {
+                buckets.by_use.remove(&bucket.last_use);
+                bucket.last_use = last_use;
+            }
+            None => {
+                if buckets.by_key.len() >= self.max_buckets {
+                    if let Some((_, victim)) = buckets.by_use.pop_first() {
+                        buckets.by_key.remove(&victim);
+                    }
+                }
+                buckets.by_key.insert(
+                    key.into(),
+                    Bucket {
+                        tokens: capacity,
+                        refilled_at: now,
+                        window_start: now,
+                        violations: 0,
+                        last_use,
+                    },
+                );
+            }
+        }
+        buckets.by_use.insert(last_use, key.into());
+
+        let bucket = buckets.by_key.get_mut(key).expect("bucket inserted above");
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(capacity);
+        bucket.refilled_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return RateDecision::Allow;
+        }
+
+        if now.saturating_duration_since(bucket.window_start) >= Duration::from_secs(self.config.time_window_seconds) {
+            bucket.window_start = now;
+            bucket.violations = 0;
+        }
+        bucket.violations += 1;
+        if self.config.max_violations.is_some_and(|max| bucket.violations >= max) {
+            return RateDecision::Close;
+        }
+
+        match self.config.on_exceeded {
+            RateLimitAction::Drop => RateDecision::Reject,
+            // Without a refill rate the message would wait forever
+            RateLimitAction::Delay if rate <= 0.0 => RateDecision::Reject,
+            RateLimitAction::Delay => {
+                // Reserve the token so later messages queue behind this one
+                let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / rate);
+                bucket.tokens -= 1.0;
+                RateDecision::Wait(wait)
+            }
+        }
+    }
+}
+
+/// Read messages from a client until the stream ends.
+///
+/// Every message is counted in `stats` and `connection` and, if a
+/// limiter is given, checked against it: messages over the limit are
+/// dropped or delayed as configured and counted as `rate_limit_hits`.
+/// Delivered messages are passed to `on_message`.
+///
+/// Returns `WebSocketError::RateLimitExceeded` once the limiter decides
+/// to close the connection; the connection is left `Closing`, and the
+/// caller should send a policy violation close frame.
+pub async fn read_loop<S, F>(
+    connection: &mut ConnectionInfo,
+    mut incoming: S,
+    limiter: Option<&RateLimiter>,
+    stats: &Mutex<WebSocketStats>,
+    mut on_message: F,
+) -> Result<()>
+where
+    S: Stream<Item = Message> + Unpin,
+    F: FnMut(Message),
+{
+    while let Some(message) = incoming.next().await {
+        let size = message.payload.len();
+        connection.add_bytes_received(size as u64);
+        stats.lock().unwrap_or_else(|e| e.into_inner()).record_message(false, size);
+
+        let decision = limiter.map_or(RateDecision::Allow, |limiter| limiter.check(connection, &message));
+        if decision != RateDecision::Allow {
+            stats.lock().unwrap_or_else(|e| e.into_inner()).record_error(ErrorType::RateLimit);
+        }
+        match decision {
+            RateDecision::Allow => {}
+            RateDecision::Wait(wait) => tokio::time::sleep(wait).await,
+            RateDecision::Reject => continue,
+            RateDecision::Close => {
+                connection.set_state(ConnectionState::Closing);
+                let limiter = limiter.expect("only a limiter closes connections");
+                return Err(WebSocketError::RateLimitExceeded {
+                    client_id: limiter.key(connection, &message),
+                    limit_type: "messages_per_second".into(),
+                    retry_after_seconds: limiter.config.time_window_seconds,
+                });
+            }
+        }
+
+        connection.update_last_message();
+        on_message(message);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited(on_exceeded: RateLimitAction, max_violations: Option<u32>) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            messages_per_second: 1,
+            burst_size: 3,
+            on_exceeded,
+            max_violations,
+            ..Default::default()
+        })
+    }
+
+    fn burst(count: usize) -> impl Stream<Item = Message> + Unpin {
+        futures::stream::iter((0..count).map(|i| Message::text(alloc::format!("message {}", i))))
+    }
+
+    #[tokio::test]
+    async fn test_burst_over_limit_is_rejected_and_counted() {
+        let limiter = limited(RateLimitAction::Drop, None);
+        let stats = Mutex::new(WebSocketStats::default());
+        let mut connection = ConnectionInfo::new("127.0.0.1:9000");
+        let mut delivered = Vec::new();
+
+        read_loop(&mut connection, burst(10), Some(&limiter), &stats, |message| {
+            delivered.push(message.as_string_lossy());
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(delivered, ["message 0", "message 1", "message 2"]);
+        let stats = stats.into_inner().unwrap();
+        assert_eq!(stats.messages_received, 10);
+        assert_eq!(stats.rate_limit_hits, 7);
+        assert_eq!(connection.message_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_sustained_abuse_closes_connection() {
+        let limiter = limited(RateLimitAction::Drop, Some(4));
+        let stats = Mutex::new(WebSocketStats::default());
+        let mut connection = ConnectionInfo::new("127.0.0.1:9000");
+        let mut delivered = 0;
+
+        let result = read_loop(&mut connection, burst(10), Some(&limiter), &stats, |_| delivered += 1).await;
+
+        assert!(matches!(result, Err(WebSocketError::RateLimitExceeded { ref client_id, .. }) if *client_id == connection.id));
+        assert_eq!(connection.state, ConnectionState::Closing);
+        assert_eq!(delivered, 3);
+        assert_eq!(stats.into_inner().unwrap().rate_limit_hits, 4);
+    }
+
+    /// Run a client handshake with `headers` against [`accept`], returning
+    /// the server's result and the status and body the client received
+    async fn handshake(
+        authenticator: &HandshakeAuthenticator,
+        stats: &Mutex<WebSocketStats>,
+        headers: &[(&str, &str)],
+    ) -> (Result<ConnectionInfo>, Option<u16>, alloc::string::String) {
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        let mut request = http::Request::get("ws://localhost/ws")
+            .header("Host", "localhost")
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let client = tokio_tungstenite::client_async(request.body(()).unwrap(), client_io);
+
+        let (server, client) = tokio::join!(accept(server_io, "127.0.0.1:9000", Some(authenticator), stats), client);
+        let (status, body) = match client {
+            Ok((_, response)) => (Some(response.status().as_u16()), alloc::string::String::new()),
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => (
+                Some(response.status().as_u16()),
+                alloc::string::String::from_utf8_lossy(response.body().as_deref().unwrap_or_default()).into_owned(),
+            ),
+            Err(_) => (None, alloc::string::String::new()),
+        };
+        (server.map(|(_, connection)| connection), status, body)
+    }
+
+    #[tokio::test]
+    async fn test_handshake_requires_valid_credentials() {
+        let authenticator = HandshakeAuthenticator::new(AuthConfig {
+            method: AuthMethod::ApiKey,
+            api_keys: [("0123456789abcdef".into(), "billing-service".into())].into_iter().collect(),
+            ..Default::default()
+        });
+        let stats = Mutex::new(WebSocketStats::default());
+
+        let (missing, status, body) = handshake(&authenticator, &stats, &[]).await;
+        assert!(matches!(missing, Err(WebSocketError::AuthenticationError { .. })));
+        assert_eq!(status, Some(401));
+        assert_eq!(body, "Unauthorized");
+        let (invalid, status, body) = handshake(&authenticator, &stats, &[("X-API-Key", "guess")]).await;
+        assert!(matches!(invalid, Err(WebSocketError::AuthenticationError { .. })));
+        assert_eq!(status, Some(401));
+        assert_eq!(body, "Unauthorized");
+
+        let (valid, status, _) = handshake(&authenticator, &stats, &[("X-API-Key", "0123456789abcdef")]).await;
+        let connection = valid.unwrap();
+        assert_eq!(status, Some(101));
+        assert_eq!(connection.state, ConnectionState::Authenticated);
+        assert_eq!(connection.user_id.as_deref(), Some("billing-service"));
+
+        let stats = stats.into_inner().unwrap();
+        assert_eq!(stats.auth_failures, 2);
+        assert_eq!(stats.active_connections, 1);
+    }
+
+    #[tokio::test]
+    async fn test_misconfigured_authenticator_hides_cause() {
+        // OAuth2 without a token validator cannot check any token
+        let authenticator = HandshakeAuthenticator::new(AuthConfig {
+            method: AuthMethod::OAuth2,
+            ..Default::default()
+        });
+        let stats = Mutex::new(WebSocketStats::default());
+
+        let (result, status, body) = handshake(&authenticator, &stats, &[("Authorization", "Bearer token")]).await;
+        assert!(matches!(result, Err(WebSocketError::ConfigurationError { .. })));
+        assert_eq!(status, Some(500));
+        assert_eq!(body, "Internal Server Error");
+        assert_eq!(stats.into_inner().unwrap().auth_failures, 0);
+    }
+
+    #[test]
+    fn test_delay_reserves_tokens_in_order() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            messages_per_second: 10,
+            burst_size: 1,
+            on_exceeded: RateLimitAction::Delay,
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_key("conn", now), RateDecision::Allow);
+        assert_eq!(limiter.check_key("conn", now), RateDecision::Wait(Duration::from_millis(100)));
+        assert_eq!(limiter.check_key("conn", now), RateDecision::Wait(Duration::from_millis(200)));
+        assert_eq!(limiter.check_key("other", now), RateDecision::Allow);
+        assert_eq!(limiter.check_key("conn", now + Duration::from_secs(1)), RateDecision::Allow);
+    }
+
+    #[test]
+    fn test_least_recently_used_bucket_is_evicted() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            messages_per_second: 1,
+            burst_size: 1,
+            ..Default::default()
+        })
+        .with_max_buckets(2);
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_key("a", now), RateDecision::Allow);
+        assert_eq!(limiter.check_key("b", now), RateDecision::Allow);
+        assert_eq!(limiter.check_key("a", now), RateDecision::Reject);
+        // "b" was used least recently, so "c" takes its place
+        assert_eq!(limiter.check_key("c", now), RateDecision::Allow);
+        assert_eq!(limiter.buckets.lock().unwrap().by_key.len(), 2);
+        assert_eq!(limiter.check_key("a", now), RateDecision::Reject);
+        assert_eq!(limiter.check_key("b", now), RateDecision::Allow);
+    }
+
+    #[test]
+    fn test_key_strategies() {
+        let mut connection = ConnectionInfo::new("10.0.0.7:51234");
+        let message = Message::text("hi").with_header("x-tenant", "acme").with_target("room-1");
+        let key = |strategy| {
+            RateLimiter::new(RateLimitConfig {
+                key_strategy: strategy,
+                ..Default::default()
+            })
+            .key(&connection, &message)
+        };
+
+        assert_eq!(key(RateLimitKey::Connection), connection.id);
+        assert_eq!(key(RateLimitKey::IP), "10.0.0.7");
+        assert_eq!(key(RateLimitKey::Header("x-tenant".into())), "acme");
+        assert_eq!(key(RateLimitKey::Path), "room-1");
+        assert_eq!(key(RateLimitKey::User), connection.id);
+        connection.set_user_id("alice");
+        assert_eq!(
+            RateLimiter::new(RateLimitConfig {
+                key_strategy: RateLimitKey::User,
+                ..Default::default()
+            })
+            .key(&connection, &message),
+            "alice"
+        );
+    }
+}
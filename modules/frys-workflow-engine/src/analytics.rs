@@ -474,6 +474,109 @@ impl PredictiveAnalyzer {
     }
 }
 
+/// Per-node execution samples aggregated across workflow runs
+#[derive(Debug, Clone, Default)]
+pub struct NodeMetricsCollector {
+    /// Samples keyed by workflow ID, then node ID
+    samples: BTreeMap<String, BTreeMap<String, Vec<NodeExecution>>>,
+}
+
+impl NodeMetricsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the node timings of a finished execution. Nodes that never
+    /// ran (skipped after an upstream failure) are ignored.
+    pub fn record_execution(&mut self, workflow_id: &str, result: &ExecutionResult) {
+        let nodes = self.samples.entry(workflow_id.to_string()).or_default();
+
+        for node_result in result.node_results.values() {
+            let success = match node_result.status {
+                crate::core::ExecutionStatus::Completed => true,
+                crate::core::ExecutionStatus::Failed | crate::core::ExecutionStatus::Timeout => false,
+                _ => continue,
+            };
+
+            let duration_ms = node_result
+                .ended_at
+                .map(|end| end.saturating_sub(node_result.started_at))
+                .unwrap_or(0);
+
+            nodes.entry(node_result.node_id.clone()).or_default().push(NodeExecution {
+                node_id: node_result.node_id.clone(),
+                duration_ms,
+                success,
+                error_message: node_result.error.clone().unwrap_or_default(),
+            });
+        }
+    }
+
+    /// Timing statistics for every node of a workflow
+    pub fn node_stats(&self, workflow_id: &str) -> Vec<NodeTimingStats> {
+        self.samples
+            .get(workflow_id)
+            .map(|nodes| {
+                nodes
+                    .iter()
+                    .filter(|(_, samples)| !samples.is_empty())
+                    .map(|(node_id, samples)| NodeTimingStats::from_samples(node_id, samples))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Nodes ranked slowest first by average duration, then p95 duration,
+    /// then failure rate
+    pub fn bottlenecks(&self, workflow_id: &str) -> Vec<NodeTimingStats> {
+        let mut stats = self.node_stats(workflow_id);
+        stats.sort_by(|a, b| {
+            b.average_duration_ms
+                .partial_cmp(&a.average_duration_ms)
+                .unwrap_or(core::cmp::Ordering::Equal)
+                .then(b.p95_duration_ms.cmp(&a.p95_duration_ms))
+                .then(
+                    b.failure_rate
+                        .partial_cmp(&a.failure_rate)
+                        .unwrap_or(core::cmp::Ordering::Equal),
+                )
+        });
+        stats
+    }
+}
+
+/// Aggregated timing statistics for a single node
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeTimingStats {
+    pub node_id: String,
+    pub executions: usize,
+    pub average_duration_ms: f64,
+    pub p95_duration_ms: u64,
+    pub max_duration_ms: u64,
+    pub failure_rate: f64,
+}
+
+impl NodeTimingStats {
+    fn from_samples(node_id: &str, samples: &[NodeExecution]) -> Self {
+        let mut durations: Vec<u64> = samples.iter().map(|s| s.duration_ms).collect();
+        durations.sort_unstable();
+
+        let executions = samples.len();
+        let failures = samples.iter().filter(|s| !s.success).count();
+        // Nearest-rank percentile
+        let p95_index = ((executions as f64 * 0.95).ceil() as usize).max(1) - 1;
+
+        Self {
+            node_id: node_id.to_string(),
+            executions,
+            average_duration_ms: durations.iter().sum::<u64>() as f64 / executions as f64,
+            p95_duration_ms: durations[p95_index],
+            max_duration_ms: durations[executions - 1],
+            failure_rate: failures as f64 / executions as f64,
+        }
+    }
+}
+
 // Analysis data structures
 #[derive(Debug, Clone)]
 pub struct WorkflowExecution {
@@ -710,6 +813,25 @@ mod tests {
         assert!(bottlenecks.is_empty());
     }
 
+    #[test]
+    fn test_node_timing_stats() {
+        let samples: Vec<NodeExecution> = (1..=20)
+            .map(|i| NodeExecution {
+                node_id: "n".to_string(),
+                duration_ms: i * 10,
+                success: i % 4 != 0,
+                error_message: String::new(),
+            })
+            .collect();
+
+        let stats = NodeTimingStats::from_samples("n", &samples);
+        assert_eq!(stats.executions, 20);
+        assert_eq!(stats.average_duration_ms, 105.0);
+        assert_eq!(stats.p95_duration_ms, 190);
+        assert_eq!(stats.max_duration_ms, 200);
+        assert_eq!(stats.failure_rate, 0.25);
+    }
+
     #[test]
     fn test_optimization_recommender() {
         let recommender = OptimizationRecommender::new();
@@ -0,0 +1,295 @@
+//! Evaluation of edge conditions
+//!
+//! `WorkflowCondition::Expression` edges are traversed when their
+//! expression holds for the source node's output. Expressions compare
+//! fields of the output with literals:
+//!
+//! ```text
+//! output.accuracy > 0.9 && output.label == "cat"
+//! output.scores[0] >= 10 || !output.approved
+//! ```
+//!
+//! Paths start at `output` and step into objects with `.field` and into
+//! arrays with `[index]`; a missing field is `null`. Comparisons are `==`,
+//! `!=`, `<`, `<=`, `>` and `>=`, combined with `&&`, `||`, `!` and
+//! parentheses. An operand on its own is tested for truthiness.
+
+use crate::core::{ExecutionStatus, WorkflowCondition, WorkflowData};
+use crate::{Result, WorkflowError};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+impl WorkflowCondition {
+    /// Whether an edge with this condition is traversed once its source
+    /// node finished with `status` and `output`.
+    ///
+    /// `Custom` conditions are not understood by the executor and never
+    /// match.
+    pub fn evaluate(&self, status: &ExecutionStatus, output: &WorkflowData) -> Result<bool> {
+        let succeeded = *status == ExecutionStatus::Completed;
+        match self {
+            WorkflowCondition::Always => Ok(matches!(
+                status,
+                ExecutionStatus::Completed | ExecutionStatus::Failed | ExecutionStatus::Timeout
+            )),
+            WorkflowCondition::OnSuccess => Ok(succeeded),
+            WorkflowCondition::OnFailure => Ok(matches!(status, ExecutionStatus::Failed | ExecutionStatus::Timeout)),
+            WorkflowCondition::Expression(expression) => Ok(succeeded && evaluate_expression(expression, output)?),
+            WorkflowCondition::Custom(_) => Ok(false),
+        }
+    }
+}
+
+/// Evaluate a condition expression against a node output
+pub fn evaluate_expression(expression: &str, output: &WorkflowData) -> Result<bool> {
+    let invalid = |reason: String| WorkflowError::InvalidWorkflow {
+        reason: alloc::format!("invalid condition '{}': {}", expression, reason),
+    };
+
+    let tokens = tokenize(expression).map_err(invalid)?;
+    let mut parser = Parser { tokens, position: 0, output };
+    let value = parser.or().map_err(invalid)?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(value),
+        Some(token) => Err(invalid(alloc::format!("unexpected {:?}", token))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 14] = ["==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")", ".", "[", "]"];
+
+fn tokenize(expression: &str) -> core::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..].find(c).ok_or("unterminated string")? + 1;
+            tokens.push(Token::Str(rest[1..end].into()));
+            rest = &rest[end + 1..];
+        } else if c.is_ascii_digit() || c == '-' {
+            let end = rest[1..]
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .map_or(rest.len(), |i| i + 1);
+            let number = rest[..end].parse().map_err(|_| alloc::format!("bad number '{}'", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].into()));
+            rest = &rest[end..];
+        } else {
+            return Err(alloc::format!("unexpected character '{}'", c));
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent evaluator over the token stream
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    output: &'a WorkflowData,
+}
+
+impl Parser<'_> {
+    fn eat(&mut self, op: &str) -> bool {
+        let matched = matches!(self.tokens.get(self.position), Some(Token::Op(next)) if *next == op);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn next(&mut self) -> core::result::Result<Token, String> {
+        let token = self.tokens.get(self.position).cloned().ok_or("unexpected end of expression")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn or(&mut self) -> core::result::Result<bool, String> {
+        let mut value = self.and()?;
+        while self.eat("||") {
+            value |= self.and()?;
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> core::result::Result<bool, String> {
+        let mut value = self.unary()?;
+        while self.eat("&&") {
+            value &= self.unary()?;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> core::result::Result<bool, String> {
+        if self.eat("!") {
+            return Ok(!self.unary()?);
+        }
+        if self.eat("(") {
+            let value = self.or()?;
+            if !self.eat(")") {
+                return Err("missing ')'".into());
+            }
+            return Ok(value);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> core::result::Result<bool, String> {
+        let left = self.operand()?;
+        let op = match self.tokens.get(self.position) {
+            Some(Token::Op(op @ ("==" | "!=" | "<" | "<=" | ">" | ">="))) => *op,
+            _ => return Ok(truthy(&left)),
+        };
+        self.position += 1;
+        let right = self.operand()?;
+
+        Ok(match op {
+            "==" => equal(&left, &right),
+            "!=" => !equal(&left, &right),
+            _ => match compare(&left, &right) {
+                Some(ordering) => match op {
+                    "<" => ordering.is_lt(),
+                    "<=" => ordering.is_le(),
+                    ">" => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+                // Values of different kinds are not ordered
+                None => false,
+            },
+        })
+    }
+
+    fn operand(&mut self) -> core::result::Result<WorkflowData, String> {
+        match self.next()? {
+            Token::Number(number) => Ok(WorkflowData::Float(number)),
+            Token::Str(string) => Ok(WorkflowData::String(string)),
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Ok(WorkflowData::Bool(true)),
+                "false" => Ok(WorkflowData::Bool(false)),
+                "null" => Ok(WorkflowData::Null),
+                "output" => self.path(),
+                other => Err(alloc::format!("unknown variable '{}', paths start at 'output'", other)),
+            },
+            Token::Op(op) => Err(alloc::format!("unexpected '{}'", op)),
+        }
+    }
+
+    fn path(&mut self) -> core::result::Result<WorkflowData, String> {
+        let mut value = Some(self.output);
+        loop {
+            if self.eat(".") {
+                let Token::Ident(field) = self.next()? else {
+                    return Err("expected a field name after '.'".into());
+                };
+                value = match value {
+                    Some(WorkflowData::Object(fields)) => fields.get(&field),
+                    _ => None,
+                };
+            } else if self.eat("[") {
+                let Token::Number(index) = self.next()? else {
+                    return Err("expected an index after '['".into());
+                };
+                if !self.eat("]") {
+                    return Err("missing ']'".into());
+                }
+                value = match value {
+                    Some(WorkflowData::Array(items)) if index >= 0.0 => items.get(index as usize),
+                    _ => None,
+                };
+            } else {
+                return Ok(value.cloned().unwrap_or(WorkflowData::Null));
+            }
+        }
+    }
+}
+
+fn number(value: &WorkflowData) -> Option<f64> {
+    match value {
+        WorkflowData::Int(n) => Some(*n as f64),
+        WorkflowData::Float(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn equal(left: &WorkflowData, right: &WorkflowData) -> bool {
+    match (number(left), number(right)) {
+        (Some(l), Some(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &WorkflowData, right: &WorkflowData) -> Option<core::cmp::Ordering> {
+    match (left, right) {
+        (WorkflowData::String(l), WorkflowData::String(r)) => Some(l.cmp(r)),
+        _ => number(left)?.partial_cmp(&number(right)?),
+    }
+}
+
+fn truthy(value: &WorkflowData) -> bool {
+    match value {
+        WorkflowData::Null => false,
+        WorkflowData::Bool(b) => *b,
+        WorkflowData::Int(n) => *n != 0,
+        WorkflowData::Float(n) => *n != 0.0,
+        WorkflowData::String(s) => !s.is_empty(),
+        WorkflowData::Bytes(b) => !b.is_empty(),
+        WorkflowData::Array(items) => !items.is_empty(),
+        WorkflowData::Object(fields) => !fields.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output() -> WorkflowData {
+        WorkflowData::Object(alloc::collections::BTreeMap::from([
+            ("accuracy".into(), WorkflowData::Float(0.93)),
+            ("label".into(), WorkflowData::String("cat".into())),
+            ("scores".into(), WorkflowData::Array(alloc::vec![WorkflowData::Int(12), WorkflowData::Int(3)])),
+            ("approved".into(), WorkflowData::Bool(false)),
+        ]))
+    }
+
+    #[test]
+    fn test_expressions() {
+        let output = output();
+        for (expression, expected) in [
+            ("output.accuracy > 0.9", true),
+            ("output.accuracy <= 0.9", false),
+            ("output.label == 'cat' && output.scores[0] >= 12", true),
+            ("output.scores[1] == 3", true),
+            ("!output.approved || output.missing > 1", true),
+            ("(output.approved || output.accuracy < 0.5) && true", false),
+            ("output.missing == null", true),
+            ("output.label > 1", false),
+        ] {
+            assert_eq!(evaluate_expression(expression, &output).unwrap(), expected, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expression in ["accuracy > 0.9", "output.accuracy >", "(output.accuracy > 1", "output.label == 'cat"] {
+            assert!(matches!(
+                evaluate_expression(expression, &output()),
+                Err(WorkflowError::InvalidWorkflow { .. })
+            ));
+        }
+    }
+}
@@ -14,6 +14,7 @@ pub type NodeId = alloc::string::String;
 
 /// Workflow definition
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct Workflow {
     /// Unique workflow identifier
     pub id: WorkflowId,
@@ -45,57 +46,81 @@ impl Workflow {
         // Check node dependencies
         self.validate_dependencies()?;
 
+        // Check map nodes fan out over an upstream output
+        self.validate_map_sources()?;
+
         // Check for orphaned nodes
         self.check_orphaned_nodes()?;
 
         Ok(())
     }
 
-    /// Detect cycles in workflow DAG
-    fn detect_cycles(&self) -> Result<()> {
-        let mut visited = alloc::collections::BTreeSet::new();
-        let mut recursion_stack = alloc::collections::BTreeSet::new();
-
-        for node_id in self.nodes.keys() {
-            if self.has_cycle(node_id, &mut visited, &mut recursion_stack) {
-                return Err(WorkflowError::CyclicDependency {
-                    nodes: recursion_stack.into_iter().collect(),
-                });
-            }
+    /// Fail with the nodes of a cycle if the workflow has one. Both
+    /// edges and declared dependencies count as connections.
+    pub fn detect_cycles(&self) -> Result<()> {
+        match self.find_cycle() {
+            Some(nodes) => Err(WorkflowError::CyclicDependency { nodes }),
+            None => Ok(()),
         }
-
-        Ok(())
     }
 
-    /// Check for cycles using DFS
-    fn has_cycle(
-        &self,
-        node_id: &NodeId,
-        visited: &mut alloc::collections::BTreeSet<NodeId>,
-        recursion_stack: &mut alloc::collections::BTreeSet<NodeId>,
-    ) -> bool {
-        if recursion_stack.contains(node_id) {
-            return true;
-        }
+    /// Nodes of a cycle in connection order, starting from the node where
+    /// the depth-first search re-entered it
+    pub fn find_cycle(&self) -> Option<alloc::vec::Vec<NodeId>> {
+        let successors = self.successors();
+        let mut finished = alloc::collections::BTreeSet::new();
+
+        for start in self.nodes.keys() {
+            if finished.contains(start) {
+                continue;
+            }
 
-        if visited.contains(node_id) {
-            return false;
+            // Iterative DFS; `path` holds the nodes on the current branch
+            // together with the index of their next successor to visit
+            let mut path: alloc::vec::Vec<(&NodeId, usize)> = alloc::vec![(start, 0)];
+            while let Some((node_id, next)) = path.last_mut() {
+                let Some(successor) = successors[*node_id].get(*next).copied() else {
+                    finished.insert(*node_id);
+                    path.pop();
+                    continue;
+                };
+                *next += 1;
+
+                if let Some(position) = path.iter().position(|(id, _)| *id == successor) {
+                    return Some(path[position..].iter().map(|(id, _)| (*id).clone()).collect());
+                }
+                if !finished.contains(successor) {
+                    path.push((successor, 0));
+                }
+            }
         }
 
-        visited.insert(node_id.clone());
-        recursion_stack.insert(node_id.clone());
+        None
+    }
 
-        // Check all outgoing edges
-        for edge in &self.edges {
-            if edge.from == *node_id {
-                if self.has_cycle(&edge.to, visited, recursion_stack) {
-                    return true;
+    /// Downstream nodes of every node, from edges and declared
+    /// dependencies. Connections to unknown nodes are ignored.
+    fn successors(&self) -> alloc::collections::BTreeMap<&NodeId, alloc::vec::Vec<&NodeId>> {
+        let mut successors: alloc::collections::BTreeMap<&NodeId, alloc::vec::Vec<&NodeId>> =
+            self.nodes.keys().map(|id| (id, alloc::vec::Vec::new())).collect();
+        let connections = self
+            .edges
+            .iter()
+            .map(|edge| (&edge.from, &edge.to))
+            .chain(self.nodes.values().flat_map(|node| node.dependencies.iter().map(move |dependency| (dependency, &node.id))));
+
+        for (from, to) in connections {
+            if !self.nodes.contains_key(to) {
+                continue;
+            }
+            if let Some(next) = successors.get_mut(from) {
+                if !next.contains(&to) {
+                    next.push(to);
                 }
             }
         }
 
-        recursion_stack.remove(node_id);
-        false
+        successors
     }
 
     /// Validate node dependencies
@@ -114,6 +139,38 @@ impl Workflow {
         Ok(())
     }
 
+    /// Check that every map node's array comes from a node upstream of it,
+    /// so it has been produced by the time the map node runs
+    fn validate_map_sources(&self) -> Result<()> {
+        let successors = self.successors();
+        for (node_id, node) in &self.nodes {
+            let NodeType::Map { over, .. } = &node.node_type else {
+                continue;
+            };
+            let mut stack: alloc::vec::Vec<&NodeId> = alloc::vec![&over.node_id];
+            let mut seen = alloc::collections::BTreeSet::new();
+            let mut upstream = false;
+            while let Some(current) = stack.pop() {
+                let Some(next) = successors.get(current) else {
+                    continue;
+                };
+                if next.contains(&node_id) {
+                    upstream = true;
+                    break;
+                }
+                stack.extend(next.iter().copied().filter(|id| seen.insert(*id)));
+            }
+            if !upstream {
+                return Err(WorkflowError::DependencyResolutionFailed {
+                    node_id: node_id.clone(),
+                    dependency: over.node_id.clone(),
+                    reason: "map source is not upstream of the map node".into(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Check for orphaned nodes (not connected to anything)
     fn check_orphaned_nodes(&self) -> Result<()> {
         let mut connected_nodes = alloc::collections::BTreeSet::new();
@@ -137,52 +194,32 @@ impl Workflow {
         Ok(())
     }
 
-    /// Get workflow execution order (topological sort)
+    /// Get workflow execution order (topological sort). Nodes whose
+    /// predecessors are all placed come first, ties broken by node ID.
     pub fn execution_order(&self) -> Result<alloc::vec::Vec<NodeId>> {
-        let mut result = alloc::vec::Vec::new();
-        let mut visited = alloc::collections::BTreeSet::new();
-        let mut temp_marked = alloc::collections::BTreeSet::new();
-
-        for node_id in self.nodes.keys() {
-            if !visited.contains(node_id) {
-                self.topological_sort(node_id, &mut visited, &mut temp_marked, &mut result)?;
-            }
-        }
-
-        result.reverse(); // Reverse to get correct execution order
-        Ok(result)
-    }
+        self.detect_cycles()?;
 
-    /// Topological sort helper
-    fn topological_sort(
-        &self,
-        node_id: &NodeId,
-        visited: &mut alloc::collections::BTreeSet<NodeId>,
-        temp_marked: &mut alloc::collections::BTreeSet<NodeId>,
-        result: &mut alloc::vec::Vec<NodeId>,
-    ) -> Result<()> {
-        if temp_marked.contains(node_id) {
-            return Err(WorkflowError::CyclicDependency {
-                nodes: temp_marked.iter().cloned().collect(),
-            });
+        let successors = self.successors();
+        let mut pending: alloc::collections::BTreeMap<&NodeId, usize> = self.nodes.keys().map(|id| (id, 0)).collect();
+        for next in successors.values().flatten() {
+            *pending.get_mut(next).expect("successors are workflow nodes") += 1;
         }
 
-        if !visited.contains(node_id) {
-            temp_marked.insert(node_id.clone());
-
-            // Visit all dependencies first
-            for edge in &self.edges {
-                if edge.to == *node_id {
-                    self.topological_sort(&edge.from, visited, temp_marked, result)?;
+        let mut ready: alloc::collections::BTreeSet<&NodeId> =
+            pending.iter().filter(|(_, count)| **count == 0).map(|(id, _)| *id).collect();
+        let mut result = alloc::vec::Vec::with_capacity(self.nodes.len());
+        while let Some(node_id) = ready.pop_first() {
+            result.push(node_id.clone());
+            for next in &successors[node_id] {
+                let count = pending.get_mut(next).expect("successors are workflow nodes");
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(next);
                 }
             }
-
-            temp_marked.remove(node_id);
-            visited.insert(node_id.clone());
-            result.push(node_id.clone());
         }
 
-        Ok(())
+        Ok(result)
     }
 }
 
@@ -286,6 +323,7 @@ impl WorkflowBuilder {
 
 /// Workflow node definition
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorkflowNode {
     /// Node unique identifier
     pub id: NodeId,
@@ -301,6 +339,15 @@ pub struct WorkflowNode {
     pub timeout: Option<Duration>,
     /// Node retry policy
     pub retry_policy: RetryPolicy,
+    /// Whether the node may run again after an interrupted attempt
+    pub idempotent: bool,
+    /// For `Parallel` nodes, how many downstream branches may run at once;
+    /// for `Map` nodes, how many elements are processed at once
+    pub max_parallel: Option<usize>,
+    /// Schema every input the node receives must match
+    pub input_schema: Option<DataSchema>,
+    /// Schema the node's output must match
+    pub output_schema: Option<DataSchema>,
     /// Node metadata
     pub metadata: NodeMetadata,
 }
@@ -316,6 +363,10 @@ impl WorkflowNode {
             dependencies: alloc::vec::Vec::new(),
             timeout: None,
             retry_policy: RetryPolicy::default(),
+            idempotent: false,
+            max_parallel: None,
+            input_schema: None,
+            output_schema: None,
             metadata: NodeMetadata::default(),
         }
     }
@@ -355,10 +406,38 @@ impl WorkflowNode {
         self.retry_policy = policy;
         self
     }
+
+    /// Mark whether the node can safely run again when an execution
+    /// resumes after being interrupted while the node was running
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+
+    /// Bound how many branches fanning out of this node, or elements of a
+    /// map node, run at once; the rest wait until a running one finishes
+    pub fn max_parallel(mut self, limit: usize) -> Self {
+        self.max_parallel = Some(limit);
+        self
+    }
+
+    /// Require the output of each predecessor (or each initial input of an
+    /// entry node) to match `schema` before the node runs
+    pub fn input_schema(mut self, schema: DataSchema) -> Self {
+        self.input_schema = Some(schema);
+        self
+    }
+
+    /// Require the node's output to match `schema` before it is passed on
+    pub fn output_schema(mut self, schema: DataSchema) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
 }
 
 /// Node types
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeType {
     /// Regular task node
     Task,
@@ -366,16 +445,63 @@ pub enum NodeType {
     Decision,
     /// Parallel execution node
     Parallel,
-    /// Sub-workflow node
-    SubWorkflow,
+    /// Runs another workflow as a single step
+    SubWorkflow {
+        /// Workflow to run, as registered with the executor
+        workflow_id: WorkflowId,
+    },
+    /// Runs a handler once per element of an array output by an earlier
+    /// node, producing the array of their outputs
+    Map {
+        /// Array to fan out over
+        over: NodeOutputRef,
+        /// Handler run for each element, as registered with the executor
+        sub_node: NodeId,
+    },
     /// Event waiting node
     Event,
     /// Timer/delay node
     Timer,
 }
 
+/// Output of a node, or a value nested inside it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeOutputRef {
+    /// Node producing the output
+    pub node_id: NodeId,
+    /// Object fields to step into, outermost first
+    pub path: alloc::vec::Vec<alloc::string::String>,
+}
+
+impl NodeOutputRef {
+    /// Refer to the whole output of `node_id`
+    pub fn new(node_id: &str) -> Self {
+        Self {
+            node_id: node_id.into(),
+            path: alloc::vec::Vec::new(),
+        }
+    }
+
+    /// Step into `field` of the value referred to so far
+    pub fn field(mut self, field: &str) -> Self {
+        self.path.push(field.into());
+        self
+    }
+
+    /// Value referred to, if the node has an output and every field on the
+    /// path exists
+    pub fn resolve<'a>(&self, outputs: &'a alloc::collections::BTreeMap<NodeId, WorkflowData>) -> Option<&'a WorkflowData> {
+        self.path.iter().try_fold(outputs.get(&self.node_id)?, |value, field| match value {
+            WorkflowData::Object(fields) => fields.get(field),
+            _ => None,
+        })
+    }
+}
+
 /// Node configuration
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeConfig {
     /// Node-specific parameters
     pub parameters: alloc::collections::BTreeMap<alloc::string::String, WorkflowData>,
@@ -387,6 +513,7 @@ pub struct NodeConfig {
 
 /// Resource requirements for node execution
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResourceRequirement {
     /// Resource type
     pub resource_type: alloc::string::String,
@@ -396,6 +523,7 @@ pub struct ResourceRequirement {
 
 /// Workflow edge (connection between nodes)
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorkflowEdge {
     /// Source node ID
     pub from: NodeId,
@@ -407,6 +535,7 @@ pub struct WorkflowEdge {
 
 /// Workflow execution condition
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum WorkflowCondition {
     /// Always execute
     Always,
@@ -422,6 +551,7 @@ pub enum WorkflowCondition {
 
 /// Workflow data types
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum WorkflowData {
     /// Null value
     Null,
@@ -443,6 +573,7 @@ pub enum WorkflowData {
 
 /// Retry policy for failed nodes
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct RetryPolicy {
     /// Maximum number of retries
     pub max_attempts: u32,
@@ -467,6 +598,7 @@ impl Default for RetryPolicy {
 
 /// Workflow metadata
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorkflowMetadata {
     /// Author
     pub author: alloc::string::String,
@@ -482,6 +614,7 @@ pub struct WorkflowMetadata {
 
 /// Node metadata
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeMetadata {
     /// Description
     pub description: alloc::string::String,
@@ -495,6 +628,7 @@ pub struct NodeMetadata {
 
 /// Workflow execution status
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExecutionStatus {
     /// Execution is pending
     Pending,
@@ -510,10 +644,13 @@ pub enum ExecutionStatus {
     Timeout,
     /// Execution is paused
     Paused,
+    /// Node was not run because no incoming branch condition matched
+    Skipped,
 }
 
 /// Workflow execution result
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExecutionResult {
     /// Execution ID
     pub execution_id: ExecutionId,
@@ -533,6 +670,7 @@ pub struct ExecutionResult {
 
 /// Node execution result
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeResult {
     /// Node ID
     pub node_id: NodeId,
@@ -548,6 +686,8 @@ pub struct NodeResult {
     pub started_at: u64,
     /// End time
     pub ended_at: Option<u64>,
+    /// Execution of the child workflow, for sub-workflow nodes
+    pub sub_execution: Option<alloc::boxed::Box<ExecutionResult>>,
 }
 
 #[cfg(test)]
@@ -618,4 +758,81 @@ mod tests {
         // Should detect cycle
         assert!(workflow.validate().is_err());
     }
+
+    #[test]
+    fn test_acyclic_dag_orders_nodes() {
+        let workflow = Workflow::builder("diamond")
+            .add_node(WorkflowNode::new("fetch"))
+            .add_node(WorkflowNode::new("left"))
+            .add_node(WorkflowNode::new("right"))
+            .add_node(WorkflowNode::new("merge").depends_on("left"))
+            .connect("fetch", "left")
+            .connect("fetch", "right")
+            .connect("right", "merge")
+            .build();
+
+        assert!(workflow.validate().is_ok());
+        assert_eq!(workflow.find_cycle(), None);
+        assert_eq!(workflow.execution_order().unwrap(), vec!["fetch", "left", "right", "merge"]);
+    }
+
+    #[test]
+    fn test_cycle_is_reported() {
+        let workflow = Workflow::builder("loop")
+            .add_node(WorkflowNode::new("start"))
+            .add_node(WorkflowNode::new("a"))
+            .add_node(WorkflowNode::new("b"))
+            .add_node(WorkflowNode::new("c"))
+            .connect("start", "a")
+            .connect("a", "b")
+            .connect("b", "c")
+            .connect("c", "a") // Back-edge
+            .build();
+
+        let cycle = vec!["a".to_string(), "b".into(), "c".into()];
+        assert_eq!(workflow.validate(), Err(WorkflowError::CyclicDependency { nodes: cycle.clone() }));
+        assert_eq!(workflow.execution_order(), Err(WorkflowError::CyclicDependency { nodes: cycle }));
+
+        // A dependency closing the loop counts as well
+        let workflow = Workflow::builder("dependency-loop")
+            .add_node(WorkflowNode::new("a").depends_on("b"))
+            .add_node(WorkflowNode::new("b"))
+            .connect("a", "b")
+            .build();
+        assert_eq!(workflow.find_cycle(), Some(vec!["a".to_string(), "b".into()]));
+    }
+
+    #[test]
+    fn test_map_source_must_be_upstream() {
+        let map = |over: &str| {
+            WorkflowNode::new("double").node_type(NodeType::Map {
+                over: NodeOutputRef::new(over),
+                sub_node: "times-two".into(),
+            })
+        };
+        let workflow = |over: &str| {
+            Workflow::builder("doubling")
+                .add_node(WorkflowNode::new("fetch"))
+                .add_node(WorkflowNode::new("numbers"))
+                .add_node(map(over))
+                .add_node(WorkflowNode::new("sum"))
+                .connect("fetch", "numbers")
+                .connect("numbers", "double")
+                .connect("double", "sum")
+                .build()
+        };
+
+        assert!(workflow("numbers").validate().is_ok());
+        assert!(workflow("fetch").validate().is_ok());
+        for over in ["sum", "double", "missing"] {
+            assert_eq!(
+                workflow(over).validate(),
+                Err(WorkflowError::DependencyResolutionFailed {
+                    node_id: "double".into(),
+                    dependency: over.into(),
+                    reason: "map source is not upstream of the map node".into(),
+                })
+            );
+        }
+    }
 }
@@ -417,6 +417,10 @@ impl WorkflowDesigner {
                     dependencies: vec![],
                     timeout: None,
                     retry_policy: RetryPolicy::default(),
+                    idempotent: false,
+                    max_parallel: None,
+                    input_schema: None,
+                    output_schema: None,
                     metadata: NodeMetadata::default(),
                 }
             }),
@@ -476,8 +480,9 @@ impl WorkflowDesigner {
             name: "cycle_detection".to_string(),
             description: "Detect cycles in workflow".to_string(),
             validate: Box::new(|workflow| {
-                // Implement cycle detection
-                Ok(())
+                workflow
+                    .detect_cycles()
+                    .map_err(|error| ValidationError::Error(error.to_string()))
             }),
         });
 
@@ -0,0 +1,524 @@
+//! Declarative workflow definitions
+//!
+//! [`Workflow::from_dsl`] builds a workflow from a YAML or JSON document
+//! (JSON being a subset of YAML, one parser reads both):
+//!
+//! ```yaml
+//! name: order-pipeline
+//! version: 1.2.0
+//! nodes:
+//!   - id: fetch
+//!     timeout: 30s
+//!     retry: { max_attempts: 5, delay: 500ms }
+//!     parameters: { url: "https://orders.internal/pending" }
+//!   - id: check
+//!     type: decision
+//!     depends_on: [fetch]
+//!   - id: ship
+//!     type: sub_workflow
+//!     workflow: shipping
+//!     idempotent: true
+//!   - id: notify
+//!     type: map
+//!     over: fetch.customers
+//!     sub_node: send-email
+//!     max_parallel: 8
+//!     depends_on: [fetch]
+//! edges:
+//!   - { from: check, to: ship, condition: "output.approved" }
+//! ```
+//!
+//! Node types are `task` (the default), `decision`, `parallel`, `event`,
+//! `timer`, `sub_workflow`, which names the workflow to run in `workflow`,
+//! and `map`, which runs the handler named in `sub_node` once per element
+//! of the array at `over`: an upstream node id, optionally followed by
+//! dotted fields of its output. `depends_on` connects a node to its upstream nodes
+//! unconditionally; `edges` add connections with a `condition` of
+//! `always`, `on_success`, `on_failure` or an expression as understood by
+//! [`WorkflowCondition::evaluate`]. Durations are seconds or a number
+//! suffixed with `ms`, `s`, `m` or `h`.
+//!
+//! Errors are [`WorkflowError::DslParseError`]s with the line and column of
+//! the offending value and the path of the field, e.g. `nodes[1].retry`.
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use ::core::time::Duration;
+use serde::Deserialize;
+
+/// Workflow document
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkflowSpec {
+    name: String,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    nodes: Vec<NodeSpec>,
+    #[serde(default)]
+    edges: Vec<EdgeSpec>,
+}
+
+/// Node entry
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NodeSpec {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default, rename = "type")]
+    node_type: NodeTypeSpec,
+    /// Workflow run by a `sub_workflow` node
+    #[serde(default)]
+    workflow: Option<String>,
+    /// Array a `map` node fans out over, as `node.field.field`
+    #[serde(default)]
+    over: Option<String>,
+    /// Handler a `map` node runs per element
+    #[serde(default)]
+    sub_node: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    #[serde(default)]
+    timeout: Option<DurationSpec>,
+    #[serde(default)]
+    retry: Option<RetrySpec>,
+    #[serde(default)]
+    idempotent: bool,
+    #[serde(default)]
+    max_parallel: Option<usize>,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    parameters: BTreeMap<String, serde_yaml::Value>,
+}
+
+/// Node type names
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NodeTypeSpec {
+    #[default]
+    Task,
+    Decision,
+    Parallel,
+    SubWorkflow,
+    Map,
+    Event,
+    Timer,
+}
+
+/// Retry policy entry; unset fields keep the [`RetryPolicy`] defaults
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RetrySpec {
+    #[serde(default)]
+    max_attempts: Option<u32>,
+    #[serde(default)]
+    delay: Option<DurationSpec>,
+    #[serde(default)]
+    backoff_multiplier: Option<f64>,
+    #[serde(default)]
+    max_delay: Option<DurationSpec>,
+}
+
+/// Edge entry
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EdgeSpec {
+    from: String,
+    to: String,
+    #[serde(default)]
+    condition: Option<String>,
+}
+
+/// Duration as seconds or a suffixed number such as `500ms`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DurationSpec {
+    Seconds(u64),
+    Text(String),
+}
+
+impl DurationSpec {
+    fn parse(&self) -> ::core::result::Result<Duration, String> {
+        let text = match self {
+            DurationSpec::Seconds(seconds) => return Ok(Duration::from_secs(*seconds)),
+            DurationSpec::Text(text) => text.trim(),
+        };
+        let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        let (value, unit) = text.split_at(split);
+        let value: u64 = value
+            .parse()
+            .map_err(|_| alloc::format!("invalid duration '{}'", text))?;
+        let seconds = |scale: u64| {
+            value
+                .checked_mul(scale)
+                .map(Duration::from_secs)
+                .ok_or_else(|| alloc::format!("duration '{}' is too long", text))
+        };
+        match unit.trim() {
+            "ms" => Ok(Duration::from_millis(value)),
+            "" | "s" => seconds(1),
+            "m" => seconds(60),
+            "h" => seconds(3600),
+            _ => Err(alloc::format!("invalid duration '{}', expected a unit of ms, s, m or h", text)),
+        }
+    }
+}
+
+impl Workflow {
+    /// Parse a workflow from a YAML or JSON definition and validate it.
+    ///
+    /// See the [module documentation](crate::dsl) for the format.
+    pub fn from_dsl(source: &str) -> Result<Self> {
+        let spec: WorkflowSpec = serde_yaml::from_str(source).map_err(|e| {
+            let (line, column) = e.location().map_or((0, 0), |location| (location.line(), location.column()));
+            let message = e.to_string();
+            // The location is reported separately
+            let reason = match message.rfind(" at line ") {
+                Some(position) if line > 0 => message[..position].into(),
+                _ => message,
+            };
+            WorkflowError::DslParseError { line, column, reason }
+        })?;
+
+        let mut builder = Workflow::builder(&spec.name);
+        if let Some(id) = spec.id {
+            builder = builder.id(id);
+        }
+        if let Some(description) = &spec.description {
+            builder = builder.description(description);
+        }
+        if let Some(version) = &spec.version {
+            builder = builder.version(version);
+        }
+
+        let ids: Vec<&str> = spec.nodes.iter().map(|node| node.id.as_str()).collect();
+        for (index, node) in spec.nodes.iter().enumerate() {
+            let error = |field: &str, reason: String| {
+                let (line, column) = locate(source, "id", &node.id);
+                WorkflowError::DslParseError {
+                    line,
+                    column,
+                    reason: alloc::format!("nodes[{}].{}: {}", index, field, reason),
+                }
+            };
+
+            if ids[..index].contains(&node.id.as_str()) {
+                return Err(error("id", alloc::format!("duplicate node '{}'", node.id)));
+            }
+            for (position, dependency) in node.depends_on.iter().enumerate() {
+                if !ids.contains(&dependency.as_str()) {
+                    return Err(error(
+                        &alloc::format!("depends_on[{}]", position),
+                        alloc::format!("unknown node '{}'", dependency),
+                    ));
+                }
+            }
+
+            let is_map = matches!(node.node_type, NodeTypeSpec::Map);
+            let type_fields = [
+                ("workflow", &node.workflow, matches!(node.node_type, NodeTypeSpec::SubWorkflow), "sub_workflow"),
+                ("over", &node.over, is_map, "map"),
+                ("sub_node", &node.sub_node, is_map, "map"),
+            ];
+            for (field, value, allowed, kind) in type_fields {
+                match value {
+                    Some(_) if !allowed => return Err(error(field, alloc::format!("only allowed on {} nodes", kind))),
+                    None if allowed => return Err(error(field, alloc::format!("required for {} nodes", kind))),
+                    _ => {}
+                }
+            }
+
+            let node_type = match (&node.node_type, &node.workflow, &node.over, &node.sub_node) {
+                (NodeTypeSpec::SubWorkflow, Some(workflow_id), _, _) => NodeType::SubWorkflow {
+                    workflow_id: workflow_id.clone(),
+                },
+                (NodeTypeSpec::Map, _, Some(over), Some(sub_node)) => {
+                    let mut path = over.split('.');
+                    let source = path.next().unwrap_or_default();
+                    if !ids.contains(&source) {
+                        return Err(error("over", alloc::format!("unknown node '{}'", source)));
+                    }
+                    NodeType::Map {
+                        over: path.fold(NodeOutputRef::new(source), NodeOutputRef::field),
+                        sub_node: sub_node.clone(),
+                    }
+                }
+                (NodeTypeSpec::Decision, ..) => NodeType::Decision,
+                (NodeTypeSpec::Parallel, ..) => NodeType::Parallel,
+                (NodeTypeSpec::Event, ..) => NodeType::Event,
+                (NodeTypeSpec::Timer, ..) => NodeType::Timer,
+                _ => NodeType::Task,
+            };
+
+            let mut workflow_node = WorkflowNode::new(&node.id)
+                .node_type(node_type)
+                .idempotent(node.idempotent)
+                .config(NodeConfig {
+                    parameters: node
+                        .parameters
+                        .iter()
+                        .map(|(name, value)| (name.clone(), to_workflow_data(value)))
+                        .collect(),
+                    priority: node.priority,
+                    required_resources: Vec::new(),
+                });
+            if let Some(name) = &node.name {
+                workflow_node = workflow_node.name(name);
+            }
+            if let Some(timeout) = &node.timeout {
+                workflow_node = workflow_node.timeout(timeout.parse().map_err(|reason| error("timeout", reason))?);
+            }
+            if let Some(retry) = &node.retry {
+                let defaults = RetryPolicy::default();
+                let duration = |field: &str, value: &Option<DurationSpec>, default: Duration| {
+                    value
+                        .as_ref()
+                        .map_or(Ok(default), DurationSpec::parse)
+                        .map_err(|reason| error(&alloc::format!("retry.{}", field), reason))
+                };
+                workflow_node = workflow_node.retry_policy(RetryPolicy {
+                    max_attempts: retry.max_attempts.unwrap_or(defaults.max_attempts),
+                    delay: duration("delay", &retry.delay, defaults.delay)?,
+                    backoff_multiplier: retry.backoff_multiplier.unwrap_or(defaults.backoff_multiplier),
+                    max_delay: duration("max_delay", &retry.max_delay, defaults.max_delay)?,
+                });
+            }
+            if let Some(limit) = node.max_parallel {
+                workflow_node = workflow_node.max_parallel(limit);
+            }
+            for dependency in &node.depends_on {
+                workflow_node = workflow_node.depends_on(dependency);
+                builder = builder.connect(dependency, &node.id);
+            }
+            builder = builder.add_node(workflow_node);
+        }
+
+        for (index, edge) in spec.edges.iter().enumerate() {
+            for (field, id) in [("from", &edge.from), ("to", &edge.to)] {
+                if !ids.contains(&id.as_str()) {
+                    let (line, column) = locate(source, field, id);
+                    return Err(WorkflowError::DslParseError {
+                        line,
+                        column,
+                        reason: alloc::format!("edges[{}].{}: unknown node '{}'", index, field, id),
+                    });
+                }
+            }
+            builder = match edge.condition.as_deref().map(str::trim) {
+                None => builder.connect(&edge.from, &edge.to),
+                Some("on_success") => builder.connect_with_condition(&edge.from, &edge.to, WorkflowCondition::OnSuccess),
+                Some("always") => builder.connect_with_condition(&edge.from, &edge.to, WorkflowCondition::Always),
+                Some("on_failure") => builder.connect_with_condition(&edge.from, &edge.to, WorkflowCondition::OnFailure),
+                Some(expression) => {
+                    builder.connect_with_condition(&edge.from, &edge.to, WorkflowCondition::Expression(expression.into()))
+                }
+            };
+        }
+
+        let workflow = builder.build();
+        workflow.validate()?;
+        Ok(workflow)
+    }
+}
+
+/// Convert a parameter value
+fn to_workflow_data(value: &serde_yaml::Value) -> WorkflowData {
+    match value {
+        serde_yaml::Value::Null => WorkflowData::Null,
+        serde_yaml::Value::Bool(value) => WorkflowData::Bool(*value),
+        serde_yaml::Value::Number(number) => match number.as_i64() {
+            Some(value) => WorkflowData::Int(value),
+            None => WorkflowData::Float(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_yaml::Value::String(value) => WorkflowData::String(value.clone()),
+        serde_yaml::Value::Sequence(items) => WorkflowData::Array(items.iter().map(to_workflow_data).collect()),
+        serde_yaml::Value::Mapping(map) => WorkflowData::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        serde_yaml::Value::String(key) => key.clone(),
+                        other => serde_yaml::to_string(other).unwrap_or_default().trim().into(),
+                    };
+                    (key, to_workflow_data(value))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => to_workflow_data(&tagged.value),
+    }
+}
+
+/// 1-based line and column of the first place `value` appears as the
+/// value of `key`, or `(0, 0)` if it cannot be found
+fn locate(source: &str, key: &str, value: &str) -> (usize, usize) {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    for (offset, _) in source.match_indices(value) {
+        let Some(before) = source[..offset].trim_end_matches(['"', '\'']).trim_end().strip_suffix(':') else {
+            continue;
+        };
+        let Some(preceding) = before.trim_end().trim_end_matches(['"', '\'']).strip_suffix(key) else {
+            continue;
+        };
+        let whole_key = !preceding.ends_with(is_word);
+        let whole_value = !source[offset + value.len()..].starts_with(is_word);
+        if whole_key && whole_value {
+            let line_start = source[..offset].rfind('\n').map_or(0, |position| position + 1);
+            let line = source[..offset].matches('\n').count() + 1;
+            return (line, source[line_start..offset].chars().count() + 1);
+        }
+    }
+    (0, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIPELINE: &str = r#"
+name: order-pipeline
+version: 1.2.0
+description: Validate, charge and ship orders
+nodes:
+  - id: fetch
+    name: Fetch pending orders
+    timeout: 30s
+    retry:
+      max_attempts: 5
+      delay: 500ms
+    parameters:
+      url: https://orders.internal/pending
+      batch: 50
+  - id: check
+    type: decision
+    depends_on: [fetch]
+  - id: charge
+    depends_on: [check]
+    idempotent: true
+  - id: ship
+    type: sub_workflow
+    workflow: shipping
+    depends_on: [charge]
+    timeout: 2m
+  - id: alert
+    type: event
+edges:
+  - from: check
+    to: alert
+    condition: output.risk > 0.8
+  - { from: charge, to: alert, condition: on_failure }
+"#;
+
+    #[test]
+    fn test_parse_multi_node_yaml() {
+        let workflow = Workflow::from_dsl(PIPELINE).unwrap();
+
+        assert_eq!(workflow.name, "order-pipeline");
+        assert_eq!(workflow.version, "1.2.0");
+        assert_eq!(workflow.nodes.len(), 5);
+        assert_eq!(workflow.execution_order().unwrap(), vec!["fetch", "check", "charge", "alert", "ship"]);
+
+        let fetch = &workflow.nodes["fetch"];
+        assert_eq!(fetch.name, "Fetch pending orders");
+        assert_eq!(fetch.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(fetch.retry_policy.max_attempts, 5);
+        assert_eq!(fetch.retry_policy.delay, Duration::from_millis(500));
+        assert_eq!(fetch.retry_policy.max_delay, RetryPolicy::default().max_delay);
+        assert_eq!(fetch.config.parameters["batch"], WorkflowData::Int(50));
+
+        assert_eq!(workflow.nodes["check"].node_type, NodeType::Decision);
+        assert_eq!(workflow.nodes["charge"].dependencies, vec!["check"]);
+        assert!(workflow.nodes["charge"].idempotent);
+        assert_eq!(
+            workflow.nodes["ship"].node_type,
+            NodeType::SubWorkflow {
+                workflow_id: "shipping".into()
+            }
+        );
+        assert_eq!(workflow.nodes["ship"].timeout, Some(Duration::from_secs(120)));
+
+        let edges: Vec<(&str, &str)> = workflow.edges.iter().map(|edge| (edge.from.as_str(), edge.to.as_str())).collect();
+        assert_eq!(edges, vec![("fetch", "check"), ("check", "charge"), ("charge", "ship"), ("check", "alert"), ("charge", "alert")]);
+        assert!(matches!(&workflow.edges[3].condition, Some(WorkflowCondition::Expression(e)) if e == "output.risk > 0.8"));
+        assert!(matches!(workflow.edges[4].condition, Some(WorkflowCondition::OnFailure)));
+    }
+
+    #[test]
+    fn test_parse_map_node() {
+        let source = "name: notify\nnodes:\n  - id: fetch\n  - id: notify\n    type: map\n    over: fetch.customers\n    sub_node: send-email\n    max_parallel: 8\n    depends_on: [fetch]\n";
+        let workflow = Workflow::from_dsl(source).unwrap();
+
+        assert_eq!(
+            workflow.nodes["notify"].node_type,
+            NodeType::Map {
+                over: NodeOutputRef::new("fetch").field("customers"),
+                sub_node: "send-email".into(),
+            }
+        );
+        assert_eq!(workflow.nodes["notify"].max_parallel, Some(8));
+
+        let error = Workflow::from_dsl("name: bad\nnodes:\n  - id: fetch\n  - id: notify\n    type: map\n    over: fetch\n").unwrap_err();
+        assert!(matches!(error, WorkflowError::DslParseError { line: 4, ref reason, .. } if reason == "nodes[1].sub_node: required for map nodes"));
+        let error = Workflow::from_dsl("name: bad\nnodes:\n  - id: fetch\n    over: fetch\n").unwrap_err();
+        assert!(matches!(error, WorkflowError::DslParseError { ref reason, .. } if reason == "nodes[0].over: only allowed on map nodes"));
+        let error = Workflow::from_dsl(&source.replace("over: fetch.", "over: missing.")).unwrap_err();
+        assert!(matches!(error, WorkflowError::DslParseError { ref reason, .. } if reason == "nodes[1].over: unknown node 'missing'"));
+
+        // The array must come from upstream
+        let error = Workflow::from_dsl(&source.replace("    depends_on: [fetch]\n", "")).unwrap_err();
+        assert!(matches!(error, WorkflowError::DependencyResolutionFailed { ref dependency, .. } if dependency == "fetch"));
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let workflow = Workflow::from_dsl(
+            r#"{"name": "etl", "id": "etl-v2", "nodes": [{"id": "extract"}, {"id": "load", "depends_on": ["extract"], "timeout": 90}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(workflow.id, "etl-v2");
+        assert_eq!(workflow.nodes["load"].timeout, Some(Duration::from_secs(90)));
+        assert_eq!(workflow.execution_order().unwrap(), vec!["extract", "load"]);
+    }
+
+    #[test]
+    fn test_malformed_specs_report_location() {
+        // Type error deep inside a node
+        let error = Workflow::from_dsl("name: bad\nnodes:\n  - id: a\n    retry:\n      max_attempts: many\n").unwrap_err();
+        match error {
+            WorkflowError::DslParseError { line, reason, .. } => {
+                assert_eq!(line, 5);
+                assert!(reason.starts_with("nodes[0].retry.max_attempts: invalid type"), "{}", reason);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        // Reference to a node that does not exist
+        let error = Workflow::from_dsl("name: bad\nnodes:\n  - id: a\n  - id: b\n    depends_on: [a, c]\n").unwrap_err();
+        assert_eq!(
+            error,
+            WorkflowError::DslParseError {
+                line: 4,
+                column: 9,
+                reason: "nodes[1].depends_on[1]: unknown node 'c'".into(),
+            }
+        );
+
+        // Misspelled field
+        let error = Workflow::from_dsl("name: bad\nnodes:\n  - id: a\n    timout: 5s\n").unwrap_err();
+        assert!(matches!(error, WorkflowError::DslParseError { line: 4, ref reason, .. } if reason.contains("unknown field `timout`")));
+
+        let error = Workflow::from_dsl("name: bad\nnodes:\n  - id: a\n    timeout: soon\n").unwrap_err();
+        assert!(matches!(error, WorkflowError::DslParseError { line: 3, ref reason, .. } if reason == "nodes[0].timeout: invalid duration 'soon'"));
+
+        let error = Workflow::from_dsl("name: bad\nnodes:\n  - id: a\n    timeout: 18446744073709551615h\n").unwrap_err();
+        assert!(
+            matches!(error, WorkflowError::DslParseError { line: 3, ref reason, .. } if reason == "nodes[0].timeout: duration '18446744073709551615h' is too long")
+        );
+    }
+}
@@ -15,6 +15,12 @@ pub struct WorkflowEngine {
     worker_pool: WorkerPool,
     /// Statistics
     stats: EngineStats,
+    /// Node executor
+    executor: WorkflowExecutor,
+    /// Per-node timing samples
+    node_metrics: std::sync::Mutex<NodeMetricsCollector>,
+    /// Scheduled workflows
+    scheduler: WorkflowScheduler,
 }
 
 impl WorkflowEngine {
@@ -47,6 +53,147 @@ impl WorkflowEngine {
         Ok(execution_id)
     }
 
+    /// Register the handler that executes a node
+    pub fn register_node_handler(&mut self, node_id: &str, handler: alloc::sync::Arc<dyn NodeHandler>) {
+        self.executor.register_handler(node_id, handler);
+    }
+
+    /// Publish lifecycle events of every execution to an event bus
+    pub fn set_event_publisher(&mut self, publisher: alloc::sync::Arc<EventBusPublisher>) {
+        self.executor.set_event_publisher(publisher);
+    }
+
+    /// Make a workflow available to sub-workflow nodes
+    pub fn register_workflow(&mut self, workflow: Workflow) -> Result<()> {
+        workflow.validate()?;
+        self.executor.register_workflow(workflow);
+        Ok(())
+    }
+
+    /// Execute a workflow and wait for it to finish
+    pub async fn run_workflow(&self, workflow: Workflow) -> Result<ExecutionResult> {
+        self.run_workflow_with_priority(workflow, ExecutionPriority::Normal).await
+    }
+
+    /// Execute a workflow at `priority` and wait for it to finish.
+    ///
+    /// The execution waits for a free worker, ahead of lower-priority
+    /// executions. Waiting executions gain a priority level every
+    /// [`EngineConfig::priority_aging`], so a flood of high-priority work
+    /// cannot starve them.
+    pub async fn run_workflow_with_priority(&self, workflow: Workflow, priority: ExecutionPriority) -> Result<ExecutionResult> {
+        workflow.validate()?;
+        self.check_resource_limits(&workflow)?;
+
+        let _worker = self.worker_pool.acquire(priority).await;
+        let execution_id = self.generate_execution_id();
+        self.stats.record_workflow_execution();
+
+        let result = self
+            .executor
+            .execute_with_checkpoints(execution_id, &workflow, ExecutionContext::new(), &self.workflow_store)
+            .await?;
+        self.record_result(&workflow.id, &result);
+
+        Ok(result)
+    }
+
+    /// Resume an interrupted execution from its last checkpoint, running
+    /// only the nodes that had not finished. Nodes that were running when
+    /// it was interrupted run again only if marked idempotent.
+    pub async fn resume_execution(&self, execution_id: &ExecutionId) -> Result<ExecutionResult> {
+        let checkpoint = self.workflow_store.load_checkpoint(execution_id)?;
+        let workflow_id = checkpoint.workflow.id.clone();
+
+        let _worker = self.worker_pool.acquire(ExecutionPriority::Normal).await;
+        let result = self.executor.resume(checkpoint, &self.workflow_store).await?;
+        self.record_result(&workflow_id, &result);
+
+        Ok(result)
+    }
+
+    /// Record the node timings and outcome of a finished execution
+    fn record_result(&self, workflow_id: &WorkflowId, result: &ExecutionResult) {
+        self.node_metrics
+            .lock()
+            .unwrap()
+            .record_execution(workflow_id, result);
+
+        match result.status {
+            ExecutionStatus::Completed => self.stats.record_execution_completed(result.duration.unwrap_or(0)),
+            _ => self.stats.record_execution_failed(),
+        }
+    }
+
+    /// Run a workflow on a schedule, skipping a run that comes due while
+    /// the previous one is still active
+    pub fn schedule_workflow(&self, workflow: Workflow, schedule: Schedule) -> Result<ScheduleId> {
+        self.schedule_workflow_with_overlap(workflow, schedule, OverlapPolicy::Skip)
+    }
+
+    /// Run a workflow on a schedule with the given overlap policy
+    pub fn schedule_workflow_with_overlap(
+        &self,
+        workflow: Workflow,
+        schedule: Schedule,
+        overlap: OverlapPolicy,
+    ) -> Result<ScheduleId> {
+        workflow.validate()?;
+        self.scheduler.add(workflow, schedule, overlap)
+    }
+
+    /// List scheduled workflows
+    pub fn list_schedules(&self) -> alloc::vec::Vec<ScheduleInfo> {
+        self.scheduler.list()
+    }
+
+    /// Cancel a schedule; runs already started finish normally
+    pub fn cancel_schedule(&self, schedule_id: &str) -> bool {
+        self.scheduler.cancel(schedule_id)
+    }
+
+    /// Start an execution for every schedule that is due
+    #[cfg(feature = "async")]
+    pub fn run_due_schedules(self: &alloc::sync::Arc<Self>) -> alloc::vec::Vec<tokio::task::JoinHandle<Result<ExecutionResult>>> {
+        self.scheduler
+            .due_runs()
+            .into_iter()
+            .map(|run| {
+                let engine = self.clone();
+                tokio::spawn(async move {
+                    let result = engine.run_workflow(run.workflow.clone()).await;
+                    // The run stays active until here
+                    drop(run);
+                    result
+                })
+            })
+            .collect()
+    }
+
+    /// Check the schedules every `poll_interval` in a background task and
+    /// start the runs that are due. The task stops once the engine is
+    /// dropped.
+    #[cfg(feature = "async")]
+    pub fn start_scheduler(self: &alloc::sync::Arc<Self>, poll_interval: Duration) -> tokio::task::JoinHandle<()> {
+        let engine = alloc::sync::Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(poll_interval);
+            loop {
+                ticks.tick().await;
+                match engine.upgrade() {
+                    Some(engine) => drop(engine.run_due_schedules()),
+                    None => break,
+                }
+            }
+        })
+    }
+
+    /// Rank the nodes of a workflow by average/p95 duration and failure
+    /// rate across all recorded runs, slowest first
+    pub fn analyze_bottlenecks(&self, workflow_id: &str) -> alloc::vec::Vec<NodeTimingStats> {
+        self.node_metrics.lock().unwrap().bottlenecks(workflow_id)
+    }
+
     /// Get execution status
     pub async fn get_execution_status(&self, execution_id: &ExecutionId) -> Result<ExecutionStatus> {
         self.execution_tracker.get_execution_status(execution_id).await
@@ -67,11 +214,6 @@ impl WorkflowEngine {
         self.execution_tracker.pause_execution(execution_id).await
     }
 
-    /// Resume execution
-    pub async fn resume_execution(&self, execution_id: &ExecutionId) -> Result<bool> {
-        self.execution_tracker.resume_execution(execution_id).await
-    }
-
     /// Store workflow definition
     pub async fn store_workflow(&self, workflow: Workflow) -> Result<()> {
         // Validate before storing
@@ -94,9 +236,13 @@ impl WorkflowEngine {
         self.workflow_store.delete_workflow(workflow_id).await
     }
 
-    /// Get engine statistics
-    pub fn stats(&self) -> &EngineStats {
-        &self.stats
+    /// Get engine statistics, including the executions waiting for a
+    /// worker at each priority
+    pub fn stats(&self) -> EngineStats {
+        EngineStats {
+            queue_depths: self.worker_pool.queue_depths(),
+            ..self.stats.clone()
+        }
     }
 
     /// Get active executions count
@@ -106,8 +252,7 @@ impl WorkflowEngine {
 
     /// Generate unique execution ID
     fn generate_execution_id(&self) -> ExecutionId {
-        // In real implementation, use UUID
-        alloc::format!("exec-{}", current_timestamp())
+        alloc::format!("exec-{}", uuid::Uuid::new_v4())
     }
 
     /// Check resource limits for workflow execution
@@ -135,6 +280,7 @@ impl WorkflowEngine {
 #[derive(Debug)]
 pub struct WorkflowEngineBuilder {
     config: EngineConfig,
+    clock: alloc::sync::Arc<dyn Clock>,
 }
 
 impl WorkflowEngineBuilder {
@@ -142,6 +288,7 @@ impl WorkflowEngineBuilder {
     pub fn new() -> Self {
         Self {
             config: EngineConfig::default(),
+            clock: alloc::sync::Arc::new(SystemClock),
         }
     }
 
@@ -159,6 +306,12 @@ impl WorkflowEngineBuilder {
         self
     }
 
+    /// Set how long a waiting execution takes to gain a priority level
+    pub fn with_priority_aging(mut self, interval: Duration) -> Self {
+        self.config.priority_aging = interval;
+        self
+    }
+
     /// Set execution timeout
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.config.default_timeout = timeout;
@@ -180,12 +333,18 @@ impl WorkflowEngineBuilder {
         self
     }
 
+    /// Set the clock driving scheduled workflows
+    pub fn with_clock(mut self, clock: alloc::sync::Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Build the workflow engine
     pub async fn build(self) -> Result<WorkflowEngine> {
         let workflow_store = if self.config.persistence_enabled {
             #[cfg(feature = "persistence")]
             {
-                WorkflowStore::with_persistence(&self.config.persistence_path.unwrap()).await?
+                WorkflowStore::with_persistence(self.config.persistence_path.as_deref().unwrap_or_default()).await?
             }
             #[cfg(not(feature = "persistence"))]
             {
@@ -200,7 +359,10 @@ impl WorkflowEngineBuilder {
 
         let execution_tracker = ExecutionTracker::new(self.config.persistence_enabled);
 
-        let worker_pool = WorkerPool::new(self.config.worker_count, execution_tracker.clone());
+        let worker_pool = WorkerPool::new(self.config.worker_count, self.config.priority_aging);
+
+        let mut executor = WorkflowExecutor::new();
+        executor.set_timeout(self.config.default_timeout);
 
         let engine = WorkflowEngine {
             config: self.config,
@@ -208,6 +370,9 @@ impl WorkflowEngineBuilder {
             execution_tracker,
             worker_pool,
             stats: EngineStats::default(),
+            executor,
+            node_metrics: std::sync::Mutex::new(NodeMetricsCollector::new()),
+            scheduler: WorkflowScheduler::new(self.clock),
         };
 
         Ok(engine)
@@ -237,6 +402,9 @@ pub struct EngineConfig {
     pub distributed_enabled: bool,
     /// Distributed endpoints
     pub distributed_endpoints: alloc::vec::Vec<alloc::string::String>,
+    /// Time an execution waits for a worker before gaining a priority
+    /// level; zero disables aging
+    pub priority_aging: Duration,
 }
 
 impl Default for EngineConfig {
@@ -249,6 +417,7 @@ impl Default for EngineConfig {
             monitoring_enabled: false,
             distributed_enabled: false,
             distributed_endpoints: alloc::vec::Vec::new(),
+            priority_aging: Duration::from_secs(DEFAULT_PRIORITY_AGING_SECS),
         }
     }
 }
@@ -270,6 +439,8 @@ pub struct EngineStats {
     pub avg_execution_time: f64,
     /// Total execution time
     pub total_execution_time: u64,
+    /// Executions waiting for a worker, by requested priority
+    pub queue_depths: alloc::collections::BTreeMap<ExecutionPriority, usize>,
 }
 
 impl EngineStats {
@@ -415,6 +586,7 @@ impl WorkflowExecution {
                 attempts: 1,
                 started_at: self.started_at,
                 ended_at: Some(current_timestamp()),
+                sub_execution: None,
             };
             node_results.insert(node_id.clone(), node_result);
         }
@@ -446,6 +618,7 @@ pub enum NodeExecutionState {
 
 /// Execution context for sharing data between nodes
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExecutionContext {
     /// Context variables
     variables: alloc::collections::BTreeMap<alloc::string::String, WorkflowData>,
@@ -482,18 +655,81 @@ impl ExecutionContext {
 }
 
 // Placeholder implementations (would be implemented in separate modules)
-#[derive(Debug, Clone)]
-struct WorkflowStore;
+#[derive(Debug, Clone, Default)]
+struct WorkflowStore {
+    /// Latest checkpoint of every unfinished execution
+    checkpoints: alloc::sync::Arc<std::sync::Mutex<alloc::collections::BTreeMap<ExecutionId, ExecutionCheckpoint>>>,
+    /// Database the checkpoints are also written to, so they survive a
+    /// restart
+    #[cfg(feature = "persistence")]
+    db: Option<sled::Db>,
+}
 impl WorkflowStore {
-    fn in_memory() -> Self { Self }
+    fn in_memory() -> Self { Self::default() }
     #[cfg(feature = "persistence")]
-    async fn with_persistence(_path: &str) -> Result<Self> { Ok(Self) }
+    async fn with_persistence(path: &str) -> Result<Self> {
+        let db = sled::open(path).map_err(|e| persistence_error("open", &e))?;
+        Ok(Self { db: Some(db), ..Self::default() })
+    }
     async fn store_workflow(&self, _workflow: Workflow) -> Result<()> { Ok(()) }
     async fn load_workflow(&self, _workflow_id: &WorkflowId) -> Result<Workflow> {
         Err(WorkflowError::WorkflowNotFound { id: _workflow_id.clone() })
     }
     async fn list_workflows(&self) -> Result<alloc::vec::Vec<WorkflowId>> { Ok(vec![]) }
     async fn delete_workflow(&self, _workflow_id: &WorkflowId) -> Result<bool> { Ok(false) }
+    fn load_checkpoint(&self, execution_id: &ExecutionId) -> Result<ExecutionCheckpoint> {
+        if let Some(checkpoint) = self.checkpoints.lock().unwrap_or_else(|e| e.into_inner()).get(execution_id) {
+            return Ok(checkpoint.clone());
+        }
+        // Checkpoints written before a restart are only in the database
+        #[cfg(feature = "persistence")]
+        if let Some(db) = &self.db {
+            if let Some(bytes) = db.get(execution_id).map_err(|e| persistence_error("load checkpoint", &e))? {
+                return serde_json::from_slice(&bytes).map_err(|e| WorkflowError::SerializationError {
+                    operation: "decode checkpoint".into(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+        Err(WorkflowError::ExecutionNotFound { execution_id: execution_id.clone() })
+    }
+}
+
+impl CheckpointStore for WorkflowStore {
+    fn save_checkpoint(&self, checkpoint: ExecutionCheckpoint) -> Result<()> {
+        #[cfg(feature = "persistence")]
+        if let Some(db) = &self.db {
+            let bytes = serde_json::to_vec(&checkpoint).map_err(|e| WorkflowError::SerializationError {
+                operation: "encode checkpoint".into(),
+                reason: e.to_string(),
+            })?;
+            db.insert(checkpoint.execution_id.as_bytes(), bytes).map_err(|e| persistence_error("save checkpoint", &e))?;
+            db.flush().map_err(|e| persistence_error("save checkpoint", &e))?;
+        }
+        self.checkpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(checkpoint.execution_id.clone(), checkpoint);
+        Ok(())
+    }
+
+    fn remove_checkpoint(&self, execution_id: &ExecutionId) -> Result<()> {
+        #[cfg(feature = "persistence")]
+        if let Some(db) = &self.db {
+            db.remove(execution_id).map_err(|e| persistence_error("remove checkpoint", &e))?;
+            db.flush().map_err(|e| persistence_error("remove checkpoint", &e))?;
+        }
+        self.checkpoints.lock().unwrap_or_else(|e| e.into_inner()).remove(execution_id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "persistence")]
+fn persistence_error(operation: &str, error: &sled::Error) -> WorkflowError {
+    WorkflowError::PersistenceError {
+        operation: operation.into(),
+        reason: error.to_string(),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -509,15 +745,175 @@ impl ExecutionTracker {
     }
     async fn cancel_execution(&self, _execution_id: &ExecutionId) -> Result<bool> { Ok(false) }
     async fn pause_execution(&self, _execution_id: &ExecutionId) -> Result<bool> { Ok(false) }
-    async fn resume_execution(&self, _execution_id: &ExecutionId) -> Result<bool> { Ok(false) }
     fn active_executions(&self) -> usize { 0 }
 }
 
+/// Priority of a workflow execution waiting for a worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ExecutionPriority {
+    /// Background work
+    Low,
+    /// Default priority
+    #[default]
+    Normal,
+    /// Ahead of normal work
+    High,
+    /// Ahead of everything else
+    Critical,
+}
+
+impl ExecutionPriority {
+    /// Every priority, lowest first
+    pub const ALL: [Self; 4] = [Self::Low, Self::Normal, Self::High, Self::Critical];
+
+    /// Priority raised by `levels`, capped at `Critical`
+    fn raised(self, levels: u128) -> Self {
+        let index = (self as u128).saturating_add(levels).min(Self::Critical as u128);
+        Self::ALL[index as usize]
+    }
+}
+
+/// Hands out a fixed number of worker slots, highest priority first
 #[derive(Debug)]
-struct WorkerPool;
+struct WorkerPool {
+    worker_count: usize,
+    /// Wait after which an execution gains a priority level
+    aging: Duration,
+    state: std::sync::Mutex<PoolState>,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    /// Slots held, including those granted to waiters not yet woken
+    running: usize,
+    next_ticket: u64,
+    /// Waiting executions by arrival order
+    waiting: alloc::collections::BTreeMap<u64, Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    priority: ExecutionPriority,
+    queued_at: std::time::Instant,
+    /// A slot was handed over to this waiter
+    granted: bool,
+    waker: Option<std::task::Waker>,
+}
+
+/// A worker slot, returned to the pool on drop
+struct WorkerSlot<'a> {
+    pool: &'a WorkerPool,
+}
+
+impl Drop for WorkerSlot<'_> {
+    fn drop(&mut self) {
+        self.pool.release(&mut self.pool.lock());
+    }
+}
+
+/// Removes a waiter whose execution stopped waiting, passing on a slot
+/// granted to it in the meantime
+struct Waiting<'a> {
+    pool: &'a WorkerPool,
+    ticket: u64,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock();
+        if state.waiting.remove(&self.ticket).is_some_and(|waiter| waiter.granted) {
+            self.pool.release(&mut state);
+        }
+    }
+}
+
 impl WorkerPool {
-    fn new(_worker_count: usize, _tracker: ExecutionTracker) -> Self { Self }
+    fn new(worker_count: usize, aging: Duration) -> Self {
+        Self {
+            worker_count: worker_count.max(1),
+            aging,
+            state: std::sync::Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     async fn submit_execution(&self, _execution_id: ExecutionId) -> Result<()> { Ok(()) }
+
+    /// Wait for a worker slot
+    async fn acquire(&self, priority: ExecutionPriority) -> WorkerSlot<'_> {
+        let ticket = {
+            let mut state = self.lock();
+            // Waiters are only queued while every slot is held
+            if state.running < self.worker_count {
+                state.running += 1;
+                return WorkerSlot { pool: self };
+            }
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiting.insert(ticket, Waiter {
+                priority,
+                queued_at: std::time::Instant::now(),
+                granted: false,
+                waker: None,
+            });
+            ticket
+        };
+
+        let waiting = Waiting { pool: self, ticket };
+        std::future::poll_fn(|cx| {
+            let mut state = self.lock();
+            match state.waiting.get_mut(&ticket) {
+                Some(waiter) if !waiter.granted => {
+                    waiter.waker = Some(cx.waker().clone());
+                    std::task::Poll::Pending
+                }
+                _ => {
+                    state.waiting.remove(&ticket);
+                    std::task::Poll::Ready(())
+                }
+            }
+        })
+        .await;
+        drop(waiting);
+        WorkerSlot { pool: self }
+    }
+
+    /// Hand a released slot to the waiter with the highest aged priority,
+    /// the earliest arrival among equals
+    fn release(&self, state: &mut PoolState) {
+        let aging = self.aging.as_nanos();
+        let next = state
+            .waiting
+            .iter()
+            .filter(|(_, waiter)| !waiter.granted)
+            .max_by_key(|(ticket, waiter)| {
+                let levels = waiter.queued_at.elapsed().as_nanos().checked_div(aging).unwrap_or(0);
+                (waiter.priority.raised(levels), std::cmp::Reverse(**ticket))
+            })
+            .map(|(ticket, _)| *ticket);
+
+        match next.and_then(|ticket| state.waiting.get_mut(&ticket)) {
+            Some(waiter) => {
+                waiter.granted = true;
+                if let Some(waker) = waiter.waker.take() {
+                    waker.wake();
+                }
+            }
+            None => state.running -= 1,
+        }
+    }
+
+    /// Waiting executions by requested priority
+    fn queue_depths(&self) -> alloc::collections::BTreeMap<ExecutionPriority, usize> {
+        let mut depths = ExecutionPriority::ALL.iter().map(|priority| (*priority, 0)).collect::<alloc::collections::BTreeMap<_, _>>();
+        for waiter in self.lock().waiting.values().filter(|waiter| !waiter.granted) {
+            *depths.entry(waiter.priority).or_default() += 1;
+        }
+        depths
+    }
 }
 
 /// Get current timestamp (simplified)
@@ -594,4 +990,337 @@ mod tests {
 
         assert_eq!(engine.config.worker_count, 2);
     }
+
+    #[tokio::test]
+    async fn test_analyze_bottlenecks_finds_slow_node() {
+        let mut engine = WorkflowEngine::builder().build().await.unwrap();
+
+        let fast = |_input: NodeInput| async { Ok(WorkflowData::Null) };
+        engine.register_node_handler("fetch", alloc::sync::Arc::new(fast));
+        engine.register_node_handler("store", alloc::sync::Arc::new(fast));
+        engine.register_node_handler(
+            "transform",
+            alloc::sync::Arc::new(|_input: NodeInput| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(WorkflowData::Null)
+            }),
+        );
+
+        let workflow = Workflow::builder("etl")
+            .add_node(WorkflowNode::new("fetch"))
+            .add_node(WorkflowNode::new("transform"))
+            .add_node(WorkflowNode::new("store"))
+            .connect("fetch", "transform")
+            .connect("transform", "store")
+            .build();
+
+        for _ in 0..5 {
+            let result = engine.run_workflow(workflow.clone()).await.unwrap();
+            assert_eq!(result.status, ExecutionStatus::Completed);
+        }
+
+        let bottlenecks = engine.analyze_bottlenecks(&workflow.id);
+        assert_eq!(bottlenecks.len(), 3);
+
+        let top = &bottlenecks[0];
+        assert_eq!(top.node_id, "transform");
+        assert_eq!(top.executions, 5);
+        assert!(top.average_duration_ms >= 50.0);
+        assert!(top.p95_duration_ms >= 50);
+        assert_eq!(top.failure_rate, 0.0);
+        assert!(bottlenecks[1].average_duration_ms < top.average_duration_ms);
+    }
+
+    #[tokio::test]
+    async fn test_interval_schedule_fires_on_mock_clock() {
+        let clock = alloc::sync::Arc::new(ManualClock::new(0));
+        let mut engine = WorkflowEngine::builder().with_clock(clock.clone()).build().await.unwrap();
+
+        let runs = alloc::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = runs.clone();
+        engine.register_node_handler(
+            "tick",
+            alloc::sync::Arc::new(move |_input: NodeInput| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async { Ok(WorkflowData::Null) }
+            }),
+        );
+        let engine = alloc::sync::Arc::new(engine);
+
+        let workflow = Workflow::builder("heartbeat").add_node(WorkflowNode::new("tick")).build();
+        let id = engine
+            .schedule_workflow(workflow, Schedule::Interval(Duration::from_millis(50)))
+            .unwrap();
+
+        // 250ms in 10ms steps is five 50ms intervals
+        for _ in 0..25 {
+            clock.advance(Duration::from_millis(10));
+            for execution in engine.run_due_schedules() {
+                assert_eq!(execution.await.unwrap().unwrap().status, ExecutionStatus::Completed);
+            }
+        }
+        assert_eq!(runs.load(std::sync::atomic::Ordering::SeqCst), 5);
+
+        let schedules = engine.list_schedules();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!((schedules[0].runs, schedules[0].next_run), (5, Some(300)));
+
+        // The background task picks up the next due run
+        let scheduler = engine.start_scheduler(Duration::from_millis(1));
+        clock.advance(Duration::from_millis(50));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while runs.load(std::sync::atomic::Ordering::SeqCst) < 6 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        scheduler.abort();
+
+        assert!(engine.cancel_schedule(&id));
+        clock.advance(Duration::from_millis(500));
+        assert!(engine.run_due_schedules().is_empty());
+        assert!(engine.list_schedules().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_execution_skips_finished_nodes() {
+        let mut engine = WorkflowEngine::builder().build().await.unwrap();
+
+        let extract_runs = alloc::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let load_runs = alloc::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let crashed = alloc::sync::Arc::new(std::sync::Mutex::new(None));
+
+        let runs = extract_runs.clone();
+        engine.register_node_handler(
+            "extract",
+            alloc::sync::Arc::new(move |_input: NodeInput| {
+                runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async { Ok(WorkflowData::Int(42)) }
+            }),
+        );
+        let (runs, execution) = (load_runs.clone(), crashed.clone());
+        engine.register_node_handler(
+            "load",
+            alloc::sync::Arc::new(move |input: NodeInput| {
+                let first_run = runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0;
+                if first_run {
+                    *execution.lock().unwrap() = Some(input.execution_id.clone());
+                }
+                async move {
+                    if first_run {
+                        // Hang until the worker "crashes"
+                        std::future::pending::<()>().await;
+                    }
+                    Ok(input.inputs["extract"].clone())
+                }
+            }),
+        );
+        let engine = alloc::sync::Arc::new(engine);
+
+        let workflow = Workflow::builder("etl")
+            .add_node(WorkflowNode::new("extract"))
+            .add_node(WorkflowNode::new("load").idempotent(true))
+            .connect("extract", "load")
+            .build();
+        let worker = tokio::spawn({
+            let engine = engine.clone();
+            async move { engine.run_workflow(workflow).await }
+        });
+
+        let execution_id = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(id) = crashed.lock().unwrap().clone() {
+                    return id;
+                }
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        worker.abort();
+        assert!(worker.await.unwrap_err().is_cancelled());
+
+        let result = engine.resume_execution(&execution_id).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.node_results["load"].output, WorkflowData::Int(42));
+        assert_eq!(extract_runs.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(load_runs.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // The checkpoint is dropped once the execution has ended
+        assert!(matches!(
+            engine.resume_execution(&execution_id).await,
+            Err(WorkflowError::ExecutionNotFound { .. })
+        ));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[tokio::test]
+    async fn test_checkpoints_survive_an_engine_restart() {
+        let dir = std::env::temp_dir().join(alloc::format!("frys-workflow-checkpoints-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.to_str().unwrap();
+        let runs = alloc::sync::Arc::new(std::sync::Mutex::new(alloc::vec::Vec::new()));
+
+        async fn engine_at(path: &str, runs: &alloc::sync::Arc<std::sync::Mutex<alloc::vec::Vec<NodeId>>>) -> WorkflowEngine {
+            let mut engine = WorkflowEngine::builder().with_persistence(path).build().await.unwrap();
+            for node in ["extract", "load"] {
+                let runs = runs.clone();
+                engine.register_node_handler(
+                    node,
+                    alloc::sync::Arc::new(move |_input: NodeInput| {
+                        runs.lock().unwrap().push(node.into());
+                        async { Ok(WorkflowData::Int(42)) }
+                    }),
+                );
+            }
+            engine
+        }
+
+        // A worker checkpointed "extract" as done and died while running "load"
+        let engine = engine_at(path, &runs).await;
+        let workflow = Workflow::builder("etl")
+            .add_node(WorkflowNode::new("extract"))
+            .add_node(WorkflowNode::new("load").idempotent(true))
+            .connect("extract", "load")
+            .build();
+        let execution_id: ExecutionId = "interrupted".into();
+        let mut node_results = alloc::collections::BTreeMap::new();
+        node_results.insert("extract".into(), NodeResult {
+            node_id: "extract".into(),
+            status: ExecutionStatus::Completed,
+            output: WorkflowData::Int(42),
+            error: None,
+            attempts: 1,
+            started_at: 0,
+            ended_at: Some(0),
+            sub_execution: None,
+        });
+        engine
+            .workflow_store
+            .save_checkpoint(ExecutionCheckpoint {
+                execution_id: execution_id.clone(),
+                workflow,
+                context: ExecutionContext::new(),
+                node_results,
+                running: alloc::vec!["load".into()],
+                started_at: 0,
+            })
+            .unwrap();
+        drop(engine);
+
+        let engine = engine_at(path, &runs).await;
+        let result = engine.resume_execution(&execution_id).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(*runs.lock().unwrap(), ["load"]);
+        drop(engine);
+
+        // Finished executions leave no checkpoint behind on disk either
+        let engine = engine_at(path, &runs).await;
+        assert!(matches!(
+            engine.resume_execution(&execution_id).await,
+            Err(WorkflowError::ExecutionNotFound { .. })
+        ));
+        drop(engine);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Single-worker engine whose "gate" node holds the worker until
+    /// notified, and whose other nodes log their start
+    async fn gated_engine(
+        aging: Duration,
+    ) -> (alloc::sync::Arc<WorkflowEngine>, alloc::sync::Arc<tokio::sync::Notify>, alloc::sync::Arc<std::sync::Mutex<alloc::vec::Vec<NodeId>>>) {
+        let mut engine = WorkflowEngine::builder().with_workers(1).with_priority_aging(aging).build().await.unwrap();
+
+        let gate = alloc::sync::Arc::new(tokio::sync::Notify::new());
+        let started = alloc::sync::Arc::new(std::sync::Mutex::new(alloc::vec::Vec::new()));
+        let notified = gate.clone();
+        engine.register_node_handler(
+            "gate",
+            alloc::sync::Arc::new(move |_input: NodeInput| {
+                let gate = notified.clone();
+                async move {
+                    gate.notified().await;
+                    Ok(WorkflowData::Null)
+                }
+            }),
+        );
+        for node in ["low", "high"] {
+            let log = started.clone();
+            engine.register_node_handler(
+                node,
+                alloc::sync::Arc::new(move |input: NodeInput| {
+                    log.lock().unwrap().push(input.node.id.clone());
+                    async { Ok(WorkflowData::Null) }
+                }),
+            );
+        }
+        (alloc::sync::Arc::new(engine), gate, started)
+    }
+
+    fn spawn_run(
+        engine: &alloc::sync::Arc<WorkflowEngine>,
+        node: &str,
+        priority: ExecutionPriority,
+    ) -> tokio::task::JoinHandle<Result<ExecutionResult>> {
+        let engine = engine.clone();
+        let workflow = Workflow::builder(node).add_node(WorkflowNode::new(node)).build();
+        tokio::spawn(async move { engine.run_workflow_with_priority(workflow, priority).await })
+    }
+
+    async fn wait_for_depth(engine: &WorkflowEngine, priority: ExecutionPriority, depth: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while engine.stats().queue_depths[&priority] < depth {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_execution_skips_queue() {
+        let (engine, gate, started) = gated_engine(Duration::from_secs(60)).await;
+
+        let blocker = spawn_run(&engine, "gate", ExecutionPriority::Normal);
+        let mut runs = (0..20).map(|_| spawn_run(&engine, "low", ExecutionPriority::Low)).collect::<alloc::vec::Vec<_>>();
+        wait_for_depth(&engine, ExecutionPriority::Low, 20).await;
+        runs.push(spawn_run(&engine, "high", ExecutionPriority::High));
+        wait_for_depth(&engine, ExecutionPriority::High, 1).await;
+
+        let depths = engine.stats().queue_depths;
+        assert_eq!(depths[&ExecutionPriority::Normal], 0);
+        assert_eq!(depths[&ExecutionPriority::Critical], 0);
+        assert!(started.lock().unwrap().is_empty());
+
+        gate.notify_one();
+        assert_eq!(blocker.await.unwrap().unwrap().status, ExecutionStatus::Completed);
+        for run in runs {
+            assert_eq!(run.await.unwrap().unwrap().status, ExecutionStatus::Completed);
+        }
+
+        let started = started.lock().unwrap();
+        assert_eq!(started.len(), 21);
+        assert_eq!(started[0], "high");
+        assert!(engine.stats().queue_depths.values().all(|depth| *depth == 0));
+    }
+
+    #[tokio::test]
+    async fn test_waiting_execution_ages_past_new_work() {
+        let (engine, gate, started) = gated_engine(Duration::from_millis(10)).await;
+
+        let blocker = spawn_run(&engine, "gate", ExecutionPriority::Normal);
+        let low = spawn_run(&engine, "low", ExecutionPriority::Low);
+        wait_for_depth(&engine, ExecutionPriority::Low, 1).await;
+        // Long enough for the low-priority execution to reach Critical
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let high = spawn_run(&engine, "high", ExecutionPriority::High);
+        wait_for_depth(&engine, ExecutionPriority::High, 1).await;
+
+        gate.notify_one();
+        for run in [blocker, low, high] {
+            assert_eq!(run.await.unwrap().unwrap().status, ExecutionStatus::Completed);
+        }
+        assert_eq!(*started.lock().unwrap(), ["low", "high"]);
+    }
 }
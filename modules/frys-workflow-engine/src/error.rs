@@ -118,6 +118,20 @@ pub enum WorkflowError {
         operation: alloc::string::String,
         reason: alloc::string::String,
     },
+
+    /// Invalid workflow schedule
+    InvalidSchedule {
+        schedule: alloc::string::String,
+        reason: alloc::string::String,
+    },
+
+    /// Node input or output does not match its declared schema
+    SchemaMismatch {
+        node_id: alloc::string::String,
+        data: alloc::string::String,
+        path: alloc::string::String,
+        reason: alloc::string::String,
+    },
 }
 
 impl fmt::Display for WorkflowError {
@@ -136,7 +150,14 @@ impl fmt::Display for WorkflowError {
                 write!(f, "Invalid workflow: {}", reason)
             }
             WorkflowError::CyclicDependency { nodes } => {
-                write!(f, "Cyclic dependency detected: {:?}", nodes)
+                write!(f, "Cyclic dependency detected: ")?;
+                for node in nodes {
+                    write!(f, "{} -> ", node)?;
+                }
+                match nodes.first() {
+                    Some(first) => write!(f, "{}", first),
+                    None => Ok(()),
+                }
             }
             WorkflowError::ExecutionTimeout { execution_id, timeout_seconds } => {
                 write!(f, "Execution '{}' timed out after {} seconds", execution_id, timeout_seconds)
@@ -180,6 +201,12 @@ impl fmt::Display for WorkflowError {
             WorkflowError::NetworkError { operation, reason } => {
                 write!(f, "Network error in '{}': {}", operation, reason)
             }
+            WorkflowError::InvalidSchedule { schedule, reason } => {
+                write!(f, "Invalid schedule '{}': {}", schedule, reason)
+            }
+            WorkflowError::SchemaMismatch { node_id, data, path, reason } => {
+                write!(f, "Node '{}' {} does not match its schema at {}: {}", node_id, data, path, reason)
+            }
         }
     }
 }
@@ -73,6 +73,129 @@ pub enum WorkflowEvent {
     },
 }
 
+/// Event bus topics of workflow lifecycle events
+pub mod event_topics {
+    pub const WORKFLOW_STARTED: &str = "workflow.started";
+    pub const WORKFLOW_COMPLETED: &str = "workflow.completed";
+    pub const WORKFLOW_FAILED: &str = "workflow.failed";
+    pub const WORKFLOW_METRICS: &str = "workflow.metrics";
+    pub const NODE_STARTED: &str = "node.started";
+    pub const NODE_COMPLETED: &str = "node.completed";
+    pub const NODE_FAILED: &str = "node.failed";
+}
+
+/// Event header names carrying the fields of workflow events
+pub mod event_headers {
+    pub const WORKFLOW_ID: &str = "workflow.id";
+    pub const EXECUTION_ID: &str = "workflow.execution_id";
+    pub const NODE_ID: &str = "workflow.node_id";
+    pub const DURATION_MS: &str = "workflow.duration_ms";
+    pub const SUCCESS: &str = "workflow.success";
+    pub const ERROR: &str = "workflow.error";
+}
+
+impl WorkflowEvent {
+    /// Event bus topic the event is published on
+    pub fn topic(&self) -> &'static str {
+        match self {
+            WorkflowEvent::WorkflowStarted { .. } => event_topics::WORKFLOW_STARTED,
+            WorkflowEvent::WorkflowCompleted { .. } => event_topics::WORKFLOW_COMPLETED,
+            WorkflowEvent::WorkflowFailed { .. } => event_topics::WORKFLOW_FAILED,
+            WorkflowEvent::NodeStarted { .. } => event_topics::NODE_STARTED,
+            WorkflowEvent::NodeCompleted { .. } => event_topics::NODE_COMPLETED,
+            WorkflowEvent::NodeFailed { .. } => event_topics::NODE_FAILED,
+            WorkflowEvent::MetricsUpdated { .. } => event_topics::WORKFLOW_METRICS,
+        }
+    }
+
+    /// Encode the event for the event bus. Its fields and metadata travel
+    /// as headers; the payload is empty.
+    pub fn to_event(&self) -> frys_eventbus::Event {
+        let mut fields: Vec<(&str, String)> = Vec::new();
+        let (workflow_id, execution_id, timestamp, metadata) = match self {
+            WorkflowEvent::WorkflowStarted { workflow_id, execution_id, timestamp, metadata } => {
+                (workflow_id, execution_id, *timestamp, Some(metadata))
+            }
+            WorkflowEvent::WorkflowCompleted { workflow_id, execution_id, timestamp, duration_ms, success, metadata } => {
+                fields.push((event_headers::DURATION_MS, duration_ms.to_string()));
+                fields.push((event_headers::SUCCESS, success.to_string()));
+                (workflow_id, execution_id, *timestamp, Some(metadata))
+            }
+            WorkflowEvent::WorkflowFailed { workflow_id, execution_id, timestamp, error, metadata } => {
+                fields.push((event_headers::ERROR, error.clone()));
+                (workflow_id, execution_id, *timestamp, Some(metadata))
+            }
+            WorkflowEvent::NodeStarted { workflow_id, execution_id, node_id, timestamp, metadata } => {
+                fields.push((event_headers::NODE_ID, node_id.clone()));
+                (workflow_id, execution_id, *timestamp, Some(metadata))
+            }
+            WorkflowEvent::NodeCompleted { workflow_id, execution_id, node_id, timestamp, duration_ms, success, metadata } => {
+                fields.push((event_headers::NODE_ID, node_id.clone()));
+                fields.push((event_headers::DURATION_MS, duration_ms.to_string()));
+                fields.push((event_headers::SUCCESS, success.to_string()));
+                (workflow_id, execution_id, *timestamp, Some(metadata))
+            }
+            WorkflowEvent::NodeFailed { workflow_id, execution_id, node_id, timestamp, error, metadata } => {
+                fields.push((event_headers::NODE_ID, node_id.clone()));
+                fields.push((event_headers::ERROR, error.clone()));
+                (workflow_id, execution_id, *timestamp, Some(metadata))
+            }
+            WorkflowEvent::MetricsUpdated { workflow_id, execution_id, timestamp, .. } => {
+                (workflow_id, execution_id, *timestamp, None)
+            }
+        };
+
+        let mut event = frys_eventbus::Event::new(self.topic().into(), Vec::new())
+            .with_header(event_headers::WORKFLOW_ID.into(), workflow_id.clone())
+            .with_header(event_headers::EXECUTION_ID.into(), execution_id.clone());
+        for (name, value) in fields {
+            event = event.with_header(name.into(), value);
+        }
+        for (name, value) in metadata.into_iter().flatten() {
+            event = event.with_header(name.clone(), value.clone());
+        }
+        event.timestamp = timestamp;
+        event
+    }
+}
+
+/// Publishes workflow lifecycle events to a `frys-eventbus` bus.
+///
+/// Publishing is best effort: an event the bus rejects is dropped rather
+/// than failing the execution that produced it.
+pub struct EventBusPublisher {
+    eventbus: tokio::sync::Mutex<frys_eventbus::EventBus>,
+}
+
+impl EventBusPublisher {
+    /// Publish to `eventbus`
+    pub fn new(eventbus: frys_eventbus::EventBus) -> Self {
+        Self {
+            eventbus: tokio::sync::Mutex::new(eventbus),
+        }
+    }
+
+    /// The underlying bus, e.g. to add subscribers
+    pub fn eventbus(&self) -> &tokio::sync::Mutex<frys_eventbus::EventBus> {
+        &self.eventbus
+    }
+
+    /// Publish an event on its topic to the bus's local subscribers.
+    ///
+    /// The lock on the bus is released before publishing, so a publish
+    /// waiting for queue space does not hold up the others.
+    pub async fn publish(&self, event: &WorkflowEvent) {
+        let publisher = self.eventbus.lock().await.local_publisher();
+        let _ = publisher.publish(event.to_event()).await;
+    }
+}
+
+impl core::fmt::Debug for EventBusPublisher {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventBusPublisher").finish_non_exhaustive()
+    }
+}
+
 /// Workflow metrics
 #[derive(Debug, Clone)]
 pub struct WorkflowMetrics {
@@ -0,0 +1,1430 @@
+//! Workflow node execution runtime
+
+use crate::core::{
+    ExecutionId, ExecutionResult, ExecutionStatus, NodeId, NodeOutputRef, NodeResult, NodeType, RetryPolicy, Workflow, WorkflowCondition,
+    WorkflowData, WorkflowId, WorkflowNode,
+};
+use crate::engine::ExecutionContext;
+use crate::events::{EventBusPublisher, WorkflowEvent};
+use crate::{Result, WorkflowError};
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::future::Future;
+use tokio::sync::Semaphore;
+
+/// Input passed to a node handler
+#[derive(Debug, Clone)]
+pub struct NodeInput {
+    /// Execution the node belongs to
+    pub execution_id: ExecutionId,
+    /// Node being executed
+    pub node: WorkflowNode,
+    /// Outputs of the node's completed predecessors
+    pub inputs: BTreeMap<NodeId, WorkflowData>,
+    /// Snapshot of the execution context
+    pub context: ExecutionContext,
+}
+
+/// Node execution handler
+#[async_trait::async_trait]
+pub trait NodeHandler: Send + Sync {
+    /// Execute the node and produce its output
+    async fn execute(&self, input: NodeInput) -> Result<WorkflowData>;
+}
+
+#[async_trait::async_trait]
+impl<F, Fut> NodeHandler for F
+where
+    F: Fn(NodeInput) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<WorkflowData>> + Send + 'static,
+{
+    async fn execute(&self, input: NodeInput) -> Result<WorkflowData> {
+        (self)(input).await
+    }
+}
+
+/// Progress of an execution, saved as its nodes start and finish so that
+/// an interrupted execution can resume without re-running finished nodes
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionCheckpoint {
+    /// Execution the checkpoint belongs to
+    pub execution_id: ExecutionId,
+    /// Workflow being executed
+    pub workflow: Workflow,
+    /// Context the execution was started with
+    pub context: ExecutionContext,
+    /// Results of the nodes that have finished
+    pub node_results: BTreeMap<NodeId, NodeResult>,
+    /// Nodes that were started but had not finished
+    pub running: Vec<NodeId>,
+    /// Execution start time
+    pub started_at: u64,
+}
+
+/// Storage for execution checkpoints
+pub trait CheckpointStore: Send + Sync {
+    /// Save the latest checkpoint of an execution, replacing earlier ones
+    fn save_checkpoint(&self, checkpoint: ExecutionCheckpoint) -> Result<()>;
+
+    /// Drop the checkpoint of an execution that has ended
+    fn remove_checkpoint(&self, execution_id: &ExecutionId) -> Result<()>;
+}
+
+/// Executes workflow DAGs by running ready nodes concurrently
+#[derive(Default, Clone)]
+pub struct WorkflowExecutor {
+    /// Handlers keyed by node ID
+    handlers: BTreeMap<NodeId, Arc<dyn NodeHandler>>,
+    /// Workflows runnable by sub-workflow nodes, keyed by workflow ID
+    workflows: BTreeMap<WorkflowId, Arc<Workflow>>,
+    /// Destination of lifecycle events
+    events: Option<Arc<EventBusPublisher>>,
+    /// Time a whole execution may take
+    timeout: Option<core::time::Duration>,
+}
+
+impl WorkflowExecutor {
+    /// Create a new executor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the handler for a node. Nodes without a handler complete
+    /// immediately with `WorkflowData::Null`, except decision nodes, which
+    /// forward their input so conditions on their outgoing edges can
+    /// inspect the upstream output.
+    pub fn register_handler(&mut self, node_id: &str, handler: Arc<dyn NodeHandler>) {
+        self.handlers.insert(node_id.into(), handler);
+    }
+
+    /// Check whether a node has a registered handler
+    pub fn has_handler(&self, node_id: &str) -> bool {
+        self.handlers.contains_key(node_id)
+    }
+
+    /// Make a workflow available to `NodeType::SubWorkflow` nodes
+    pub fn register_workflow(&mut self, workflow: Workflow) {
+        self.workflows.insert(workflow.id.clone(), Arc::new(workflow));
+    }
+
+    /// Publish `workflow.*` and `node.*` lifecycle events of every
+    /// execution through `publisher`
+    pub fn set_event_publisher(&mut self, publisher: Arc<EventBusPublisher>) {
+        self.events = Some(publisher);
+    }
+
+    /// Limit the time a whole execution may take. Executions run without
+    /// a limit unless one is set.
+    pub fn set_timeout(&mut self, timeout: core::time::Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    /// Execute a workflow to completion.
+    ///
+    /// Nodes run in waves: every node whose predecessors have all finished
+    /// is started concurrently. An edge is traversed when its condition
+    /// holds for the source node (by default, when the source completed),
+    /// and a node runs if at least one incoming edge was traversed. Nodes
+    /// with no traversed edge are reported as `Skipped`, and nodes
+    /// downstream of a failed node as `Cancelled`.
+    ///
+    /// A sub-workflow node runs its registered workflow as one step: the
+    /// node's inputs become the inputs of the child's entry nodes, the
+    /// child's output becomes the node's output, and the child execution is
+    /// kept in the node result. A workflow that (indirectly) contains
+    /// itself fails at the recursive node.
+    ///
+    /// Branches fanning out of a `Parallel` node with `max_parallel` set
+    /// are queued until one of its branch slots frees up.
+    ///
+    /// A map node runs its sub-node's handler once per element of the
+    /// array it maps over, at most `max_parallel` (by default
+    /// `DEFAULT_MAP_CONCURRENCY`) at a time. Each run receives its element
+    /// as the output of the node the array came from, and is retried and
+    /// timed out on its own. The node's output is the array of their
+    /// outputs in element order; it fails with the first element that
+    /// fails, or if what it maps over is missing or not an array.
+    ///
+    /// A node with an input schema fails without running if any of its
+    /// inputs does not match, and a node whose output does not match its
+    /// output schema fails like a handler returning an error, so the
+    /// mismatching data never reaches downstream nodes.
+    ///
+    /// An execution still running when its timeout (see
+    /// [`set_timeout`](Self::set_timeout)) elapses ends with status
+    /// `Timeout`: its running nodes are cancelled and reported as
+    /// `Timeout`, nodes not yet started as `Cancelled`, and nodes that
+    /// finished keep their results and outputs. A resumed execution gets
+    /// the full timeout again.
+    pub async fn execute(&self, execution_id: ExecutionId, workflow: &Workflow, context: ExecutionContext) -> Result<ExecutionResult> {
+        self.execute_nested(execution_id, workflow, context, BTreeMap::new(), alloc::vec![workflow.id.clone()], None)
+            .await
+    }
+
+    /// Execute a workflow like [`execute`](Self::execute), saving a
+    /// checkpoint to `store` whenever nodes start or finish. The checkpoint
+    /// is removed once the execution ends.
+    pub async fn execute_with_checkpoints(
+        &self,
+        execution_id: ExecutionId,
+        workflow: &Workflow,
+        context: ExecutionContext,
+        store: &dyn CheckpointStore,
+    ) -> Result<ExecutionResult> {
+        let checkpoint = ExecutionCheckpoint {
+            execution_id,
+            workflow: workflow.clone(),
+            context,
+            node_results: BTreeMap::new(),
+            running: Vec::new(),
+            started_at: current_timestamp(),
+        };
+        self.resume(checkpoint, store).await
+    }
+
+    /// Continue an interrupted execution from its last checkpoint.
+    ///
+    /// Finished nodes keep their checkpointed results and do not run again.
+    /// Nodes that were running when the checkpoint was taken run again if
+    /// they are marked idempotent; any other such node fails, since its
+    /// interrupted attempt may already have had side effects.
+    pub async fn resume(&self, mut checkpoint: ExecutionCheckpoint, store: &dyn CheckpointStore) -> Result<ExecutionResult> {
+        for node_id in core::mem::take(&mut checkpoint.running) {
+            let Some(node) = checkpoint.workflow.nodes.get(&node_id) else { continue };
+            if node.idempotent {
+                continue;
+            }
+            checkpoint.node_results.insert(node_id.clone(), NodeResult {
+                node_id,
+                status: ExecutionStatus::Failed,
+                output: WorkflowData::Null,
+                error: Some("interrupted while running and not marked idempotent".into()),
+                attempts: 0,
+                started_at: checkpoint.started_at,
+                ended_at: None,
+                sub_execution: None,
+            });
+        }
+
+        let workflow = checkpoint.workflow.clone();
+        let result = self
+            .execute_nested(
+                checkpoint.execution_id.clone(),
+                &workflow,
+                checkpoint.context.clone(),
+                BTreeMap::new(),
+                alloc::vec![workflow.id.clone()],
+                Some(Checkpointing { store, checkpoint }),
+            )
+            .await?;
+        store.remove_checkpoint(&result.execution_id)?;
+        Ok(result)
+    }
+
+    /// Execute a workflow whose entry nodes receive `initial_inputs`, below
+    /// the workflows in `stack`, continuing from and saving to
+    /// `checkpointing` if given
+    async fn execute_nested(
+        &self,
+        execution_id: ExecutionId,
+        workflow: &Workflow,
+        context: ExecutionContext,
+        initial_inputs: BTreeMap<NodeId, WorkflowData>,
+        stack: Vec<WorkflowId>,
+        mut checkpointing: Option<Checkpointing<'_>>,
+    ) -> Result<ExecutionResult> {
+        let (started_at, mut node_results) = match &checkpointing {
+            Some(checkpointing) => (checkpointing.checkpoint.started_at, checkpointing.checkpoint.node_results.clone()),
+            None => (current_timestamp(), BTreeMap::new()),
+        };
+        let incoming = incoming_edges(workflow);
+        let mut outputs: BTreeMap<NodeId, WorkflowData> = node_results
+            .values()
+            .filter(|result| result.status == ExecutionStatus::Completed)
+            .map(|result| (result.node_id.clone(), result.output.clone()))
+            .collect();
+        let mut running: Vec<NodeId> = Vec::new();
+        // Sub-workflows run inside a node of their parent, under its deadline
+        let deadline = self
+            .timeout
+            .filter(|_| stack.len() == 1)
+            .map(|timeout| (tokio::time::Instant::now() + timeout, timeout));
+        let mut timed_out = false;
+        self.emit(WorkflowEvent::WorkflowStarted {
+            workflow_id: workflow.id.clone(),
+            execution_id: execution_id.clone(),
+            timestamp: current_timestamp(),
+            metadata: BTreeMap::new(),
+        })
+        .await;
+        // Branch slots of parallel nodes with a fan-out limit
+        let branch_limits: BTreeMap<NodeId, Arc<Semaphore>> = workflow
+            .nodes
+            .values()
+            .filter(|node| node.node_type == NodeType::Parallel)
+            .filter_map(|node| Some((node.id.clone(), Arc::new(Semaphore::new(node.max_parallel?.max(1))))))
+            .collect();
+
+        loop {
+            let ready: Vec<NodeId> = workflow
+                .nodes
+                .keys()
+                .filter(|id| !node_results.contains_key(*id))
+                .filter(|id| incoming[*id].iter().all(|(from, _)| node_results.contains_key(from)))
+                .cloned()
+                .collect();
+
+            if ready.is_empty() || timed_out {
+                break;
+            }
+
+            let wave_started_at = current_timestamp();
+            let mut tasks = tokio::task::JoinSet::new();
+            for node_id in ready {
+                let node = &workflow.nodes[&node_id];
+                let routed = route(&incoming[&node_id], &node_results).and_then(|traversed| {
+                    let step = match &node.node_type {
+                        NodeType::SubWorkflow { workflow_id } => self.sub_workflow(workflow_id, &stack).map(Step::SubWorkflow),
+                        NodeType::Map { over, sub_node } => self.map_node(node, over, sub_node, &outputs).map(Step::Map),
+                        _ => Ok(Step::Handler),
+                    };
+                    step.map(|step| (traversed, step))
+                        .map_err(|error| (ExecutionStatus::Failed, alloc::format!("{}", error)))
+                });
+                let (traversed, step) = match routed {
+                    Ok(routed) => routed,
+                    Err((status, error)) => {
+                        node_results.insert(node_id.clone(), NodeResult {
+                            node_id,
+                            status,
+                            output: WorkflowData::Null,
+                            error: Some(error),
+                            attempts: 0,
+                            started_at: current_timestamp(),
+                            ended_at: None,
+                            sub_execution: None,
+                        });
+                        continue;
+                    }
+                };
+
+                let inputs = if incoming[&node_id].is_empty() {
+                    initial_inputs.clone()
+                } else {
+                    traversed
+                        .iter()
+                        .filter_map(|from| outputs.get(from).map(|output| (from.clone(), output.clone())))
+                        .collect()
+                };
+                if let Err(error) = check_inputs(node, &inputs) {
+                    node_results.insert(node_id.clone(), NodeResult {
+                        node_id,
+                        status: ExecutionStatus::Failed,
+                        output: WorkflowData::Null,
+                        error: Some(alloc::format!("{}", error)),
+                        attempts: 0,
+                        started_at: current_timestamp(),
+                        ended_at: None,
+                        sub_execution: None,
+                    });
+                    continue;
+                }
+                let input = NodeInput {
+                    execution_id: execution_id.clone(),
+                    node: node.clone(),
+                    inputs,
+                    context: context.clone(),
+                };
+                // Taken in node ID order so branches of several limited
+                // parallel nodes cannot deadlock
+                let limits: Vec<Arc<Semaphore>> = branch_limits
+                    .iter()
+                    .filter(|(from, _)| traversed.contains(from))
+                    .map(|(_, limit)| limit.clone())
+                    .collect();
+                let handler = self.handlers.get(&node_id).cloned();
+                let events = self.events.clone();
+                let workflow_id = workflow.id.clone();
+                running.push(node_id.clone());
+                tasks.spawn(async move {
+                    let mut slots = Vec::with_capacity(limits.len());
+                    for limit in limits {
+                        slots.push(limit.acquire_owned().await.expect("branch limits are never closed"));
+                    }
+
+                    if let Some(events) = &events {
+                        events
+                            .publish(&WorkflowEvent::NodeStarted {
+                                workflow_id: workflow_id.clone(),
+                                execution_id: input.execution_id.clone(),
+                                node_id: input.node.id.clone(),
+                                timestamp: current_timestamp(),
+                                metadata: BTreeMap::new(),
+                            })
+                            .await;
+                    }
+                    let execution_id = input.execution_id.clone();
+                    let result = match step {
+                        Step::Handler => run_node(handler, input).await,
+                        Step::SubWorkflow(sub_workflow) => run_sub_workflow(sub_workflow, input).await,
+                        Step::Map(map) => run_map(map, input).await,
+                    };
+                    if let Some(events) = &events {
+                        events.publish(&node_finished(workflow_id, execution_id, &result)).await;
+                    }
+                    result
+                });
+            }
+            if let Some(checkpointing) = &mut checkpointing {
+                checkpointing.save(&node_results, &running)?;
+            }
+
+            loop {
+                let joined = match deadline {
+                    Some((deadline, _)) => match tokio::time::timeout_at(deadline, tasks.join_next()).await {
+                        Ok(joined) => joined,
+                        Err(_) => {
+                            timed_out = true;
+                            break;
+                        }
+                    },
+                    None => tasks.join_next().await,
+                };
+                let Some(joined) = joined else { break };
+                let result = joined.map_err(|e| WorkflowError::NodeExecutionFailed {
+                    node_id: "unknown".into(),
+                    execution_id: execution_id.clone(),
+                    reason: alloc::format!("node task panicked: {}", e),
+                })?;
+                if result.status == ExecutionStatus::Completed {
+                    outputs.insert(result.node_id.clone(), result.output.clone());
+                }
+                running.retain(|id| *id != result.node_id);
+                node_results.insert(result.node_id.clone(), result);
+                if let Some(checkpointing) = &mut checkpointing {
+                    checkpointing.save(&node_results, &running)?;
+                }
+            }
+
+            if timed_out {
+                tasks.abort_all();
+                let timeout = deadline.map(|(_, timeout)| timeout).unwrap_or_default();
+                for node_id in running.drain(..) {
+                    node_results.insert(node_id.clone(), NodeResult {
+                        node_id,
+                        status: ExecutionStatus::Timeout,
+                        output: WorkflowData::Null,
+                        error: Some(alloc::format!("cancelled: workflow timed out after {:?}", timeout)),
+                        attempts: 1,
+                        started_at: wave_started_at,
+                        ended_at: Some(current_timestamp()),
+                        sub_execution: None,
+                    });
+                }
+                if let Some(checkpointing) = &mut checkpointing {
+                    checkpointing.save(&node_results, &running)?;
+                }
+            }
+        }
+
+        // Nodes never reached because an upstream node failed or the
+        // workflow timed out
+        let skipped = if timed_out {
+            "skipped: workflow timed out"
+        } else {
+            "skipped: upstream node failed"
+        };
+        for node_id in workflow.nodes.keys() {
+            node_results.entry(node_id.clone()).or_insert_with(|| NodeResult {
+                node_id: node_id.clone(),
+                status: ExecutionStatus::Cancelled,
+                output: WorkflowData::Null,
+                error: Some(skipped.into()),
+                attempts: 0,
+                started_at: current_timestamp(),
+                ended_at: None,
+                sub_execution: None,
+            });
+        }
+
+        let status = if timed_out {
+            ExecutionStatus::Timeout
+        } else if node_results
+            .values()
+            .any(|r| !matches!(r.status, ExecutionStatus::Completed | ExecutionStatus::Skipped))
+        {
+            ExecutionStatus::Failed
+        } else {
+            ExecutionStatus::Completed
+        };
+
+        // Workflow output is the output of every terminal node
+        let output = WorkflowData::Object(
+            workflow
+                .nodes
+                .keys()
+                .filter(|id| !incoming.values().flatten().any(|(from, _)| from == *id))
+                .filter_map(|id| outputs.get(id).map(|o| (id.clone(), o.clone())))
+                .collect(),
+        );
+
+        let ended_at = current_timestamp();
+        self.emit(WorkflowEvent::WorkflowCompleted {
+            workflow_id: workflow.id.clone(),
+            execution_id: execution_id.clone(),
+            timestamp: ended_at,
+            duration_ms: ended_at.saturating_sub(started_at),
+            success: status == ExecutionStatus::Completed,
+            metadata: BTreeMap::new(),
+        })
+        .await;
+
+        Ok(ExecutionResult {
+            execution_id,
+            status,
+            output,
+            started_at,
+            ended_at: Some(ended_at),
+            duration: Some(ended_at.saturating_sub(started_at)),
+            node_results,
+        })
+    }
+}
+
+impl WorkflowExecutor {
+    /// Publish a lifecycle event if a publisher is set
+    async fn emit(&self, event: WorkflowEvent) {
+        if let Some(events) = &self.events {
+            events.publish(&event).await;
+        }
+    }
+
+    /// Prepare the handler for a sub-workflow node nested below `stack`
+    fn sub_workflow(&self, workflow_id: &WorkflowId, stack: &[WorkflowId]) -> Result<Arc<SubWorkflow>> {
+        if stack.contains(workflow_id) {
+            return Err(WorkflowError::InvalidWorkflow {
+                reason: alloc::format!("recursive sub-workflow: {} -> {}", stack.join(" -> "), workflow_id),
+            });
+        }
+
+        let workflow = self
+            .workflows
+            .get(workflow_id)
+            .cloned()
+            .ok_or_else(|| WorkflowError::WorkflowNotFound { id: workflow_id.clone() })?;
+        let mut stack = stack.to_vec();
+        stack.push(workflow_id.clone());
+
+        Ok(Arc::new(SubWorkflow {
+            executor: self.clone(),
+            workflow,
+            stack,
+            last_execution: std::sync::Mutex::new(None),
+        }))
+    }
+}
+
+impl WorkflowExecutor {
+    /// Prepare a map node over the array `over` refers to among the
+    /// `outputs` produced so far
+    fn map_node(
+        &self,
+        node: &WorkflowNode,
+        over: &NodeOutputRef,
+        sub_node: &NodeId,
+        outputs: &BTreeMap<NodeId, WorkflowData>,
+    ) -> Result<Arc<MapNode>> {
+        let items = match over.resolve(outputs) {
+            Some(WorkflowData::Array(items)) => items.clone(),
+            Some(_) => {
+                return Err(WorkflowError::InvalidWorkflow {
+                    reason: alloc::format!("map node '{}': {} is not an array", node.id, describe(over)),
+                })
+            }
+            None => {
+                return Err(WorkflowError::InvalidWorkflow {
+                    reason: alloc::format!("map node '{}': no output at {}", node.id, describe(over)),
+                })
+            }
+        };
+
+        Ok(Arc::new(MapNode {
+            handler: self.handlers.get(sub_node).cloned(),
+            source: over.node_id.clone(),
+            items,
+            timeout: node.timeout,
+            retry_policy: node.retry_policy.clone(),
+            max_parallel: node.max_parallel.unwrap_or(crate::DEFAULT_MAP_CONCURRENCY).max(1),
+        }))
+    }
+}
+
+impl core::fmt::Debug for WorkflowExecutor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WorkflowExecutor")
+            .field("handlers", &self.handlers.keys().collect::<Vec<_>>())
+            .field("workflows", &self.workflows.keys().collect::<Vec<_>>())
+            .field("events", &self.events.is_some())
+            .finish()
+    }
+}
+
+/// How a node is run
+enum Step {
+    /// By its registered handler, if any
+    Handler,
+    /// As a child workflow
+    SubWorkflow(Arc<SubWorkflow>),
+    /// Once per element of an array
+    Map(Arc<MapNode>),
+}
+
+/// Checkpoint of a running execution and where it is saved
+struct Checkpointing<'a> {
+    store: &'a dyn CheckpointStore,
+    checkpoint: ExecutionCheckpoint,
+}
+
+impl Checkpointing<'_> {
+    fn save(&mut self, node_results: &BTreeMap<NodeId, NodeResult>, running: &[NodeId]) -> Result<()> {
+        self.checkpoint.node_results = node_results.clone();
+        self.checkpoint.running = running.to_vec();
+        self.store.save_checkpoint(self.checkpoint.clone())
+    }
+}
+
+/// Handler running a child workflow as a single node
+struct SubWorkflow {
+    executor: WorkflowExecutor,
+    workflow: Arc<Workflow>,
+    /// Workflows executing above the child, outermost first, ending with it
+    stack: Vec<WorkflowId>,
+    /// Child execution of the latest attempt
+    last_execution: std::sync::Mutex<Option<ExecutionResult>>,
+}
+
+#[async_trait::async_trait]
+impl NodeHandler for SubWorkflow {
+    async fn execute(&self, input: NodeInput) -> Result<WorkflowData> {
+        let execution_id = alloc::format!("{}/{}", input.execution_id, input.node.id);
+        let result = self
+            .executor
+            .execute_nested(execution_id, &self.workflow, input.context, input.inputs, self.stack.clone(), None)
+            .await?;
+
+        let outcome = match result.status {
+            ExecutionStatus::Completed => Ok(result.output.clone()),
+            ref status => Err(WorkflowError::NodeExecutionFailed {
+                node_id: input.node.id,
+                execution_id: result.execution_id.clone(),
+                reason: alloc::format!("sub-workflow '{}' ended {:?}", self.workflow.id, status),
+            }),
+        };
+        *self.last_execution.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+        outcome
+    }
+}
+
+/// Handler running one sub-task per element of an array
+struct MapNode {
+    /// Handler of the sub-task
+    handler: Option<Arc<dyn NodeHandler>>,
+    /// Node the array came from; each sub-task gets its element as the
+    /// output of this node
+    source: NodeId,
+    items: Vec<WorkflowData>,
+    /// Timeout of each sub-task
+    timeout: Option<core::time::Duration>,
+    /// Retry policy of each sub-task
+    retry_policy: RetryPolicy,
+    /// Sub-tasks allowed to run at once
+    max_parallel: usize,
+}
+
+#[async_trait::async_trait]
+impl NodeHandler for MapNode {
+    async fn execute(&self, input: NodeInput) -> Result<WorkflowData> {
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut pending = self.items.iter().enumerate();
+        let mut outputs = alloc::vec![WorkflowData::Null; self.items.len()];
+        loop {
+            // Only `max_parallel` elements are in flight; the next one is
+            // spawned as an earlier one finishes
+            while tasks.len() < self.max_parallel {
+                let Some((index, item)) = pending.next() else {
+                    break;
+                };
+                // The map node's schemas apply to the collected array
+                let mut node = input.node.clone();
+                node.id = alloc::format!("{}[{}]", input.node.id, index);
+                node.node_type = NodeType::Task;
+                node.timeout = self.timeout;
+                node.retry_policy = self.retry_policy.clone();
+                node.input_schema = None;
+                node.output_schema = None;
+                let element = NodeInput {
+                    execution_id: input.execution_id.clone(),
+                    node,
+                    inputs: BTreeMap::from([(self.source.clone(), item.clone())]),
+                    context: input.context.clone(),
+                };
+                let handler = self.handler.clone();
+                tasks.spawn(async move { (index, run_node(handler, element).await) });
+            }
+
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            let (index, result) = joined.map_err(|e| WorkflowError::NodeExecutionFailed {
+                node_id: input.node.id.clone(),
+                execution_id: input.execution_id.clone(),
+                reason: alloc::format!("map element task panicked: {}", e),
+            })?;
+            if result.status != ExecutionStatus::Completed {
+                // Dropping the join set cancels the remaining elements
+                return Err(WorkflowError::NodeExecutionFailed {
+                    node_id: result.node_id,
+                    execution_id: input.execution_id.clone(),
+                    reason: result.error.unwrap_or_else(|| alloc::format!("ended {:?}", result.status)),
+                });
+            }
+            outputs[index] = result.output;
+        }
+        Ok(WorkflowData::Array(outputs))
+    }
+}
+
+/// Run a map node. Retries and timeouts apply to each element rather than
+/// to the map as a whole.
+async fn run_map(map: Arc<MapNode>, mut input: NodeInput) -> NodeResult {
+    input.node.timeout = None;
+    input.node.retry_policy.max_attempts = 1;
+    run_node(Some(map), input).await
+}
+
+/// `over` as written in error messages
+fn describe(over: &NodeOutputRef) -> alloc::string::String {
+    core::iter::once(alloc::format!("'{}'", over.node_id))
+        .chain(over.path.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Run a sub-workflow node, attaching the child execution to its result
+async fn run_sub_workflow(sub_workflow: Arc<SubWorkflow>, input: NodeInput) -> NodeResult {
+    let mut result = run_node(Some(sub_workflow.clone()), input).await;
+    result.sub_execution = sub_workflow
+        .last_execution
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .map(alloc::boxed::Box::new);
+    result
+}
+
+/// Incoming edges of every node with their conditions. Declared
+/// dependencies without a matching edge count as unconditional edges.
+fn incoming_edges(workflow: &Workflow) -> BTreeMap<NodeId, Vec<(NodeId, Option<WorkflowCondition>)>> {
+    let mut incoming: BTreeMap<NodeId, Vec<(NodeId, Option<WorkflowCondition>)>> =
+        workflow.nodes.keys().map(|id| (id.clone(), Vec::new())).collect();
+
+    for edge in &workflow.edges {
+        if let Some(edges) = incoming.get_mut(&edge.to) {
+            edges.push((edge.from.clone(), edge.condition.clone()));
+        }
+    }
+    for (id, node) in &workflow.nodes {
+        let edges = incoming.get_mut(id).expect("every node has an entry");
+        for dependency in &node.dependencies {
+            if !edges.iter().any(|(from, _)| from == dependency) {
+                edges.push((dependency.clone(), None));
+            }
+        }
+    }
+
+    incoming
+}
+
+/// Decide whether a node whose predecessors have all finished runs.
+///
+/// Returns the sources of the traversed edges, or the status and reason
+/// the node is resolved with instead of running.
+fn route(
+    incoming: &[(NodeId, Option<WorkflowCondition>)],
+    results: &BTreeMap<NodeId, NodeResult>,
+) -> core::result::Result<Vec<NodeId>, (ExecutionStatus, alloc::string::String)> {
+    let mut traversed = Vec::new();
+    for (from, condition) in incoming {
+        let source = &results[from];
+        let condition = condition.as_ref().unwrap_or(&WorkflowCondition::OnSuccess);
+        match condition.evaluate(&source.status, &source.output) {
+            Ok(true) => traversed.push(from.clone()),
+            Ok(false) => {
+                let failed = matches!(
+                    source.status,
+                    ExecutionStatus::Failed | ExecutionStatus::Timeout | ExecutionStatus::Cancelled
+                );
+                if failed {
+                    return Err((ExecutionStatus::Cancelled, "skipped: upstream node failed".into()));
+                }
+            }
+            Err(error) => {
+                return Err((ExecutionStatus::Failed, alloc::format!("condition on edge from '{}': {}", from, error)));
+            }
+        }
+    }
+
+    if incoming.is_empty() || !traversed.is_empty() {
+        Ok(traversed)
+    } else {
+        Err((ExecutionStatus::Skipped, "skipped: no matching branch".into()))
+    }
+}
+
+/// Lifecycle event of a node that ran
+fn node_finished(workflow_id: WorkflowId, execution_id: ExecutionId, result: &NodeResult) -> WorkflowEvent {
+    let timestamp = result.ended_at.unwrap_or_else(current_timestamp);
+    let metadata = BTreeMap::from([("workflow.attempts".into(), result.attempts.to_string())]);
+    match result.status {
+        ExecutionStatus::Completed => WorkflowEvent::NodeCompleted {
+            workflow_id,
+            execution_id,
+            node_id: result.node_id.clone(),
+            timestamp,
+            duration_ms: timestamp.saturating_sub(result.started_at),
+            success: true,
+            metadata,
+        },
+        _ => WorkflowEvent::NodeFailed {
+            workflow_id,
+            execution_id,
+            node_id: result.node_id.clone(),
+            timestamp,
+            error: result.error.clone().unwrap_or_default(),
+            metadata,
+        },
+    }
+}
+
+/// Output of a node that has no handler
+fn default_output(input: &NodeInput) -> WorkflowData {
+    if input.node.node_type != NodeType::Decision {
+        return WorkflowData::Null;
+    }
+    match input.inputs.values().next() {
+        Some(only) if input.inputs.len() == 1 => only.clone(),
+        _ => WorkflowData::Object(input.inputs.clone().into_iter().collect()),
+    }
+}
+
+/// Run a single node, applying its timeout and retry policy
+async fn run_node(handler: Option<Arc<dyn NodeHandler>>, input: NodeInput) -> NodeResult {
+    let node_id = input.node.id.clone();
+    let policy = input.node.retry_policy.clone();
+    let max_attempts = policy.max_attempts.max(1);
+    let started_at = current_timestamp();
+    let mut delay = policy.delay;
+    let mut attempts = 0;
+    let mut last_error = None;
+
+    while attempts < max_attempts {
+        attempts += 1;
+
+        let outcome = match &handler {
+            None => Ok(default_output(&input)),
+            Some(handler) => match input.node.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, handler.execute(input.clone()))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(WorkflowError::NodeExecutionFailed {
+                            node_id: node_id.clone(),
+                            execution_id: input.execution_id.clone(),
+                            reason: alloc::format!("timed out after {:?}", timeout),
+                        })
+                    }),
+                None => handler.execute(input.clone()).await,
+            },
+        };
+        let outcome = outcome.and_then(|output| check_output(&input.node, output));
+
+        match outcome {
+            Ok(output) => {
+                return NodeResult {
+                    node_id,
+                    status: ExecutionStatus::Completed,
+                    output,
+                    error: None,
+                    attempts,
+                    started_at,
+                    ended_at: Some(current_timestamp()),
+                    sub_execution: None,
+                };
+            }
+            Err(error) => {
+                last_error = Some(alloc::format!("{}", error));
+                if attempts < max_attempts {
+                    tokio::time::sleep(delay).await;
+                    delay = delay.mul_f64(policy.backoff_multiplier).min(policy.max_delay);
+                }
+            }
+        }
+    }
+
+    NodeResult {
+        node_id,
+        status: ExecutionStatus::Failed,
+        output: WorkflowData::Null,
+        error: last_error,
+        attempts,
+        started_at,
+        ended_at: Some(current_timestamp()),
+        sub_execution: None,
+    }
+}
+
+/// Check every input of a node against its input schema
+fn check_inputs(node: &WorkflowNode, inputs: &BTreeMap<NodeId, WorkflowData>) -> Result<()> {
+    let Some(schema) = &node.input_schema else { return Ok(()) };
+    for (from, input) in inputs {
+        schema.validate(input).map_err(|violation| WorkflowError::SchemaMismatch {
+            node_id: node.id.clone(),
+            data: alloc::format!("input from '{}'", from),
+            path: violation.path,
+            reason: violation.reason,
+        })?;
+    }
+    Ok(())
+}
+
+/// Check a node's output against its output schema
+fn check_output(node: &WorkflowNode, output: WorkflowData) -> Result<WorkflowData> {
+    let Some(schema) = &node.output_schema else { return Ok(output) };
+    match schema.validate(&output) {
+        Ok(()) => Ok(output),
+        Err(violation) => Err(WorkflowError::SchemaMismatch {
+            node_id: node.id.clone(),
+            data: "output".into(),
+            path: violation.path,
+            reason: violation.reason,
+        }),
+    }
+}
+
+/// Current wall-clock time in milliseconds
+fn current_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::RetryPolicy;
+    use core::time::Duration;
+
+    fn no_retry() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Checkpoint store that keeps nothing
+    struct Discard;
+
+    impl CheckpointStore for Discard {
+        fn save_checkpoint(&self, _checkpoint: ExecutionCheckpoint) -> Result<()> {
+            Ok(())
+        }
+
+        fn remove_checkpoint(&self, _execution_id: &ExecutionId) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_outputs_downstream() {
+        let workflow = Workflow::builder("pipeline")
+            .add_node(WorkflowNode::new("a"))
+            .add_node(WorkflowNode::new("b"))
+            .connect("a", "b")
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        executor.register_handler("a", Arc::new(|_input: NodeInput| async { Ok(WorkflowData::Int(20)) }));
+        executor.register_handler(
+            "b",
+            Arc::new(|input: NodeInput| async move {
+                match input.inputs.get("a") {
+                    Some(WorkflowData::Int(v)) => Ok(WorkflowData::Int(v + 1)),
+                    _ => Ok(WorkflowData::Null),
+                }
+            }),
+        );
+
+        let result = executor.execute("exec-1".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.node_results["b"].output, WorkflowData::Int(21));
+    }
+
+    #[tokio::test]
+    async fn test_failed_node_skips_dependents() {
+        let workflow = Workflow::builder("failing")
+            .add_node(WorkflowNode::new("a").retry_policy(no_retry()))
+            .add_node(WorkflowNode::new("b"))
+            .connect("a", "b")
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        executor.register_handler(
+            "a",
+            Arc::new(|_input: NodeInput| async {
+                Err(WorkflowError::InvalidWorkflow { reason: "boom".into() })
+            }),
+        );
+
+        let result = executor.execute("exec-2".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.node_results["a"].status, ExecutionStatus::Failed);
+        assert_eq!(result.node_results["b"].status, ExecutionStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_node_timeout() {
+        let workflow = Workflow::builder("slow")
+            .add_node(
+                WorkflowNode::new("slow")
+                    .timeout(Duration::from_millis(10))
+                    .retry_policy(no_retry()),
+            )
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        executor.register_handler(
+            "slow",
+            Arc::new(|_input: NodeInput| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(WorkflowData::Null)
+            }),
+        );
+
+        let result = executor.execute("exec-3".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.node_results["slow"].status, ExecutionStatus::Failed);
+        assert!(result.node_results["slow"].error.as_ref().unwrap().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_workflow_timeout_keeps_completed_outputs() {
+        let workflow = Workflow::builder("deadline")
+            .add_node(WorkflowNode::new("fetch"))
+            .add_node(WorkflowNode::new("transform"))
+            .add_node(WorkflowNode::new("store"))
+            .connect("fetch", "transform")
+            .connect("transform", "store")
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        executor.set_timeout(Duration::from_millis(50));
+        executor.register_handler("fetch", Arc::new(|_input: NodeInput| async { Ok(WorkflowData::Int(7)) }));
+        executor.register_handler(
+            "transform",
+            Arc::new(|_input: NodeInput| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(WorkflowData::Null)
+            }),
+        );
+
+        let started = std::time::Instant::now();
+        let result = executor.execute("exec-timeout".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(result.status, ExecutionStatus::Timeout);
+        assert_eq!(result.node_results["fetch"].status, ExecutionStatus::Completed);
+        assert_eq!(result.node_results["fetch"].output, WorkflowData::Int(7));
+        assert_eq!(result.node_results["transform"].status, ExecutionStatus::Timeout);
+        assert_eq!(result.node_results["store"].status, ExecutionStatus::Cancelled);
+        assert_eq!(result.node_results["store"].error.as_deref(), Some("skipped: workflow timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_decision_routes_to_matching_branch() {
+        let workflow = Workflow::builder("model-release")
+            .add_node(WorkflowNode::new("evaluate"))
+            .add_node(WorkflowNode::new("decide").node_type(NodeType::Decision))
+            .add_node(WorkflowNode::new("deploy"))
+            .add_node(WorkflowNode::new("retrain"))
+            .connect("evaluate", "decide")
+            .connect_with_condition("decide", "deploy", WorkflowCondition::Expression("output.accuracy > 0.9".into()))
+            .connect_with_condition("decide", "retrain", WorkflowCondition::Expression("output.accuracy <= 0.9".into()))
+            .build();
+
+        for (accuracy, taken, skipped) in [(0.95, "deploy", "retrain"), (0.7, "retrain", "deploy")] {
+            let runs = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let mut executor = WorkflowExecutor::new();
+            executor.register_handler(
+                "evaluate",
+                Arc::new(move |_input: NodeInput| async move {
+                    Ok(WorkflowData::Object(BTreeMap::from([("accuracy".into(), WorkflowData::Float(accuracy))])))
+                }),
+            );
+            for branch in ["deploy", "retrain"] {
+                let runs = runs.clone();
+                executor.register_handler(
+                    branch,
+                    Arc::new(move |input: NodeInput| {
+                        runs.lock().unwrap().push(input.node.id.clone());
+                        async { Ok(WorkflowData::Bool(true)) }
+                    }),
+                );
+            }
+
+            let result = executor.execute("exec-4".into(), &workflow, ExecutionContext::new()).await.unwrap();
+            assert_eq!(result.status, ExecutionStatus::Completed);
+            assert_eq!(*runs.lock().unwrap(), vec![taken.to_string()]);
+            assert_eq!(result.node_results[taken].status, ExecutionStatus::Completed);
+            assert_eq!(result.node_results[skipped].status, ExecutionStatus::Skipped);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sub_workflow_output_flows_back() {
+        let child = Workflow::builder("double-then-increment")
+            .id("child".into())
+            .add_node(WorkflowNode::new("double"))
+            .add_node(WorkflowNode::new("increment"))
+            .connect("double", "increment")
+            .build();
+        let parent = Workflow::builder("parent")
+            .add_node(WorkflowNode::new("prepare"))
+            .add_node(WorkflowNode::new("compute").node_type(NodeType::SubWorkflow { workflow_id: "child".into() }))
+            .add_node(WorkflowNode::new("report"))
+            .connect("prepare", "compute")
+            .connect("compute", "report")
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        executor.register_workflow(child);
+        executor.register_handler("prepare", Arc::new(|_input: NodeInput| async { Ok(WorkflowData::Int(20)) }));
+        executor.register_handler(
+            "double",
+            Arc::new(|input: NodeInput| async move {
+                // The child's entry node sees the sub-workflow node's inputs
+                match input.inputs.get("prepare") {
+                    Some(WorkflowData::Int(v)) => Ok(WorkflowData::Int(v * 2)),
+                    other => Err(WorkflowError::InvalidWorkflow { reason: alloc::format!("unexpected input {:?}", other) }),
+                }
+            }),
+        );
+        executor.register_handler(
+            "increment",
+            Arc::new(|input: NodeInput| async move {
+                match input.inputs.get("double") {
+                    Some(WorkflowData::Int(v)) => Ok(WorkflowData::Int(v + 1)),
+                    _ => Ok(WorkflowData::Null),
+                }
+            }),
+        );
+        executor.register_handler(
+            "report",
+            Arc::new(|input: NodeInput| async move { Ok(input.inputs["compute"].clone()) }),
+        );
+
+        let result = executor.execute("exec-5".into(), &parent, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        let child_output = WorkflowData::Object(BTreeMap::from([("increment".into(), WorkflowData::Int(41))]));
+        assert_eq!(result.node_results["report"].output, child_output);
+
+        let sub_execution = result.node_results["compute"].sub_execution.as_ref().unwrap();
+        assert_eq!(sub_execution.execution_id, "exec-5/compute");
+        assert_eq!(sub_execution.status, ExecutionStatus::Completed);
+        assert_eq!(sub_execution.node_results["double"].output, WorkflowData::Int(40));
+    }
+
+    #[tokio::test]
+    async fn test_recursive_sub_workflow_fails() {
+        let outer = Workflow::builder("outer")
+            .id("outer".into())
+            .add_node(
+                WorkflowNode::new("call-inner")
+                    .node_type(NodeType::SubWorkflow { workflow_id: "inner".into() })
+                    .retry_policy(no_retry()),
+            )
+            .build();
+        let inner = Workflow::builder("inner")
+            .id("inner".into())
+            .add_node(WorkflowNode::new("call-outer").node_type(NodeType::SubWorkflow { workflow_id: "outer".into() }))
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        executor.register_workflow(outer.clone());
+        executor.register_workflow(inner);
+
+        let result = executor.execute("exec-6".into(), &outer, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+
+        let inner_execution = result.node_results["call-inner"].sub_execution.as_ref().unwrap();
+        let recursive = &inner_execution.node_results["call-outer"];
+        assert_eq!(recursive.status, ExecutionStatus::Failed);
+        assert_eq!(recursive.attempts, 0);
+        assert!(recursive.error.as_ref().unwrap().contains("outer -> inner -> outer"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_reruns_only_idempotent_interrupted_nodes() {
+        let workflow = Workflow::builder("fan-out")
+            .add_node(WorkflowNode::new("a"))
+            .add_node(WorkflowNode::new("retryable").idempotent(true))
+            .add_node(WorkflowNode::new("charge"))
+            .connect("a", "retryable")
+            .connect("a", "charge")
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        executor.register_handler("a", Arc::new(|_input: NodeInput| async { panic!("a already finished") }));
+        executor.register_handler("retryable", Arc::new(|_input: NodeInput| async { Ok(WorkflowData::Int(1)) }));
+        executor.register_handler("charge", Arc::new(|_input: NodeInput| async { panic!("charge must not run twice") }));
+
+        let completed = NodeResult {
+            node_id: "a".into(),
+            status: ExecutionStatus::Completed,
+            output: WorkflowData::Int(7),
+            error: None,
+            attempts: 1,
+            started_at: 0,
+            ended_at: Some(0),
+            sub_execution: None,
+        };
+        let checkpoint = ExecutionCheckpoint {
+            execution_id: "exec-1".into(),
+            workflow: workflow.clone(),
+            context: ExecutionContext::new(),
+            node_results: BTreeMap::from([("a".into(), completed)]),
+            running: alloc::vec!["retryable".into(), "charge".into()],
+            started_at: 0,
+        };
+
+        let result = executor.resume(checkpoint, &Discard).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.node_results["a"].output, WorkflowData::Int(7));
+        assert_eq!(result.node_results["retryable"].status, ExecutionStatus::Completed);
+        assert_eq!(result.node_results["charge"].status, ExecutionStatus::Failed);
+        assert_eq!(result.node_results["charge"].attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_fan_out_respects_max_parallel() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut builder = Workflow::builder("fan-out")
+            .add_node(WorkflowNode::new("fan").node_type(NodeType::Parallel).max_parallel(3));
+        let mut executor = WorkflowExecutor::new();
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..10 {
+            let id = alloc::format!("branch-{}", i);
+            builder = builder.add_node(WorkflowNode::new(&id)).connect("fan", &id);
+
+            let (running, peak) = (running.clone(), peak.clone());
+            executor.register_handler(
+                &id,
+                Arc::new(move |_input: NodeInput| {
+                    let (running, peak) = (running.clone(), peak.clone());
+                    async move {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(WorkflowData::Null)
+                    }
+                }),
+            );
+        }
+
+        let result = executor
+            .execute("exec-1".into(), &builder.build(), ExecutionContext::new())
+            .await
+            .unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.node_results.len(), 11);
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_map_node_fans_out_over_array() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        let workflow = Workflow::builder("doubling")
+            .add_node(WorkflowNode::new("numbers"))
+            .add_node(
+                WorkflowNode::new("double")
+                    .node_type(NodeType::Map {
+                        over: NodeOutputRef::new("numbers").field("values"),
+                        sub_node: "times-two".into(),
+                    })
+                    .max_parallel(2),
+            )
+            .add_node(WorkflowNode::new("sum"))
+            .connect("numbers", "double")
+            .connect("double", "sum")
+            .build();
+        let values = |values: Vec<i64>| {
+            WorkflowData::Object(BTreeMap::from([(
+                "values".into(),
+                WorkflowData::Array(values.into_iter().map(WorkflowData::Int).collect()),
+            )]))
+        };
+
+        let executor_for = |numbers: WorkflowData| {
+            let mut executor = WorkflowExecutor::new();
+            let running = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            executor.register_handler(
+                "numbers",
+                Arc::new(move |_input: NodeInput| {
+                    let numbers = numbers.clone();
+                    async move { Ok(numbers) }
+                }),
+            );
+            let (counter, max) = (running.clone(), peak.clone());
+            executor.register_handler(
+                "times-two",
+                Arc::new(move |input: NodeInput| {
+                    let (running, peak) = (counter.clone(), max.clone());
+                    async move {
+                        peak.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        match input.inputs.get("numbers") {
+                            Some(WorkflowData::Int(v)) => Ok(WorkflowData::Int(v * 2)),
+                            other => Err(WorkflowError::InvalidWorkflow {
+                                reason: alloc::format!("not a number: {:?}", other),
+                            }),
+                        }
+                    }
+                }),
+            );
+            executor.register_handler(
+                "sum",
+                Arc::new(|input: NodeInput| async move {
+                    match input.inputs.get("double") {
+                        Some(WorkflowData::Array(items)) => Ok(WorkflowData::Int(
+                            items.iter().map(|item| if let WorkflowData::Int(v) = item { *v } else { 0 }).sum(),
+                        )),
+                        _ => Ok(WorkflowData::Null),
+                    }
+                }),
+            );
+            (executor, peak)
+        };
+
+        let (executor, peak) = executor_for(values(alloc::vec![1, 2, 3, 4, 5]));
+        let result = executor.execute("exec-1".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(
+            result.node_results["double"].output,
+            WorkflowData::Array([2, 4, 6, 8, 10].into_iter().map(WorkflowData::Int).collect())
+        );
+        assert_eq!(result.node_results["sum"].output, WorkflowData::Int(30));
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+        let (executor, peak) = executor_for(values(Vec::new()));
+        let result = executor.execute("exec-2".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.node_results["double"].output, WorkflowData::Array(Vec::new()));
+        assert_eq!(result.node_results["sum"].output, WorkflowData::Int(0));
+        assert_eq!(peak.load(Ordering::SeqCst), 0);
+
+        let (executor, _) = executor_for(WorkflowData::Int(5));
+        let result = executor.execute("exec-3".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.node_results["double"].status, ExecutionStatus::Failed);
+        assert_eq!(result.node_results["sum"].status, ExecutionStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_schema_mismatch_fails_node() {
+        let score = || {
+            crate::schema::DataSchema::of_type("object")
+                .property("score", crate::schema::DataSchema::of_type("number"))
+                .required("score")
+        };
+        let workflow = Workflow::builder("scored")
+            .add_node(WorkflowNode::new("a").output_schema(score()).retry_policy(no_retry()))
+            .add_node(WorkflowNode::new("b"))
+            .add_node(WorkflowNode::new("c").input_schema(score()))
+            .connect("a", "b")
+            .connect("b", "c")
+            .build();
+
+        let mut executor = WorkflowExecutor::new();
+        let score_of = |score: WorkflowData| WorkflowData::Object(BTreeMap::from([("score".into(), score)]));
+        executor.register_handler(
+            "a",
+            Arc::new(move |_input: NodeInput| async move { Ok(score_of(WorkflowData::String("high".into()))) }),
+        );
+
+        let result = executor.execute("exec-1".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.node_results["a"].status, ExecutionStatus::Failed);
+        assert_eq!(
+            result.node_results["a"].error.as_deref(),
+            Some("Node 'a' output does not match its schema at $.score: expected number, found string")
+        );
+        assert_eq!(result.node_results["b"].status, ExecutionStatus::Cancelled);
+
+        // b's output reaches c, whose input schema it does not match
+        executor.register_handler("a", Arc::new(move |_input: NodeInput| async move { Ok(score_of(WorkflowData::Float(0.9))) }));
+        let result = executor.execute("exec-2".into(), &workflow, ExecutionContext::new()).await.unwrap();
+        assert_eq!(result.node_results["b"].status, ExecutionStatus::Completed);
+        assert_eq!(result.node_results["c"].status, ExecutionStatus::Failed);
+        assert_eq!(result.node_results["c"].attempts, 0);
+        assert_eq!(
+            result.node_results["c"].error.as_deref(),
+            Some("Node 'c' input from 'b' does not match its schema at $: expected object, found null")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_events_reach_event_bus() {
+        let mut eventbus = frys_eventbus::EventBus::new(frys_eventbus::EventBusConfig::default())
+            .await
+            .unwrap();
+        let subscriber = eventbus.subscribe("*", frys_eventbus::Filter::default()).await.unwrap();
+
+        let workflow = Workflow::builder("pipeline")
+            .add_node(WorkflowNode::new("a"))
+            .add_node(WorkflowNode::new("b"))
+            .connect("a", "b")
+            .build();
+        let mut executor = WorkflowExecutor::new();
+        executor.set_event_publisher(Arc::new(EventBusPublisher::new(eventbus)));
+        executor.execute("exec-1".into(), &workflow, ExecutionContext::new()).await.unwrap();
+
+        let mut seen = Vec::new();
+        while let Some(event) = subscriber.try_receive() {
+            use crate::events::event_headers;
+            assert_eq!(event.headers.get(event_headers::EXECUTION_ID).unwrap(), "exec-1");
+            if event.topic.ends_with(".completed") {
+                assert!(event.headers.get(event_headers::DURATION_MS).is_some());
+            }
+            seen.push((event.topic.clone(), event.headers.get(event_headers::NODE_ID).cloned()));
+        }
+
+        let node = |id: &str| Some(alloc::string::String::from(id));
+        assert_eq!(
+            seen,
+            [
+                ("workflow.started".into(), None),
+                ("node.started".into(), node("a")),
+                ("node.completed".into(), node("a")),
+                ("node.started".into(), node("b")),
+                ("node.completed".into(), node("b")),
+                ("workflow.completed".into(), None),
+            ]
+        );
+    }
+}
@@ -85,8 +85,12 @@
 pub mod core;
 pub mod engine;
 pub mod executor;
+pub mod condition;
+pub mod schema;
+pub mod scheduler;
 pub mod nodes;
 pub mod flows;
+#[cfg(feature = "dsl")]
 pub mod dsl;
 pub mod concurrent;
 pub mod designer;
@@ -99,6 +103,9 @@ pub mod architecture;
 pub use core::*;
 pub use engine::*;
 pub use executor::*;
+pub use condition::*;
+pub use schema::*;
+pub use scheduler::*;
 pub use architecture::*;
 pub use nodes::*;
 pub use flows::*;
@@ -123,6 +130,8 @@ pub const DEFAULT_WORKFLOW_TIMEOUT: u64 = 300; // 5 minutes
 pub const MAX_WORKFLOW_NODES: usize = 1000;
 pub const MAX_CONCURRENT_WORKFLOWS: usize = 10000;
 pub const DEFAULT_WORKER_POOL_SIZE: usize = 4;
+pub const DEFAULT_PRIORITY_AGING_SECS: u64 = 10;
+pub const DEFAULT_MAP_CONCURRENCY: usize = 8;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,521 @@
+//! Time-based triggering of workflow executions
+//!
+//! A [`WorkflowScheduler`] holds workflows registered with a [`Schedule`]
+//! and hands out the runs that are due at the current time of its
+//! [`Clock`]. The engine registers schedules through
+//! [`schedule_workflow`](crate::WorkflowEngine::schedule_workflow) and
+//! executes due runs from the background task started by
+//! [`start_scheduler`](crate::WorkflowEngine::start_scheduler).
+//!
+//! Cron expressions have the five standard fields and are evaluated in UTC:
+//!
+//! ```text
+//! minute  hour  day-of-month  month  day-of-week
+//! */15    9-17  *             *      1-5
+//! ```
+//!
+//! Each field is `*`, a value, a range `a-b` or a step `*/n` / `a-b/n`,
+//! or a comma-separated list of these. Day of week runs from 0 (Sunday) to
+//! 6, and 7 is accepted for Sunday as well. As in classic cron, when both
+//! day fields are restricted a day matching either one fires; a field
+//! matching every value, such as `*/1` or `1-31`, is not restricted.
+
+use crate::core::{Workflow, WorkflowId};
+use crate::{Result, WorkflowError};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+use std::sync::Mutex;
+
+/// Identifier of a registered schedule
+pub type ScheduleId = String;
+
+/// When a scheduled workflow runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// Five-field cron expression, evaluated in UTC
+    Cron(String),
+    /// Fixed interval; the first run is one interval after scheduling
+    Interval(Duration),
+}
+
+/// What to do when a run comes due while the previous one is still active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// Skip the new run
+    #[default]
+    Skip,
+    /// Start the new run alongside the active one
+    Allow,
+}
+
+/// Source of the current time for the scheduler
+pub trait Clock: Send + Sync + core::fmt::Debug {
+    /// Milliseconds since the Unix epoch
+    fn now_millis(&self) -> u64;
+}
+
+/// Wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64)
+    }
+}
+
+/// Clock that only moves when told to, for tests and simulations
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: AtomicU64,
+}
+
+impl ManualClock {
+    /// Create a clock reading `millis` since the epoch
+    pub fn new(millis: u64) -> Self {
+        Self {
+            now: AtomicU64::new(millis),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: Duration) {
+        self.now.fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    /// Set the clock to `millis` since the epoch
+    pub fn set(&self, millis: u64) {
+        self.now.store(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpression {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronExpression {
+    /// Parse a five-field cron expression
+    pub fn parse(expression: &str) -> Result<Self> {
+        let invalid = |reason: String| WorkflowError::InvalidSchedule {
+            schedule: expression.into(),
+            reason,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(invalid(alloc::format!("expected 5 fields, found {}", fields.len())));
+        };
+
+        let mut weekday_bits = parse_field(weekdays, 0, 7).map_err(invalid)?;
+        // Both 0 and 7 mean Sunday
+        if weekday_bits & (1 << 7) != 0 {
+            weekday_bits = (weekday_bits | 1) & !(1 << 7);
+        }
+
+        let day_bits = parse_field(days, 1, 31).map_err(invalid)?;
+
+        // A day field matching every value, however written (`*`, `*/1`,
+        // `1-31`), leaves the choice of day to the other one
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59).map_err(invalid)?,
+            hours: parse_field(hours, 0, 23).map_err(invalid)?,
+            days: day_bits,
+            months: parse_field(months, 1, 12).map_err(invalid)?,
+            weekdays: weekday_bits,
+            any_day: day_bits == all_values(1, 31),
+            any_weekday: weekday_bits == all_values(0, 6),
+        })
+    }
+
+    /// First time the expression matches strictly after `millis`, or
+    /// `None` if it never matches within the next few years
+    pub fn next_after(&self, millis: u64) -> Option<u64> {
+        use chrono::{Datelike, NaiveDate, TimeZone, Timelike, Utc};
+
+        let now = Utc.timestamp_millis_opt(i64::try_from(millis).ok()?).single()?;
+        let mut t = now.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let limit = t + chrono::Duration::days(5 * 366);
+
+        while t < limit {
+            if !has(self.months, t.month()) {
+                let (year, month) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?.and_utc();
+            } else if !self.day_matches(t.day(), t.weekday().num_days_from_sunday()) {
+                t = t.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?.and_utc();
+            } else if !has(self.hours, t.hour()) {
+                t = t.with_minute(0)? + chrono::Duration::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t += chrono::Duration::minutes(1);
+            } else {
+                return u64::try_from(t.timestamp_millis()).ok();
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, day: u32, weekday: u32) -> bool {
+        match (self.any_day, self.any_weekday) {
+            (false, false) => has(self.days, day) || has(self.weekdays, weekday),
+            (false, true) => has(self.days, day),
+            (true, false) => has(self.weekdays, weekday),
+            (true, true) => true,
+        }
+    }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Bit set of every value from `min` to `max`
+fn all_values(min: u32, max: u32) -> u64 {
+    (min..=max).fold(0, |bits, value| bits | 1 << value)
+}
+
+/// `interval` in whole milliseconds, if that fits a timestamp
+fn interval_millis(interval: &Duration) -> Option<u64> {
+    u64::try_from(interval.as_millis()).ok()
+}
+
+/// Parse one cron field into a bit set of the values it matches
+fn parse_field(field: &str, min: u32, max: u32) -> core::result::Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| alloc::format!("bad step in '{}'", part))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(alloc::format!("zero step in '{}'", part));
+        }
+
+        let value = |s: &str| match s.parse::<u32>() {
+            Ok(v) if (min..=max).contains(&v) => Ok(v),
+            _ => Err(alloc::format!("'{}' is not in {}-{}", s, min, max)),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `a/n` runs from `a` to the end of the field
+                None if part.contains('/') => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(alloc::format!("empty range '{}'", part));
+        }
+
+        for v in (start..=end).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+/// Snapshot of a registered schedule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleInfo {
+    /// Schedule identifier
+    pub id: ScheduleId,
+    /// Scheduled workflow
+    pub workflow_id: WorkflowId,
+    /// When the workflow runs
+    pub schedule: Schedule,
+    /// Behaviour when runs overlap
+    pub overlap: OverlapPolicy,
+    /// Next due time in milliseconds since the epoch, `None` if the
+    /// schedule will not fire again
+    pub next_run: Option<u64>,
+    /// Runs started
+    pub runs: u64,
+    /// Runs skipped because the previous run was still active
+    pub skipped_runs: u64,
+    /// Runs currently executing
+    pub active_runs: usize,
+}
+
+/// A due run of a scheduled workflow.
+///
+/// The run counts as active until this value is dropped, so it should be
+/// kept alive for the whole execution.
+#[derive(Debug)]
+pub struct ScheduledRun {
+    /// Schedule that fired
+    pub schedule_id: ScheduleId,
+    /// Workflow to execute
+    pub workflow: Workflow,
+    active: Arc<AtomicUsize>,
+}
+
+impl Drop for ScheduledRun {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+struct ScheduledWorkflow {
+    workflow: Workflow,
+    schedule: Schedule,
+    cron: Option<CronExpression>,
+    overlap: OverlapPolicy,
+    next_run: Option<u64>,
+    runs: u64,
+    skipped_runs: u64,
+    active: Arc<AtomicUsize>,
+}
+
+impl ScheduledWorkflow {
+    /// First due time after `now`
+    fn next_after(&self, now: u64) -> Option<u64> {
+        match (&self.schedule, &self.cron) {
+            (_, Some(cron)) => cron.next_after(now),
+            (Schedule::Interval(interval), None) => {
+                let interval = interval_millis(interval)?;
+                // Runs missed while the scheduler was not polled collapse
+                // into the one being started, keeping the original phase
+                let previous = self.next_run.unwrap_or(now);
+                ((now - previous) / interval + 1).checked_mul(interval)?.checked_add(previous)
+            }
+            (Schedule::Cron(_), None) => None,
+        }
+    }
+}
+
+/// Registry of scheduled workflows
+#[derive(Debug)]
+pub struct WorkflowScheduler {
+    clock: Arc<dyn Clock>,
+    schedules: Mutex<BTreeMap<ScheduleId, ScheduledWorkflow>>,
+    next_id: AtomicU64,
+}
+
+impl WorkflowScheduler {
+    /// Create a scheduler reading time from `clock`
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            schedules: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Register `workflow` to run on `schedule`
+    pub fn add(&self, workflow: Workflow, schedule: Schedule, overlap: OverlapPolicy) -> Result<ScheduleId> {
+        let now = self.clock.now_millis();
+        let (cron, next_run) = match &schedule {
+            Schedule::Cron(expression) => {
+                let cron = CronExpression::parse(expression)?;
+                let next_run = cron.next_after(now).ok_or_else(|| WorkflowError::InvalidSchedule {
+                    schedule: expression.clone(),
+                    reason: "expression never matches".into(),
+                })?;
+                (Some(cron), next_run)
+            }
+            Schedule::Interval(interval) if interval.as_millis() == 0 => {
+                return Err(WorkflowError::InvalidSchedule {
+                    schedule: alloc::format!("{:?}", interval),
+                    reason: "interval must be positive".into(),
+                });
+            }
+            Schedule::Interval(interval) => {
+                let next_run = interval_millis(interval).and_then(|millis| now.checked_add(millis));
+                let next_run = next_run.ok_or_else(|| WorkflowError::InvalidSchedule {
+                    schedule: alloc::format!("{:?}", interval),
+                    reason: "interval is too long".into(),
+                })?;
+                (None, next_run)
+            }
+        };
+
+        let id = alloc::format!("schedule-{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.lock().insert(id.clone(), ScheduledWorkflow {
+            workflow,
+            schedule,
+            cron,
+            overlap,
+            next_run: Some(next_run),
+            runs: 0,
+            skipped_runs: 0,
+            active: Arc::new(AtomicUsize::new(0)),
+        });
+        Ok(id)
+    }
+
+    /// Remove a schedule. Runs already started are not affected.
+    pub fn cancel(&self, id: &str) -> bool {
+        self.lock().remove(id).is_some()
+    }
+
+    /// All registered schedules
+    pub fn list(&self) -> Vec<ScheduleInfo> {
+        self.lock()
+            .iter()
+            .map(|(id, scheduled)| ScheduleInfo {
+                id: id.clone(),
+                workflow_id: scheduled.workflow.id.clone(),
+                schedule: scheduled.schedule.clone(),
+                overlap: scheduled.overlap,
+                next_run: scheduled.next_run,
+                runs: scheduled.runs,
+                skipped_runs: scheduled.skipped_runs,
+                active_runs: scheduled.active.load(Ordering::SeqCst),
+            })
+            .collect()
+    }
+
+    /// Take the runs that are due now and move their schedules on.
+    ///
+    /// A schedule fires at most once per call, however many due times
+    /// passed since the last call.
+    pub fn due_runs(&self) -> Vec<ScheduledRun> {
+        let now = self.clock.now_millis();
+        let mut runs = Vec::new();
+
+        for (id, scheduled) in self.lock().iter_mut() {
+            if scheduled.next_run.is_none_or(|next_run| next_run > now) {
+                continue;
+            }
+            scheduled.next_run = scheduled.next_after(now);
+
+            if scheduled.overlap == OverlapPolicy::Skip && scheduled.active.load(Ordering::SeqCst) > 0 {
+                scheduled.skipped_runs += 1;
+                continue;
+            }
+
+            scheduled.runs += 1;
+            scheduled.active.fetch_add(1, Ordering::SeqCst);
+            runs.push(ScheduledRun {
+                schedule_id: id.clone(),
+                workflow: scheduled.workflow.clone(),
+                active: scheduled.active.clone(),
+            });
+        }
+
+        runs
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<ScheduleId, ScheduledWorkflow>> {
+        self.schedules.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for WorkflowScheduler {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::WorkflowNode;
+
+    /// 2024-01-01T00:00:00Z, a Monday
+    const MONDAY: u64 = 1_704_067_200_000;
+    const MINUTE: u64 = 60_000;
+
+    #[test]
+    fn test_cron_next_after() {
+        let every_quarter = CronExpression::parse("*/15 * * * *").unwrap();
+        assert_eq!(every_quarter.next_after(MONDAY), Some(MONDAY + 15 * MINUTE));
+        assert_eq!(every_quarter.next_after(MONDAY + 15 * MINUTE + 1), Some(MONDAY + 30 * MINUTE));
+
+        // Weekdays at 09:30, so Friday is followed by the next Monday
+        let weekday_mornings = CronExpression::parse("30 9 * * 1-5").unwrap();
+        assert_eq!(weekday_mornings.next_after(MONDAY), Some(MONDAY + (9 * 60 + 30) * MINUTE));
+        let friday_evening = MONDAY + 4 * 24 * 60 * MINUTE + 18 * 60 * MINUTE;
+        assert_eq!(
+            weekday_mornings.next_after(friday_evening),
+            Some(MONDAY + 7 * 24 * 60 * MINUTE + (9 * 60 + 30) * MINUTE)
+        );
+
+        // A day-of-month field matching every day does not widen the weekday
+        for days in ["*", "*/1", "1-31"] {
+            let mondays = CronExpression::parse(&alloc::format!("0 0 {} * 1", days)).unwrap();
+            assert_eq!(mondays.next_after(MONDAY), Some(MONDAY + 7 * 24 * 60 * MINUTE));
+        }
+        let first_of_month = CronExpression::parse("0 0 1 * 0-6").unwrap();
+        assert_eq!(first_of_month.next_after(MONDAY), Some(MONDAY + 31 * 24 * 60 * MINUTE));
+
+        // February 30th never comes
+        assert_eq!(CronExpression::parse("0 0 30 2 *").unwrap().next_after(MONDAY), None);
+
+        for invalid in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(matches!(CronExpression::parse(invalid), Err(WorkflowError::InvalidSchedule { .. })));
+        }
+    }
+
+    #[test]
+    fn test_due_runs_skip_overlaps() {
+        let clock = Arc::new(ManualClock::new(0));
+        let scheduler = WorkflowScheduler::new(clock.clone());
+        let workflow = Workflow::builder("report").add_node(WorkflowNode::new("build")).build();
+        let skip = scheduler
+            .add(workflow.clone(), Schedule::Interval(Duration::from_millis(10)), OverlapPolicy::Skip)
+            .unwrap();
+        scheduler
+            .add(workflow, Schedule::Interval(Duration::from_millis(10)), OverlapPolicy::Allow)
+            .unwrap();
+
+        clock.advance(Duration::from_millis(10));
+        let first = scheduler.due_runs();
+        assert_eq!(first.len(), 2);
+
+        // Both first runs are still active
+        clock.advance(Duration::from_millis(10));
+        let second = scheduler.due_runs();
+        assert_eq!(second.len(), 1);
+        assert_ne!(second[0].schedule_id, skip);
+
+        drop(first);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(scheduler.due_runs().len(), 2);
+
+        let skipping = scheduler.list().into_iter().find(|info| info.id == skip).unwrap();
+        assert_eq!((skipping.runs, skipping.skipped_runs, skipping.next_run), (2, 1, Some(40)));
+
+        assert!(scheduler.cancel(&skip));
+        assert!(!scheduler.cancel(&skip));
+        assert_eq!(scheduler.list().len(), 1);
+    }
+
+    #[test]
+    fn test_huge_intervals_do_not_overflow() {
+        let clock = Arc::new(ManualClock::new(MONDAY));
+        let scheduler = WorkflowScheduler::new(clock.clone());
+        let workflow = Workflow::builder("report").add_node(WorkflowNode::new("build")).build();
+
+        let too_long = scheduler.add(workflow.clone(), Schedule::Interval(Duration::MAX), OverlapPolicy::Allow);
+        assert!(matches!(too_long, Err(WorkflowError::InvalidSchedule { ref reason, .. }) if reason == "interval is too long"));
+
+        // Fires once, then its next run is past the end of time
+        let interval = Duration::from_millis(u64::MAX - MONDAY);
+        let id = scheduler.add(workflow, Schedule::Interval(interval), OverlapPolicy::Allow).unwrap();
+        clock.advance(interval);
+        assert_eq!(scheduler.due_runs().len(), 1);
+        let info = scheduler.list().into_iter().find(|info| info.id == id).unwrap();
+        assert_eq!(info.next_run, None);
+    }
+}
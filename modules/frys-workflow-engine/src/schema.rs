@@ -0,0 +1,345 @@
+//! Schemas describing the data nodes accept and produce
+//!
+//! A [`DataSchema`] is a JSON Schema document held as [`WorkflowData`].
+//! The supported keywords are:
+//!
+//! - `type`: `"null"`, `"boolean"`, `"integer"`, `"number"`, `"string"`,
+//!   `"array"`, `"object"`, or a list of them. `"bytes"` additionally
+//!   matches `WorkflowData::Bytes`, which has no JSON counterpart.
+//! - `enum`: list of allowed values
+//! - `minimum`, `maximum`: inclusive bounds of numbers
+//! - `minLength`, `maxLength`: bounds on the character count of strings
+//! - `properties`, `required`, `additionalProperties` (boolean) for objects
+//! - `items`, `minItems`, `maxItems` for arrays
+//!
+//! Other keywords are ignored, as JSON Schema prescribes.
+
+use crate::core::WorkflowData;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// JSON Schema a node's input or output must satisfy
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataSchema(WorkflowData);
+
+/// Where and why a value did not match a schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Location of the offending value, e.g. `$.items[2].name`
+    pub path: String,
+    /// What the value failed to satisfy
+    pub reason: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}: {}", self.path, self.reason)
+    }
+}
+
+impl DataSchema {
+    /// Wrap a JSON Schema document
+    pub fn new(schema: WorkflowData) -> Self {
+        Self(schema)
+    }
+
+    /// Schema accepting values of one JSON type
+    pub fn of_type(type_name: &str) -> Self {
+        Self::new(WorkflowData::Object(BTreeMap::new())).keyword("type", WorkflowData::String(type_name.into()))
+    }
+
+    /// Describe an object property
+    pub fn property(mut self, name: &str, schema: DataSchema) -> Self {
+        if let WorkflowData::Object(keywords) = &mut self.0 {
+            let properties = keywords
+                .entry("properties".into())
+                .or_insert_with(|| WorkflowData::Object(BTreeMap::new()));
+            if let WorkflowData::Object(properties) = properties {
+                properties.insert(name.into(), schema.0);
+            }
+        }
+        self
+    }
+
+    /// Require an object property to be present
+    pub fn required(mut self, name: &str) -> Self {
+        if let WorkflowData::Object(keywords) = &mut self.0 {
+            let required = keywords.entry("required".into()).or_insert_with(|| WorkflowData::Array(Vec::new()));
+            if let WorkflowData::Array(required) = required {
+                required.push(WorkflowData::String(name.into()));
+            }
+        }
+        self
+    }
+
+    /// Describe every element of an array
+    pub fn items(self, schema: DataSchema) -> Self {
+        self.keyword("items", schema.0)
+    }
+
+    /// Restrict values to a fixed set
+    pub fn allowed(self, values: Vec<WorkflowData>) -> Self {
+        self.keyword("enum", WorkflowData::Array(values))
+    }
+
+    /// Inclusive lower bound of numbers
+    pub fn minimum(self, minimum: f64) -> Self {
+        self.keyword("minimum", WorkflowData::Float(minimum))
+    }
+
+    /// Inclusive upper bound of numbers
+    pub fn maximum(self, maximum: f64) -> Self {
+        self.keyword("maximum", WorkflowData::Float(maximum))
+    }
+
+    /// Set a schema keyword
+    pub fn keyword(mut self, keyword: &str, value: WorkflowData) -> Self {
+        if let WorkflowData::Object(keywords) = &mut self.0 {
+            keywords.insert(keyword.into(), value);
+        }
+        self
+    }
+
+    /// The underlying JSON Schema document
+    pub fn as_data(&self) -> &WorkflowData {
+        &self.0
+    }
+
+    /// Check `data` against the schema, reporting the first mismatch
+    pub fn validate(&self, data: &WorkflowData) -> core::result::Result<(), SchemaViolation> {
+        let mut path = String::from("$");
+        validate_at(&self.0, data, &mut path)
+    }
+}
+
+impl From<WorkflowData> for DataSchema {
+    fn from(schema: WorkflowData) -> Self {
+        Self::new(schema)
+    }
+}
+
+fn validate_at(schema: &WorkflowData, data: &WorkflowData, path: &mut String) -> core::result::Result<(), SchemaViolation> {
+    let violation = |path: &String, reason: String| SchemaViolation { path: path.clone(), reason };
+    let keywords = match schema {
+        WorkflowData::Object(keywords) => keywords,
+        // `true` accepts anything and `false` nothing
+        WorkflowData::Bool(true) => return Ok(()),
+        WorkflowData::Bool(false) => return Err(violation(path, "no value is allowed here".into())),
+        _ => return Err(violation(path, "schema is not an object".into())),
+    };
+
+    if let Some(types) = keywords.get("type") {
+        let names: Vec<&str> = match types {
+            WorkflowData::String(name) => alloc::vec![name.as_str()],
+            WorkflowData::Array(names) => names
+                .iter()
+                .filter_map(|name| match name {
+                    WorkflowData::String(name) => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect(),
+            _ => return Err(violation(path, "schema keyword 'type' is not a string or list".into())),
+        };
+        if !names.iter().any(|name| has_type(data, name)) {
+            return Err(violation(path, alloc::format!("expected {}, found {}", names.join(" or "), type_name(data))));
+        }
+    }
+
+    if let Some(WorkflowData::Array(allowed)) = keywords.get("enum") {
+        if !allowed.iter().any(|value| same_value(value, data)) {
+            return Err(violation(path, "value is not one of the allowed values".into()));
+        }
+    }
+
+    if let Some(value) = number(data) {
+        if let Some(minimum) = keywords.get("minimum").and_then(number) {
+            if value < minimum {
+                return Err(violation(path, alloc::format!("{} is less than the minimum {}", value, minimum)));
+            }
+        }
+        if let Some(maximum) = keywords.get("maximum").and_then(number) {
+            if value > maximum {
+                return Err(violation(path, alloc::format!("{} is greater than the maximum {}", value, maximum)));
+            }
+        }
+    }
+
+    match data {
+        WorkflowData::String(text) => {
+            check_len(keywords, "minLength", "maxLength", text.chars().count(), "characters")
+                .map_err(|reason| violation(path, reason))?;
+        }
+        WorkflowData::Array(elements) => {
+            check_len(keywords, "minItems", "maxItems", elements.len(), "items").map_err(|reason| violation(path, reason))?;
+            if let Some(items) = keywords.get("items") {
+                for (index, element) in elements.iter().enumerate() {
+                    let len = path.len();
+                    path.push_str(&alloc::format!("[{}]", index));
+                    validate_at(items, element, path)?;
+                    path.truncate(len);
+                }
+            }
+        }
+        WorkflowData::Object(fields) => {
+            if let Some(WorkflowData::Array(required)) = keywords.get("required") {
+                for name in required {
+                    if let WorkflowData::String(name) = name {
+                        if !fields.contains_key(name) {
+                            return Err(violation(path, alloc::format!("missing required property '{}'", name)));
+                        }
+                    }
+                }
+            }
+            let properties = match keywords.get("properties") {
+                Some(WorkflowData::Object(properties)) => Some(properties),
+                _ => None,
+            };
+            let closed = keywords.get("additionalProperties") == Some(&WorkflowData::Bool(false));
+            for (name, value) in fields {
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property) => {
+                        let len = path.len();
+                        path.push('.');
+                        path.push_str(name);
+                        validate_at(property, value, path)?;
+                        path.truncate(len);
+                    }
+                    None if closed => {
+                        return Err(violation(path, alloc::format!("unexpected property '{}'", name)));
+                    }
+                    None => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Check a length against the bounds named by `min` and `max`
+fn check_len(
+    keywords: &BTreeMap<String, WorkflowData>,
+    min: &str,
+    max: &str,
+    len: usize,
+    unit: &str,
+) -> core::result::Result<(), String> {
+    let bound = |keyword: &str| match keywords.get(keyword) {
+        Some(WorkflowData::Int(bound)) => usize::try_from(*bound).ok(),
+        _ => None,
+    };
+    if let Some(min) = bound(min) {
+        if len < min {
+            return Err(alloc::format!("has {} {}, fewer than the minimum {}", len, unit, min));
+        }
+    }
+    if let Some(max) = bound(max) {
+        if len > max {
+            return Err(alloc::format!("has {} {}, more than the maximum {}", len, unit, max));
+        }
+    }
+    Ok(())
+}
+
+fn has_type(data: &WorkflowData, name: &str) -> bool {
+    match (name, data) {
+        ("null", WorkflowData::Null)
+        | ("boolean", WorkflowData::Bool(_))
+        | ("integer" | "number", WorkflowData::Int(_))
+        | ("number", WorkflowData::Float(_))
+        | ("string", WorkflowData::String(_))
+        | ("bytes", WorkflowData::Bytes(_))
+        | ("array", WorkflowData::Array(_))
+        | ("object", WorkflowData::Object(_)) => true,
+        ("integer", WorkflowData::Float(value)) => value.fract() == 0.0,
+        _ => false,
+    }
+}
+
+fn type_name(data: &WorkflowData) -> &'static str {
+    match data {
+        WorkflowData::Null => "null",
+        WorkflowData::Bool(_) => "boolean",
+        WorkflowData::Int(_) => "integer",
+        WorkflowData::Float(_) => "number",
+        WorkflowData::String(_) => "string",
+        WorkflowData::Bytes(_) => "bytes",
+        WorkflowData::Array(_) => "array",
+        WorkflowData::Object(_) => "object",
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn number(data: &WorkflowData) -> Option<f64> {
+    match data {
+        WorkflowData::Int(value) => Some(*value as f64),
+        WorkflowData::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// JSON equality, under which `1` and `1.0` are the same number
+fn same_value(a: &WorkflowData, b: &WorkflowData) -> bool {
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(score: WorkflowData) -> WorkflowData {
+        WorkflowData::Object(BTreeMap::from([
+            ("label".into(), WorkflowData::String("cat".into())),
+            ("score".into(), score),
+        ]))
+    }
+
+    #[test]
+    fn test_validate_reports_path_of_mismatch() {
+        let schema = DataSchema::of_type("array").items(
+            DataSchema::of_type("object")
+                .property("label", DataSchema::of_type("string"))
+                .property("score", DataSchema::of_type("number").minimum(0.0).maximum(1.0))
+                .required("score"),
+        );
+
+        let valid = WorkflowData::Array(alloc::vec![record(WorkflowData::Float(0.5)), record(WorkflowData::Int(1))]);
+        assert_eq!(schema.validate(&valid), Ok(()));
+
+        let out_of_range = WorkflowData::Array(alloc::vec![record(WorkflowData::Float(0.5)), record(WorkflowData::Float(1.5))]);
+        let violation = schema.validate(&out_of_range).unwrap_err();
+        assert_eq!(violation.path, "$[1].score");
+        assert!(violation.reason.contains("maximum"));
+
+        let wrong_type = WorkflowData::Array(alloc::vec![record(WorkflowData::String("high".into()))]);
+        let violation = schema.validate(&wrong_type).unwrap_err();
+        assert_eq!(violation.reason, "expected number, found string");
+
+        let missing = WorkflowData::Array(alloc::vec![WorkflowData::Object(BTreeMap::new())]);
+        assert_eq!(schema.validate(&missing).unwrap_err().path, "$[0]");
+    }
+
+    #[test]
+    fn test_enum_and_closed_objects() {
+        let schema = DataSchema::of_type("object")
+            .property("state", DataSchema::new(WorkflowData::Object(BTreeMap::new())).allowed(alloc::vec![
+                WorkflowData::String("open".into()),
+                WorkflowData::String("closed".into()),
+            ]))
+            .keyword("additionalProperties", WorkflowData::Bool(false));
+
+        let state = |state: &str| WorkflowData::Object(BTreeMap::from([("state".into(), WorkflowData::String(state.into()))]));
+        assert!(schema.validate(&state("open")).is_ok());
+        assert!(schema.validate(&state("pending")).is_err());
+
+        let extra = WorkflowData::Object(BTreeMap::from([("extra".into(), WorkflowData::Null)]));
+        assert_eq!(schema.validate(&extra).unwrap_err().reason, "unexpected property 'extra'");
+    }
+}